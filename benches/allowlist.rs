@@ -0,0 +1,120 @@
+//! Cost of a parser-level sender allowlist against filtering afterwards.
+//!
+//! Run with: `cargo bench --bench allowlist`
+//!
+//! Before timing, the allocation count of each approach on the same large
+//! Telegram export is printed, e.g.
+//! `50000 messages, 5000 kept - parse + filter: 700033 allocations, allowlist: 565030 allocations`.
+//! Skipped records still cost their deserialization.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+
+use chatpack::config::TelegramConfig;
+use chatpack::core::{FilterConfig, apply_filters};
+use chatpack::parser::Parser;
+use chatpack::parsers::TelegramParser;
+
+use tempfile::NamedTempFile;
+
+const MESSAGE_COUNT: usize = 50_000;
+const SENDER_COUNT: usize = 10;
+
+/// Global allocator that counts allocations.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Generates an export where each of `SENDER_COUNT` senders takes turns,
+/// with formatted text so building the content has a cost.
+fn generate_telegram_export(count: usize) -> String {
+    let mut out = String::from(r#"{"name":"Bench","type":"personal_chat","messages":["#);
+    for i in 0..count {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!(
+            r#"{{"id":{},"type":"message","date_unixtime":"{}","from":"User {}","text":["Message number {} with ",{{"type":"bold","text":"bold"}}," text"]}}"#,
+            i,
+            1_705_314_600 + i,
+            i % SENDER_COUNT,
+            i
+        ));
+    }
+    out.push_str("]}");
+    out
+}
+
+fn create_temp_file(content: &str) -> NamedTempFile {
+    let mut file = tempfile::Builder::new()
+        .suffix(".json")
+        .tempfile()
+        .expect("Failed to create temp file");
+    file.write_all(content.as_bytes())
+        .expect("Failed to write temp file");
+    file.flush().expect("Failed to flush temp file");
+    file
+}
+
+fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn bench_allowlist(c: &mut Criterion) {
+    let content = generate_telegram_export(MESSAGE_COUNT);
+    let file = create_temp_file(&content);
+    let path = file.path();
+
+    let parser = TelegramParser::new();
+    let filter = FilterConfig::new().with_sender("User 0");
+    let allowlisted =
+        TelegramParser::with_config(TelegramConfig::new().with_sender_allowlist(["User 0"]));
+
+    let filter_allocs = count_allocations(|| apply_filters(parser.parse(path).unwrap(), &filter));
+    let allowlist_allocs = count_allocations(|| allowlisted.parse(path).unwrap());
+    println!(
+        "{MESSAGE_COUNT} messages, {} kept - parse + filter: {filter_allocs} allocations, allowlist: {allowlist_allocs} allocations",
+        MESSAGE_COUNT / SENDER_COUNT
+    );
+
+    let mut group = c.benchmark_group("telegram_sender");
+    group.throughput(Throughput::Bytes(content.len() as u64));
+    group.sample_size(20);
+
+    group.bench_function("parse_then_filter", |b| {
+        b.iter(|| apply_filters(parser.parse(black_box(path)).unwrap(), &filter));
+    });
+
+    group.bench_function("allowlist", |b| {
+        b.iter(|| allowlisted.parse(black_box(path)).unwrap());
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_allowlist);
+criterion_main!(benches);
@@ -0,0 +1,136 @@
+//! Cost of streaming every field against a minimal projection.
+//!
+//! Run with: `cargo bench --bench projection`
+//!
+//! Before timing, the heap allocated while parsing the same large Telegram
+//! export, and the heap still kept by the collected messages, is printed for
+//! each approach, e.g.
+//! `50000 messages - minimal: 198238007 bytes allocated, 20222944 kept`.
+//! A minimal projection never builds content, so it allocates and keeps
+//! less; what it keeps is mostly the `Message` structs themselves. Both
+//! still deserialize the whole record, which is most of what is allocated.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+
+use chatpack::Message;
+use chatpack::streaming::{Projection, StreamingConfig, StreamingParser, TelegramStreamingParser};
+
+use tempfile::NamedTempFile;
+
+const MESSAGE_COUNT: usize = 50_000;
+
+/// Global allocator that tracks live heap bytes.
+struct CountingAlloc;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Generates an export of replies with formatted text, so content is most
+/// of each message.
+fn generate_telegram_export(count: usize) -> String {
+    let mut out = String::from(r#"{"name":"Bench","type":"personal_chat","messages":["#);
+    for i in 0..count {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!(
+            r#"{{"id":{},"type":"message","date_unixtime":"{}","from":"User {}","reply_to_message_id":{},"text":["Message number {} with ",{{"type":"bold","text":"bold"}}," text and a little more to read"]}}"#,
+            i + 1,
+            1_705_314_600 + i,
+            i % 10,
+            i.max(1),
+            i
+        ));
+    }
+    out.push_str("]}");
+    out
+}
+
+fn create_temp_file(content: &str) -> NamedTempFile {
+    let mut file = tempfile::Builder::new()
+        .suffix(".json")
+        .tempfile()
+        .expect("Failed to create temp file");
+    file.write_all(content.as_bytes())
+        .expect("Failed to write temp file");
+    file.flush().expect("Failed to flush temp file");
+    file
+}
+
+fn collect(parser: &TelegramStreamingParser, path: &str) -> Vec<Message> {
+    parser.stream(path).unwrap().map(Result::unwrap).collect()
+}
+
+/// Heap bytes allocated while running `f`, and still held by what it
+/// returns.
+fn heap_bytes<T>(f: impl FnOnce() -> T) -> (usize, usize) {
+    let live = LIVE_BYTES.load(Ordering::Relaxed);
+    let allocated = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let kept = f();
+    let bytes = (
+        ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated,
+        LIVE_BYTES.load(Ordering::Relaxed) - live,
+    );
+    drop(black_box(kept));
+    bytes
+}
+
+fn bench_projection(c: &mut Criterion) {
+    let content = generate_telegram_export(MESSAGE_COUNT);
+    let file = create_temp_file(&content);
+    let path = file.path().to_str().unwrap();
+
+    let full = TelegramStreamingParser::new();
+    let minimal = TelegramStreamingParser::with_config(
+        StreamingConfig::new().with_projection(Projection::minimal()),
+    );
+
+    for (name, parser) in [("all fields", &full), ("minimal", &minimal)] {
+        let (allocated, kept) = heap_bytes(|| collect(parser, path));
+        println!("{MESSAGE_COUNT} messages - {name}: {allocated} bytes allocated, {kept} kept");
+    }
+
+    let mut group = c.benchmark_group("telegram_projection");
+    group.throughput(Throughput::Bytes(content.len() as u64));
+    group.sample_size(20);
+
+    group.bench_function("all_fields", |b| {
+        b.iter(|| collect(&full, black_box(path)));
+    });
+
+    group.bench_function("minimal", |b| {
+        b.iter(|| collect(&minimal, black_box(path)));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_projection);
+criterion_main!(benches);
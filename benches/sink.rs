@@ -0,0 +1,125 @@
+//! Allocation and throughput comparison of `parse` and `parse_into`.
+//!
+//! Run with: `cargo bench --bench sink`
+//!
+//! Before timing, the allocation count of each approach on the same large
+//! Discord JSONL file is printed, e.g.
+//! `50000 messages - parse: 100016 allocations, parse_into: 4 allocations`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+
+use chatpack::parser::Parser;
+use chatpack::parsers::DiscordParser;
+use chatpack::sink::MessageRef;
+
+use tempfile::NamedTempFile;
+
+const MESSAGE_COUNT: usize = 50_000;
+
+/// Global allocator that counts allocations.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn generate_discord_jsonl(count: usize) -> String {
+    let mut out = String::new();
+    for i in 0..count {
+        let (name, nickname) = if i % 2 == 0 {
+            ("alice", "\"Alice\"")
+        } else {
+            ("bob", "null")
+        };
+        out.push_str(&format!(
+            r#"{{"id":"{}","type":"Default","timestamp":"2024-01-15T10:30:00+00:00","timestampEdited":null,"content":"Message number {}","author":{{"id":"111","name":"{}","nickname":{}}},"attachments":[],"stickers":[],"embeds":[]}}"#,
+            1_000_000 + i,
+            i,
+            name,
+            nickname
+        ));
+        out.push('\n');
+    }
+    out
+}
+
+fn create_temp_file(content: &str) -> NamedTempFile {
+    let mut file = tempfile::Builder::new()
+        .suffix(".jsonl")
+        .tempfile()
+        .expect("Failed to create temp file");
+    file.write_all(content.as_bytes())
+        .expect("Failed to write temp file");
+    file.flush().expect("Failed to flush temp file");
+    file
+}
+
+/// Sums content lengths, so the sink does some work without allocating.
+fn total_content_len(parser: &DiscordParser, path: &Path) -> usize {
+    let mut total = 0;
+    let mut sink = |msg: MessageRef<'_>| {
+        total += msg.content.len();
+        ControlFlow::Continue(())
+    };
+    parser.parse_into(path, &mut sink).unwrap();
+    total
+}
+
+fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn bench_sink(c: &mut Criterion) {
+    let content = generate_discord_jsonl(MESSAGE_COUNT);
+    let file = create_temp_file(&content);
+    let path = file.path();
+    let parser = DiscordParser::new();
+
+    let parse_allocs = count_allocations(|| parser.parse(path).unwrap());
+    let sink_allocs = count_allocations(|| total_content_len(&parser, path));
+    println!(
+        "{MESSAGE_COUNT} messages - parse: {parse_allocs} allocations, parse_into: {sink_allocs} allocations"
+    );
+
+    let mut group = c.benchmark_group("discord_jsonl");
+    group.throughput(Throughput::Bytes(content.len() as u64));
+    group.sample_size(20);
+
+    group.bench_function("parse", |b| {
+        b.iter(|| parser.parse(black_box(path)).unwrap());
+    });
+
+    group.bench_function("parse_into", |b| {
+        b.iter(|| total_content_len(&parser, black_box(path)));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_sink);
+criterion_main!(benches);
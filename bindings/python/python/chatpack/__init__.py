@@ -0,0 +1,22 @@
+"""Python bindings for chatpack.
+
+Messages are plain dicts using the same keys as chatpack's JSON output:
+``sender``, ``content`` and, when present, ``timestamp``, ``id``,
+``reply_to`` and ``edited``.
+"""
+
+from ._chatpack import (
+    ChatpackError,
+    apply_filters,
+    merge_consecutive,
+    parse,
+    to_jsonl,
+)
+
+__all__ = [
+    "ChatpackError",
+    "apply_filters",
+    "merge_consecutive",
+    "parse",
+    "to_jsonl",
+]
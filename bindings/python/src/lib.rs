@@ -0,0 +1,208 @@
+//! Python bindings for chatpack.
+//!
+//! Exposes parsing, filtering, merging and JSONL output to Python. Messages
+//! cross the boundary as dicts keyed like chatpack's JSON output, so a
+//! message looks the same in Python as in [`to_jsonl`](chatpack::core::to_jsonl):
+//!
+//! ```python
+//! {"id": 1, "timestamp": "2024-01-15T10:30:00Z", "sender": "Alice", "content": "Hi"}
+//! ```
+//!
+//! Optional keys (`timestamp`, `id`, `reply_to`, `edited`) are omitted when
+//! the value is absent. All chatpack errors surface as `chatpack.ChatpackError`
+//! with the library's `Display` message.
+//!
+//! Build with `maturin develop` from this directory.
+
+#![cfg(feature = "python")]
+
+use std::path::Path;
+
+use chatpack::core::{FilterConfig, OutputConfig};
+use chatpack::parser::{Parser, Platform, create_parser};
+use chatpack::{ChatpackError as CoreError, Message};
+use chrono::{DateTime, Utc};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+create_exception!(
+    chatpack,
+    ChatpackError,
+    PyException,
+    "Raised when chatpack fails to parse, filter or serialize messages."
+);
+
+/// Timestamp format used by chatpack's JSON and JSONL writers.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// Converts a core error into a `ChatpackError` carrying its message.
+fn to_py_err(err: CoreError) -> PyErr {
+    ChatpackError::new_err(err.to_string())
+}
+
+/// A [`Message`] crossing the Python boundary as a dict.
+struct PyMessage(Message);
+
+impl<'py> IntoPyObject<'py> for PyMessage {
+    type Target = PyDict;
+    type Output = Bound<'py, PyDict>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        let msg = self.0;
+        let dict = PyDict::new(py);
+        // Keys in the order of chatpack::core::output::RECORD_FIELDS
+        if let Some(id) = msg.id {
+            dict.set_item("id", id)?;
+        }
+        if let Some(ts) = msg.timestamp {
+            dict.set_item("timestamp", ts.format(TIMESTAMP_FORMAT).to_string())?;
+        }
+        dict.set_item("sender", msg.sender)?;
+        dict.set_item("content", msg.content)?;
+        if let Some(reply_to) = msg.reply_to {
+            dict.set_item("reply_to", reply_to)?;
+        }
+        if let Some(edited) = msg.edited {
+            dict.set_item("edited", edited.format(TIMESTAMP_FORMAT).to_string())?;
+        }
+        Ok(dict)
+    }
+}
+
+impl<'py> FromPyObject<'py> for PyMessage {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let dict = ob.downcast::<PyDict>()?;
+
+        let sender: String = required(dict, "sender")?;
+        let content: String = required(dict, "content")?;
+        let timestamp = optional_timestamp(dict, "timestamp")?;
+        let id: Option<u64> = optional(dict, "id")?;
+        let reply_to: Option<u64> = optional(dict, "reply_to")?;
+        let edited = optional_timestamp(dict, "edited")?;
+
+        Ok(Self(Message::with_metadata(
+            sender, content, timestamp, id, reply_to, edited,
+        )))
+    }
+}
+
+/// Reads an optional key, treating `None` the same as a missing key.
+fn optional<'py, T: FromPyObject<'py>>(
+    dict: &Bound<'py, PyDict>,
+    key: &str,
+) -> PyResult<Option<T>> {
+    match dict.get_item(key)? {
+        Some(value) if !value.is_none() => value.extract().map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn required<'py, T: FromPyObject<'py>>(dict: &Bound<'py, PyDict>, key: &str) -> PyResult<T> {
+    optional(dict, key)?
+        .ok_or_else(|| PyKeyError::new_err(format!("message dict is missing '{key}'")))
+}
+
+/// Reads an RFC 3339 timestamp string, as produced by chatpack's writers.
+fn optional_timestamp(dict: &Bound<'_, PyDict>, key: &str) -> PyResult<Option<DateTime<Utc>>> {
+    let Some(value) = optional::<String>(dict, key)? else {
+        return Ok(None);
+    };
+    DateTime::parse_from_rfc3339(&value)
+        .map(|dt| Some(dt.to_utc()))
+        .map_err(|e| PyValueError::new_err(format!("invalid {key} '{value}': {e}")))
+}
+
+fn into_core(messages: Vec<PyMessage>) -> Vec<Message> {
+    messages.into_iter().map(|m| m.0).collect()
+}
+
+fn into_py(messages: Vec<Message>) -> Vec<PyMessage> {
+    messages.into_iter().map(PyMessage).collect()
+}
+
+/// Parses a chat export file.
+///
+/// `platform` accepts the same names as the Rust `Platform` type
+/// (`"telegram"`, `"tg"`, `"whatsapp"`, ...). The GIL is released while
+/// parsing.
+#[pyfunction]
+fn parse(py: Python<'_>, platform: &str, path: &str) -> PyResult<Vec<PyMessage>> {
+    let platform: Platform = platform.parse().map_err(ChatpackError::new_err)?;
+    let messages = py
+        .allow_threads(|| create_parser(platform).parse(Path::new(path)))
+        .map_err(to_py_err)?;
+    Ok(into_py(messages))
+}
+
+/// Filters messages by date range and sender.
+///
+/// `after` and `before` accept the same forms as `FilterConfig::after_datetime`
+/// (`"2024-01-15"`, `"2024-01-15 10:30"`, RFC 3339, or relative forms like `"7d"`).
+#[pyfunction]
+#[pyo3(signature = (messages, *, after = None, before = None, sender = None))]
+fn apply_filters(
+    messages: Vec<PyMessage>,
+    after: Option<&str>,
+    before: Option<&str>,
+    sender: Option<String>,
+) -> PyResult<Vec<PyMessage>> {
+    let mut config = FilterConfig::new();
+    if let Some(after) = after {
+        config = config.after_datetime(after).map_err(to_py_err)?;
+    }
+    if let Some(before) = before {
+        config = config.before_datetime(before).map_err(to_py_err)?;
+    }
+    if let Some(sender) = sender {
+        config = config.with_sender(sender);
+    }
+
+    let filtered = chatpack::core::apply_filters(into_core(messages), &config);
+    Ok(into_py(filtered))
+}
+
+/// Merges consecutive messages from the same sender.
+#[pyfunction]
+fn merge_consecutive(messages: Vec<PyMessage>) -> Vec<PyMessage> {
+    into_py(chatpack::core::merge_consecutive(into_core(messages)))
+}
+
+/// Serializes messages to a JSONL string (one JSON object per line).
+#[pyfunction]
+#[pyo3(signature = (messages, *, timestamps = false, ids = false, replies = false, edited = false))]
+fn to_jsonl(
+    messages: Vec<PyMessage>,
+    timestamps: bool,
+    ids: bool,
+    replies: bool,
+    edited: bool,
+) -> PyResult<String> {
+    let mut config = OutputConfig::new();
+    if timestamps {
+        config = config.with_timestamps();
+    }
+    if ids {
+        config = config.with_ids();
+    }
+    if replies {
+        config = config.with_replies();
+    }
+    if edited {
+        config = config.with_edited();
+    }
+
+    chatpack::core::to_jsonl(&into_core(messages), &config).map_err(to_py_err)
+}
+
+#[pymodule]
+fn _chatpack(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ChatpackError", m.py().get_type::<ChatpackError>())?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(apply_filters, m)?)?;
+    m.add_function(wrap_pyfunction!(merge_consecutive, m)?)?;
+    m.add_function(wrap_pyfunction!(to_jsonl, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,76 @@
+import json
+from pathlib import Path
+
+import pytest
+
+import chatpack
+
+FIXTURE = str(Path(__file__).parent / "fixtures" / "telegram.json")
+
+
+def test_parse_returns_dicts():
+    messages = chatpack.parse("telegram", FIXTURE)
+
+    assert len(messages) == 3
+    assert messages[0] == {
+        "sender": "Alice",
+        "content": "Hello!",
+        "timestamp": "2024-01-15T10:30:00Z",
+        "id": 1,
+    }
+    assert messages[2]["reply_to"] == 2
+    assert "edited" not in messages[0]
+
+
+def test_parse_platform_alias():
+    assert chatpack.parse("tg", FIXTURE) == chatpack.parse("telegram", FIXTURE)
+
+
+def test_apply_filters():
+    messages = chatpack.parse("telegram", FIXTURE)
+
+    after = chatpack.apply_filters(messages, after="2024-01-16")
+    assert [m["sender"] for m in after] == ["Bob"]
+
+    alice = chatpack.apply_filters(messages, sender="Alice")
+    assert len(alice) == 2
+
+
+def test_merge_consecutive():
+    merged = chatpack.merge_consecutive(chatpack.parse("telegram", FIXTURE))
+
+    assert [m["sender"] for m in merged] == ["Alice", "Bob"]
+    assert merged[0]["content"] == "Hello!\nAre you there?"
+
+
+def test_to_jsonl_round_trips_fields():
+    messages = chatpack.parse("telegram", FIXTURE)
+    lines = chatpack.to_jsonl(messages, timestamps=True, ids=True).splitlines()
+
+    assert len(lines) == 3
+    assert json.loads(lines[0]) == messages[0]
+
+
+def test_hand_built_messages():
+    messages = [{"sender": "A", "content": "x"}, {"sender": "A", "content": "y", "id": None}]
+    assert chatpack.to_jsonl(messages) == '{"sender":"A","content":"x"}\n{"sender":"A","content":"y"}\n'
+
+
+def test_unknown_platform_raises():
+    with pytest.raises(chatpack.ChatpackError, match="Unknown platform"):
+        chatpack.parse("myspace", FIXTURE)
+
+
+def test_missing_file_raises():
+    with pytest.raises(chatpack.ChatpackError, match="IO error"):
+        chatpack.parse("telegram", "/nonexistent/result.json")
+
+
+def test_invalid_filter_date_raises():
+    with pytest.raises(chatpack.ChatpackError, match="Invalid date"):
+        chatpack.apply_filters([], after="not-a-date")
+
+
+def test_message_missing_sender_raises():
+    with pytest.raises(KeyError):
+        chatpack.merge_consecutive([{"content": "orphan"}])
@@ -0,0 +1,90 @@
+/* chatpack C ABI. Build the library with `cargo rustc --release --features ffi --crate-type cdylib`. */
+
+#ifndef CHATPACK_H
+#define CHATPACK_H
+
+/* Declarations of src/ffi.rs. Keep in sync with it, or regenerate with
+   `cbindgen --config cbindgen.toml --output include/chatpack.h`. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The call succeeded.
+#define CHATPACK_OK 0
+
+// A required pointer argument was null.
+#define CHATPACK_ERR_NULL_POINTER -1
+
+// A string argument was not valid UTF-8.
+#define CHATPACK_ERR_INVALID_UTF8 -2
+
+// An argument was invalid (unknown platform, malformed config, bad date).
+#define CHATPACK_ERR_INVALID_ARGUMENT -3
+
+// The input file could not be read.
+#define CHATPACK_ERR_IO -4
+
+// The export could not be parsed or the output could not be produced.
+#define CHATPACK_ERR_PARSE -5
+
+// A panic was caught at the boundary.
+#define CHATPACK_ERR_PANIC -6
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses an export file and returns its messages as a JSON array.
+//
+// `platform` accepts the same names as [`Platform`] (`"telegram"`, `"tg"`,
+// `"whatsapp"`, ...). Each message is serialized with the field names of
+// [`Message`](crate::Message); absent optional fields are omitted.
+//
+// # Safety
+//
+// - `platform` and `path` must be null or NUL-terminated strings.
+// - `out_json` must be null or valid for writes. On success it receives a
+//   string that must be released with [`chatpack_free_string`].
+int32_t chatpack_parse(const char *platform, const char *path, char **out_json);
+
+// Runs the full pipeline: parse, filter, merge, and format.
+//
+// `config_json` is a JSON object with optional fields `format`
+// (`"csv"`, `"json"`, `"jsonl"`; default `"csv"`), `merge` (default
+// `true`), `after`, `before`, `sender`, `sender_map` (a [`SenderMap`],
+// applied before filtering), `dialogue` (a pair of names for
+// [`extract_dialogue`] with default windows), and `output` (an
+// [`OutputConfig`]). Pass null for the defaults.
+//
+// # Safety
+//
+// - `platform` and `path` must be null or NUL-terminated strings.
+// - `config_json` may be null; otherwise it must be NUL-terminated.
+// - `out_output` must be null or valid for writes. On success it receives a
+//   string that must be released with [`chatpack_free_string`].
+int32_t chatpack_convert(const char *platform,
+                         const char *path,
+                         const char *config_json,
+                         char **out_output);
+
+// Releases a string returned by this library. Null is ignored.
+//
+// # Safety
+//
+// `s` must be null or a pointer previously returned through an `out_*`
+// parameter of this library, not yet freed.
+void chatpack_free_string(char *s);
+
+// Returns the error message of the last failed call on this thread.
+//
+// Returns null if the last call succeeded. The pointer stays valid until
+// the next chatpack call on the same thread and must not be freed.
+const char *chatpack_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHATPACK_H */
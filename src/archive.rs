@@ -0,0 +1,606 @@
+//! Single-file archives of a processed chat.
+//!
+//! [`write_archive`] stores the messages of a run together with what the
+//! chat was, how it was processed, and what came of it, in one zip file
+//! that [`read_archive`] opens again, years later if need be:
+//!
+//! | File | Contents |
+//! |------|----------|
+//! | `manifest.json` | [`ArchiveManifest`]: archive format version and the chatpack version that wrote it |
+//! | `messages.jsonl` | Every message, one serialized [`Message`] per line |
+//! | `metadata.json` | [`ChatMetadata`] |
+//! | `config.json` | [`RunConfig`] |
+//! | `stats.json` | [`ProcessingStats`] |
+//!
+//! Readers ignore files and JSON keys they do not know, so an archive from
+//! a later chatpack opens as long as its format version is supported.
+//! [`ARCHIVE_EXTENSION`] is the usual file extension.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[cfg(feature = "telegram")]
+//! # fn main() -> chatpack::Result<()> {
+//! use chatpack::archive::{ArchiveContents, ChatMetadata, read_archive, write_archive};
+//! use chatpack::parser::Platform;
+//! use chatpack::prelude::*;
+//!
+//! let messages = vec![Message::new("Alice", "Hello!"), Message::new("Bob", "Hi!")];
+//! let contents = ArchiveContents::new(messages).with_metadata(
+//!     ChatMetadata::new()
+//!         .with_name("Team chat")
+//!         .with_platform(Platform::Telegram),
+//! );
+//!
+//! write_archive("team.chatpack", &contents)?;
+//! let restored = read_archive("team.chatpack")?;
+//! assert_eq!(restored.messages, contents.messages);
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "telegram"))]
+//! # fn main() {}
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::Message;
+use crate::core::filter::FilterConfig;
+use crate::core::models::OutputConfig;
+use crate::core::processor::ProcessingStats;
+use crate::error::ChatpackError;
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+use crate::parser::Platform;
+
+/// Version of the archive layout written by [`write_archive`].
+///
+/// [`read_archive`] opens archives of this version and earlier.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// File extension for archives, without the dot.
+pub const ARCHIVE_EXTENSION: &str = "chatpack";
+
+const FORMAT: &str = "chatpack archive";
+const MANIFEST_FILE: &str = "manifest.json";
+const MESSAGES_FILE: &str = "messages.jsonl";
+const METADATA_FILE: &str = "metadata.json";
+const CONFIG_FILE: &str = "config.json";
+const STATS_FILE: &str = "stats.json";
+
+/// What wrote an archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Layout version, [`ARCHIVE_FORMAT_VERSION`] when written.
+    pub format_version: u32,
+    /// Version of chatpack that wrote the archive.
+    pub chatpack_version: String,
+}
+
+impl ArchiveManifest {
+    /// Describes an archive written by this version of chatpack.
+    pub fn current() -> Self {
+        Self {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            chatpack_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// The chat an archive was made from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatMetadata {
+    /// Name of the chat, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Platform the export came from.
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Platform>,
+    /// Export file or directory the messages were parsed from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl ChatMetadata {
+    /// Creates empty metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the chat name.
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the platform.
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ))]
+    #[must_use]
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+
+    /// Sets the export the messages were parsed from.
+    #[must_use]
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+}
+
+/// How the archived messages were processed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunConfig {
+    /// Filter applied to the parsed messages.
+    pub filter: FilterConfig,
+    /// Output fields the run was configured with.
+    pub output_config: OutputConfig,
+    /// Whether consecutive messages from the same sender were merged.
+    pub merge: bool,
+}
+
+impl RunConfig {
+    /// Creates a config for a run without filtering or merging.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the filter.
+    #[must_use]
+    pub fn with_filter(mut self, filter: FilterConfig) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets the output fields.
+    #[must_use]
+    pub fn with_output_config(mut self, config: OutputConfig) -> Self {
+        self.output_config = config;
+        self
+    }
+
+    /// Sets whether messages were merged.
+    #[must_use]
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+}
+
+/// Everything stored in an archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveContents {
+    /// The processed messages.
+    pub messages: Vec<Message>,
+    /// The chat they came from.
+    pub metadata: ChatMetadata,
+    /// How they were processed.
+    pub config: RunConfig,
+    /// Counts from the run.
+    pub stats: ProcessingStats,
+    /// What wrote the archive.
+    ///
+    /// [`new`](Self::new) describes this version of chatpack and
+    /// [`read_archive`] returns the one recorded in the file;
+    /// [`write_archive`] always records [`ArchiveManifest::current`].
+    pub manifest: ArchiveManifest,
+}
+
+impl ArchiveContents {
+    /// Creates contents holding `messages`, with empty metadata, a default
+    /// run config, and stats counting the messages as parsed and written.
+    pub fn new(messages: Vec<Message>) -> Self {
+        let count = messages.len();
+        Self {
+            messages,
+            metadata: ChatMetadata::default(),
+            config: RunConfig::default(),
+            stats: ProcessingStats::new(count, count),
+            manifest: ArchiveManifest::current(),
+        }
+    }
+
+    /// Sets the chat metadata.
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: ChatMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sets the run config.
+    #[must_use]
+    pub fn with_config(mut self, config: RunConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the run stats.
+    #[must_use]
+    pub fn with_stats(mut self, stats: ProcessingStats) -> Self {
+        self.stats = stats;
+        self
+    }
+}
+
+/// Writes `contents` to a zip archive at `path`, replacing any file there.
+///
+/// Messages are written with their serde representation, so
+/// [`read_archive`] returns them as they were, except for the fields
+/// [`Message`] never serializes.
+/// Entries carry a fixed modification time, so the same contents always
+/// produce the same bytes.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+pub fn write_archive(
+    path: impl AsRef<Path>,
+    contents: &ArchiveContents,
+) -> Result<(), ChatpackError> {
+    let mut messages = Vec::new();
+    for msg in &contents.messages {
+        serde_json::to_writer(&mut messages, msg)?;
+        messages.push(b'\n');
+    }
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+
+    add_file(
+        &mut zip,
+        MANIFEST_FILE,
+        &serde_json::to_vec_pretty(&ArchiveManifest::current())?,
+    )?;
+    add_file(&mut zip, MESSAGES_FILE, &messages)?;
+    add_file(
+        &mut zip,
+        METADATA_FILE,
+        &serde_json::to_vec_pretty(&contents.metadata)?,
+    )?;
+    add_file(
+        &mut zip,
+        CONFIG_FILE,
+        &serde_json::to_vec_pretty(&contents.config)?,
+    )?;
+    add_file(
+        &mut zip,
+        STATS_FILE,
+        &serde_json::to_vec_pretty(&contents.stats)?,
+    )?;
+
+    zip.finish().map_err(zip_error)?.flush()?;
+    Ok(())
+}
+
+/// Reads an archive written by [`write_archive`].
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be opened, or
+/// [`ChatpackError::InvalidFormat`] if it is not a zip file, is damaged,
+/// lacks one of the archive's files, or has a format version newer than
+/// [`ARCHIVE_FORMAT_VERSION`].
+pub fn read_archive(path: impl AsRef<Path>) -> Result<ArchiveContents, ChatpackError> {
+    let file = BufReader::new(File::open(path)?);
+    let mut zip = ZipArchive::new(file).map_err(zip_error)?;
+
+    let manifest: ArchiveManifest = read_json(&mut zip, MANIFEST_FILE)?;
+    if manifest.format_version == 0 || manifest.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(ChatpackError::invalid_format(
+            FORMAT,
+            format!(
+                "format version {} is not supported (newest supported: {ARCHIVE_FORMAT_VERSION})",
+                manifest.format_version
+            ),
+        ));
+    }
+
+    Ok(ArchiveContents {
+        messages: read_messages(&mut zip)?,
+        metadata: read_json(&mut zip, METADATA_FILE)?,
+        config: read_json(&mut zip, CONFIG_FILE)?,
+        stats: read_json(&mut zip, STATS_FILE)?,
+        manifest,
+    })
+}
+
+fn add_file<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    content: &[u8],
+) -> Result<(), ChatpackError> {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default());
+    zip.start_file(name, options).map_err(zip_error)?;
+    zip.write_all(content)?;
+    Ok(())
+}
+
+fn read_text<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<String, ChatpackError> {
+    let mut file = zip.by_name(name).map_err(|e| match e {
+        ZipError::FileNotFound => ChatpackError::invalid_format(FORMAT, format!("missing {name}")),
+        e => zip_error(e),
+    })?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)
+        .map_err(|e| ChatpackError::invalid_format(FORMAT, format!("{name}: {e}")))?;
+    Ok(text)
+}
+
+fn read_messages<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<Vec<Message>, ChatpackError> {
+    read_text(zip, MESSAGES_FILE)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                ChatpackError::invalid_format(
+                    FORMAT,
+                    format!("{MESSAGES_FILE} line {}: {e}", i + 1),
+                )
+            })
+        })
+        .collect()
+}
+
+fn read_json<R: Read + Seek, T: DeserializeOwned>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<T, ChatpackError> {
+    serde_json::from_str(&read_text(zip, name)?)
+        .map_err(|e| ChatpackError::invalid_format(FORMAT, format!("{name}: {e}")))
+}
+
+/// Maps a zip error to an I/O error when the file system failed, and to
+/// [`ChatpackError::InvalidFormat`] when the archive is damaged.
+fn zip_error(e: ZipError) -> ChatpackError {
+    match e {
+        ZipError::Io(e)
+            if !matches!(
+                e.kind(),
+                io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData
+            ) =>
+        {
+            e.into()
+        }
+        e => ChatpackError::invalid_format(FORMAT, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::loss::LossReport;
+    use crate::core::rand::Seed;
+    use crate::{AttachmentRef, Message};
+
+    fn sample() -> ArchiveContents {
+        let ts = |s: i64| chrono::DateTime::from_timestamp(1_705_314_600 + s, 0).unwrap();
+        let messages = vec![
+            Message::new("Alice", "Hello; \"quoted\"")
+                .with_id(1)
+                .with_timestamp(ts(0)),
+            Message::new("Bob", "Line one\nline two 👋")
+                .with_id(2)
+                .with_timestamp(ts(60))
+                .with_reply_to(1)
+                .with_edited(ts(90))
+                .with_attachment(AttachmentRef::from_path("photos/cat.png")),
+            Message::new("Alice", "Forwarded news")
+                .with_timestamp(ts(120))
+                .with_forwarded_from("News channel")
+                .with_topic("General"),
+        ];
+        let loss = LossReport {
+            messages_merged: 3,
+            ..LossReport::default()
+        };
+        let metadata = ChatMetadata::new()
+            .with_name("Team chat")
+            .with_source("result.json");
+        #[cfg(feature = "telegram")]
+        let metadata = metadata.with_platform(Platform::Telegram);
+        ArchiveContents::new(messages)
+            .with_metadata(metadata)
+            .with_config(
+                RunConfig::new()
+                    .with_filter(FilterConfig::new().with_sender("Alice"))
+                    .with_output_config(OutputConfig::new().with_timestamps())
+                    .with_merge(true),
+            )
+            .with_stats(
+                ProcessingStats::new(5, 2)
+                    .with_filtered(4)
+                    .with_seed(Seed::new(42))
+                    .with_loss(loss),
+            )
+    }
+
+    /// Rewrites the archive at `path` with `edit` applied to each entry.
+    fn rewrite(
+        path: &Path,
+        edit: impl Fn(&str, Vec<u8>) -> Option<Vec<u8>>,
+        extra: &[(&str, &str)],
+    ) {
+        let mut zip = ZipArchive::new(File::open(path).unwrap()).unwrap();
+        let mut entries = Vec::new();
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i).unwrap();
+            let mut content = Vec::new();
+            file.read_to_end(&mut content).unwrap();
+            entries.push((file.name().unwrap().to_string(), content));
+        }
+        let mut out = ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in entries {
+            if let Some(content) = edit(&name, content) {
+                out.start_file(name, SimpleFileOptions::default()).unwrap();
+                out.write_all(&content).unwrap();
+            }
+        }
+        for (name, content) in extra {
+            out.start_file(*name, SimpleFileOptions::default()).unwrap();
+            out.write_all(content.as_bytes()).unwrap();
+        }
+        out.finish().unwrap();
+    }
+
+    // =========================================================================
+    // Round trip
+    // =========================================================================
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chat.chatpack");
+        let contents = sample();
+
+        write_archive(&path, &contents).unwrap();
+        let restored = read_archive(&path).unwrap();
+
+        assert_eq!(restored, contents);
+        assert_eq!(restored.manifest, ArchiveManifest::current());
+    }
+
+    #[test]
+    fn test_same_contents_same_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.chatpack"), dir.path().join("b.chatpack"));
+        write_archive(&a, &sample()).unwrap();
+        write_archive(&b, &sample()).unwrap();
+        assert_eq!(std::fs::read(a).unwrap(), std::fs::read(b).unwrap());
+    }
+
+    #[test]
+    fn test_empty_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.chatpack");
+        write_archive(&path, &ArchiveContents::new(Vec::new())).unwrap();
+        assert_eq!(
+            read_archive(&path).unwrap(),
+            ArchiveContents::new(Vec::new())
+        );
+    }
+
+    // =========================================================================
+    // Compatibility
+    // =========================================================================
+
+    #[test]
+    fn test_unknown_files_and_keys_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("newer.chatpack");
+        write_archive(&path, &sample()).unwrap();
+        rewrite(
+            &path,
+            |name, content| {
+                if name != METADATA_FILE {
+                    return Some(content);
+                }
+                let mut value: serde_json::Value = serde_json::from_slice(&content).unwrap();
+                value["participants"] = serde_json::json!(["Alice", "Bob"]);
+                Some(serde_json::to_vec(&value).unwrap())
+            },
+            &[("attachments/index.json", "[]")],
+        );
+
+        assert_eq!(read_archive(&path).unwrap(), sample());
+    }
+
+    #[test]
+    fn test_newer_format_version_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("future.chatpack");
+        write_archive(&path, &sample()).unwrap();
+        rewrite(
+            &path,
+            |name, content| {
+                if name != MANIFEST_FILE {
+                    return Some(content);
+                }
+                let manifest = ArchiveManifest {
+                    format_version: ARCHIVE_FORMAT_VERSION + 1,
+                    chatpack_version: "99.0.0".into(),
+                };
+                Some(serde_json::to_vec(&manifest).unwrap())
+            },
+            &[],
+        );
+
+        let err = read_archive(&path).unwrap_err();
+        assert!(err.is_invalid_format());
+        assert!(err.to_string().contains("format version 2"), "{err}");
+    }
+
+    // =========================================================================
+    // Damaged archives
+    // =========================================================================
+
+    #[test]
+    fn test_not_a_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chat.chatpack");
+        std::fs::write(&path, "{\"messages\": []}").unwrap();
+
+        let err = read_archive(&path).unwrap_err();
+        assert!(err.is_invalid_format(), "{err:?}");
+        assert!(err.to_string().contains("chatpack archive"), "{err}");
+    }
+
+    #[test]
+    fn test_truncated_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chat.chatpack");
+        write_archive(&path, &sample()).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+
+        assert!(read_archive(&path).unwrap_err().is_invalid_format());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chat.chatpack");
+        write_archive(&path, &sample()).unwrap();
+        rewrite(
+            &path,
+            |name, content| (name != STATS_FILE).then_some(content),
+            &[],
+        );
+
+        let err = read_archive(&path).unwrap_err();
+        assert!(err.is_invalid_format());
+        assert!(err.to_string().contains("missing stats.json"), "{err}");
+    }
+
+    #[test]
+    fn test_missing_archive_is_io_error() {
+        let err = read_archive("/nonexistent/chat.chatpack").unwrap_err();
+        assert!(err.is_io());
+    }
+}
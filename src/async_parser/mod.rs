@@ -34,6 +34,7 @@ use tokio::fs;
 
 use crate::Message;
 use crate::error::ChatpackError;
+use crate::parsing::encoding::decode_text;
 
 mod telegram;
 
@@ -73,7 +74,16 @@ pub trait AsyncParser: Send + Sync {
 }
 
 /// Helper function to read a file asynchronously.
-pub(crate) async fn read_file_async(path: impl AsRef<Path>) -> Result<String, ChatpackError> {
-    let content = fs::read_to_string(path).await?;
-    Ok(content)
+///
+/// Files larger than `limit` bytes are rejected before reading.
+pub(crate) async fn read_file_async(
+    path: impl AsRef<Path>,
+    limit: u64,
+) -> Result<String, ChatpackError> {
+    let size = fs::metadata(path.as_ref()).await?.len();
+    if size > limit {
+        return Err(ChatpackError::file_too_large(size, limit));
+    }
+    let bytes = fs::read(path).await?;
+    decode_text(bytes)
 }
@@ -1,18 +1,31 @@
 //! Async Telegram parser.
 
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
 
 use crate::Message;
 use crate::config::TelegramConfig;
 use crate::error::ChatpackError;
-use crate::parsing::telegram::{TelegramExport, parse_telegram_message};
+use crate::message::assign_source_indices;
+use crate::parser::Platform;
+use crate::parsing::content::normalize_messages;
+use crate::parsing::raw::decode_with_raw;
+use crate::parsing::telegram::{
+    ChunkOverlap, TelegramExport, TelegramRawMessage, TopicTracker, check_export_size, message_len,
+    telegram_export_chunks,
+};
+use crate::parsing::timestamps::check_messages;
 
 use super::{AsyncParser, read_file_async};
 
 /// Async parser for Telegram JSON exports.
 ///
+/// Like [`TelegramParser`](crate::parsers::TelegramParser), it reads every
+/// chunk of an export split into `result.json`, `result(1).json`, … when
+/// given their folder or `result.json`.
+///
 /// # Example
 ///
 /// ```rust,no_run
@@ -29,7 +42,6 @@ use super::{AsyncParser, read_file_async};
 /// # }
 /// ```
 pub struct AsyncTelegramParser {
-    #[allow(dead_code)]
     config: TelegramConfig,
 }
 
@@ -60,21 +72,82 @@ impl AsyncParser for AsyncTelegramParser {
     }
 
     async fn parse(&self, path: impl AsRef<Path> + Send) -> Result<Vec<Message>, ChatpackError> {
-        let content = read_file_async(path).await?;
-        self.parse_str(&content)
+        let chunks = export_chunks(path.as_ref().to_path_buf(), self.config.max_file_size).await?;
+        let mut topics = TopicTracker::default();
+        let mut overlap = ChunkOverlap::default();
+        let mut messages = Vec::new();
+        for chunk in &chunks {
+            let content = read_file_async(chunk, self.config.max_file_size).await?;
+            let decoded = self.decode(&content, &mut topics)?;
+            messages.extend(decoded.into_iter().filter(|m| overlap.keep(m)));
+            overlap.next_chunk();
+        }
+        Ok(self.finish(messages))
     }
 
     fn parse_str(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        let export: TelegramExport = serde_json::from_str(content)?;
-
-        let messages = export
-            .messages
-            .iter()
-            .filter_map(parse_telegram_message)
-            .collect();
+        let messages = self.decode(content, &mut TopicTracker::default())?;
+        Ok(self.finish(messages))
+    }
+}
 
+impl AsyncTelegramParser {
+    /// Decodes the messages of one export file, following forum topics
+    /// with `topics`.
+    fn decode(
+        &self,
+        content: &str,
+        topics: &mut TopicTracker,
+    ) -> Result<Vec<Message>, ChatpackError> {
+        let merge = self.config.merge_albums;
+        let mut messages = Vec::new();
+        if self.config.capture_raw {
+            let (records, _) = decode_with_raw::<TelegramRawMessage>(content, false)?;
+            let mut rest = records.as_slice();
+            while let [(_, raw), ..] = rest {
+                let len = message_len(rest, merge, |(record, _)| record);
+                let album: Vec<&TelegramRawMessage> = rest[..len].iter().map(|(r, _)| r).collect();
+                messages.extend(
+                    topics
+                        .convert(&album, &self.config)
+                        .map(|m| m.with_raw(*raw)),
+                );
+                rest = &rest[len..];
+            }
+        } else {
+            let export: TelegramExport = serde_json::from_str(content)?;
+            let mut rest = export.messages.as_slice();
+            while !rest.is_empty() {
+                let len = message_len(rest, merge, |record| record);
+                let album: Vec<&TelegramRawMessage> = rest[..len].iter().collect();
+                messages.extend(topics.convert(&album, &self.config));
+                rest = &rest[len..];
+            }
+        }
         Ok(messages)
     }
+
+    /// Checks timestamps, normalizes content, and numbers the messages.
+    fn finish(&self, mut messages: Vec<Message>) -> Vec<Message> {
+        check_messages(&mut messages, self.config.clamp_timestamps.as_ref());
+        normalize_messages(&mut messages, self.config.content);
+        assign_source_indices(&mut messages, Platform::Telegram);
+        messages
+    }
+}
+
+/// Lists the chunks of the export at `path` off the async runtime, and
+/// checks their combined size against `limit`.
+async fn export_chunks(path: PathBuf, limit: u64) -> Result<Vec<PathBuf>, ChatpackError> {
+    tokio::task::spawn_blocking(move || {
+        let chunks = telegram_export_chunks(&path)?;
+        if chunks.len() > 1 {
+            check_export_size(&chunks, limit)?;
+        }
+        Ok(chunks)
+    })
+    .await
+    .map_err(io::Error::other)?
 }
 
 #[cfg(test)]
@@ -146,6 +219,42 @@ mod tests {
         assert_eq!(messages[0].content, "Hello async!");
     }
 
+    #[tokio::test]
+    async fn test_async_parse_joins_chunks() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        for (name, ids) in [
+            ("result.json", [1, 2]),
+            ("result(1).json", [2, 3]),
+            ("result(2).json", [1, 4]),
+        ] {
+            let records: Vec<String> = ids
+                .iter()
+                .map(|id| {
+                    format!(
+                        r#"{{"id": {id}, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "m{id}"}}"#
+                    )
+                })
+                .collect();
+            let json = format!(r#"{{"messages": [{}]}}"#, records.join(","));
+            tokio::fs::write(dir.path().join(name), json)
+                .await
+                .expect("write");
+        }
+
+        let parser = AsyncTelegramParser::new();
+        for path in [dir.path().to_path_buf(), dir.path().join("result.json")] {
+            let messages = parser.parse(&path).await.expect("parse failed");
+            let ids: Vec<u64> = messages.iter().filter_map(|m| m.id).collect();
+            assert_eq!(ids, [1, 2, 3, 1, 4]);
+            let indices: Vec<u64> = messages.iter().filter_map(|m| m.source_index).collect();
+            assert_eq!(indices, [0, 1, 2, 3, 4]);
+        }
+
+        let small = AsyncTelegramParser::with_config(TelegramConfig::new().with_max_file_size(200));
+        let err = small.parse(dir.path()).await.unwrap_err();
+        assert!(err.is_file_too_large());
+    }
+
     #[tokio::test]
     async fn test_async_parse_file_not_found() {
         let parser = AsyncTelegramParser::new();
@@ -247,4 +356,33 @@ mod tests {
 
         assert_eq!(messages.len(), 2);
     }
+
+    #[test]
+    fn test_parse_str_merges_albums_like_sync_parser() {
+        use crate::parser::Parser;
+        use crate::parsers::TelegramParser;
+
+        let json = r#"{
+            "messages": [
+                {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "grouped_id": 7, "photo": "photos/1.jpg", "text": ""},
+                {"id": 2, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "grouped_id": 7, "photo": "photos/2.jpg", "text": "Trip"},
+                {"id": 3, "type": "message", "date_unixtime": "1705314660", "from": "Bob", "text": "Nice"}
+            ]
+        }"#;
+
+        for merge in [true, false] {
+            let config = TelegramConfig::new().with_merge_albums(merge);
+            let expected = TelegramParser::with_config(config.clone())
+                .parse_str(json)
+                .unwrap();
+            let messages = AsyncTelegramParser::with_config(config)
+                .parse_str(json)
+                .unwrap();
+            assert_eq!(messages, expected, "merge_albums: {merge}");
+        }
+
+        let messages = AsyncTelegramParser::new().parse_str(json).unwrap();
+        assert_eq!(messages[0].content, "[Album: 2 photos]\nTrip");
+        assert_eq!(messages[0].id, Some(1));
+    }
 }
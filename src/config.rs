@@ -25,8 +25,352 @@
 //! let parser = TelegramParser::with_config(config);
 //! ```
 
+use std::borrow::Cow;
+use std::ops::Range;
+
+use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 
+#[cfg(all(
+    feature = "streaming",
+    any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    )
+))]
+use crate::Message;
+
+/// Default for `max_file_size` on all platform configs: 1GB.
+///
+/// Files above this size are too large to load into memory safely; use
+/// streaming instead.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024 * 1024;
+
+fn default_max_file_size() -> u64 {
+    DEFAULT_MAX_FILE_SIZE
+}
+
+/// Default for `max_line_length` on the WhatsApp and Discord configs: 1MB.
+///
+/// Far longer than any real message line, and short enough that a crafted
+/// single-line file cannot exhaust memory.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 1024 * 1024;
+
+fn default_max_line_length() -> usize {
+    DEFAULT_MAX_LINE_LENGTH
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Whitespace rules applied to message content after parsing.
+///
+/// Every platform config embeds one, and the batch and streaming parsers of
+/// a platform apply it identically. See
+/// [`normalize_content`](crate::parsing::content::normalize_content) for the
+/// exact rules.
+///
+/// Serialized inline with the platform config, so the keys are
+/// `trim_content`, `collapse_internal_blank_lines` and
+/// `ensure_single_space_before_markers`.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::config::{ContentPolicy, WhatsAppConfig};
+///
+/// let config = WhatsAppConfig::new()
+///     .with_content_policy(ContentPolicy::new().with_collapse_internal_blank_lines(true));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentPolicy {
+    /// Strip leading and trailing whitespace (default: true)
+    #[serde(default = "default_true")]
+    pub trim_content: bool,
+
+    /// Collapse runs of blank lines inside a message into a single blank
+    /// line (default: false)
+    #[serde(default)]
+    pub collapse_internal_blank_lines: bool,
+
+    /// Put `[Attachment: …]` and `[Sticker: …]` marker lines directly after
+    /// the preceding line, with no trailing spaces or blank lines between
+    /// them (default: true)
+    #[serde(default = "default_true")]
+    pub ensure_single_space_before_markers: bool,
+}
+
+impl Default for ContentPolicy {
+    fn default() -> Self {
+        Self {
+            trim_content: true,
+            collapse_internal_blank_lines: false,
+            ensure_single_space_before_markers: true,
+        }
+    }
+}
+
+impl ContentPolicy {
+    /// Creates a policy with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a policy that leaves content exactly as parsed.
+    pub fn raw() -> Self {
+        Self {
+            trim_content: false,
+            collapse_internal_blank_lines: false,
+            ensure_single_space_before_markers: false,
+        }
+    }
+
+    /// Sets whether to strip leading and trailing whitespace.
+    #[must_use]
+    pub fn with_trim_content(mut self, trim: bool) -> Self {
+        self.trim_content = trim;
+        self
+    }
+
+    /// Sets whether to collapse runs of blank lines.
+    #[must_use]
+    pub fn with_collapse_internal_blank_lines(mut self, collapse: bool) -> Self {
+        self.collapse_internal_blank_lines = collapse;
+        self
+    }
+
+    /// Sets whether to tidy the whitespace before attachment markers.
+    #[must_use]
+    pub fn with_ensure_single_space_before_markers(mut self, ensure: bool) -> Self {
+        self.ensure_single_space_before_markers = ensure;
+        self
+    }
+}
+
+/// Which message fields a streaming iterator fills in.
+///
+/// Sender and timestamp are always kept. Every other field is kept only
+/// when asked for; a field left out is empty on every message, and the
+/// parsers skip building it from the record. Filtering happens before the
+/// projection, so a projected iterator yields the same messages, in the
+/// same order, as a full one.
+///
+/// The projection is fixed when the iterator is built, through
+/// [`StreamingConfig::projection`](crate::streaming::StreamingConfig::projection),
+/// and reported by
+/// [`MessageIterator::projection`](crate::streaming::MessageIterator::projection).
+///
+/// # Examples
+///
+/// ```
+/// use chatpack::config::Projection;
+///
+/// // Counting messages per sender needs neither content nor IDs.
+/// let projection = Projection::minimal();
+/// assert!(!projection.need_content);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Projection {
+    /// Keep [`Message::content`] (and [`Message::raw`]).
+    pub need_content: bool,
+    /// Keep [`Message::id`] and [`Message::reply_to`].
+    pub need_ids: bool,
+    /// Keep [`Message::edited`], [`Message::original_sender`],
+    /// [`Message::forwarded_from`], and [`Message::topic`].
+    pub need_metadata: bool,
+    /// Keep [`Message::attachments`].
+    pub need_attachments: bool,
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl Projection {
+    /// Keeps every field.
+    pub const fn all() -> Self {
+        Self {
+            need_content: true,
+            need_ids: true,
+            need_metadata: true,
+            need_attachments: true,
+        }
+    }
+
+    /// Keeps only sender and timestamp, enough for statistics and sender
+    /// lists.
+    pub const fn minimal() -> Self {
+        Self {
+            need_content: false,
+            need_ids: false,
+            need_metadata: false,
+            need_attachments: false,
+        }
+    }
+
+    /// Returns whether every field is kept.
+    pub fn is_all(&self) -> bool {
+        *self == Self::all()
+    }
+
+    /// Empties the fields of `msg` this projection leaves out. Used by the
+    /// streaming parsers.
+    #[cfg(all(
+        feature = "streaming",
+        any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json"
+        )
+    ))]
+    pub(crate) fn apply(&self, msg: &mut Message) {
+        if !self.need_content {
+            msg.content = String::new();
+            msg.raw = None;
+        }
+        if !self.need_ids {
+            msg.id = None;
+            msg.reply_to = None;
+        }
+        if !self.need_metadata {
+            msg.edited = None;
+            msg.original_sender = None;
+            msg.forwarded_from = None;
+            msg.topic = None;
+        }
+        if !self.need_attachments {
+            msg.attachments = Vec::new();
+        }
+    }
+}
+
+/// What parsers do with a message whose timestamp fails a [`TimestampCheck`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampAction {
+    /// Drop the message.
+    Drop,
+    /// Keep the message with its timestamp set to `None`.
+    Nullify,
+    /// Keep the message unchanged.
+    Warn,
+}
+
+/// Clock sanity check applied to parsed timestamps.
+///
+/// Guards against device clock glitches, such as a message dated 2124 or at
+/// the Unix epoch, that would otherwise sort to the ends of the chat and
+/// skew date statistics. Every message whose timestamp falls outside
+/// `range` is handled by `action` and counted with the skipped records
+/// (`Parser::parse_counted`, `MessageIterator::skipped`, and
+/// `MessageSink::on_skip` for parsers that report skips), whichever the
+/// action. Messages without a timestamp always pass.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::config::{TimestampAction, TimestampCheck, WhatsAppConfig};
+///
+/// let config = WhatsAppConfig::new()
+///     .with_clamp_timestamps(TimestampCheck::new(TimestampAction::Nullify));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimestampCheck {
+    /// Timestamps considered plausible, end excluded (default:
+    /// 1990-01-01 until one day after the check was created).
+    pub range: Range<DateTime<Utc>>,
+
+    /// What to do with implausible timestamps.
+    pub action: TimestampAction,
+}
+
+impl TimestampCheck {
+    /// Creates a check over the default range.
+    pub fn new(action: TimestampAction) -> Self {
+        let start = DateTime::from_timestamp(631_152_000, 0).unwrap_or_default(); // 1990-01-01
+        Self {
+            range: start..Utc::now() + TimeDelta::days(1),
+            action,
+        }
+    }
+
+    /// Sets the plausible range.
+    #[must_use]
+    pub fn with_range(mut self, range: Range<DateTime<Utc>>) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Applies the check to a timestamp.
+    ///
+    /// Returns the action taken if `timestamp` is out of range, after
+    /// clearing it for [`TimestampAction::Nullify`].
+    ///
+    /// ```rust
+    /// use chatpack::config::{TimestampAction, TimestampCheck};
+    /// use chrono::DateTime;
+    ///
+    /// let check = TimestampCheck::new(TimestampAction::Nullify);
+    /// let mut epoch = DateTime::from_timestamp(0, 0);
+    /// assert_eq!(check.apply(&mut epoch), Some(TimestampAction::Nullify));
+    /// assert_eq!(epoch, None);
+    /// ```
+    pub fn apply(&self, timestamp: &mut Option<DateTime<Utc>>) -> Option<TimestampAction> {
+        let ts = (*timestamp)?;
+        if self.range.contains(&ts) {
+            return None;
+        }
+        if self.action == TimestampAction::Nullify {
+            *timestamp = None;
+        }
+        Some(self.action)
+    }
+}
+
+/// How Telegram mentions are written into message content.
+///
+/// Telegram exports `@username` mentions with their `@` and mentions of
+/// users without a username (`mention_name`) as the bare display name.
+/// Mentions whose text is empty are written as `@id<user_id>` in every
+/// format.
+///
+/// ```rust
+/// use chatpack::config::MentionFormat;
+///
+/// assert_eq!(MentionFormat::AsExported.render("John"), "John");
+/// assert_eq!(MentionFormat::At.render("John"), "@John");
+/// assert_eq!(MentionFormat::Plain.render("@alice"), "alice");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MentionFormat {
+    /// Keep the text as exported: `John`, `@alice` (default).
+    #[default]
+    AsExported,
+    /// Prefix every mention with `@`: `@John`, `@alice`.
+    At,
+    /// Strip the `@`: `John`, `alice`.
+    Plain,
+}
+
+impl MentionFormat {
+    /// Renders the display text of a mention.
+    pub fn render(self, text: &str) -> Cow<'_, str> {
+        match self {
+            MentionFormat::AsExported => Cow::Borrowed(text),
+            MentionFormat::At if text.starts_with('@') => Cow::Borrowed(text),
+            MentionFormat::At => Cow::Owned(format!("@{text}")),
+            MentionFormat::Plain => Cow::Borrowed(text.strip_prefix('@').unwrap_or(text)),
+        }
+    }
+}
+
 /// Configuration for Telegram export parsing.
 ///
 /// Telegram exports are JSON files with a `messages` array. This config
@@ -54,6 +398,76 @@ pub struct TelegramConfig {
 
     /// Skip invalid messages instead of returning errors (default: true)
     pub skip_invalid: bool,
+
+    /// Timezone assumed for the local `date` field of old exports that lack
+    /// `date_unixtime` (default: UTC). Serialized as `"+HH:MM"`.
+    #[serde(default = "utc_offset", with = "fixed_offset_serde")]
+    pub assume_timezone: FixedOffset,
+
+    /// Largest file `Parser::parse` will load into memory, in bytes
+    /// (default: 1GB). Larger files are rejected with
+    /// [`ChatpackError::FileTooLarge`](crate::ChatpackError::FileTooLarge);
+    /// `Parser::stream` switches to streaming instead.
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+
+    /// Collect structured [`AttachmentRef`](crate::AttachmentRef)s into
+    /// `Message::attachments` (default: false). Only `Parser::parse` and
+    /// `Parser::parse_str` collect them; streaming does not.
+    #[serde(default)]
+    pub collect_attachment_refs: bool,
+
+    /// How `mention` and `mention_name` entities are written (default:
+    /// as exported).
+    #[serde(default)]
+    pub mention_format: MentionFormat,
+
+    /// Clock sanity check applied to message timestamps (default: none).
+    #[serde(default)]
+    pub clamp_timestamps: Option<TimestampCheck>,
+
+    /// Keep each message's source record in
+    /// [`Message::raw`](crate::Message::raw), for debugging (default: false).
+    #[serde(default)]
+    pub capture_raw: bool,
+
+    /// Keep phone calls as `[Call: 12m 30s]` or `[Missed call]` messages
+    /// from the caller, and voice messages without a caption as
+    /// `[Voice message]` (default: false).
+    #[serde(default)]
+    pub include_calls: bool,
+
+    /// Senders to keep, compared ignoring ASCII case (default: all).
+    /// Records from anyone else are dropped right after they are decoded,
+    /// before their message is built.
+    #[serde(default)]
+    pub sender_allowlist: Option<Vec<String>>,
+
+    /// Forum topics to keep, by title, compared ignoring ASCII case
+    /// (default: all). Messages posted in other topics are dropped, and so
+    /// is every message of an export that is not a forum. Messages outside
+    /// any created topic are in [`GENERAL_TOPIC`](crate::parsing::GENERAL_TOPIC).
+    #[serde(default)]
+    pub topic_filter: Option<Vec<String>>,
+
+    /// End at a record cut off by the end of the file, such as an
+    /// interrupted download, instead of failing (default: false). The
+    /// complete records before the cut are kept and the cut-off record is
+    /// counted as skipped. Exports with no complete record still fail.
+    #[serde(default)]
+    pub tolerate_truncation: bool,
+
+    /// Collapse an album, consecutive records from one sender sharing a
+    /// `grouped_id`, into one message from its first record, such as
+    /// `[Album: 4 photos, 1 video]` followed by the caption (default:
+    /// true).
+    #[serde(default = "default_true")]
+    pub merge_albums: bool,
+
+    /// Whitespace rules applied to message content (inlined when
+    /// serialized; see [`ContentPolicy`]).
+    #[serde(flatten)]
+    pub content: ContentPolicy,
 }
 
 impl Default for TelegramConfig {
@@ -63,6 +477,18 @@ impl Default for TelegramConfig {
             buffer_size: 64 * 1024,             // 64KB
             max_message_size: 10 * 1024 * 1024, // 10MB
             skip_invalid: true,
+            assume_timezone: utc_offset(),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            collect_attachment_refs: false,
+            mention_format: MentionFormat::default(),
+            clamp_timestamps: None,
+            capture_raw: false,
+            include_calls: false,
+            sender_allowlist: None,
+            topic_filter: None,
+            tolerate_truncation: false,
+            merge_albums: true,
+            content: ContentPolicy::default(),
         }
     }
 }
@@ -109,6 +535,148 @@ impl TelegramConfig {
         self.skip_invalid = skip;
         self
     }
+
+    /// Sets the timezone assumed for local `date` fields.
+    ///
+    /// Only used for messages without `date_unixtime` (pre-2021 exports).
+    ///
+    /// ```rust
+    /// use chatpack::config::TelegramConfig;
+    /// use chrono::FixedOffset;
+    ///
+    /// let config = TelegramConfig::new()
+    ///     .with_assume_timezone(FixedOffset::east_opt(3 * 3600).unwrap());
+    /// ```
+    #[must_use]
+    pub fn with_assume_timezone(mut self, offset: FixedOffset) -> Self {
+        self.assume_timezone = offset;
+        self
+    }
+
+    /// Sets the largest file size loaded into memory by `Parser::parse`.
+    ///
+    /// Use `u64::MAX` to disable the check.
+    #[must_use]
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Sets whether to collect attachment refs into `Message::attachments`.
+    #[must_use]
+    pub fn with_collect_attachment_refs(mut self, collect: bool) -> Self {
+        self.collect_attachment_refs = collect;
+        self
+    }
+
+    /// Sets how mentions are written into message content.
+    #[must_use]
+    pub fn with_mention_format(mut self, format: MentionFormat) -> Self {
+        self.mention_format = format;
+        self
+    }
+
+    /// Sets the clock sanity check applied to message timestamps.
+    #[must_use]
+    pub fn with_clamp_timestamps(mut self, check: TimestampCheck) -> Self {
+        self.clamp_timestamps = Some(check);
+        self
+    }
+
+    /// Enables or disables keeping each message's source record.
+    #[must_use]
+    pub fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    /// Enables or disables representing calls and voice messages.
+    #[must_use]
+    pub fn with_include_calls(mut self, include: bool) -> Self {
+        self.include_calls = include;
+        self
+    }
+
+    /// Keeps only messages from `senders`, ignoring ASCII case.
+    ///
+    /// ```rust
+    /// use chatpack::config::TelegramConfig;
+    ///
+    /// let config = TelegramConfig::new().with_sender_allowlist(["Alice", "Bob"]);
+    /// ```
+    #[must_use]
+    pub fn with_sender_allowlist<I, S>(mut self, senders: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.sender_allowlist = Some(senders.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Keeps only messages posted in the forum topics titled `topics`,
+    /// ignoring ASCII case.
+    ///
+    /// ```rust
+    /// use chatpack::config::TelegramConfig;
+    ///
+    /// let config = TelegramConfig::new().with_topic_filter(["Releases"]);
+    /// ```
+    #[must_use]
+    pub fn with_topic_filter<I, S>(mut self, topics: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.topic_filter = Some(topics.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets whether an export cut off partway through a record keeps the
+    /// records before the cut instead of failing.
+    #[must_use]
+    pub fn with_tolerate_truncation(mut self, tolerate: bool) -> Self {
+        self.tolerate_truncation = tolerate;
+        self
+    }
+
+    /// Enables or disables collapsing albums into one message.
+    #[must_use]
+    pub fn with_merge_albums(mut self, merge: bool) -> Self {
+        self.merge_albums = merge;
+        self
+    }
+
+    /// Sets the whitespace rules applied to message content.
+    #[must_use]
+    pub fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
+}
+
+fn utc_offset() -> FixedOffset {
+    FixedOffset::east_opt(0).unwrap()
+}
+
+/// Serializes [`FixedOffset`] as a `"+HH:MM"` string.
+mod fixed_offset_serde {
+    use chrono::FixedOffset;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        offset: &FixedOffset,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(offset)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<FixedOffset, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
 }
 
 /// Configuration for WhatsApp export parsing.
@@ -135,8 +703,68 @@ pub struct WhatsAppConfig {
     /// Skip system messages (user added/removed, etc.) (default: true)
     pub skip_system_messages: bool,
 
+    /// Replace localized media placeholders such as `<Medien ausgeschlossen>`
+    /// or `image omitted` with `[Media omitted]` (default: true)
+    #[serde(default = "default_true")]
+    pub normalize_media_placeholders: bool,
+
+    /// Replace localized deleted-message lines such as `This message was
+    /// deleted` or `Du hast diese Nachricht gelöscht.` with
+    /// [`MESSAGE_DELETED`](crate::message::MESSAGE_DELETED) (default: true)
+    #[serde(default = "default_true")]
+    pub normalize_deleted_messages: bool,
+
     /// Skip invalid messages instead of returning errors (default: true)
     pub skip_invalid: bool,
+
+    /// Largest file `Parser::parse` will load into memory, in bytes
+    /// (default: 1GB). Larger files are rejected with
+    /// [`ChatpackError::FileTooLarge`](crate::ChatpackError::FileTooLarge);
+    /// `Parser::stream` switches to streaming instead.
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+
+    /// Longest line read, in bytes, without its line ending (default: 1MB).
+    /// Longer lines are never held in memory whole: they are skipped and
+    /// counted with `skip_invalid`, and fail with
+    /// [`ChatpackError::BufferOverflow`](crate::ChatpackError::BufferOverflow)
+    /// otherwise.
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+
+    /// Keep a `[Replying to: …]` line in place of a quoted snippet that
+    /// matches no earlier message; when false the quote is dropped
+    /// (default: true). Resolved quotes are always removed and recorded in
+    /// `reply_to`.
+    #[serde(default = "default_true")]
+    pub mark_unresolved_quotes: bool,
+
+    /// Clock sanity check applied to message timestamps (default: none).
+    #[serde(default)]
+    pub clamp_timestamps: Option<TimestampCheck>,
+
+    /// Keep each message's source record in
+    /// [`Message::raw`](crate::Message::raw), for debugging (default: false).
+    #[serde(default)]
+    pub capture_raw: bool,
+
+    /// Senders to keep, compared ignoring ASCII case (default: all).
+    /// Records from anyone else are dropped right after they are decoded,
+    /// before their message is built.
+    #[serde(default)]
+    pub sender_allowlist: Option<Vec<String>>,
+
+    /// Read the export twice, first learning its sender names, to tell
+    /// senders containing `: ` (a contact saved as `Mom: work`) apart from
+    /// content containing it (default: false). See
+    /// [`KnownSenders`](crate::parsing::whatsapp::KnownSenders) for the rules.
+    #[serde(default)]
+    pub strict_sender_detection: bool,
+
+    /// Whitespace rules applied to message content (inlined when
+    /// serialized; see [`ContentPolicy`]).
+    #[serde(flatten)]
+    pub content: ContentPolicy,
 }
 
 impl Default for WhatsAppConfig {
@@ -145,7 +773,17 @@ impl Default for WhatsAppConfig {
             streaming: false,
             buffer_size: 64 * 1024, // 64KB
             skip_system_messages: true,
+            normalize_media_placeholders: true,
+            normalize_deleted_messages: true,
             skip_invalid: true,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            mark_unresolved_quotes: true,
+            clamp_timestamps: None,
+            capture_raw: false,
+            sender_allowlist: None,
+            strict_sender_detection: false,
+            content: ContentPolicy::default(),
         }
     }
 }
@@ -186,12 +824,88 @@ impl WhatsAppConfig {
         self
     }
 
+    /// Sets whether to normalize localized media placeholders.
+    #[must_use]
+    pub fn with_normalize_media_placeholders(mut self, normalize: bool) -> Self {
+        self.normalize_media_placeholders = normalize;
+        self
+    }
+
+    /// Sets whether to normalize localized deleted-message lines.
+    #[must_use]
+    pub fn with_normalize_deleted_messages(mut self, normalize: bool) -> Self {
+        self.normalize_deleted_messages = normalize;
+        self
+    }
+
     /// Sets whether to skip invalid messages.
     #[must_use]
     pub fn with_skip_invalid(mut self, skip: bool) -> Self {
         self.skip_invalid = skip;
         self
     }
+
+    /// Sets the largest file size loaded into memory by `Parser::parse`.
+    ///
+    /// Use `u64::MAX` to disable the check.
+    #[must_use]
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Sets the longest line read, in bytes.
+    #[must_use]
+    pub fn with_max_line_length(mut self, bytes: usize) -> Self {
+        self.max_line_length = bytes;
+        self
+    }
+
+    /// Sets whether unresolved quotes leave a `[Replying to: …]` line.
+    #[must_use]
+    pub fn with_mark_unresolved_quotes(mut self, mark: bool) -> Self {
+        self.mark_unresolved_quotes = mark;
+        self
+    }
+
+    /// Sets the clock sanity check applied to message timestamps.
+    #[must_use]
+    pub fn with_clamp_timestamps(mut self, check: TimestampCheck) -> Self {
+        self.clamp_timestamps = Some(check);
+        self
+    }
+
+    /// Enables or disables keeping each message's source record.
+    #[must_use]
+    pub fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    /// Keeps only messages from `senders`, ignoring ASCII case.
+    #[must_use]
+    pub fn with_sender_allowlist<I, S>(mut self, senders: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.sender_allowlist = Some(senders.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Enables or disables learning sender names before parsing.
+    #[must_use]
+    pub fn with_strict_sender_detection(mut self, strict: bool) -> Self {
+        self.strict_sender_detection = strict;
+        self
+    }
+
+    /// Sets the whitespace rules applied to message content.
+    #[must_use]
+    pub fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
 }
 
 /// Configuration for Instagram export parsing.
@@ -223,6 +937,75 @@ pub struct InstagramConfig {
 
     /// Skip invalid messages instead of returning errors (default: true)
     pub skip_invalid: bool,
+
+    /// Largest file `Parser::parse` will load into memory, in bytes
+    /// (default: 1GB). Larger files are rejected with
+    /// [`ChatpackError::FileTooLarge`](crate::ChatpackError::FileTooLarge);
+    /// `Parser::stream` switches to streaming instead.
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+
+    /// Collect structured [`AttachmentRef`](crate::AttachmentRef)s into
+    /// `Message::attachments` (default: false). Only `Parser::parse` and
+    /// `Parser::parse_str` collect them; streaming does not.
+    #[serde(default)]
+    pub collect_attachment_refs: bool,
+
+    /// Clock sanity check applied to message timestamps (default: none).
+    #[serde(default)]
+    pub clamp_timestamps: Option<TimestampCheck>,
+
+    /// Keep each message's source record in
+    /// [`Message::raw`](crate::Message::raw), for debugging (default: false).
+    #[serde(default)]
+    pub capture_raw: bool,
+
+    /// Replace the content of call records with `[Call: 12m 30s]` or
+    /// `[Missed call]`, and keep audio messages without text as
+    /// `[Voice message]` (default: false).
+    #[serde(default)]
+    pub include_calls: bool,
+
+    /// Senders to keep, compared ignoring ASCII case (default: all).
+    /// Records from anyone else are dropped right after they are decoded,
+    /// before their message is built. Names are
+    /// compared after the mojibake fix.
+    #[serde(default)]
+    pub sender_allowlist: Option<Vec<String>>,
+
+    /// End at a record cut off by the end of the file, such as an
+    /// interrupted download, instead of failing (default: false). The
+    /// complete records before the cut are kept and the cut-off record is
+    /// counted as skipped. Exports with no complete record still fail.
+    #[serde(default)]
+    pub tolerate_truncation: bool,
+
+    /// Map senders missing from the export's `participants` list, which
+    /// holds only current names, onto a current participant (default:
+    /// false).
+    ///
+    /// Meta writes the name a sender had at the time on each message, so
+    /// after a username change one person appears under two names. In a
+    /// two-person thread, an old name is mapped to the participant who
+    /// never writes between its first and last message; the renamed
+    /// message keeps the old name in
+    /// [`Message::original_sender`](crate::Message::original_sender). The
+    /// heuristic guesses wrong if a participant's old and new names
+    /// overlap in time, and leaves the name alone if both participants
+    /// fit or neither does. Group threads are never guessed: their unknown
+    /// names are listed by
+    /// [`InstagramParser::parse_with_report`](crate::parsers::InstagramParser::parse_with_report)
+    /// for a [`SenderMap`](crate::core::processor::SenderMap). Names are
+    /// compared after the mojibake fix and after `sender_allowlist`. Only
+    /// `Parser::parse` and `Parser::parse_str` canonicalize; streaming
+    /// does not.
+    #[serde(default)]
+    pub canonicalize_participants: bool,
+
+    /// Whitespace rules applied to message content (inlined when
+    /// serialized; see [`ContentPolicy`]).
+    #[serde(flatten)]
+    pub content: ContentPolicy,
 }
 
 impl Default for InstagramConfig {
@@ -233,6 +1016,15 @@ impl Default for InstagramConfig {
             max_message_size: 10 * 1024 * 1024, // 10MB
             fix_encoding: true,
             skip_invalid: true,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            collect_attachment_refs: false,
+            clamp_timestamps: None,
+            capture_raw: false,
+            include_calls: false,
+            sender_allowlist: None,
+            tolerate_truncation: false,
+            canonicalize_participants: false,
+            content: ContentPolicy::default(),
         }
     }
 }
@@ -286,6 +1078,77 @@ impl InstagramConfig {
         self.skip_invalid = skip;
         self
     }
+
+    /// Sets the largest file size loaded into memory by `Parser::parse`.
+    ///
+    /// Use `u64::MAX` to disable the check.
+    #[must_use]
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Sets whether to collect attachment refs into `Message::attachments`.
+    #[must_use]
+    pub fn with_collect_attachment_refs(mut self, collect: bool) -> Self {
+        self.collect_attachment_refs = collect;
+        self
+    }
+
+    /// Sets the clock sanity check applied to message timestamps.
+    #[must_use]
+    pub fn with_clamp_timestamps(mut self, check: TimestampCheck) -> Self {
+        self.clamp_timestamps = Some(check);
+        self
+    }
+
+    /// Enables or disables keeping each message's source record.
+    #[must_use]
+    pub fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    /// Enables or disables representing calls and voice messages.
+    #[must_use]
+    pub fn with_include_calls(mut self, include: bool) -> Self {
+        self.include_calls = include;
+        self
+    }
+
+    /// Keeps only messages from `senders`, ignoring ASCII case.
+    #[must_use]
+    pub fn with_sender_allowlist<I, S>(mut self, senders: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.sender_allowlist = Some(senders.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets whether an export cut off partway through a record keeps the
+    /// records before the cut instead of failing.
+    #[must_use]
+    pub fn with_tolerate_truncation(mut self, tolerate: bool) -> Self {
+        self.tolerate_truncation = tolerate;
+        self
+    }
+
+    /// Enables or disables mapping old sender names onto current
+    /// participants.
+    #[must_use]
+    pub fn with_canonicalize_participants(mut self, canonicalize: bool) -> Self {
+        self.canonicalize_participants = canonicalize;
+        self
+    }
+
+    /// Sets the whitespace rules applied to message content.
+    #[must_use]
+    pub fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
 }
 
 /// Configuration for Discord export parsing.
@@ -312,14 +1175,93 @@ pub struct DiscordConfig {
     /// Maximum message size in bytes (default: 10MB)
     pub max_message_size: usize,
 
-    /// Prefer nickname over username when available (default: true)
+    /// Prefer nickname over username when available (default: true).
+    /// JSON and JSONL exports only; TXT and CSV exports have a single name.
     pub prefer_nickname: bool,
 
+    /// Append the author's legacy `#discriminator` to the sender, e.g.
+    /// `alice#1234` (default: false). Zero discriminators of migrated
+    /// usernames are never appended. JSON and JSONL exports only.
+    #[serde(default)]
+    pub append_discriminator: bool,
+
     /// Include attachment/sticker information (default: true)
     pub include_attachments: bool,
 
     /// Skip invalid messages instead of returning errors (default: true)
     pub skip_invalid: bool,
+
+    /// Largest file `Parser::parse` will load into memory, in bytes
+    /// (default: 1GB). Larger files are rejected with
+    /// [`ChatpackError::FileTooLarge`](crate::ChatpackError::FileTooLarge);
+    /// `Parser::stream` switches to streaming instead.
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+
+    /// Longest TXT line read, in bytes, without its line ending (default:
+    /// 1MB). Longer lines are never held in memory whole: they are skipped and
+    /// counted with `skip_invalid`, and fail with
+    /// [`ChatpackError::BufferOverflow`](crate::ChatpackError::BufferOverflow)
+    /// otherwise.
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+
+    /// Collect structured [`AttachmentRef`](crate::AttachmentRef)s into
+    /// `Message::attachments` (default: false). Only `Parser::parse` and
+    /// `Parser::parse_str` collect them; streaming does not.
+    #[serde(default)]
+    pub collect_attachment_refs: bool,
+
+    /// Clock sanity check applied to message timestamps (default: none).
+    #[serde(default)]
+    pub clamp_timestamps: Option<TimestampCheck>,
+
+    /// Keep each message's source record in
+    /// [`Message::raw`](crate::Message::raw), for debugging (default: false).
+    /// JSON and JSONL exports only.
+    #[serde(default)]
+    pub capture_raw: bool,
+
+    /// Keep system notifications such as joins, pins, and boosts as
+    /// messages like `[Alice joined the server]` instead of skipping them
+    /// (default: false). `Reply` messages are always regular messages.
+    /// See [`DiscordEvent`](crate::parsing::discord::DiscordEvent).
+    #[serde(default)]
+    pub include_system_events: bool,
+
+    /// Senders to keep, compared ignoring ASCII case (default: all).
+    /// Records from anyone else are dropped right after they are decoded,
+    /// before their message is built. Names are
+    /// compared as chosen by the nickname and discriminator settings.
+    #[serde(default)]
+    pub sender_allowlist: Option<Vec<String>>,
+
+    /// End at a record cut off by the end of the file, such as an
+    /// interrupted download, instead of failing (default: false). The
+    /// complete records before the cut are kept and the cut-off record is
+    /// counted as skipped. Exports with no complete record still fail.
+    #[serde(default)]
+    pub tolerate_truncation: bool,
+
+    /// Keep a `[Replying to: author: …]` line in place of a TXT reply
+    /// context that matches no earlier message; when false it is dropped
+    /// (default: true). Resolved replies are always removed and recorded
+    /// in `reply_to`.
+    #[serde(default = "default_true")]
+    pub mark_unresolved_quotes: bool,
+
+    /// [chrono format](chrono::format::strftime) tried for a CSV `Date`
+    /// cell that is neither RFC 3339 nor one of DiscordChatExporter's known
+    /// formats, such as `"%Y/%m/%d %H.%M"` (default: none). Dates without
+    /// an offset are read as UTC. Dates no format matches are left unset
+    /// and counted by `Parser::parse_counted`.
+    #[serde(default)]
+    pub csv_date_format: Option<String>,
+
+    /// Whitespace rules applied to message content (inlined when
+    /// serialized; see [`ContentPolicy`]).
+    #[serde(flatten)]
+    pub content: ContentPolicy,
 }
 
 impl Default for DiscordConfig {
@@ -329,8 +1271,20 @@ impl Default for DiscordConfig {
             buffer_size: 64 * 1024,             // 64KB
             max_message_size: 10 * 1024 * 1024, // 10MB
             prefer_nickname: true,
+            append_discriminator: false,
             include_attachments: true,
             skip_invalid: true,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            collect_attachment_refs: false,
+            clamp_timestamps: None,
+            capture_raw: false,
+            include_system_events: false,
+            sender_allowlist: None,
+            tolerate_truncation: false,
+            mark_unresolved_quotes: true,
+            csv_date_format: None,
+            content: ContentPolicy::default(),
         }
     }
 }
@@ -378,6 +1332,13 @@ impl DiscordConfig {
         self
     }
 
+    /// Sets whether to append `#discriminator` to sender names.
+    #[must_use]
+    pub fn with_append_discriminator(mut self, append: bool) -> Self {
+        self.append_discriminator = append;
+        self
+    }
+
     /// Sets whether to include attachments in message content.
     #[must_use]
     pub fn with_include_attachments(mut self, include: bool) -> Self {
@@ -391,6 +1352,468 @@ impl DiscordConfig {
         self.skip_invalid = skip;
         self
     }
+
+    /// Sets the largest file size loaded into memory by `Parser::parse`.
+    ///
+    /// Use `u64::MAX` to disable the check.
+    #[must_use]
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Sets the longest line read, in bytes.
+    #[must_use]
+    pub fn with_max_line_length(mut self, bytes: usize) -> Self {
+        self.max_line_length = bytes;
+        self
+    }
+
+    /// Sets whether to collect attachment refs into `Message::attachments`.
+    #[must_use]
+    pub fn with_collect_attachment_refs(mut self, collect: bool) -> Self {
+        self.collect_attachment_refs = collect;
+        self
+    }
+
+    /// Sets the clock sanity check applied to message timestamps.
+    #[must_use]
+    pub fn with_clamp_timestamps(mut self, check: TimestampCheck) -> Self {
+        self.clamp_timestamps = Some(check);
+        self
+    }
+
+    /// Enables or disables keeping each message's source record.
+    #[must_use]
+    pub fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    /// Enables or disables keeping system notifications as messages.
+    #[must_use]
+    pub fn with_include_system_events(mut self, include: bool) -> Self {
+        self.include_system_events = include;
+        self
+    }
+
+    /// Keeps only messages from `senders`, ignoring ASCII case.
+    #[must_use]
+    pub fn with_sender_allowlist<I, S>(mut self, senders: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.sender_allowlist = Some(senders.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets whether an export cut off partway through a record keeps the
+    /// records before the cut instead of failing.
+    #[must_use]
+    pub fn with_tolerate_truncation(mut self, tolerate: bool) -> Self {
+        self.tolerate_truncation = tolerate;
+        self
+    }
+
+    /// Sets whether unresolved TXT reply contexts leave a
+    /// `[Replying to: …]` line.
+    #[must_use]
+    pub fn with_mark_unresolved_quotes(mut self, mark: bool) -> Self {
+        self.mark_unresolved_quotes = mark;
+        self
+    }
+
+    /// Sets the chrono format tried for CSV dates in no known format.
+    #[must_use]
+    pub fn with_csv_date_format(mut self, format: impl Into<String>) -> Self {
+        self.csv_date_format = Some(format.into());
+        self
+    }
+
+    /// Sets the whitespace rules applied to message content.
+    #[must_use]
+    pub fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
+}
+
+// Schema introspection needs serde_json, which every parser feature enables
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+pub use schema::{ConfigField, ConfigSchema, FieldKind};
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+pub(crate) use schema::{config_to_json, update_config_json};
+
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+mod schema {
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+
+    use super::{DiscordConfig, InstagramConfig, TelegramConfig, WhatsAppConfig};
+    use crate::error::ChatpackError;
+
+    /// The JSON type of a config option, for rendering an editor for it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    #[non_exhaustive]
+    pub enum FieldKind {
+        /// `true` or `false`.
+        Bool,
+        /// A non-negative integer.
+        Integer,
+        /// A string; the description gives its format.
+        String,
+        /// One of the listed strings.
+        Enum(&'static [&'static str]),
+        /// An array of strings.
+        StringList,
+        /// A nested object, such as a [`TimestampCheck`](super::TimestampCheck).
+        Object,
+    }
+
+    /// One option of a platform config, as listed by [`ConfigSchema::schema`].
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct ConfigField {
+        /// Key of the option in the config's JSON form.
+        pub name: &'static str,
+        /// JSON type of the option.
+        pub kind: FieldKind,
+        /// Value of the option in the config's `Default`.
+        pub default: Value,
+        /// One-line description for display.
+        pub description: &'static str,
+    }
+
+    impl ConfigField {
+        /// Returns whether the option may be `null`, meaning unset.
+        pub fn is_optional(&self) -> bool {
+            self.default.is_null()
+        }
+    }
+
+    /// Lists the options of a platform config.
+    ///
+    /// Each entry matches a key of the config's JSON form, in declaration
+    /// order, with the [`ContentPolicy`](super::ContentPolicy) keys last.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use chatpack::config::{ConfigSchema, FieldKind, WhatsAppConfig};
+    ///
+    /// let schema = WhatsAppConfig::schema();
+    /// let field = schema.iter().find(|f| f.name == "skip_system_messages").unwrap();
+    /// assert_eq!(field.kind, FieldKind::Bool);
+    /// assert_eq!(field.default, serde_json::json!(true));
+    /// ```
+    pub trait ConfigSchema {
+        /// Returns every option with its type, default, and description.
+        fn schema() -> Vec<ConfigField>;
+    }
+
+    const MENTION_FORMATS: &[&str] = &["as_exported", "at", "plain"];
+
+    type Spec = (&'static str, FieldKind, &'static str);
+
+    const STREAMING: Spec = (
+        "streaming",
+        FieldKind::Bool,
+        "Stream large files instead of loading them whole",
+    );
+    const BUFFER_SIZE: Spec = (
+        "buffer_size",
+        FieldKind::Integer,
+        "Read buffer size for streaming, in bytes",
+    );
+    const MAX_MESSAGE_SIZE: Spec = (
+        "max_message_size",
+        FieldKind::Integer,
+        "Largest single message accepted while streaming, in bytes",
+    );
+    const SKIP_INVALID: Spec = (
+        "skip_invalid",
+        FieldKind::Bool,
+        "Skip invalid records instead of failing",
+    );
+    const MAX_FILE_SIZE: Spec = (
+        "max_file_size",
+        FieldKind::Integer,
+        "Largest file loaded into memory, in bytes",
+    );
+    const MAX_LINE_LENGTH: Spec = (
+        "max_line_length",
+        FieldKind::Integer,
+        "Longest line read, in bytes; longer lines are skipped or fail",
+    );
+    const COLLECT_ATTACHMENT_REFS: Spec = (
+        "collect_attachment_refs",
+        FieldKind::Bool,
+        "Collect structured attachment references",
+    );
+    const CLAMP_TIMESTAMPS: Spec = (
+        "clamp_timestamps",
+        FieldKind::Object,
+        "Clock sanity check for timestamps: a range and an action",
+    );
+    const CAPTURE_RAW: Spec = (
+        "capture_raw",
+        FieldKind::Bool,
+        "Keep each message's source record for debugging",
+    );
+    const INCLUDE_CALLS: Spec = (
+        "include_calls",
+        FieldKind::Bool,
+        "Keep calls and voice messages as placeholder messages",
+    );
+    const MARK_UNRESOLVED_QUOTES: Spec = (
+        "mark_unresolved_quotes",
+        FieldKind::Bool,
+        "Keep a [Replying to: …] line for quotes that match no message",
+    );
+    const TOLERATE_TRUNCATION: Spec = (
+        "tolerate_truncation",
+        FieldKind::Bool,
+        "Keep the records before a cut-off end instead of failing",
+    );
+    const SENDER_ALLOWLIST: Spec = (
+        "sender_allowlist",
+        FieldKind::StringList,
+        "Senders to keep, ignoring ASCII case",
+    );
+    const CONTENT: [Spec; 3] = [
+        (
+            "trim_content",
+            FieldKind::Bool,
+            "Trim whitespace around message content",
+        ),
+        (
+            "collapse_internal_blank_lines",
+            FieldKind::Bool,
+            "Collapse runs of blank lines inside messages",
+        ),
+        (
+            "ensure_single_space_before_markers",
+            FieldKind::Bool,
+            "Put attachment and sticker markers right after the preceding line",
+        ),
+    ];
+
+    /// Builds the schema of `C`, taking defaults from `C::default()`.
+    fn fields<C: Default + Serialize>(specs: &[Spec]) -> Vec<ConfigField> {
+        let defaults = serde_json::to_value(C::default()).expect("configs serialize to JSON");
+        specs
+            .iter()
+            .chain(&CONTENT)
+            .map(|&(name, kind, description)| ConfigField {
+                name,
+                kind,
+                default: defaults[name].clone(),
+                description,
+            })
+            .collect()
+    }
+
+    impl ConfigSchema for TelegramConfig {
+        fn schema() -> Vec<ConfigField> {
+            fields::<Self>(&[
+                STREAMING,
+                BUFFER_SIZE,
+                MAX_MESSAGE_SIZE,
+                SKIP_INVALID,
+                (
+                    "assume_timezone",
+                    FieldKind::String,
+                    "UTC offset (\"+HH:MM\") for old exports without Unix timestamps",
+                ),
+                MAX_FILE_SIZE,
+                COLLECT_ATTACHMENT_REFS,
+                (
+                    "mention_format",
+                    FieldKind::Enum(MENTION_FORMATS),
+                    "How mentions are written",
+                ),
+                CLAMP_TIMESTAMPS,
+                CAPTURE_RAW,
+                INCLUDE_CALLS,
+                SENDER_ALLOWLIST,
+                (
+                    "topic_filter",
+                    FieldKind::StringList,
+                    "Forum topics to keep, by title, ignoring ASCII case",
+                ),
+                TOLERATE_TRUNCATION,
+                (
+                    "merge_albums",
+                    FieldKind::Bool,
+                    "Collapse each photo or video album into one message",
+                ),
+            ])
+        }
+    }
+
+    impl ConfigSchema for WhatsAppConfig {
+        fn schema() -> Vec<ConfigField> {
+            fields::<Self>(&[
+                STREAMING,
+                BUFFER_SIZE,
+                (
+                    "skip_system_messages",
+                    FieldKind::Bool,
+                    "Drop system notifications such as joins and encryption notices",
+                ),
+                (
+                    "normalize_media_placeholders",
+                    FieldKind::Bool,
+                    "Replace localized media placeholders with [Media omitted]",
+                ),
+                (
+                    "normalize_deleted_messages",
+                    FieldKind::Bool,
+                    "Replace localized deleted-message lines with [Message deleted]",
+                ),
+                SKIP_INVALID,
+                MAX_FILE_SIZE,
+                MAX_LINE_LENGTH,
+                MARK_UNRESOLVED_QUOTES,
+                CLAMP_TIMESTAMPS,
+                CAPTURE_RAW,
+                SENDER_ALLOWLIST,
+                (
+                    "strict_sender_detection",
+                    FieldKind::Bool,
+                    "Learn sender names first to split senders containing \": \" correctly",
+                ),
+            ])
+        }
+    }
+
+    impl ConfigSchema for InstagramConfig {
+        fn schema() -> Vec<ConfigField> {
+            fields::<Self>(&[
+                STREAMING,
+                BUFFER_SIZE,
+                MAX_MESSAGE_SIZE,
+                (
+                    "fix_encoding",
+                    FieldKind::Bool,
+                    "Repair Mojibake in non-ASCII text",
+                ),
+                SKIP_INVALID,
+                MAX_FILE_SIZE,
+                COLLECT_ATTACHMENT_REFS,
+                CLAMP_TIMESTAMPS,
+                CAPTURE_RAW,
+                INCLUDE_CALLS,
+                SENDER_ALLOWLIST,
+                TOLERATE_TRUNCATION,
+                (
+                    "canonicalize_participants",
+                    FieldKind::Bool,
+                    "Map old names in two-person threads onto current participants",
+                ),
+            ])
+        }
+    }
+
+    impl ConfigSchema for DiscordConfig {
+        fn schema() -> Vec<ConfigField> {
+            fields::<Self>(&[
+                STREAMING,
+                BUFFER_SIZE,
+                MAX_MESSAGE_SIZE,
+                (
+                    "prefer_nickname",
+                    FieldKind::Bool,
+                    "Use server nicknames instead of usernames",
+                ),
+                (
+                    "append_discriminator",
+                    FieldKind::Bool,
+                    "Append #discriminator to legacy usernames",
+                ),
+                (
+                    "include_attachments",
+                    FieldKind::Bool,
+                    "Include attachment and sticker information",
+                ),
+                SKIP_INVALID,
+                MAX_FILE_SIZE,
+                MAX_LINE_LENGTH,
+                COLLECT_ATTACHMENT_REFS,
+                CLAMP_TIMESTAMPS,
+                CAPTURE_RAW,
+                (
+                    "include_system_events",
+                    FieldKind::Bool,
+                    "Keep system events such as pins and joins",
+                ),
+                SENDER_ALLOWLIST,
+                TOLERATE_TRUNCATION,
+                MARK_UNRESOLVED_QUOTES,
+                (
+                    "csv_date_format",
+                    FieldKind::String,
+                    "chrono format for CSV dates in no known format",
+                ),
+            ])
+        }
+    }
+
+    /// Returns `config` in its JSON form.
+    pub(crate) fn config_to_json<C: Serialize>(config: &C) -> Value {
+        serde_json::to_value(config).expect("configs serialize to JSON")
+    }
+
+    /// Applies the options in `update`, a JSON object, to `config`.
+    ///
+    /// Options not in `update` keep their values. Nothing changes unless
+    /// every option is known and valid.
+    pub(crate) fn update_config_json<C>(config: &mut C, update: Value) -> Result<(), ChatpackError>
+    where
+        C: ConfigSchema + Serialize + DeserializeOwned,
+    {
+        let Value::Object(update) = update else {
+            return Err(ChatpackError::invalid_config(format!(
+                "expected a JSON object, got {update}"
+            )));
+        };
+        let schema = C::schema();
+        let mut merged = config_to_json(config);
+        for (name, value) in update {
+            if !schema.iter().any(|field| field.name == name) {
+                return Err(ChatpackError::invalid_config(format!(
+                    "unknown option '{name}'"
+                )));
+            }
+            merged[name.as_str()] = value;
+            // Checked one option at a time so the error names the culprit
+            if let Err(e) = C::deserialize(&merged) {
+                return Err(ChatpackError::invalid_config(format!(
+                    "option '{name}': {e}"
+                )));
+            }
+        }
+        *config =
+            C::deserialize(merged).map_err(|e| ChatpackError::invalid_config(e.to_string()))?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -471,6 +1894,76 @@ mod tests {
         assert_eq!(parsed.buffer_size, config.buffer_size);
     }
 
+    #[test]
+    fn test_telegram_config_mention_format() {
+        assert_eq!(
+            TelegramConfig::default().mention_format,
+            MentionFormat::AsExported
+        );
+
+        let config = TelegramConfig::new().with_mention_format(MentionFormat::At);
+        let json = serde_json::to_string(&config).expect("serialize failed");
+        assert!(json.contains(r#""mention_format":"at""#));
+        let parsed: TelegramConfig = serde_json::from_str(&json).expect("deserialize failed");
+        assert_eq!(parsed.mention_format, MentionFormat::At);
+    }
+
+    #[test]
+    fn test_telegram_config_assume_timezone() {
+        assert_eq!(
+            TelegramConfig::default().assume_timezone.local_minus_utc(),
+            0
+        );
+
+        let offset = FixedOffset::east_opt(3 * 3600).unwrap();
+        let config = TelegramConfig::new().with_assume_timezone(offset);
+        let json = serde_json::to_string(&config).expect("serialize failed");
+        assert!(json.contains("\"+03:00\""));
+
+        let parsed: TelegramConfig = serde_json::from_str(&json).expect("deserialize failed");
+        assert_eq!(parsed.assume_timezone, offset);
+
+        // Older serialized configs without the field still load
+        let legacy =
+            r#"{"streaming":false,"buffer_size":1,"max_message_size":1,"skip_invalid":true}"#;
+        let parsed: TelegramConfig = serde_json::from_str(legacy).expect("deserialize failed");
+        assert_eq!(parsed.assume_timezone.local_minus_utc(), 0);
+        assert_eq!(parsed.max_file_size, DEFAULT_MAX_FILE_SIZE);
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        assert_eq!(
+            WhatsAppConfig::new().max_line_length,
+            DEFAULT_MAX_LINE_LENGTH
+        );
+        let config = DiscordConfig::new().with_max_line_length(4096);
+        assert_eq!(config.max_line_length, 4096);
+
+        // Older serialized configs without the field still load
+        let mut json = serde_json::to_value(WhatsAppConfig::new().with_max_line_length(1)).unwrap();
+        json.as_object_mut().unwrap().remove("max_line_length");
+        let parsed: WhatsAppConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.max_line_length, DEFAULT_MAX_LINE_LENGTH);
+    }
+
+    #[test]
+    fn test_max_file_size() {
+        assert_eq!(TelegramConfig::new().max_file_size, DEFAULT_MAX_FILE_SIZE);
+        assert_eq!(WhatsAppConfig::new().max_file_size, DEFAULT_MAX_FILE_SIZE);
+        assert_eq!(InstagramConfig::new().max_file_size, DEFAULT_MAX_FILE_SIZE);
+        assert_eq!(DiscordConfig::new().max_file_size, DEFAULT_MAX_FILE_SIZE);
+
+        let config = DiscordConfig::new().with_max_file_size(1024);
+        assert_eq!(config.max_file_size, 1024);
+        assert_eq!(
+            WhatsAppConfig::streaming()
+                .with_max_file_size(u64::MAX)
+                .max_file_size,
+            u64::MAX
+        );
+    }
+
     // =========================================================================
     // WhatsAppConfig tests
     // =========================================================================
@@ -516,6 +2009,20 @@ mod tests {
         assert!(!config.skip_system_messages);
     }
 
+    #[test]
+    fn test_whatsapp_config_with_normalize_media_placeholders() {
+        assert!(WhatsAppConfig::new().normalize_media_placeholders);
+        let config = WhatsAppConfig::new().with_normalize_media_placeholders(false);
+        assert!(!config.normalize_media_placeholders);
+    }
+
+    #[test]
+    fn test_whatsapp_config_with_mark_unresolved_quotes() {
+        assert!(WhatsAppConfig::new().mark_unresolved_quotes);
+        let config = WhatsAppConfig::new().with_mark_unresolved_quotes(false);
+        assert!(!config.mark_unresolved_quotes);
+    }
+
     #[test]
     fn test_whatsapp_config_with_skip_invalid() {
         let config = WhatsAppConfig::new().with_skip_invalid(false);
@@ -680,6 +2187,13 @@ mod tests {
         assert!(!config.prefer_nickname);
     }
 
+    #[test]
+    fn test_discord_config_with_append_discriminator() {
+        assert!(!DiscordConfig::new().append_discriminator);
+        let config = DiscordConfig::new().with_append_discriminator(true);
+        assert!(config.append_discriminator);
+    }
+
     #[test]
     fn test_discord_config_with_include_attachments() {
         let config = DiscordConfig::new().with_include_attachments(false);
@@ -692,6 +2206,13 @@ mod tests {
         assert!(!config.skip_invalid);
     }
 
+    #[test]
+    fn test_discord_config_with_mark_unresolved_quotes() {
+        assert!(DiscordConfig::new().mark_unresolved_quotes);
+        let config = DiscordConfig::new().with_mark_unresolved_quotes(false);
+        assert!(!config.mark_unresolved_quotes);
+    }
+
     #[test]
     fn test_discord_config_builder_chain() {
         let config = DiscordConfig::new()
@@ -747,6 +2268,29 @@ mod tests {
         assert_eq!(discord.prefer_nickname, discord_clone.prefer_nickname);
     }
 
+    #[test]
+    fn test_configs_tolerate_truncation() {
+        assert!(!TelegramConfig::default().tolerate_truncation);
+        assert!(!InstagramConfig::default().tolerate_truncation);
+        assert!(!DiscordConfig::default().tolerate_truncation);
+
+        assert!(
+            TelegramConfig::new()
+                .with_tolerate_truncation(true)
+                .tolerate_truncation
+        );
+        assert!(
+            InstagramConfig::new()
+                .with_tolerate_truncation(true)
+                .tolerate_truncation
+        );
+        assert!(
+            DiscordConfig::new()
+                .with_tolerate_truncation(true)
+                .tolerate_truncation
+        );
+    }
+
     #[test]
     fn test_configs_debug() {
         let telegram = TelegramConfig::new();
@@ -766,4 +2310,204 @@ mod tests {
         let debug_str = format!("{:?}", discord);
         assert!(debug_str.contains("DiscordConfig"));
     }
+
+    // =========================================================================
+    // ConfigSchema tests
+    // =========================================================================
+
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ))]
+    fn assert_schema_matches_default<C: ConfigSchema + Default + Serialize>() {
+        let schema_defaults: serde_json::Map<String, serde_json::Value> = C::schema()
+            .into_iter()
+            .map(|field| (field.name.to_string(), field.default))
+            .collect();
+        assert_eq!(
+            serde_json::Value::Object(schema_defaults),
+            serde_json::to_value(C::default()).unwrap()
+        );
+    }
+
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ))]
+    #[test]
+    fn test_schema_defaults_match_default_impls() {
+        assert_schema_matches_default::<TelegramConfig>();
+        assert_schema_matches_default::<WhatsAppConfig>();
+        assert_schema_matches_default::<InstagramConfig>();
+        assert_schema_matches_default::<DiscordConfig>();
+    }
+
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ))]
+    #[test]
+    fn test_schema_kinds() {
+        let schema = TelegramConfig::schema();
+        let kind = |name: &str| schema.iter().find(|f| f.name == name).unwrap().kind;
+        assert_eq!(kind("buffer_size"), FieldKind::Integer);
+        assert_eq!(kind("assume_timezone"), FieldKind::String);
+        assert_eq!(
+            kind("mention_format"),
+            FieldKind::Enum(&["as_exported", "at", "plain"])
+        );
+        assert_eq!(kind("sender_allowlist"), FieldKind::StringList);
+        assert!(schema.iter().all(|f| !f.description.is_empty()));
+
+        let optional: Vec<_> = schema
+            .iter()
+            .filter(|f| f.is_optional())
+            .map(|f| f.name)
+            .collect();
+        assert_eq!(
+            optional,
+            ["clamp_timestamps", "sender_allowlist", "topic_filter"]
+        );
+
+        let json = serde_json::to_value(&schema[7]).unwrap();
+        assert_eq!(
+            json["kind"],
+            serde_json::json!({"enum": ["as_exported", "at", "plain"]})
+        );
+    }
+
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ))]
+    #[test]
+    fn test_update_config_json() {
+        use serde_json::json;
+
+        let mut config = WhatsAppConfig::new();
+        update_config_json(
+            &mut config,
+            json!({"skip_system_messages": false, "sender_allowlist": ["Alice"]}),
+        )
+        .unwrap();
+        assert!(!config.skip_system_messages);
+        assert_eq!(config.sender_allowlist, Some(vec!["Alice".to_string()]));
+        assert_eq!(config.buffer_size, 64 * 1024);
+
+        update_config_json(&mut config, json!({"trim_content": false})).unwrap();
+        assert!(!config.content.trim_content);
+
+        let err =
+            update_config_json(&mut config, json!({"streaming": true, "colour": 1})).unwrap_err();
+        assert!(err.is_invalid_config());
+        assert!(err.to_string().contains("'colour'"));
+        assert!(!config.streaming);
+
+        let err = update_config_json(&mut config, json!({"buffer_size": "big"})).unwrap_err();
+        assert!(err.to_string().contains("'buffer_size'"));
+        assert_eq!(config.buffer_size, 64 * 1024);
+
+        assert!(update_config_json(&mut config, json!([1])).is_err());
+        assert_eq!(
+            config_to_json(&config)["skip_system_messages"],
+            json!(false)
+        );
+    }
+
+    // =========================================================================
+    // Projection tests
+    // =========================================================================
+
+    #[cfg(all(
+        feature = "streaming",
+        any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json"
+        )
+    ))]
+    fn full_message() -> Message {
+        use crate::AttachmentRef;
+
+        let ts = chrono::DateTime::from_timestamp(1_705_314_600, 0).unwrap();
+        let mut msg = Message::new("Alice", "Hello")
+            .with_id(2)
+            .with_reply_to(1)
+            .with_timestamp(ts)
+            .with_edited(ts);
+        msg.forwarded_from = Some("Bob".into());
+        msg.raw = Some("{}".into());
+        msg.attachments.push(AttachmentRef::new("photo.jpg"));
+        msg
+    }
+
+    #[cfg(all(
+        feature = "streaming",
+        any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json"
+        )
+    ))]
+    #[test]
+    fn test_projection_all_keeps_everything() {
+        let mut msg = full_message();
+        Projection::all().apply(&mut msg);
+        assert_eq!(msg, full_message());
+        assert_eq!(Projection::default(), Projection::all());
+    }
+
+    #[cfg(all(
+        feature = "streaming",
+        any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json"
+        )
+    ))]
+    #[test]
+    fn test_projection_minimal_keeps_sender_and_timestamp() {
+        let mut msg = full_message();
+        Projection::minimal().apply(&mut msg);
+
+        let expected = Message::new("Alice", "").with_timestamp(msg.timestamp.unwrap());
+        assert_eq!(msg, expected);
+        assert!(msg.raw.is_none());
+        assert!(!Projection::minimal().is_all());
+    }
+
+    #[cfg(all(
+        feature = "streaming",
+        any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json"
+        )
+    ))]
+    #[test]
+    fn test_projection_content_only() {
+        let mut msg = full_message();
+        let projection = Projection {
+            need_ids: false,
+            ..Projection::all()
+        };
+        projection.apply(&mut msg);
+
+        assert_eq!(msg.content, "Hello");
+        assert_eq!(msg.id, None);
+        assert_eq!(msg.reply_to, None);
+        assert!(msg.edited.is_some());
+    }
 }
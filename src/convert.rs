@@ -0,0 +1,480 @@
+//! One-call conversion from an export file to an output file.
+//!
+//! [`convert`] parses, filters, merges, and writes in one step, with the
+//! platform detected from the input and the format inferred from the output
+//! extension unless [`ConvertOptions`] pins them. [`convert_many`] does the
+//! same for several exports written into one file.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # fn main() -> chatpack::Result<()> {
+//! use chatpack::{ConvertOptions, convert};
+//!
+//! let stats = convert("result.json", "chat.csv", ConvertOptions::default())?;
+//! println!("{} -> {} messages", stats.original_count, stats.merged_count);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+
+use crate::Message;
+use crate::core::filter::{FilterConfig, apply_filters};
+use crate::core::loss::LossReport;
+use crate::core::models::OutputConfig;
+use crate::core::output::OnEmpty;
+use crate::core::processor::{
+    MergeConfig, OrderingReport, ProcessingStats, balance_senders, check_ordering,
+    merge_consecutive_with_config, sample_messages, sort_messages,
+};
+use crate::core::rand::Seed;
+use crate::core::transform::{ContentTransform, TransformChain};
+use crate::error::ChatpackError;
+use crate::format::{OutputFormat, write_to_format};
+use crate::parser::{Parser, Platform, detect_platform, try_create_parser};
+use crate::sink::CollectingSink;
+
+/// Settings for [`convert`].
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::ConvertOptions;
+/// use chatpack::core::FilterConfig;
+/// use chatpack::format::OutputFormat;
+/// use chatpack::parser::Platform;
+///
+/// let options = ConvertOptions::new()
+///     .with_platform(Platform::WhatsApp)
+///     .with_format(OutputFormat::Jsonl)
+///     .with_filter(FilterConfig::new().with_sender("Alice"))
+///     .with_merge(false);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    /// Platform of the input; `None` detects it with
+    /// [`detect_platform`] (default: `None`).
+    pub platform: Option<Platform>,
+    /// Output format; `None` infers it from the output extension
+    /// (default: `None`).
+    pub format: Option<OutputFormat>,
+    /// Messages to keep (default: all).
+    pub filter: FilterConfig,
+    /// Merge consecutive messages from the same sender (default: true).
+    pub merge: bool,
+    /// How merging compares senders (default: ignoring case).
+    pub merge_config: MergeConfig,
+    /// Which fields to write (default: sender and content).
+    pub output_config: OutputConfig,
+    /// Fail with [`ChatpackError::InvalidFilter`] instead of writing an
+    /// empty output when [`FilterConfig::validate`] rejects the filter
+    /// (default: false).
+    pub strict_filters: bool,
+    /// Content rewrites run after filtering and before merging (default:
+    /// none).
+    pub transforms: TransformChain,
+    /// What to do when no messages are left to write (default: write an
+    /// empty output).
+    pub on_empty: OnEmpty,
+    /// Sort each input's messages chronologically before filtering, for
+    /// exports whose [`ordering`](ProcessingStats::ordering) shows them
+    /// out of order (default: false).
+    pub sort: bool,
+    /// Cap each sender at this many of the merged messages, kept at random
+    /// with [`balance_senders`] (default: no cap).
+    pub max_per_sender: Option<usize>,
+    /// Keep a random sample of this many of the merged messages with
+    /// [`sample_messages`], after any per-sender cap (default: keep all).
+    pub sample: Option<usize>,
+    /// Seed of the per-sender cap and the sample, recorded in the returned
+    /// [`seed`](ProcessingStats::seed) when either is set (default:
+    /// [`Seed::DEFAULT`]).
+    pub seed: Seed,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            platform: None,
+            format: None,
+            filter: FilterConfig::default(),
+            merge: true,
+            merge_config: MergeConfig::default(),
+            output_config: OutputConfig::default(),
+            strict_filters: false,
+            transforms: TransformChain::default(),
+            on_empty: OnEmpty::default(),
+            sort: false,
+            max_per_sender: None,
+            sample: None,
+            seed: Seed::DEFAULT,
+        }
+    }
+}
+
+impl ConvertOptions {
+    /// Creates options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the input platform instead of detecting it.
+    #[must_use]
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+
+    /// Sets the output format instead of inferring it.
+    #[must_use]
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Sets the filter.
+    #[must_use]
+    pub fn with_filter(mut self, filter: FilterConfig) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Enables or disables merging consecutive messages.
+    #[must_use]
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Sets how merging compares senders.
+    #[must_use]
+    pub fn with_merge_config(mut self, config: MergeConfig) -> Self {
+        self.merge_config = config;
+        self
+    }
+
+    /// Sets which fields to write.
+    #[must_use]
+    pub fn with_output_config(mut self, config: OutputConfig) -> Self {
+        self.output_config = config;
+        self
+    }
+
+    /// Enables or disables rejecting filters that can match nothing.
+    #[must_use]
+    pub fn with_strict_filters(mut self, strict: bool) -> Self {
+        self.strict_filters = strict;
+        self
+    }
+
+    /// Adds a transform to run after filtering and before merging.
+    #[must_use]
+    pub fn with_transform(mut self, transform: impl ContentTransform + 'static) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Sets the transforms to run after filtering and before merging.
+    #[must_use]
+    pub fn with_transforms(mut self, transforms: TransformChain) -> Self {
+        self.transforms = transforms;
+        self
+    }
+
+    /// Sets what to do when no messages are left to write.
+    #[must_use]
+    pub fn with_on_empty(mut self, on_empty: OnEmpty) -> Self {
+        self.on_empty = on_empty;
+        self
+    }
+
+    /// Enables or disables sorting each input chronologically before
+    /// filtering.
+    #[must_use]
+    pub fn with_sort(mut self, sort: bool) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Caps each sender at `max` messages, kept at random.
+    #[must_use]
+    pub fn with_max_per_sender(mut self, max: usize) -> Self {
+        self.max_per_sender = Some(max);
+        self
+    }
+
+    /// Keeps a random sample of `n` messages.
+    #[must_use]
+    pub fn with_sample(mut self, n: usize) -> Self {
+        self.sample = Some(n);
+        self
+    }
+
+    /// Sets the seed of the per-sender cap and the sample.
+    #[must_use]
+    pub fn with_seed(mut self, seed: Seed) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the seed to record in the stats, if a randomized step runs.
+    fn recorded_seed(&self) -> Option<Seed> {
+        (self.max_per_sender.is_some() || self.sample.is_some()).then_some(self.seed)
+    }
+}
+
+/// Converts the export at `input` into `output`.
+///
+/// Equivalent to parsing with the platform's default parser, then
+/// [`apply_filters`], [`merge_consecutive`](crate::core::processor::merge_consecutive)
+/// if enabled, the per-sender cap and sample if set, and [`write_to_format`].
+/// The returned stats record what the conversion left out in
+/// [`loss`](ProcessingStats::loss), how far the input was out of order in
+/// [`ordering`](ProcessingStats::ordering), and the
+/// [`seed`](ProcessingStats::seed) of the cap and sample.
+///
+/// # Errors
+///
+/// Returns an error if the platform cannot be detected or is not enabled,
+/// the output extension is unknown and no format was given, the filter is
+/// invalid and [`strict_filters`](ConvertOptions::strict_filters) is set,
+/// no messages are left and [`on_empty`](ConvertOptions::on_empty) is
+/// [`OnEmpty::Error`], or parsing or writing fails.
+#[allow(clippy::needless_pass_by_value)]
+pub fn convert(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    options: ConvertOptions,
+) -> Result<ProcessingStats, ChatpackError> {
+    let (input, output) = (input.as_ref(), output.as_ref());
+    if options.strict_filters {
+        options.filter.validate()?;
+    }
+    let platform = match options.platform {
+        Some(platform) => platform,
+        None => detect_platform(input)?,
+    };
+    let format = match options.format {
+        Some(format) => format,
+        None => OutputFormat::from_path(output.to_str().unwrap_or_default())?,
+    };
+
+    run(
+        try_create_parser(platform)?.as_ref(),
+        input,
+        output,
+        format,
+        &options,
+    )
+}
+
+/// Converts several exports into one `output`, in order.
+///
+/// Each input is parsed, filtered, and merged on its own, as [`convert`]
+/// would, and its messages are tagged with their position in `inputs` as
+/// [`source_file`](crate::Message::source_file). Messages from different
+/// inputs are never merged, and
+/// [`OutputConfig::conversation_boundaries`] marks where each input starts.
+/// The platform of each input is detected separately unless
+/// `options.platform` is set.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::convert::{ConvertOptions, convert_many};
+/// use chatpack::core::models::{BoundaryStyle, OutputConfig};
+///
+/// let output_config = OutputConfig::new()
+///     .with_conversation_boundaries(BoundaryStyle::Marker("<|conversation|>".into()));
+/// let options = ConvertOptions::new().with_output_config(output_config);
+/// convert_many(&["alice.json", "bob.txt"], "train.jsonl", options)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns the first error [`convert`] would return for any input.
+///
+/// The [`loss`](ProcessingStats::loss) of the returned stats sums the
+/// losses of every input.
+#[allow(clippy::needless_pass_by_value)]
+pub fn convert_many<P: AsRef<Path>>(
+    inputs: &[P],
+    output: impl AsRef<Path>,
+    options: ConvertOptions,
+) -> Result<ProcessingStats, ChatpackError> {
+    let output = output.as_ref();
+    if options.strict_filters {
+        options.filter.validate()?;
+    }
+    let format = match options.format {
+        Some(format) => format,
+        None => OutputFormat::from_path(output.to_str().unwrap_or_default())?,
+    };
+
+    let (mut all, mut original, mut filtered) = (Vec::new(), 0, 0);
+    let mut loss = LossReport::new();
+    let mut ordering = OrderingReport::default();
+    for (index, input) in inputs.iter().enumerate() {
+        let input = input.as_ref();
+        let platform = match options.platform {
+            Some(platform) => platform,
+            None => detect_platform(input)?,
+        };
+        let (mut messages, mut input_loss) = parse(try_create_parser(platform)?.as_ref(), input)?;
+        ordering.add(&check_ordering(&messages));
+        for msg in &mut messages {
+            msg.source_file = Some(index);
+        }
+        let (messages, parsed, kept) = process(messages, &options, &mut input_loss);
+        all.extend(messages);
+        original += parsed;
+        filtered += kept;
+        loss.add(&input_loss);
+    }
+
+    let all = select(all, &options);
+    write(&all, output, format, &options)?;
+    Ok(stats(&options, original, filtered, all.len())
+        .with_loss(loss)
+        .with_ordering(ordering))
+}
+
+/// Parses, filters, merges, and writes on the calling thread.
+///
+/// `options.platform` and `options.format` are ignored in favor of the
+/// resolved `parser` and `format`.
+pub(crate) fn run(
+    parser: &dyn Parser,
+    input: &Path,
+    output: &Path,
+    format: OutputFormat,
+    options: &ConvertOptions,
+) -> Result<ProcessingStats, ChatpackError> {
+    let (messages, mut loss) = parse(parser, input)?;
+    let ordering = check_ordering(&messages);
+    let (messages, original, filtered) = process(messages, options, &mut loss);
+    let messages = select(messages, options);
+
+    write(&messages, output, format, options)?;
+    Ok(stats(options, original, filtered, messages.len())
+        .with_loss(loss)
+        .with_ordering(ordering))
+}
+
+/// Writes `messages` to `output`, unless there are none and
+/// `options.on_empty` says not to.
+fn write(
+    messages: &[Message],
+    output: &Path,
+    format: OutputFormat,
+    options: &ConvertOptions,
+) -> Result<(), ChatpackError> {
+    if messages.is_empty() && !write_empty(options.on_empty, output)? {
+        return Ok(());
+    }
+    write_to_format(
+        messages,
+        output.to_str().unwrap_or_default(),
+        format,
+        &options.output_config,
+    )
+}
+
+/// Returns `true` if an output with no messages should still be written to
+/// `path`, or the error [`OnEmpty::Error`] asks for.
+pub(crate) fn write_empty(on_empty: OnEmpty, path: &Path) -> Result<bool, ChatpackError> {
+    match on_empty {
+        OnEmpty::SkipFile => Ok(false),
+        OnEmpty::Error => Err(ChatpackError::empty_output(path)),
+        OnEmpty::WriteEmpty => Ok(true),
+    }
+}
+
+/// Parses `input`, counting the system and blank messages the parser left
+/// out.
+fn parse(parser: &dyn Parser, input: &Path) -> Result<(Vec<Message>, LossReport), ChatpackError> {
+    let mut sink = CollectingSink::new();
+    parser.parse_into(input, &mut sink)?;
+    let loss = LossReport {
+        system_messages_skipped: sink.system_messages,
+        blank_messages_skipped: sink.blank_messages,
+        ..LossReport::default()
+    };
+    Ok((sink.messages, loss))
+}
+
+/// Sorts if asked, then filters, transforms, and merges one input's
+/// messages, returning them with the original and filtered counts and
+/// recording what is lost in `loss`.
+fn process(
+    mut messages: Vec<Message>,
+    options: &ConvertOptions,
+    loss: &mut LossReport,
+) -> (Vec<Message>, usize, usize) {
+    let original = messages.len();
+    if options.sort {
+        sort_messages(&mut messages);
+    }
+
+    let mut messages = apply_filters(messages, &options.filter);
+    let filtered = messages.len();
+    loss.messages_filtered += original - filtered;
+    options.transforms.apply_all(&mut messages);
+
+    let messages = if options.merge {
+        merge_consecutive_with_config(messages, &options.merge_config, loss)
+    } else {
+        messages
+    };
+    for msg in &messages {
+        loss.count_unwritten(msg, &options.output_config);
+    }
+    (messages, original, filtered)
+}
+
+/// Applies the per-sender cap and then the sample, if set.
+fn select(messages: Vec<Message>, options: &ConvertOptions) -> Vec<Message> {
+    let messages = match options.max_per_sender {
+        Some(max) => balance_senders(messages, max, options.seed),
+        None => messages,
+    };
+    match options.sample {
+        Some(n) => sample_messages(messages, n, options.seed),
+        None => messages,
+    }
+}
+
+/// Builds the stats for a conversion, recording the seed when a randomized
+/// step ran.
+fn stats(
+    options: &ConvertOptions,
+    original: usize,
+    filtered: usize,
+    written: usize,
+) -> ProcessingStats {
+    let stats = processing_stats(&options.filter, original, filtered, written);
+    match options.recorded_seed() {
+        Some(seed) => stats.with_seed(seed),
+        None => stats,
+    }
+}
+
+/// Builds the stats for a run, recording the filtered count only when the
+/// filter was active.
+pub(crate) fn processing_stats(
+    filter: &FilterConfig,
+    original: usize,
+    filtered: usize,
+    merged: usize,
+) -> ProcessingStats {
+    let stats = ProcessingStats::new(original, merged);
+    if filter.is_active() {
+        stats.with_filtered(filtered)
+    } else {
+        stats
+    }
+}
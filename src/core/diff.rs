@@ -0,0 +1,321 @@
+//! Compare two sets of messages.
+//!
+//! [`diff_messages`] reports which messages were added, removed, or changed
+//! between two runs, for example after tweaking filters or normalization
+//! options. Previous JSONL output can be loaded with
+//! [`read_jsonl`](crate::core::output::read_jsonl).
+//!
+//! # Matching
+//!
+//! Messages are matched by key:
+//!
+//! 1. Messages with an `id` match by `id`. Same `id` with different content
+//!    or metadata is a change.
+//! 2. Messages without an `id` match by `(sender, timestamp, content)`.
+//!    Leftovers on both sides with the same sender and timestamp are then
+//!    paired up in order as changes, so re-normalized content of id-less
+//!    (e.g. WhatsApp) messages shows as changed rather than removed + added.
+//!
+//! Compare outputs written with the same [`OutputConfig`](crate::core::OutputConfig):
+//! a message with an `id` never matches one without.
+//!
+//! # Example
+//!
+//! ```
+//! use chatpack::core::diff::diff_messages;
+//! use chatpack::Message;
+//!
+//! let old = vec![Message::new("Alice", "Hi").with_id(1), Message::new("Bob", "Yo").with_id(2)];
+//! let new = vec![Message::new("Alice", "Hi!").with_id(1), Message::new("Carol", "Hey").with_id(3)];
+//!
+//! let diff = diff_messages(&old, &new);
+//! assert_eq!(diff.added.len(), 1);
+//! assert_eq!(diff.removed.len(), 1);
+//! assert_eq!(diff.changed.len(), 1);
+//! assert_eq!(diff.to_string(), "1 added, 1 removed, 1 changed");
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Write as _};
+
+use chrono::{DateTime, Utc};
+
+use crate::Message;
+
+/// A message present in both runs whose content or metadata differs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageChange {
+    /// The message as it was in the old run.
+    pub old: Message,
+    /// The message as it is in the new run.
+    pub new: Message,
+}
+
+/// Differences between two sets of messages.
+///
+/// Lists keep the order of the input they come from. [`Display`](fmt::Display)
+/// prints a one-line summary; [`listing`](Self::listing) prints every entry.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageDiff {
+    /// Messages only in the new run.
+    pub added: Vec<Message>,
+    /// Messages only in the old run.
+    pub removed: Vec<Message>,
+    /// Messages in both runs that differ.
+    pub changed: Vec<MessageChange>,
+}
+
+impl MessageDiff {
+    /// Returns `true` if the two runs are identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns a line per difference, `diff`-style.
+    ///
+    /// ```text
+    /// - [2024-01-15 10:30:00] Bob: Yo
+    /// + Carol: Hey
+    /// ~ Alice: Hi -> Hi!
+    /// ```
+    pub fn listing(&self) -> String {
+        let mut out = String::new();
+        for msg in &self.removed {
+            let _ = writeln!(out, "- {}", describe(msg));
+        }
+        for msg in &self.added {
+            let _ = writeln!(out, "+ {}", describe(msg));
+        }
+        for change in &self.changed {
+            let _ = writeln!(out, "~ {} -> {}", describe(&change.old), change.new.content);
+        }
+        out
+    }
+}
+
+impl fmt::Display for MessageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
+    }
+}
+
+fn describe(msg: &Message) -> String {
+    match msg.timestamp {
+        Some(ts) => format!(
+            "[{}] {}: {}",
+            ts.format("%Y-%m-%d %H:%M:%S"),
+            msg.sender,
+            msg.content
+        ),
+        None => format!("{}: {}", msg.sender, msg.content),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Key<'a> {
+    Id(u64),
+    Content(&'a str, Option<DateTime<Utc>>, &'a str),
+}
+
+impl<'a> Key<'a> {
+    fn of(msg: &'a Message) -> Self {
+        match msg.id {
+            Some(id) => Key::Id(id),
+            None => Key::Content(&msg.sender, msg.timestamp, &msg.content),
+        }
+    }
+}
+
+/// Compares two sets of messages. See the [module docs](self) for matching
+/// rules.
+pub fn diff_messages(old: &[Message], new: &[Message]) -> MessageDiff {
+    // Duplicate keys are matched one-to-one in order
+    let mut index: HashMap<Key<'_>, VecDeque<usize>> = HashMap::new();
+    for (i, msg) in old.iter().enumerate() {
+        index.entry(Key::of(msg)).or_default().push_back(i);
+    }
+
+    let mut matched = vec![false; old.len()];
+    let mut diff = MessageDiff::default();
+    let mut unmatched_new = Vec::new();
+
+    for msg in new {
+        match index.get_mut(&Key::of(msg)).and_then(VecDeque::pop_front) {
+            Some(i) => {
+                matched[i] = true;
+                if old[i] != *msg {
+                    diff.changed.push(MessageChange {
+                        old: old[i].clone(),
+                        new: msg.clone(),
+                    });
+                }
+            }
+            None => unmatched_new.push(msg),
+        }
+    }
+
+    // Pair id-less leftovers by (sender, timestamp) as content changes
+    let mut leftovers: HashMap<(&str, DateTime<Utc>), VecDeque<usize>> = HashMap::new();
+    for (i, msg) in old.iter().enumerate() {
+        if let (false, None, Some(ts)) = (matched[i], msg.id, msg.timestamp) {
+            leftovers
+                .entry((msg.sender.as_str(), ts))
+                .or_default()
+                .push_back(i);
+        }
+    }
+    for msg in unmatched_new {
+        let paired = match (msg.id, msg.timestamp) {
+            (None, Some(ts)) => leftovers
+                .get_mut(&(msg.sender.as_str(), ts))
+                .and_then(VecDeque::pop_front),
+            _ => None,
+        };
+        match paired {
+            Some(i) => {
+                matched[i] = true;
+                diff.changed.push(MessageChange {
+                    old: old[i].clone(),
+                    new: msg.clone(),
+                });
+            }
+            None => diff.added.push(msg.clone()),
+        }
+    }
+
+    diff.removed = old
+        .iter()
+        .zip(&matched)
+        .filter(|(_, m)| !**m)
+        .map(|(msg, _)| msg.clone())
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 15, 10, minute, 0).unwrap()
+    }
+
+    fn wa(sender: &str, content: &str, minute: u32) -> Message {
+        Message::new(sender, content).with_timestamp(at(minute))
+    }
+
+    // =========================================================================
+    // Id-keyed tests
+    // =========================================================================
+
+    #[test]
+    fn test_identical_runs() {
+        let messages = vec![Message::new("Alice", "Hi").with_id(1), wa("Bob", "Yo", 1)];
+        let diff = diff_messages(&messages, &messages);
+        assert!(diff.is_empty());
+        assert_eq!(diff.listing(), "");
+    }
+
+    #[test]
+    fn test_id_content_change() {
+        let old = vec![Message::new("Alice", "Hello   world").with_id(7)];
+        let new = vec![Message::new("Alice", "Hello world").with_id(7)];
+
+        let diff = diff_messages(&old, &new);
+
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].old.content, "Hello   world");
+        assert_eq!(diff.changed[0].new.content, "Hello world");
+    }
+
+    #[test]
+    fn test_id_added_removed() {
+        let old = vec![
+            Message::new("Alice", "a").with_id(1),
+            Message::new("Bob", "b").with_id(2),
+        ];
+        let new = vec![
+            Message::new("Bob", "b").with_id(2),
+            Message::new("Carol", "c").with_id(3),
+        ];
+
+        let diff = diff_messages(&old, &new);
+
+        assert_eq!(diff.removed[0].id, Some(1));
+        assert_eq!(diff.added[0].id, Some(3));
+        assert!(diff.changed.is_empty());
+    }
+
+    // =========================================================================
+    // Id-less tests
+    // =========================================================================
+
+    #[test]
+    fn test_idless_filter_change() {
+        let old = vec![wa("Alice", "a", 0), wa("Bob", "b", 1), wa("Alice", "c", 2)];
+        let new = vec![wa("Bob", "b", 1), wa("Alice", "c", 2), wa("Carol", "d", 3)];
+
+        let diff = diff_messages(&old, &new);
+
+        assert_eq!(diff.to_string(), "1 added, 1 removed, 0 changed");
+        assert_eq!(diff.removed[0].content, "a");
+        assert_eq!(diff.added[0].content, "d");
+    }
+
+    #[test]
+    fn test_idless_content_change_pairs_by_sender_and_time() {
+        let old = vec![wa("Alice", "caf\u{0065}\u{0301}", 0), wa("Bob", "ok", 1)];
+        let new = vec![wa("Alice", "caf\u{00e9}", 0), wa("Bob", "ok", 1)];
+
+        let diff = diff_messages(&old, &new);
+
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].new.content, "caf\u{00e9}");
+    }
+
+    #[test]
+    fn test_idless_without_timestamps_not_paired() {
+        let old = vec![Message::new("Alice", "old text")];
+        let new = vec![Message::new("Alice", "new text")];
+
+        let diff = diff_messages(&old, &new);
+
+        assert_eq!(diff.to_string(), "1 added, 1 removed, 0 changed");
+    }
+
+    #[test]
+    fn test_idless_duplicates_matched_one_to_one() {
+        let old = vec![wa("Bob", "lol", 5), wa("Bob", "lol", 5)];
+        let new = vec![wa("Bob", "lol", 5)];
+
+        let diff = diff_messages(&old, &new);
+
+        assert_eq!(diff.removed.len(), 1);
+        assert!(diff.added.is_empty() && diff.changed.is_empty());
+    }
+
+    #[test]
+    fn test_listing() {
+        let old = vec![wa("Bob", "Yo", 1), Message::new("Alice", "Hi").with_id(1)];
+        let new = vec![
+            Message::new("Alice", "Hi!").with_id(1),
+            Message::new("Carol", "Hey"),
+        ];
+
+        let listing = diff_messages(&old, &new).listing();
+
+        assert_eq!(
+            listing,
+            "- [2024-01-15 10:01:00] Bob: Yo\n+ Carol: Hey\n~ Alice: Hi -> Hi!\n"
+        );
+    }
+}
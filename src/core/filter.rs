@@ -1,7 +1,8 @@
 //! Filter messages by date range and sender.
 //!
-//! This module provides [`FilterConfig`] for defining filter criteria and
-//! [`apply_filters`] for filtering message collections.
+//! This module provides [`FilterConfig`] for defining filter criteria,
+//! [`apply_filters`] for filtering message collections, and
+//! [`FilterConfig::filter_iter`] for filtering streams lazily.
 //!
 //! # Filter Types
 //!
@@ -9,7 +10,18 @@
 //! |--------|--------|-------------|
 //! | Date from | [`with_date_from`](FilterConfig::with_date_from) | Messages on or after date |
 //! | Date to | [`with_date_to`](FilterConfig::with_date_to) | Messages on or before date |
+//! | Datetime from | [`after_datetime`](FilterConfig::after_datetime) | Messages at or after a moment |
+//! | Datetime to | [`before_datetime`](FilterConfig::before_datetime) | Messages at or before a moment |
+//! | Relative | [`after_relative`](FilterConfig::after_relative) | e.g. `30d`, `4w`, `yesterday` |
+//! | Date field | [`with_date_field`](FilterConfig::with_date_field) | Range applies to sent or edit time |
 //! | Sender | [`with_sender`](FilterConfig::with_sender) | Messages from specific user |
+//! | Link-only | [`with_drop_link_only`](FilterConfig::with_drop_link_only) | Drop messages that are just a URL |
+//! | Forward-only | [`with_drop_forward_only`](FilterConfig::with_drop_forward_only) | Drop forwarded posts |
+//! | Deleted | [`with_drop_deleted`](FilterConfig::with_drop_deleted) | Drop deleted-message markers |
+//!
+//! Configs combine into a [`FilterExpr`] tree with
+//! [`and`](FilterConfig::and), [`or`](FilterConfig::or), and `!`, for
+//! selections a single config cannot express.
 //!
 //! # Examples
 //!
@@ -58,14 +70,26 @@
 //!
 //! # Behavior Notes
 //!
-//! - Messages without timestamps are **excluded** when date filters are active
-//! - Sender matching is case-insensitive for ASCII characters
-//! - Multiple filters are combined with AND logic
+//! - Messages without timestamps are **excluded** when date filters are active;
+//!   with [`DateField::Edited`], so are messages that were never edited
+//! - Both bounds are **inclusive**: a message exactly at the cutoff is kept
+//! - Sender matching ignores case and invisible characters such as
+//!   zero-width spaces and bidi controls (see
+//!   [`senders_match`])
+//! - Multiple filters are combined with AND logic; use [`FilterExpr`] for
+//!   OR and NOT
+//! - A config that can match nothing (inverted range, blank sender) is not
+//!   an error; check for it with [`FilterConfig::validate`]
+
+use std::ops::Not;
 
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Duration, Months, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
 
 use crate::Message;
-use crate::error::ChatpackError;
+use crate::core::links::is_link_only;
+use crate::core::textutil::{fold_sender, senders_match};
+use crate::error::{ChatpackError, FilterConfigError};
 
 /// Configuration for filtering messages by date and sender.
 ///
@@ -93,7 +117,8 @@ use crate::error::ChatpackError;
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FilterConfig {
     /// Include only messages on or after this timestamp.
     pub after: Option<DateTime<Utc>>,
@@ -101,8 +126,31 @@ pub struct FilterConfig {
     /// Include only messages on or before this timestamp.
     pub before: Option<DateTime<Utc>>,
 
-    /// Include only messages from this sender (case-insensitive).
+    /// Which of a message's timestamps `after` and `before` apply to.
+    pub date_field: DateField,
+
+    /// Include only messages from this sender (compared with
+    /// [`senders_match`]).
     pub from: Option<String>,
+
+    /// Drop messages whose content is nothing but a URL, as decided by
+    /// [`is_link_only`]: wrapping `<>`, brackets, trailing punctuation,
+    /// and whitespace are allowed, any other text or emoji is not.
+    pub drop_link_only: bool,
+
+    /// With `drop_link_only`, also drop messages made of several URLs and
+    /// nothing else.
+    pub link_only_allows_multiple: bool,
+
+    /// Drop forwarded messages, those with a
+    /// [`forwarded_from`](Message::forwarded_from). Only the Telegram
+    /// parser sets it; Telegram exports a comment on a forward as a
+    /// separate message, so a forward holds no text of its sender's own.
+    pub drop_forward_only: bool,
+
+    /// Drop markers of deleted messages, those for which
+    /// [`Message::is_deleted`] holds.
+    pub drop_deleted: bool,
 }
 
 impl FilterConfig {
@@ -160,28 +208,143 @@ impl FilterConfig {
     /// # }
     /// ```
     pub fn with_date_to(mut self, date_str: &str) -> Result<Self, ChatpackError> {
-        let naive = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-            .map_err(|_| ChatpackError::invalid_date(date_str))?;
-
-        // End of the day to include the full day
-        let naive_dt = naive.and_hms_opt(23, 59, 59).unwrap();
-        let dt = naive_dt.and_utc();
-        self.before = Some(dt);
+        let date = parse_date(date_str)?;
+        self.before = Some(end_of_day(date));
         Ok(self)
     }
 
+    /// Sets the start filter with time-of-day precision (inclusive).
+    ///
+    /// Accepts `YYYY-MM-DD HH:MM`, `YYYY-MM-DD HH:MM:SS` (a `T` separator
+    /// also works), RFC 3339 with an offset, or a bare `YYYY-MM-DD` which
+    /// expands to the start of that day. Times without an offset are UTC.
+    /// Relative expressions such as `30d` are resolved against the current
+    /// time; see [`after_relative`](Self::after_relative).
+    ///
+    /// A message whose timestamp equals the cutoff **is** included.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::InvalidDate`] if the date part is invalid, or
+    /// [`ChatpackError::InvalidTime`] if the date is valid but the time is not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    ///
+    /// # fn main() -> chatpack::Result<()> {
+    /// let config = FilterConfig::new().after_datetime("2024-01-15 14:30")?;
+    /// assert_eq!(config.after.unwrap().to_rfc3339(), "2024-01-15T14:30:00+00:00");
+    ///
+    /// let config = FilterConfig::new().after_datetime("2024-01-15T14:30:00+02:00")?;
+    /// assert_eq!(config.after.unwrap().to_rfc3339(), "2024-01-15T12:30:00+00:00");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn after_datetime(self, input: &str) -> Result<Self, ChatpackError> {
+        if looks_relative(input) {
+            return self.after_relative(input, Utc::now());
+        }
+        let dt = parse_datetime(input, false)?;
+        Ok(self.with_after(dt))
+    }
+
+    /// Sets the end filter with time-of-day precision (inclusive).
+    ///
+    /// Accepts the same forms as [`after_datetime`](Self::after_datetime).
+    /// A bare `YYYY-MM-DD` expands to `23:59:59` of that day, matching
+    /// [`with_date_to`](Self::with_date_to).
+    ///
+    /// A message whose timestamp equals the cutoff **is** included.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::InvalidDate`] if the date part is invalid, or
+    /// [`ChatpackError::InvalidTime`] if the date is valid but the time is not.
+    pub fn before_datetime(self, input: &str) -> Result<Self, ChatpackError> {
+        if looks_relative(input) {
+            return self.before_relative(input, Utc::now());
+        }
+        let dt = parse_datetime(input, true)?;
+        Ok(self.with_before(dt))
+    }
+
+    /// Sets the start filter from a relative expression.
+    ///
+    /// The expression is resolved against `now`, which makes results
+    /// deterministic in tests; pass [`Utc::now()`] in applications.
+    ///
+    /// | Expression | Meaning |
+    /// |------------|---------|
+    /// | `Nd` | `N` × 24 hours before `now` |
+    /// | `Nw` | `N` × 7 days before `now` |
+    /// | `Nm` | `N` calendar months before `now` (day clamped, e.g. Mar 31 → Feb 29) |
+    /// | `Ny` | `N` calendar years before `now` |
+    /// | `today` | Start of the current UTC day |
+    /// | `yesterday` | Start of the previous UTC day |
+    ///
+    /// Units are case-insensitive.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::InvalidDate`] listing the accepted forms if
+    /// the expression is not recognized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// # fn main() -> chatpack::Result<()> {
+    /// let now = Utc.with_ymd_and_hms(2024, 3, 31, 12, 0, 0).unwrap();
+    ///
+    /// let config = FilterConfig::new().after_relative("30d", now)?;
+    /// assert_eq!(config.after, Some(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()));
+    ///
+    /// let config = FilterConfig::new().after_relative("1m", now)?;
+    /// assert_eq!(config.after, Some(Utc.with_ymd_and_hms(2024, 2, 29, 12, 0, 0).unwrap()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn after_relative(self, expr: &str, now: DateTime<Utc>) -> Result<Self, ChatpackError> {
+        let dt = parse_relative(expr, now, false)?;
+        Ok(self.with_after(dt))
+    }
+
+    /// Sets the end filter from a relative expression.
+    ///
+    /// Accepts the same forms as [`after_relative`](Self::after_relative).
+    /// `today` and `yesterday` resolve to the **end** of that day
+    /// (`23:59:59`), so `before_relative("yesterday")` excludes today.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::InvalidDate`] listing the accepted forms if
+    /// the expression is not recognized.
+    pub fn before_relative(self, expr: &str, now: DateTime<Utc>) -> Result<Self, ChatpackError> {
+        let dt = parse_relative(expr, now, true)?;
+        Ok(self.with_before(dt))
+    }
+
     /// Sets the sender filter.
     ///
-    /// Only messages from this sender will be included.
-    /// Matching is case-insensitive for ASCII characters.
+    /// Only messages from this sender will be included. Matching ignores
+    /// case, including non-ASCII case, and invisible characters such as a
+    /// trailing zero-width space (see [`senders_match`]).
     ///
     /// # Examples
     ///
     /// ```
     /// use chatpack::core::filter::FilterConfig;
+    /// use chatpack::Message;
     ///
     /// // Matches "Alice", "alice", "ALICE"
     /// let config = FilterConfig::new().with_sender("Alice");
+    ///
+    /// let config = FilterConfig::new().with_sender("алия");
+    /// assert!(config.matches(&Message::new("Алия\u{200B}", "Hi")));
     /// ```
     #[must_use]
     pub fn with_sender(mut self, sender: impl Into<String>) -> Self {
@@ -219,6 +382,32 @@ impl FilterConfig {
         self
     }
 
+    /// Sets which of a message's timestamps the date range applies to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::{DateField, FilterConfig};
+    /// use chatpack::Message;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// # fn main() -> chatpack::Result<()> {
+    /// let sent = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    /// let edited = Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+    /// let msg = Message::new("Alice", "Updated").with_timestamp(sent).with_edited(edited);
+    ///
+    /// let config = FilterConfig::new().with_date_from("2024-06-01")?;
+    /// assert!(!config.matches(&msg));
+    /// assert!(config.with_date_field(DateField::Edited).matches(&msg));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_date_field(mut self, field: DateField) -> Self {
+        self.date_field = field;
+        self
+    }
+
     /// Sets the end timestamp directly.
     ///
     /// Use this when you already have a parsed [`DateTime`].
@@ -228,9 +417,65 @@ impl FilterConfig {
         self
     }
 
+    /// Drops messages that are nothing but a URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    /// use chatpack::Message;
+    ///
+    /// let config = FilterConfig::new().with_drop_link_only(true);
+    /// assert!(!config.matches(&Message::new("Alice", "<https://example.com>")));
+    /// assert!(config.matches(&Message::new("Alice", "https://example.com 👋")));
+    /// ```
+    #[must_use]
+    pub fn with_drop_link_only(mut self, drop: bool) -> Self {
+        self.drop_link_only = drop;
+        self
+    }
+
+    /// Sets whether messages made of several URLs count as link-only.
+    #[must_use]
+    pub fn with_link_only_allows_multiple(mut self, allow: bool) -> Self {
+        self.link_only_allows_multiple = allow;
+        self
+    }
+
+    /// Drops forwarded messages.
+    #[must_use]
+    pub fn with_drop_forward_only(mut self, drop: bool) -> Self {
+        self.drop_forward_only = drop;
+        self
+    }
+
+    /// Drops markers of deleted messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    /// use chatpack::message::MESSAGE_DELETED;
+    /// use chatpack::Message;
+    ///
+    /// let config = FilterConfig::new().with_drop_deleted(true);
+    /// assert!(!config.matches(&Message::new("Alice", MESSAGE_DELETED)));
+    /// assert!(config.matches(&Message::new("Alice", "Hi")));
+    /// ```
+    #[must_use]
+    pub fn with_drop_deleted(mut self, drop: bool) -> Self {
+        self.drop_deleted = drop;
+        self
+    }
+
     /// Returns `true` if any filter is active.
     pub fn is_active(&self) -> bool {
-        self.after.is_some() || self.before.is_some() || self.from.is_some()
+        self.after.is_some()
+            || self.before.is_some()
+            || self.from.is_some()
+            || self.drop_link_only
+            || self.drop_forward_only
+            || self.drop_deleted
     }
 
     /// Returns `true` if date filters are active.
@@ -242,18 +487,313 @@ impl FilterConfig {
     pub fn has_user_filter(&self) -> bool {
         self.from.is_some()
     }
+
+    /// Returns the distinct senders in `messages` that the sender filter
+    /// matches only after normalization: names that differ from it in
+    /// invisible characters or non-ASCII case.
+    ///
+    /// Such names look identical to the filter but are not, and would have
+    /// matched nothing under a plain comparison, so they are worth pointing
+    /// out when a filter's result is surprising. Empty without a sender
+    /// filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    /// use chatpack::Message;
+    ///
+    /// let messages = vec![
+    ///     Message::new("Алия\u{200B}", "Привет"),
+    ///     Message::new("Алия\u{200B}", "Как дела?"),
+    ///     Message::new("Bob", "Hi"),
+    /// ];
+    ///
+    /// let config = FilterConfig::new().with_sender("Алия");
+    /// assert_eq!(config.normalized_sender_matches(&messages), ["Алия\u{200B}"]);
+    /// ```
+    pub fn normalized_sender_matches<'a>(&self, messages: &'a [Message]) -> Vec<&'a str> {
+        let Some(from) = &self.from else {
+            return Vec::new();
+        };
+        let mut names: Vec<&str> = Vec::new();
+        for msg in messages {
+            let sender = msg.sender.as_str();
+            if !sender.eq_ignore_ascii_case(from)
+                && senders_match(sender, from)
+                && !names.contains(&sender)
+            {
+                names.push(sender);
+            }
+        }
+        names
+    }
+
+    /// Checks for filters that exclude every message, which is almost always
+    /// a mistake rather than intent.
+    ///
+    /// Reports, in order:
+    ///
+    /// - [`InvertedDateRange`](FilterConfigError::InvertedDateRange): the
+    ///   start bound is later than the end bound
+    /// - [`BlankSender`](FilterConfigError::BlankSender): the sender filter
+    ///   is empty, or only whitespace and invisible characters
+    ///
+    /// Filtering never calls this itself; an invalid config simply matches
+    /// nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first problem found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    /// use chatpack::error::FilterConfigError;
+    ///
+    /// # fn main() -> chatpack::Result<()> {
+    /// let config = FilterConfig::new()
+    ///     .with_date_from("2024-06-01")?
+    ///     .with_date_to("2024-01-01")?;
+    /// assert!(matches!(
+    ///     config.validate(),
+    ///     Err(FilterConfigError::InvertedDateRange { .. })
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate(&self) -> Result<(), FilterConfigError> {
+        if let (Some(after), Some(before)) = (self.after, self.before) {
+            if after > before {
+                return Err(FilterConfigError::InvertedDateRange { after, before });
+            }
+        }
+        if let Some(from) = &self.from {
+            if fold_sender(from).trim().is_empty() {
+                return Err(FilterConfigError::BlankSender(from.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the message passes all active filters.
+    ///
+    /// This is the predicate used by [`apply_filters`] and
+    /// [`filter_iter`](Self::filter_iter).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    /// use chatpack::Message;
+    ///
+    /// let config = FilterConfig::new().with_sender("alice");
+    /// assert!(config.matches(&Message::new("Alice", "Hi")));
+    /// assert!(!config.matches(&Message::new("Bob", "Hi")));
+    /// ```
+    pub fn matches(&self, msg: &Message) -> bool {
+        if self.drop_forward_only && msg.forwarded_from.is_some() {
+            return false;
+        }
+        if self.drop_deleted && msg.is_deleted() {
+            return false;
+        }
+        if self.drop_link_only && is_link_only(&msg.content, self.link_only_allows_multiple) {
+            return false;
+        }
+
+        // Filter by sender (ignoring case and invisible characters)
+        if let Some(ref from) = self.from {
+            if !senders_match(&msg.sender, from) {
+                return false;
+            }
+        }
+
+        // Filter by date (only if message has the selected timestamp)
+        if self.has_date_filter() {
+            match self.date_field.of(msg) {
+                Some(ts) => {
+                    if self.after.is_some_and(|after| ts < after) {
+                        return false;
+                    }
+                    if self.before.is_some_and(|before| ts > before) {
+                        return false;
+                    }
+                }
+                None => {
+                    // No such timestamp - exclude from date-filtered results
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Combines this config with `other`; a message must match both.
+    #[must_use]
+    pub fn and(self, other: impl Into<FilterExpr>) -> FilterExpr {
+        FilterExpr::from(self).and(other)
+    }
+
+    /// Combines this config with `other`; a message must match either.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    /// use chatpack::Message;
+    ///
+    /// let expr = FilterConfig::new().with_sender("alice").or(FilterConfig::new().with_sender("bob"));
+    /// assert!(expr.matches(&Message::new("Bob", "Hi")));
+    /// assert!(!expr.matches(&Message::new("Carol", "Hi")));
+    /// ```
+    #[must_use]
+    pub fn or(self, other: impl Into<FilterExpr>) -> FilterExpr {
+        FilterExpr::from(self).or(other)
+    }
+
+    /// Lazily filters a stream of parse results.
+    ///
+    /// Messages that don't match are dropped; errors are passed through
+    /// so the caller decides how to handle them. Nothing is buffered,
+    /// which makes this suitable for [`Parser::stream`](crate::parser::Parser::stream).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::core::filter::FilterConfig;
+    /// use chatpack::Message;
+    ///
+    /// let stream = vec![
+    ///     Ok(Message::new("Alice", "Hello")),
+    ///     Ok(Message::new("Bob", "Hi")),
+    /// ];
+    ///
+    /// let config = FilterConfig::new().with_sender("Bob");
+    /// let kept: Vec<_> = config
+    ///     .filter_iter(stream)
+    ///     .collect::<chatpack::Result<_>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(kept, vec![Message::new("Bob", "Hi")]);
+    /// ```
+    pub fn filter_iter<I>(&self, iter: I) -> FilterIter<I::IntoIter>
+    where
+        I: IntoIterator<Item = Result<Message, ChatpackError>>,
+    {
+        FilterIter {
+            inner: iter.into_iter(),
+            config: self.clone(),
+        }
+    }
+}
+
+/// Parse a date string in YYYY-MM-DD format.
+fn parse_date(date_str: &str) -> Result<NaiveDate, ChatpackError> {
+    NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+        .map_err(|_| ChatpackError::invalid_date(date_str))
 }
 
 /// Parse a date string in YYYY-MM-DD format to `DateTime`<Utc> at start of day.
 fn parse_date_start(date_str: &str) -> Result<DateTime<Utc>, ChatpackError> {
-    let naive = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-        .map_err(|_| ChatpackError::invalid_date(date_str))?;
+    let naive = parse_date(date_str)?;
 
     // Start of the day
     let naive_dt = naive.and_hms_opt(0, 0, 0).unwrap();
     Ok(naive_dt.and_utc())
 }
 
+/// Last whole second of the given day.
+fn end_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(23, 59, 59).unwrap().and_utc()
+}
+
+/// Parse a date or datetime string; bare dates expand to the start or end of day.
+fn parse_datetime(input: &str, end_of_day_default: bool) -> Result<DateTime<Utc>, ChatpackError> {
+    let input = input.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    let (date_part, time_part) = match input.find([' ', 'T']) {
+        Some(idx) => (&input[..idx], Some(input[idx + 1..].trim())),
+        None => (input, None),
+    };
+
+    let date = NaiveDate::parse_from_str(date_part, "%Y-%m-%d")
+        .map_err(|_| ChatpackError::invalid_date(input))?;
+
+    let Some(time_part) = time_part else {
+        return Ok(if end_of_day_default {
+            end_of_day(date)
+        } else {
+            date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+        });
+    };
+
+    let time = NaiveTime::parse_from_str(time_part, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time_part, "%H:%M"))
+        .map_err(|_| ChatpackError::invalid_time(input))?;
+
+    Ok(date.and_time(time).and_utc())
+}
+
+/// Accepted forms listed in relative-date errors.
+const RELATIVE_FORMS: &str = "YYYY-MM-DD or a relative form: Nd, Nw, Nm, Ny, today, yesterday";
+
+/// Returns `true` if the input is shaped like a relative expression rather
+/// than an absolute date (no separators, e.g. `30d` or `today`).
+fn looks_relative(input: &str) -> bool {
+    let input = input.trim();
+    !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Resolves a relative expression against `now`.
+fn parse_relative(
+    expr: &str,
+    now: DateTime<Utc>,
+    end_of_day_default: bool,
+) -> Result<DateTime<Utc>, ChatpackError> {
+    let invalid = || ChatpackError::InvalidDate {
+        input: expr.to_string(),
+        expected: RELATIVE_FORMS,
+    };
+    let normalized = expr.trim().to_ascii_lowercase();
+
+    let day = match normalized.as_str() {
+        "today" => Some(now.date_naive()),
+        "yesterday" => now.date_naive().pred_opt(),
+        _ => None,
+    };
+    if let Some(day) = day {
+        return Ok(if end_of_day_default {
+            end_of_day(day)
+        } else {
+            day.and_hms_opt(0, 0, 0).unwrap().and_utc()
+        });
+    }
+
+    let split = normalized
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = normalized.split_at(split);
+    let amount: u32 = amount.parse().map_err(|_| invalid())?;
+
+    let resolved = match unit {
+        "d" => now.checked_sub_signed(Duration::days(i64::from(amount))),
+        "w" => now.checked_sub_signed(Duration::weeks(i64::from(amount))),
+        "m" => now.checked_sub_months(Months::new(amount)),
+        "y" => amount
+            .checked_mul(12)
+            .and_then(|months| now.checked_sub_months(Months::new(months))),
+        _ => None,
+    };
+    resolved.ok_or_else(invalid)
+}
+
 /// Filters a collection of messages based on the provided configuration.
 ///
 /// Returns a new vector containing only messages that match all active filters.
@@ -261,7 +801,7 @@ fn parse_date_start(date_str: &str) -> Result<DateTime<Utc>, ChatpackError> {
 ///
 /// # Filter Behavior
 ///
-/// - **Sender filter**: Case-insensitive ASCII matching
+/// - **Sender filter**: Ignores case and invisible characters
 /// - **Date filters**: Messages without timestamps are excluded
 /// - **Multiple filters**: Combined with AND logic
 ///
@@ -288,7 +828,7 @@ fn parse_date_start(date_str: &str) -> Result<DateTime<Utc>, ChatpackError> {
 /// # Performance
 ///
 /// This function consumes the input vector. For streaming use cases,
-/// apply filtering inline during iteration instead.
+/// use [`FilterConfig::filter_iter`] instead.
 pub fn apply_filters(messages: Vec<Message>, config: &FilterConfig) -> Vec<Message> {
     if !config.is_active() {
         return messages;
@@ -296,37 +836,232 @@ pub fn apply_filters(messages: Vec<Message>, config: &FilterConfig) -> Vec<Messa
 
     messages
         .into_iter()
-        .filter(|msg| {
-            // Filter by sender (case-insensitive)
-            if let Some(ref from) = config.from {
-                if !msg.sender.eq_ignore_ascii_case(from) {
-                    return false;
-                }
+        .filter(|msg| config.matches(msg))
+        .collect()
+}
+
+/// Which of a message's timestamps a [`FilterConfig`] date range applies
+/// to.
+///
+/// A message without the selected timestamp is excluded whenever a date
+/// filter is active, as a message without a timestamp always is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateField {
+    /// When the message was sent, [`Message::timestamp`].
+    #[default]
+    Sent,
+    /// When the message was last edited, [`Message::edited`]. Messages
+    /// that were never edited have no such time.
+    Edited,
+    /// The later of the two: the edit time of edited messages and the sent
+    /// time of the rest.
+    EitherNewest,
+}
+
+impl DateField {
+    /// Returns all date field names.
+    pub fn all_names() -> &'static [&'static str] {
+        &["sent", "edited", "newest"]
+    }
+
+    /// Returns the timestamp of `msg` this field selects.
+    ///
+    /// ```
+    /// use chatpack::core::filter::DateField;
+    /// use chatpack::Message;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// let sent = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    /// let msg = Message::new("Alice", "Hi").with_timestamp(sent);
+    ///
+    /// assert_eq!(DateField::Sent.of(&msg), Some(sent));
+    /// assert_eq!(DateField::Edited.of(&msg), None);
+    /// assert_eq!(DateField::EitherNewest.of(&msg), Some(sent));
+    /// ```
+    pub fn of(self, msg: &Message) -> Option<DateTime<Utc>> {
+        match self {
+            DateField::Sent => msg.timestamp,
+            DateField::Edited => msg.edited,
+            DateField::EitherNewest => msg.timestamp.max(msg.edited),
+        }
+    }
+}
+
+impl std::fmt::Display for DateField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DateField::Sent => "sent",
+            DateField::Edited => "edited",
+            DateField::EitherNewest => "newest",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for DateField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sent" => Ok(DateField::Sent),
+            "edited" => Ok(DateField::Edited),
+            "newest" | "either-newest" | "either_newest" => Ok(DateField::EitherNewest),
+            _ => Err(format!(
+                "Unknown date field: '{}'. Expected one of: {}",
+                s,
+                DateField::all_names().join(", ")
+            )),
+        }
+    }
+}
+
+/// A boolean combination of [`FilterConfig`]s.
+///
+/// Built with [`FilterConfig::and`], [`FilterConfig::or`], and `!`, or
+/// deserialized from a config file. An empty `And` matches every message
+/// and an empty `Or` matches none.
+///
+/// `Not` inverts its operand exactly, so `!leaf` keeps messages without a
+/// timestamp when `leaf` has a date filter, since the leaf itself excludes
+/// them.
+///
+/// # Examples
+///
+/// ```
+/// use chatpack::core::filter::{FilterConfig, FilterExpr};
+/// use chatpack::Message;
+/// use chrono::{TimeZone, Utc};
+///
+/// # fn main() -> chatpack::Result<()> {
+/// // Everything from Alice, plus Bob's messages since March
+/// let expr = FilterConfig::new()
+///     .with_sender("Alice")
+///     .or(FilterConfig::new().with_sender("Bob").with_date_from("2024-03-01")?);
+///
+/// let april = Utc.with_ymd_and_hms(2024, 4, 1, 12, 0, 0).unwrap();
+/// assert!(expr.matches(&Message::new("Alice", "Hi")));
+/// assert!(expr.matches(&Message::new("Bob", "Hi").with_timestamp(april)));
+/// assert!(!expr.matches(&Message::new("Bob", "Hi")));
+/// assert!((!expr).matches(&Message::new("Bob", "Hi")));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterExpr {
+    /// Matches what the config matches.
+    Leaf(FilterConfig),
+    /// Matches when every operand matches.
+    And(Vec<FilterExpr>),
+    /// Matches when any operand matches.
+    Or(Vec<FilterExpr>),
+    /// Matches when the operand does not.
+    Not(Box<FilterExpr>),
+}
+
+impl FilterExpr {
+    /// Combines this expression with `other`; a message must match both.
+    ///
+    /// Chained calls extend one `And` instead of nesting.
+    #[must_use]
+    pub fn and(self, other: impl Into<FilterExpr>) -> Self {
+        match self {
+            FilterExpr::And(mut operands) => {
+                operands.push(other.into());
+                FilterExpr::And(operands)
             }
+            expr => FilterExpr::And(vec![expr, other.into()]),
+        }
+    }
 
-            // Filter by date (only if message has timestamp)
-            if config.has_date_filter() {
-                match msg.timestamp {
-                    Some(ts) => {
-                        if config.after.is_some_and(|after| ts < after) {
-                            return false;
-                        }
-                        if config.before.is_some_and(|before| ts > before) {
-                            return false;
-                        }
-                    }
-                    None => {
-                        // No timestamp - exclude from date-filtered results
-                        return false;
-                    }
-                }
+    /// Combines this expression with `other`; a message must match either.
+    ///
+    /// Chained calls extend one `Or` instead of nesting.
+    #[must_use]
+    pub fn or(self, other: impl Into<FilterExpr>) -> Self {
+        match self {
+            FilterExpr::Or(mut operands) => {
+                operands.push(other.into());
+                FilterExpr::Or(operands)
             }
+            expr => FilterExpr::Or(vec![expr, other.into()]),
+        }
+    }
+
+    /// Returns `true` if the message satisfies the expression.
+    pub fn matches(&self, msg: &Message) -> bool {
+        match self {
+            FilterExpr::Leaf(config) => config.matches(msg),
+            FilterExpr::And(operands) => operands.iter().all(|expr| expr.matches(msg)),
+            FilterExpr::Or(operands) => operands.iter().any(|expr| expr.matches(msg)),
+            FilterExpr::Not(expr) => !expr.matches(msg),
+        }
+    }
+}
 
-            true
-        })
+impl From<FilterConfig> for FilterExpr {
+    fn from(config: FilterConfig) -> Self {
+        FilterExpr::Leaf(config)
+    }
+}
+
+impl Not for FilterExpr {
+    type Output = FilterExpr;
+
+    fn not(self) -> Self::Output {
+        match self {
+            FilterExpr::Not(expr) => *expr,
+            expr => FilterExpr::Not(Box::new(expr)),
+        }
+    }
+}
+
+impl Not for FilterConfig {
+    type Output = FilterExpr;
+
+    fn not(self) -> Self::Output {
+        !FilterExpr::from(self)
+    }
+}
+
+/// Filters a collection of messages with a [`FilterExpr`].
+///
+/// [`apply_filters`] is the single-config case, equivalent to passing
+/// `FilterExpr::Leaf(config)`.
+pub fn apply_filter_expr(messages: Vec<Message>, expr: &FilterExpr) -> Vec<Message> {
+    messages
+        .into_iter()
+        .filter(|msg| expr.matches(msg))
         .collect()
 }
 
+/// Lazy filtering adapter returned by [`FilterConfig::filter_iter`].
+///
+/// Yields `Ok` messages that match the filter and passes errors through
+/// unchanged, so a streaming parser can still report failures.
+#[derive(Debug, Clone)]
+pub struct FilterIter<I> {
+    inner: I,
+    config: FilterConfig,
+}
+
+impl<I> Iterator for FilterIter<I>
+where
+    I: Iterator<Item = Result<Message, ChatpackError>>,
+{
+    type Item = Result<Message, ChatpackError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Ok(msg) if !self.config.matches(&msg) => {}
+                item => return Some(item),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -447,4 +1182,548 @@ mod tests {
                 .is_active()
         );
     }
+
+    #[test]
+    fn test_matches() {
+        let config = FilterConfig::new()
+            .after_date("2024-06-01")
+            .unwrap()
+            .with_sender("Alice");
+
+        assert!(config.matches(&make_msg("alice", "Hi", Some("2024-06-15"))));
+        assert!(!config.matches(&make_msg("Bob", "Hi", Some("2024-06-15"))));
+        assert!(!config.matches(&make_msg("Alice", "Hi", Some("2024-01-01"))));
+        assert!(!config.matches(&make_msg("Alice", "Hi", None)));
+        assert!(FilterConfig::new().matches(&make_msg("Bob", "Hi", None)));
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_configs() {
+        let day = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let configs = [
+            FilterConfig::new(),
+            FilterConfig::new().with_sender("Alice"),
+            FilterConfig::new()
+                .after_date("2024-01-01")
+                .unwrap()
+                .before_date("2024-12-31")
+                .unwrap(),
+            FilterConfig::new().with_after(day).with_before(day),
+            FilterConfig::new().after_date("2024-06-01").unwrap(),
+        ];
+        for config in configs {
+            assert_eq!(config.validate(), Ok(()), "{config:?}");
+        }
+    }
+
+    #[test]
+    fn test_validate_inverted_date_range() {
+        let config = FilterConfig::new()
+            .after_date("2024-06-01")
+            .unwrap()
+            .before_date("2024-01-01")
+            .unwrap();
+
+        assert_eq!(
+            config.validate(),
+            Err(FilterConfigError::InvertedDateRange {
+                after: Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap(),
+                before: Utc.with_ymd_and_hms(2024, 1, 1, 23, 59, 59).unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_blank_sender() {
+        for sender in ["", "  \t", "\u{200B} \u{200F}"] {
+            let config = FilterConfig::new().with_user(sender.to_string());
+            assert_eq!(
+                config.validate(),
+                Err(FilterConfigError::BlankSender(sender.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_iter_passes_errors_through() {
+        let stream = vec![
+            Ok(make_msg("Alice", "Keep", None)),
+            Err(ChatpackError::invalid_format("Test", "broken line")),
+            Ok(make_msg("Bob", "Drop", None)),
+            Ok(make_msg("ALICE", "Keep too", None)),
+        ];
+
+        let config = FilterConfig::new().with_sender("alice");
+        let results: Vec<_> = config.filter_iter(stream).collect();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().content, "Keep");
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().content, "Keep too");
+    }
+
+    #[test]
+    fn test_filter_iter_is_lazy() {
+        let mut pulled = 0;
+        let stream = (0..100).map(|i| {
+            pulled += 1;
+            Ok(make_msg(
+                if i % 2 == 0 { "Alice" } else { "Bob" },
+                "x",
+                None,
+            ))
+        });
+
+        let config = FilterConfig::new().with_sender("Bob");
+        let first: Vec<_> = config.filter_iter(stream).take(2).collect();
+
+        assert_eq!(first.len(), 2);
+        assert_eq!(pulled, 4);
+    }
+
+    // =========================================================================
+    // Link-only and forward-only tests
+    // =========================================================================
+
+    fn kept(config: &FilterConfig, contents: &[&str]) -> Vec<String> {
+        let messages = contents.iter().map(|c| Message::new("Alice", *c)).collect();
+        apply_filters(messages, config)
+            .into_iter()
+            .map(|m| m.content)
+            .collect()
+    }
+
+    #[test]
+    fn test_drop_link_only() {
+        let config = FilterConfig::new().with_drop_link_only(true);
+        assert!(config.is_active());
+        let kept = kept(
+            &config,
+            &[
+                "https://example.com",
+                "<https://example.com/page>",
+                "https://example.com/a.",
+                "[](https://example.com)",
+                "[the docs](https://example.com)",
+                "https://example.com 🎉",
+                "https://example.com🎉",
+                "https://a.example https://b.example",
+                "see https://example.com",
+            ],
+        );
+        assert_eq!(
+            kept,
+            [
+                "[the docs](https://example.com)",
+                "https://example.com 🎉",
+                "https://example.com🎉",
+                "https://a.example https://b.example",
+                "see https://example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drop_link_only_multiple() {
+        let config = FilterConfig::new()
+            .with_drop_link_only(true)
+            .with_link_only_allows_multiple(true);
+        let kept = kept(
+            &config,
+            &[
+                "https://a.example\nhttps://b.example",
+                "https://a.example or https://b.example",
+            ],
+        );
+        assert_eq!(kept, ["https://a.example or https://b.example"]);
+    }
+
+    #[test]
+    fn test_drop_forward_only() {
+        let config = FilterConfig::new().with_drop_forward_only(true);
+        let messages = vec![
+            Message::new("Alice", "Look at this").with_id(1),
+            Message::new("Alice", "Channel post")
+                .with_id(2)
+                .with_forwarded_from("News"),
+        ];
+        let filtered = apply_filters(messages, &config);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, Some(1));
+    }
+
+    #[test]
+    fn test_drop_deleted() {
+        let messages = vec![
+            Message::new("Alice", "Hello").with_id(1),
+            Message::new("Alice", crate::message::MESSAGE_DELETED).with_id(2),
+        ];
+        let config = FilterConfig::new().with_drop_deleted(true);
+        assert!(config.is_active());
+        let filtered = apply_filters(messages.clone(), &config);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, Some(1));
+        assert_eq!(apply_filters(messages, &FilterConfig::new()).len(), 2);
+    }
+
+    // =========================================================================
+    // Datetime precision tests
+    // =========================================================================
+
+    fn at(h: u32, m: u32, sec: u32) -> Message {
+        Message::new("Alice", "x")
+            .with_timestamp(Utc.with_ymd_and_hms(2024, 1, 15, h, m, sec).unwrap())
+    }
+
+    #[test]
+    fn test_after_datetime_forms() {
+        let expected = Utc.with_ymd_and_hms(2024, 1, 15, 14, 30, 0).unwrap();
+        for input in [
+            "2024-01-15 14:30",
+            "2024-01-15 14:30:00",
+            "2024-01-15T14:30",
+            "2024-01-15T14:30:00Z",
+            "2024-01-15T16:30:00+02:00",
+        ] {
+            let config = FilterConfig::new().after_datetime(input).unwrap();
+            assert_eq!(config.after, Some(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_datetime_bare_date_expands_to_day_bounds() {
+        let config = FilterConfig::new()
+            .after_datetime("2024-01-15")
+            .unwrap()
+            .before_datetime("2024-01-15")
+            .unwrap();
+        assert_eq!(
+            config.after,
+            FilterConfig::new()
+                .with_date_from("2024-01-15")
+                .unwrap()
+                .after
+        );
+        assert_eq!(
+            config.before,
+            FilterConfig::new()
+                .with_date_to("2024-01-15")
+                .unwrap()
+                .before
+        );
+    }
+
+    #[test]
+    fn test_after_datetime_boundary_inclusive() {
+        let config = FilterConfig::new()
+            .after_datetime("2024-01-15 14:30:00")
+            .unwrap();
+        assert!(config.matches(&at(14, 30, 0)));
+        assert!(!config.matches(&at(14, 29, 59)));
+    }
+
+    #[test]
+    fn test_before_datetime_boundary_inclusive() {
+        let config = FilterConfig::new()
+            .before_datetime("2024-01-15 14:30")
+            .unwrap();
+        assert!(config.matches(&at(14, 30, 0)));
+        assert!(!config.matches(&at(14, 30, 1)));
+    }
+
+    #[test]
+    fn test_datetime_invalid_date_vs_time() {
+        let err = FilterConfig::new()
+            .after_datetime("2024-13-45 10:00")
+            .unwrap_err();
+        assert!(matches!(err, ChatpackError::InvalidDate { .. }));
+
+        let err = FilterConfig::new()
+            .after_datetime("2024-01-15 25:61")
+            .unwrap_err();
+        assert!(matches!(err, ChatpackError::InvalidTime { .. }));
+        assert!(err.to_string().contains("Invalid time"));
+
+        let err = FilterConfig::new()
+            .before_datetime("2024-01-15 noon")
+            .unwrap_err();
+        assert!(err.is_invalid_time());
+    }
+
+    // =========================================================================
+    // Relative date tests
+    // =========================================================================
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 31, 15, 45, 0).unwrap()
+    }
+
+    #[test]
+    fn test_relative_days_and_weeks() {
+        let config = FilterConfig::new().after_relative("7d", now()).unwrap();
+        assert_eq!(
+            config.after,
+            Some(Utc.with_ymd_and_hms(2024, 3, 24, 15, 45, 0).unwrap())
+        );
+
+        let config = FilterConfig::new().after_relative("2W", now()).unwrap();
+        assert_eq!(
+            config.after,
+            Some(Utc.with_ymd_and_hms(2024, 3, 17, 15, 45, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_relative_months_are_calendar_months() {
+        // 2024-03-31 minus one month clamps to the end of February (leap year)
+        let config = FilterConfig::new().after_relative("1m", now()).unwrap();
+        assert_eq!(
+            config.after,
+            Some(Utc.with_ymd_and_hms(2024, 2, 29, 15, 45, 0).unwrap())
+        );
+
+        // Not the same as 30 days
+        let days = FilterConfig::new().after_relative("30d", now()).unwrap();
+        assert_ne!(config.after, days.after);
+
+        let config = FilterConfig::new().after_relative("1y", now()).unwrap();
+        assert_eq!(
+            config.after,
+            Some(Utc.with_ymd_and_hms(2023, 3, 31, 15, 45, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_relative_today_yesterday() {
+        let config = FilterConfig::new()
+            .after_relative("yesterday", now())
+            .unwrap()
+            .before_relative("yesterday", now())
+            .unwrap();
+        assert_eq!(
+            config.after,
+            Some(Utc.with_ymd_and_hms(2024, 3, 30, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            config.before,
+            Some(Utc.with_ymd_and_hms(2024, 3, 30, 23, 59, 59).unwrap())
+        );
+
+        let config = FilterConfig::new().after_relative("Today", now()).unwrap();
+        assert_eq!(
+            config.after,
+            Some(Utc.with_ymd_and_hms(2024, 3, 31, 0, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_relative_invalid_lists_forms() {
+        for expr in ["30x", "d", "lastweek", "-3d", "99999999999d"] {
+            let err = FilterConfig::new().after_relative(expr, now()).unwrap_err();
+            assert!(
+                matches!(err, ChatpackError::InvalidDate { .. }),
+                "expr: {expr}"
+            );
+            assert!(err.to_string().contains("Nd, Nw, Nm, Ny"), "expr: {expr}");
+        }
+    }
+
+    #[test]
+    fn test_after_datetime_accepts_relative() {
+        let config = FilterConfig::new().after_datetime("30d").unwrap();
+        let after = config.after.unwrap();
+        assert!(after < Utc::now());
+        assert!(after > Utc::now() - Duration::days(31));
+
+        let err = FilterConfig::new().after_datetime("30x").unwrap_err();
+        assert!(err.to_string().contains("today, yesterday"));
+    }
+
+    fn on_day(sender: &str, day: u32) -> Message {
+        Message::new(sender, "x")
+            .with_timestamp(Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn test_filter_expr_truth_table() {
+        // (from Alice AND before Mar 10) OR (from Bob AND NOT after Mar 20)
+        let alice_early = FilterConfig::new()
+            .with_sender("Alice")
+            .with_date_to("2024-03-10")
+            .unwrap();
+        let bob = FilterConfig::new().with_sender("Bob");
+        let late = FilterConfig::new().with_date_from("2024-03-20").unwrap();
+        let expr = FilterExpr::from(alice_early).or(bob.and(!late));
+
+        let cases = [
+            (on_day("Alice", 5), true),
+            (on_day("Alice", 15), false),
+            (on_day("Bob", 5), true),
+            (on_day("Bob", 25), false),
+            (on_day("Carol", 5), false),
+            (Message::new("Alice", "x"), false),
+            (Message::new("Bob", "x"), true),
+        ];
+        for (msg, expected) in &cases {
+            assert_eq!(expr.matches(msg), *expected, "{msg:?}");
+            assert_eq!((!expr.clone()).matches(msg), !*expected, "{msg:?}");
+        }
+
+        let messages: Vec<Message> = cases.into_iter().map(|(msg, _)| msg).collect();
+        assert_eq!(apply_filter_expr(messages, &expr).len(), 3);
+    }
+
+    #[test]
+    fn test_filter_expr_not_without_timestamp() {
+        let dated = FilterConfig::new().with_date_from("2024-03-01").unwrap();
+        let undated = Message::new("Alice", "x");
+
+        assert!(!dated.matches(&undated));
+        assert!((!dated.clone()).matches(&undated));
+        assert!(!(!!dated).matches(&undated));
+    }
+
+    #[test]
+    fn test_filter_expr_combinators() {
+        let a = FilterConfig::new().with_sender("a");
+        let b = FilterConfig::new().with_sender("b");
+        let c = FilterConfig::new().with_sender("c");
+
+        let chained = a.clone().or(b.clone()).or(c.clone());
+        assert_eq!(
+            chained,
+            FilterExpr::Or(vec![a.clone().into(), b.clone().into(), c.into()])
+        );
+        assert!(matches!(a.clone().and(b.clone()).or(a), FilterExpr::Or(ops) if ops.len() == 2));
+        assert!(FilterExpr::And(vec![]).matches(&Message::new("x", "y")));
+        assert!(!FilterExpr::Or(vec![]).matches(&Message::new("x", "y")));
+        assert_eq!(!!FilterExpr::from(b.clone()), FilterExpr::Leaf(b));
+    }
+
+    #[test]
+    fn test_filter_expr_serde_round_trip() {
+        let expr = FilterConfig::new()
+            .with_sender("Alice")
+            .or(!FilterConfig::new().with_date_from("2024-03-01").unwrap());
+        let json = serde_json::to_string(&expr).unwrap();
+        assert_eq!(serde_json::from_str::<FilterExpr>(&json).unwrap(), expr);
+
+        let parsed: FilterExpr =
+            serde_json::from_str(r#"{"or": [{"leaf": {"from": "Alice"}}, {"not": {"leaf": {}}}]}"#)
+                .unwrap();
+        assert!(parsed.matches(&Message::new("alice", "x")));
+        assert!(!parsed.matches(&Message::new("Bob", "x")));
+    }
+
+    // =========================================================================
+    // Sender normalization tests
+    // =========================================================================
+
+    #[test]
+    fn test_filter_by_sender_normalized() {
+        let messages = vec![
+            make_msg("Алия\u{200B}", "Trailing ZWSP", None),
+            make_msg("АЛИЯ", "Upper case", None),
+            make_msg("\u{202B}алия\u{202C}", "Bidi embedded", None),
+            make_msg("Алина", "Other", None),
+        ];
+
+        let config = FilterConfig::new().with_sender("Алия");
+        let filtered = apply_filters(messages.clone(), &config);
+        assert_eq!(filtered.len(), 3);
+        assert!(filtered.iter().all(|m| m.content != "Other"));
+        // The stored sender is untouched
+        assert_eq!(filtered[0].sender, "Алия\u{200B}");
+
+        assert_eq!(
+            config.normalized_sender_matches(&messages),
+            ["Алия\u{200B}", "АЛИЯ", "\u{202B}алия\u{202C}"]
+        );
+    }
+
+    #[test]
+    fn test_filter_by_sender_with_emoji() {
+        let messages = vec![
+            make_msg("Mom ❤\u{FE0F}\u{200B}", "Hi", None),
+            make_msg("Dad 👨\u{200D}💻", "Hey", None),
+            make_msg("Dad 👨💻", "Split", None),
+        ];
+
+        let mom = FilterConfig::new().with_sender("MOM ❤\u{FE0F}");
+        assert_eq!(apply_filters(messages.clone(), &mom).len(), 1);
+
+        // A joiner inside an emoji sequence is part of the name
+        let dad = FilterConfig::new().with_sender("dad 👨\u{200D}💻");
+        let filtered = apply_filters(messages.clone(), &dad);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].content, "Hey");
+        assert_eq!(dad.normalized_sender_matches(&messages), Vec::<&str>::new());
+    }
+
+    // =========================================================================
+    // Date field tests
+    // =========================================================================
+
+    fn edited_msg(content: &str, sent: &str, edited: Option<&str>) -> Message {
+        let mut msg = make_msg("Alice", content, Some(sent));
+        if let Some(edited) = edited {
+            msg.edited = make_msg("Alice", "", Some(edited)).timestamp;
+        }
+        msg
+    }
+
+    #[test]
+    fn test_date_field_selects_timestamp() {
+        let messages = vec![
+            edited_msg("Old, edited late", "2024-01-01", Some("2024-06-15")),
+            edited_msg("Old, never edited", "2024-01-01", None),
+            edited_msg("New, never edited", "2024-06-15", None),
+        ];
+        let config = FilterConfig::new().with_date_from("2024-06-01").unwrap();
+        let contents = |field| -> Vec<String> {
+            let config = config.clone().with_date_field(field);
+            apply_filters(messages.clone(), &config)
+                .into_iter()
+                .map(|m| m.content)
+                .collect()
+        };
+
+        assert_eq!(contents(DateField::Sent), ["New, never edited"]);
+        assert_eq!(contents(DateField::Edited), ["Old, edited late"]);
+        assert_eq!(
+            contents(DateField::EitherNewest),
+            ["Old, edited late", "New, never edited"]
+        );
+    }
+
+    #[test]
+    fn test_date_field_ignored_without_date_filter() {
+        let msg = edited_msg("Never edited", "2024-01-01", None);
+        let config = FilterConfig::new().with_date_field(DateField::Edited);
+        assert!(config.matches(&msg));
+    }
+
+    #[test]
+    fn test_date_field_from_str() {
+        assert_eq!("sent".parse::<DateField>(), Ok(DateField::Sent));
+        assert_eq!("EDITED".parse::<DateField>(), Ok(DateField::Edited));
+        for name in ["newest", "either-newest", "either_newest"] {
+            assert_eq!(name.parse::<DateField>(), Ok(DateField::EitherNewest));
+        }
+        let err = "created".parse::<DateField>().unwrap_err();
+        assert!(err.contains("sent, edited, newest"));
+
+        for name in DateField::all_names() {
+            let field: DateField = name.parse().unwrap();
+            assert_eq!(field.to_string(), *name);
+        }
+    }
+
+    #[test]
+    fn test_date_field_serde() {
+        assert_eq!(FilterConfig::default().date_field, DateField::Sent);
+        let config: FilterConfig =
+            serde_json::from_str(r#"{"date_field": "either_newest"}"#).unwrap();
+        assert_eq!(config.date_field, DateField::EitherNewest);
+    }
 }
@@ -0,0 +1,189 @@
+//! Stable content hashes of messages.
+//!
+//! [`Message::content_hash`](crate::Message::content_hash) and
+//! [`Message::content_hash_hex`](crate::Message::content_hash_hex) identify a
+//! message by what was said, by whom, and when, so the same message hashes
+//! the same across runs, platforms, and chatpack versions. Use them to
+//! deduplicate across runs or to match messages for incremental syncs.
+//!
+//! # Algorithm
+//!
+//! The hash is SipHash-2-4 over a canonical encoding of the message, keyed
+//! with [`KEY`]. [`content_hash`](crate::Message::content_hash) is the
+//! 64-bit output; [`content_hash_hex`](crate::Message::content_hash_hex) is
+//! the 128-bit output as 32 lowercase hex digits, its 16 bytes in the order
+//! the SipHash reference implementation writes them.
+//!
+//! The encoding is three fields, in this order, each written as its length
+//! in bytes (a little-endian `u64`) followed by its bytes:
+//!
+//! 1. the sender, as UTF-8;
+//! 2. the timestamp as decimal Unix seconds (`1705314600`), or nothing if
+//!    the message has none;
+//! 3. the content, as UTF-8.
+//!
+//! Ids, reply references, edit times, the platform, and attachments are
+//! left out: they differ between exports of the same conversation. Text is
+//! hashed exactly as parsed, with no normalization.
+//!
+//! Changing any of this changes every hash, so it only happens with a new
+//! [`SCHEMA_VERSION`](crate::core::models::SCHEMA_VERSION).
+//!
+//! # Example
+//!
+//! ```
+//! use chatpack::Message;
+//!
+//! let a = Message::new("Alice", "Hello").with_id(1);
+//! let b = Message::new("Alice", "Hello").with_id(2);
+//! assert_eq!(a.content_hash(), b.content_hash());
+//! assert_eq!(a.content_hash_hex().len(), 32);
+//! ```
+
+use std::fmt::Write;
+
+use crate::Message;
+
+/// SipHash key of the content hash: the ASCII bytes of `chatpack` and
+/// `content1`, each read as a little-endian `u64`.
+pub const KEY: (u64, u64) = (
+    u64::from_le_bytes(*b"chatpack"),
+    u64::from_le_bytes(*b"content1"),
+);
+
+impl Message {
+    /// Returns the 64-bit content hash of this message.
+    ///
+    /// See the [`hash`](crate::core::hash) module for what is hashed and
+    /// how.
+    pub fn content_hash(&self) -> u64 {
+        let [out, _] = sip_hash(KEY, &canonical_bytes(self), false);
+        out
+    }
+
+    /// Returns the 128-bit content hash of this message as 32 lowercase hex
+    /// digits.
+    ///
+    /// See the [`hash`](crate::core::hash) module for what is hashed and
+    /// how.
+    pub fn content_hash_hex(&self) -> String {
+        sip_hash(KEY, &canonical_bytes(self), true)
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .fold(String::with_capacity(32), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            })
+    }
+}
+
+/// Encodes the hashed fields of `msg` as described in the module docs.
+fn canonical_bytes(msg: &Message) -> Vec<u8> {
+    let timestamp = msg
+        .timestamp
+        .map(|ts| ts.timestamp().to_string())
+        .unwrap_or_default();
+    let fields = [
+        msg.sender.as_bytes(),
+        timestamp.as_bytes(),
+        msg.content.as_bytes(),
+    ];
+    let mut bytes = Vec::with_capacity(fields.iter().map(|f| f.len() + 8).sum());
+    for field in fields {
+        bytes.extend_from_slice(&(field.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(field);
+    }
+    bytes
+}
+
+/// SipHash-2-4 of `data`. Returns the 64-bit output in the first word, or
+/// the two words of the 128-bit output when `wide` is set.
+pub(crate) fn sip_hash(key: (u64, u64), data: &[u8], wide: bool) -> [u64; 2] {
+    let mut v = [
+        key.0 ^ 0x736f_6d65_7073_6575,
+        key.1 ^ 0x646f_7261_6e64_6f6d,
+        key.0 ^ 0x6c79_6765_6e65_7261,
+        key.1 ^ 0x7465_6462_7974_6573,
+    ];
+    if wide {
+        v[1] ^= 0xee;
+    }
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let m = u64::from_le_bytes(chunk.try_into().expect("chunk of 8 bytes"));
+        v[3] ^= m;
+        sip_rounds(&mut v, 2);
+        v[0] ^= m;
+    }
+    let mut last = [0u8; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    // Only the low byte of the length is mixed in, per the specification
+    last[7] = data.len().to_le_bytes()[0];
+    let m = u64::from_le_bytes(last);
+    v[3] ^= m;
+    sip_rounds(&mut v, 2);
+    v[0] ^= m;
+
+    v[2] ^= if wide { 0xee } else { 0xff };
+    sip_rounds(&mut v, 4);
+    let first = v[0] ^ v[1] ^ v[2] ^ v[3];
+    if !wide {
+        return [first, 0];
+    }
+    v[1] ^= 0xdd;
+    sip_rounds(&mut v, 4);
+    [first, v[0] ^ v[1] ^ v[2] ^ v[3]]
+}
+
+fn sip_rounds(v: &mut [u64; 4], rounds: usize) {
+    for _ in 0..rounds {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Key `00 01 … 0f` of the SipHash reference test vectors.
+    const TEST_KEY: (u64, u64) = (0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+
+    #[test]
+    fn test_sip_hash_reference_vectors() {
+        let data: Vec<u8> = (0..15).collect();
+        assert_eq!(sip_hash(TEST_KEY, &[], false)[0], 0x726f_db47_dd0e_0e31);
+        assert_eq!(sip_hash(TEST_KEY, &data, false)[0], 0xa129_ca61_49be_45e5);
+        assert_eq!(
+            sip_hash(TEST_KEY, &[], true),
+            [0xe6a8_25ba_047f_81a3, 0x9302_55c7_1472_f66d]
+        );
+    }
+
+    #[test]
+    fn test_canonical_bytes() {
+        let msg = Message::new("Al", "Hi")
+            .with_timestamp(chrono::DateTime::from_timestamp(5, 0).unwrap());
+        let mut expected = vec![2, 0, 0, 0, 0, 0, 0, 0, b'A', b'l'];
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0, b'5']);
+        expected.extend([2, 0, 0, 0, 0, 0, 0, 0, b'H', b'i']);
+        assert_eq!(canonical_bytes(&msg), expected);
+    }
+
+    #[test]
+    fn test_fields_are_not_ambiguous() {
+        assert_ne!(
+            Message::new("ab", "c").content_hash(),
+            Message::new("a", "bc").content_hash()
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Finding URLs in message text.
+//!
+//! The one definition of "a URL" in chatpack, shared by the link-only
+//! filter ([`FilterConfig::drop_link_only`](crate::core::filter::FilterConfig::drop_link_only))
+//! and URL redaction ([`Redact::with_urls`](crate::core::transform::Redact::with_urls)).
+//!
+//! A URL starts with `http://` or `https://` (in any case) or `www.`, at
+//! the start of the text or after a character that is not a letter or
+//! digit. It runs over letters, digits, and ASCII punctuation other than
+//! `<`, `>`, `"`, and `` ` ``, so whitespace and emoji end it. Trailing
+//! `.`, `,`, `;`, `:`, `!`, `?`, `'`, and `*`, and closing brackets with no
+//! matching opening bracket inside the URL, are left out, so
+//! `(see https://example.com/a_(b)).` ends where a reader expects.
+//!
+//! # Example
+//!
+//! ```
+//! use chatpack::core::links::{find_urls, is_link_only};
+//!
+//! let text = "Docs: <https://example.com/guide>, mirror at www.example.org.";
+//! let urls: Vec<&str> = find_urls(text).map(|r| &text[r]).collect();
+//! assert_eq!(urls, ["https://example.com/guide", "www.example.org"]);
+//!
+//! assert!(is_link_only("<https://example.com>!", false));
+//! assert!(!is_link_only("https://example.com 👋", false));
+//! ```
+
+use std::ops::Range;
+
+/// Prefixes a URL starts with, compared ignoring ASCII case.
+const PREFIXES: [&str; 3] = ["https://", "http://", "www."];
+
+/// Characters dropped from the end of a URL.
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '*'];
+
+/// Characters allowed around the links of a link-only message.
+const WRAPPING: &[char] = &[
+    '<', '>', '(', ')', '[', ']', '.', ',', ';', ':', '!', '?', '\'', '"',
+];
+
+/// Returns the byte ranges of the URLs in `text`, in order.
+pub fn find_urls(text: &str) -> Urls<'_> {
+    Urls { text, pos: 0 }
+}
+
+/// Returns `true` if `text` is nothing but a URL, optionally wrapped in
+/// `<>`, brackets, or quotes and followed by punctuation and whitespace.
+///
+/// With `allow_multiple`, text made of several URLs and nothing else
+/// counts too. A markdown link with a label, such as
+/// `[docs](https://example.com)`, does not: the label is text.
+pub fn is_link_only(text: &str, allow_multiple: bool) -> bool {
+    let mut count = 0;
+    let mut end = 0;
+    for url in find_urls(text) {
+        if !is_wrapping(&text[end..url.start]) {
+            return false;
+        }
+        count += 1;
+        end = url.end;
+    }
+    count > 0 && (count == 1 || allow_multiple) && is_wrapping(&text[end..])
+}
+
+fn is_wrapping(gap: &str) -> bool {
+    gap.chars()
+        .all(|c| c.is_whitespace() || WRAPPING.contains(&c))
+}
+
+/// Iterator over the URLs of a text, returned by [`find_urls`].
+#[derive(Debug, Clone)]
+pub struct Urls<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Iterator for Urls<'_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let text = self.text;
+        let mut prev = text[..self.pos].chars().next_back();
+        for (offset, c) in text[self.pos..].char_indices() {
+            let start = self.pos + offset;
+            let at_boundary = !prev.is_some_and(char::is_alphanumeric);
+            prev = Some(c);
+            if !at_boundary {
+                continue;
+            }
+            let Some(prefix) = PREFIXES
+                .iter()
+                .find(|p| starts_with_ignore_case(&text[start..], p))
+            else {
+                continue;
+            };
+            let end = url_end(text, start);
+            if end > start + prefix.len() {
+                self.pos = end;
+                return Some(start..end);
+            }
+        }
+        self.pos = text.len();
+        None
+    }
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+}
+
+/// Returns where the URL starting at `start` ends.
+fn url_end(text: &str, start: usize) -> usize {
+    let rest = &text[start..];
+    let len = rest
+        .find(|c: char| {
+            !(c.is_alphanumeric() || c.is_ascii_punctuation()) || matches!(c, '<' | '>' | '"' | '`')
+        })
+        .unwrap_or(rest.len());
+    let mut url = &rest[..len];
+    loop {
+        let trimmed = url.trim_end_matches(TRAILING);
+        let trimmed = [('(', ')'), ('[', ']'), ('{', '}')]
+            .iter()
+            .find(|&&(open, close)| {
+                trimmed.ends_with(close)
+                    && trimmed.matches(close).count() > trimmed.matches(open).count()
+            })
+            .map_or(trimmed, |&(_, close)| {
+                &trimmed[..trimmed.len() - close.len_utf8()]
+            });
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+    start + url.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(text: &str) -> Vec<&str> {
+        find_urls(text).map(|r| &text[r]).collect()
+    }
+
+    #[test]
+    fn test_find_urls() {
+        assert_eq!(
+            urls("see https://example.com/a?b=1"),
+            ["https://example.com/a?b=1"]
+        );
+        assert_eq!(urls("HTTP://EXAMPLE.COM"), ["HTTP://EXAMPLE.COM"]);
+        assert_eq!(urls("www.example.com and more"), ["www.example.com"]);
+        assert_eq!(
+            urls("no links here, just https:// alone"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(urls("shttps://example.com"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_find_urls_trims_wrapping() {
+        assert_eq!(urls("<https://example.com>"), ["https://example.com"]);
+        assert_eq!(urls("(https://example.com)."), ["https://example.com"]);
+        assert_eq!(
+            urls("https://en.wikipedia.org/wiki/Rust_(language)!"),
+            ["https://en.wikipedia.org/wiki/Rust_(language)"]
+        );
+        assert_eq!(urls("\"https://example.com\""), ["https://example.com"]);
+        assert_eq!(urls("https://example.com👋"), ["https://example.com"]);
+    }
+
+    #[test]
+    fn test_is_link_only() {
+        assert!(is_link_only("https://example.com", false));
+        assert!(is_link_only("  <https://example.com>  ", false));
+        assert!(is_link_only("https://example.com!!\n", false));
+        assert!(is_link_only("[](https://example.com)", false));
+        assert!(!is_link_only("[docs](https://example.com)", false));
+        assert!(!is_link_only("https://example.com 👋", false));
+        assert!(!is_link_only("look: https://example.com", false));
+        assert!(!is_link_only("", false));
+        assert!(!is_link_only("!?", true));
+    }
+
+    #[test]
+    fn test_is_link_only_multiple() {
+        let text = "https://a.example https://b.example";
+        assert!(!is_link_only(text, false));
+        assert!(is_link_only(text, true));
+        assert!(!is_link_only(
+            "https://a.example and https://b.example",
+            true
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+//! Records of information a conversion leaves out.
+//!
+//! Parsing, filtering, merging, and writing each drop something by design:
+//! service messages, messages that fail the filter, the ids of merged
+//! messages, fields the [`OutputConfig`] does not write. A [`LossReport`]
+//! counts all of it, so a conversion can be audited afterwards.
+//! [`convert`](crate::convert()) and the [`pipeline`](crate::pipeline)
+//! runners return one in [`ProcessingStats::loss`](crate::core::ProcessingStats::loss).
+//!
+//! # Example
+//!
+//! ```
+//! use chatpack::Message;
+//! use chatpack::core::loss::LossReport;
+//! use chatpack::core::models::OutputConfig;
+//! use chatpack::core::processor::merge_consecutive_with_loss;
+//!
+//! let messages = vec![
+//!     Message::new("Alice", "Hi").with_id(1),
+//!     Message::new("Alice", "Anyone?").with_id(2),
+//! ];
+//!
+//! let mut loss = LossReport::new();
+//! let merged = merge_consecutive_with_loss(messages, &mut loss);
+//! for msg in &merged {
+//!     loss.count_unwritten(msg, &OutputConfig::new());
+//! }
+//!
+//! assert_eq!(loss.messages_merged, 1);
+//! assert_eq!(loss.ids_discarded, 1);
+//! assert_eq!(loss.fields_dropped["id"], 1);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+use crate::core::models::OutputConfig;
+
+/// What a conversion left out, stage by stage.
+///
+/// Serializes to JSON with the field names below, for machine-readable
+/// reports; [`Display`](fmt::Display) lists the non-zero counts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LossReport {
+    /// System and service messages the parser left out, such as joins,
+    /// pins, and encryption notices.
+    ///
+    /// Counted from [`SkipReason::SystemMessage`](crate::sink::SkipReason::SystemMessage)
+    /// reports, which `parse_into` of the Telegram and WhatsApp parsers and
+    /// of Discord JSONL exports sends, streaming or not.
+    pub system_messages_skipped: usize,
+
+    /// Messages with no text the parser left out.
+    ///
+    /// Counted from [`SkipReason::BlankMessage`](crate::sink::SkipReason::BlankMessage)
+    /// reports, which the WhatsApp parser sends outside streaming mode.
+    pub blank_messages_skipped: usize,
+
+    /// Messages removed by the filter.
+    pub messages_filtered: usize,
+
+    /// Messages merged into the message before them.
+    pub messages_merged: usize,
+
+    /// Ids of merged messages, lost because a merged message keeps only
+    /// the id of its first part.
+    pub ids_discarded: usize,
+
+    /// Attachment refs moved onto an earlier message by merging.
+    pub attachments_flattened: usize,
+
+    /// `original_sender` and `forwarded_from` values of merged messages,
+    /// lost because they differ from those of the message merged into,
+    /// which keeps its own.
+    pub metadata_discarded: usize,
+
+    /// Written messages that had a value for a field the output config
+    /// leaves out, by field name (`timestamp`, `id`, `reply_to`, `edited`,
+    /// `original_sender`, `forwarded_from`, `topic`, `source`, `platform`,
+    /// `raw`, `attachments`).
+    pub fields_dropped: BTreeMap<String, usize>,
+}
+
+impl LossReport {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if nothing was lost.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Counts the fields of `msg` that a writer using `config` does not
+    /// write. Call it once for every message written.
+    ///
+    /// Attachment refs are never written with the messages; the attachment
+    /// manifest writer (`write_attachment_manifest`) lists them instead.
+    pub fn count_unwritten(&mut self, msg: &Message, config: &OutputConfig) {
+        let fields = [
+            (
+                "timestamp",
+                msg.timestamp.is_some(),
+                config.include_timestamps,
+            ),
+            ("id", msg.id.is_some(), config.include_ids),
+            ("reply_to", msg.reply_to.is_some(), config.include_replies),
+            ("edited", msg.edited.is_some(), config.include_edited),
+            (
+                "original_sender",
+                msg.original_sender.is_some(),
+                config.include_metadata,
+            ),
+            (
+                "forwarded_from",
+                msg.forwarded_from.is_some(),
+                config.include_metadata,
+            ),
+            ("topic", msg.topic.is_some(), config.include_metadata),
+            ("source", msg.source.is_some(), config.include_metadata),
+            ("raw", msg.raw.is_some(), config.include_raw),
+            ("attachments", !msg.attachments.is_empty(), false),
+            ("platform", msg.platform.is_some(), config.include_platform),
+        ];
+        for (name, present, written) in fields {
+            self.count_field(name, present, written);
+        }
+    }
+
+    fn count_field(&mut self, name: &str, present: bool, written: bool) {
+        if present && !written {
+            *self.fields_dropped.entry(name.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Counts `msg` being merged into `last`, the message before it.
+    pub(crate) fn count_merged(&mut self, last: &Message, msg: &Message) {
+        let discarded = |kept: &Option<String>, value: &Option<String>| {
+            usize::from(value.is_some() && value != kept)
+        };
+        self.messages_merged += 1;
+        self.ids_discarded += usize::from(msg.id.is_some());
+        self.attachments_flattened += msg.attachments.len();
+        self.metadata_discarded += discarded(&last.original_sender, &msg.original_sender)
+            + discarded(&last.forwarded_from, &msg.forwarded_from);
+    }
+
+    /// Returns each non-zero count with what it counts, such as
+    /// `(3, "system messages skipped")`, in [`Display`](fmt::Display)
+    /// order.
+    pub(crate) fn entries(&self) -> Vec<(usize, String)> {
+        let mut entries: Vec<(usize, String)> = self
+            .stage_counts()
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, what)| (count, what.to_string()))
+            .collect();
+        entries.extend(
+            self.fields_dropped
+                .iter()
+                .map(|(field, count)| (*count, format!("{field} values not written"))),
+        );
+        entries
+    }
+
+    fn stage_counts(&self) -> [(usize, &'static str); 7] {
+        [
+            (self.system_messages_skipped, "system messages skipped"),
+            (self.blank_messages_skipped, "blank messages skipped"),
+            (self.messages_filtered, "messages removed by the filter"),
+            (self.messages_merged, "messages merged into the one before"),
+            (self.ids_discarded, "message ids discarded by merging"),
+            (
+                self.attachments_flattened,
+                "attachment refs moved by merging",
+            ),
+            (
+                self.metadata_discarded,
+                "original sender and forward values discarded by merging",
+            ),
+        ]
+    }
+
+    /// Adds the counts of `other`, such as the report for another input.
+    pub fn add(&mut self, other: &LossReport) {
+        self.system_messages_skipped += other.system_messages_skipped;
+        self.blank_messages_skipped += other.blank_messages_skipped;
+        self.messages_filtered += other.messages_filtered;
+        self.messages_merged += other.messages_merged;
+        self.ids_discarded += other.ids_discarded;
+        self.attachments_flattened += other.attachments_flattened;
+        self.metadata_discarded += other.metadata_discarded;
+        for (field, count) in &other.fields_dropped {
+            *self.fields_dropped.entry(field.clone()).or_default() += count;
+        }
+    }
+}
+
+impl fmt::Display for LossReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no information lost");
+        }
+        let mut lines: Vec<String> = self
+            .stage_counts()
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, what)| format!("{count} {what}"))
+            .collect();
+        lines.extend(
+            self.fields_dropped
+                .iter()
+                .map(|(field, count)| format!("{field} not written for {count} messages")),
+        );
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AttachmentRef;
+    use crate::platform::Platform;
+    use chrono::Utc;
+
+    #[test]
+    fn test_count_unwritten() {
+        let msg = Message::new("Alice", "Hi")
+            .with_id(1)
+            .with_reply_to(0)
+            .with_timestamp(Utc::now())
+            .with_topic("General")
+            .with_attachment(AttachmentRef::new("a.png"));
+        let mut loss = LossReport::new();
+        loss.count_unwritten(&msg, &OutputConfig::new().with_timestamps());
+        loss.count_unwritten(
+            &msg.clone().with_platform(Platform::Telegram),
+            &OutputConfig::all(),
+        );
+
+        let expected: BTreeMap<String, usize> = [
+            ("attachments".to_string(), 2),
+            ("id".to_string(), 1),
+            ("platform".to_string(), 1),
+            ("reply_to".to_string(), 1),
+            ("topic".to_string(), 2),
+        ]
+        .into();
+        assert_eq!(loss.fields_dropped, expected);
+    }
+
+    #[test]
+    fn test_count_merged_and_add() {
+        let first = Message::new("them", "w").with_original_sender("Bob");
+        let mut loss = LossReport::new();
+        loss.count_merged(&first, &Message::new("A", "x").with_id(2));
+        loss.count_merged(
+            &first,
+            &Message::new("A", "y").with_attachment(AttachmentRef::new("b.jpg")),
+        );
+        loss.count_merged(&first, &first.clone());
+        loss.count_merged(&first, &first.clone().with_original_sender("Carol"));
+        assert_eq!(loss.messages_merged, 4);
+        assert_eq!(loss.ids_discarded, 1);
+        assert_eq!(loss.attachments_flattened, 1);
+        assert_eq!(loss.metadata_discarded, 1);
+
+        let mut total = LossReport {
+            system_messages_skipped: 1,
+            ..LossReport::default()
+        };
+        total.add(&loss);
+        total.add(&loss);
+        assert_eq!(total.messages_merged, 8);
+        assert_eq!(total.metadata_discarded, 2);
+        assert_eq!(total.system_messages_skipped, 1);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(LossReport::new().to_string(), "no information lost");
+        assert!(LossReport::new().is_empty());
+
+        let mut loss = LossReport {
+            system_messages_skipped: 2,
+            ids_discarded: 1,
+            ..LossReport::default()
+        };
+        loss.fields_dropped.insert("edited".into(), 3);
+        assert_eq!(
+            loss.to_string(),
+            "2 system messages skipped\n1 message ids discarded by merging\nedited not written for 3 messages"
+        );
+    }
+}
@@ -2,8 +2,16 @@
 //!
 //! This module contains:
 //! - [`models`] - Data structures for messages and configuration
+//! - [`diff`] - Comparing the messages of two runs
 //! - [`filter`] - Message filtering by date and sender
+//! - [`hash`] - Stable content hashes of messages
+//! - [`links`] - Finding URLs in message text
 //! - [`processor`] - Message merging and statistics
+//! - [`transform`] - Pluggable content rewrites
+//! - [`rand`] - Seeded randomness for reproducible sampling
+//! - [`stats`] - Per-sender content statistics and activity over time
+//! - [`textutil`] - Grapheme-safe text measuring and truncation
+//! - [`units`] - Parsing durations and sizes like `"1h30m"` and `"50MB"`
 //! - [`output`] - Format writers (CSV, JSON, JSONL)
 //!
 //! # Quick Start
@@ -19,14 +27,27 @@
 //! # }
 //! ```
 
+pub mod diff;
 pub mod filter;
+pub mod hash;
+pub mod links;
+pub mod loss;
 pub mod models;
 pub mod output;
 pub mod processor;
+pub mod rand;
+pub mod stats;
+pub mod textutil;
+pub mod transform;
+pub mod units;
 
 // Re-export main types for convenience
-pub use filter::{FilterConfig, apply_filters};
-pub use models::OutputConfig;
+pub use diff::{MessageChange, MessageDiff, diff_messages};
+pub use filter::{
+    DateField, FilterConfig, FilterExpr, FilterIter, apply_filter_expr, apply_filters,
+};
+pub use loss::LossReport;
+pub use models::{BoundaryStyle, LineEnding, OutputConfig, SCHEMA_VERSION, SortKey};
 
 // Re-export Message from the crate root
 pub use crate::Message;
@@ -37,4 +58,18 @@ pub use output::{to_csv, write_csv};
 #[cfg(feature = "json-output")]
 pub use output::{to_json, to_jsonl, write_json, write_jsonl};
 
-pub use processor::{ProcessingStats, merge_consecutive};
+pub use processor::{
+    ContextWindow, ContextWindows, MergeConfig, NearDupConfig, OrderingCheck, OrderingReport,
+    ProcessingStats, RelabelConfig, SenderMap, SenderMapReport, SenderMatch, anonymize_senders,
+    apply_transforms, balance_senders, check_ordering, map_senders, merge_consecutive,
+    merge_consecutive_with_config, merge_consecutive_with_loss, near_dedupe, partition_by_topic,
+    relabel_perspective, sample_messages, sort_messages, suggest_file_names, suggest_title,
+    with_context,
+};
+pub use rand::Seed;
+pub use stats::{
+    Bucket, BucketCount, ChatStats, ResponseStat, SenderStats, aggregate_counts, find_id_gaps,
+    response_table, response_times,
+};
+pub use transform::{ContentTransform, Redact, TransformChain};
+pub use units::{parse_duration, parse_size};
@@ -14,6 +14,30 @@
 //! | [`with_ids`](OutputConfig::with_ids) | `id` | Platform-specific message ID |
 //! | [`with_replies`](OutputConfig::with_replies) | `reply_to` | Parent message reference |
 //! | [`with_edited`](OutputConfig::with_edited) | `edited` | Last edit timestamp |
+//! | [`with_platform`](OutputConfig::with_platform) | `platform` | Platform the message was parsed from |
+//! | [`with_raw`](OutputConfig::with_raw) | `raw` | Source record, for debugging |
+//! | [`with_hash`](OutputConfig::with_hash) | `hash` | Stable [content hash](crate::core::hash) |
+//!
+//! [`with_line_ending`](OutputConfig::with_line_ending) and
+//! [`with_bom`](OutputConfig::with_bom) make CSV output open cleanly in
+//! Excel on Windows, which expects CRLF line endings and detects UTF-8 by
+//! its byte order mark.
+//!
+//! When several exports are written to one file,
+//! [`with_conversation_boundaries`](OutputConfig::with_conversation_boundaries)
+//! marks where each one starts (see [`BoundaryStyle`]).
+//!
+//! # Schema Versions
+//!
+//! [`with_schema_version`](OutputConfig::with_schema_version) adds a `"_v"`
+//! key holding [`SCHEMA_VERSION`] to every JSON and JSONL record. Within a
+//! schema version, keys are only ever added: a key is never renamed,
+//! removed, or given a different type without bumping the version.
+//! Consumers should ignore keys they do not know, as
+//! [`from_jsonl`](crate::core::output::from_jsonl) does. The same guarantee
+//! covers the serde representation of [`Message`](crate::Message).
+//! `tests/schema/keys.txt` records the keys of each version, and a test
+//! fails when the writers stop matching it.
 //!
 //! # Examples
 //!
@@ -35,6 +59,13 @@
 
 use serde::{Deserialize, Serialize};
 
+/// Version of the record schema written by the JSON and JSONL writers.
+///
+/// Written as `"_v"` when [`OutputConfig::include_schema_version`] is set.
+/// See the [module docs](self#schema-versions) for what a version
+/// guarantees.
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Controls which message fields are included in output.
 ///
 /// Used by [`write_csv`](crate::core::output::write_csv),
@@ -73,6 +104,7 @@ use serde::{Deserialize, Serialize};
 /// # fn main() {}
 /// ```
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OutputConfig {
     /// Include message timestamps in output.
     ///
@@ -87,10 +119,166 @@ pub struct OutputConfig {
     /// Useful for reconstructing conversation threads.
     pub include_replies: bool,
 
+    /// Include each message's [`source_index`](crate::Message::source_index):
+    /// an `Index` column in CSV, an `index` field in JSON and JSONL.
+    ///
+    /// WhatsApp and Discord TXT exports have no message ids, so their
+    /// [`reply_to`](crate::Message::reply_to) holds the quoted message's
+    /// index; write both to follow those replies in the output. Not part of
+    /// [`all`](Self::all), which keeps its output unchanged.
+    pub include_index: bool,
+
     /// Include edit timestamps in output.
     ///
     /// Shows when messages were last modified.
     pub include_edited: bool,
+
+    /// Include each message's
+    /// [`original_sender`](crate::Message::original_sender),
+    /// [`forwarded_from`](crate::Message::forwarded_from),
+    /// [`topic`](crate::Message::topic), and
+    /// [`source`](crate::Message::source): `OriginalSender`,
+    /// `ForwardedFrom`, `Topic`, and `Source` columns in CSV, fields of the
+    /// same names in JSON and JSONL. Messages without a value get an empty
+    /// cell or no field.
+    ///
+    /// Not part of [`all`](Self::all), which keeps its output unchanged.
+    pub include_metadata: bool,
+
+    /// Include the platform each message was parsed from (see
+    /// [`Message::platform`](crate::Message::platform)), as its lowercase
+    /// name: a `Platform` column in CSV, a `platform` field in JSON and
+    /// JSONL. Messages without a platform get an empty cell or no field.
+    ///
+    /// Not part of [`all`](Self::all), which keeps its output unchanged.
+    pub include_platform: bool,
+
+    /// Include each message's source record, when the parser captured it
+    /// (see [`Message::raw`](crate::Message::raw)).
+    ///
+    /// For debugging; not part of [`all`](Self::all).
+    pub include_raw: bool,
+
+    /// Add a `"_v"` key holding [`SCHEMA_VERSION`] to every JSON and JSONL
+    /// record, so consumers can tell which schema a file was written with.
+    ///
+    /// Ignored by CSV; not part of [`all`](Self::all).
+    pub include_schema_version: bool,
+
+    /// Include each message's 128-bit
+    /// [content hash](crate::Message::content_hash_hex) as 32 hex digits: a
+    /// `Hash` column in CSV, a `hash` field in JSON and JSONL.
+    ///
+    /// Not part of [`all`](Self::all), which keeps its output unchanged.
+    pub include_hash: bool,
+
+    /// How to mark the start of each input's messages in multi-file output.
+    ///
+    /// Applies to messages with a [`source_file`](crate::Message::source_file),
+    /// so single-file output is unchanged. `None` writes no boundaries.
+    pub conversation_boundaries: Option<BoundaryStyle>,
+
+    /// Order in which messages are written (default: as given).
+    ///
+    /// Sorting writers buffer the messages first. For slice input they sort
+    /// references, so the messages are neither mutated nor cloned.
+    pub sort_by: SortKey,
+
+    /// Line ending after each CSV record, the last one included (default:
+    /// LF).
+    ///
+    /// Line breaks inside a quoted cell are written as they are. Ignored by
+    /// JSON and JSONL.
+    pub line_ending: LineEnding,
+
+    /// Start CSV output with a UTF-8 byte order mark, which Excel needs to
+    /// read it as UTF-8 rather than the system code page.
+    ///
+    /// Ignored by JSON and JSONL; not part of [`all`](Self::all).
+    pub write_bom: bool,
+}
+
+/// How conversation boundaries are written.
+///
+/// A conversation starts at each message whose
+/// [`source_file`](crate::Message::source_file) differs from the previous
+/// message's.
+///
+/// # Examples
+///
+/// ```
+/// use chatpack::core::models::{BoundaryStyle, OutputConfig};
+///
+/// let config = OutputConfig::new()
+///     .with_conversation_boundaries(BoundaryStyle::Marker("<|conversation|>".into()));
+/// assert!(config.conversation_boundaries.is_some());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryStyle {
+    /// Write a record with an empty sender and this text as its content
+    /// before the first message of each conversation.
+    Marker(String),
+    /// Add `conversation_start: true` to the first message of each
+    /// conversation (a `ConversationStart` column in CSV).
+    MetaField,
+}
+
+/// Order in which writers emit messages.
+///
+/// Sorts are stable and break timestamp ties by
+/// [`source_index`](crate::Message::source_index), as
+/// [`Message::cmp_chronological`](crate::Message::cmp_chronological) does.
+///
+/// # Examples
+///
+/// ```
+/// use chatpack::core::models::{OutputConfig, SortKey};
+///
+/// let config = OutputConfig::new().with_sort_by(SortKey::SenderThenTime);
+/// assert_eq!(config.sort_by, SortKey::SenderThenTime);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// The order the messages are passed in, which is chronological for
+    /// parser output.
+    #[default]
+    Chronological,
+    /// Grouped by sender, oldest first within each sender.
+    SenderThenTime,
+    /// Grouped by sender, newest first within each sender.
+    SenderThenTimeDescending,
+}
+
+/// Line ending written after each CSV record.
+///
+/// # Examples
+///
+/// ```
+/// use chatpack::core::models::{LineEnding, OutputConfig};
+///
+/// let config = OutputConfig::new().with_line_ending(LineEnding::CrLf);
+/// assert_eq!(config.line_ending.as_str(), "\r\n");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    /// `\n`, as on Unix.
+    #[default]
+    Lf,
+    /// `\r\n`, as on Windows.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Returns the characters of this line ending.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
 }
 
 impl OutputConfig {
@@ -119,7 +307,17 @@ impl OutputConfig {
             include_timestamps: true,
             include_ids: true,
             include_replies: true,
+            include_index: false,
             include_edited: true,
+            include_metadata: false,
+            include_platform: false,
+            include_raw: false,
+            include_schema_version: false,
+            include_hash: false,
+            conversation_boundaries: None,
+            sort_by: SortKey::Chronological,
+            line_ending: LineEnding::Lf,
+            write_bom: false,
         }
     }
 
@@ -151,6 +349,77 @@ impl OutputConfig {
         self
     }
 
+    /// Enable original sender, forward, topic, and source label inclusion
+    /// in output.
+    #[must_use]
+    pub fn with_metadata(mut self) -> Self {
+        self.include_metadata = true;
+        self
+    }
+
+    /// Enable platform inclusion in output.
+    #[must_use]
+    pub fn with_platform(mut self) -> Self {
+        self.include_platform = true;
+        self
+    }
+
+    /// Enable source index inclusion in output.
+    #[must_use]
+    pub fn with_index(mut self) -> Self {
+        self.include_index = true;
+        self
+    }
+
+    /// Enable source record inclusion in output.
+    #[must_use]
+    pub fn with_raw(mut self) -> Self {
+        self.include_raw = true;
+        self
+    }
+
+    /// Enable the `"_v"` schema version key in JSON and JSONL output.
+    #[must_use]
+    pub fn with_schema_version(mut self) -> Self {
+        self.include_schema_version = true;
+        self
+    }
+
+    /// Enable content hash inclusion in output.
+    #[must_use]
+    pub fn with_hash(mut self) -> Self {
+        self.include_hash = true;
+        self
+    }
+
+    /// Marks where each input's messages start in multi-file output.
+    #[must_use]
+    pub fn with_conversation_boundaries(mut self, style: BoundaryStyle) -> Self {
+        self.conversation_boundaries = Some(style);
+        self
+    }
+
+    /// Sets the order in which messages are written.
+    #[must_use]
+    pub fn with_sort_by(mut self, key: SortKey) -> Self {
+        self.sort_by = key;
+        self
+    }
+
+    /// Sets the line ending after each CSV record.
+    #[must_use]
+    pub fn with_line_ending(mut self, ending: LineEnding) -> Self {
+        self.line_ending = ending;
+        self
+    }
+
+    /// Enable the UTF-8 byte order mark at the start of CSV output.
+    #[must_use]
+    pub fn with_bom(mut self) -> Self {
+        self.write_bom = true;
+        self
+    }
+
     /// Returns `true` if any metadata option is enabled.
     pub fn has_any(&self) -> bool {
         self.include_timestamps || self.include_ids || self.include_replies || self.include_edited
@@ -178,6 +447,7 @@ mod tests {
         assert!(config.include_ids);
         assert!(config.include_replies);
         assert!(config.include_edited);
+        assert!(!config.include_metadata);
     }
 
     #[test]
@@ -0,0 +1,177 @@
+//! Activity table writer.
+//!
+//! Writes the [`BucketCount`]s from
+//! [`aggregate_counts`](crate::core::stats::aggregate_counts) as one CSV row
+//! per time bucket, ready for plotting without post-processing.
+
+use std::collections::HashMap;
+
+use crate::core::stats::BucketCount;
+use crate::error::ChatpackError;
+
+/// Writes bucketed message counts to a CSV file.
+///
+/// Same format as [`to_aggregate_csv`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "csv-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::write_aggregate_csv;
+/// use chatpack::core::stats::{Bucket, aggregate_counts};
+/// use chatpack::prelude::*;
+///
+/// let messages = chatpack::parser::create_parser(Platform::Telegram)
+///     .parse("result.json".as_ref())?;
+/// write_aggregate_csv(&aggregate_counts(&messages, Bucket::Day), "activity.csv", 5)?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "csv-output"))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be written.
+pub fn write_aggregate_csv(
+    buckets: &[BucketCount],
+    output_path: &str,
+    top_senders: usize,
+) -> Result<(), ChatpackError> {
+    std::fs::write(output_path, to_aggregate_csv(buckets, top_senders)?)?;
+    Ok(())
+}
+
+/// Converts bucketed message counts to a CSV string.
+///
+/// Writes a semicolon-delimited table with one row per bucket and the
+/// columns `Start` (RFC 3339, UTC), `Count`, one column for each of the
+/// `top_senders` senders with the most messages overall (ties broken by
+/// name), and `Others` for everyone else.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "csv-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::Message;
+/// use chatpack::core::output::to_aggregate_csv;
+/// use chatpack::core::stats::{Bucket, aggregate_counts};
+/// use chrono::{TimeZone, Utc};
+///
+/// let on = |day| Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+/// let messages = vec![
+///     Message::new("Alice", "Hi").with_timestamp(on(1)),
+///     Message::new("Bob", "Hey").with_timestamp(on(1)),
+///     Message::new("Alice", "Back").with_timestamp(on(3)),
+/// ];
+///
+/// let csv = to_aggregate_csv(&aggregate_counts(&messages, Bucket::Day), 1)?;
+/// assert_eq!(
+///     csv,
+///     "Start;Count;Alice;Others\n\
+///      2024-01-01T00:00:00Z;2;1;1\n\
+///      2024-01-02T00:00:00Z;0;0;0\n\
+///      2024-01-03T00:00:00Z;1;1;0\n"
+/// );
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "csv-output"))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Csv`] if serialization fails.
+pub fn to_aggregate_csv(
+    buckets: &[BucketCount],
+    top_senders: usize,
+) -> Result<String, ChatpackError> {
+    let senders = top(buckets, top_senders);
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .from_writer(Vec::new());
+
+    let mut header = vec!["Start", "Count"];
+    header.extend(&senders);
+    header.push("Others");
+    writer.write_record(&header)?;
+
+    for bucket in buckets {
+        let listed: Vec<usize> = senders
+            .iter()
+            .map(|sender| bucket.per_sender.get(*sender).copied().unwrap_or(0))
+            .collect();
+        let others = bucket.count - listed.iter().sum::<usize>();
+
+        let mut row = vec![
+            bucket.start.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            bucket.count.to_string(),
+        ];
+        row.extend(listed.iter().map(usize::to_string));
+        row.push(others.to_string());
+        writer.write_record(&row)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Returns the `n` senders with the most messages across `buckets`.
+fn top(buckets: &[BucketCount], n: usize) -> Vec<&str> {
+    let mut totals: HashMap<&str, usize> = HashMap::new();
+    for bucket in buckets {
+        for (sender, count) in &bucket.per_sender {
+            *totals.entry(sender.as_str()).or_default() += count;
+        }
+    }
+    let mut senders: Vec<(&str, usize)> = totals.into_iter().collect();
+    senders.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    senders
+        .into_iter()
+        .take(n)
+        .map(|(sender, _)| sender)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Message;
+    use crate::core::stats::{Bucket, aggregate_counts};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_top_senders_and_others() {
+        let at = |hour| Utc.with_ymd_and_hms(2024, 5, 1, hour, 30, 0).unwrap();
+        let messages = vec![
+            Message::new("Carol", "1").with_timestamp(at(9)),
+            Message::new("Bob", "2").with_timestamp(at(9)),
+            Message::new("Alice", "3").with_timestamp(at(9)),
+            Message::new("Bob", "4").with_timestamp(at(11)),
+            Message::new("Alice", "5").with_timestamp(at(11)),
+            Message::new("Dave", "6").with_timestamp(at(11)),
+        ];
+        let buckets = aggregate_counts(&messages, Bucket::Hour);
+
+        // Alice and Bob tie on 2 messages; Carol and Dave on 1
+        let csv = to_aggregate_csv(&buckets, 3).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "Start;Count;Alice;Bob;Carol;Others",
+                "2024-05-01T09:00:00Z;3;1;1;1;0",
+                "2024-05-01T10:00:00Z;0;0;0;0;0",
+                "2024-05-01T11:00:00Z;3;1;1;0;1",
+            ]
+        );
+
+        let csv = to_aggregate_csv(&buckets, 0).unwrap();
+        assert!(csv.starts_with("Start;Count;Others\n2024-05-01T09:00:00Z;3;3\n"));
+        assert_eq!(to_aggregate_csv(&[], 3).unwrap(), "Start;Count;Others\n");
+    }
+}
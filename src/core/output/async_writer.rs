@@ -0,0 +1,328 @@
+//! Async counterparts of the file writers.
+//!
+//! Each function writes the same bytes as its sync namesake, but opens and
+//! writes the file through `tokio::fs`, so it never blocks the runtime.
+//! Records are still serialized in memory; only I/O is async.
+//!
+//! [`write_jsonl_stream_async`] takes a [`Stream`] of messages, such as one
+//! from an async parser, and writes each line as its message arrives.
+
+use std::path::Path;
+
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+#[cfg(feature = "json-output")]
+use tokio::io::BufWriter;
+#[cfg(feature = "json-output")]
+use tokio_stream::{Stream, StreamExt};
+
+#[cfg(feature = "json-output")]
+use super::boundary::Boundaries;
+#[cfg(feature = "json-output")]
+use super::record::OutputRecord;
+use crate::Message;
+use crate::core::models::OutputConfig;
+#[cfg(feature = "json-output")]
+use crate::core::models::SortKey;
+use crate::error::ChatpackError;
+
+/// Writes messages to a CSV file asynchronously.
+///
+/// Async version of [`write_csv`](super::write_csv); the file content is
+/// identical.
+///
+/// # Examples
+///
+/// ```no_run
+/// use chatpack::core::output::write_csv_async;
+/// use chatpack::prelude::*;
+///
+/// # async fn example() -> chatpack::Result<()> {
+/// let messages = vec![Message::new("Alice", "Hello!")];
+/// write_csv_async(&messages, "output.csv", &OutputConfig::new()).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+#[cfg(feature = "csv-output")]
+pub async fn write_csv_async(
+    messages: &[Message],
+    output_path: impl AsRef<Path>,
+    config: &OutputConfig,
+) -> Result<(), ChatpackError> {
+    let csv = super::to_csv(messages, config)?;
+    write_all(output_path.as_ref(), csv.as_bytes()).await
+}
+
+/// Writes messages to a JSON file asynchronously.
+///
+/// Async version of [`write_json`](super::write_json); the file content is
+/// identical.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+#[cfg(feature = "json-output")]
+pub async fn write_json_async(
+    messages: &[Message],
+    output_path: impl AsRef<Path>,
+    config: &OutputConfig,
+) -> Result<(), ChatpackError> {
+    let json = super::to_json(messages, config)?;
+    write_all(output_path.as_ref(), json.as_bytes()).await
+}
+
+/// Writes messages to a JSONL file asynchronously.
+///
+/// Async version of [`write_jsonl`](super::write_jsonl); the file content
+/// is identical.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+#[cfg(feature = "json-output")]
+pub async fn write_jsonl_async(
+    messages: &[Message],
+    output_path: impl AsRef<Path>,
+    config: &OutputConfig,
+) -> Result<(), ChatpackError> {
+    let mut writer = BufWriter::new(File::create(output_path).await?);
+    for record in OutputRecord::records(messages, config) {
+        write_line(&mut writer, &record).await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Writes messages to a JSONL file as they arrive from `messages`.
+///
+/// The async counterpart of
+/// [`write_jsonl_iter_with`](super::write_jsonl_iter_with): each message is
+/// written as soon as the stream yields it, and the file ends up identical
+/// to what [`write_jsonl`](super::write_jsonl) writes for the same
+/// messages. A [`sort_by`](OutputConfig::sort_by) other than chronological
+/// needs every message before the first line, so the stream is collected
+/// first.
+///
+/// Items are `Result`s so a parser's stream can be passed as is. The first
+/// error stops writing; lines already written stay in the file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use chatpack::core::output::write_jsonl_stream_async;
+/// use chatpack::prelude::*;
+///
+/// # async fn example() -> chatpack::Result<()> {
+/// let messages = vec![Message::new("Alice", "Hello!"), Message::new("Bob", "Hi!")];
+/// let stream = tokio_stream::iter(messages.into_iter().map(Ok::<_, ChatpackError>));
+/// let written = write_jsonl_stream_async(stream, "output.jsonl", &OutputConfig::new()).await?;
+/// assert_eq!(written, 2);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns the first error from `messages`, or [`ChatpackError::Io`] if
+/// the file cannot be created or written.
+#[cfg(feature = "json-output")]
+pub async fn write_jsonl_stream_async<S, E>(
+    messages: S,
+    output_path: impl AsRef<Path>,
+    config: &OutputConfig,
+) -> Result<usize, ChatpackError>
+where
+    S: Stream<Item = Result<Message, E>>,
+    ChatpackError: From<E>,
+{
+    let mut messages = std::pin::pin!(messages);
+    let mut writer = BufWriter::new(File::create(output_path).await?);
+    let mut written = 0;
+
+    if config.sort_by == SortKey::Chronological {
+        let mut boundaries = Boundaries::new(config);
+        while let Some(msg) = messages.next().await {
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(e) => {
+                    writer.flush().await?;
+                    return Err(e.into());
+                }
+            };
+            for record in OutputRecord::with_boundary(&msg, config, &mut boundaries) {
+                write_line(&mut writer, &record).await?;
+            }
+            written += 1;
+        }
+    } else {
+        let mut buffered = Vec::new();
+        while let Some(msg) = messages.next().await {
+            buffered.push(msg?);
+        }
+        for record in OutputRecord::records(&buffered, config) {
+            write_line(&mut writer, &record).await?;
+        }
+        written = buffered.len();
+    }
+
+    writer.flush().await?;
+    Ok(written)
+}
+
+#[cfg(feature = "json-output")]
+async fn write_line(
+    writer: &mut BufWriter<File>,
+    record: &OutputRecord,
+) -> Result<(), ChatpackError> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    Ok(())
+}
+
+async fn write_all(path: &Path, content: &[u8]) -> Result<(), ChatpackError> {
+    let mut file = File::create(path).await?;
+    file.write_all(content).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::{BoundaryStyle, SortKey};
+
+    fn sample() -> Vec<Message> {
+        let ts = |s: i64| chrono::DateTime::from_timestamp(1_705_314_600 + s, 0).unwrap();
+        vec![
+            Message::new("Alice", "Hello; \"quoted\"")
+                .with_id(1)
+                .with_timestamp(ts(0))
+                .with_source_file(0),
+            Message::new("Bob", "Line one\nline two")
+                .with_id(2)
+                .with_timestamp(ts(60))
+                .with_reply_to(1)
+                .with_source_file(0),
+            Message::new("Alice", "Next chat 👋")
+                .with_id(3)
+                .with_timestamp(ts(30))
+                .with_source_file(1),
+        ]
+    }
+
+    fn configs() -> Vec<OutputConfig> {
+        vec![
+            OutputConfig::new(),
+            OutputConfig::all().with_hash().with_schema_version(),
+            OutputConfig::all()
+                .with_conversation_boundaries(BoundaryStyle::Marker("--- new chat ---".into())),
+            OutputConfig::all().with_conversation_boundaries(BoundaryStyle::MetaField),
+            OutputConfig::all().with_sort_by(SortKey::SenderThenTime),
+        ]
+    }
+
+    // =========================================================================
+    // Byte comparison with the sync writers
+    // =========================================================================
+
+    #[cfg(feature = "csv-output")]
+    #[tokio::test]
+    async fn test_write_csv_async_matches_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let (sync_path, async_path) = (dir.path().join("sync.csv"), dir.path().join("async.csv"));
+        for config in configs() {
+            super::super::write_csv(&sample(), sync_path.to_str().unwrap(), &config).unwrap();
+            write_csv_async(&sample(), &async_path, &config)
+                .await
+                .unwrap();
+            assert_eq!(
+                std::fs::read(&async_path).unwrap(),
+                std::fs::read(&sync_path).unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "json-output")]
+    #[tokio::test]
+    async fn test_write_json_async_matches_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let (sync_path, async_path) = (dir.path().join("sync.json"), dir.path().join("async.json"));
+        for config in configs() {
+            super::super::write_json(&sample(), sync_path.to_str().unwrap(), &config).unwrap();
+            write_json_async(&sample(), &async_path, &config)
+                .await
+                .unwrap();
+            assert_eq!(
+                std::fs::read(&async_path).unwrap(),
+                std::fs::read(&sync_path).unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "json-output")]
+    #[tokio::test]
+    async fn test_write_jsonl_async_matches_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let sync_path = dir.path().join("sync.jsonl");
+        for config in configs() {
+            super::super::write_jsonl(&sample(), sync_path.to_str().unwrap(), &config).unwrap();
+            let expected = std::fs::read(&sync_path).unwrap();
+
+            let async_path = dir.path().join("async.jsonl");
+            write_jsonl_async(&sample(), &async_path, &config)
+                .await
+                .unwrap();
+            assert_eq!(std::fs::read(&async_path).unwrap(), expected);
+
+            let stream_path = dir.path().join("stream.jsonl");
+            let stream = tokio_stream::iter(sample().into_iter().map(Ok::<_, ChatpackError>));
+            let written = write_jsonl_stream_async(stream, &stream_path, &config)
+                .await
+                .unwrap();
+            assert_eq!(written, 3);
+            assert_eq!(std::fs::read(&stream_path).unwrap(), expected);
+        }
+    }
+
+    // =========================================================================
+    // Stream errors
+    // =========================================================================
+
+    #[cfg(feature = "json-output")]
+    #[tokio::test]
+    async fn test_write_jsonl_stream_async_stops_at_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("partial.jsonl");
+        let items = vec![
+            Ok(Message::new("Alice", "first")),
+            Err(ChatpackError::invalid_format("Test", "bad line")),
+            Ok(Message::new("Bob", "never written")),
+        ];
+
+        let result =
+            write_jsonl_stream_async(tokio_stream::iter(items), &path, &OutputConfig::new()).await;
+
+        assert!(result.unwrap_err().is_invalid_format());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"sender\":\"Alice\",\"content\":\"first\"}\n"
+        );
+    }
+
+    #[cfg(feature = "json-output")]
+    #[tokio::test]
+    async fn test_write_jsonl_async_missing_dir() {
+        let result = write_jsonl_async(
+            &sample(),
+            "/nonexistent/dir/out.jsonl",
+            &OutputConfig::new(),
+        )
+        .await;
+        assert!(result.unwrap_err().is_io());
+    }
+}
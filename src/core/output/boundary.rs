@@ -0,0 +1,93 @@
+//! Conversation boundary tracking shared by the writers.
+
+use crate::Message;
+use crate::core::models::{BoundaryStyle, OutputConfig};
+
+/// What a writer adds for one message under
+/// [`OutputConfig::conversation_boundaries`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Boundary<'a> {
+    /// Nothing: the message continues the current conversation.
+    None,
+    /// Write a marker record with this content before the message.
+    Marker(&'a str),
+    /// Flag the message with `conversation_start`.
+    Start,
+}
+
+/// Detects the first message of each source file as a writer walks its
+/// input.
+#[derive(Debug)]
+pub(crate) struct Boundaries<'a> {
+    style: Option<&'a BoundaryStyle>,
+    current: Option<usize>,
+}
+
+impl<'a> Boundaries<'a> {
+    pub(crate) fn new(config: &'a OutputConfig) -> Self {
+        Self {
+            style: config.conversation_boundaries.as_ref(),
+            current: None,
+        }
+    }
+
+    /// Returns the boundary before `msg`, which must be the next message
+    /// written.
+    pub(crate) fn next(&mut self, msg: &Message) -> Boundary<'a> {
+        let Some(style) = self.style else {
+            return Boundary::None;
+        };
+        if msg.source_file.is_none() || msg.source_file == self.current {
+            return Boundary::None;
+        }
+        self.current = msg.source_file;
+        match style {
+            BoundaryStyle::Marker(marker) => Boundary::Marker(marker),
+            BoundaryStyle::MetaField => Boundary::Start,
+        }
+    }
+
+    /// Whether `conversation_start` is written at all.
+    #[cfg(feature = "csv-output")]
+    pub(crate) fn has_meta_field(&self) -> bool {
+        matches!(self.style, Some(BoundaryStyle::MetaField))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boundaries_per_source() {
+        let config = OutputConfig::new().with_conversation_boundaries(BoundaryStyle::MetaField);
+        let mut boundaries = Boundaries::new(&config);
+        let seen: Vec<Boundary<'_>> = [0, 0, 1, 1, 2]
+            .into_iter()
+            .map(|i| boundaries.next(&Message::new("A", "x").with_source_file(i)))
+            .collect();
+
+        assert_eq!(
+            seen,
+            [
+                Boundary::Start,
+                Boundary::None,
+                Boundary::Start,
+                Boundary::None,
+                Boundary::Start
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_boundaries_without_source() {
+        let config =
+            OutputConfig::new().with_conversation_boundaries(BoundaryStyle::Marker("<|c|>".into()));
+        let mut boundaries = Boundaries::new(&config);
+        assert_eq!(boundaries.next(&Message::new("A", "x")), Boundary::None);
+        assert_eq!(
+            boundaries.next(&Message::new("A", "x").with_source_file(0)),
+            Boundary::Marker("<|c|>")
+        );
+    }
+}
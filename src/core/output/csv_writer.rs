@@ -3,16 +3,31 @@
 //! CSV format provides the best token efficiency for LLM context windows,
 //! achieving up to 13x compression compared to raw chat exports.
 
-use std::fs::File;
+use std::borrow::Borrow;
+use std::io::Write;
+use std::path::PathBuf;
 
+use super::boundary::{Boundaries, Boundary};
+use super::order::ordered;
+use super::target::{OnExists, WriteOptions, create_output};
 use crate::Message;
-use crate::core::models::OutputConfig;
+use crate::core::models::{LineEnding, OutputConfig};
 use crate::error::ChatpackError;
 
+/// Timestamp format of the `Timestamp` and `Edited` columns.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// UTF-8 byte order mark written first with
+/// [`write_bom`](OutputConfig::write_bom).
+const BOM: &str = "\u{FEFF}";
+
 /// Writes messages to a CSV file.
 ///
 /// Uses semicolon (`;`) as delimiter for Excel compatibility and to avoid
-/// conflicts with commas in message content.
+/// conflicts with commas in message content. Records end with
+/// [`line_ending`](OutputConfig::line_ending), and
+/// [`write_bom`](OutputConfig::write_bom) starts the file with a UTF-8
+/// byte order mark.
 ///
 /// # Format
 ///
@@ -20,8 +35,14 @@ use crate::error::ChatpackError;
 /// - Base: `Sender`, `Content`
 /// - `with_timestamps()`: adds `Timestamp` column
 /// - `with_ids()`: adds `ID` column
+/// - `with_index()`: adds `Index` column
 /// - `with_replies()`: adds `ReplyTo` column
 /// - `with_edited()`: adds `Edited` column
+/// - `with_metadata()`: adds `OriginalSender` and `ForwardedFrom` columns
+///   after `Sender`, and `Topic` and `Source` columns after `Content`
+/// - `with_platform()`: adds `Platform` column
+/// - `with_raw()`: adds `Raw` column
+/// - `with_hash()`: adds `Hash` column
 ///
 /// # Examples
 ///
@@ -46,21 +67,69 @@ pub fn write_csv(
     output_path: &str,
     config: &OutputConfig,
 ) -> Result<(), ChatpackError> {
-    let file = File::create(output_path)?;
-    let mut writer = csv::WriterBuilder::new().delimiter(b';').from_writer(file);
+    write_csv_iter(messages, output_path, config, OnExists::Overwrite)?;
+    Ok(())
+}
 
-    // Build header dynamically
-    let header = build_header(config);
-    writer.write_record(&header)?;
+/// Writes messages to a CSV file, handling an existing file as `options`
+/// says.
+///
+/// Same format as [`write_csv`]. Returns the path written, which differs
+/// from `output_path` when [`OnExists::Rename`] picked a new name.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "csv-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::{OnExists, WriteOptions, write_csv_with};
+/// use chatpack::prelude::*;
+///
+/// let messages = vec![Message::new("Alice", "Hello!")];
+/// let options = WriteOptions::new().with_on_exists(OnExists::Rename);
+/// let written = write_csv_with(&messages, "output.csv", &OutputConfig::new(), &options)?;
+/// println!("wrote {}", written.display());
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "csv-output"))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::OutputExists`] if the file exists and
+/// `options.on_exists` is [`OnExists::Error`], or [`ChatpackError::Io`] if
+/// the file cannot be created or written.
+pub fn write_csv_with(
+    messages: &[Message],
+    output_path: &str,
+    config: &OutputConfig,
+    options: &WriteOptions,
+) -> Result<PathBuf, ChatpackError> {
+    write_csv_iter(messages, output_path, config, options.on_exists)
+}
 
-    // Write each message
-    for msg in messages {
-        let record = build_record(msg, config);
-        writer.write_record(&record)?;
+/// Writes messages to a CSV file as they arrive from `messages`.
+pub(crate) fn write_csv_iter<I>(
+    messages: I,
+    output_path: &str,
+    config: &OutputConfig,
+    on_exists: OnExists,
+) -> Result<PathBuf, ChatpackError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Message>,
+{
+    let (mut file, path) = create_output(output_path, on_exists)?;
+    if config.write_bom {
+        file.write_all(BOM.as_bytes())?;
     }
+    let mut writer = builder(config).from_writer(file);
+
+    write_records(&mut writer, messages, config)?;
 
     writer.flush()?;
-    Ok(())
+    Ok(path)
 }
 
 /// Converts messages to a CSV string.
@@ -89,17 +158,10 @@ pub fn write_csv(
 /// # fn main() {}
 /// ```
 pub fn to_csv(messages: &[Message], config: &OutputConfig) -> Result<String, ChatpackError> {
-    let mut writer = csv::WriterBuilder::new()
-        .delimiter(b';')
-        .from_writer(Vec::new());
+    let bom = if config.write_bom { BOM } else { "" };
+    let mut writer = builder(config).from_writer(bom.as_bytes().to_vec());
 
-    let header = build_header(config);
-    writer.write_record(&header)?;
-
-    for msg in messages {
-        let record = build_record(msg, config);
-        writer.write_record(&record)?;
-    }
+    write_records(&mut writer, messages, config)?;
 
     let bytes = writer
         .into_inner()
@@ -107,7 +169,61 @@ pub fn to_csv(messages: &[Message], config: &OutputConfig) -> Result<String, Cha
     Ok(String::from_utf8(bytes)?)
 }
 
-/// Build CSV header based on output configuration.
+/// Returns a CSV writer builder with the delimiter and line ending of
+/// `config`.
+fn builder(config: &OutputConfig) -> csv::WriterBuilder {
+    let terminator = match config.line_ending {
+        LineEnding::Lf => csv::Terminator::Any(b'\n'),
+        LineEnding::CrLf => csv::Terminator::CRLF,
+    };
+    let mut builder = csv::WriterBuilder::new();
+    builder.delimiter(b';').terminator(terminator);
+    builder
+}
+
+/// Writes the header and one record per message, plus any conversation
+/// boundaries.
+fn write_records<W, I>(
+    writer: &mut csv::Writer<W>,
+    messages: I,
+    config: &OutputConfig,
+) -> Result<(), ChatpackError>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Borrow<Message>,
+{
+    let mut boundaries = Boundaries::new(config);
+    let meta_field = boundaries.has_meta_field();
+
+    let mut header = build_header(config);
+    if meta_field {
+        header.push("ConversationStart");
+    }
+    writer.write_record(&header)?;
+
+    for msg in ordered(messages, config.sort_by) {
+        let msg = msg.borrow();
+        let boundary = boundaries.next(msg);
+        if let Boundary::Marker(marker) = boundary {
+            writer.write_record(build_record(&Message::new("", marker), config))?;
+        }
+        let mut record = build_record(msg, config);
+        if meta_field {
+            record.push(
+                if boundary == Boundary::Start {
+                    "true"
+                } else {
+                    ""
+                }
+                .to_string(),
+            );
+        }
+        writer.write_record(&record)?;
+    }
+    Ok(())
+}
+
 fn build_header(config: &OutputConfig) -> Vec<&'static str> {
     let mut header = Vec::new();
 
@@ -119,14 +235,32 @@ fn build_header(config: &OutputConfig) -> Vec<&'static str> {
     }
 
     header.push("Sender");
+    if config.include_metadata {
+        header.extend(["OriginalSender", "ForwardedFrom"]);
+    }
     header.push("Content");
+    if config.include_metadata {
+        header.extend(["Topic", "Source"]);
+    }
 
+    if config.include_index {
+        header.push("Index");
+    }
     if config.include_replies {
         header.push("ReplyTo");
     }
     if config.include_edited {
         header.push("Edited");
     }
+    if config.include_platform {
+        header.push("Platform");
+    }
+    if config.include_raw {
+        header.push("Raw");
+    }
+    if config.include_hash {
+        header.push("Hash");
+    }
 
     header
 }
@@ -141,28 +275,161 @@ fn build_record(msg: &Message, config: &OutputConfig) -> Vec<String> {
     if config.include_timestamps {
         record.push(
             msg.timestamp
-                .map(|ts| ts.format("%Y-%m-%d %H:%M:%S").to_string())
+                .map(|ts| ts.format(TIMESTAMP_FORMAT).to_string())
                 .unwrap_or_default(),
         );
     }
 
+    let metadata = |value: &Option<String>| value.clone().unwrap_or_default();
     record.push(msg.sender.clone());
+    if config.include_metadata {
+        record.push(metadata(&msg.original_sender));
+        record.push(metadata(&msg.forwarded_from));
+    }
     record.push(msg.content.clone());
+    if config.include_metadata {
+        record.push(metadata(&msg.topic));
+        record.push(metadata(&msg.source));
+    }
 
+    if config.include_index {
+        record.push(
+            msg.source_index
+                .map(|index| index.to_string())
+                .unwrap_or_default(),
+        );
+    }
     if config.include_replies {
         record.push(msg.reply_to.map(|id| id.to_string()).unwrap_or_default());
     }
     if config.include_edited {
         record.push(
             msg.edited
-                .map(|ts| ts.format("%Y-%m-%d %H:%M:%S").to_string())
+                .map(|ts| ts.format(TIMESTAMP_FORMAT).to_string())
                 .unwrap_or_default(),
         );
     }
+    if config.include_platform {
+        record.push(msg.platform_name().unwrap_or_default().to_string());
+    }
+    if config.include_raw {
+        record.push(msg.raw.clone().unwrap_or_default());
+    }
+    if config.include_hash {
+        record.push(msg.content_hash_hex());
+    }
 
     record
 }
 
+/// Parses CSV produced by [`to_csv`] or [`write_csv`] back into messages.
+///
+/// Columns are found by their header, so any [`OutputConfig`] can be read.
+/// Empty cells and columns the writer left out come back as `None`; the
+/// `Hash` and `ConversationStart` columns are ignored. Timestamps are read
+/// as UTC, to the second, as they were written. Either line ending and a
+/// leading byte order mark are accepted.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "csv-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::{from_csv, to_csv};
+/// use chatpack::prelude::*;
+///
+/// let messages = vec![Message::new("Alice", "Hello; world").with_id(1)];
+/// let csv = to_csv(&messages, &OutputConfig::new().with_ids())?;
+///
+/// assert_eq!(from_csv(&csv)?, messages);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "csv-output"))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::InvalidFormat`] if the `Sender` or `Content`
+/// column is missing, or naming the first row with a cell that cannot be
+/// read.
+pub fn from_csv(content: &str) -> Result<Vec<Message>, ChatpackError> {
+    let invalid = |reason: String| ChatpackError::invalid_format("chatpack CSV", reason);
+    let content = content.strip_prefix(BOM).unwrap_or(content);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .from_reader(content.as_bytes());
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let (Some(sender), Some(content)) = (column("Sender"), column("Content")) else {
+        return Err(invalid(format!(
+            "expected Sender and Content columns, found {:?}",
+            headers.iter().collect::<Vec<_>>()
+        )));
+    };
+    let [id, timestamp, index, reply_to, edited, raw] =
+        ["ID", "Timestamp", "Index", "ReplyTo", "Edited", "Raw"].map(column);
+    let metadata = ["OriginalSender", "ForwardedFrom", "Topic", "Source"].map(column);
+
+    let mut messages = Vec::new();
+    for (i, row) in reader.records().enumerate() {
+        let row = row?;
+        // Rows are numbered from 1, after the header
+        let bad = |name: &str, reason: String| invalid(format!("row {}: {name}: {reason}", i + 1));
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|index| row.get(index))
+                .filter(|cell| !cell.is_empty())
+        };
+        let number = |index, name| {
+            cell(index)
+                .map(|cell: &str| cell.parse::<u64>().map_err(|e| bad(name, e.to_string())))
+                .transpose()
+        };
+        let time = |index, name| {
+            cell(index)
+                .map(|cell: &str| {
+                    chrono::NaiveDateTime::parse_from_str(cell, TIMESTAMP_FORMAT)
+                        .map(|ts| ts.and_utc())
+                        .map_err(|e| bad(name, e.to_string()))
+                })
+                .transpose()
+        };
+
+        let mut msg = Message::new(
+            row.get(sender).unwrap_or_default(),
+            row.get(content).unwrap_or_default(),
+        );
+        msg.id = number(id, "ID")?;
+        msg.timestamp = time(timestamp, "Timestamp")?;
+        msg.source_index = number(index, "Index")?;
+        msg.reply_to = number(reply_to, "ReplyTo")?;
+        msg.edited = time(edited, "Edited")?;
+        let [original_sender, forwarded_from, topic, source] =
+            metadata.map(|index| cell(index).map(str::to_string));
+        msg.original_sender = original_sender;
+        msg.forwarded_from = forwarded_from;
+        msg.topic = topic;
+        msg.source = source;
+        msg.platform = cell(column("Platform"))
+            .map(|cell| cell.parse().map_err(|e| bad("Platform", e)))
+            .transpose()?;
+        msg.raw = cell(raw).map(str::to_string);
+        messages.push(msg);
+    }
+    Ok(messages)
+}
+
+/// Reads a CSV file written by [`write_csv`].
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be read, or
+/// [`ChatpackError::InvalidFormat`] as for [`from_csv`].
+pub fn read_csv(path: impl AsRef<std::path::Path>) -> Result<Vec<Message>, ChatpackError> {
+    from_csv(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -176,4 +443,63 @@ mod tests {
         assert!(csv.contains("Sender;Content"));
         assert!(csv.contains("Alice;Hello"));
     }
+
+    #[test]
+    fn test_to_csv_platform_column() {
+        use crate::platform::Platform;
+
+        let messages = vec![
+            Message::new("Alice", "Hello").with_platform(Platform::Discord),
+            Message::new("Bob", "Hi"),
+        ];
+        let config = OutputConfig::new().with_platform();
+
+        let csv = to_csv(&messages, &config).unwrap();
+        assert_eq!(
+            csv,
+            "Sender;Content;Platform\nAlice;Hello;discord\nBob;Hi;\n"
+        );
+    }
+
+    #[test]
+    fn test_from_csv_round_trip() {
+        let ts = chrono::DateTime::from_timestamp(1_705_314_600, 0);
+        let messages = vec![
+            Message::with_metadata("Alice", "Hi;\n\"there\"", ts, Some(1), None, ts),
+            Message::with_metadata("Bob", "Reply", ts, Some(2), Some(1), None).with_raw("{}"),
+            Message::new("Carol", "")
+                .with_original_sender("Caroline")
+                .with_forwarded_from("News")
+                .with_topic("General")
+                .with_source("work"),
+        ];
+        let config = OutputConfig::all().with_metadata().with_raw().with_hash();
+        let csv = to_csv(&messages, &config).unwrap();
+
+        assert_eq!(from_csv(&csv).unwrap(), messages);
+        let plain = to_csv(&messages, &OutputConfig::new()).unwrap();
+        assert_eq!(from_csv(&plain).unwrap()[1], Message::new("Bob", "Reply"));
+        assert_eq!(from_csv(&plain).unwrap()[2], Message::new("Carol", ""));
+    }
+
+    #[test]
+    fn test_to_csv_crlf_and_bom() {
+        let messages = vec![Message::new("Alice", "Hi"), Message::new("Bob", "Hey")];
+        let config = OutputConfig::new()
+            .with_line_ending(LineEnding::CrLf)
+            .with_bom();
+        let csv = to_csv(&messages, &config).unwrap();
+
+        assert_eq!(csv, "\u{FEFF}Sender;Content\r\nAlice;Hi\r\nBob;Hey\r\n");
+        assert_eq!(from_csv(&csv).unwrap(), messages);
+    }
+
+    #[test]
+    fn test_from_csv_errors() {
+        let err = from_csv("Name;Text\nAlice;Hi\n").unwrap_err();
+        assert!(err.is_invalid_format());
+
+        let err = from_csv("ID;Sender;Content\n1;A;x\nseven;B;y\n").unwrap_err();
+        assert!(err.to_string().contains("row 2: ID"), "{err}");
+    }
 }
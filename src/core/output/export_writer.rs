@@ -0,0 +1,255 @@
+//! Platform-shaped export writers.
+//!
+//! Writes messages back into the JSON shape of the original platform export,
+//! so cleaned chats can be re-imported into tools that expect a Telegram
+//! Desktop `result.json` or a DiscordChatExporter JSON file. Files produced
+//! here re-parse to the same [`Message`] values.
+
+use std::fs::File;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::Message;
+use crate::error::ChatpackError;
+
+#[cfg(feature = "discord-json")]
+use crate::parsing::discord::DiscordRawMessage;
+#[cfg(feature = "telegram")]
+use crate::parsing::telegram::TelegramRawMessage;
+
+/// Top-level Telegram export object.
+#[cfg(feature = "telegram")]
+#[derive(Serialize)]
+struct TelegramExportOut<'a> {
+    name: &'a str,
+    #[serde(rename = "type")]
+    chat_type: &'static str,
+    messages: Vec<TelegramRawMessage>,
+}
+
+/// Top-level DiscordChatExporter object.
+#[cfg(feature = "discord-json")]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscordExportOut<'a> {
+    channel: DiscordChannelOut<'a>,
+    messages: Vec<DiscordRawMessage>,
+    message_count: usize,
+}
+
+#[cfg(feature = "discord-json")]
+#[derive(Serialize)]
+struct DiscordChannelOut<'a> {
+    name: &'a str,
+}
+
+/// Writes messages as a Telegram Desktop export (`result.json` shape).
+///
+/// # Format
+///
+/// ```json
+/// {
+///   "name": "Chat Name",
+///   "type": "personal_chat",
+///   "messages": [
+///     {"id": 1, "type": "message", "date": "2024-01-15T10:30:00",
+///      "date_unixtime": "1705314600", "from": "Alice", "text": "Hello"}
+///   ]
+/// }
+/// ```
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(all(feature = "json-output", feature = "telegram"))]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::write_telegram_json;
+/// use chatpack::Message;
+///
+/// let messages = vec![Message::new("Alice", "Hello!")];
+/// write_telegram_json(&messages, "result.json", "Cleaned chat")?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(all(feature = "json-output", feature = "telegram")))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+#[cfg(feature = "telegram")]
+pub fn write_telegram_json(
+    messages: &[Message],
+    output_path: &str,
+    chat_name: &str,
+) -> Result<(), ChatpackError> {
+    let json = to_telegram_json(messages, chat_name)?;
+    let mut file = File::create(output_path)?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+/// Converts messages to a Telegram export JSON string.
+///
+/// Same format as [`write_telegram_json`], but returns a [`String`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(all(feature = "json-output", feature = "telegram"))]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::to_telegram_json;
+/// use chatpack::Message;
+///
+/// let json = to_telegram_json(&[Message::new("Alice", "Hello!")], "Chat")?;
+/// assert!(json.contains(r#""from": "Alice""#));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(all(feature = "json-output", feature = "telegram")))]
+/// # fn main() {}
+/// ```
+#[cfg(feature = "telegram")]
+pub fn to_telegram_json(messages: &[Message], chat_name: &str) -> Result<String, ChatpackError> {
+    let export = TelegramExportOut {
+        name: chat_name,
+        chat_type: "personal_chat",
+        messages: messages.iter().map(TelegramRawMessage::from).collect(),
+    };
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+/// Writes messages as a DiscordChatExporter JSON file.
+///
+/// # Format
+///
+/// ```json
+/// {
+///   "channel": {"name": "general"},
+///   "messages": [
+///     {"id": "1", "timestamp": "2024-01-15T10:30:00+00:00",
+///      "timestampEdited": null, "content": "Hello", "author": {"name": "Alice"}}
+///   ],
+///   "messageCount": 1
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+#[cfg(feature = "discord-json")]
+pub fn write_discord_json(
+    messages: &[Message],
+    output_path: &str,
+    channel_name: &str,
+) -> Result<(), ChatpackError> {
+    let json = to_discord_json(messages, channel_name)?;
+    let mut file = File::create(output_path)?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+/// Converts messages to a DiscordChatExporter JSON string.
+///
+/// Same format as [`write_discord_json`], but returns a [`String`].
+#[cfg(feature = "discord-json")]
+pub fn to_discord_json(messages: &[Message], channel_name: &str) -> Result<String, ChatpackError> {
+    let export = DiscordExportOut {
+        channel: DiscordChannelOut { name: channel_name },
+        messages: messages.iter().map(DiscordRawMessage::from).collect(),
+        message_count: messages.len(),
+    };
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use chrono::DateTime;
+
+    fn sample_messages() -> Vec<Message> {
+        let ts = DateTime::from_timestamp(1705314600, 0);
+        let edited = DateTime::from_timestamp(1705314900, 0);
+        vec![
+            Message::with_metadata("Alice", "Hello!", ts, Some(1), None, None),
+            Message::with_metadata(
+                "Bob",
+                "Hi \"Alice\"\nнова строка",
+                ts,
+                Some(2),
+                Some(1),
+                edited,
+            ),
+            Message::new("Charlie", "No metadata"),
+        ]
+    }
+
+    #[cfg(feature = "telegram")]
+    #[test]
+    fn test_telegram_round_trip() {
+        use crate::parsers::TelegramParser;
+
+        let messages = sample_messages();
+        let json = to_telegram_json(&messages, "Round Trip").unwrap();
+        assert!(json.contains(r#""name": "Round Trip""#));
+
+        let reparsed = TelegramParser::new().parse_str(&json).unwrap();
+        assert_eq!(reparsed, messages);
+    }
+
+    #[cfg(feature = "telegram")]
+    #[test]
+    fn test_write_telegram_json() {
+        use crate::parsers::TelegramParser;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.json");
+        write_telegram_json(&sample_messages(), path.to_str().unwrap(), "Chat").unwrap();
+
+        let reparsed = TelegramParser::new().parse(&path).unwrap();
+        assert_eq!(reparsed, sample_messages());
+    }
+
+    #[cfg(feature = "discord-json")]
+    #[test]
+    fn test_discord_round_trip() {
+        use crate::parsers::DiscordParser;
+
+        let messages = sample_messages();
+        let json = to_discord_json(&messages, "general").unwrap();
+        assert!(json.contains(r#""messageCount": 3"#));
+
+        let reparsed = DiscordParser::new().parse_str(&json).unwrap();
+        assert_eq!(reparsed, messages);
+    }
+
+    #[cfg(feature = "discord-json")]
+    #[test]
+    fn test_write_discord_json() {
+        use crate::parsers::DiscordParser;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.json");
+        write_discord_json(&sample_messages(), path.to_str().unwrap(), "general").unwrap();
+
+        let reparsed = DiscordParser::new().parse(&path).unwrap();
+        assert_eq!(reparsed, sample_messages());
+    }
+
+    #[test]
+    fn test_empty_export() {
+        #[cfg(feature = "telegram")]
+        assert!(
+            to_telegram_json(&[], "Empty")
+                .unwrap()
+                .contains(r#""messages": []"#)
+        );
+        #[cfg(feature = "discord-json")]
+        assert!(
+            to_discord_json(&[], "empty")
+                .unwrap()
+                .contains(r#""messageCount": 0"#)
+        );
+    }
+}
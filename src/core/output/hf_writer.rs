@@ -0,0 +1,408 @@
+//! Hugging Face `datasets` text records.
+//!
+//! Writes JSON Lines in the shape `datasets` loads as a text corpus: one
+//! `{"text": ..., "meta": {...}}` object per line. The text of a message
+//! is `Sender: content`; its `meta` holds the sender, the timestamp (RFC
+//! 3339, with [`include_timestamps`](OutputConfig::include_timestamps)),
+//! and the [`source`](Message::source) file when there is one.
+//!
+//! [`write_hf_conversations`] and [`to_hf_conversations`] write one record
+//! per conversation instead, joining its turns with newlines. Conversations
+//! are groups of messages chosen by the caller, such as the values of
+//! [`partition_by_topic`](crate::core::processor::partition_by_topic).
+//!
+//! Missing values are left out of `meta` rather than written as `null`.
+//! [`sort_by`](OutputConfig::sort_by) orders messages (within each
+//! conversation); the other [`OutputConfig`] options do not apply.
+
+use std::borrow::Borrow;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::order::ordered;
+use super::target::{OnExists, create_output};
+use crate::Message;
+use crate::core::models::OutputConfig;
+use crate::error::ChatpackError;
+
+/// One line of HF text output.
+#[derive(Serialize)]
+struct HfRecord<M> {
+    text: String,
+    meta: M,
+}
+
+/// The `meta` of a message record.
+#[derive(Serialize)]
+struct MessageMeta<'a> {
+    sender: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+}
+
+/// The `meta` of a conversation record.
+#[derive(Serialize)]
+struct ConversationMeta<'a> {
+    /// Distinct senders, in order of first message
+    senders: Vec<&'a str>,
+    /// Timestamp of the first message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    /// Source of the first message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+    messages: usize,
+}
+
+fn turn(msg: &Message) -> String {
+    format!("{}: {}", msg.sender, msg.content)
+}
+
+fn timestamp(msg: &Message, config: &OutputConfig) -> Option<String> {
+    let format_ts = |ts: DateTime<Utc>| ts.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    msg.timestamp
+        .filter(|_| config.include_timestamps)
+        .map(format_ts)
+}
+
+fn message_record<'a>(msg: &'a Message, config: &OutputConfig) -> HfRecord<MessageMeta<'a>> {
+    HfRecord {
+        text: turn(msg),
+        meta: MessageMeta {
+            sender: &msg.sender,
+            timestamp: timestamp(msg, config),
+            source: msg.source.as_deref(),
+        },
+    }
+}
+
+/// Returns the record of a conversation, or `None` if it is empty.
+fn conversation_record<'a>(
+    messages: &'a [Message],
+    config: &OutputConfig,
+) -> Option<HfRecord<ConversationMeta<'a>>> {
+    let turns: Vec<&Message> = ordered(messages, config.sort_by).collect();
+    let first = *turns.first()?;
+    let mut senders: Vec<&str> = Vec::new();
+    for msg in &turns {
+        if !senders.contains(&msg.sender.as_str()) {
+            senders.push(&msg.sender);
+        }
+    }
+    let text = turns
+        .iter()
+        .map(|msg| turn(msg))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(HfRecord {
+        text,
+        meta: ConversationMeta {
+            senders,
+            timestamp: timestamp(first, config),
+            source: first.source.as_deref(),
+            messages: turns.len(),
+        },
+    })
+}
+
+/// Writes messages as Hugging Face `datasets` text records, one JSON
+/// object per line.
+///
+/// # Format
+///
+/// ```text
+/// {"text":"Alice: Hello","meta":{"sender":"Alice","timestamp":"2024-01-15T10:30:00Z"}}
+/// {"text":"Bob: Hi","meta":{"sender":"Bob","timestamp":"2024-01-15T10:31:00Z"}}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+pub fn write_hf_text(
+    messages: &[Message],
+    output_path: &str,
+    config: &OutputConfig,
+) -> Result<(), ChatpackError> {
+    write_hf_text_iter(messages, output_path, config, OnExists::Overwrite)?;
+    Ok(())
+}
+
+/// Writes HF text records to a file as messages arrive from `messages`.
+pub(crate) fn write_hf_text_iter<I>(
+    messages: I,
+    output_path: &str,
+    config: &OutputConfig,
+    on_exists: OnExists,
+) -> Result<PathBuf, ChatpackError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Message>,
+{
+    let (file, path) = create_output(output_path, on_exists)?;
+    let mut writer = BufWriter::new(file);
+    for msg in ordered(messages, config.sort_by) {
+        serde_json::to_writer(&mut writer, &message_record(msg.borrow(), config))?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+/// Converts messages to Hugging Face `datasets` text records.
+///
+/// Same format as [`write_hf_text`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::to_hf_text;
+/// use chatpack::prelude::*;
+///
+/// let messages = vec![Message::new("Alice", "Hello")];
+/// let jsonl = to_hf_text(&messages, &OutputConfig::new())?;
+///
+/// assert_eq!(jsonl, "{\"text\":\"Alice: Hello\",\"meta\":{\"sender\":\"Alice\"}}\n");
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json-output"))]
+/// # fn main() {}
+/// ```
+pub fn to_hf_text(messages: &[Message], config: &OutputConfig) -> Result<String, ChatpackError> {
+    let mut output = String::new();
+    for msg in ordered(messages, config.sort_by) {
+        output.push_str(&serde_json::to_string(&message_record(msg, config))?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Writes one Hugging Face `datasets` text record per conversation.
+///
+/// The text joins the conversation's `Sender: content` turns with
+/// newlines. Its `meta` lists the distinct `senders` in order of first
+/// message, the `timestamp` and `source` of the first message, and the
+/// number of `messages`. Empty conversations are skipped.
+///
+/// # Format
+///
+/// ```text
+/// {"text":"Alice: Hello\nBob: Hi","meta":{"senders":["Alice","Bob"],"messages":2}}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+pub fn write_hf_conversations<I, C>(
+    conversations: I,
+    output_path: &str,
+    config: &OutputConfig,
+) -> Result<(), ChatpackError>
+where
+    I: IntoIterator<Item = C>,
+    C: AsRef<[Message]>,
+{
+    let (file, _) = create_output(output_path, OnExists::Overwrite)?;
+    let mut writer = BufWriter::new(file);
+    for conversation in conversations {
+        if let Some(record) = conversation_record(conversation.as_ref(), config) {
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Converts conversations to Hugging Face `datasets` text records, one
+/// per conversation.
+///
+/// Same format as [`write_hf_conversations`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::to_hf_conversations;
+/// use chatpack::core::processor::partition_by_topic;
+/// use chatpack::prelude::*;
+///
+/// let messages = vec![
+///     Message::new("Alice", "Shipped!").with_topic("Releases"),
+///     Message::new("Bob", "Hi").with_topic("General"),
+///     Message::new("Bob", "Nice").with_topic("Releases"),
+/// ];
+/// let topics = partition_by_topic(messages);
+/// let jsonl = to_hf_conversations(topics.values(), &OutputConfig::new())?;
+///
+/// let releases = jsonl.lines().nth(1).unwrap();
+/// assert!(releases.starts_with(r#"{"text":"Alice: Shipped!\nBob: Nice""#));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json-output"))]
+/// # fn main() {}
+/// ```
+pub fn to_hf_conversations<I, C>(
+    conversations: I,
+    config: &OutputConfig,
+) -> Result<String, ChatpackError>
+where
+    I: IntoIterator<Item = C>,
+    C: AsRef<[Message]>,
+{
+    let mut output = String::new();
+    for conversation in conversations {
+        if let Some(record) = conversation_record(conversation.as_ref(), config) {
+            output.push_str(&serde_json::to_string(&record)?);
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 15, 10, minute, 0).unwrap()
+    }
+
+    fn messages() -> Vec<Message> {
+        vec![
+            Message::new("Alice", "Hello").with_timestamp(at(30)),
+            Message::new("Bob", "Hi\nthere")
+                .with_timestamp(at(31))
+                .with_source("chat.json"),
+            Message::new("Alice", "Bye").with_timestamp(at(32)),
+        ]
+    }
+
+    fn records(jsonl: &str) -> Vec<Value> {
+        jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_message_records() {
+        let config = OutputConfig::new().with_timestamps();
+        let records = records(&to_hf_text(&messages(), &config).unwrap());
+
+        assert_eq!(records.len(), 3);
+        for record in &records {
+            assert_eq!(keys(record), ["meta", "text"]);
+        }
+        assert_eq!(records[1]["text"], "Bob: Hi\nthere");
+        assert_eq!(keys(&records[1]["meta"]), ["sender", "source", "timestamp"]);
+        assert_eq!(records[1]["meta"]["sender"], "Bob");
+        assert_eq!(records[1]["meta"]["source"], "chat.json");
+        assert_eq!(keys(&records[0]["meta"]), ["sender", "timestamp"]);
+    }
+
+    #[test]
+    fn test_timestamps_are_iso_strings() {
+        let config = OutputConfig::new().with_timestamps();
+        let records = records(&to_hf_text(&messages(), &config).unwrap());
+
+        let timestamp = records[0]["meta"]["timestamp"].as_str().unwrap();
+        assert_eq!(timestamp, "2024-01-15T10:30:00Z");
+        assert_eq!(DateTime::parse_from_rfc3339(timestamp).unwrap(), at(30));
+    }
+
+    #[test]
+    fn test_timestamps_follow_config() {
+        let records = records(&to_hf_text(&messages(), &OutputConfig::new()).unwrap());
+        assert!(records[0]["meta"].get("timestamp").is_none());
+    }
+
+    #[test]
+    fn test_sort_order() {
+        use crate::core::models::SortKey;
+
+        let config = OutputConfig::new().with_sort_by(SortKey::SenderThenTime);
+        let jsonl = to_hf_text(&messages(), &config).unwrap();
+        let senders: Vec<Value> = records(&jsonl)
+            .iter()
+            .map(|r| r["meta"]["sender"].clone())
+            .collect();
+        assert_eq!(senders, ["Alice", "Alice", "Bob"]);
+    }
+
+    #[test]
+    fn test_conversation_records() {
+        let messages = messages();
+        let conversations = [&messages[..2], &messages[2..]];
+        let config = OutputConfig::new().with_timestamps();
+        let records = records(&to_hf_conversations(conversations, &config).unwrap());
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["text"], "Alice: Hello\nBob: Hi\nthere");
+        assert_eq!(
+            keys(&records[0]["meta"]),
+            ["messages", "senders", "timestamp"]
+        );
+        assert_eq!(
+            records[0]["meta"]["senders"],
+            serde_json::json!(["Alice", "Bob"])
+        );
+        assert_eq!(records[0]["meta"]["timestamp"], "2024-01-15T10:30:00Z");
+        assert_eq!(records[0]["meta"]["messages"], 2);
+        assert_eq!(records[1]["text"], "Alice: Bye");
+    }
+
+    #[test]
+    fn test_empty_conversations_are_skipped() {
+        let messages = messages();
+        let conversations: [&[Message]; 3] = [&[], &messages[..1], &[]];
+        let jsonl = to_hf_conversations(conversations, &OutputConfig::new()).unwrap();
+        assert_eq!(jsonl.lines().count(), 1);
+
+        let none: [&[Message]; 0] = [];
+        assert_eq!(to_hf_conversations(none, &OutputConfig::new()).unwrap(), "");
+        assert_eq!(to_hf_text(&[], &OutputConfig::new()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_write_matches_string() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = OutputConfig::new().with_timestamps();
+        let messages = messages();
+
+        let path = dir.path().join("hf.jsonl");
+        write_hf_text(&messages, path.to_str().unwrap(), &config).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            to_hf_text(&messages, &config).unwrap()
+        );
+
+        let conversations = [messages.clone()];
+        let path = dir.path().join("hf_conversations.jsonl");
+        write_hf_conversations(&conversations, path.to_str().unwrap(), &config).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            to_hf_conversations(&conversations, &config).unwrap()
+        );
+    }
+}
@@ -2,59 +2,18 @@
 //!
 //! Writes messages as a JSON array, suitable for APIs and structured data processing.
 
-use std::fs::File;
-use std::io::Write;
+use std::borrow::Borrow;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
 
-use serde::Serialize;
+use serde::ser::{SerializeSeq, Serializer};
 
+use super::record::{InputRecord, OutputRecord};
+use super::target::{OnExists, WriteOptions, create_output};
 use crate::Message;
 use crate::core::models::OutputConfig;
 use crate::error::ChatpackError;
 
-/// Internal message representation for JSON serialization.
-///
-/// Only includes fields enabled in [`OutputConfig`].
-#[derive(Serialize)]
-struct JsonMessage {
-    sender: String,
-    content: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    timestamp: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    id: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    reply_to: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    edited: Option<String>,
-}
-
-impl JsonMessage {
-    fn from_message(msg: &Message, config: &OutputConfig) -> Self {
-        Self {
-            sender: msg.sender.clone(),
-            content: msg.content.clone(),
-            timestamp: if config.include_timestamps {
-                msg.timestamp
-                    .map(|ts| ts.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-            } else {
-                None
-            },
-            id: if config.include_ids { msg.id } else { None },
-            reply_to: if config.include_replies {
-                msg.reply_to
-            } else {
-                None
-            },
-            edited: if config.include_edited {
-                msg.edited
-                    .map(|ts| ts.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-            } else {
-                None
-            },
-        }
-    }
-}
-
 /// Writes messages to a JSON file as an array.
 ///
 /// Produces a pretty-printed JSON array suitable for APIs and structured
@@ -92,12 +51,56 @@ pub fn write_json(
     output_path: &str,
     config: &OutputConfig,
 ) -> Result<(), ChatpackError> {
-    let json = to_json(messages, config)?;
-    let mut file = File::create(output_path)?;
-    file.write_all(json.as_bytes())?;
+    write_json_iter(messages, output_path, config, OnExists::Overwrite)?;
     Ok(())
 }
 
+/// Writes messages to a JSON file, handling an existing file as `options`
+/// says.
+///
+/// Same format as [`write_json`]. Returns the path written, which differs
+/// from `output_path` when [`OnExists::Rename`] picked a new name.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::OutputExists`] if the file exists and
+/// `options.on_exists` is [`OnExists::Error`], or [`ChatpackError::Io`] if
+/// the file cannot be created or written.
+pub fn write_json_with(
+    messages: &[Message],
+    output_path: &str,
+    config: &OutputConfig,
+    options: &WriteOptions,
+) -> Result<PathBuf, ChatpackError> {
+    write_json_iter(messages, output_path, config, options.on_exists)
+}
+
+/// Writes messages to a JSON file as they arrive from `messages`.
+///
+/// Produces the same bytes as [`to_json`] without holding the whole array
+/// in memory.
+pub(crate) fn write_json_iter<I>(
+    messages: I,
+    output_path: &str,
+    config: &OutputConfig,
+    on_exists: OnExists,
+) -> Result<PathBuf, ChatpackError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Message>,
+{
+    let (file, path) = create_output(output_path, on_exists)?;
+    let mut writer = BufWriter::new(file);
+    let mut serializer = serde_json::Serializer::pretty(&mut writer);
+    let mut seq = serializer.serialize_seq(None)?;
+    for record in OutputRecord::records(messages, config) {
+        seq.serialize_element(&record)?;
+    }
+    SerializeSeq::end(seq)?;
+    writer.flush()?;
+    Ok(path)
+}
+
 /// Converts messages to a JSON array string.
 ///
 /// Same format as [`write_json`], but returns a [`String`] instead of writing
@@ -120,14 +123,66 @@ pub fn write_json(
 /// # fn main() {}
 /// ```
 pub fn to_json(messages: &[Message], config: &OutputConfig) -> Result<String, ChatpackError> {
-    let json_messages: Vec<JsonMessage> = messages
-        .iter()
-        .map(|m| JsonMessage::from_message(m, config))
-        .collect();
+    let json_messages: Vec<OutputRecord> = OutputRecord::records(messages, config).collect();
 
     Ok(serde_json::to_string_pretty(&json_messages)?)
 }
 
+/// Parses a JSON array produced by [`to_json`] or [`write_json`] back into
+/// messages.
+///
+/// Reads records the way [`from_jsonl`](super::from_jsonl) reads lines:
+/// fields the writer left out come back as `None`, and unknown keys are
+/// ignored.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::{from_json, to_json};
+/// use chatpack::prelude::*;
+///
+/// let messages = vec![Message::new("Alice", "Hello").with_id(1)];
+/// let json = to_json(&messages, &OutputConfig::new().with_ids())?;
+///
+/// assert_eq!(from_json(&json)?, messages);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json-output"))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::InvalidFormat`] if `content` is not an array of
+/// message objects, naming the first bad element.
+pub fn from_json(content: &str) -> Result<Vec<Message>, ChatpackError> {
+    let invalid = |reason: String| ChatpackError::invalid_format("chatpack JSON", reason);
+    let records: Vec<serde_json::Value> =
+        serde_json::from_str(content).map_err(|e| invalid(e.to_string()))?;
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let element = |reason: String| invalid(format!("element {}: {reason}", i + 1));
+            let record: InputRecord =
+                serde_json::from_value(value).map_err(|e| element(e.to_string()))?;
+            record.into_message().map_err(element)
+        })
+        .collect()
+}
+
+/// Reads a JSON file written by [`write_json`].
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be read, or
+/// [`ChatpackError::InvalidFormat`] as for [`from_json`].
+pub fn read_json(path: impl AsRef<std::path::Path>) -> Result<Vec<Message>, ChatpackError> {
+    from_json(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,4 +249,47 @@ mod tests {
         assert!(content.contains(r#""timestamp": "2024-06-15T12:30:00Z""#));
         assert!(content.contains(r#""id": 123"#));
     }
+
+    #[test]
+    fn test_write_json_matches_to_json() {
+        let config = OutputConfig::all();
+        for messages in [
+            vec![],
+            vec![Message::new("Alice", "Hello \"quoted\"\nline")],
+            vec![
+                Message::new("Alice", "Hi").with_id(1),
+                Message::new("Bob", "Hey"),
+            ],
+        ] {
+            let temp_file = NamedTempFile::new().unwrap();
+            let path = temp_file.path().to_str().unwrap();
+            write_json(&messages, path, &config).unwrap();
+
+            let written = std::fs::read_to_string(path).unwrap();
+            assert_eq!(written, to_json(&messages, &config).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_from_json_round_trip() {
+        let ts = chrono::DateTime::from_timestamp(1_705_314_600, 0);
+        let messages = vec![
+            Message::with_metadata("Alice", "Hi\n\"there\"", ts, Some(1), None, ts),
+            Message::new("Bob", "Reply").with_original_sender("Robert"),
+        ];
+        let json = to_json(
+            &messages,
+            &OutputConfig::all().with_metadata().with_schema_version(),
+        )
+        .unwrap();
+        assert_eq!(from_json(&json).unwrap(), messages);
+        assert_eq!(from_json("[]").unwrap(), []);
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        assert!(from_json("{}").unwrap_err().is_invalid_format());
+        let err = from_json(r#"[{"sender":"A","content":"x"},{"sender":"B"}]"#).unwrap_err();
+        assert!(err.to_string().contains("element 2"), "{err}");
+    }
 }
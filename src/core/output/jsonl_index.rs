@@ -0,0 +1,441 @@
+//! Sidecar index for seeking into large JSONL files by time.
+//!
+//! [`JsonlWriteOptions::with_index_every`](super::JsonlWriteOptions::with_index_every)
+//! writes `<output>.idx` next to the JSONL file, recording the byte offset
+//! and timestamp of every Nth record. [`JsonlIndex::open`] reads it back and
+//! [`JsonlIndex::find_offset`] tells a consumer where to start reading for
+//! messages after a given time, without scanning the file from the start.
+//!
+//! # Format
+//!
+//! All integers are little-endian. A 32-byte header:
+//!
+//! | Offset | Size | Field |
+//! |--------|------|-------|
+//! | 0 | 8 | Magic `CPJLIDX1` |
+//! | 8 | 8 | `u64`: records per entry (N) |
+//! | 16 | 8 | `u64`: length of the JSONL file in bytes when indexed |
+//! | 24 | 8 | `u64`: number of records in the JSONL file |
+//!
+//! followed by one 24-byte entry for records 0, N, 2N, …:
+//!
+//! | Offset | Size | Field |
+//! |--------|------|-------|
+//! | 0 | 8 | `u64`: record number, counting from 0 |
+//! | 8 | 8 | `u64`: byte offset of the record's line |
+//! | 16 | 8 | `i64`: Unix seconds of the record's timestamp |
+//!
+//! A record without a timestamp, such as a conversation marker, takes the
+//! timestamp of the last earlier record that had one, and `i64::MIN` if
+//! none did. For messages in chronological order the entries' timestamps
+//! then never decrease.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::error::ChatpackError;
+
+/// Magic bytes at the start of an index file; the last byte is the format
+/// version.
+const MAGIC: &[u8; 8] = b"CPJLIDX1";
+
+const HEADER_LEN: usize = 32;
+
+const ENTRY_LEN: usize = 24;
+
+/// Format name in [`ChatpackError::InvalidFormat`] errors.
+const FORMAT: &str = "chatpack JSONL index";
+
+/// Returns the path of the index of `jsonl_path`: the same path with `.idx`
+/// appended, so `chat.jsonl` is indexed by `chat.jsonl.idx`.
+pub fn index_path(jsonl_path: impl AsRef<Path>) -> PathBuf {
+    let mut path = jsonl_path.as_ref().as_os_str().to_owned();
+    path.push(".idx");
+    PathBuf::from(path)
+}
+
+/// One indexed record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Record number in the JSONL file, counting from 0.
+    pub record: u64,
+    /// Byte offset of the start of the record's line.
+    pub offset: u64,
+    /// Timestamp of the record, or of the last earlier record that had one.
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Index of a JSONL file, mapping every Nth record to its byte offset and
+/// timestamp.
+///
+/// # Example
+///
+/// ```no_run
+/// # #[cfg(feature = "json-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use std::io::{BufRead, BufReader, Seek, SeekFrom};
+///
+/// use chatpack::core::output::JsonlIndex;
+/// use chrono::{TimeZone, Utc};
+///
+/// let index = JsonlIndex::open_or_build("chat.jsonl", 1000)?;
+/// let after = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+///
+/// let mut file = std::fs::File::open("chat.jsonl")?;
+/// file.seek(SeekFrom::Start(index.find_offset(after)))?;
+/// for line in BufReader::new(file).lines() {
+///     // Records before `after` may come first; skip them
+///     println!("{}", line?);
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json-output"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonlIndex {
+    jsonl_path: PathBuf,
+    every: u64,
+    jsonl_len: u64,
+    records: u64,
+    entries: Vec<IndexEntry>,
+}
+
+impl JsonlIndex {
+    /// Reads the index of `jsonl_path` from its [`index_path`].
+    ///
+    /// Does not check that the index is up to date; see
+    /// [`is_stale`](Self::is_stale).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::Io`] if the index cannot be read, or
+    /// [`ChatpackError::InvalidFormat`] if it is not a valid index.
+    pub fn open(jsonl_path: impl AsRef<Path>) -> Result<Self, ChatpackError> {
+        let jsonl_path = jsonl_path.as_ref();
+        let bytes = fs::read(index_path(jsonl_path))?;
+        Self::decode(jsonl_path, &bytes)
+    }
+
+    /// Indexes every `every`th record of an existing JSONL file.
+    ///
+    /// Timestamps are read from the records' `timestamp` fields, so the file
+    /// must have been written with
+    /// [`OutputConfig::with_timestamps`](crate::core::models::OutputConfig::with_timestamps)
+    /// for [`find_offset`](Self::find_offset) to be useful. `every` of 0 is
+    /// treated as 1. The index is not saved; call [`write`](Self::write).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::Io`] if the file cannot be read, or
+    /// [`ChatpackError::InvalidFormat`] naming the first line that is not a
+    /// JSON object.
+    pub fn build(jsonl_path: impl AsRef<Path>, every: usize) -> Result<Self, ChatpackError> {
+        /// The only field the index needs from a record.
+        #[derive(Deserialize)]
+        struct Timestamped {
+            #[serde(default)]
+            timestamp: Option<DateTime<Utc>>,
+        }
+
+        let jsonl_path = jsonl_path.as_ref();
+        let mut reader = BufReader::new(File::open(jsonl_path)?);
+        let mut builder = IndexBuilder::new(every);
+        let mut line = Vec::new();
+        let mut line_number = 0;
+        loop {
+            line.clear();
+            let len = reader.read_until(b'\n', &mut line)?;
+            if len == 0 {
+                break;
+            }
+            line_number += 1;
+            if line.trim_ascii().is_empty() {
+                builder.skip(len);
+                continue;
+            }
+            let record: Timestamped = serde_json::from_slice(&line).map_err(|e| {
+                ChatpackError::invalid_format("chatpack JSONL", format!("line {line_number}: {e}"))
+            })?;
+            builder.push(len, record.timestamp.map(|ts| ts.timestamp()));
+        }
+        Ok(builder.finish(jsonl_path))
+    }
+
+    /// Opens the index of `jsonl_path`, or builds and writes it if it is
+    /// missing or [stale](Self::is_stale).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`build`](Self::build) and [`write`](Self::write).
+    pub fn open_or_build(
+        jsonl_path: impl AsRef<Path>,
+        every: usize,
+    ) -> Result<Self, ChatpackError> {
+        let jsonl_path = jsonl_path.as_ref();
+        if let Ok(index) = Self::open(jsonl_path) {
+            if !index.is_stale()? {
+                return Ok(index);
+            }
+        }
+        let index = Self::build(jsonl_path, every)?;
+        index.write()?;
+        Ok(index)
+    }
+
+    /// Writes the index to the [`index_path`] of its JSONL file, replacing
+    /// any index there.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::Io`] if the index cannot be written.
+    pub fn write(&self) -> Result<PathBuf, ChatpackError> {
+        let path = index_path(&self.jsonl_path);
+        fs::write(&path, self.encode())?;
+        Ok(path)
+    }
+
+    /// Returns `true` if the JSONL file changed after the index was written:
+    /// it is newer than the index or no longer the length it was indexed at.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::Io`] if either file's metadata cannot be
+    /// read.
+    pub fn is_stale(&self) -> Result<bool, ChatpackError> {
+        let jsonl = fs::metadata(&self.jsonl_path)?;
+        let index = fs::metadata(index_path(&self.jsonl_path))?;
+        Ok(jsonl.len() != self.jsonl_len || jsonl.modified()? > index.modified()?)
+    }
+
+    /// Returns the byte offset to start reading at for records with
+    /// timestamps at or after `after`.
+    ///
+    /// The offset is that of the last indexed record before `after`, so up
+    /// to N records before `after` may come first; skip them while reading.
+    /// Returns 0 if no indexed record is before `after`, or if the records
+    /// have no timestamps. Assumes the records are in chronological order.
+    pub fn find_offset(&self, after: DateTime<Utc>) -> u64 {
+        // Records carry whole seconds; compare at that precision so an
+        // `after` within a second never skips a record of that second
+        let after = after.timestamp();
+        let before = self
+            .entries
+            .partition_point(|e| e.timestamp.is_none_or(|ts| ts.timestamp() < after));
+        // Entries without a timestamp only lead the index, so one here means
+        // no record is known to be before `after`
+        before
+            .checked_sub(1)
+            .and_then(|i| self.entries[i].timestamp.map(|_| self.entries[i].offset))
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of records per entry.
+    pub fn every(&self) -> u64 {
+        self.every
+    }
+
+    /// Returns the number of records in the JSONL file.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Returns the indexed records.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.entries.len() * ENTRY_LEN);
+        bytes.extend_from_slice(MAGIC);
+        for field in [self.every, self.jsonl_len, self.records] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.record.to_le_bytes());
+            bytes.extend_from_slice(&entry.offset.to_le_bytes());
+            let ts = entry.timestamp.map_or(i64::MIN, |ts| ts.timestamp());
+            bytes.extend_from_slice(&ts.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn decode(jsonl_path: &Path, bytes: &[u8]) -> Result<Self, ChatpackError> {
+        let invalid = |reason: &str| ChatpackError::invalid_format(FORMAT, reason);
+        if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+            return Err(invalid("missing header"));
+        }
+        let (header, body) = bytes.split_at(HEADER_LEN);
+        if body.len() % ENTRY_LEN != 0 {
+            return Err(invalid("truncated entry"));
+        }
+        let word = |chunk: &[u8], i: usize| {
+            u64::from_le_bytes(chunk[i * 8..(i + 1) * 8].try_into().expect("8 bytes"))
+        };
+
+        let entries = body
+            .chunks_exact(ENTRY_LEN)
+            .map(|chunk| {
+                let ts = i64::from_le_bytes(chunk[16..].try_into().expect("8 bytes"));
+                let timestamp = if ts == i64::MIN {
+                    None
+                } else {
+                    Some(
+                        DateTime::from_timestamp(ts, 0)
+                            .ok_or_else(|| invalid("timestamp out of range"))?,
+                    )
+                };
+                Ok(IndexEntry {
+                    record: word(chunk, 0),
+                    offset: word(chunk, 1),
+                    timestamp,
+                })
+            })
+            .collect::<Result<Vec<_>, ChatpackError>>()?;
+
+        Ok(Self {
+            jsonl_path: jsonl_path.to_path_buf(),
+            every: word(header, 1),
+            jsonl_len: word(header, 2),
+            records: word(header, 3),
+            entries,
+        })
+    }
+}
+
+/// Collects index entries as records are written or read.
+pub(crate) struct IndexBuilder {
+    every: u64,
+    records: u64,
+    offset: u64,
+    last_timestamp: Option<i64>,
+    entries: Vec<IndexEntry>,
+}
+
+impl IndexBuilder {
+    pub(crate) fn new(every: usize) -> Self {
+        Self {
+            every: every.max(1) as u64,
+            records: 0,
+            offset: 0,
+            last_timestamp: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds a record of `len` bytes, newline included, with its Unix
+    /// timestamp if it has one.
+    pub(crate) fn push(&mut self, len: usize, timestamp: Option<i64>) {
+        if timestamp.is_some() {
+            self.last_timestamp = timestamp;
+        }
+        if self.records.is_multiple_of(self.every) {
+            self.entries.push(IndexEntry {
+                record: self.records,
+                offset: self.offset,
+                timestamp: self
+                    .last_timestamp
+                    .and_then(|ts| DateTime::from_timestamp(ts, 0)),
+            });
+        }
+        self.records += 1;
+        self.skip(len);
+    }
+
+    /// Adds `len` bytes that are not a record, such as a blank line.
+    pub(crate) fn skip(&mut self, len: usize) {
+        self.offset += len as u64;
+    }
+
+    pub(crate) fn finish(self, jsonl_path: &Path) -> JsonlIndex {
+        JsonlIndex {
+            jsonl_path: jsonl_path.to_path_buf(),
+            every: self.every,
+            jsonl_len: self.offset,
+            records: self.records,
+            entries: self.entries,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_index() -> JsonlIndex {
+        let mut builder = IndexBuilder::new(2);
+        for (len, ts) in [(10, None), (12, Some(100)), (11, None), (9, Some(300))] {
+            builder.push(len, ts);
+        }
+        builder.finish(Path::new("chat.jsonl"))
+    }
+
+    #[test]
+    fn test_builder_carries_timestamps_forward() {
+        let index = sample_index();
+        assert_eq!(index.records(), 4);
+        assert_eq!(index.jsonl_len, 42);
+        let entries: Vec<_> = index
+            .entries()
+            .iter()
+            .map(|e| (e.record, e.offset, e.timestamp.map(|ts| ts.timestamp())))
+            .collect();
+        assert_eq!(entries, [(0, 0, None), (2, 22, Some(100))]);
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let index = sample_index();
+        let bytes = index.encode();
+        assert_eq!(bytes.len(), HEADER_LEN + 2 * ENTRY_LEN);
+        assert_eq!(
+            JsonlIndex::decode(Path::new("chat.jsonl"), &bytes).unwrap(),
+            index
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        let path = Path::new("chat.jsonl");
+        assert!(JsonlIndex::decode(path, b"CPJLIDX1").is_err());
+        let mut bytes = sample_index().encode();
+        bytes.pop();
+        assert!(
+            JsonlIndex::decode(path, &bytes)
+                .unwrap_err()
+                .is_invalid_format()
+        );
+    }
+
+    #[test]
+    fn test_find_offset_without_timestamps_starts_at_zero() {
+        let mut builder = IndexBuilder::new(2);
+        for _ in 0..6 {
+            builder.push(10, None);
+        }
+        let index = builder.finish(Path::new("chat.jsonl"));
+        assert_eq!(index.entries().len(), 3);
+        assert_eq!(index.find_offset(DateTime::<Utc>::MAX_UTC), 0);
+    }
+
+    #[test]
+    fn test_find_offset_skips_leading_untimed_entries() {
+        let index = sample_index();
+        let after = DateTime::from_timestamp(200, 0).unwrap();
+        assert_eq!(index.find_offset(after), 22);
+        let after = DateTime::from_timestamp(50, 0).unwrap();
+        assert_eq!(index.find_offset(after), 0);
+    }
+
+    #[test]
+    fn test_index_path() {
+        assert_eq!(
+            index_path("out/chat.jsonl"),
+            Path::new("out/chat.jsonl.idx")
+        );
+    }
+}
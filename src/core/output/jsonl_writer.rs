@@ -6,59 +6,21 @@
 //! - Streaming processing
 //! - Large datasets that don't fit in memory
 
-use std::fs::File;
+use std::borrow::Borrow;
+use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
+use std::path::PathBuf;
 
 use serde::Serialize;
 
+use super::jsonl_index::{IndexBuilder, JsonlIndex};
+use super::record::{InputRecord, OutputRecord};
+use super::target::{OnExists, create_output};
 use crate::Message;
-use crate::core::models::OutputConfig;
+use crate::core::models::{self, OutputConfig};
+use crate::core::processor::ContextWindow;
 use crate::error::ChatpackError;
 
-/// Internal message representation for JSONL serialization.
-///
-/// Only includes fields enabled in [`OutputConfig`].
-#[derive(Serialize)]
-struct JsonlMessage {
-    sender: String,
-    content: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    timestamp: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    id: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    reply_to: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    edited: Option<String>,
-}
-
-impl JsonlMessage {
-    fn from_message(msg: &Message, config: &OutputConfig) -> Self {
-        Self {
-            sender: msg.sender.clone(),
-            content: msg.content.clone(),
-            timestamp: if config.include_timestamps {
-                msg.timestamp
-                    .map(|ts| ts.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-            } else {
-                None
-            },
-            id: if config.include_ids { msg.id } else { None },
-            reply_to: if config.include_replies {
-                msg.reply_to
-            } else {
-                None
-            },
-            edited: if config.include_edited {
-                msg.edited
-                    .map(|ts| ts.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-            } else {
-                None
-            },
-        }
-    }
-}
-
 /// Writes messages to a JSONL (JSON Lines) file.
 ///
 /// Each line is a complete, valid JSON object that can be parsed independently.
@@ -97,17 +59,179 @@ pub fn write_jsonl(
     output_path: &str,
     config: &OutputConfig,
 ) -> Result<(), ChatpackError> {
-    let file = File::create(output_path)?;
-    let mut writer = BufWriter::new(file);
+    write_jsonl_iter(messages, output_path, config, OnExists::Overwrite)?;
+    Ok(())
+}
+
+/// Writes messages to a JSONL file as they arrive from `messages`.
+pub(crate) fn write_jsonl_iter<I>(
+    messages: I,
+    output_path: &str,
+    config: &OutputConfig,
+    on_exists: OnExists,
+) -> Result<PathBuf, ChatpackError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Message>,
+{
+    let options = JsonlWriteOptions::new().with_on_exists(on_exists);
+    write_jsonl_iter_with(messages, output_path, config, &options)
+}
+
+/// How [`write_jsonl_with`] opens and flushes its file.
+///
+/// The default truncates the file and flushes once at the end, like
+/// [`write_jsonl`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json-output")]
+/// # fn main() {
+/// use chatpack::core::output::JsonlWriteOptions;
+///
+/// // Append to an existing file and make every line visible to `tail -f`
+/// let options = JsonlWriteOptions::new().with_append(true).with_flush_every(1);
+/// assert_eq!(options.flush_every, Some(1));
+/// # }
+/// # #[cfg(not(feature = "json-output"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonlWriteOptions {
+    /// Append to the file instead of truncating it (default: false).
+    pub append: bool,
+    /// Flush after every `n` lines; `None` flushes only at the end
+    /// (default: `None`). `Some(0)` is treated as `Some(1)`.
+    pub flush_every: Option<usize>,
+    /// What to do when the file exists and `append` is off (default:
+    /// overwrite). Appending always writes to the existing file.
+    pub on_exists: OnExists,
+    /// Also write a [`JsonlIndex`] of every `n`th record next to the file
+    /// (default: `None`). `Some(0)` is treated as `Some(1)`.
+    pub index_every: Option<usize>,
+}
+
+impl JsonlWriteOptions {
+    /// Creates options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    for msg in messages {
-        let json_msg = JsonlMessage::from_message(msg, config);
+    /// Enables or disables append mode.
+    #[must_use]
+    pub fn with_append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Flushes after every `lines` lines.
+    #[must_use]
+    pub fn with_flush_every(mut self, lines: usize) -> Self {
+        self.flush_every = Some(lines);
+        self
+    }
+
+    /// Sets what to do when the file exists and `append` is off.
+    #[must_use]
+    pub fn with_on_exists(mut self, on_exists: OnExists) -> Self {
+        self.on_exists = on_exists;
+        self
+    }
+
+    /// Writes an index of every `records`th record to `<output>.idx`.
+    #[must_use]
+    pub fn with_index_every(mut self, records: usize) -> Self {
+        self.index_every = Some(records);
+        self
+    }
+}
+
+/// Writes messages to a JSONL file with explicit open and flush behavior.
+///
+/// Same format as [`write_jsonl`]. Lines are only flushed whole, so a
+/// reader tailing the file never sees a partial line at a flush point.
+/// Returns the path written, which differs from `output_path` when
+/// [`OnExists::Rename`] picked a new name.
+///
+/// With [`index_every`](JsonlWriteOptions::index_every) set, a
+/// [`JsonlIndex`] is written to the path with `.idx` appended once the
+/// file is complete. An appended-to file is indexed whole, reading its
+/// timestamps back from the records.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::OutputExists`] if the file exists and
+/// `options.on_exists` is [`OnExists::Error`], or [`ChatpackError::Io`] if
+/// the file cannot be opened or written.
+pub fn write_jsonl_with(
+    messages: &[Message],
+    output_path: &str,
+    config: &OutputConfig,
+    options: &JsonlWriteOptions,
+) -> Result<PathBuf, ChatpackError> {
+    write_jsonl_iter_with(messages, output_path, config, options)
+}
+
+/// Writes messages to a JSONL file as they arrive from `messages`, with
+/// explicit open and flush behavior.
+///
+/// The streaming counterpart of [`write_jsonl_with`]: pair it with a
+/// streaming parser to write each message as soon as it is parsed.
+///
+/// # Errors
+///
+/// Same as [`write_jsonl_with`].
+pub fn write_jsonl_iter_with<I>(
+    messages: I,
+    output_path: &str,
+    config: &OutputConfig,
+    options: &JsonlWriteOptions,
+) -> Result<PathBuf, ChatpackError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Message>,
+{
+    let (file, path) = if options.append {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(output_path)?;
+        (file, PathBuf::from(output_path))
+    } else {
+        create_output(output_path, options.on_exists)?
+    };
+    let mut writer = BufWriter::new(file);
+    let flush_every = options.flush_every.map(|n| n.max(1));
+    // Appending starts mid-file, so that index is built from the file after
+    let mut index = options
+        .index_every
+        .filter(|_| !options.append)
+        .map(IndexBuilder::new);
+
+    for (i, json_msg) in OutputRecord::records(messages, config).enumerate() {
         let line = serde_json::to_string(&json_msg)?;
         writeln!(writer, "{line}")?;
+        if let Some(index) = &mut index {
+            index.push(line.len() + 1, json_msg.unix_timestamp);
+        }
+        if flush_every.is_some_and(|n| (i + 1) % n == 0) {
+            writer.flush()?;
+        }
     }
 
     writer.flush()?;
-    Ok(())
+    drop(writer);
+    match (index, options.index_every) {
+        (Some(index), _) => {
+            index.finish(&path).write()?;
+        }
+        (None, Some(every)) => {
+            JsonlIndex::build(&path, every)?.write()?;
+        }
+        (None, None) => {}
+    }
+    Ok(path)
 }
 
 /// Converts messages to a JSONL string.
@@ -140,8 +264,7 @@ pub fn write_jsonl(
 pub fn to_jsonl(messages: &[Message], config: &OutputConfig) -> Result<String, ChatpackError> {
     let mut output = String::new();
 
-    for msg in messages {
-        let json_msg = JsonlMessage::from_message(msg, config);
+    for json_msg in OutputRecord::records(messages, config) {
         let line = serde_json::to_string(&json_msg)?;
         output.push_str(&line);
         output.push('\n');
@@ -150,9 +273,171 @@ pub fn to_jsonl(messages: &[Message], config: &OutputConfig) -> Result<String, C
     Ok(output)
 }
 
+/// Parses JSONL produced by [`to_jsonl`] or [`write_jsonl`] back into messages.
+///
+/// Fields left out by the writer's [`OutputConfig`] come back as `None`.
+/// Blank lines are ignored. Keys this version does not know, such as those
+/// added by a later writer, are ignored too, and lines may carry any
+/// schema version up to [`SCHEMA_VERSION`](models::SCHEMA_VERSION).
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::{from_jsonl, to_jsonl};
+/// use chatpack::prelude::*;
+///
+/// let messages = vec![Message::new("Alice", "Hello").with_id(1)];
+/// let jsonl = to_jsonl(&messages, &OutputConfig::new().with_ids())?;
+///
+/// assert_eq!(from_jsonl(&jsonl)?, messages);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json-output"))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::InvalidFormat`] naming the first line that is
+/// not a valid message object or has an unknown schema version.
+pub fn from_jsonl(content: &str) -> Result<Vec<Message>, ChatpackError> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let invalid = |reason: String| {
+                ChatpackError::invalid_format("chatpack JSONL", format!("line {}: {reason}", i + 1))
+            };
+            let record: InputRecord =
+                serde_json::from_str(line).map_err(|e| invalid(e.to_string()))?;
+            record.into_message().map_err(invalid)
+        })
+        .collect()
+}
+
+/// Reads a JSONL file written by [`write_jsonl`].
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be read, or
+/// [`ChatpackError::InvalidFormat`] as for [`from_jsonl`].
+pub fn read_jsonl(path: impl AsRef<std::path::Path>) -> Result<Vec<Message>, ChatpackError> {
+    from_jsonl(&std::fs::read_to_string(path)?)
+}
+
+/// One line of context JSONL.
+///
+/// The schema version is written once per line, not on the nested messages.
+#[derive(Serialize)]
+struct JsonlContextWindow {
+    #[serde(rename = "_v", skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    context: Vec<OutputRecord>,
+    message: OutputRecord,
+}
+
+impl JsonlContextWindow {
+    fn from_window(window: &ContextWindow, config: &OutputConfig) -> Self {
+        let nested = |msg: &Message| OutputRecord {
+            version: None,
+            ..OutputRecord::from_message(msg, config)
+        };
+        Self {
+            version: config
+                .include_schema_version
+                .then_some(models::SCHEMA_VERSION),
+            context: window.context.iter().map(nested).collect(),
+            message: nested(&window.target),
+        }
+    }
+}
+
+/// Writes context windows to a JSONL file, one window per line.
+///
+/// Each message is written like [`write_jsonl`] writes it, honoring
+/// [`OutputConfig`]. Accepts a slice or a lazy iterator, so pairing this
+/// with [`ContextWindows`](crate::core::processor::ContextWindows) over a
+/// streaming parser keeps memory bounded.
+///
+/// # Format
+///
+/// ```text
+/// {"context":[],"message":{"sender":"Alice","content":"Hi"}}
+/// {"context":[{"sender":"Alice","content":"Hi"}],"message":{"sender":"Bob","content":"Hello"}}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::Io`] if the file cannot be created or written.
+pub fn write_context_jsonl<I>(
+    windows: I,
+    output_path: &str,
+    config: &OutputConfig,
+) -> Result<(), ChatpackError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<ContextWindow>,
+{
+    let file = File::create(output_path)?;
+    let mut writer = BufWriter::new(file);
+
+    for window in windows {
+        let json_window = JsonlContextWindow::from_window(window.borrow(), config);
+        let line = serde_json::to_string(&json_window)?;
+        writeln!(writer, "{line}")?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Converts context windows to a JSONL string.
+///
+/// Same format as [`write_context_jsonl`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::to_context_jsonl;
+/// use chatpack::core::processor::with_context;
+/// use chatpack::prelude::*;
+///
+/// let messages = vec![Message::new("Alice", "Hi"), Message::new("Bob", "Hello")];
+/// let jsonl = to_context_jsonl(&with_context(&messages, 3), &OutputConfig::new())?;
+///
+/// let last = jsonl.lines().last().unwrap();
+/// assert!(last.starts_with(r#"{"context":[{"sender":"Alice""#));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json-output"))]
+/// # fn main() {}
+/// ```
+pub fn to_context_jsonl<I>(windows: I, config: &OutputConfig) -> Result<String, ChatpackError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<ContextWindow>,
+{
+    let mut output = String::new();
+
+    for window in windows {
+        let json_window = JsonlContextWindow::from_window(window.borrow(), config);
+        let line = serde_json::to_string(&json_window)?;
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::models::SCHEMA_VERSION;
     use std::io::{BufRead, BufReader};
     use tempfile::NamedTempFile;
 
@@ -246,4 +531,145 @@ mod tests {
         assert!(!content.contains('['));
         assert!(!content.contains(']'));
     }
+
+    #[test]
+    fn test_write_jsonl_flush_every_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.jsonl");
+        let path = path.to_str().unwrap();
+        let options = JsonlWriteOptions::new().with_flush_every(1);
+
+        // Read the file back before yielding each message after the first
+        let mut seen = Vec::new();
+        let messages = (0..4).map(|i| {
+            if i > 0 {
+                let content = std::fs::read_to_string(path).unwrap();
+                assert!(content.ends_with('\n'));
+                seen.push(from_jsonl(&content).unwrap().len());
+            }
+            Message::new("Alice", format!("line {i}"))
+        });
+        write_jsonl_iter_with(messages, path, &OutputConfig::new(), &options).unwrap();
+
+        assert_eq!(seen, [1, 2, 3]);
+        assert_eq!(read_jsonl(path).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_write_jsonl_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.jsonl");
+        let path = path.to_str().unwrap();
+        let config = OutputConfig::new();
+        let append = JsonlWriteOptions::new().with_append(true);
+
+        write_jsonl_with(&[Message::new("Alice", "one")], path, &config, &append).unwrap();
+        write_jsonl_with(&[Message::new("Bob", "two")], path, &config, &append).unwrap();
+        assert_eq!(read_jsonl(path).unwrap().len(), 2);
+
+        let truncate = JsonlWriteOptions::default();
+        write_jsonl_with(&[Message::new("Carol", "three")], path, &config, &truncate).unwrap();
+        assert_eq!(read_jsonl(path).unwrap(), [Message::new("Carol", "three")]);
+    }
+
+    #[test]
+    fn test_from_jsonl_round_trip() {
+        let ts = chrono::DateTime::from_timestamp(1_705_314_600, 0);
+        let messages = vec![
+            Message::with_metadata("Alice", "Hi\n\"there\"", ts, Some(1), None, ts),
+            Message::with_metadata("them", "Reply", ts, Some(2), Some(1), None)
+                .with_original_sender("Bob"),
+        ];
+        let jsonl = to_jsonl(&messages, &OutputConfig::all().with_metadata()).unwrap();
+
+        assert_eq!(from_jsonl(&jsonl).unwrap(), messages);
+        assert_eq!(from_jsonl(&format!("\n{jsonl}\n")).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let messages = vec![Message::new("Alice", "Hi"), Message::new("Bob", "Hello")];
+        let config = OutputConfig::new().with_schema_version();
+
+        let jsonl = to_jsonl(&messages, &config).unwrap();
+        assert!(
+            jsonl
+                .lines()
+                .all(|line| line.starts_with(r#"{"_v":1,"sender":"#))
+        );
+        assert_eq!(from_jsonl(&jsonl).unwrap(), messages);
+
+        let windows = crate::core::processor::with_context(&messages, 1);
+        let context = to_context_jsonl(&windows, &config).unwrap();
+        assert_eq!(
+            context.lines().last().unwrap(),
+            r#"{"_v":1,"context":[{"sender":"Alice","content":"Hi"}],"message":{"sender":"Bob","content":"Hello"}}"#
+        );
+    }
+
+    #[test]
+    fn test_from_jsonl_versions_and_unknown_keys() {
+        let jsonl = concat!(
+            r#"{"sender":"A","content":"x"}"#,
+            "\n",
+            r#"{"_v":1,"sender":"B","content":"y","channel":"general","segments":[1]}"#,
+            "\n",
+        );
+        assert_eq!(
+            from_jsonl(jsonl).unwrap(),
+            [Message::new("A", "x"), Message::new("B", "y")]
+        );
+
+        for version in [0, SCHEMA_VERSION + 1] {
+            let line = format!(r#"{{"_v":{version},"sender":"A","content":"x"}}"#);
+            let err = from_jsonl(&line).unwrap_err().to_string();
+            assert!(err.contains("unsupported schema version"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_from_jsonl_reports_line() {
+        let err = from_jsonl("{\"sender\":\"A\",\"content\":\"x\"}\nnot json\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_context_jsonl_format() {
+        let messages = vec![
+            Message::new("Alice", "Hi").with_id(1),
+            Message::new("Bob", "Hello").with_id(2),
+            Message::new("Alice", "Bye").with_id(3),
+        ];
+        let windows = crate::core::processor::with_context(&messages, 1);
+
+        let jsonl = to_context_jsonl(&windows, &OutputConfig::new().with_ids()).unwrap();
+        let lines: Vec<&str> = jsonl.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            r#"{"context":[],"message":{"id":1,"sender":"Alice","content":"Hi"}}"#
+        );
+        assert_eq!(
+            lines[2],
+            r#"{"context":[{"id":2,"sender":"Bob","content":"Hello"}],"message":{"id":3,"sender":"Alice","content":"Bye"}}"#
+        );
+    }
+
+    #[test]
+    fn test_write_context_jsonl_from_iterator() {
+        use crate::core::processor::ContextWindows;
+
+        let messages = vec![Message::new("Alice", "Hi"), Message::new("Bob", "Hello")];
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_str().unwrap();
+
+        write_context_jsonl(ContextWindows::new(messages, 4), path, &OutputConfig::new()).unwrap();
+
+        let content = std::fs::read_to_string(path).unwrap();
+        let last: serde_json::Value =
+            serde_json::from_str(content.lines().last().unwrap()).unwrap();
+        assert_eq!(last["context"][0]["sender"], "Alice");
+        assert_eq!(last["message"]["content"], "Hello");
+        assert!(last["message"].get("timestamp").is_none());
+    }
 }
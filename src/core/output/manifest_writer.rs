@@ -0,0 +1,231 @@
+//! Attachment manifest writer.
+//!
+//! Lists every [`AttachmentRef`](crate::AttachmentRef) in a set of messages
+//! together with the sender, timestamp, and ID of the message that
+//! references it, so tooling can copy the media alongside a text export.
+
+use serde::Serialize;
+
+use crate::Message;
+use crate::error::ChatpackError;
+use crate::format::OutputFormat;
+
+/// One manifest row: an attachment and the message it belongs to.
+#[derive(Serialize)]
+struct ManifestEntry<'a> {
+    file_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+    sender: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_id: Option<u64>,
+}
+
+fn entries(messages: &[Message]) -> impl Iterator<Item = ManifestEntry<'_>> {
+    messages.iter().flat_map(|msg| {
+        msg.attachments.iter().map(move |att| ManifestEntry {
+            file_name: &att.file_name,
+            path: att.path.as_deref(),
+            sender: &msg.sender,
+            timestamp: msg
+                .timestamp
+                .map(|ts| ts.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+            message_id: msg.id,
+        })
+    })
+}
+
+/// Writes the attachment manifest of `messages` to a file.
+///
+/// The format follows the extension, as with [`OutputFormat::from_path`]:
+/// `.csv` writes a semicolon-delimited table with the columns `FileName`,
+/// `Path`, `Sender`, `Timestamp`, and `MessageID`; `.json` writes an array
+/// of objects and `.jsonl` one object per line, with empty fields omitted.
+///
+/// Messages only carry attachments when the parser collected them, e.g.
+/// with [`DiscordConfig::with_collect_attachment_refs`](crate::config::DiscordConfig::with_collect_attachment_refs).
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(all(feature = "discord-json", feature = "csv-output"))]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::config::DiscordConfig;
+/// use chatpack::core::output::write_attachment_manifest;
+/// use chatpack::parser::Parser;
+/// use chatpack::parsers::DiscordParser;
+///
+/// let config = DiscordConfig::new().with_collect_attachment_refs(true);
+/// let messages = DiscordParser::with_config(config).parse("channel.json".as_ref())?;
+///
+/// write_attachment_manifest(&messages, "attachments.csv")?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(all(feature = "discord-json", feature = "csv-output")))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::InvalidFormat`] for an unknown extension or a
+/// format whose feature is disabled, and [`ChatpackError::Io`] if the file
+/// cannot be written.
+pub fn write_attachment_manifest(messages: &[Message], path: &str) -> Result<(), ChatpackError> {
+    let format = OutputFormat::from_path(path)?;
+    let manifest = to_attachment_manifest(messages, format)?;
+    std::fs::write(path, manifest)?;
+    Ok(())
+}
+
+/// Converts the attachment manifest of `messages` to a string.
+///
+/// Same formats as [`write_attachment_manifest`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "csv-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::to_attachment_manifest;
+/// use chatpack::format::OutputFormat;
+/// use chatpack::{AttachmentRef, Message};
+///
+/// let messages = vec![
+///     Message::new("Alice", "[Attachment: cat.png]")
+///         .with_id(7)
+///         .with_attachment(AttachmentRef::from_path("photos/cat.png")),
+///     Message::new("Bob", "Cute!"),
+/// ];
+///
+/// let csv = to_attachment_manifest(&messages, OutputFormat::Csv)?;
+/// assert_eq!(
+///     csv,
+///     "FileName;Path;Sender;Timestamp;MessageID\ncat.png;photos/cat.png;Alice;;7\n"
+/// );
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "csv-output"))]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::UnsupportedFormat`] if the format's feature is
+/// disabled.
+#[allow(unused_variables)]
+pub fn to_attachment_manifest(
+    messages: &[Message],
+    format: OutputFormat,
+) -> Result<String, ChatpackError> {
+    match format {
+        #[cfg(feature = "csv-output")]
+        OutputFormat::Csv => to_csv_manifest(messages),
+        #[cfg(feature = "json-output")]
+        OutputFormat::Json => {
+            let entries: Vec<_> = entries(messages).collect();
+            Ok(serde_json::to_string_pretty(&entries)?)
+        }
+        #[cfg(feature = "json-output")]
+        OutputFormat::Jsonl => {
+            let mut output = String::new();
+            for entry in entries(messages) {
+                output.push_str(&serde_json::to_string(&entry)?);
+                output.push('\n');
+            }
+            Ok(output)
+        }
+        OutputFormat::HfText => Err(ChatpackError::unsupported_operation(
+            "attachment manifests",
+            "HF text output",
+        )),
+        #[allow(unreachable_patterns)]
+        _ => Err(ChatpackError::unsupported_format(format)),
+    }
+}
+
+#[cfg(feature = "csv-output")]
+fn to_csv_manifest(messages: &[Message]) -> Result<String, ChatpackError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .from_writer(Vec::new());
+
+    writer.write_record(["FileName", "Path", "Sender", "Timestamp", "MessageID"])?;
+    for entry in entries(messages) {
+        writer.write_record([
+            entry.file_name,
+            entry.path.unwrap_or_default(),
+            entry.sender,
+            entry.timestamp.as_deref().unwrap_or_default(),
+            &entry
+                .message_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+        ])?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AttachmentRef;
+
+    fn messages() -> Vec<Message> {
+        let ts = chrono::DateTime::from_timestamp(1_705_314_600, 0).unwrap();
+        vec![
+            Message::new("Alice", "Look")
+                .with_id(1)
+                .with_timestamp(ts)
+                .with_attachment(AttachmentRef::from_path("photos/a.jpg"))
+                .with_attachment(AttachmentRef::new("missing.mp4")),
+            Message::new("Bob", "Nice").with_id(2),
+        ]
+    }
+
+    #[test]
+    #[cfg(feature = "csv-output")]
+    fn test_csv_manifest() {
+        let csv = to_attachment_manifest(&messages(), OutputFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "a.jpg;photos/a.jpg;Alice;2024-01-15T10:30:00Z;1");
+        assert_eq!(lines[2], "missing.mp4;;Alice;2024-01-15T10:30:00Z;1");
+    }
+
+    #[test]
+    #[cfg(feature = "json-output")]
+    fn test_json_manifest() {
+        let json = to_attachment_manifest(&messages(), OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value.as_array().unwrap().len(), 2);
+        assert_eq!(value[0]["path"], "photos/a.jpg");
+        assert_eq!(value[0]["message_id"], 1);
+        assert!(value[1].get("path").is_none());
+
+        let jsonl = to_attachment_manifest(&messages(), OutputFormat::Jsonl).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "json-output")]
+    fn test_write_manifest_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("attachments.json");
+        let path = path.to_str().unwrap();
+
+        write_attachment_manifest(&messages(), path).unwrap();
+
+        let value: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(value[0]["file_name"], "a.jpg");
+        assert!(write_attachment_manifest(&messages(), "attachments.txt").is_err());
+    }
+}
@@ -10,6 +10,9 @@
 //! | CSV | [`write_csv`] / [`to_csv`] | `csv-output` | LLM context (13x compression) |
 //! | JSON | [`write_json`] / [`to_json`] | `json-output` | APIs, structured data |
 //! | JSONL | [`write_jsonl`] / [`to_jsonl`] | `json-output` | RAG pipelines, streaming |
+//! | Telegram JSON | [`write_telegram_json`] / [`to_telegram_json`] | `json-output` + `telegram` | Re-import into Telegram tooling |
+//! | Discord JSON | [`write_discord_json`] / [`to_discord_json`] | `json-output` + `discord` | Re-import into Discord tooling |
+//! | HF text | [`write_hf_text`] / [`to_hf_text`] | `json-output` | Hugging Face `datasets` text corpora |
 //!
 //! # Examples
 //!
@@ -52,21 +55,140 @@
 //! # fn main() {}
 //! ```
 //!
+//! JSON and JSONL records have the same keys in the same order, listed in
+//! [`RECORD_FIELDS`], which follows the CSV columns. A missing value is
+//! left out rather than written as `null`.
+//!
+//! [`from_csv`], [`from_json`], and [`from_jsonl`] (or [`read_csv`],
+//! [`read_json`], and [`read_jsonl`] for files) parse the output back into
+//! messages, with the fields it left out as `None`.
+//!
+//! Before handing output to line- or delimiter-based tools, check it with
+//! [`validate_for_format`].
+//!
+//! For `tail -f` consumers and append workflows, [`write_jsonl_with`] and
+//! [`write_jsonl_iter_with`] take [`JsonlWriteOptions`] to append instead of
+//! truncating and to flush every few lines. Their
+//! [`index_every`](JsonlWriteOptions::index_every) option also writes a
+//! sidecar [`JsonlIndex`] so consumers of a large file can seek to a point
+//! in time instead of reading from the start.
+//!
+//! The plain `write_*` functions replace an existing file. To refuse or
+//! write next to it instead, pass [`WriteOptions`] with an [`OnExists`]
+//! policy to [`write_csv_with`], [`write_json_with`], or
+//! [`write_to_format_with`](crate::format::write_to_format_with), or set
+//! [`JsonlWriteOptions::on_exists`]. Conversions take an [`OnEmpty`]
+//! policy for when no messages are left to write.
+//!
+//! For a quick look before a long conversion, [`render_preview`] lays the
+//! first messages out as aligned, wrapped text for a terminal (`preview`
+//! feature). It can color each sender and tag them with an emoji; the
+//! same assignments are available to other human-readable outputs from
+//! [`sender_color`] and [`sender_emoji`], which give a sender the same color
+//! and emoji in every run.
+//!
+//! JSONL output can be read back with [`read_jsonl`] / [`from_jsonl`], for
+//! example to [diff](crate::core::diff) it against a later run.
+//!
+//! To pin consumers to a record schema, enable
+//! [`OutputConfig::with_schema_version`](crate::core::models::OutputConfig::with_schema_version):
+//! every JSON and JSONL record then starts with a `"_v"` key (see
+//! [schema versions](crate::core::models#schema-versions)).
+//!
+//! # Determinism
+//!
+//! Writers produce byte-identical output for identical input: JSON keys
+//! follow struct field order and nothing is serialized from a hash map.
+//! Keyed data that ends up in output must use `BTreeMap` or be sorted
+//! first. `tests/snapshots.rs` pins the exact output of every writer.
+//!
 //! # Feature Flags
 //!
 //! - `csv-output`: Enables CSV functions ([`write_csv`], [`to_csv`])
 //! - `json-output`: Enables JSON functions ([`write_json`], [`to_json`], [`write_jsonl`], [`to_jsonl`])
+//!   and the HF text writers ([`write_hf_text`], [`to_hf_text`], [`write_hf_conversations`],
+//!   [`to_hf_conversations`])
+//! - `json-output` + `telegram` / `discord`: Enables the platform-shaped export writers
+//! - `csv-output` or `json-output`: Enables the attachment manifest writer
+//! - `csv-output`: Enables the activity table writer ([`write_aggregate_csv`], [`to_aggregate_csv`])
+//! - `preview`: Enables [`render_preview`]
+//! - `async` + `csv-output` / `json-output`: Enables the async writers
+//!   (`write_csv_async`, `write_json_async`, `write_jsonl_async`,
+//!   `write_jsonl_stream_async`)
 
+#[cfg(feature = "csv-output")]
+mod aggregate_writer;
+#[cfg(all(
+    feature = "async",
+    any(feature = "csv-output", feature = "json-output")
+))]
+mod async_writer;
+#[cfg(any(feature = "csv-output", feature = "json-output"))]
+mod boundary;
 #[cfg(feature = "csv-output")]
 mod csv_writer;
+#[cfg(all(
+    feature = "json-output",
+    any(feature = "telegram", feature = "discord-json")
+))]
+mod export_writer;
+#[cfg(feature = "json-output")]
+mod hf_writer;
 #[cfg(feature = "json-output")]
 mod json_writer;
 #[cfg(feature = "json-output")]
+mod jsonl_index;
+#[cfg(feature = "json-output")]
 mod jsonl_writer;
+#[cfg(any(feature = "csv-output", feature = "json-output"))]
+mod manifest_writer;
+#[cfg(any(feature = "csv-output", feature = "json-output"))]
+mod order;
+mod palette;
+#[cfg(feature = "preview")]
+mod preview;
+#[cfg(feature = "json-output")]
+mod record;
+mod target;
+mod validate;
 
 #[cfg(feature = "csv-output")]
-pub use csv_writer::{to_csv, write_csv};
+pub use aggregate_writer::{to_aggregate_csv, write_aggregate_csv};
+#[cfg(all(feature = "async", feature = "csv-output"))]
+pub use async_writer::write_csv_async;
+#[cfg(all(feature = "async", feature = "json-output"))]
+pub use async_writer::{write_json_async, write_jsonl_async, write_jsonl_stream_async};
+#[cfg(feature = "csv-output")]
+pub(crate) use csv_writer::write_csv_iter;
+#[cfg(feature = "csv-output")]
+pub use csv_writer::{from_csv, read_csv, to_csv, write_csv, write_csv_with};
+#[cfg(all(feature = "json-output", feature = "discord-json"))]
+pub use export_writer::{to_discord_json, write_discord_json};
+#[cfg(all(feature = "json-output", feature = "telegram"))]
+pub use export_writer::{to_telegram_json, write_telegram_json};
+#[cfg(feature = "json-output")]
+pub(crate) use hf_writer::write_hf_text_iter;
+#[cfg(feature = "json-output")]
+pub use hf_writer::{to_hf_conversations, to_hf_text, write_hf_conversations, write_hf_text};
+#[cfg(feature = "json-output")]
+pub(crate) use json_writer::write_json_iter;
+#[cfg(feature = "json-output")]
+pub use json_writer::{from_json, read_json, to_json, write_json, write_json_with};
+#[cfg(feature = "json-output")]
+pub use jsonl_index::{IndexEntry, JsonlIndex, index_path};
+#[cfg(feature = "json-output")]
+pub(crate) use jsonl_writer::write_jsonl_iter;
 #[cfg(feature = "json-output")]
-pub use json_writer::{to_json, write_json};
+pub use jsonl_writer::{
+    JsonlWriteOptions, from_jsonl, read_jsonl, to_context_jsonl, to_jsonl, write_context_jsonl,
+    write_jsonl, write_jsonl_iter_with, write_jsonl_with,
+};
+#[cfg(any(feature = "csv-output", feature = "json-output"))]
+pub use manifest_writer::{to_attachment_manifest, write_attachment_manifest};
+pub use palette::{EMOJI, PALETTE, SenderColor, color_enabled, sender_color, sender_emoji};
+#[cfg(feature = "preview")]
+pub use preview::{PreviewOptions, render_preview};
 #[cfg(feature = "json-output")]
-pub use jsonl_writer::{to_jsonl, write_jsonl};
+pub use record::RECORD_FIELDS;
+pub use target::{OnEmpty, OnExists, WriteOptions};
+pub use validate::{MAX_FIELD_LEN, ValidationIssue, ValidationWarning, validate_for_format};
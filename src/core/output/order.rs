@@ -0,0 +1,109 @@
+//! Output ordering shared by the writers.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+use crate::Message;
+use crate::core::models::SortKey;
+
+/// Messages in the order a writer emits them.
+pub(crate) enum Ordered<I: Iterator> {
+    /// Input order, passed through without buffering.
+    AsGiven(I),
+    /// The buffered input after a stable sort.
+    Sorted(std::vec::IntoIter<I::Item>),
+}
+
+impl<I: Iterator> Iterator for Ordered<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Ordered::AsGiven(iter) => iter.next(),
+            Ordered::Sorted(iter) => iter.next(),
+        }
+    }
+}
+
+/// Orders `messages` by `key`.
+///
+/// [`SortKey::Chronological`] keeps input order and stays lazy. Other keys
+/// buffer the items, which are references for slice input, and sort them.
+pub(crate) fn ordered<I>(messages: I, key: SortKey) -> Ordered<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Message>,
+{
+    if key == SortKey::Chronological {
+        return Ordered::AsGiven(messages.into_iter());
+    }
+    let mut items: Vec<I::Item> = messages.into_iter().collect();
+    items.sort_by(|a, b| compare(a.borrow(), b.borrow(), key));
+    Ordered::Sorted(items.into_iter())
+}
+
+fn compare(a: &Message, b: &Message, key: SortKey) -> Ordering {
+    let by_sender = a.sender.cmp(&b.sender);
+    match key {
+        SortKey::Chronological => Ordering::Equal,
+        SortKey::SenderThenTime => by_sender.then_with(|| a.cmp_chronological(b)),
+        SortKey::SenderThenTimeDescending => by_sender
+            .then_with(|| b.timestamp.cmp(&a.timestamp))
+            .then_with(|| a.source_index.cmp(&b.source_index)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn sample() -> Vec<Message> {
+        let at = |minute| Utc.with_ymd_and_hms(2024, 1, 15, 10, minute, 0).unwrap();
+        vec![
+            Message::new("Bob", "b1").with_timestamp(at(0)),
+            Message::new("Alice", "a1").with_timestamp(at(1)),
+            Message::new("Bob", "b2").with_timestamp(at(2)),
+            Message::new("Alice", "a2").with_timestamp(at(2)),
+            Message::new("Alice", "a3").with_timestamp(at(2)),
+        ]
+    }
+
+    fn contents(messages: &[Message], key: SortKey) -> Vec<&str> {
+        ordered(messages, key).map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_chronological_keeps_input_order() {
+        let messages = sample();
+        assert_eq!(
+            contents(&messages, SortKey::Chronological),
+            ["b1", "a1", "b2", "a2", "a3"]
+        );
+    }
+
+    #[test]
+    fn test_sender_then_time() {
+        let messages = sample();
+        assert_eq!(
+            contents(&messages, SortKey::SenderThenTime),
+            ["a1", "a2", "a3", "b1", "b2"]
+        );
+        // Ties keep input order in both directions
+        assert_eq!(
+            contents(&messages, SortKey::SenderThenTimeDescending),
+            ["a2", "a3", "a1", "b2", "b1"]
+        );
+    }
+
+    #[test]
+    fn test_source_index_breaks_ties() {
+        let mut messages = sample();
+        messages[3].source_index = Some(9);
+        messages[4].source_index = Some(8);
+        assert_eq!(
+            contents(&messages, SortKey::SenderThenTime),
+            ["a1", "a3", "a2", "b1", "b2"]
+        );
+    }
+}
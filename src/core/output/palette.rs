@@ -0,0 +1,213 @@
+//! Deterministic per-sender colors and emoji for human-readable output.
+//!
+//! [`sender_color`] and [`sender_emoji`] pick from fixed tables by a
+//! SipHash of the sender's [folded](crate::core::textutil::fold_sender)
+//! name, so a sender keeps the same color and emoji across runs, platforms,
+//! and chatpack versions, and spellings that only differ in case or
+//! invisible characters share them. Changing the tables or the key changes
+//! every assignment.
+
+use std::ffi::OsString;
+
+use crate::core::hash::sip_hash;
+use crate::core::textutil::fold_sender;
+
+/// A color of [`PALETTE`], in the forms terminals and HTML take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SenderColor {
+    /// Lowercase name, such as `"blue"`.
+    pub name: &'static str,
+    /// CSS hex color, such as `"#0087ff"`.
+    pub hex: &'static str,
+    /// Index in the 256-color ANSI palette with the same color as
+    /// [`hex`](Self::hex).
+    pub ansi: u8,
+}
+
+impl SenderColor {
+    /// Returns the ANSI escape code that sets this as the foreground color.
+    ///
+    /// ```
+    /// use chatpack::core::output::PALETTE;
+    ///
+    /// assert_eq!(PALETTE[0].ansi_fg(), "\x1b[38;5;33m");
+    /// ```
+    pub fn ansi_fg(&self) -> String {
+        format!("\x1b[38;5;{}m", self.ansi)
+    }
+}
+
+/// Colors [`sender_color`] picks from: mid-tone hues that read on light
+/// and dark backgrounds, each exactly one of the 256 ANSI colors.
+pub const PALETTE: [SenderColor; 8] = [
+    SenderColor {
+        name: "blue",
+        hex: "#0087ff",
+        ansi: 33,
+    },
+    SenderColor {
+        name: "orange",
+        hex: "#d75f00",
+        ansi: 166,
+    },
+    SenderColor {
+        name: "green",
+        hex: "#00af00",
+        ansi: 34,
+    },
+    SenderColor {
+        name: "raspberry",
+        hex: "#d7005f",
+        ansi: 161,
+    },
+    SenderColor {
+        name: "purple",
+        hex: "#875fd7",
+        ansi: 98,
+    },
+    SenderColor {
+        name: "teal",
+        hex: "#00afaf",
+        ansi: 37,
+    },
+    SenderColor {
+        name: "ochre",
+        hex: "#af8700",
+        ansi: 136,
+    },
+    SenderColor {
+        name: "pink",
+        hex: "#d75f87",
+        ansi: 168,
+    },
+];
+
+/// Emoji [`sender_emoji`] picks from.
+pub const EMOJI: [&str; 16] = [
+    "🦊", "🐻", "🐼", "🐨", "🐯", "🦁", "🐸", "🐵", "🐧", "🦉", "🐙", "🦋", "🐢", "🦄", "🐝", "🐳",
+];
+
+/// SipHash key of sender assignments: the ASCII bytes of `chatpack` and
+/// `senders1`, each read as a little-endian `u64`.
+const KEY: (u64, u64) = (
+    u64::from_le_bytes(*b"chatpack"),
+    u64::from_le_bytes(*b"senders1"),
+);
+
+/// Returns the [`PALETTE`] color of `sender`.
+///
+/// ```
+/// use chatpack::core::output::sender_color;
+///
+/// assert_eq!(sender_color("Alice"), sender_color("ALICE\u{200B}"));
+/// ```
+pub fn sender_color(sender: &str) -> SenderColor {
+    PALETTE[pick(sender, PALETTE.len(), 0)]
+}
+
+/// Returns the [`EMOJI`] of `sender`.
+///
+/// Chosen independently of [`sender_color`], so two senders sharing a
+/// color usually still differ in emoji.
+///
+/// ```
+/// use chatpack::core::output::sender_emoji;
+///
+/// assert_eq!(sender_emoji("Alice"), sender_emoji("alice"));
+/// ```
+pub fn sender_emoji(sender: &str) -> &'static str {
+    EMOJI[pick(sender, EMOJI.len(), 32)]
+}
+
+/// Returns whether to write ANSI colors to a stream, given whether it is a
+/// terminal.
+///
+/// Colors are off when the stream is not a terminal, or when the
+/// [`NO_COLOR`](https://no-color.org) environment variable is set to
+/// anything but an empty string.
+///
+/// ```no_run
+/// use std::io::IsTerminal;
+///
+/// use chatpack::core::output::color_enabled;
+///
+/// let color = color_enabled(std::io::stderr().is_terminal());
+/// ```
+pub fn color_enabled(is_terminal: bool) -> bool {
+    color_enabled_with(is_terminal, std::env::var_os("NO_COLOR"))
+}
+
+fn color_enabled_with(is_terminal: bool, no_color: Option<OsString>) -> bool {
+    is_terminal && no_color.is_none_or(|value| value.is_empty())
+}
+
+/// Returns an index below `len` from the 32 bits of `sender`'s hash
+/// starting at bit `shift`.
+fn pick(sender: &str, len: usize, shift: u32) -> usize {
+    let [hash, _] = sip_hash(KEY, fold_sender(sender).as_bytes(), false);
+    ((hash >> shift) & 0xffff_ffff) as usize % len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignments_are_pinned() {
+        // These must not change between versions
+        let colors: Vec<&str> = ["Alice", "Bob", "Carol", "Алия", "🎉"]
+            .iter()
+            .map(|s| sender_color(s).name)
+            .collect();
+        assert_eq!(colors, ["pink", "blue", "blue", "blue", "pink"]);
+        let emoji: Vec<&str> = ["Alice", "Bob", "Carol", "Алия", "🎉"]
+            .iter()
+            .map(|s| sender_emoji(s))
+            .collect();
+        assert_eq!(emoji, ["🐻", "🦄", "🐻", "🦁", "🐢"]);
+    }
+
+    #[test]
+    fn test_assignments_follow_folded_name() {
+        for (a, b) in [
+            ("Alice", "alice"),
+            ("Алия\u{200B}", "АЛИЯ"),
+            ("Bob", "\u{200F}BOB"),
+        ] {
+            assert_eq!(sender_color(a), sender_color(b));
+            assert_eq!(sender_emoji(a), sender_emoji(b));
+        }
+    }
+
+    #[test]
+    fn test_assignments_spread() {
+        let names: Vec<String> = (0..200).map(|i| format!("user{i}")).collect();
+        for color in PALETTE {
+            assert!(names.iter().any(|n| sender_color(n) == color), "{color:?}");
+        }
+        for emoji in EMOJI {
+            assert!(names.iter().any(|n| sender_emoji(n) == emoji), "{emoji}");
+        }
+    }
+
+    #[test]
+    fn test_palette_hex_matches_ansi() {
+        const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        for color in PALETTE {
+            let cube = color.ansi - 16;
+            let rgb = [cube / 36, cube / 6 % 6, cube % 6].map(|i| LEVELS[usize::from(i)]);
+            assert_eq!(
+                color.hex,
+                format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+            );
+        }
+    }
+
+    #[test]
+    fn test_color_enabled_respects_no_color() {
+        assert!(color_enabled_with(true, None));
+        assert!(color_enabled_with(true, Some(OsString::new())));
+        assert!(!color_enabled_with(true, Some("1".into())));
+        assert!(!color_enabled_with(false, None));
+    }
+}
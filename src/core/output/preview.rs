@@ -0,0 +1,394 @@
+//! Human-readable terminal preview of messages.
+//!
+//! [`render_preview`] lays the first few messages out in aligned columns:
+//! timestamp, sender padded to the widest shown sender, then the content
+//! word-wrapped to the terminal width. Widths are measured in terminal
+//! columns, so CJK text and emoji take two.
+
+use chrono::{DateTime, Utc};
+use unicode_width::UnicodeWidthStr;
+
+use crate::Message;
+use crate::core::output::{sender_color, sender_emoji};
+use crate::core::textutil::split_graphemes;
+
+/// Narrowest content column; a narrower terminal wraps past its edge
+/// rather than one character per line.
+const MIN_CONTENT_WIDTH: usize = 20;
+
+/// Timestamp format of the first column.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Width of a timestamp in [`TIMESTAMP_FORMAT`].
+const TIMESTAMP_FORMAT_WIDTH: usize = "2024-01-15 10:30".len();
+
+/// Gap between columns.
+const GAP: &str = "  ";
+
+/// How [`render_preview`] lays messages out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewOptions {
+    /// Total line width in terminal columns (default: 80).
+    pub width: usize,
+
+    /// Render at most this many messages (default: all).
+    pub limit: Option<usize>,
+
+    /// Widest sender column; longer names are cut with `…` (default: 20).
+    pub max_sender_width: usize,
+
+    /// Show a timestamp column (default: `true`).
+    pub timestamps: bool,
+
+    /// Dim timestamps with ANSI escape codes (default: `false`).
+    pub color: bool,
+
+    /// Color each sender's name with its [`sender_color`], using ANSI
+    /// escape codes (default: `false`).
+    pub color_senders: bool,
+
+    /// Put each sender's [`sender_emoji`] before their name (default:
+    /// `false`).
+    pub sender_emoji: bool,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            limit: None,
+            max_sender_width: 20,
+            timestamps: true,
+            color: false,
+            color_senders: false,
+            sender_emoji: false,
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Creates options with the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the total line width.
+    #[must_use]
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Renders at most `limit` messages.
+    #[must_use]
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the widest sender column.
+    #[must_use]
+    pub fn with_max_sender_width(mut self, width: usize) -> Self {
+        self.max_sender_width = width;
+        self
+    }
+
+    /// Shows or hides the timestamp column.
+    #[must_use]
+    pub fn with_timestamps(mut self, timestamps: bool) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Dims timestamps with ANSI escape codes.
+    #[must_use]
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Colors each sender's name with ANSI escape codes.
+    ///
+    /// Pass [`color_enabled`](crate::core::output::color_enabled) to color
+    /// only terminals that have not opted out with `NO_COLOR`.
+    #[must_use]
+    pub fn with_color_senders(mut self, color: bool) -> Self {
+        self.color_senders = color;
+        self
+    }
+
+    /// Puts each sender's emoji before their name.
+    #[must_use]
+    pub fn with_sender_emoji(mut self, emoji: bool) -> Self {
+        self.sender_emoji = emoji;
+        self
+    }
+}
+
+/// Renders messages as aligned, wrapped text for a terminal.
+///
+/// Each message starts a new line with its timestamp and sender; its
+/// content wraps at word boundaries, or between grapheme clusters inside
+/// a word too long for a line. Line breaks inside the content are kept.
+/// Control characters in senders and content are shown escaped (`\t`,
+/// `\u{1b}`) so they cannot move the cursor or change colors, and so are
+/// bidi overrides and isolates (`\u{202e}`), which would reorder the rest
+/// of the line. When
+/// [`limit`](PreviewOptions::limit) leaves messages out, a last line says
+/// how many.
+///
+/// Never panics, whatever the content or options.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::Message;
+/// use chatpack::core::output::{PreviewOptions, render_preview};
+///
+/// let messages = vec![
+///     Message::new("Alice", "Hello!"),
+///     Message::new("Bob", "Hi\tthere"),
+/// ];
+/// let preview = render_preview(&messages, PreviewOptions::new().with_timestamps(false));
+/// assert_eq!(preview, "Alice  Hello!\nBob    Hi\\tthere\n");
+/// ```
+pub fn render_preview(messages: &[Message], options: PreviewOptions) -> String {
+    let shown = &messages[..options.limit.unwrap_or(usize::MAX).min(messages.len())];
+
+    let senders: Vec<String> = shown
+        .iter()
+        .map(|msg| {
+            let name = fit(
+                &escape_controls(&msg.sender),
+                options.max_sender_width.max(1),
+            );
+            if options.sender_emoji {
+                format!("{} {name}", sender_emoji(&msg.sender))
+            } else {
+                name
+            }
+        })
+        .collect();
+    let sender_width = senders.iter().map(|s| s.width()).max().unwrap_or(0);
+    let show_timestamps = options.timestamps && shown.iter().any(|m| m.timestamp.is_some());
+    let timestamp_width = if show_timestamps {
+        TIMESTAMP_FORMAT_WIDTH + GAP.len()
+    } else {
+        0
+    };
+    let indent = timestamp_width + sender_width + GAP.len();
+    let content_width = options.width.saturating_sub(indent).max(MIN_CONTENT_WIDTH);
+
+    let mut out = String::new();
+    for (msg, sender) in shown.iter().zip(&senders) {
+        let mut prefix = String::new();
+        if show_timestamps {
+            push_timestamp(&mut prefix, msg.timestamp, options.color);
+            prefix.push_str(GAP);
+        }
+        if options.color_senders {
+            prefix.push_str(&sender_color(&msg.sender).ansi_fg());
+            prefix.push_str(sender);
+            prefix.push_str("\x1b[0m");
+        } else {
+            prefix.push_str(sender);
+        }
+        pad(&mut prefix, sender_width - sender.width());
+        prefix.push_str(GAP);
+
+        let mut first = true;
+        for line in msg.content.trim_end_matches('\n').split('\n') {
+            for wrapped in wrap(&escape_controls(line), content_width) {
+                if first {
+                    out.push_str(&prefix);
+                    first = false;
+                } else if !wrapped.is_empty() {
+                    pad(&mut out, indent);
+                }
+                out.push_str(&wrapped);
+                trim_line_end(&mut out);
+                out.push('\n');
+            }
+        }
+    }
+
+    let hidden = messages.len() - shown.len();
+    if hidden > 0 {
+        let noun = if hidden == 1 { "message" } else { "messages" };
+        out.push_str(&format!("… {hidden} more {noun}\n"));
+    }
+    out
+}
+
+fn push_timestamp(out: &mut String, timestamp: Option<DateTime<Utc>>, color: bool) {
+    let Some(ts) = timestamp else {
+        pad(out, TIMESTAMP_FORMAT_WIDTH);
+        return;
+    };
+    if color {
+        out.push_str("\x1b[2m");
+    }
+    out.push_str(&ts.format(TIMESTAMP_FORMAT).to_string());
+    if color {
+        out.push_str("\x1b[0m");
+    }
+}
+
+fn pad(out: &mut String, columns: usize) {
+    out.extend(std::iter::repeat_n(' ', columns));
+}
+
+fn trim_line_end(out: &mut String) {
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+}
+
+/// Returns `true` for the bidi embedding, override, and isolate characters
+/// (U+202A to U+202E, U+2066 to U+2069).
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Replaces control and bidi control characters with visible escapes.
+fn escape_controls(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() || is_bidi_control(c) => {
+                out.push_str(&format!("\\u{{{:x}}}", u32::from(c)));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Cuts `text` to at most `width` columns, ending it with `…` if cut.
+fn fit(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for cluster in split_graphemes(text) {
+        let w = cluster.width();
+        if used + w + 1 > width {
+            break;
+        }
+        out.push_str(cluster);
+        used += w;
+    }
+    out.push('…');
+    out
+}
+
+/// Word-wraps one line of text to `width` columns.
+///
+/// Always returns at least one (possibly empty) line.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut used = 0;
+    for word in text.split_whitespace() {
+        let word_width = word.width();
+        if used > 0 && used + 1 + word_width <= width {
+            line.push(' ');
+            line.push_str(word);
+            used += 1 + word_width;
+            continue;
+        }
+        if used > 0 {
+            lines.push(std::mem::take(&mut line));
+            used = 0;
+        }
+        if word_width <= width {
+            line.push_str(word);
+            used = word_width;
+            continue;
+        }
+        for cluster in split_graphemes(word) {
+            let w = cluster.width();
+            if used > 0 && used + w > width {
+                lines.push(std::mem::take(&mut line));
+                used = 0;
+            }
+            line.push_str(cluster);
+            used += w;
+        }
+    }
+    if used > 0 || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("aaa bbb ccc", 7), ["aaa bbb", "ccc"]);
+        assert_eq!(wrap("", 7), [""]);
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        // CJK characters are two columns wide
+        assert_eq!(wrap("你好世界", 5), ["你好", "世界"]);
+        assert_eq!(wrap("👨‍👩‍👧👨‍👩‍👧", 3), ["👨‍👩‍👧", "👨‍👩‍👧"]);
+    }
+
+    #[test]
+    fn test_escape_and_fit() {
+        assert_eq!(
+            escape_controls("a\tb\x1b[31m\u{7f}"),
+            "a\\tb\\u{1b}[31m\\u{7f}"
+        );
+        assert_eq!(
+            escape_controls("Eve\u{202e}gnp.exe\u{2066}x\u{2069}\u{200f}"),
+            "Eve\\u{202e}gnp.exe\\u{2066}x\\u{2069}\u{200f}"
+        );
+        assert_eq!(fit("Alexander", 5), "Alex…");
+        assert_eq!(fit("李雷李雷", 5), "李雷…");
+        assert_eq!(fit("Bob", 5), "Bob");
+    }
+
+    #[test]
+    fn test_limit_and_color() {
+        let ts = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let messages = vec![
+            Message::new("Alice", "one").with_timestamp(ts),
+            Message::new("Bob", "two"),
+            Message::new("Bob", "three"),
+        ];
+        let preview = render_preview(
+            &messages,
+            PreviewOptions::new().with_limit(2).with_color(true),
+        );
+        assert_eq!(
+            preview,
+            "\x1b[2m2023-11-14 22:13\x1b[0m  Alice  one\n                  Bob    two\n… 1 more message\n"
+        );
+    }
+
+    #[test]
+    fn test_sender_colors_and_emoji() {
+        let messages = vec![
+            Message::new("Alice", "one"),
+            Message::new("Bob", "two"),
+            Message::new("alice", "three"),
+        ];
+        let options = PreviewOptions::new().with_timestamps(false);
+
+        let plain = render_preview(&messages, options.with_sender_emoji(true));
+        assert_eq!(plain, "🐻 Alice  one\n🦄 Bob    two\n🐻 alice  three\n");
+        assert!(!plain.contains('\x1b'));
+
+        let colored = render_preview(&messages, options.with_color_senders(true));
+        assert_eq!(
+            colored,
+            "\x1b[38;5;168mAlice\x1b[0m  one\n\x1b[38;5;33mBob\x1b[0m    two\n\x1b[38;5;168malice\x1b[0m  three\n"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! The record the JSON and JSONL writers write for each message.
+//!
+//! Both writers build the same [`OutputRecord`], so a message has the same
+//! keys, in the same order, in either format. Keys appear in
+//! [`RECORD_FIELDS`] order, which follows the CSV columns, and a key is
+//! left out whenever its value is missing: records never hold `null`.
+//! [`OutputConfig`] flags decide which values are written at all.
+
+use std::borrow::Borrow;
+
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+use super::boundary::{Boundaries, Boundary};
+use super::order::ordered;
+use crate::Message;
+use crate::core::models::{self, OutputConfig};
+
+/// Keys of a JSON or JSONL record, in the order they are written.
+///
+/// The keys shared with the CSV writer come in the same order as its
+/// columns (`ID`, `Timestamp`, `Sender`, `Content`, `Index`, `ReplyTo`,
+/// `Edited`, `Platform`, `Raw`, `Hash`, `ConversationStart`). Keys added later go
+/// where they belong in this list, not at the end.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "json-output")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::output::{RECORD_FIELDS, to_jsonl};
+/// use chatpack::prelude::*;
+///
+/// let msg = Message::new("Alice", "Hi").with_id(7);
+/// let line = to_jsonl(&[msg], &OutputConfig::new().with_ids())?;
+/// assert_eq!(line, "{\"id\":7,\"sender\":\"Alice\",\"content\":\"Hi\"}\n");
+/// assert_eq!(&RECORD_FIELDS[1..4], ["id", "timestamp", "sender"]);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json-output"))]
+/// # fn main() {}
+/// ```
+pub const RECORD_FIELDS: &[&str] = &[
+    "_v",
+    "id",
+    "timestamp",
+    "sender",
+    "original_sender",
+    "forwarded_from",
+    "content",
+    "topic",
+    "source",
+    "index",
+    "reply_to",
+    "edited",
+    "platform",
+    "raw",
+    "hash",
+    "conversation_start",
+];
+
+/// One message as the JSON writers write it.
+///
+/// Holds only the values [`OutputConfig`] asks for; serializes them in
+/// [`RECORD_FIELDS`] order, skipping those that are `None`.
+pub(crate) struct OutputRecord {
+    pub(crate) version: Option<u32>,
+    pub(crate) id: Option<u64>,
+    pub(crate) timestamp: Option<String>,
+    pub(crate) sender: String,
+    pub(crate) original_sender: Option<String>,
+    pub(crate) forwarded_from: Option<String>,
+    pub(crate) content: String,
+    pub(crate) topic: Option<String>,
+    pub(crate) source: Option<String>,
+    pub(crate) index: Option<u64>,
+    pub(crate) reply_to: Option<u64>,
+    pub(crate) edited: Option<String>,
+    pub(crate) platform: Option<&'static str>,
+    pub(crate) raw: Option<String>,
+    pub(crate) hash: Option<String>,
+    pub(crate) conversation_start: Option<bool>,
+    /// Unix seconds of the written timestamp, for the JSONL index.
+    pub(crate) unix_timestamp: Option<i64>,
+}
+
+impl OutputRecord {
+    pub(crate) fn from_message(msg: &Message, config: &OutputConfig) -> Self {
+        let format_ts =
+            |ts: chrono::DateTime<chrono::Utc>| ts.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let metadata = |value: Option<&String>| value.filter(|_| config.include_metadata).cloned();
+        Self {
+            version: config
+                .include_schema_version
+                .then_some(models::SCHEMA_VERSION),
+            id: msg.id.filter(|_| config.include_ids),
+            timestamp: msg
+                .timestamp
+                .filter(|_| config.include_timestamps)
+                .map(format_ts),
+            sender: msg.sender.clone(),
+            original_sender: metadata(msg.original_sender.as_ref()),
+            forwarded_from: metadata(msg.forwarded_from.as_ref()),
+            content: msg.content.clone(),
+            topic: metadata(msg.topic.as_ref()),
+            source: metadata(msg.source.as_ref()),
+            index: msg.source_index.filter(|_| config.include_index),
+            reply_to: msg.reply_to.filter(|_| config.include_replies),
+            edited: msg.edited.filter(|_| config.include_edited).map(format_ts),
+            platform: msg.platform_name().filter(|_| config.include_platform),
+            raw: msg.raw.clone().filter(|_| config.include_raw),
+            hash: config.include_hash.then(|| msg.content_hash_hex()),
+            conversation_start: None,
+            unix_timestamp: msg
+                .timestamp
+                .filter(|_| config.include_timestamps)
+                .map(|ts| ts.timestamp()),
+        }
+    }
+
+    /// Converts the messages to records in output order, adding
+    /// conversation boundaries.
+    pub(crate) fn records<I>(messages: I, config: &OutputConfig) -> impl Iterator<Item = Self>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Message>,
+    {
+        let mut boundaries = Boundaries::new(config);
+        ordered(messages, config.sort_by)
+            .flat_map(move |msg| Self::with_boundary(msg.borrow(), config, &mut boundaries))
+    }
+
+    /// Converts the next message in output order to its record, preceded
+    /// by a marker record if it starts a conversation.
+    pub(crate) fn with_boundary(
+        msg: &Message,
+        config: &OutputConfig,
+        boundaries: &mut Boundaries<'_>,
+    ) -> impl Iterator<Item = Self> + use<> {
+        let mut record = Self::from_message(msg, config);
+        let marker = match boundaries.next(msg) {
+            Boundary::None => None,
+            Boundary::Marker(marker) => Some(Self::from_message(&Message::new("", marker), config)),
+            Boundary::Start => {
+                record.conversation_start = Some(true);
+                None
+            }
+        };
+        marker.into_iter().chain(std::iter::once(record))
+    }
+}
+
+impl Serialize for OutputRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut record = serializer.serialize_struct("Record", RECORD_FIELDS.len())?;
+        optional(&mut record, "_v", self.version.as_ref())?;
+        optional(&mut record, "id", self.id.as_ref())?;
+        optional(&mut record, "timestamp", self.timestamp.as_ref())?;
+        record.serialize_field("sender", &self.sender)?;
+        optional(
+            &mut record,
+            "original_sender",
+            self.original_sender.as_ref(),
+        )?;
+        optional(&mut record, "forwarded_from", self.forwarded_from.as_ref())?;
+        record.serialize_field("content", &self.content)?;
+        optional(&mut record, "topic", self.topic.as_ref())?;
+        optional(&mut record, "source", self.source.as_ref())?;
+        optional(&mut record, "index", self.index.as_ref())?;
+        optional(&mut record, "reply_to", self.reply_to.as_ref())?;
+        optional(&mut record, "edited", self.edited.as_ref())?;
+        optional(&mut record, "platform", self.platform.as_ref())?;
+        optional(&mut record, "raw", self.raw.as_ref())?;
+        optional(&mut record, "hash", self.hash.as_ref())?;
+        optional(
+            &mut record,
+            "conversation_start",
+            self.conversation_start.as_ref(),
+        )?;
+        record.end()
+    }
+}
+
+/// A record read back by [`from_json`](super::from_json) or
+/// [`from_jsonl`](super::from_jsonl).
+#[derive(Deserialize)]
+pub(crate) struct InputRecord {
+    #[serde(rename = "_v", default)]
+    version: Option<u32>,
+    #[serde(default)]
+    index: Option<u64>,
+    #[serde(flatten)]
+    message: Message,
+}
+
+impl InputRecord {
+    /// Returns the message, or why its schema version is not supported.
+    pub(crate) fn into_message(self) -> Result<Message, String> {
+        match self.version {
+            Some(v) if v == 0 || v > models::SCHEMA_VERSION => Err(format!(
+                "unsupported schema version {v} (expected 1 to {})",
+                models::SCHEMA_VERSION
+            )),
+            _ => {
+                let mut message = self.message;
+                message.source_index = self.index;
+                Ok(message)
+            }
+        }
+    }
+}
+
+/// Writes `value` under `key`, or skips the key if there is no value.
+fn optional<S, T>(record: &mut S, key: &'static str, value: Option<&T>) -> Result<(), S::Error>
+where
+    S: SerializeStruct,
+    T: Serialize + ?Sized,
+{
+    match value {
+        Some(value) => record.serialize_field(key, value),
+        None => record.skip_field(key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_in_canonical_order() {
+        use crate::core::models::BoundaryStyle;
+        use crate::platform::Platform;
+
+        let ts = chrono::DateTime::from_timestamp(1_705_314_600, 0).unwrap();
+        let msg = Message::new("Alice", "Hi")
+            .with_id(2)
+            .with_timestamp(ts)
+            .with_reply_to(1)
+            .with_edited(ts)
+            .with_original_sender("Alice Smith")
+            .with_forwarded_from("Channel")
+            .with_topic("General")
+            .with_source("team")
+            .with_platform(Platform::Telegram)
+            .with_raw("{}")
+            .with_source_file(0)
+            .with_source_index(0);
+        let config = OutputConfig::all()
+            .with_index()
+            .with_metadata()
+            .with_platform()
+            .with_raw()
+            .with_schema_version()
+            .with_hash()
+            .with_conversation_boundaries(BoundaryStyle::MetaField);
+
+        let record = OutputRecord::records([msg], &config).next().unwrap();
+        let json = serde_json::to_string(&record).unwrap();
+        let positions: Vec<usize> = RECORD_FIELDS
+            .iter()
+            .map(|key| json.find(&format!("\"{key}\":")).expect(key))
+            .collect();
+        assert!(positions.is_sorted(), "{json}");
+    }
+
+    #[test]
+    fn test_missing_values_are_omitted() {
+        let record = OutputRecord::from_message(&Message::new("Bob", "Yo"), &OutputConfig::all());
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"{"sender":"Bob","content":"Yo"}"#);
+    }
+}
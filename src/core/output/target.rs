@@ -0,0 +1,188 @@
+//! What writers do when their output file already exists or would be
+//! empty.
+
+#[cfg(any(feature = "csv-output", feature = "json-output"))]
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// What a writer does when its output file already exists.
+///
+/// The plain `write_*` functions and [`WriteOptions::default`] use
+/// [`Overwrite`](OnExists::Overwrite), so existing code keeps replacing
+/// files. Tools that write where a user points them should prefer
+/// [`Error`](OnExists::Error) unless the user asked to overwrite.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::output::OnExists;
+///
+/// assert_eq!(OnExists::default(), OnExists::Overwrite);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum OnExists {
+    /// Replace the existing file (default).
+    #[default]
+    Overwrite,
+    /// Fail with [`ChatpackError::OutputExists`](crate::ChatpackError::OutputExists)
+    /// and leave the existing file untouched.
+    Error,
+    /// Write next to the existing file instead, as `name-1.ext`, or
+    /// `name-2.ext` if that exists too, and so on.
+    Rename,
+}
+
+/// What a conversion does when no messages are left to write.
+///
+/// [`WriteEmpty`](OnEmpty::WriteEmpty) is the default, so a filter that
+/// matches nothing still produces a valid empty file, as it always has.
+/// Scripts that treat an empty result as a mistake should use
+/// [`Error`](OnEmpty::Error); batch jobs writing one file per input can use
+/// [`SkipFile`](OnEmpty::SkipFile) so empty inputs leave no file behind.
+///
+/// Honored by [`ConvertOptions`](crate::convert::ConvertOptions) and
+/// `PipelineConfig` (`parallel` feature). Either way, no file is created
+/// or replaced unless the policy is `WriteEmpty`.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::output::OnEmpty;
+///
+/// assert_eq!(OnEmpty::default(), OnEmpty::WriteEmpty);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum OnEmpty {
+    /// Write an output with no messages (default).
+    #[default]
+    WriteEmpty,
+    /// Write nothing and report success.
+    SkipFile,
+    /// Write nothing and fail with
+    /// [`ChatpackError::EmptyOutput`](crate::ChatpackError::EmptyOutput).
+    Error,
+}
+
+/// How [`write_csv_with`](super::write_csv_with),
+/// [`write_json_with`](super::write_json_with), and
+/// [`write_to_format_with`](crate::format::write_to_format_with) create
+/// their file.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::output::{OnExists, WriteOptions};
+///
+/// let options = WriteOptions::new().with_on_exists(OnExists::Error);
+/// assert_eq!(options.on_exists, OnExists::Error);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// What to do when the output file exists (default: overwrite).
+    pub on_exists: OnExists,
+}
+
+impl WriteOptions {
+    /// Creates options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets what to do when the output file exists.
+    #[must_use]
+    pub fn with_on_exists(mut self, on_exists: OnExists) -> Self {
+        self.on_exists = on_exists;
+        self
+    }
+}
+
+/// Creates the file at `path` following `on_exists`, returning it with the
+/// path actually created.
+///
+/// `Error` and `Rename` create the file with `create_new`, so a file that
+/// appears between the check and the write is never replaced.
+#[cfg(any(feature = "csv-output", feature = "json-output"))]
+pub(crate) fn create_output(
+    path: &str,
+    on_exists: OnExists,
+) -> Result<(std::fs::File, PathBuf), crate::ChatpackError> {
+    use std::fs::{File, OpenOptions};
+    use std::io::ErrorKind;
+
+    let create_new = |path: &Path| OpenOptions::new().write(true).create_new(true).open(path);
+    let path = Path::new(path);
+    match on_exists {
+        OnExists::Overwrite => Ok((File::create(path)?, path.to_path_buf())),
+        OnExists::Error => match create_new(path) {
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                Err(crate::ChatpackError::output_exists(path))
+            }
+            file => Ok((file?, path.to_path_buf())),
+        },
+        OnExists::Rename => {
+            let mut candidate = path.to_path_buf();
+            for n in 1.. {
+                match create_new(&candidate) {
+                    Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                        candidate = numbered(path, n);
+                    }
+                    file => return Ok((file?, candidate)),
+                }
+            }
+            unreachable!("ran out of numbered file names")
+        }
+    }
+}
+
+/// Returns `path` with `-n` appended to its file stem.
+#[cfg(any(feature = "csv-output", feature = "json-output"))]
+fn numbered(path: &Path, n: u64) -> PathBuf {
+    let mut name: std::ffi::OsString = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("-{n}"));
+    if let Some(ext) = path.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "csv-output", feature = "json-output"))]
+    #[test]
+    fn test_numbered() {
+        assert_eq!(
+            numbered(Path::new("out/chat.csv"), 2),
+            Path::new("out/chat-2.csv")
+        );
+        assert_eq!(numbered(Path::new("chat"), 1), Path::new("chat-1"));
+    }
+
+    #[cfg(feature = "csv-output")]
+    #[test]
+    fn test_create_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chat.csv");
+        let path_str = path.to_str().unwrap();
+
+        let (_, created) = create_output(path_str, OnExists::Error).unwrap();
+        assert_eq!(created, path);
+        let err = create_output(path_str, OnExists::Error).unwrap_err();
+        assert!(err.is_output_exists());
+
+        let (_, renamed) = create_output(path_str, OnExists::Rename).unwrap();
+        assert_eq!(renamed, dir.path().join("chat-1.csv"));
+        let (_, renamed) = create_output(path_str, OnExists::Rename).unwrap();
+        assert_eq!(renamed, dir.path().join("chat-2.csv"));
+
+        let (_, replaced) = create_output(path_str, OnExists::Overwrite).unwrap();
+        assert_eq!(replaced, path);
+    }
+}
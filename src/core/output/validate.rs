@@ -0,0 +1,294 @@
+//! Pre-flight checks for output consumers.
+//!
+//! [`validate_for_format`] flags data that is valid for chatpack's writers
+//! but likely to trip up naive consumers, such as a tab in a sender name
+//! read by a tab-splitting script. Messages are never modified.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+use crate::Message;
+use crate::core::models::OutputConfig;
+use crate::format::OutputFormat;
+
+/// Longest field, in bytes, that passes without a
+/// [`FieldTooLong`](ValidationIssue::FieldTooLong) warning.
+///
+/// Matches the default `field_size_limit` of Python's `csv` module.
+pub const MAX_FIELD_LEN: usize = 128 * 1024;
+
+/// What is wrong with a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    /// The field contains a control character other than a newline.
+    ControlCharacter {
+        /// `"sender"` or `"content"`.
+        field: &'static str,
+        /// The first offending character.
+        character: char,
+    },
+
+    /// The field is longer than [`MAX_FIELD_LEN`] bytes.
+    FieldTooLong {
+        /// `"sender"` or `"content"`.
+        field: &'static str,
+        /// Length in bytes.
+        len: usize,
+    },
+
+    /// A CSV cell contains a line break. The writer quotes it correctly,
+    /// but line-based readers will split the record.
+    NewlineInCsvCell {
+        /// `"sender"` or `"content"`.
+        field: &'static str,
+    },
+
+    /// Another message has the same `id`. Only checked when ids are output.
+    DuplicateId {
+        /// The repeated id.
+        id: u64,
+        /// Index of the first message with this id.
+        first_index: usize,
+    },
+
+    /// The timestamp is earlier than the previous timestamped message.
+    /// Only checked when timestamps are output.
+    OutOfOrder {
+        /// The previous message's timestamp.
+        previous: DateTime<Utc>,
+    },
+}
+
+/// A [`ValidationIssue`] and the index of the message it was found in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationWarning {
+    /// Index of the message in the validated slice.
+    pub index: usize,
+    /// What was found.
+    pub issue: ValidationIssue,
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "message {}: ", self.index)?;
+        match &self.issue {
+            ValidationIssue::ControlCharacter { field, character } => write!(
+                f,
+                "{field} contains control character U+{:04X}",
+                u32::from(*character)
+            ),
+            ValidationIssue::FieldTooLong { field, len } => {
+                write!(f, "{field} is {len} bytes (limit {MAX_FIELD_LEN})")
+            }
+            ValidationIssue::NewlineInCsvCell { field } => {
+                write!(f, "{field} contains a line break")
+            }
+            ValidationIssue::DuplicateId { id, first_index } => {
+                write!(f, "id {id} already used by message {first_index}")
+            }
+            ValidationIssue::OutOfOrder { previous } => write!(
+                f,
+                "timestamp is earlier than the previous message ({})",
+                previous.format("%Y-%m-%d %H:%M:%S")
+            ),
+        }
+    }
+}
+
+/// Checks `messages` for data likely to break naive consumers of `format`.
+///
+/// Checks, per message:
+///
+/// - Control characters in `sender` or `content` (newlines excepted)
+/// - Fields longer than [`MAX_FIELD_LEN`] bytes
+/// - Line breaks in CSV cells (CSV only)
+/// - Duplicate ids (when [`OutputConfig::include_ids`] is set)
+/// - Timestamps earlier than the previous message's (when
+///   [`OutputConfig::include_timestamps`] is set)
+///
+/// Warnings are returned in message order. An empty result means the
+/// output is safe to hand to line- and delimiter-based tools.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::core::output::{ValidationIssue, validate_for_format};
+/// use chatpack::format::OutputFormat;
+/// use chatpack::prelude::*;
+///
+/// let messages = vec![
+///     Message::new("Alice\tSmith", "Hi"),
+///     Message::new("Bob", "Hello"),
+/// ];
+///
+/// let warnings = validate_for_format(&messages, OutputFormat::Csv, &OutputConfig::new());
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].index, 0);
+/// assert!(matches!(warnings[0].issue, ValidationIssue::ControlCharacter { field: "sender", .. }));
+/// assert_eq!(warnings[0].to_string(), "message 0: sender contains control character U+0009");
+/// ```
+pub fn validate_for_format(
+    messages: &[Message],
+    format: OutputFormat,
+    config: &OutputConfig,
+) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+    let mut seen_ids: HashMap<u64, usize> = HashMap::new();
+    let mut previous: Option<DateTime<Utc>> = None;
+
+    for (index, msg) in messages.iter().enumerate() {
+        let mut warn = |issue| warnings.push(ValidationWarning { index, issue });
+
+        for (field, value) in [("sender", &msg.sender), ("content", &msg.content)] {
+            if let Some(character) = value
+                .chars()
+                .find(|c| c.is_control() && !matches!(c, '\n' | '\r'))
+            {
+                warn(ValidationIssue::ControlCharacter { field, character });
+            }
+            if value.len() > MAX_FIELD_LEN {
+                warn(ValidationIssue::FieldTooLong {
+                    field,
+                    len: value.len(),
+                });
+            }
+            if format == OutputFormat::Csv && value.contains(['\n', '\r']) {
+                warn(ValidationIssue::NewlineInCsvCell { field });
+            }
+        }
+
+        if config.include_ids {
+            if let Some(id) = msg.id {
+                if let Some(&first_index) = seen_ids.get(&id) {
+                    warn(ValidationIssue::DuplicateId { id, first_index });
+                } else {
+                    seen_ids.insert(id, index);
+                }
+            }
+        }
+
+        if config.include_timestamps {
+            if let Some(ts) = msg.timestamp {
+                if let Some(prev) = previous.filter(|prev| ts < *prev) {
+                    warn(ValidationIssue::OutOfOrder { previous: prev });
+                }
+                previous = Some(ts);
+            }
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        use chrono::TimeZone;
+        Utc.with_ymd_and_hms(2024, 1, 15, 10, minute, 0).unwrap()
+    }
+
+    fn issues(messages: &[Message], format: OutputFormat) -> Vec<ValidationIssue> {
+        validate_for_format(messages, format, &OutputConfig::all())
+            .into_iter()
+            .map(|w| w.issue)
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_messages() {
+        let messages = vec![
+            Message::new("Alice", "Hello")
+                .with_id(1)
+                .with_timestamp(at(0)),
+            Message::new("Bob", "Hi").with_id(2).with_timestamp(at(0)),
+            Message::new("Alice", "Bye")
+                .with_id(3)
+                .with_timestamp(at(1)),
+        ];
+
+        for &format in OutputFormat::all() {
+            assert!(issues(&messages, format).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_control_character() {
+        let messages = vec![Message::new("Alice", "bell\u{7}")];
+
+        assert_eq!(
+            issues(&messages, OutputFormat::Json),
+            [ValidationIssue::ControlCharacter {
+                field: "content",
+                character: '\u{7}'
+            }]
+        );
+    }
+
+    #[test]
+    fn test_field_too_long() {
+        let messages = vec![Message::new("Alice", "x".repeat(MAX_FIELD_LEN + 1))];
+
+        assert_eq!(
+            issues(&messages, OutputFormat::Jsonl),
+            [ValidationIssue::FieldTooLong {
+                field: "content",
+                len: MAX_FIELD_LEN + 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_newline_flagged_for_csv_only() {
+        let messages = vec![Message::new("Alice", "line one\nline two")];
+
+        assert_eq!(
+            issues(&messages, OutputFormat::Csv),
+            [ValidationIssue::NewlineInCsvCell { field: "content" }]
+        );
+        assert!(issues(&messages, OutputFormat::Jsonl).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_id() {
+        let messages = vec![
+            Message::new("Alice", "a").with_id(7),
+            Message::new("Bob", "b").with_id(8),
+            Message::new("Carol", "c").with_id(7),
+        ];
+
+        let warnings = validate_for_format(&messages, OutputFormat::Json, &OutputConfig::all());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].index, 2);
+        assert_eq!(
+            warnings[0].to_string(),
+            "message 2: id 7 already used by message 0"
+        );
+
+        // Not output, not checked
+        let config = OutputConfig::new();
+        assert!(validate_for_format(&messages, OutputFormat::Json, &config).is_empty());
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let messages = vec![
+            Message::new("Alice", "a").with_timestamp(at(5)),
+            Message::new("Bob", "no timestamp"),
+            Message::new("Carol", "c").with_timestamp(at(3)),
+            Message::new("Dave", "d").with_timestamp(at(4)),
+        ];
+
+        let warnings = validate_for_format(&messages, OutputFormat::Csv, &OutputConfig::all());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].index, 2);
+        assert_eq!(
+            warnings[0].issue,
+            ValidationIssue::OutOfOrder { previous: at(5) }
+        );
+    }
+}
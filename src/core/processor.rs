@@ -8,6 +8,21 @@
 //! | Function | Description |
 //! |----------|-------------|
 //! | [`merge_consecutive`] | Combine consecutive messages from same sender |
+//! | [`map_senders`] | Rename sender aliases to canonical names |
+//! | [`relabel_perspective`] | Relabel senders as "me" and "them" |
+//! | [`apply_transforms`] | Rewrite content with [`ContentTransform`]s |
+//! | [`extract_dialogue`] | Keep the back-and-forth between two people |
+//! | [`trim_to_active_spans`] | Keep stretches where several people were talking |
+//! | [`near_dedupe`] | Drop near-duplicates of a sender's recent messages |
+//! | [`with_context`] | Pair each message with the ones before it |
+//! | [`sample_messages`] | Keep a seeded random sample of messages |
+//! | [`balance_senders`] | Cap each sender at a seeded random subset |
+//! | [`anonymize_senders`] | Replace senders with seeded pseudonyms |
+//! | [`partition_by_topic`] | Group messages by forum topic |
+//! | [`suggest_title`] | Slug a group's most frequent words |
+//! | [`suggest_file_names`] | Name partitioned output files |
+//! | [`check_ordering`] | Count messages out of chronological order |
+//! | [`sort_messages`] | Put messages in chronological order |
 //! | [`ProcessingStats`] | Track compression metrics |
 //!
 //! # Token Compression
@@ -40,7 +55,105 @@
 //! println!("{}", stats); // "4 → 2 messages (50.0% reduction)"
 //! ```
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::Message;
+use crate::core::links;
+use crate::core::loss::LossReport;
+use crate::core::rand::{Rng, Seed};
+use crate::core::textutil;
+use crate::core::transform::ContentTransform;
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json",
+    feature = "json-output",
+    feature = "fixtures",
+    feature = "ffi"
+))]
+use crate::error::ChatpackError;
+
+/// How sender names are compared when deciding whether two messages are
+/// from the same sender.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SenderMatch {
+    /// Names must be identical.
+    Exact,
+    /// Names may differ in case, including non-ASCII case.
+    #[default]
+    CaseInsensitive,
+    /// Names are compared as by [`FilterConfig::with_sender`]: ignoring
+    /// case and invisible characters (see [`textutil::senders_match`]).
+    ///
+    /// [`FilterConfig::with_sender`]: crate::core::filter::FilterConfig::with_sender
+    Normalized,
+}
+
+impl SenderMatch {
+    /// Returns whether `a` and `b` name the same sender.
+    ///
+    /// ```rust
+    /// use chatpack::core::processor::SenderMatch;
+    ///
+    /// assert!(!SenderMatch::Exact.matches("alice", "Alice"));
+    /// assert!(SenderMatch::CaseInsensitive.matches("алия", "Алия"));
+    /// assert!(!SenderMatch::CaseInsensitive.matches("Алия\u{200B}", "Алия"));
+    /// assert!(SenderMatch::Normalized.matches("Алия\u{200B}", "Алия"));
+    /// ```
+    pub fn matches(self, a: &str, b: &str) -> bool {
+        match self {
+            SenderMatch::Exact => a == b,
+            SenderMatch::CaseInsensitive => {
+                a.eq_ignore_ascii_case(b)
+                    || a.chars()
+                        .flat_map(char::to_lowercase)
+                        .eq(b.chars().flat_map(char::to_lowercase))
+            }
+            SenderMatch::Normalized => textutil::senders_match(a, b),
+        }
+    }
+}
+
+/// Configuration for [`merge_consecutive_with_config`].
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::{MergeConfig, SenderMatch};
+///
+/// let config = MergeConfig::new().with_sender_match(SenderMatch::Normalized);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MergeConfig {
+    /// How senders are compared (default: [`SenderMatch::CaseInsensitive`]).
+    pub sender_match: SenderMatch,
+}
+
+impl MergeConfig {
+    /// Creates a configuration with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how senders are compared.
+    #[must_use]
+    pub fn with_sender_match(mut self, sender_match: SenderMatch) -> Self {
+        self.sender_match = sender_match;
+        self
+    }
+
+    /// Returns whether `next` continues the run ending in `last`: whether
+    /// it is from the same sender and source.
+    pub(crate) fn continues(&self, last: &Message, next: &Message) -> bool {
+        self.sender_match.matches(&last.sender, &next.sender) && last.same_source(next)
+    }
+}
 
 /// Merges consecutive messages from the same sender into single entries.
 ///
@@ -50,12 +163,18 @@ use crate::Message;
 /// # Algorithm
 ///
 /// Messages are merged when:
-/// 1. They come from the same sender (exact string match)
+/// 1. They come from the same sender, ignoring case (see [`SenderMatch`];
+///    [`merge_consecutive_with_config`] picks another comparison)
 /// 2. They are consecutive (no messages from others in between)
+/// 3. They come from the same input file ([`Message::source_file`]) and,
+///    when parsers tagged them, the same platform and forum topic
 ///
 /// When merging:
 /// - Contents are joined with newline (`\n`)
-/// - First message's metadata (timestamp, id, `reply_to`, edited) is preserved
+/// - First message's sender, as written, and metadata (timestamp, id,
+///   `reply_to`, edited, original sender, forward origin, raw) are
+///   preserved; [`merge_consecutive_with_loss`] counts what the others lose
+/// - Attachment refs of all merged messages are kept, in order
 ///
 /// # Example
 ///
@@ -83,13 +202,71 @@ use crate::Message;
 /// - Allocates a new output vector
 /// - O(n) time complexity
 pub fn merge_consecutive(messages: Vec<Message>) -> Vec<Message> {
+    merge_consecutive_with_loss(messages, &mut LossReport::new())
+}
+
+/// Merges like [`merge_consecutive`], counting in `loss` the messages
+/// merged and the ids and attachment refs they lose or move.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::loss::LossReport;
+/// use chatpack::core::processor::merge_consecutive_with_loss;
+/// use chatpack::Message;
+///
+/// let messages = vec![
+///     Message::new("Alice", "Hi").with_id(1),
+///     Message::new("Alice", "How are you?").with_id(2),
+///     Message::new("Bob", "Good!").with_id(3),
+/// ];
+///
+/// let mut loss = LossReport::new();
+/// let merged = merge_consecutive_with_loss(messages, &mut loss);
+///
+/// assert_eq!(merged.len(), 2);
+/// assert_eq!(loss.messages_merged, 1);
+/// assert_eq!(loss.ids_discarded, 1);
+/// ```
+pub fn merge_consecutive_with_loss(messages: Vec<Message>, loss: &mut LossReport) -> Vec<Message> {
+    merge_consecutive_with_config(messages, &MergeConfig::default(), loss)
+}
+
+/// Merges like [`merge_consecutive_with_loss`], comparing senders as
+/// `config` says.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::loss::LossReport;
+/// use chatpack::core::processor::{MergeConfig, SenderMatch, merge_consecutive_with_config};
+/// use chatpack::Message;
+///
+/// let messages = vec![
+///     Message::new("alice", "Hi"),
+///     Message::new("Alice", "How are you?"),
+/// ];
+///
+/// let exact = MergeConfig::new().with_sender_match(SenderMatch::Exact);
+/// let merged = merge_consecutive_with_config(messages.clone(), &exact, &mut LossReport::new());
+/// assert_eq!(merged.len(), 2);
+///
+/// let merged =
+///     merge_consecutive_with_config(messages, &MergeConfig::default(), &mut LossReport::new());
+/// assert_eq!(merged.len(), 1);
+/// assert_eq!(merged[0].sender, "alice");
+/// ```
+pub fn merge_consecutive_with_config(
+    messages: Vec<Message>,
+    config: &MergeConfig,
+    loss: &mut LossReport,
+) -> Vec<Message> {
     let mut merged: Vec<Message> = Vec::with_capacity(messages.len());
 
     for msg in messages {
         match merged.last_mut() {
-            Some(last) if last.sender == msg.sender => {
-                last.content.push('\n');
-                last.content.push_str(&msg.content);
+            Some(last) if config.continues(last, &msg) => {
+                append_merged(last, msg, loss);
             }
             _ => {
                 merged.push(msg);
@@ -101,45 +278,1527 @@ pub fn merge_consecutive(messages: Vec<Message>) -> Vec<Message> {
     merged
 }
 
-/// Statistics about the processing result.
+/// Appends `msg` to `last`, the message it is merged into.
+pub(crate) fn append_merged(last: &mut Message, msg: Message, loss: &mut LossReport) {
+    loss.count_merged(last, &msg);
+    last.content.push('\n');
+    last.content.push_str(&msg.content);
+    last.attachments.extend(msg.attachments);
+}
+
+/// Mapping from sender aliases to canonical names.
 ///
-/// Provides information about how many messages were processed
-/// and the compression achieved through merging.
+/// Lookups ignore case and invisible characters but are otherwise exact:
+/// `"john smith"` matches `"John Smith"`, while `"John"` does not match
+/// `"John Smith"`. Names are compared as in
+/// [`senders_match`](textutil::senders_match).
+///
+/// Serialized as `{"aliases": {"John Smith": "John", "+1 555 0100": "John"}}`.
+/// Alias keys are stored [folded](textutil::fold_sender), so `"John"` and
+/// `"JOHN"` are one alias; if they name different canonical names, the last
+/// one wins and [`map_senders`] reports a collision.
 ///
 /// # Example
 ///
 /// ```rust
-/// use chatpack::core::processor::ProcessingStats;
+/// use chatpack::core::processor::SenderMap;
 ///
-/// let stats = ProcessingStats::new(100, 60);
-/// println!("Compression: {:.1}%", stats.compression_ratio()); // 40.0%
+/// let map = SenderMap::new()
+///     .with_alias("John Smith", "John")
+///     .with_alias("+1 555 0100", "John");
+///
+/// assert_eq!(map.canonical("JOHN SMITH"), Some("John"));
+/// assert_eq!(map.canonical("John"), None);
 /// ```
-#[derive(Debug, Clone, PartialEq)]
-pub struct ProcessingStats {
-    /// Number of messages before processing
-    pub original_count: usize,
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "SenderMapRepr")]
+pub struct SenderMap {
+    aliases: BTreeMap<String, String>,
+    /// Folded aliases that were given two different canonical names.
+    #[serde(skip)]
+    conflicts: BTreeSet<String>,
+}
 
-    /// Number of messages after merging
-    pub merged_count: usize,
+#[derive(Deserialize)]
+struct SenderMapRepr {
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+}
 
-    /// Number of messages after filtering (if filtering was applied)
-    pub filtered_count: Option<usize>,
+impl From<SenderMapRepr> for SenderMap {
+    fn from(repr: SenderMapRepr) -> Self {
+        repr.aliases.into_iter().collect()
+    }
 }
 
-impl ProcessingStats {
-    /// Creates new statistics with original and merged counts.
-    pub fn new(original: usize, merged: usize) -> Self {
+impl SenderMap {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an alias, replacing any previous mapping for it.
+    #[must_use]
+    pub fn with_alias(mut self, alias: impl AsRef<str>, canonical: impl Into<String>) -> Self {
+        self.insert(alias, canonical);
+        self
+    }
+
+    /// Adds an alias in place, replacing any previous mapping for it.
+    pub fn insert(&mut self, alias: impl AsRef<str>, canonical: impl Into<String>) {
+        let alias = textutil::fold_sender(alias.as_ref());
+        let canonical = canonical.into();
+        if self
+            .aliases
+            .get(&alias)
+            .is_some_and(|previous| *previous != canonical)
+        {
+            self.conflicts.insert(alias.clone());
+        }
+        self.aliases.insert(alias, canonical);
+    }
+
+    /// Parses a map from JSON (`{"aliases": {...}}`).
+    ///
+    /// Other formats such as TOML can be loaded with their own serde crate,
+    /// since [`SenderMap`] implements [`Deserialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::Json`] if the JSON is malformed.
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
+    ))]
+    pub fn from_json(json: &str) -> Result<Self, ChatpackError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Loads a map from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::Io`] if the file cannot be read, or
+    /// [`ChatpackError::Json`] if it is malformed.
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
+    ))]
+    pub fn from_json_file(path: impl AsRef<std::path::Path>) -> Result<Self, ChatpackError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Returns the canonical name for `sender`, if it is a known alias.
+    pub fn canonical(&self, sender: &str) -> Option<&str> {
+        self.aliases
+            .get(&textutil::fold_sender(sender))
+            .map(String::as_str)
+    }
+
+    /// Returns the number of aliases.
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    /// Returns `true` if the map has no aliases.
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+impl<A: AsRef<str>, C: Into<String>> FromIterator<(A, C)> for SenderMap {
+    fn from_iter<I: IntoIterator<Item = (A, C)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (alias, canonical) in iter {
+            map.insert(alias, canonical);
+        }
+        map
+    }
+}
+
+/// Result of [`map_senders`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SenderMapReport {
+    /// Messages whose sender was renamed.
+    pub remapped: usize,
+
+    /// Aliases that were given two different canonical names, such as
+    /// `"John" -> "John"` and `"JOHN" -> "Johnny"`; the last one was used.
+    ///
+    /// Several aliases of one canonical name are not collisions: grouping
+    /// the names of one person is the point of a sender map. A non-zero
+    /// count is a prompt to review the map, not an error.
+    pub collisions: usize,
+}
+
+/// Renames senders to their canonical names.
+///
+/// Apply this before [`apply_filters`](crate::core::filter::apply_filters)
+/// so sender filters match canonical names, and before [`merge_consecutive`]
+/// so aliases of one person merge. Senders not in the map are left
+/// untouched.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::{SenderMap, map_senders, merge_consecutive};
+/// use chatpack::Message;
+///
+/// let mut messages = vec![
+///     Message::new("John Smith", "Hi"),
+///     Message::new("+1 555 0100", "It's me"),
+///     Message::new("Alice", "Hey John"),
+/// ];
+///
+/// let map = SenderMap::new()
+///     .with_alias("john smith", "John")
+///     .with_alias("+1 555 0100", "John");
+///
+/// let report = map_senders(&mut messages, &map);
+/// assert_eq!(report.remapped, 2);
+/// assert_eq!(merge_consecutive(messages).len(), 2);
+/// ```
+pub fn map_senders(messages: &mut [Message], map: &SenderMap) -> SenderMapReport {
+    let mut report = SenderMapReport {
+        remapped: 0,
+        collisions: map.conflicts.len(),
+    };
+    for msg in messages.iter_mut() {
+        match map.canonical(&msg.sender) {
+            Some(canonical) if msg.sender != canonical => {
+                msg.sender = canonical.to_string();
+                report.remapped += 1;
+            }
+            _ => {}
+        }
+    }
+    report
+}
+
+/// Labels used by [`relabel_perspective`].
+///
+/// The default labels are `"me"` and `"them"`; [`chat_roles`](Self::chat_roles)
+/// uses `"assistant"` and `"user"`, for fine-tuning data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelabelConfig {
+    /// Label for messages sent under one of my aliases (default: `"me"`).
+    pub me_label: String,
+
+    /// Label for everyone else's messages (default: `"them"`).
+    pub other_label: String,
+
+    /// Relabel everyone else with `other_label` (default: true). When
+    /// false, other senders keep their names.
+    pub collapse_others: bool,
+
+    /// Keep the name a relabeled message had in
+    /// [`Message::original_sender`] (default: true). A name already kept
+    /// there is not replaced.
+    pub keep_original_sender: bool,
+}
+
+impl Default for RelabelConfig {
+    fn default() -> Self {
         Self {
-            original_count: original,
-            merged_count: merged,
-            filtered_count: None,
+            me_label: "me".to_string(),
+            other_label: "them".to_string(),
+            collapse_others: true,
+            keep_original_sender: true,
         }
     }
+}
+
+impl RelabelConfig {
+    /// Creates a configuration with the default labels.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    /// Sets the filtered count.
+    /// Creates a configuration labeling my messages `"assistant"` and
+    /// everyone else's `"user"`.
+    pub fn chat_roles() -> Self {
+        Self::new()
+            .with_me_label("assistant")
+            .with_other_label("user")
+    }
+
+    /// Sets the label for my messages.
     #[must_use]
-    pub fn with_filtered(mut self, filtered: usize) -> Self {
-        self.filtered_count = Some(filtered);
+    pub fn with_me_label(mut self, label: impl Into<String>) -> Self {
+        self.me_label = label.into();
+        self
+    }
+
+    /// Sets the label for everyone else's messages.
+    #[must_use]
+    pub fn with_other_label(mut self, label: impl Into<String>) -> Self {
+        self.other_label = label.into();
+        self
+    }
+
+    /// Sets whether everyone else is relabeled.
+    #[must_use]
+    pub fn with_collapse_others(mut self, collapse: bool) -> Self {
+        self.collapse_others = collapse;
+        self
+    }
+
+    /// Sets whether relabeled messages keep their previous sender.
+    #[must_use]
+    pub fn with_keep_original_sender(mut self, keep: bool) -> Self {
+        self.keep_original_sender = keep;
+        self
+    }
+}
+
+/// Relabels senders from one person's point of view: messages sent under
+/// any of the `me` aliases get `config.me_label`, and everyone else's get
+/// `config.other_label`.
+///
+/// Aliases match whole sender names, ignoring case, as in [`SenderMap`].
+/// Returns the number of messages relabeled.
+///
+/// # Merging
+///
+/// Run it before [`merge_consecutive`] to merge by label: with
+/// `collapse_others`, consecutive messages from different people on the
+/// other side become one turn, keeping the `original_sender` of the first
+/// ([`LossReport::metadata_discarded`] counts the others). That is the
+/// intended shape for "me vs them" datasets; leave
+/// `collapse_others` off to keep their speakers apart.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::{RelabelConfig, merge_consecutive, relabel_perspective};
+/// use chatpack::Message;
+///
+/// let mut messages = vec![
+///     Message::new("Berik", "Lunch?"),
+///     Message::new("Alice", "Sure"),
+///     Message::new("Bob", "Me too"),
+///     Message::new("berik work", "Noon then"),
+/// ];
+///
+/// let relabeled = relabel_perspective(
+///     &mut messages,
+///     &["Berik", "Berik Work"],
+///     &RelabelConfig::chat_roles(),
+/// );
+/// assert_eq!(relabeled, 4);
+/// assert_eq!(messages[1].sender, "user");
+/// assert_eq!(messages[1].original_sender.as_deref(), Some("Alice"));
+///
+/// let merged = merge_consecutive(messages);
+/// assert_eq!(merged.len(), 3);
+/// assert_eq!(merged[1].content, "Sure\nMe too");
+/// ```
+pub fn relabel_perspective(messages: &mut [Message], me: &[&str], config: &RelabelConfig) -> usize {
+    let me: HashSet<String> = me
+        .iter()
+        .map(|alias| textutil::fold_sender(alias))
+        .collect();
+    let mut relabeled = 0;
+    for msg in messages {
+        let label = if me.contains(&textutil::fold_sender(&msg.sender)) {
+            &config.me_label
+        } else if config.collapse_others {
+            &config.other_label
+        } else {
+            continue;
+        };
+        if msg.sender == *label {
+            continue;
+        }
+        let original = std::mem::replace(&mut msg.sender, label.clone());
+        if config.keep_original_sender && msg.original_sender.is_none() {
+            msg.original_sender = Some(original);
+        }
+        relabeled += 1;
+    }
+    relabeled
+}
+
+/// Runs `transforms` on each message in turn, in order.
+///
+/// Same as [`TransformChain::apply_all`](crate::core::transform::TransformChain::apply_all)
+/// for transforms kept outside a chain.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::apply_transforms;
+/// use chatpack::core::transform::{ContentTransform, Redact};
+/// use chatpack::Message;
+///
+/// let transforms: Vec<Box<dyn ContentTransform>> = vec![
+///     Box::new(|msg: &mut Message| msg.content = msg.content.replace("ACME-", "#")),
+///     Box::new(Redact::new(["#13"])),
+/// ];
+/// let mut messages = vec![Message::new("Alice", "Fixed ACME-12 and ACME-13")];
+///
+/// apply_transforms(&mut messages, &transforms);
+/// assert_eq!(messages[0].content, "Fixed #12 and [REDACTED]");
+/// ```
+pub fn apply_transforms(messages: &mut [Message], transforms: &[Box<dyn ContentTransform>]) {
+    for msg in messages {
+        for transform in transforms {
+            transform.transform(msg);
+        }
+    }
+}
+
+/// Windows used by [`extract_dialogue`] to decide what counts as a reply.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::DialogueConfig;
+/// use chrono::Duration;
+///
+/// let config = DialogueConfig::new()
+///     .with_window_messages(2)
+///     .with_window_duration(Duration::minutes(30));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DialogueConfig {
+    /// How many of the pair's messages may separate a message from the
+    /// other person's nearest one (default: 3). Third-party messages are not
+    /// counted.
+    pub window_messages: usize,
+
+    /// How far apart in time a message and the other person's nearest one
+    /// may be (default: 10 minutes). `None` disables the time window.
+    pub window_duration: Option<Duration>,
+}
+
+impl Default for DialogueConfig {
+    fn default() -> Self {
+        Self {
+            window_messages: 3,
+            window_duration: Some(Duration::minutes(10)),
+        }
+    }
+}
+
+impl DialogueConfig {
+    /// Creates a configuration with default windows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the message window.
+    #[must_use]
+    pub fn with_window_messages(mut self, messages: usize) -> Self {
+        self.window_messages = messages;
+        self
+    }
+
+    /// Sets the time window.
+    #[must_use]
+    pub fn with_window_duration(mut self, duration: Duration) -> Self {
+        self.window_duration = Some(duration);
+        self
+    }
+
+    /// Disables the time window, leaving only the message window.
+    #[must_use]
+    pub fn without_window_duration(mut self) -> Self {
+        self.window_duration = None;
+        self
+    }
+}
+
+/// Extracts the dialogue between two people from a group chat.
+///
+/// # Inclusion Rules
+///
+/// 1. Only messages from `a` or `b` are kept. Names match ignoring case
+///    and invisible characters, like [`FilterConfig::with_sender`](crate::core::filter::FilterConfig::with_sender).
+/// 2. Positions are counted among the pair's messages only, so third-party
+///    messages in between are dropped without widening the gap.
+/// 3. A message is kept if the *other* person's nearest message before or
+///    after it is at most [`window_messages`](DialogueConfig::window_messages)
+///    positions away, **or** both have timestamps at most
+///    [`window_duration`](DialogueConfig::window_duration) apart.
+///
+/// Messages are expected in chronological order; the output keeps it. If
+/// `a` and `b` name the same person, nothing is returned.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::{DialogueConfig, extract_dialogue};
+/// use chatpack::Message;
+///
+/// let messages = vec![
+///     Message::new("Alice", "Anyone seen my keys?"),
+///     Message::new("Carol", "Not me"),
+///     Message::new("Bob", "On the table"),
+///     Message::new("Alice", "Thanks!"),
+/// ];
+///
+/// let config = DialogueConfig::new().with_window_messages(1);
+/// let dialogue = extract_dialogue(&messages, "alice", "bob", &config);
+///
+/// assert_eq!(dialogue.len(), 3);
+/// assert!(dialogue.iter().all(|m| m.sender != "Carol"));
+/// ```
+pub fn extract_dialogue(
+    messages: &[Message],
+    a: &str,
+    b: &str,
+    config: &DialogueConfig,
+) -> Vec<Message> {
+    if textutil::senders_match(a, b) {
+        return Vec::new();
+    }
+
+    // The pair's messages, tagged with whether they come from `a`
+    let pair: Vec<(bool, &Message)> = messages
+        .iter()
+        .filter_map(|m| {
+            if textutil::senders_match(&m.sender, a) {
+                Some((true, m))
+            } else if textutil::senders_match(&m.sender, b) {
+                Some((false, m))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // Nearest message from the other person before and after each position
+    let mut prev_other = vec![None; pair.len()];
+    let mut last: [Option<usize>; 2] = [None, None];
+    for (i, &(is_a, _)) in pair.iter().enumerate() {
+        prev_other[i] = last[usize::from(is_a)];
+        last[usize::from(!is_a)] = Some(i);
+    }
+    let mut next_other = vec![None; pair.len()];
+    let mut last: [Option<usize>; 2] = [None, None];
+    for (i, &(is_a, _)) in pair.iter().enumerate().rev() {
+        next_other[i] = last[usize::from(is_a)];
+        last[usize::from(!is_a)] = Some(i);
+    }
+
+    let within = |i: usize, j: usize| {
+        if i.abs_diff(j) <= config.window_messages {
+            return true;
+        }
+        match (
+            config.window_duration,
+            pair[i].1.timestamp,
+            pair[j].1.timestamp,
+        ) {
+            (Some(window), Some(ti), Some(tj)) => (ti - tj).abs() <= window,
+            _ => false,
+        }
+    };
+
+    pair.iter()
+        .enumerate()
+        .filter(|&(i, _)| {
+            prev_other[i].is_some_and(|j| within(i, j))
+                || next_other[i].is_some_and(|j| within(i, j))
+        })
+        .map(|(_, &(_, m))| m.clone())
+        .collect()
+}
+
+/// Thresholds used by [`trim_to_active_spans`].
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::ActivityConfig;
+/// use chrono::Duration;
+///
+/// let config = ActivityConfig::new()
+///     .with_min_senders(3)
+///     .with_window(Duration::minutes(30));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivityConfig {
+    /// How many distinct senders a window needs (default: 2).
+    pub min_senders: usize,
+
+    /// Length of the sliding window (default: 1 hour).
+    pub window: Duration,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            min_senders: 2,
+            window: Duration::hours(1),
+        }
+    }
+}
+
+impl ActivityConfig {
+    /// Creates a configuration with default thresholds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many distinct senders a window needs.
+    #[must_use]
+    pub fn with_min_senders(mut self, min_senders: usize) -> Self {
+        self.min_senders = min_senders;
+        self
+    }
+
+    /// Sets the length of the sliding window.
+    #[must_use]
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+/// Trims a chat to the spans where several people were active.
+///
+/// A message is kept if it falls in some time window of length
+/// [`window`](ActivityConfig::window) (both ends inclusive) in which at least
+/// [`min_senders`](ActivityConfig::min_senders) distinct senders posted.
+/// Overlapping and adjacent qualifying windows merge into one span, so a
+/// stretch where one person posts alone for longer than the window is cut
+/// out, except for the messages near its edges that share a window with
+/// the surrounding conversation.
+///
+/// Messages without a timestamp cannot be placed in a window and are
+/// dropped. Senders are compared exactly. Messages are expected in
+/// chronological order; the output keeps it.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::{ActivityConfig, trim_to_active_spans};
+/// use chatpack::Message;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let at = |hour| Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap();
+/// let messages = vec![
+///     Message::new("Alice", "Hi").with_timestamp(at(9)),
+///     Message::new("Bob", "Hey").with_timestamp(at(9)),
+///     Message::new("Admin", "Announcement").with_timestamp(at(12)),
+///     Message::new("Admin", "Reminder").with_timestamp(at(15)),
+/// ];
+///
+/// let config = ActivityConfig::new().with_window(Duration::hours(1));
+/// let trimmed = trim_to_active_spans(messages, &config);
+///
+/// assert_eq!(trimmed.len(), 2);
+/// assert_eq!(trimmed[1].sender, "Bob");
+/// ```
+pub fn trim_to_active_spans(messages: Vec<Message>, config: &ActivityConfig) -> Vec<Message> {
+    let timed: Vec<_> = messages
+        .into_iter()
+        .filter_map(|m| Some((m.timestamp?, m)))
+        .collect();
+
+    // Slide a window anchored at each message; any window containing a
+    // stretch of messages can be moved to start at its first one
+    let mut keep = vec![false; timed.len()];
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut end = 0;
+    let mut kept_until = 0;
+    for start in 0..timed.len() {
+        end = end.max(start);
+        while end < timed.len() && timed[end].0 - timed[start].0 <= config.window {
+            *counts.entry(timed[end].1.sender.as_str()).or_default() += 1;
+            end += 1;
+        }
+        if counts.len() >= config.min_senders {
+            keep[kept_until.max(start)..end].fill(true);
+            kept_until = end;
+        }
+
+        let sender = timed[start].1.sender.as_str();
+        if let Some(count) = counts.get_mut(sender) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(sender);
+            }
+        }
+    }
+
+    timed
+        .into_iter()
+        .zip(keep)
+        .filter_map(|((_, msg), keep)| keep.then_some(msg))
+        .collect()
+}
+
+/// Settings for [`near_dedupe`].
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::NearDupConfig;
+/// use chrono::Duration;
+///
+/// let config = NearDupConfig::new()
+///     .with_window(Duration::minutes(10))
+///     .with_similarity(0.7)
+///     .with_min_words(2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearDupConfig {
+    /// How far apart two messages may be and still be duplicates (default:
+    /// 5 minutes).
+    pub window: Duration,
+
+    /// Token Jaccard similarity, from 0.0 to 1.0, at which a message counts
+    /// as a duplicate (default: 0.8).
+    pub similarity: f32,
+
+    /// Fewest words a message needs to count as a duplicate (default: 3).
+    /// Shorter replies such as "ok" or "thanks" are often sent twice on
+    /// purpose, so they are always kept.
+    pub min_words: usize,
+}
+
+impl Default for NearDupConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::minutes(5),
+            similarity: 0.8,
+            min_words: 3,
+        }
+    }
+}
+
+impl NearDupConfig {
+    /// Creates a configuration with default thresholds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how far apart two messages may be and still be duplicates.
+    #[must_use]
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the similarity at which a message counts as a duplicate.
+    #[must_use]
+    pub fn with_similarity(mut self, similarity: f32) -> Self {
+        self.similarity = similarity;
+        self
+    }
+
+    /// Sets the fewest words a message needs to count as a duplicate.
+    #[must_use]
+    pub fn with_min_words(mut self, min_words: usize) -> Self {
+        self.min_words = min_words;
+        self
+    }
+}
+
+/// Removes messages that repeat, nearly word for word, a message the same
+/// sender posted shortly before, returning how many were removed.
+///
+/// For overlapping exports of one conversation, such as a Telegram chat
+/// and its WhatsApp continuation, or messages pasted from one into the
+/// other. A message is dropped when an earlier kept message from the same
+/// sender, at most [`window`](NearDupConfig::window) older, has a token
+/// Jaccard similarity of at least [`similarity`](NearDupConfig::similarity)
+/// with it: the words both share over the words either uses, ignoring case
+/// and punctuation. Senders are compared ignoring case and invisible
+/// characters, as by [`senders_match`](textutil::senders_match).
+///
+/// Messages without a timestamp, or with fewer than
+/// [`min_words`](NearDupConfig::min_words) words, are kept. The input
+/// need not be sorted; survivors keep their order, and of two duplicates
+/// sent at the same time the first in `messages` is kept.
+///
+/// Takes O(n log n) to sort plus O(n·w) comparisons, where w is the number
+/// of kept messages in a window, each linear in the two messages' word
+/// counts.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::{NearDupConfig, near_dedupe};
+/// use chatpack::Message;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let at = |minute| Utc.with_ymd_and_hms(2024, 1, 1, 9, minute, 0).unwrap();
+/// let mut messages = vec![
+///     Message::new("Alice", "See you at the station at 7").with_timestamp(at(0)),
+///     Message::new("alice", "see you at the station at 7!").with_timestamp(at(1)),
+///     Message::new("Alice", "ok").with_timestamp(at(2)),
+///     Message::new("Alice", "ok").with_timestamp(at(3)),
+/// ];
+///
+/// let removed = near_dedupe(&mut messages, &NearDupConfig::new());
+/// assert_eq!(removed, 1);
+/// assert_eq!(messages.len(), 3);
+/// ```
+pub fn near_dedupe(messages: &mut Vec<Message>, config: &NearDupConfig) -> usize {
+    struct Key {
+        ts: DateTime<Utc>,
+        sender: String,
+        words: Vec<String>,
+    }
+
+    let keys: Vec<Option<Key>> = messages
+        .iter()
+        .map(|msg| {
+            let ts = msg.timestamp?;
+            let words = words(&msg.content);
+            (!words.is_empty() && words.len() >= config.min_words).then(|| Key {
+                ts,
+                sender: textutil::fold_sender(&msg.sender),
+                words,
+            })
+        })
+        .collect();
+    // Stable, so of two messages sent at once the first in `messages` wins
+    let mut order: Vec<(DateTime<Utc>, usize)> = keys
+        .iter()
+        .enumerate()
+        .filter_map(|(i, key)| Some((key.as_ref()?.ts, i)))
+        .collect();
+    order.sort_by_key(|&(ts, _)| ts);
+
+    let mut duplicate = vec![false; messages.len()];
+    let mut window: VecDeque<&Key> = VecDeque::new();
+    for (_, i) in order {
+        let Some(key) = &keys[i] else {
+            continue;
+        };
+        while window
+            .front()
+            .is_some_and(|kept| key.ts - kept.ts > config.window)
+        {
+            window.pop_front();
+        }
+        duplicate[i] = window.iter().any(|kept| {
+            kept.sender == key.sender && jaccard(&kept.words, &key.words) >= config.similarity
+        });
+        if !duplicate[i] {
+            window.push_back(key);
+        }
+    }
+
+    let before = messages.len();
+    let mut flags = duplicate.into_iter();
+    messages.retain(|_| !flags.next().unwrap_or(false));
+    before - messages.len()
+}
+
+/// Returns the distinct lowercase words of `text`, sorted.
+fn words(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    words.sort_unstable();
+    words.dedup();
+    words
+}
+
+/// Returns the Jaccard similarity of two sorted, distinct word lists.
+fn jaccard(a: &[String], b: &[String]) -> f32 {
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        return 0.0;
+    }
+    shared as f32 / union as f32
+}
+
+/// A message together with the messages that came before it.
+///
+/// Produced by [`with_context`] and [`ContextWindows`], written by
+/// [`write_context_jsonl`](crate::core::output::write_context_jsonl).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextWindow {
+    /// The message to respond to or predict.
+    pub target: Message,
+    /// Up to `k` preceding messages, oldest first.
+    pub context: Vec<Message>,
+}
+
+/// Iterator pairing each message with its preceding `k` messages.
+///
+/// Keeps only the last `k` messages in a ring buffer, so memory stays
+/// `O(k)` however long the input is. Wrap a streaming parser to window
+/// exports that don't fit in memory:
+///
+/// ```no_run
+/// # #[cfg(feature = "streaming")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::core::processor::ContextWindows;
+/// use chatpack::streaming::{StreamingParser, TelegramStreamingParser};
+///
+/// let messages = TelegramStreamingParser::new()
+///     .stream("huge_export.json")?
+///     .filter_map(Result::ok);
+///
+/// for window in ContextWindows::new(messages, 5) {
+///     println!("{} messages of context for {}", window.context.len(), window.target.sender);
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "streaming"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextWindows<I> {
+    inner: I,
+    k: usize,
+    buffer: VecDeque<Message>,
+}
+
+impl<I: Iterator<Item = Message>> ContextWindows<I> {
+    /// Creates a windowing iterator over `messages` with `k` messages of
+    /// context.
+    pub fn new(messages: impl IntoIterator<IntoIter = I>, k: usize) -> Self {
+        Self {
+            inner: messages.into_iter(),
+            k,
+            buffer: VecDeque::with_capacity(k),
+        }
+    }
+}
+
+impl<I: Iterator<Item = Message>> Iterator for ContextWindows<I> {
+    type Item = ContextWindow;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let target = self.inner.next()?;
+        let context = self.buffer.iter().cloned().collect();
+
+        if self.k > 0 {
+            if self.buffer.len() == self.k {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back(target.clone());
+        }
+
+        Some(ContextWindow { target, context })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Pairs every message with the `k` messages before it.
+///
+/// Windows at the start of the conversation are shorter. Each message
+/// counts as one unit, so run [`merge_consecutive`] first to treat a burst
+/// from one sender as a single turn. For inputs that don't fit in memory,
+/// use [`ContextWindows`] directly.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::with_context;
+/// use chatpack::Message;
+///
+/// let messages = vec![
+///     Message::new("Alice", "Hi"),
+///     Message::new("Bob", "Hello"),
+///     Message::new("Alice", "How are you?"),
+/// ];
+///
+/// let windows = with_context(&messages, 2);
+/// assert_eq!(windows.len(), 3);
+/// assert!(windows[0].context.is_empty());
+/// assert_eq!(windows[2].context.len(), 2);
+/// assert_eq!(windows[2].target.content, "How are you?");
+/// ```
+pub fn with_context(messages: &[Message], k: usize) -> Vec<ContextWindow> {
+    ContextWindows::new(messages.iter().cloned(), k).collect()
+}
+
+/// Keeps a uniform random sample of `n` messages, in their original order.
+///
+/// The sample depends only on the input and `seed`; see
+/// [`rand`](crate::core::rand). Returns all messages when `n` is at least
+/// `messages.len()`.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::sample_messages;
+/// use chatpack::core::rand::Seed;
+/// use chatpack::Message;
+///
+/// let messages: Vec<_> = (0..10).map(|i| Message::new("Alice", i.to_string())).collect();
+///
+/// let a = sample_messages(messages.clone(), 3, Seed::default());
+/// let b = sample_messages(messages, 3, Seed::default());
+/// assert_eq!(a.len(), 3);
+/// assert_eq!(a, b);
+/// ```
+pub fn sample_messages(messages: Vec<Message>, n: usize, seed: Seed) -> Vec<Message> {
+    let keep = Rng::new(seed).sample_indices(messages.len(), n);
+    select(messages, &keep)
+}
+
+/// Caps every sender at `max_per_sender` messages, keeping a seeded random
+/// subset of the messages of senders above the cap.
+///
+/// Messages stay in their original order. Senders are processed in sorted
+/// order so the result does not depend on hash iteration order.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::balance_senders;
+/// use chatpack::core::rand::Seed;
+/// use chatpack::Message;
+///
+/// let mut messages: Vec<_> = (0..10).map(|i| Message::new("Alice", i.to_string())).collect();
+/// messages.push(Message::new("Bob", "Hi"));
+///
+/// let balanced = balance_senders(messages, 2, Seed::default());
+/// assert_eq!(balanced.iter().filter(|m| m.sender == "Alice").count(), 2);
+/// assert_eq!(balanced.iter().filter(|m| m.sender == "Bob").count(), 1);
+/// ```
+pub fn balance_senders(messages: Vec<Message>, max_per_sender: usize, seed: Seed) -> Vec<Message> {
+    let mut by_sender: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, msg) in messages.iter().enumerate() {
+        by_sender.entry(msg.sender.as_str()).or_default().push(i);
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut keep = Vec::with_capacity(messages.len());
+    for indices in by_sender.values() {
+        let picked = rng.sample_indices(indices.len(), max_per_sender);
+        keep.extend(picked.into_iter().map(|i| indices[i]));
+    }
+    keep.sort_unstable();
+
+    select(messages, &keep)
+}
+
+/// Keeps the messages at the ascending positions in `keep`.
+fn select(messages: Vec<Message>, keep: &[usize]) -> Vec<Message> {
+    let mut keep = keep.iter().copied().peekable();
+    messages
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, msg)| keep.next_if_eq(&i).map(|_| msg))
+        .collect()
+}
+
+/// Replaces every sender with a pseudonym (`"User 1"`, `"User 2"`, ...).
+///
+/// Pseudonym numbers are assigned to the distinct senders in a seeded random
+/// order, so numbering does not reveal who spoke first. Returns the mapping
+/// from original sender to pseudonym so it can be stored or reversed.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::anonymize_senders;
+/// use chatpack::core::rand::Seed;
+/// use chatpack::Message;
+///
+/// let mut messages = vec![Message::new("Alice", "Hi"), Message::new("Bob", "Hey")];
+/// let mapping = anonymize_senders(&mut messages, Seed::default());
+///
+/// assert_eq!(mapping.len(), 2);
+/// assert_eq!(messages[0].sender, mapping["Alice"]);
+/// assert!(messages[1].sender.starts_with("User "));
+/// ```
+pub fn anonymize_senders(messages: &mut [Message], seed: Seed) -> BTreeMap<String, String> {
+    let mut senders: Vec<String> = messages
+        .iter()
+        .map(|m| m.sender.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    senders.sort_unstable();
+    Rng::new(seed).shuffle(&mut senders);
+
+    let mapping: BTreeMap<String, String> = senders
+        .into_iter()
+        .enumerate()
+        .map(|(i, sender)| (sender, format!("User {}", i + 1)))
+        .collect();
+    for msg in messages.iter_mut() {
+        msg.sender.clone_from(&mapping[&msg.sender]);
+    }
+    mapping
+}
+
+/// Groups messages by their forum [`topic`](Message::topic), keeping each
+/// group in its original order.
+///
+/// Messages without a topic are grouped under `None`. Use this to write
+/// one output per topic of a Telegram forum export.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::partition_by_topic;
+/// use chatpack::Message;
+///
+/// let messages = vec![
+///     Message::new("Alice", "Shipped!").with_topic("Releases"),
+///     Message::new("Bob", "Hi").with_topic("General"),
+///     Message::new("Bob", "Nice").with_topic("Releases"),
+/// ];
+///
+/// let topics = partition_by_topic(messages);
+/// assert_eq!(topics[&Some("Releases".to_string())].len(), 2);
+/// assert_eq!(topics.keys().next(), Some(&Some("General".to_string())));
+/// ```
+pub fn partition_by_topic(messages: Vec<Message>) -> BTreeMap<Option<String>, Vec<Message>> {
+    let mut topics: BTreeMap<Option<String>, Vec<Message>> = BTreeMap::new();
+    for msg in messages {
+        topics.entry(msg.topic.clone()).or_default().push(msg);
+    }
+    topics
+}
+
+/// How many messages at the start of a group [`suggest_title`] reads.
+const TITLE_MESSAGES: usize = 10;
+
+/// How many words [`suggest_title`] puts in a title at most.
+const TITLE_WORDS: usize = 3;
+
+/// Words too common to say what a conversation is about: English,
+/// Russian, and the device names Windows reserves as file names.
+const STOPWORDS: &str = "
+    about above after again all also and any are aren because been before being but can
+    could couldn did didn does doesn doing don down for from get going gonna good got had
+    has have hello her here hers hey him his how into isn its just know let like lol maybe
+    more most not now off okay once one only other our out over really right said say see
+    she should shouldn some still such sure than thanks that the their them then there these
+    they think this those through too very want was wasn well were what when where which
+    while who why will with won would wouldn yeah yes you your yours
+    без будет будут был была были было быть вам вас ведь вот всё все всего всех где давай даже для его
+    её если есть ещё еще зачем здесь или как какая какой когда конечно кто куда ладно лучше
+    меня мне может можно мой моя надо нас него нет неё нее ней них нужно ничего нибудь окей она
+    они очень под потом потому привет при про просто раз сам себе себя сейчас спасибо так
+    такой там тебе тебя тем теперь тоже только том тот тут уже хорошо хоть чего чем через
+    что чтобы чтоб эта эти это этого этой этом этот эту
+    aux con nul prn
+";
+
+/// Suggests a short, filesystem-safe title for a group of messages.
+///
+/// Picks the most frequent meaningful words in the first few messages:
+/// words of at least three characters that are not numbers, URLs, or
+/// English or Russian stopwords. Ties go to the word used first. Words
+/// are lowercased and joined with `-`, in order of frequency, until the
+/// next word would take the slug past `max_len` bytes. A first word that
+/// is too long on its own is cut between grapheme clusters.
+///
+/// The result holds only `-` and grapheme clusters that start with a
+/// letter or digit. These may come from any script, so the result is not
+/// limited to ASCII, but it never holds spaces, dots, slashes, or control
+/// characters, so it is safe as part of a file name on any platform.
+///
+/// Returns `None` when no word qualifies, for example in an emoji-only
+/// conversation, or when `max_len` is too small for any of them. The
+/// result depends only on the messages, never on hash order.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::suggest_title;
+/// use chatpack::Message;
+///
+/// let messages = vec![
+///     Message::new("Alice", "Planning the trip: what's our budget?"),
+///     Message::new("Bob", "Budget is tight, so trip by train"),
+///     Message::new("Alice", "Train it is. Planning done!"),
+/// ];
+///
+/// assert_eq!(suggest_title(&messages, 40).as_deref(), Some("planning-trip-budget"));
+/// assert_eq!(suggest_title(&messages, 14).as_deref(), Some("planning-trip"));
+/// assert_eq!(suggest_title(&[Message::new("Bob", "👍🎉")], 40), None);
+/// ```
+pub fn suggest_title(messages: &[Message], max_len: usize) -> Option<String> {
+    // Word -> (count, first position)
+    let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for msg in messages.iter().take(TITLE_MESSAGES) {
+        for word in title_words(&msg.content) {
+            let next = counts.len();
+            counts.entry(word).or_insert((0, next)).0 += 1;
+        }
+    }
+    let mut ranked: Vec<(String, (usize, usize))> = counts.into_iter().collect();
+    ranked.sort_by(|(_, (a_count, a_pos)), (_, (b_count, b_pos))| {
+        b_count.cmp(a_count).then(a_pos.cmp(b_pos))
+    });
+
+    let mut slug = String::new();
+    for (word, _) in ranked.into_iter().take(TITLE_WORDS) {
+        if slug.is_empty() {
+            slug.push_str(textutil::prefix_bytes(&word, max_len));
+        } else if slug.len() + 1 + word.len() <= max_len {
+            slug.push('-');
+            slug.push_str(&word);
+        } else {
+            break;
+        }
+    }
+    (!slug.is_empty()).then_some(slug)
+}
+
+/// Returns the lowercased candidate title words of `text`, in order.
+fn title_words(text: &str) -> Vec<String> {
+    let mut without_urls = String::with_capacity(text.len());
+    let mut end = 0;
+    for url in links::find_urls(text) {
+        without_urls.push_str(&text[end..url.start]);
+        without_urls.push(' ');
+        end = url.end;
+    }
+    without_urls.push_str(&text[end..]);
+
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let is_word_char = |g: &str| g.chars().next().is_some_and(char::is_alphanumeric);
+    for grapheme in textutil::split_graphemes(&without_urls).chain([" "]) {
+        if is_word_char(grapheme) {
+            word.extend(grapheme.chars().flat_map(char::to_lowercase));
+            continue;
+        }
+        if textutil::len_graphemes(&word) >= 3
+            && !word.chars().all(|c| c.is_numeric())
+            && !STOPWORDS.split_whitespace().any(|stop| stop == word)
+        {
+            words.push(std::mem::take(&mut word));
+        }
+        word.clear();
+    }
+    words
+}
+
+/// Suggests a distinct file name for each group of messages, such as
+/// `2024-01-15_planning-trip-budget.jsonl`.
+///
+/// A name is the UTC date of the group's first timestamped message and
+/// its [`suggest_title`] (limited to `max_len` bytes), joined by `_`.
+/// Without a timestamp, the group's 1-based index, padded to four digits,
+/// stands in for the date; without a title, the date or index is the
+/// whole name. Names that would repeat get `-2`, `-3`, and so on before
+/// the extension, in group order, so partitioned output never overwrites
+/// itself.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::suggest_file_names;
+/// use chatpack::Message;
+/// use chrono::{TimeZone, Utc};
+///
+/// let day = Utc.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap();
+/// let groups = vec![
+///     vec![Message::new("Alice", "Budget for the trip").with_timestamp(day)],
+///     vec![Message::new("Bob", "The budget for a trip").with_timestamp(day)],
+///     vec![Message::new("Bob", "🎉")],
+/// ];
+///
+/// let names = suggest_file_names(groups.iter().map(Vec::as_slice), 40, "jsonl");
+/// assert_eq!(
+///     names,
+///     [
+///         "2024-01-15_budget-trip.jsonl",
+///         "2024-01-15_budget-trip-2.jsonl",
+///         "0003.jsonl",
+///     ]
+/// );
+/// ```
+pub fn suggest_file_names<'a>(
+    groups: impl IntoIterator<Item = &'a [Message]>,
+    max_len: usize,
+    extension: &str,
+) -> Vec<String> {
+    let mut used = HashSet::new();
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let prefix = group.iter().find_map(|m| m.timestamp).map_or_else(
+                || format!("{:04}", i + 1),
+                |ts| ts.format("%Y-%m-%d").to_string(),
+            );
+            let stem = match suggest_title(group, max_len) {
+                Some(title) => format!("{prefix}_{title}"),
+                None => prefix,
+            };
+            let mut name = format!("{stem}.{extension}");
+            let mut n = 2;
+            while !used.insert(name.clone()) {
+                name = format!("{stem}-{n}.{extension}");
+                n += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+/// How far a list of messages is from chronological order, from
+/// [`check_ordering`] or an [`OrderingCheck`].
+///
+/// Each timestamped message is compared with the timestamped message
+/// before it, so an export made by concatenating several sorted ones
+/// shows one inversion per seam, and clock skew between devices shows as
+/// inversions of a few seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrderingReport {
+    /// Messages older than the timestamped message before them.
+    pub inversions: usize,
+
+    /// Inversions going back more than [`CLOCK_SKEW`](Self::CLOCK_SKEW).
+    pub large_inversions: usize,
+
+    /// Largest step back in time between neighbouring timestamped
+    /// messages, zero when there is none. Serialized as
+    /// `max_backward_jump_ms`.
+    #[serde(rename = "max_backward_jump_ms", with = "millis")]
+    pub max_backward_jump: Duration,
+}
+
+impl OrderingReport {
+    /// Backward steps up to this long are put down to clock skew between
+    /// devices and do not make the input [unsorted](Self::appears_unsorted).
+    pub const CLOCK_SKEW: Duration = Duration::minutes(1);
+
+    /// Returns `true` if no message is older than the one before it.
+    pub fn is_sorted(&self) -> bool {
+        self.inversions == 0
+    }
+
+    /// Returns `true` if some message goes back further than
+    /// [`CLOCK_SKEW`](Self::CLOCK_SKEW), so merging and anything else that
+    /// relies on input order would misbehave. Fix it with [`sort_messages`].
+    pub fn appears_unsorted(&self) -> bool {
+        self.large_inversions > 0
+    }
+
+    /// Adds the counts of `other`, as for another input of one run.
+    pub fn add(&mut self, other: &OrderingReport) {
+        self.inversions += other.inversions;
+        self.large_inversions += other.large_inversions;
+        self.max_backward_jump = self.max_backward_jump.max(other.max_backward_jump);
+    }
+}
+
+/// Builds an [`OrderingReport`] one message at a time, for messages that
+/// are streamed rather than collected.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::core::processor::OrderingCheck;
+/// use chatpack::Message;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let noon = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+/// let mut check = OrderingCheck::new();
+/// check.observe(&Message::new("Alice", "Hi").with_timestamp(noon));
+/// check.observe(&Message::new("Bob", "Hey").with_timestamp(noon - Duration::hours(2)));
+///
+/// let report = check.report();
+/// assert_eq!(report.inversions, 1);
+/// assert_eq!(report.max_backward_jump, Duration::hours(2));
+/// assert!(report.appears_unsorted());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OrderingCheck {
+    last: Option<DateTime<Utc>>,
+    report: OrderingReport,
+}
+
+impl OrderingCheck {
+    /// Creates a check that has seen no messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compares `msg` with the last timestamped message seen. Messages
+    /// without a timestamp are skipped.
+    pub fn observe(&mut self, msg: &Message) {
+        let Some(ts) = msg.timestamp else {
+            return;
+        };
+        if let Some(last) = self.last {
+            let jump = last - ts;
+            if jump > Duration::zero() {
+                self.report.inversions += 1;
+                if jump > OrderingReport::CLOCK_SKEW {
+                    self.report.large_inversions += 1;
+                }
+                self.report.max_backward_jump = self.report.max_backward_jump.max(jump);
+            }
+        }
+        self.last = Some(ts);
+    }
+
+    /// Returns the report of the messages seen so far.
+    pub fn report(&self) -> OrderingReport {
+        self.report
+    }
+}
+
+/// Reports how far `messages` are from chronological order, in one pass.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::core::processor::{check_ordering, sort_messages};
+/// use chatpack::Message;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let at = |hour| Utc.with_ymd_and_hms(2024, 1, 15, hour, 0, 0).unwrap();
+/// // Two channels' exports, one after the other
+/// let mut messages = vec![
+///     Message::new("Alice", "general 1").with_timestamp(at(9)),
+///     Message::new("Bob", "general 2").with_timestamp(at(11)),
+///     Message::new("Alice", "random 1").with_timestamp(at(10)),
+///     Message::new("Carol", "random 2").with_timestamp(at(12)),
+/// ];
+///
+/// let report = check_ordering(&messages);
+/// assert_eq!(report.inversions, 1);
+/// assert_eq!(report.max_backward_jump, Duration::hours(1));
+///
+/// sort_messages(&mut messages);
+/// assert!(check_ordering(&messages).is_sorted());
+/// ```
+pub fn check_ordering(messages: &[Message]) -> OrderingReport {
+    let mut check = OrderingCheck::new();
+    for msg in messages {
+        check.observe(msg);
+    }
+    check.report()
+}
+
+/// Sorts `messages` oldest first with [`Message::cmp_chronological`].
+///
+/// The sort is stable, so messages with equal timestamps keep their
+/// order; messages without a timestamp move to the front.
+pub fn sort_messages(messages: &mut [Message]) {
+    messages.sort_by(Message::cmp_chronological);
+}
+
+/// Serializes a [`Duration`] as whole milliseconds.
+mod millis {
+    use super::{Deserialize, Deserializer, Duration, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_i64(d.num_milliseconds())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        i64::deserialize(d).map(Duration::milliseconds)
+    }
+}
+
+/// Statistics about the processing result.
+///
+/// Provides information about how many messages were processed
+/// and the compression achieved through merging.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::core::processor::ProcessingStats;
+///
+/// let stats = ProcessingStats::new(100, 60);
+/// println!("Compression: {:.1}%", stats.compression_ratio()); // 40.0%
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessingStats {
+    /// Number of messages before processing
+    pub original_count: usize,
+
+    /// Number of messages after merging
+    pub merged_count: usize,
+
+    /// Number of messages after filtering (if filtering was applied)
+    pub filtered_count: Option<usize>,
+
+    /// Seed used by randomized steps (sampling, balancing, anonymization),
+    /// recorded so the run can be reproduced
+    pub seed: Option<Seed>,
+
+    /// What the run left out, when it was tracked
+    pub loss: LossReport,
+
+    /// How far the parsed input was from chronological order, when it was
+    /// checked
+    pub ordering: OrderingReport,
+}
+
+impl ProcessingStats {
+    /// Creates new statistics with original and merged counts.
+    pub fn new(original: usize, merged: usize) -> Self {
+        Self {
+            original_count: original,
+            merged_count: merged,
+            filtered_count: None,
+            seed: None,
+            loss: LossReport::new(),
+            ordering: OrderingReport::default(),
+        }
+    }
+
+    /// Sets the filtered count.
+    #[must_use]
+    pub fn with_filtered(mut self, filtered: usize) -> Self {
+        self.filtered_count = Some(filtered);
+        self
+    }
+
+    /// Records the seed used by randomized steps.
+    #[must_use]
+    pub fn with_seed(mut self, seed: Seed) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Records what the run left out.
+    #[must_use]
+    pub fn with_loss(mut self, loss: LossReport) -> Self {
+        self.loss = loss;
+        self
+    }
+
+    /// Records how far the parsed input was from chronological order.
+    #[must_use]
+    pub fn with_ordering(mut self, ordering: OrderingReport) -> Self {
+        self.ordering = ordering;
         self
     }
 
@@ -238,6 +1897,33 @@ mod tests {
         assert_eq!(merged.len(), 1);
     }
 
+    #[test]
+    fn test_merge_stops_at_source_file() {
+        let messages = vec![
+            Message::new("Alice", "Hi").with_source_file(0),
+            Message::new("Alice", "Again").with_source_file(0),
+            Message::new("Alice", "Other chat").with_source_file(1),
+        ];
+        let merged = merge_consecutive(messages);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[1].content, "Other chat");
+    }
+
+    #[test]
+    fn test_merge_stops_at_platform() {
+        use crate::platform::Platform;
+
+        let messages = vec![
+            Message::new("Alice", "Hi").with_platform(Platform::Telegram),
+            Message::new("Alice", "Again").with_platform(Platform::Telegram),
+            Message::new("Alice", "Hi again").with_platform(Platform::WhatsApp),
+        ];
+        let merged = merge_consecutive(messages);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].content, "Hi\nAgain");
+        assert_eq!(merged[1].platform, Some(Platform::WhatsApp));
+    }
+
     #[test]
     fn test_merge_preserves_metadata() {
         use chrono::{TimeZone, Utc};
@@ -255,6 +1941,54 @@ mod tests {
         assert_eq!(merged[0].id, Some(1)); // First message's ID preserved
     }
 
+    #[test]
+    fn test_merge_sender_match_modes() {
+        let messages = vec![
+            Message::new("alice", "a"),
+            Message::new("Alice", "b"),
+            Message::new("ALICE", "c"),
+            Message::new("Алия", "d"),
+            Message::new("АЛИЯ", "e"),
+            Message::new("Алия\u{200B}", "f"),
+            Message::new("Bob", "g"),
+        ];
+        let merged_len = |sender_match| {
+            let config = MergeConfig::new().with_sender_match(sender_match);
+            merge_consecutive_with_config(messages.clone(), &config, &mut LossReport::new()).len()
+        };
+
+        assert_eq!(merged_len(SenderMatch::Exact), 7);
+        assert_eq!(merged_len(SenderMatch::CaseInsensitive), 4);
+        assert_eq!(merged_len(SenderMatch::Normalized), 3);
+        assert_eq!(merge_consecutive(messages.clone()).len(), 4);
+    }
+
+    #[test]
+    fn test_merge_keeps_first_sender_casing() {
+        let messages = vec![
+            Message::new("alice", "Hi"),
+            Message::new("Alice", "Again"),
+            Message::new("ALICE", "And again"),
+        ];
+        let mut loss = LossReport::new();
+
+        let merged = merge_consecutive_with_loss(messages, &mut loss);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].sender, "alice");
+        assert_eq!(merged[0].content, "Hi\nAgain\nAnd again");
+        assert_eq!(loss.messages_merged, 2);
+    }
+
+    #[test]
+    fn test_merge_config_serde() {
+        let config: MergeConfig =
+            serde_json::from_str(r#"{"sender_match": "normalized"}"#).unwrap();
+        assert_eq!(config.sender_match, SenderMatch::Normalized);
+        let config: MergeConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, MergeConfig::default());
+    }
+
     #[test]
     fn test_compression_ratio() {
         let stats = ProcessingStats::new(100, 50);
@@ -288,4 +2022,774 @@ mod tests {
         assert!(display.contains("60"));
         assert!(display.contains("40.0%"));
     }
+
+    // =========================================================================
+    // map_senders tests
+    // =========================================================================
+
+    #[test]
+    fn test_map_senders_case_insensitive_exact() {
+        let mut messages = vec![
+            Message::new("JOHN SMITH", "a"),
+            Message::new("John", "b"),
+            Message::new("John Smithson", "c"),
+        ];
+        let map = SenderMap::new().with_alias("John Smith", "John");
+
+        let report = map_senders(&mut messages, &map);
+
+        assert_eq!(report.remapped, 1);
+        assert_eq!(messages[0].sender, "John");
+        assert_eq!(messages[1].sender, "John");
+        // No substring matching
+        assert_eq!(messages[2].sender, "John Smithson");
+    }
+
+    #[test]
+    fn test_map_senders_ignores_invisible_characters() {
+        let mut messages = vec![
+            Message::new("Алия\u{200B}", "a"),
+            Message::new("\u{200F}АЛИЯ", "b"),
+        ];
+        let map = SenderMap::new().with_alias("алия", "Aliya");
+
+        let report = map_senders(&mut messages, &map);
+
+        assert_eq!(report.remapped, 2);
+        assert_eq!(senders(&messages), ["Aliya", "Aliya"]);
+    }
+
+    #[test]
+    fn test_map_senders_unmapped_untouched() {
+        let mut messages = vec![Message::new("Alice", "a"), Message::new("Bob", "b")];
+        let map: SenderMap = [("Carol", "C")].into_iter().collect();
+
+        let report = map_senders(&mut messages, &map);
+
+        assert_eq!(report, SenderMapReport::default());
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[1].sender, "Bob");
+    }
+
+    #[test]
+    fn test_map_senders_collisions() {
+        let mut messages = vec![
+            Message::new("Ann", "a"),
+            Message::new("Anna K", "b"),
+            Message::new("Bob", "c"),
+            Message::new("Robert", "d"),
+        ];
+        // Alias groups, including one onto a sender already present, are
+        // not collisions
+        let map = SenderMap::new()
+            .with_alias("Ann", "A.")
+            .with_alias("Anna K", "A.")
+            .with_alias("Robert", "Bob");
+        let report = map_senders(&mut messages, &map);
+        assert_eq!(report.remapped, 3);
+        assert_eq!(report.collisions, 0);
+
+        // One alias given two canonical names is
+        let mut messages = vec![Message::new("Ann", "a")];
+        let map = SenderMap::new()
+            .with_alias("Ann", "A.")
+            .with_alias("ANN", "Anna")
+            .with_alias("ann", "Anna");
+        let report = map_senders(&mut messages, &map);
+        assert_eq!(report.collisions, 1);
+        assert_eq!(messages[0].sender, "Anna");
+    }
+
+    #[test]
+    fn test_map_senders_canonical_already_used() {
+        let mut messages = vec![Message::new("John", "a")];
+        let map = SenderMap::new().with_alias("john", "John");
+
+        let report = map_senders(&mut messages, &map);
+
+        // Mapping onto itself is not a rename
+        assert_eq!(report.remapped, 0);
+        assert_eq!(report.collisions, 0);
+    }
+
+    fn senders(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.sender.as_str()).collect()
+    }
+
+    #[test]
+    fn test_relabel_perspective_aliases() {
+        let mut messages = vec![
+            Message::new("Berik", "a"),
+            Message::new("BERIK WORK", "b"),
+            Message::new("Berik W", "c"),
+            Message::new("Alice", "d"),
+        ];
+        let relabeled = relabel_perspective(
+            &mut messages,
+            &["berik", "Berik Work"],
+            &RelabelConfig::new(),
+        );
+
+        assert_eq!(relabeled, 4);
+        assert_eq!(senders(&messages), ["me", "me", "them", "them"]);
+    }
+
+    #[test]
+    fn test_relabel_perspective_original_sender() {
+        let mut messages = vec![
+            Message::new("Berik", "a"),
+            Message::new("Alice", "b"),
+            Message::new("me", "c"),
+        ];
+        relabel_perspective(&mut messages, &["Berik", "me"], &RelabelConfig::new());
+        assert_eq!(messages[0].original_sender.as_deref(), Some("Berik"));
+        assert_eq!(messages[1].original_sender.as_deref(), Some("Alice"));
+        // Already labeled, so nothing to keep
+        assert_eq!(messages[2].original_sender, None);
+
+        // Relabeling again keeps the first name
+        relabel_perspective(&mut messages, &["me"], &RelabelConfig::chat_roles());
+        assert_eq!(senders(&messages), ["assistant", "user", "assistant"]);
+        assert_eq!(messages[0].original_sender.as_deref(), Some("Berik"));
+
+        let mut messages = vec![Message::new("Alice", "a")];
+        let config = RelabelConfig::new().with_keep_original_sender(false);
+        relabel_perspective(&mut messages, &["Berik"], &config);
+        assert_eq!(messages[0].original_sender, None);
+    }
+
+    #[test]
+    fn test_relabel_perspective_without_collapse() {
+        let mut messages = vec![
+            Message::new("Berik", "a"),
+            Message::new("Alice", "b"),
+            Message::new("Bob", "c"),
+        ];
+        let config = RelabelConfig::new().with_collapse_others(false);
+        assert_eq!(relabel_perspective(&mut messages, &["Berik"], &config), 1);
+        assert_eq!(senders(&messages), ["me", "Alice", "Bob"]);
+        assert_eq!(merge_consecutive(messages).len(), 3);
+    }
+
+    #[test]
+    fn test_relabel_perspective_then_merge() {
+        let mut messages = vec![
+            Message::new("Alice", "a"),
+            Message::new("Bob", "b"),
+            Message::new("Berik", "c"),
+        ];
+        relabel_perspective(&mut messages, &["Berik"], &RelabelConfig::new());
+
+        // Different people on the other side merge into one turn
+        let merged = merge_consecutive(messages);
+        assert_eq!(senders(&merged), ["them", "me"]);
+        assert_eq!(merged[0].content, "a\nb");
+        assert_eq!(merged[0].original_sender.as_deref(), Some("Alice"));
+    }
+
+    #[cfg(feature = "json-output")]
+    #[test]
+    fn test_sender_map_from_json() {
+        let map =
+            SenderMap::from_json(r#"{"aliases": {"John Smith": "John", "+1 555 0100": "John"}}"#)
+                .unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.canonical("john smith"), Some("John"));
+        assert_eq!(map.canonical("+1 555 0100"), Some("John"));
+
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(SenderMap::from_json(&json).unwrap(), map);
+        assert!(SenderMap::from_json(r#"{"aliases": []}"#).is_err());
+
+        let map = SenderMap::from_json(r#"{"aliases": {"John": "A", "JOHN": "B"}}"#).unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map_senders(&mut [], &map).collisions, 1);
+    }
+
+    // =========================================================================
+    // extract_dialogue tests
+    // =========================================================================
+
+    fn group_chat() -> Vec<Message> {
+        use chrono::{TimeZone, Utc};
+
+        let at = |minute: u32| Utc.with_ymd_and_hms(2024, 1, 1, 12, minute, 0).unwrap();
+        vec![
+            Message::new("Alice", "Morning all").with_timestamp(at(0)),
+            Message::new("Carol", "Hi").with_timestamp(at(1)),
+            Message::new("Dave", "Hey").with_timestamp(at(1)),
+            Message::new("Carol", "Coffee?").with_timestamp(at(2)),
+            Message::new("Bob", "Morning Alice").with_timestamp(at(3)),
+            Message::new("Alice", "How was the trip?").with_timestamp(at(4)),
+            Message::new("Bob", "Great").with_timestamp(at(5)),
+            Message::new("Carol", "Lunch later?").with_timestamp(at(40)),
+            Message::new("Alice", "Lonely remark").with_timestamp(at(50)),
+            Message::new("Alice", "Still lonely").with_timestamp(at(51)),
+            Message::new("Alice", "Very lonely").with_timestamp(at(52)),
+            Message::new("Alice", "Extremely lonely").with_timestamp(at(53)),
+        ]
+    }
+
+    fn contents(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_extract_dialogue_drops_third_parties_and_lonely() {
+        let config = DialogueConfig::new()
+            .with_window_messages(1)
+            .with_window_duration(Duration::minutes(5));
+        let dialogue = extract_dialogue(&group_chat(), "alice", "BOB", &config);
+
+        // Carol and Dave in between do not widen the gap between Alice and
+        // Bob. "Lonely remark" directly follows "Great" among the pair's
+        // messages; the later ones are 2+ positions and 45+ minutes away.
+        assert_eq!(
+            contents(&dialogue),
+            [
+                "Morning all",
+                "Morning Alice",
+                "How was the trip?",
+                "Great",
+                "Lonely remark"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_dialogue_message_window() {
+        let config = DialogueConfig::new()
+            .with_window_messages(2)
+            .without_window_duration();
+        let dialogue = extract_dialogue(&group_chat(), "Alice", "Bob", &config);
+
+        // "Still lonely" is two of the pair's messages after "Great"
+        assert_eq!(dialogue.len(), 6);
+        assert_eq!(dialogue[5].content, "Still lonely");
+    }
+
+    #[test]
+    fn test_extract_dialogue_time_window() {
+        let config = DialogueConfig::new()
+            .with_window_messages(0)
+            .with_window_duration(Duration::hours(1));
+        let dialogue = extract_dialogue(&group_chat(), "Alice", "Bob", &config);
+
+        // Every message of the pair is within an hour of the other's
+        assert_eq!(dialogue.len(), 8);
+
+        let config = config.with_window_duration(Duration::minutes(2));
+        let dialogue = extract_dialogue(&group_chat(), "Alice", "Bob", &config);
+        assert_eq!(
+            contents(&dialogue),
+            ["Morning Alice", "How was the trip?", "Great"]
+        );
+    }
+
+    #[test]
+    fn test_extract_dialogue_without_timestamps() {
+        let messages = vec![
+            Message::new("Alice", "a1"),
+            Message::new("Alice", "a2"),
+            Message::new("Alice", "a3"),
+            Message::new("Bob", "b1"),
+        ];
+        let config = DialogueConfig::new().with_window_messages(1);
+        let dialogue = extract_dialogue(&messages, "Alice", "Bob", &config);
+
+        assert_eq!(contents(&dialogue), ["a3", "b1"]);
+    }
+
+    #[test]
+    fn test_extract_dialogue_same_person() {
+        let config = DialogueConfig::new();
+        assert!(extract_dialogue(&group_chat(), "Alice", "alice", &config).is_empty());
+        assert!(extract_dialogue(&group_chat(), "Alice", "Zed", &config).is_empty());
+    }
+
+    // =========================================================================
+    // trim_to_active_spans tests
+    // =========================================================================
+
+    /// A conversation, three hours of announcements, then another one.
+    fn announcement_stretch() -> Vec<Message> {
+        use chrono::{TimeZone, Utc};
+
+        let at =
+            |hour: u32, minute: u32| Utc.with_ymd_and_hms(2024, 1, 1, hour, minute, 0).unwrap();
+        vec![
+            Message::new("Alice", "Morning").with_timestamp(at(9, 0)),
+            Message::new("Bob", "Morning!").with_timestamp(at(9, 10)),
+            Message::new("Alice", "Standup?").with_timestamp(at(9, 20)),
+            Message::new("Admin", "Office closed Friday").with_timestamp(at(10, 20)),
+            Message::new("Admin", "Parking update").with_timestamp(at(11, 30)),
+            Message::new("Admin", "Fire drill at 2").with_timestamp(at(12, 45)),
+            Message::new("Admin", "Reminder").with_timestamp(at(13, 50)),
+            Message::new("Carol", "Lunch?").with_timestamp(at(14, 50)),
+            Message::new("Bob", "Sure").with_timestamp(at(14, 55)),
+        ]
+    }
+
+    #[test]
+    fn test_trim_removes_announcement_stretch() {
+        let trimmed = trim_to_active_spans(announcement_stretch(), &ActivityConfig::new());
+
+        // "Office closed Friday" is exactly an hour after "Standup?", and
+        // "Reminder" exactly an hour before "Lunch?"; both ends are inclusive
+        assert_eq!(
+            contents(&trimmed),
+            [
+                "Morning",
+                "Morning!",
+                "Standup?",
+                "Office closed Friday",
+                "Reminder",
+                "Lunch?",
+                "Sure"
+            ]
+        );
+
+        let config = ActivityConfig::new().with_window(Duration::minutes(59));
+        let trimmed = trim_to_active_spans(announcement_stretch(), &config);
+        assert_eq!(
+            contents(&trimmed),
+            ["Morning", "Morning!", "Standup?", "Lunch?", "Sure"]
+        );
+    }
+
+    #[test]
+    fn test_trim_min_senders() {
+        let config = ActivityConfig::new().with_min_senders(3);
+        assert!(trim_to_active_spans(announcement_stretch(), &config).is_empty());
+
+        // With two-hour windows, the first and last announcements each
+        // share one with two other senders
+        let config = config.with_window(Duration::hours(2));
+        let trimmed = trim_to_active_spans(announcement_stretch(), &config);
+        assert_eq!(
+            contents(&trimmed),
+            [
+                "Morning",
+                "Morning!",
+                "Standup?",
+                "Office closed Friday",
+                "Reminder",
+                "Lunch?",
+                "Sure"
+            ]
+        );
+
+        let config = ActivityConfig::new().with_min_senders(1);
+        assert_eq!(
+            trim_to_active_spans(announcement_stretch(), &config).len(),
+            9
+        );
+    }
+
+    #[test]
+    fn test_trim_drops_untimed_messages() {
+        let mut messages = announcement_stretch();
+        messages.insert(1, Message::new("Bob", "No timestamp"));
+        let config = ActivityConfig::new().with_min_senders(1);
+        let trimmed = trim_to_active_spans(messages, &config);
+        assert_eq!(trimmed.len(), 9);
+        assert!(trimmed.iter().all(|m| m.content != "No timestamp"));
+    }
+
+    // =========================================================================
+    // with_context tests
+    // =========================================================================
+
+    fn numbered(n: usize) -> Vec<Message> {
+        (0..n)
+            .map(|i| Message::new(if i % 2 == 0 { "Alice" } else { "Bob" }, i.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_with_context_boundaries() {
+        let windows = with_context(&numbered(5), 2);
+
+        assert_eq!(windows.len(), 5);
+        assert!(windows[0].context.is_empty());
+        assert_eq!(contents(&windows[1].context), ["0"]);
+        assert_eq!(contents(&windows[2].context), ["0", "1"]);
+        assert_eq!(contents(&windows[4].context), ["2", "3"]);
+        assert_eq!(windows[4].target.content, "4");
+    }
+
+    #[test]
+    fn test_with_context_zero_and_large_k() {
+        let messages = numbered(3);
+
+        assert!(
+            with_context(&messages, 0)
+                .iter()
+                .all(|w| w.context.is_empty())
+        );
+
+        let windows = with_context(&messages, 10);
+        assert_eq!(contents(&windows[2].context), ["0", "1"]);
+        assert!(with_context(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn test_with_context_merged_units() {
+        let messages = vec![
+            Message::new("Alice", "Hi"),
+            Message::new("Alice", "You there?"),
+            Message::new("Bob", "Yes"),
+            Message::new("Alice", "Great"),
+        ];
+
+        let windows = with_context(&merge_consecutive(messages), 1);
+
+        assert_eq!(windows.len(), 3);
+        assert_eq!(contents(&windows[1].context), ["Hi\nYou there?"]);
+        assert_eq!(contents(&windows[2].context), ["Yes"]);
+    }
+
+    #[test]
+    fn test_context_windows_buffer_bounded() {
+        let mut windows = ContextWindows::new(numbered(100), 3);
+        let last = windows.by_ref().last().unwrap();
+
+        assert_eq!(contents(&last.context), ["96", "97", "98"]);
+        assert!(windows.buffer.len() <= 3);
+    }
+
+    // =========================================================================
+    // Seeded sampling tests
+    // =========================================================================
+
+    fn chat_of(n: usize) -> Vec<Message> {
+        (0..n)
+            .map(|i| Message::new(["Alice", "Bob", "Carol"][i % 3], i.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_sample_messages_reproducible() {
+        let a = sample_messages(chat_of(100), 10, Seed::new(1));
+        let b = sample_messages(chat_of(100), 10, Seed::new(1));
+        let c = sample_messages(chat_of(100), 10, Seed::new(2));
+
+        assert_eq!(a.len(), 10);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let order: Vec<usize> = a.iter().map(|m| m.content.parse().unwrap()).collect();
+        assert!(order.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(sample_messages(chat_of(5), 10, Seed::default()), chat_of(5));
+    }
+
+    #[test]
+    fn test_balance_senders_reproducible() {
+        let mut messages = chat_of(60);
+        messages.push(Message::new("Dave", "Hi"));
+
+        let a = balance_senders(messages.clone(), 5, Seed::new(1));
+        let b = balance_senders(messages.clone(), 5, Seed::new(1));
+        let c = balance_senders(messages, 5, Seed::new(2));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        for sender in ["Alice", "Bob", "Carol"] {
+            assert_eq!(a.iter().filter(|m| m.sender == sender).count(), 5);
+        }
+        assert_eq!(a.iter().filter(|m| m.sender == "Dave").count(), 1);
+    }
+
+    #[test]
+    fn test_anonymize_senders_reproducible() {
+        let senders: Vec<String> = (0..20).map(|i| format!("Person {i}")).collect();
+        let chat: Vec<Message> = senders.iter().map(|s| Message::new(s, "Hi")).collect();
+
+        let mut a = chat.clone();
+        let mut b = chat.clone();
+        let mut c = chat;
+        let mapping = anonymize_senders(&mut a, Seed::new(1));
+        anonymize_senders(&mut b, Seed::new(1));
+        anonymize_senders(&mut c, Seed::new(2));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(mapping.len(), 20);
+        assert_eq!(a[0].sender, mapping["Person 0"]);
+        assert!(a.iter().all(|m| m.sender.starts_with("User ")));
+    }
+
+    // =========================================================================
+    // Title suggestion tests
+    // =========================================================================
+
+    #[test]
+    fn test_suggest_title() {
+        let chat = vec![
+            Message::new("Alice", "Can we talk about the release schedule?"),
+            Message::new(
+                "Bob",
+                "Sure, the release is Friday. See https://example.com/release",
+            ),
+            Message::new("Alice", "Friday works. Schedule the release notes too"),
+        ];
+        assert_eq!(
+            suggest_title(&chat, 64).as_deref(),
+            Some("release-schedule-friday")
+        );
+
+        let russian = vec![
+            Message::new("Алиса", "Когда будет ОТПУСК? Нужно спланировать отпуск"),
+            Message::new("Боб", "Отпуск в июле, это точно"),
+        ];
+        assert_eq!(
+            suggest_title(&russian, 64).as_deref(),
+            Some("отпуск-спланировать-июле")
+        );
+    }
+
+    #[test]
+    fn test_suggest_title_reads_first_messages_only() {
+        let mut chat = vec![Message::new("Alice", "garden plans")];
+        chat.extend((0..20).map(|_| Message::new("Bob", "football")));
+        assert_eq!(
+            suggest_title(&chat, 64).as_deref(),
+            Some("football-garden-plans")
+        );
+        assert_eq!(
+            suggest_title(&chat[..1], 64).as_deref(),
+            Some("garden-plans")
+        );
+    }
+
+    #[test]
+    fn test_suggest_title_max_len() {
+        let chat = vec![Message::new("Alice", "Photosynthesis explained simply")];
+        assert_eq!(suggest_title(&chat, 23).as_deref(), Some("photosynthesis"));
+        assert_eq!(suggest_title(&chat, 5).as_deref(), Some("photo"));
+        assert_eq!(suggest_title(&chat, 0), None);
+
+        // A two-byte letter is never cut in half
+        let cyrillic = vec![Message::new("Боб", "Фотосинтез")];
+        assert_eq!(suggest_title(&cyrillic, 5).as_deref(), Some("фо"));
+    }
+
+    #[test]
+    fn test_suggest_title_nothing_meaningful() {
+        assert_eq!(suggest_title(&[], 40), None);
+        let chat = vec![
+            Message::new("Alice", "👍🎉 🇰🇿"),
+            Message::new("Bob", "ok, yes, 2024 and 12345"),
+            Message::new("Alice", "https://example.com/long/path"),
+        ];
+        assert_eq!(suggest_title(&chat, 40), None);
+    }
+
+    #[test]
+    fn test_suggest_file_names() {
+        use chrono::TimeZone;
+
+        let day = chrono::Utc.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap();
+        let groups = [
+            vec![Message::new("A", "Planning trip budget").with_timestamp(day)],
+            vec![Message::new("A", "planning trip budget").with_timestamp(day)],
+            vec![Message::new("A", "🎉").with_timestamp(day)],
+            vec![Message::new("A", "🎉").with_timestamp(day)],
+            vec![Message::new("A", "Untimed topic")],
+            vec![],
+        ];
+
+        let names = suggest_file_names(groups.iter().map(Vec::as_slice), 40, "jsonl");
+
+        assert_eq!(
+            names,
+            [
+                "2024-01-15_planning-trip-budget.jsonl",
+                "2024-01-15_planning-trip-budget-2.jsonl",
+                "2024-01-15.jsonl",
+                "2024-01-15-2.jsonl",
+                "0005_untimed-topic.jsonl",
+                "0006.jsonl",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_processing_stats_records_seed() {
+        let stats = ProcessingStats::new(10, 5);
+        assert_eq!(stats.seed, None);
+        assert_eq!(stats.with_seed(Seed::new(3)).seed, Some(Seed::new(3)));
+    }
+
+    // =========================================================================
+    // Ordering tests
+    // =========================================================================
+
+    /// Messages at `offsets` seconds after noon.
+    fn at_offsets(offsets: &[i64]) -> Vec<Message> {
+        use chrono::TimeZone;
+        let noon = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+        offsets
+            .iter()
+            .map(|&s| Message::new("A", "x").with_timestamp(noon + Duration::seconds(s)))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_ordering_sorted() {
+        let report = check_ordering(&at_offsets(&[0, 10, 10, 60, 3600]));
+        assert_eq!(report, OrderingReport::default());
+        assert!(report.is_sorted());
+        assert!(!report.appears_unsorted());
+    }
+
+    #[test]
+    fn test_check_ordering_clock_skew() {
+        // Two phones a few seconds apart
+        let report = check_ordering(&at_offsets(&[0, 30, 25, 40, 38, 90]));
+        assert_eq!(report.inversions, 2);
+        assert_eq!(report.large_inversions, 0);
+        assert_eq!(report.max_backward_jump, Duration::seconds(5));
+        assert!(!report.is_sorted());
+        assert!(!report.appears_unsorted());
+    }
+
+    #[test]
+    fn test_check_ordering_interleaved() {
+        // Three hourly channels, each sorted, concatenated
+        let mut offsets = Vec::new();
+        for channel in 0..3 {
+            offsets.extend((0..4).map(|hour| hour * 3600 + channel * 60));
+        }
+        let mut messages = at_offsets(&offsets);
+        messages.insert(5, Message::new("B", "untimed"));
+
+        let report = check_ordering(&messages);
+        assert_eq!(report.inversions, 2);
+        assert_eq!(report.large_inversions, 2);
+        assert_eq!(report.max_backward_jump, Duration::seconds(3 * 3600 - 60));
+        assert!(report.appears_unsorted());
+
+        sort_messages(&mut messages);
+        assert!(check_ordering(&messages).is_sorted());
+        assert_eq!(messages[0].sender, "B");
+    }
+
+    #[test]
+    fn test_ordering_report_add_and_serde() {
+        let mut total = check_ordering(&at_offsets(&[0, 30, 25]));
+        total.add(&check_ordering(&at_offsets(&[600, 0])));
+        assert_eq!(total.inversions, 2);
+        assert_eq!(total.large_inversions, 1);
+        assert_eq!(total.max_backward_jump, Duration::minutes(10));
+
+        let json = serde_json::to_value(total).unwrap();
+        assert_eq!(json["max_backward_jump_ms"], 600_000);
+        let back: OrderingReport = serde_json::from_value(json).unwrap();
+        assert_eq!(back, total);
+    }
+
+    // =========================================================================
+    // Near-duplicate tests
+    // =========================================================================
+
+    /// A message from `sender` at `minute` past nine.
+    fn said(sender: &str, content: &str, minute: u32) -> Message {
+        use chrono::TimeZone;
+        Message::new(sender, content)
+            .with_timestamp(Utc.with_ymd_and_hms(2024, 1, 15, 9, minute, 0).unwrap())
+    }
+
+    #[test]
+    fn test_near_dedupe_threshold() {
+        // 6 shared words out of 7: similarity 0.857
+        let messages = vec![
+            said("Alice", "Meet me at the station at seven tonight", 0),
+            said("Alice", "meet me at the station, at seven!", 2),
+        ];
+
+        let mut above = messages.clone();
+        let config = NearDupConfig::new().with_similarity(0.85);
+        assert_eq!(near_dedupe(&mut above, &config), 1);
+        assert_eq!(above, messages[..1]);
+
+        let mut below = messages.clone();
+        let config = NearDupConfig::new().with_similarity(0.86);
+        assert_eq!(near_dedupe(&mut below, &config), 0);
+        assert_eq!(below, messages);
+    }
+
+    #[test]
+    fn test_near_dedupe_short_messages_at_different_times() {
+        let mut messages = vec![
+            said("Bob", "ok", 0),
+            said("Bob", "Lunch?", 10),
+            said("Bob", "ok", 20),
+            said("Bob", "OK", 40),
+        ];
+        assert_eq!(near_dedupe(&mut messages, &NearDupConfig::new()), 0);
+        assert_eq!(messages.len(), 4);
+    }
+
+    #[test]
+    fn test_near_dedupe_keeps_short_replies_within_window() {
+        let messages = vec![
+            said("Bob", "ok", 0),
+            said("Alice", "Can you bring the charger?", 1),
+            said("Bob", "ok", 3),
+        ];
+
+        let mut kept = messages.clone();
+        assert_eq!(near_dedupe(&mut kept, &NearDupConfig::new()), 0);
+        assert_eq!(kept, messages);
+
+        let mut dropped = messages.clone();
+        let config = NearDupConfig::new().with_min_words(1);
+        assert_eq!(near_dedupe(&mut dropped, &config), 1);
+        assert_eq!(dropped, messages[..2]);
+    }
+
+    #[test]
+    fn test_near_dedupe_needs_same_sender_within_window() {
+        use chrono::Timelike;
+        let mut messages = vec![
+            said("Alice", "Running late, sorry", 0),
+            said("Bob", "Running late, sorry", 1),
+            said("ALICE", "running late sorry", 3),
+            said("Alice", "Running late, sorry", 30),
+        ];
+        assert_eq!(near_dedupe(&mut messages, &NearDupConfig::new()), 1);
+        let minutes: Vec<u32> = messages
+            .iter()
+            .map(|m| m.timestamp.unwrap().minute())
+            .collect();
+        assert_eq!(minutes, [0, 1, 30]);
+    }
+
+    #[test]
+    fn test_near_dedupe_unsorted_input() {
+        // A second export appended after the first, overlapping it in time
+        let mut messages = vec![
+            said("Alice", "Tickets are booked", 5),
+            said("Bob", "Great", 6),
+            said("Alice", "tickets are booked", 4),
+            Message::new("Alice", "Tickets are booked"),
+            said("Alice", "📷", 4),
+            said("Alice", "📷", 5),
+        ];
+        assert_eq!(near_dedupe(&mut messages, &NearDupConfig::new()), 1);
+        assert_eq!(messages.len(), 5);
+        // The earlier copy survives, in its place
+        assert_eq!(messages[0].content, "Great");
+        assert_eq!(messages[1].content, "tickets are booked");
+    }
+
+    #[test]
+    fn test_jaccard() {
+        let (a, b) = (words("a b c"), words("b, C d"));
+        assert!((jaccard(&a, &b) - 0.5).abs() < f32::EPSILON);
+        assert!(jaccard(&[], &[]).abs() < f32::EPSILON);
+        assert_eq!(words("Hello, hello WORLD"), ["hello", "world"]);
+    }
 }
@@ -0,0 +1,993 @@
+//! Per-sender content statistics.
+//!
+//! [`ChatStats`] summarizes how much each participant writes: message count,
+//! words, characters, questions asked, and emoji used. This is useful for
+//! picking which speaker to treat as the "assistant" role when building
+//! fine-tuning datasets.
+//!
+//! # Example
+//!
+//! ```
+//! use chatpack::core::stats::ChatStats;
+//! use chatpack::Message;
+//!
+//! let messages = vec![
+//!     Message::new("Alice", "How are you?"),
+//!     Message::new("Bob", "Great, thanks 🎉"),
+//!     Message::new("Alice", "Nice"),
+//! ];
+//!
+//! let stats = ChatStats::from_messages(&messages);
+//! assert_eq!(stats.total_messages, 3);
+//!
+//! let alice = stats.sender("Alice").unwrap();
+//! assert_eq!(alice.message_count, 2);
+//! assert_eq!(alice.question_count, 1);
+//! assert_eq!(stats.sender("Bob").unwrap().emoji_count, 1);
+//! ```
+//!
+//! # Word Counting
+//!
+//! Words are counted by splitting on Unicode whitespace. This works for
+//! Latin, Cyrillic, Arabic and other space-delimited scripts, but scripts
+//! written without spaces (Chinese, Japanese, Thai) count each unbroken run
+//! as a single word. Character counts are exact for all scripts.
+//!
+//! # Response Times
+//!
+//! [`response_times`] measures how quickly each participant answers each
+//! other one, as a [`ResponseStat`] per ordered sender pair.
+//!
+//! # Activity Over Time
+//!
+//! [`aggregate_counts`] counts messages per hour, day, week, or month, for
+//! plotting how active a chat was. `write_aggregate_csv` in
+//! [`output`](crate::core::output) writes the result as one row per bucket.
+//!
+//! # Id Gaps
+//!
+//! [`find_id_gaps`] lists the message ids missing between the lowest and
+//! highest one, which in exports with sequential ids points at deleted
+//! messages or entries the parser skipped.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+use chrono::{DateTime, Datelike, Days, Duration, Months, NaiveTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+/// Content statistics for a single sender.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SenderStats {
+    /// Sender name as it appears in the messages.
+    pub sender: String,
+
+    /// Number of messages sent.
+    pub message_count: usize,
+
+    /// Total whitespace-separated words.
+    pub word_count: usize,
+
+    /// Total characters (Unicode scalar values).
+    pub char_count: usize,
+
+    /// Messages ending with a question mark (`?` or `？`).
+    pub question_count: usize,
+
+    /// Total emoji code points.
+    pub emoji_count: usize,
+}
+
+impl SenderStats {
+    /// Creates empty statistics for a sender.
+    pub fn new(sender: impl Into<String>) -> Self {
+        Self {
+            sender: sender.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Average message length in characters.
+    pub fn avg_message_length(&self) -> f64 {
+        if self.message_count == 0 {
+            return 0.0;
+        }
+        self.char_count as f64 / self.message_count as f64
+    }
+
+    /// Average number of words per message.
+    pub fn avg_words_per_message(&self) -> f64 {
+        if self.message_count == 0 {
+            return 0.0;
+        }
+        self.word_count as f64 / self.message_count as f64
+    }
+
+    /// Adds a single message to the totals.
+    fn record(&mut self, content: &str) {
+        self.message_count += 1;
+        self.word_count += content.split_whitespace().count();
+        self.char_count += content.chars().count();
+        self.emoji_count += content.chars().filter(|&c| is_emoji(c)).count();
+        if content.trim_end().ends_with(['?', '？']) {
+            self.question_count += 1;
+        }
+    }
+}
+
+/// Aggregate statistics for a conversation.
+///
+/// Built in a single pass over the messages with
+/// [`from_messages`](Self::from_messages).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatStats {
+    /// Total number of messages.
+    pub total_messages: usize,
+
+    /// Per-sender statistics, sorted by message count (descending),
+    /// then by sender name.
+    pub senders: Vec<SenderStats>,
+}
+
+impl ChatStats {
+    /// Computes statistics over a slice of messages.
+    pub fn from_messages(messages: &[Message]) -> Self {
+        Self::from_iter(messages)
+    }
+
+    /// Returns statistics for a sender (exact match).
+    pub fn sender(&self, name: &str) -> Option<&SenderStats> {
+        self.senders.iter().find(|s| s.sender == name)
+    }
+
+    /// Returns the number of distinct senders.
+    pub fn sender_count(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Returns the sender with the most words, if any.
+    pub fn most_verbose(&self) -> Option<&SenderStats> {
+        self.senders.iter().max_by_key(|s| s.word_count)
+    }
+}
+
+impl<'a> FromIterator<&'a Message> for ChatStats {
+    fn from_iter<I: IntoIterator<Item = &'a Message>>(iter: I) -> Self {
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut senders: Vec<SenderStats> = Vec::new();
+        let mut total_messages = 0;
+
+        for msg in iter {
+            total_messages += 1;
+            let idx = *index.entry(msg.sender.as_str()).or_insert_with(|| {
+                senders.push(SenderStats::new(msg.sender.as_str()));
+                senders.len() - 1
+            });
+            senders[idx].record(&msg.content);
+        }
+
+        senders.sort_by(|a, b| {
+            b.message_count
+                .cmp(&a.message_count)
+                .then_with(|| a.sender.cmp(&b.sender))
+        });
+
+        Self {
+            total_messages,
+            senders,
+        }
+    }
+}
+
+impl std::fmt::Display for ChatStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .senders
+            .iter()
+            .map(|s| s.sender.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Sender".len());
+
+        writeln!(
+            f,
+            "{:<width$}  {:>8}  {:>8}  {:>9}  {:>8}  {:>9}  {:>6}",
+            "Sender", "Messages", "Words", "Chars", "Avg len", "Questions", "Emoji"
+        )?;
+        for s in &self.senders {
+            writeln!(
+                f,
+                "{:<width$}  {:>8}  {:>8}  {:>9}  {:>8.1}  {:>9}  {:>6}",
+                s.sender,
+                s.message_count,
+                s.word_count,
+                s.char_count,
+                s.avg_message_length(),
+                s.question_count,
+                s.emoji_count
+            )?;
+        }
+        write!(
+            f,
+            "{} messages from {} senders",
+            self.total_messages,
+            self.senders.len()
+        )
+    }
+}
+
+/// How quickly one sender answered another.
+///
+/// Produced by [`response_times`]. Gaps are in seconds; percentiles
+/// interpolate linearly between the nearest gaps, so the median of an even
+/// number of gaps is the mean of the middle two.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseStat {
+    /// Sender of the message being answered.
+    pub from: String,
+
+    /// Sender of the reply.
+    pub to: String,
+
+    /// Number of replies measured.
+    pub count: usize,
+
+    /// Median gap in seconds.
+    pub median_seconds: f64,
+
+    /// 90th percentile gap in seconds.
+    pub p90_seconds: f64,
+
+    /// Longest gap in seconds.
+    pub max_seconds: f64,
+}
+
+/// Measures response times between each pair of senders.
+///
+/// Every message directly followed by one from a different sender counts
+/// as a reply from the second to the first. The pair is skipped when:
+///
+/// - either message has no timestamp,
+/// - the gap is negative (out-of-order input) or longer than `max_gap`,
+///   which keeps overnight silences out of the numbers,
+/// - both messages are from the same sender.
+///
+/// Merged messages (see [`merge_consecutive`](crate::core::processor::merge_consecutive))
+/// carry the timestamp of their first part, so a reply is measured from
+/// the start of the merged message. Results are sorted by reply count
+/// (descending), then by sender names.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::core::stats::response_times;
+/// use chatpack::Message;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let at = |minute| Utc.with_ymd_and_hms(2024, 1, 1, 9, minute, 0).unwrap();
+/// let messages = vec![
+///     Message::new("Alice", "Up?").with_timestamp(at(0)),
+///     Message::new("Bob", "Yes").with_timestamp(at(2)),
+///     Message::new("Alice", "Coffee?").with_timestamp(at(3)),
+/// ];
+///
+/// let stats = response_times(&messages, Duration::hours(6));
+/// assert_eq!(stats.len(), 2);
+/// let alice_to_bob = stats.iter().find(|s| s.from == "Alice").unwrap();
+/// assert_eq!(alice_to_bob.median_seconds, 120.0);
+/// ```
+pub fn response_times(messages: &[Message], max_gap: Duration) -> Vec<ResponseStat> {
+    let mut gaps: HashMap<(&str, &str), Vec<f64>> = HashMap::new();
+    for pair in messages.windows(2) {
+        let (prev, next) = (&pair[0], &pair[1]);
+        if prev.sender == next.sender {
+            continue;
+        }
+        let (Some(sent), Some(replied)) = (prev.timestamp, next.timestamp) else {
+            continue;
+        };
+        let gap = replied - sent;
+        if gap < Duration::zero() || gap > max_gap {
+            continue;
+        }
+        gaps.entry((prev.sender.as_str(), next.sender.as_str()))
+            .or_default()
+            .push(gap.num_milliseconds() as f64 / 1000.0);
+    }
+
+    let mut stats: Vec<ResponseStat> = gaps
+        .into_iter()
+        .map(|((from, to), mut seconds)| {
+            seconds.sort_by(f64::total_cmp);
+            ResponseStat {
+                from: from.to_string(),
+                to: to.to_string(),
+                count: seconds.len(),
+                median_seconds: percentile(&seconds, 50),
+                p90_seconds: percentile(&seconds, 90),
+                max_seconds: seconds[seconds.len() - 1],
+            }
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.from.cmp(&b.from))
+            .then_with(|| a.to.cmp(&b.to))
+    });
+    stats
+}
+
+/// Renders response statistics as a compact table.
+///
+/// ```
+/// use chatpack::core::stats::{ResponseStat, response_table};
+///
+/// let stats = vec![ResponseStat {
+///     from: "Alice".into(),
+///     to: "Bob".into(),
+///     count: 12,
+///     median_seconds: 95.0,
+///     p90_seconds: 610.0,
+///     max_seconds: 4000.0,
+/// }];
+/// assert_eq!(
+///     response_table(&stats),
+///     "From   To   Replies   Median      P90      Max\n\
+///      Alice  Bob       12   1m 35s  10m 10s   1h 06m"
+/// );
+/// ```
+pub fn response_table(stats: &[ResponseStat]) -> String {
+    let width = |name: &dyn Fn(&ResponseStat) -> &str, header: &str| {
+        stats
+            .iter()
+            .map(|s| name(s).chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(header.len())
+    };
+    let from_width = width(&|s| &s.from, "From");
+    let to_width = width(&|s| &s.to, "To");
+
+    let mut table = format!(
+        "{:<from_width$}  {:<to_width$}  {:>7}  {:>7}  {:>7}  {:>7}",
+        "From", "To", "Replies", "Median", "P90", "Max"
+    );
+    for s in stats {
+        table.push_str(&format!(
+            "\n{:<from_width$}  {:<to_width$}  {:>7}  {:>7}  {:>7}  {:>7}",
+            s.from,
+            s.to,
+            s.count,
+            format_gap(s.median_seconds),
+            format_gap(s.p90_seconds),
+            format_gap(s.max_seconds)
+        ));
+    }
+    table
+}
+
+/// Length of the time buckets counted by [`aggregate_counts`].
+///
+/// Buckets are aligned in UTC: hours on the hour, days at midnight, weeks
+/// on Monday at midnight, and months on the 1st. Daylight saving changes
+/// therefore never stretch or shrink a bucket; every week is exactly 168
+/// hours long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bucket {
+    /// One hour.
+    Hour,
+    /// One day.
+    Day,
+    /// One week, starting on Monday.
+    Week,
+    /// One calendar month.
+    Month,
+}
+
+impl Bucket {
+    /// Returns all bucket names.
+    pub fn all_names() -> &'static [&'static str] {
+        &["hour", "day", "week", "month"]
+    }
+
+    /// Returns the start of the bucket containing `ts`.
+    ///
+    /// ```
+    /// use chatpack::core::stats::Bucket;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// // A Thursday
+    /// let ts = Utc.with_ymd_and_hms(2024, 3, 14, 17, 45, 0).unwrap();
+    /// assert_eq!(Bucket::Hour.start_of(ts), Utc.with_ymd_and_hms(2024, 3, 14, 17, 0, 0).unwrap());
+    /// assert_eq!(Bucket::Week.start_of(ts), Utc.with_ymd_and_hms(2024, 3, 11, 0, 0, 0).unwrap());
+    /// assert_eq!(Bucket::Month.start_of(ts), Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap());
+    /// ```
+    pub fn start_of(self, ts: DateTime<Utc>) -> DateTime<Utc> {
+        let date = ts.date_naive();
+        let (day, hour) = match self {
+            Bucket::Hour => (date, ts.hour()),
+            Bucket::Day => (date, 0),
+            Bucket::Week => (
+                date - Days::new(u64::from(date.weekday().num_days_from_monday())),
+                0,
+            ),
+            Bucket::Month => (date - Days::new(u64::from(date.day0())), 0),
+        };
+        day.and_time(NaiveTime::MIN).and_utc() + Duration::hours(i64::from(hour))
+    }
+
+    /// Returns the start of the bucket after the one starting at `start`.
+    fn next(self, start: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Bucket::Hour => start + Duration::hours(1),
+            Bucket::Day => start + Duration::days(1),
+            Bucket::Week => start + Duration::weeks(1),
+            Bucket::Month => start + Months::new(1),
+        }
+    }
+}
+
+impl std::fmt::Display for Bucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Bucket::Hour => "hour",
+            Bucket::Day => "day",
+            Bucket::Week => "week",
+            Bucket::Month => "month",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Bucket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hour" | "hourly" => Ok(Bucket::Hour),
+            "day" | "daily" => Ok(Bucket::Day),
+            "week" | "weekly" => Ok(Bucket::Week),
+            "month" | "monthly" => Ok(Bucket::Month),
+            _ => Err(format!(
+                "Unknown bucket: '{}'. Expected one of: {}",
+                s,
+                Bucket::all_names().join(", ")
+            )),
+        }
+    }
+}
+
+/// Messages counted in one time bucket.
+///
+/// Produced by [`aggregate_counts`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketCount {
+    /// Start of the bucket, in UTC.
+    pub start: DateTime<Utc>,
+
+    /// Number of messages in the bucket.
+    pub count: usize,
+
+    /// Number of messages in the bucket by sender. Senders without
+    /// messages in the bucket are absent.
+    pub per_sender: BTreeMap<String, usize>,
+}
+
+impl BucketCount {
+    fn empty(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            count: 0,
+            per_sender: BTreeMap::new(),
+        }
+    }
+}
+
+/// Counts messages per time bucket.
+///
+/// Returns one [`BucketCount`] for every bucket from the one holding the
+/// earliest timestamp to the one holding the latest, in order. Buckets
+/// without messages in between are included with zero counts, so plots
+/// show quiet periods instead of skipping them. Messages without a
+/// timestamp are not counted, and input order does not matter.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::core::stats::{Bucket, aggregate_counts};
+/// use chatpack::Message;
+/// use chrono::{TimeZone, Utc};
+///
+/// let on = |day| Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+/// let messages = vec![
+///     Message::new("Alice", "Hi").with_timestamp(on(1)),
+///     Message::new("Bob", "Hey").with_timestamp(on(1)),
+///     Message::new("Alice", "Back").with_timestamp(on(3)),
+/// ];
+///
+/// let counts = aggregate_counts(&messages, Bucket::Day);
+/// let per_day: Vec<usize> = counts.iter().map(|b| b.count).collect();
+/// assert_eq!(per_day, [2, 0, 1]);
+/// assert_eq!(counts[0].per_sender["Bob"], 1);
+/// ```
+pub fn aggregate_counts(messages: &[Message], bucket: Bucket) -> Vec<BucketCount> {
+    let mut counts: BTreeMap<DateTime<Utc>, BucketCount> = BTreeMap::new();
+    for msg in messages {
+        let Some(ts) = msg.timestamp else {
+            continue;
+        };
+        let start = bucket.start_of(ts);
+        let counted = counts
+            .entry(start)
+            .or_insert_with(|| BucketCount::empty(start));
+        counted.count += 1;
+        match counted.per_sender.get_mut(&msg.sender) {
+            Some(count) => *count += 1,
+            None => {
+                counted.per_sender.insert(msg.sender.clone(), 1);
+            }
+        }
+    }
+
+    let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        return Vec::new();
+    };
+    let mut buckets = Vec::with_capacity(counts.len());
+    let mut start = first;
+    while start <= last {
+        buckets.push(
+            counts
+                .remove(&start)
+                .unwrap_or_else(|| BucketCount::empty(start)),
+        );
+        start = bucket.next(start);
+    }
+    buckets
+}
+
+/// Returns the ranges of ids missing between the lowest and highest
+/// [`Message::id`] in `messages`, in ascending order.
+///
+/// Messages without an id are ignored, and so is their order: ids are
+/// sorted first, and repeated ids count once.
+///
+/// # Examples
+///
+/// ```
+/// use chatpack::core::stats::find_id_gaps;
+/// use chatpack::Message;
+///
+/// let messages: Vec<Message> = [1, 2, 5, 9, 8]
+///     .into_iter()
+///     .map(|id| Message::new("Alice", "Hi").with_id(id))
+///     .collect();
+/// assert_eq!(find_id_gaps(&messages), [3..5, 6..8]);
+/// ```
+pub fn find_id_gaps(messages: &[Message]) -> Vec<Range<u64>> {
+    let mut ids: Vec<u64> = messages.iter().filter_map(|msg| msg.id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids.windows(2)
+        .filter(|pair| pair[1] > pair[0] + 1)
+        .map(|pair| pair[0] + 1..pair[1])
+        .collect()
+}
+
+/// Returns the `percent`th percentile of sorted, non-empty `values`,
+/// interpolating linearly between neighbours.
+fn percentile(values: &[f64], percent: usize) -> f64 {
+    let position = percent * (values.len() - 1);
+    let (index, remainder) = (position / 100, position % 100);
+    let lower = values[index];
+    if remainder == 0 {
+        return lower;
+    }
+    lower + (values[index + 1] - lower) * remainder as f64 / 100.0
+}
+
+/// Formats a gap in seconds as `45s`, `2m 05s`, or `1h 06m`.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn format_gap(seconds: f64) -> String {
+    let total = seconds.round() as i64;
+    if total < 60 {
+        format!("{total}s")
+    } else if total < 3600 {
+        format!("{}m {:02}s", total / 60, total % 60)
+    } else {
+        format!("{}h {:02}m", total / 3600, total / 60 % 60)
+    }
+}
+
+/// Returns `true` for code points in the common emoji blocks.
+///
+/// Skin-tone modifiers, zero-width joiners and variation selectors are not
+/// counted, so a ZWJ sequence counts each visible component.
+fn is_emoji(c: char) -> bool {
+    matches!(
+        u32::from(c),
+        0x1F300..=0x1F3FA
+            | 0x1F400..=0x1F5FF
+            | 0x1F600..=0x1F64F
+            | 0x1F680..=0x1F6FF
+            | 0x1F900..=0x1F9FF
+            | 0x1FA70..=0x1FAFF
+            | 0x2600..=0x26FF
+            | 0x2700..=0x27BF
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // =========================================================================
+    // SenderStats tests
+    // =========================================================================
+
+    #[test]
+    fn test_sender_stats_counts() {
+        let messages = vec![
+            Message::new("Alice", "Hello there, how are you?"),
+            Message::new("Alice", "Fine"),
+        ];
+        let stats = ChatStats::from_messages(&messages);
+        let alice = stats.sender("Alice").unwrap();
+
+        assert_eq!(alice.message_count, 2);
+        assert_eq!(alice.word_count, 6);
+        assert_eq!(alice.char_count, 29);
+        assert_eq!(alice.question_count, 1);
+        assert!((alice.avg_message_length() - 14.5).abs() < f64::EPSILON);
+        assert!((alice.avg_words_per_message() - 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_cyrillic_words_and_chars() {
+        let messages = vec![Message::new("Иван", "Привет, как дела?")];
+        let stats = ChatStats::from_messages(&messages);
+        let ivan = stats.sender("Иван").unwrap();
+
+        assert_eq!(ivan.word_count, 3);
+        assert_eq!(ivan.char_count, 17);
+        assert_eq!(ivan.question_count, 1);
+    }
+
+    #[test]
+    fn test_cjk_counts_runs_as_words() {
+        // No whitespace: the whole run is one "word" (documented limitation)
+        let messages = vec![Message::new("田中", "今日はいい天気ですね？")];
+        let stats = ChatStats::from_messages(&messages);
+        let tanaka = stats.sender("田中").unwrap();
+
+        assert_eq!(tanaka.word_count, 1);
+        assert_eq!(tanaka.char_count, 11);
+        assert_eq!(tanaka.question_count, 1);
+    }
+
+    #[test]
+    fn test_emoji_only_messages() {
+        let messages = vec![
+            Message::new("Bob", "🎉🔥💀"),
+            Message::new("Bob", "👍🏽"),
+            Message::new("Bob", "❤️"),
+        ];
+        let stats = ChatStats::from_messages(&messages);
+        let bob = stats.sender("Bob").unwrap();
+
+        // Skin-tone modifier and variation selector are not counted
+        assert_eq!(bob.emoji_count, 5);
+        assert_eq!(bob.word_count, 3);
+        assert_eq!(bob.question_count, 0);
+    }
+
+    #[test]
+    fn test_empty_sender_stats() {
+        let stats = SenderStats::new("Nobody");
+        assert!(stats.avg_message_length().abs() < f64::EPSILON);
+        assert!(stats.avg_words_per_message().abs() < f64::EPSILON);
+    }
+
+    // =========================================================================
+    // ChatStats tests
+    // =========================================================================
+
+    #[test]
+    fn test_sorted_by_message_count() {
+        let messages = vec![
+            Message::new("Charlie", "a"),
+            Message::new("Bob", "a"),
+            Message::new("Alice", "a"),
+            Message::new("Bob", "b"),
+        ];
+        let stats = ChatStats::from_messages(&messages);
+
+        let order: Vec<_> = stats.senders.iter().map(|s| s.sender.as_str()).collect();
+        assert_eq!(order, vec!["Bob", "Alice", "Charlie"]);
+        assert_eq!(stats.total_messages, 4);
+        assert_eq!(stats.sender_count(), 3);
+    }
+
+    #[test]
+    fn test_most_verbose() {
+        let messages = vec![
+            Message::new("Alice", "one two three four"),
+            Message::new("Bob", "hi"),
+            Message::new("Bob", "yo"),
+        ];
+        let stats = ChatStats::from_messages(&messages);
+        assert_eq!(stats.most_verbose().unwrap().sender, "Alice");
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let stats = ChatStats::from_messages(&[]);
+        assert_eq!(stats.total_messages, 0);
+        assert!(stats.senders.is_empty());
+        assert!(stats.most_verbose().is_none());
+    }
+
+    #[test]
+    fn test_display_table() {
+        let messages = vec![
+            Message::new("Алиса", "Привет?"),
+            Message::new("Bob", "Hi"),
+            Message::new("Алиса", "Ок"),
+        ];
+        let table = ChatStats::from_messages(&messages).to_string();
+        let lines: Vec<_> = table.lines().collect();
+
+        assert!(lines[0].starts_with("Sender"));
+        assert!(lines[1].starts_with("Алиса"));
+        assert!(lines[2].starts_with("Bob"));
+        assert_eq!(lines[1].chars().count(), lines[2].chars().count());
+        assert_eq!(lines[3], "3 messages from 2 senders");
+    }
+
+    // =========================================================================
+    // Response time tests
+    // =========================================================================
+
+    /// Alice answers Bob after 300s and 20s (and once overnight); Bob
+    /// answers Alice after 60s, 120s, 180s and 600s.
+    fn timeline() -> Vec<Message> {
+        use chrono::{TimeZone, Utc};
+
+        let at = |seconds: i64| Utc.timestamp_opt(1_704_099_600 + seconds, 0).unwrap();
+        vec![
+            Message::new("Alice", "a1").with_timestamp(at(0)),
+            Message::new("Bob", "b1").with_timestamp(at(60)),
+            Message::new("Alice", "a2").with_timestamp(at(360)),
+            Message::new("Bob", "b2").with_timestamp(at(480)),
+            Message::new("Bob", "self follow-up").with_timestamp(at(490)),
+            Message::new("Alice", "no timestamp"),
+            Message::new("Bob", "after untimed").with_timestamp(at(1000)),
+            Message::new("Alice", "next morning").with_timestamp(at(41_000)),
+            Message::new("Bob", "b3").with_timestamp(at(41_180)),
+            Message::new("Alice", "a3").with_timestamp(at(41_200)),
+            Message::new("Bob", "b4").with_timestamp(at(41_800)),
+        ]
+    }
+
+    fn pair<'a>(stats: &'a [ResponseStat], from: &str) -> &'a ResponseStat {
+        stats.iter().find(|s| s.from == from).unwrap()
+    }
+
+    #[test]
+    fn test_response_times_percentiles() {
+        let stats = response_times(&timeline(), Duration::hours(6));
+        assert_eq!(stats.len(), 2);
+
+        // Sorted by reply count
+        let alice_to_bob = &stats[0];
+        assert_eq!(
+            (alice_to_bob.from.as_str(), alice_to_bob.to.as_str()),
+            ("Alice", "Bob")
+        );
+        assert_eq!(alice_to_bob.count, 4);
+        // Gaps 60, 120, 180, 600
+        assert!((alice_to_bob.median_seconds - 150.0).abs() < 1e-9);
+        assert!((alice_to_bob.p90_seconds - 474.0).abs() < 1e-9);
+        assert!((alice_to_bob.max_seconds - 600.0).abs() < 1e-9);
+
+        // Gaps 20, 300; the overnight gap is over the ceiling
+        let bob_to_alice = pair(&stats, "Bob");
+        assert_eq!(bob_to_alice.count, 2);
+        assert!((bob_to_alice.median_seconds - 160.0).abs() < 1e-9);
+        assert!((bob_to_alice.p90_seconds - 272.0).abs() < 1e-9);
+        assert!((bob_to_alice.max_seconds - 300.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_response_times_ceiling() {
+        let stats = response_times(&timeline(), Duration::days(1));
+        let bob_to_alice = pair(&stats, "Bob");
+        assert_eq!(bob_to_alice.count, 3);
+        assert!((bob_to_alice.median_seconds - 300.0).abs() < 1e-9);
+        assert!((bob_to_alice.max_seconds - 40_000.0).abs() < 1e-9);
+
+        // A gap equal to the ceiling counts
+        let stats = response_times(&timeline(), Duration::minutes(5));
+        assert_eq!(pair(&stats, "Bob").count, 2);
+        assert_eq!(pair(&stats, "Alice").count, 3);
+    }
+
+    #[test]
+    fn test_response_times_single_gap_and_empty() {
+        let stats = response_times(&timeline()[..2], Duration::hours(1));
+        assert_eq!(stats.len(), 1);
+        assert!((stats[0].median_seconds - 60.0).abs() < 1e-9);
+        assert!((stats[0].p90_seconds - 60.0).abs() < 1e-9);
+
+        assert!(response_times(&[], Duration::hours(1)).is_empty());
+        let untimed = [Message::new("Alice", "a"), Message::new("Bob", "b")];
+        assert!(response_times(&untimed, Duration::hours(1)).is_empty());
+    }
+
+    #[test]
+    fn test_response_table() {
+        let stats = response_times(&timeline(), Duration::hours(6));
+        let table = response_table(&stats);
+        let lines: Vec<_> = table.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "Alice  Bob          4   2m 30s   7m 54s  10m 00s");
+        assert_eq!(lines[2], "Bob    Alice        2   2m 40s   4m 32s   5m 00s");
+    }
+
+    // =========================================================================
+    // Aggregation tests
+    // =========================================================================
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    /// April 2024: Alice writes `day % 3` messages a day, Bob one on every
+    /// even day, nobody from the 10th to the 14th.
+    fn april() -> Vec<Message> {
+        let mut messages = Vec::new();
+        for day in (1..=30).filter(|day| !(10..=14).contains(day)) {
+            for i in 0..day % 3 {
+                messages.push(Message::new("Alice", "a").with_timestamp(utc(4, day, 8 + i, 0)));
+            }
+            if day % 2 == 0 {
+                messages.push(Message::new("Bob", "b").with_timestamp(utc(4, day, 23, 59)));
+            }
+        }
+        messages.push(Message::new("Carol", "untimed"));
+        messages
+    }
+
+    #[test]
+    fn test_aggregate_days() {
+        let days = aggregate_counts(&april(), Bucket::Day);
+
+        // From the 1st (Alice) to the 30th (Bob), gaps included
+        assert_eq!(days.len(), 30);
+        for (day, bucket) in (1..=30).zip(&days) {
+            assert_eq!(bucket.start, utc(4, day, 0, 0));
+            let (alice, bob) = if (10..=14).contains(&day) {
+                (0, 0)
+            } else {
+                (day % 3, usize::from(day % 2 == 0))
+            };
+            assert_eq!(bucket.count, alice as usize + bob, "April {day}");
+            assert_eq!(
+                bucket.per_sender.get("Alice").copied(),
+                (alice > 0).then_some(alice as usize)
+            );
+            assert_eq!(
+                bucket.per_sender.get("Bob").copied(),
+                (bob > 0).then_some(bob)
+            );
+        }
+        assert!(days.iter().all(|b| !b.per_sender.contains_key("Carol")));
+
+        let total: usize = days.iter().map(|b| b.count).sum();
+        assert_eq!(total, april().len() - 1);
+    }
+
+    #[test]
+    fn test_aggregate_hours_and_months() {
+        let hours = aggregate_counts(&april(), Bucket::Hour);
+        assert_eq!(hours.first().unwrap().start, utc(4, 1, 8, 0));
+        assert_eq!(hours.last().unwrap().start, utc(4, 30, 23, 0));
+        assert_eq!(hours.len(), 29 * 24 + 16);
+
+        let months = aggregate_counts(&april(), Bucket::Month);
+        assert_eq!(months.len(), 1);
+        assert_eq!(months[0].start, utc(4, 1, 0, 0));
+        assert_eq!(months[0].per_sender["Bob"], 12);
+
+        // February of a leap year sits between January and March
+        let messages = [
+            Message::new("A", "x").with_timestamp(utc(3, 1, 0, 0)),
+            Message::new("A", "y").with_timestamp(utc(1, 31, 23, 59)),
+        ];
+        let starts: Vec<_> = aggregate_counts(&messages, Bucket::Month)
+            .iter()
+            .map(|b| (b.start, b.count))
+            .collect();
+        assert_eq!(
+            starts,
+            [
+                (utc(1, 1, 0, 0), 1),
+                (utc(2, 1, 0, 0), 0),
+                (utc(3, 1, 0, 0), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_weeks_across_dst_changes() {
+        // US clocks went forward on Sunday March 10, EU clocks on Sunday
+        // March 31; UTC weeks still start on Monday at midnight
+        let messages = [
+            Message::new("A", "sunday night").with_timestamp(utc(3, 10, 23, 30)),
+            Message::new("A", "monday").with_timestamp(utc(3, 11, 0, 30)),
+            Message::new("A", "eu change").with_timestamp(utc(3, 31, 1, 30)),
+            Message::new("A", "eu monday").with_timestamp(utc(4, 1, 0, 0)),
+            Message::new("A", "first").with_timestamp(utc(3, 4, 0, 0)),
+        ];
+        let weeks = aggregate_counts(&messages, Bucket::Week);
+
+        let starts: Vec<_> = weeks.iter().map(|b| (b.start, b.count)).collect();
+        assert_eq!(
+            starts,
+            [
+                (utc(3, 4, 0, 0), 2),
+                (utc(3, 11, 0, 0), 1),
+                (utc(3, 18, 0, 0), 0),
+                (utc(3, 25, 0, 0), 1),
+                (utc(4, 1, 0, 0), 1),
+            ]
+        );
+        for pair in weeks.windows(2) {
+            assert_eq!(pair[1].start - pair[0].start, Duration::hours(168));
+        }
+    }
+
+    #[test]
+    fn test_aggregate_empty_and_parse() {
+        assert!(aggregate_counts(&[], Bucket::Day).is_empty());
+        assert!(aggregate_counts(&[Message::new("A", "x")], Bucket::Day).is_empty());
+
+        for name in Bucket::all_names() {
+            let bucket: Bucket = name.parse().unwrap();
+            assert_eq!(bucket.to_string(), *name);
+        }
+        assert_eq!("Weekly".parse::<Bucket>(), Ok(Bucket::Week));
+        assert!("year".parse::<Bucket>().is_err());
+    }
+
+    // =========================================================================
+    // Id gap tests
+    // =========================================================================
+
+    fn with_ids(ids: &[u64]) -> Vec<Message> {
+        ids.iter()
+            .map(|&id| Message::new("A", "x").with_id(id))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_id_gaps() {
+        assert_eq!(find_id_gaps(&with_ids(&[1, 2, 4, 7])), [3..4, 5..7]);
+        assert_eq!(find_id_gaps(&with_ids(&[10, 3, 3, 5])), [4..5, 6..10]);
+    }
+
+    #[test]
+    fn test_find_id_gaps_none() {
+        assert!(find_id_gaps(&[]).is_empty());
+        assert!(find_id_gaps(&with_ids(&[4, 5, 6])).is_empty());
+        let mut messages = with_ids(&[1, 3]);
+        messages.push(Message::new("B", "no id"));
+        assert_eq!(find_id_gaps(&messages), vec![2..3]);
+    }
+}
@@ -0,0 +1,292 @@
+//! Grapheme-safe text measuring and truncation.
+//!
+//! Anything that shortens message text must cut between user-perceived
+//! characters (grapheme clusters), not bytes or chars, or it leaves half an
+//! emoji family or a flag's first letter behind. Use [`truncate_graphemes`]
+//! and [`len_graphemes`] for that.
+//!
+//! With the `graphemes` feature, clusters follow Unicode's extended grapheme
+//! cluster rules (UAX #29). Without it, a close approximation is used:
+//! combining marks, variation selectors, skin-tone modifiers, tag sequences,
+//! zero-width-joiner sequences, regional indicator pairs, and CRLF are kept
+//! with the character before them.
+//!
+//! Sender names are compared with [`senders_match`], which ignores case and
+//! the invisible characters that exports leave in display names.
+
+use std::borrow::Cow;
+
+/// Returns the number of grapheme clusters in `s`.
+///
+/// ```rust
+/// use chatpack::core::textutil::len_graphemes;
+///
+/// assert_eq!(len_graphemes("e\u{301}"), 1);
+/// assert_eq!(len_graphemes("👨‍👩‍👧 hi"), 4);
+/// assert_eq!(len_graphemes("🇰🇿"), 1);
+/// ```
+pub fn len_graphemes(s: &str) -> usize {
+    cluster_starts(s).count()
+}
+
+/// Keeps the first `n` grapheme clusters of `s`, appending `ellipsis` if
+/// anything was cut.
+///
+/// Returns `s` unchanged, without allocating, when it has `n` clusters or
+/// fewer.
+///
+/// ```rust
+/// use chatpack::core::textutil::truncate_graphemes;
+///
+/// assert_eq!(truncate_graphemes("Hello, world", 5, "…"), "Hello…");
+/// assert_eq!(truncate_graphemes("Hi", 5, "…"), "Hi");
+/// // The family emoji stays whole
+/// assert_eq!(truncate_graphemes("👨‍👩‍👧‍👦👍", 1, ""), "👨‍👩‍👧‍👦");
+/// ```
+pub fn truncate_graphemes<'a>(s: &'a str, n: usize, ellipsis: &str) -> Cow<'a, str> {
+    let prefix = prefix_graphemes(s, n);
+    if prefix.len() == s.len() {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(prefix.len() + ellipsis.len());
+    out.push_str(prefix);
+    out.push_str(ellipsis);
+    Cow::Owned(out)
+}
+
+/// Returns `name` in the form sender names are compared in.
+///
+/// Zero-width spaces, word joiners, byte order marks, and bidi controls are
+/// removed, and zero-width joiners and non-joiners are removed from the
+/// ends, where they cannot join anything; inside a name they are kept, as
+/// they belong to emoji sequences and to words in some scripts. The rest is
+/// case folded: lowercased, with `ß` folded to `ss` and final `ς` to `σ`.
+///
+/// ```rust
+/// use chatpack::core::textutil::fold_sender;
+///
+/// assert_eq!(fold_sender("Алия\u{200B}"), "алия");
+/// assert_eq!(fold_sender("\u{202A}Straße\u{202C}"), "strasse");
+/// ```
+pub fn fold_sender(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    let trimmed = name.trim_matches(|c| is_invisible(c) || matches!(c, '\u{200C}' | '\u{200D}'));
+    for c in trimmed.chars().filter(|&c| !is_invisible(c)) {
+        match c {
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            _ => folded.extend(c.to_lowercase()),
+        }
+    }
+    folded
+}
+
+/// Returns whether two sender names refer to the same sender: whether they
+/// are equal once [folded](fold_sender).
+///
+/// ```rust
+/// use chatpack::core::textutil::senders_match;
+///
+/// assert!(senders_match("Алия\u{200B}", "алия"));
+/// assert!(senders_match("ALICE", "alice"));
+/// assert!(!senders_match("Alice", "Alicia"));
+/// ```
+pub fn senders_match(a: &str, b: &str) -> bool {
+    if a.eq_ignore_ascii_case(b) {
+        return true;
+    }
+    if a.is_ascii() && b.is_ascii() {
+        return false;
+    }
+    fold_sender(a) == fold_sender(b)
+}
+
+/// Returns whether `c` is invisible formatting that [`fold_sender`] drops
+/// wherever it appears.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}'                // zero width space
+            | '\u{2060}'          // word joiner
+            | '\u{FEFF}'          // byte order mark
+            | '\u{200E}' | '\u{200F}' | '\u{061C}' // bidi marks
+            | '\u{202A}'..='\u{202E}' // bidi embeddings and overrides
+            | '\u{2066}'..='\u{2069}' // bidi isolates
+    )
+}
+
+/// Returns the first `n` grapheme clusters of `s`.
+pub(crate) fn prefix_graphemes(s: &str, n: usize) -> &str {
+    match cluster_starts(s).nth(n) {
+        Some(end) => &s[..end],
+        None => s,
+    }
+}
+
+/// Returns the longest run of whole grapheme clusters at the start of `s`
+/// that fits in `max_bytes` bytes.
+pub(crate) fn prefix_bytes(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let end = cluster_starts(s)
+        .take_while(|&start| start <= max_bytes)
+        .last()
+        .unwrap_or(0);
+    &s[..end]
+}
+
+/// Splits `s` into its grapheme clusters.
+pub(crate) fn split_graphemes(s: &str) -> impl Iterator<Item = &str> + '_ {
+    let mut ends = cluster_starts(s).skip(1).chain(std::iter::once(s.len()));
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        if start >= s.len() {
+            return None;
+        }
+        let end = ends.next()?;
+        let cluster = &s[start..end];
+        start = end;
+        Some(cluster)
+    })
+}
+
+/// Byte offsets at which the grapheme clusters of `s` start.
+#[cfg(feature = "graphemes")]
+fn cluster_starts(s: &str) -> impl Iterator<Item = usize> + '_ {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    s.grapheme_indices(true).map(|(start, _)| start)
+}
+
+/// Byte offsets at which the grapheme clusters of `s` start.
+#[cfg(not(feature = "graphemes"))]
+fn cluster_starts(s: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut prev: Option<char> = None;
+    // Regional indicators seen in a row before the current char
+    let mut regional_run = 0usize;
+    s.char_indices().filter_map(move |(start, c)| {
+        let joins = prev.is_some_and(|p| {
+            extends(c)
+                || p == '\u{200D}'
+                || (p == '\r' && c == '\n')
+                || (is_regional_indicator(c) && regional_run % 2 == 1)
+        });
+        regional_run = if is_regional_indicator(c) {
+            regional_run + 1
+        } else {
+            0
+        };
+        prev = Some(c);
+        (!joins).then_some(start)
+    })
+}
+
+/// Returns whether `c` attaches to the character before it.
+#[cfg(not(feature = "graphemes"))]
+fn extends(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'   // combining diacritical marks
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{FE00}'..='\u{FE0F}' // variation selectors
+            | '\u{E0100}'..='\u{E01EF}'
+            | '\u{200D}'              // zero width joiner
+            | '\u{1F3FB}'..='\u{1F3FF}' // skin tone modifiers
+            | '\u{E0020}'..='\u{E007F}' // tag sequences
+    )
+}
+
+#[cfg(not(feature = "graphemes"))]
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1F1E6}'..='\u{1F1FF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY: &str = "👨\u{200D}👩\u{200D}👧\u{200D}👦";
+
+    #[test]
+    fn test_len_graphemes() {
+        assert_eq!(len_graphemes(""), 0);
+        assert_eq!(len_graphemes("abc"), 3);
+        assert_eq!(len_graphemes(FAMILY), 1);
+        assert_eq!(len_graphemes("🇰🇿🇺🇸"), 2);
+        assert_eq!(len_graphemes("👍🏽!"), 2);
+        assert_eq!(len_graphemes("a\r\nb"), 3);
+        assert_eq!(len_graphemes("🏳\u{FE0F}\u{200D}🌈"), 1);
+        assert_eq!(len_graphemes("𝔸𝔹"), 2);
+    }
+
+    #[test]
+    fn test_truncate_graphemes() {
+        let text = format!("{FAMILY}🇰🇿e\u{301}x");
+        assert_eq!(truncate_graphemes(&text, 0, "…"), "…");
+        assert_eq!(truncate_graphemes(&text, 1, "…"), format!("{FAMILY}…"));
+        assert_eq!(truncate_graphemes(&text, 2, ""), format!("{FAMILY}🇰🇿"));
+        assert_eq!(
+            truncate_graphemes(&text, 3, "..."),
+            format!("{FAMILY}🇰🇿e\u{301}...")
+        );
+        assert!(matches!(
+            truncate_graphemes(&text, 4, "…"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(truncate_graphemes("", 0, "…"), "");
+    }
+
+    #[test]
+    fn test_prefix_bytes() {
+        let text = format!("ab{FAMILY}c");
+        assert_eq!(prefix_bytes(&text, 100), text);
+        assert_eq!(prefix_bytes(&text, 2 + FAMILY.len()), format!("ab{FAMILY}"));
+        assert_eq!(prefix_bytes(&text, 2 + FAMILY.len() - 1), "ab");
+        assert_eq!(prefix_bytes("e\u{301}x", 2), "");
+        assert_eq!(prefix_bytes("", 0), "");
+    }
+
+    #[test]
+    fn test_split_graphemes() {
+        let text = format!("a{FAMILY}🇰🇿e\u{301}");
+        let clusters: Vec<&str> = split_graphemes(&text).collect();
+        assert_eq!(clusters, ["a", FAMILY, "🇰🇿", "e\u{301}"]);
+        assert_eq!(split_graphemes("").count(), 0);
+    }
+
+    #[test]
+    fn test_fold_sender() {
+        assert_eq!(fold_sender("Алия\u{200B}"), "алия");
+        assert_eq!(fold_sender("\u{200F}АЛИЯ"), "алия");
+        assert_eq!(fold_sender("Al\u{2060}ice\u{FEFF}"), "alice");
+        assert_eq!(fold_sender("\u{2067}Σοφία\u{2069}"), "σοφία");
+        assert_eq!(fold_sender("ΟΔΥΣΣΕΎΣ"), fold_sender("οδυσσεύς"));
+        assert_eq!(fold_sender("Weiß"), "weiss");
+        // Joiners are dropped at the ends but kept inside
+        assert_eq!(fold_sender("\u{200D}Bob\u{200C}"), "bob");
+        assert_eq!(
+            fold_sender(&format!("Dad {FAMILY}")),
+            format!("dad {FAMILY}")
+        );
+        assert_eq!(fold_sender("\u{200B}"), "");
+    }
+
+    #[test]
+    fn test_senders_match() {
+        assert!(senders_match("Alice", "ALICE"));
+        assert!(senders_match("Алия\u{200B}", "Алия"));
+        assert!(senders_match("АЛИЯ", "алия"));
+        assert!(senders_match("🎉 Party\u{200B}", "🎉 party"));
+        assert!(senders_match(
+            &format!("{FAMILY} Team"),
+            &format!("{FAMILY} TEAM")
+        ));
+        assert!(!senders_match(&format!("{FAMILY} Team"), "👨👩👧👦 Team"));
+        assert!(!senders_match("Alice", "Alice Smith"));
+        assert!(!senders_match("Алия", "Алина"));
+    }
+}
@@ -0,0 +1,300 @@
+//! Pluggable rewrites of message content.
+//!
+//! A [`ContentTransform`] edits one message in place: strip a bot's command
+//! prefix, turn ticket ids into links, redact a name. Transforms are
+//! collected in a [`TransformChain`] on
+//! [`ConvertOptions`](crate::convert::ConvertOptions) or
+//! [`PipelineConfig`](crate::pipeline::PipelineConfig), which run it after
+//! filtering and before merging, or applied directly with
+//! [`apply_transforms`](crate::core::processor::apply_transforms).
+//!
+//! Any `Fn(&mut Message)` closure is a transform. The built-in ones are
+//! [`ContentPolicy`](crate::config::ContentPolicy), which normalizes whitespace as the parsers do, and
+//! [`Redact`].
+//!
+//! # Ordering
+//!
+//! A chain runs its transforms in the order they were added, all of them
+//! on one message before the next message, so each transform sees the
+//! output of the ones before it.
+//!
+//! # Errors
+//!
+//! Transforms are infallible by design: [`ContentTransform::transform`]
+//! returns nothing. A transform that cannot handle a message should leave
+//! it unchanged; dropping messages is the filter's job.
+//!
+//! # Example
+//!
+//! ```
+//! use chatpack::Message;
+//! use chatpack::core::transform::{Redact, TransformChain};
+//!
+//! let chain = TransformChain::new()
+//!     .with(|msg: &mut Message| {
+//!         if let Some(rest) = msg.content.strip_prefix("!say ") {
+//!             msg.content = rest.to_string();
+//!         }
+//!     })
+//!     .with(Redact::new(["hunter2"]));
+//!
+//! let mut msg = Message::new("Bot", "!say my password is hunter2");
+//! chain.apply(&mut msg);
+//! assert_eq!(msg.content, "my password is [REDACTED]");
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::Message;
+use crate::core::links::find_urls;
+
+/// Rewrites a message in place.
+///
+/// Implemented for every `Fn(&mut Message)` closure. Transforms are shared
+/// between pipeline threads, so they must be `Send + Sync`.
+pub trait ContentTransform: Send + Sync {
+    /// Rewrites `msg`.
+    fn transform(&self, msg: &mut Message);
+
+    /// Name of the transform, for debugging output. Defaults to the type
+    /// name.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<F> ContentTransform for F
+where
+    F: Fn(&mut Message) + Send + Sync,
+{
+    fn transform(&self, msg: &mut Message) {
+        self(msg);
+    }
+}
+
+/// Normalizes whitespace in the content, as the parsers do under a
+/// platform config's policy.
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+impl ContentTransform for crate::config::ContentPolicy {
+    fn transform(&self, msg: &mut Message) {
+        crate::parsing::content::normalize_content_in_place(&mut msg.content, *self);
+    }
+
+    fn name(&self) -> &'static str {
+        "content_policy"
+    }
+}
+
+/// Replaces every occurrence of some terms, and optionally every URL, in
+/// the content with a placeholder.
+///
+/// Matching is exact and case-sensitive. Terms are replaced in the order
+/// given, so list a longer term before any term it contains. URLs, as
+/// defined in [`links`](crate::core::links), are replaced after the terms.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::Message;
+/// use chatpack::core::transform::{ContentTransform, Redact};
+///
+/// let redact = Redact::new(["ACME-1234"]).with_replacement("<ticket>");
+/// let mut msg = Message::new("Alice", "See ACME-1234");
+/// redact.transform(&mut msg);
+/// assert_eq!(msg.content, "See <ticket>");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redact {
+    /// Text to replace; empty terms are ignored.
+    pub terms: Vec<String>,
+    /// What each term is replaced with (default: `[REDACTED]`).
+    pub replacement: String,
+    /// Also replace every URL (default: false).
+    pub urls: bool,
+}
+
+impl Redact {
+    /// Creates a transform replacing `terms` with `[REDACTED]`.
+    pub fn new<I, S>(terms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            terms: terms.into_iter().map(Into::into).collect(),
+            replacement: "[REDACTED]".to_string(),
+            urls: false,
+        }
+    }
+
+    /// Sets whether URLs are replaced too.
+    #[must_use]
+    pub fn with_urls(mut self, redact: bool) -> Self {
+        self.urls = redact;
+        self
+    }
+
+    /// Sets what each term is replaced with.
+    #[must_use]
+    pub fn with_replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+}
+
+impl ContentTransform for Redact {
+    fn transform(&self, msg: &mut Message) {
+        for term in &self.terms {
+            if !term.is_empty() && msg.content.contains(term.as_str()) {
+                msg.content = msg.content.replace(term.as_str(), &self.replacement);
+            }
+        }
+        if self.urls {
+            let mut redacted = String::with_capacity(msg.content.len());
+            let mut end = 0;
+            for url in find_urls(&msg.content) {
+                redacted.push_str(&msg.content[end..url.start]);
+                redacted.push_str(&self.replacement);
+                end = url.end;
+            }
+            if end > 0 {
+                redacted.push_str(&msg.content[end..]);
+                msg.content = redacted;
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "redact"
+    }
+}
+
+/// Transforms run one after another on each message.
+///
+/// Cloning shares the transforms. See the [module docs](self) for the
+/// ordering guarantee.
+#[derive(Clone, Default)]
+pub struct TransformChain {
+    transforms: Vec<Arc<dyn ContentTransform>>,
+}
+
+impl TransformChain {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `transform` after the ones already in the chain.
+    #[must_use]
+    pub fn with(mut self, transform: impl ContentTransform + 'static) -> Self {
+        self.push(transform);
+        self
+    }
+
+    /// Adds `transform` after the ones already in the chain.
+    pub fn push(&mut self, transform: impl ContentTransform + 'static) {
+        self.transforms.push(Arc::new(transform));
+    }
+
+    /// Returns `true` if the chain has no transforms.
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Returns the number of transforms.
+    pub fn len(&self) -> usize {
+        self.transforms.len()
+    }
+
+    /// Returns the names of the transforms, in order.
+    pub fn names(&self) -> Vec<&str> {
+        self.transforms.iter().map(|t| t.name()).collect()
+    }
+
+    /// Runs every transform on `msg`, in order.
+    pub fn apply(&self, msg: &mut Message) {
+        for transform in &self.transforms {
+            transform.transform(msg);
+        }
+    }
+
+    /// Runs the chain on each message in turn.
+    pub fn apply_all(&self, messages: &mut [Message]) {
+        if self.is_empty() {
+            return;
+        }
+        for msg in messages {
+            self.apply(msg);
+        }
+    }
+}
+
+impl fmt::Debug for TransformChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ContentPolicy;
+
+    #[test]
+    fn test_chain_order() {
+        let chain = TransformChain::new()
+            .with(|msg: &mut Message| msg.content.push('a'))
+            .with(|msg: &mut Message| msg.content = msg.content.to_uppercase())
+            .with(|msg: &mut Message| msg.content.push('b'));
+
+        let mut msg = Message::new("Alice", "x");
+        chain.apply(&mut msg);
+        assert_eq!(msg.content, "XAb");
+        assert_eq!(chain.len(), 3);
+    }
+
+    #[test]
+    fn test_redact() {
+        let redact = Redact::new(["secret token", "secret", ""]);
+        let mut msg = Message::new("Alice", "the secret token and a secret");
+        redact.transform(&mut msg);
+        assert_eq!(msg.content, "the [REDACTED] and a [REDACTED]");
+    }
+
+    #[test]
+    fn test_redact_urls() {
+        let redact = Redact::new(["Bob"]).with_urls(true);
+        let mut msg = Message::new("Alice", "Bob, see <https://example.com/x>.");
+        redact.transform(&mut msg);
+        assert_eq!(msg.content, "[REDACTED], see <[REDACTED]>.");
+
+        let mut msg = Message::new("Alice", "https://example.com");
+        Redact::new(Vec::<String>::new()).transform(&mut msg);
+        assert_eq!(msg.content, "https://example.com");
+    }
+
+    #[test]
+    fn test_content_policy_transform() {
+        let mut msg = Message::new("Alice", "  hi\n\n\nthere ");
+        ContentPolicy::new()
+            .with_collapse_internal_blank_lines(true)
+            .transform(&mut msg);
+        assert_eq!(msg.content, "hi\n\nthere");
+    }
+
+    #[test]
+    fn test_names_and_debug() {
+        let chain = TransformChain::new()
+            .with(ContentPolicy::new())
+            .with(Redact::new(["x"]));
+        assert_eq!(chain.names(), ["content_policy", "redact"]);
+        assert_eq!(format!("{chain:?}"), r#"["content_policy", "redact"]"#);
+        assert!(TransformChain::new().is_empty());
+    }
+}
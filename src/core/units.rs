@@ -0,0 +1,517 @@
+//! Human-friendly durations and sizes.
+//!
+//! The one reading of `"5m"` or `"50MB"` in chatpack, for command-line
+//! flags and config files alike.
+//!
+//! - [`parse_duration`] takes a number followed by `s`, `m`, `h`, `d`, or
+//!   `w` (seconds to weeks). Numbers may have a fraction (`"1.5h"`), and
+//!   parts may be chained (`"1h30m"`, `"1d 12h"`). A bare `"0"` is zero.
+//! - [`parse_size`] takes a number of bytes, optionally followed by a
+//!   decimal unit (`KB`, `MB`, `GB`, `TB`: powers of 1000) or a binary one
+//!   (`KiB`, `MiB`, `GiB`, `TiB`: powers of 1024). A fraction of a byte is
+//!   dropped.
+//!
+//! Units ignore ASCII case and may be separated from their number by
+//! whitespace. Negative values are rejected, and so are commas, which
+//! some locales use as decimal separator: `"1,5h"` fails with a hint to
+//! write `"1.5h"` rather than being misread.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use chatpack::core::units::{parse_duration, parse_size};
+//!
+//! assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+//! assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5400));
+//! assert_eq!(parse_size("50MB").unwrap(), 50_000_000);
+//! assert_eq!(parse_size("256mib").unwrap(), 256 * 1024 * 1024);
+//!
+//! let err = parse_duration("1,5h").unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "\"1,5h\" uses a comma as decimal separator; write \"1.5h\" instead"
+//! );
+//! ```
+
+use std::time::Duration;
+
+use crate::error::UnitParseError;
+
+/// The grammar accepted by [`parse_duration`], as named in its errors.
+pub const DURATION_GRAMMAR: &str = "a number followed by s, m, h, d, or w, optionally chained, e.g. \"30s\", \"1.5h\", or \"1h30m\"";
+
+/// The grammar accepted by [`parse_size`], as named in its errors.
+pub const SIZE_GRAMMAR: &str = "a number of bytes, optionally followed by KB, MB, GB, TB (powers of 1000) or KiB, MiB, GiB, TiB (powers of 1024), e.g. \"50MB\" or \"256MiB\"";
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Duration units and their length in seconds.
+const DURATION_UNITS: [(&str, u128); 5] = [
+    ("s", 1),
+    ("m", 60),
+    ("h", 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("w", 7 * 24 * 60 * 60),
+];
+
+/// Size units and their size in bytes.
+const SIZE_UNITS: [(&str, u128); 9] = [
+    ("b", 1),
+    ("kb", 1_000),
+    ("mb", 1_000_000),
+    ("gb", 1_000_000_000),
+    ("tb", 1_000_000_000_000),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+];
+
+/// Fraction digits past this many are ignored; more cannot change a
+/// result by a whole nanosecond or byte.
+const MAX_FRACTION_DIGITS: usize = 18;
+
+/// Parses a duration such as `"30s"`, `"1.5h"`, or `"1h30m"`.
+///
+/// See the [module documentation](self) for the grammar.
+///
+/// # Errors
+///
+/// Returns a [`UnitParseError`] naming the accepted grammar if the value
+/// is empty, negative, uses a decimal comma, has a malformed number, a
+/// number without a unit, or an unknown unit, or if it exceeds
+/// `u64::MAX` seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, UnitParseError> {
+    let value = checked_value(input, DURATION_GRAMMAR)?;
+    if value.trim_start_matches('0').is_empty() {
+        return Ok(Duration::ZERO);
+    }
+
+    let overflow = || UnitParseError::Overflow {
+        input: input.to_string(),
+    };
+    let mut nanos: u128 = 0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let (number, after) = split_number(rest);
+        let number =
+            Number::parse(number).ok_or_else(|| invalid_number(input, DURATION_GRAMMAR))?;
+        let (unit, after) = split_unit(after.trim_start());
+        if unit.is_empty() {
+            return Err(UnitParseError::MissingUnit {
+                input: input.to_string(),
+                expected: DURATION_GRAMMAR,
+            });
+        }
+        let seconds = lookup(&DURATION_UNITS, unit, input, DURATION_GRAMMAR)?;
+        nanos = number
+            .scale(seconds * NANOS_PER_SEC)
+            .and_then(|part| nanos.checked_add(part))
+            .ok_or_else(overflow)?;
+        rest = after.trim_start();
+    }
+
+    let secs = u64::try_from(nanos / NANOS_PER_SEC).map_err(|_| overflow())?;
+    // The remainder is below one billion
+    let subsec = (nanos % NANOS_PER_SEC) as u32;
+    Ok(Duration::new(secs, subsec))
+}
+
+/// Parses a size in bytes such as `"4096"`, `"50MB"`, or `"256MiB"`.
+///
+/// See the [module documentation](self) for the grammar.
+///
+/// # Errors
+///
+/// Returns a [`UnitParseError`] naming the accepted grammar if the value
+/// is empty, negative, uses a decimal comma, has a malformed number or an
+/// unknown unit, or if it exceeds `u64::MAX` bytes.
+pub fn parse_size(input: &str) -> Result<u64, UnitParseError> {
+    let value = checked_value(input, SIZE_GRAMMAR)?;
+    let (number, unit) = split_number(value);
+    let number = Number::parse(number).ok_or_else(|| invalid_number(input, SIZE_GRAMMAR))?;
+    let unit = unit.trim_start();
+    let factor = if unit.is_empty() {
+        1
+    } else {
+        lookup(&SIZE_UNITS, unit, input, SIZE_GRAMMAR)?
+    };
+    number
+        .scale(factor)
+        .and_then(|bytes| u64::try_from(bytes).ok())
+        .ok_or_else(|| UnitParseError::Overflow {
+            input: input.to_string(),
+        })
+}
+
+/// Returns `input` trimmed, rejecting empty and negative values and
+/// decimal commas.
+fn checked_value<'a>(input: &'a str, expected: &'static str) -> Result<&'a str, UnitParseError> {
+    let value = input.trim();
+    if value.is_empty() {
+        return Err(UnitParseError::Empty { expected });
+    }
+    if value.starts_with('-') {
+        return Err(UnitParseError::Negative {
+            input: input.to_string(),
+            expected,
+        });
+    }
+    if value.contains(',') {
+        return Err(UnitParseError::CommaDecimal {
+            input: input.to_string(),
+            suggestion: value.replace(',', "."),
+        });
+    }
+    Ok(value)
+}
+
+fn invalid_number(input: &str, expected: &'static str) -> UnitParseError {
+    UnitParseError::InvalidNumber {
+        input: input.to_string(),
+        expected,
+    }
+}
+
+/// Splits `s` after its leading digits and dots.
+fn split_number(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Splits `s` before the next digit, dot, or whitespace.
+fn split_unit(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace())
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Returns the factor of `unit` in `units`, ignoring ASCII case.
+fn lookup(
+    units: &[(&str, u128)],
+    unit: &str,
+    input: &str,
+    expected: &'static str,
+) -> Result<u128, UnitParseError> {
+    units
+        .iter()
+        .find(|(name, _)| unit.eq_ignore_ascii_case(name))
+        .map(|&(_, factor)| factor)
+        .ok_or_else(|| UnitParseError::UnknownUnit {
+            unit: unit.to_string(),
+            input: input.to_string(),
+            expected,
+        })
+}
+
+/// A non-negative decimal number, split at its decimal point.
+struct Number<'a> {
+    whole: &'a str,
+    fraction: &'a str,
+}
+
+impl<'a> Number<'a> {
+    /// Splits `s`, which must hold at least one digit and at most one dot.
+    fn parse(s: &'a str) -> Option<Self> {
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let valid = !(whole.is_empty() && fraction.is_empty())
+            && whole.bytes().all(|b| b.is_ascii_digit())
+            && fraction.bytes().all(|b| b.is_ascii_digit());
+        valid.then_some(Self { whole, fraction })
+    }
+
+    /// Returns the number times `factor`, rounded down, or `None` on
+    /// overflow.
+    fn scale(&self, factor: u128) -> Option<u128> {
+        let whole: u128 = if self.whole.is_empty() {
+            0
+        } else {
+            // Only digits, so this fails on overflow alone
+            self.whole.parse().ok()?
+        };
+        let mut total = whole.checked_mul(factor)?;
+        let digits = &self.fraction[..self.fraction.len().min(MAX_FRACTION_DIGITS)];
+        if !digits.is_empty() {
+            let fraction: u128 = digits.parse().ok()?;
+            let scale = 10u128.pow(u32::try_from(digits.len()).ok()?);
+            total = total.checked_add(fraction.checked_mul(factor)? / scale)?;
+        }
+        Some(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    // =========================================================================
+    // parse_duration tests
+    // =========================================================================
+
+    #[test]
+    fn test_duration_units() {
+        assert_eq!(parse_duration("30s").unwrap(), secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), secs(300));
+        assert_eq!(parse_duration("4h").unwrap(), secs(4 * 3600));
+        assert_eq!(parse_duration("30d").unwrap(), secs(30 * 86_400));
+        assert_eq!(parse_duration("2w").unwrap(), secs(14 * 86_400));
+    }
+
+    #[test]
+    fn test_duration_ignores_case_and_whitespace() {
+        assert_eq!(parse_duration(" 4H ").unwrap(), secs(4 * 3600));
+        assert_eq!(parse_duration("5 m").unwrap(), secs(300));
+    }
+
+    #[test]
+    fn test_duration_decimal() {
+        assert_eq!(parse_duration("1.5h").unwrap(), secs(5400));
+        assert_eq!(parse_duration("0.5s").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration(".25m").unwrap(), secs(15));
+        assert_eq!(parse_duration("2.s").unwrap(), secs(2));
+        assert_eq!(
+            parse_duration("0.000000001s").unwrap(),
+            Duration::from_nanos(1)
+        );
+    }
+
+    #[test]
+    fn test_duration_compound() {
+        assert_eq!(parse_duration("1h30m").unwrap(), secs(5400));
+        assert_eq!(parse_duration("1d 12h").unwrap(), secs(36 * 3600));
+        assert_eq!(parse_duration("1w1d1h1m1s").unwrap(), secs(694_861));
+        assert_eq!(parse_duration("1.5h30m").unwrap(), secs(2 * 3600));
+    }
+
+    #[test]
+    fn test_duration_zero() {
+        assert_eq!(parse_duration("0").unwrap(), Duration::ZERO);
+        assert_eq!(parse_duration("000").unwrap(), Duration::ZERO);
+        assert_eq!(parse_duration("0s").unwrap(), Duration::ZERO);
+        assert_eq!(parse_duration("0.0h").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_duration_empty() {
+        for input in ["", "   "] {
+            assert_eq!(
+                parse_duration(input),
+                Err(UnitParseError::Empty {
+                    expected: DURATION_GRAMMAR
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_duration_negative() {
+        let err = parse_duration("-5m").unwrap_err();
+        assert!(matches!(err, UnitParseError::Negative { .. }));
+        assert!(
+            err.to_string()
+                .starts_with("\"-5m\" is negative; expected a number")
+        );
+    }
+
+    #[test]
+    fn test_duration_comma_decimal() {
+        assert_eq!(
+            parse_duration("1,5h"),
+            Err(UnitParseError::CommaDecimal {
+                input: "1,5h".into(),
+                suggestion: "1.5h".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_duration_missing_unit() {
+        let err = parse_duration("30").unwrap_err();
+        assert!(matches!(err, UnitParseError::MissingUnit { .. }));
+        assert_eq!(
+            err.to_string(),
+            format!("\"30\" has no unit; expected {DURATION_GRAMMAR}")
+        );
+        assert!(matches!(
+            parse_duration("1h30"),
+            Err(UnitParseError::MissingUnit { .. })
+        ));
+    }
+
+    #[test]
+    fn test_duration_unknown_unit() {
+        let err = parse_duration("5y").unwrap_err();
+        assert_eq!(
+            err,
+            UnitParseError::UnknownUnit {
+                unit: "y".into(),
+                input: "5y".into(),
+                expected: DURATION_GRAMMAR,
+            }
+        );
+        assert!(err.to_string().contains("s, m, h, d, or w"));
+        assert!(matches!(
+            parse_duration("5min"),
+            Err(UnitParseError::UnknownUnit { .. })
+        ));
+        assert!(matches!(
+            parse_duration("5%"),
+            Err(UnitParseError::UnknownUnit { .. })
+        ));
+        assert!(matches!(
+            parse_duration("1h-30m"),
+            Err(UnitParseError::UnknownUnit { ref unit, .. }) if unit == "h-"
+        ));
+    }
+
+    #[test]
+    fn test_duration_invalid_number() {
+        for input in ["h", "1.2.3h", "+5m", "."] {
+            assert!(
+                matches!(
+                    parse_duration(input),
+                    Err(UnitParseError::InvalidNumber { .. })
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_duration_overflow() {
+        let max = format!("{}s", u64::MAX);
+        assert_eq!(parse_duration(&max).unwrap(), secs(u64::MAX));
+        for input in [
+            format!("{}s", u128::from(u64::MAX) + 1),
+            format!("{}w", u64::MAX),
+            format!("{}s1s", u64::MAX),
+            "9".repeat(60) + "s",
+        ] {
+            assert_eq!(
+                parse_duration(&input),
+                Err(UnitParseError::Overflow {
+                    input: input.clone()
+                })
+            );
+        }
+    }
+
+    // =========================================================================
+    // parse_size tests
+    // =========================================================================
+
+    #[test]
+    fn test_size_bytes() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("4096B").unwrap(), 4096);
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert_eq!(parse_size("0MiB").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_size_decimal_units() {
+        assert_eq!(parse_size("50KB").unwrap(), 50_000);
+        assert_eq!(parse_size("50MB").unwrap(), 50_000_000);
+        assert_eq!(parse_size("2GB").unwrap(), 2_000_000_000);
+        assert_eq!(parse_size("1TB").unwrap(), 1_000_000_000_000);
+    }
+
+    #[test]
+    fn test_size_binary_units() {
+        assert_eq!(parse_size("1KiB").unwrap(), 1024);
+        assert_eq!(parse_size("256MiB").unwrap(), 256 << 20);
+        assert_eq!(parse_size("2GiB").unwrap(), 2 << 30);
+        assert_eq!(parse_size("1TiB").unwrap(), 1 << 40);
+    }
+
+    #[test]
+    fn test_size_ignores_case_and_whitespace() {
+        assert_eq!(parse_size("50mb").unwrap(), 50_000_000);
+        assert_eq!(parse_size("256 mib").unwrap(), 256 << 20);
+        assert_eq!(parse_size(" 1GIB ").unwrap(), 1 << 30);
+    }
+
+    #[test]
+    fn test_size_decimal_rounds_down() {
+        assert_eq!(parse_size("1.5KiB").unwrap(), 1536);
+        assert_eq!(parse_size("0.5GB").unwrap(), 500_000_000);
+        assert_eq!(parse_size("1.5").unwrap(), 1);
+        assert_eq!(parse_size("0.0001KB").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_size_max() {
+        assert_eq!(parse_size(&u64::MAX.to_string()).unwrap(), u64::MAX);
+        assert_eq!(parse_size(&format!("{}B", u64::MAX)).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_size_overflow() {
+        for input in [
+            (u128::from(u64::MAX) + 1).to_string(),
+            "20000000TB".to_string(),
+            "16777216TiB".to_string(),
+            "9".repeat(60),
+        ] {
+            assert_eq!(
+                parse_size(&input),
+                Err(UnitParseError::Overflow {
+                    input: input.clone()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_size_empty_and_negative() {
+        assert_eq!(
+            parse_size(""),
+            Err(UnitParseError::Empty {
+                expected: SIZE_GRAMMAR
+            })
+        );
+        assert!(matches!(
+            parse_size("-1MB"),
+            Err(UnitParseError::Negative { .. })
+        ));
+    }
+
+    #[test]
+    fn test_size_comma_decimal() {
+        let err = parse_size("1,5GB").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "\"1,5GB\" uses a comma as decimal separator; write \"1.5GB\" instead"
+        );
+    }
+
+    #[test]
+    fn test_size_unknown_unit() {
+        let err = parse_size("50M").unwrap_err();
+        assert!(matches!(err, UnitParseError::UnknownUnit { ref unit, .. } if unit == "M"));
+        assert!(err.to_string().contains("KiB, MiB, GiB, TiB"));
+        assert!(matches!(
+            parse_size("50 MB extra"),
+            Err(UnitParseError::UnknownUnit { .. })
+        ));
+    }
+
+    #[test]
+    fn test_size_invalid_number() {
+        for input in ["MB", "1.2.3MB", "+5", "."] {
+            assert!(
+                matches!(parse_size(input), Err(UnitParseError::InvalidNumber { .. })),
+                "{input}"
+            );
+        }
+    }
+}
@@ -13,8 +13,11 @@
 use std::io;
 use std::path::PathBuf;
 
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
+use crate::format::OutputFormat;
+
 /// A specialized [`Result`] type for chatpack operations.
 ///
 /// This type is broadly used across the library for any operation that
@@ -90,10 +93,22 @@ pub enum ChatpackError {
         expected: &'static str,
     },
 
+    /// Invalid time of day in a datetime filter.
+    ///
+    /// The date part parsed correctly, but the time part did not match
+    /// `HH:MM` or `HH:MM:SS`.
+    #[error("Invalid time in '{input}'. Expected format: {expected}")]
+    InvalidTime {
+        /// The full datetime string that was provided
+        input: String,
+        /// Expected format description
+        expected: &'static str,
+    },
+
     /// CSV writing error.
     ///
     /// This can occur when writing output to CSV format.
-    #[cfg(any(feature = "csv-output", feature = "discord"))]
+    #[cfg(any(feature = "csv-output", feature = "discord-csv"))]
     #[error("CSV error: {0}")]
     Csv(#[from] csv::Error),
 
@@ -102,9 +117,12 @@ pub enum ChatpackError {
     /// This can occur when parsing or writing JSON.
     #[cfg(any(
         feature = "telegram",
+        feature = "whatsapp",
         feature = "instagram",
-        feature = "discord",
-        feature = "json-output"
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
     ))]
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
@@ -146,6 +164,132 @@ pub enum ChatpackError {
         /// Context about what was being parsed
         context: String,
     },
+
+    /// The input file exceeds the in-memory size limit.
+    ///
+    /// Checked before reading, so a mistakenly chosen multi-gigabyte file
+    /// fails fast instead of exhausting memory. The limit is the
+    /// `max_file_size` field of the platform config.
+    #[error("File too large: {size} bytes (limit: {limit} bytes). {hint}")]
+    FileTooLarge {
+        /// Size of the file in bytes
+        size: u64,
+        /// Configured limit in bytes
+        limit: u64,
+        /// Suggested way around the limit
+        hint: &'static str,
+    },
+
+    /// The operation was cancelled before it finished.
+    ///
+    /// Returned by `pipeline::run_pipelined` when its cancellation token is
+    /// cancelled. Output written so far is complete up to the last message
+    /// processed.
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    /// A filter configuration that cannot match what the caller meant.
+    ///
+    /// Returned by [`FilterConfig::validate`](crate::core::FilterConfig::validate)
+    /// and by operations that validate filters strictly.
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(#[from] FilterConfigError),
+
+    /// A duration or size that could not be read.
+    ///
+    /// Returned for values parsed with
+    /// [`parse_duration`](crate::core::units::parse_duration) and
+    /// [`parse_size`](crate::core::units::parse_size).
+    #[error("Invalid value: {0}")]
+    InvalidUnit(#[from] UnitParseError),
+
+    /// The output file already exists and the write was told not to
+    /// replace it.
+    ///
+    /// Returned by writers given
+    /// [`OnExists::Error`](crate::core::output::OnExists::Error). The
+    /// existing file is left untouched.
+    #[error("Output file {} already exists; choose another path or allow overwriting", path.display())]
+    OutputExists {
+        /// The path that was not written
+        path: PathBuf,
+    },
+
+    /// No messages were left to write and the conversion was told not to
+    /// write an empty output.
+    ///
+    /// Returned by conversions given
+    /// [`OnEmpty::Error`](crate::core::output::OnEmpty::Error). No file is
+    /// created or replaced.
+    #[error("No messages to write to {}; the input is empty or the filters matched nothing", path.display())]
+    EmptyOutput {
+        /// The path that was not written
+        path: PathBuf,
+    },
+
+    /// An output read back after a run does not match what the run
+    /// reported.
+    ///
+    /// Returned by [`pipeline::verify`](crate::pipeline::verify). Each
+    /// mismatch is one line of the message.
+    #[error("Output {} failed verification:\n  {}", path.display(), mismatches.join("\n  "))]
+    VerificationFailed {
+        /// The output that was checked
+        path: PathBuf,
+        /// What did not match, one description per check
+        mismatches: Vec<String>,
+    },
+
+    /// The requested output format is not compiled into this build.
+    ///
+    /// Returned by [`write_to_format`](crate::format::write_to_format),
+    /// [`to_format_string`](crate::format::to_format_string), and the other
+    /// format dispatchers. Check up front with
+    /// [`OutputFormat::is_enabled`].
+    #[error(
+        "Output format {format} is not available in this build; enable the '{feature}' feature"
+    )]
+    UnsupportedFormat {
+        /// The format that was requested
+        format: OutputFormat,
+        /// The Cargo feature that provides it
+        feature: &'static str,
+    },
+
+    /// The input format is not compiled into this build.
+    ///
+    /// Returned by `DiscordParser` for TXT and CSV exports when the
+    /// `discord-txt` or `discord-csv` feature is disabled.
+    #[error("Input format {format} is not available in this build; enable the '{feature}' feature")]
+    UnsupportedInputFormat {
+        /// The format that was detected, e.g. `"Discord TXT"`
+        format: &'static str,
+        /// The Cargo feature that provides it
+        feature: &'static str,
+    },
+
+    /// A configuration update was rejected.
+    ///
+    /// Returned by `Parser::set_config_json` for unknown option names and
+    /// values of the wrong type. The configuration is left unchanged.
+    #[error("Invalid configuration: {message}")]
+    InvalidConfig {
+        /// What was wrong, naming the option
+        message: String,
+    },
+
+    /// The parser cannot perform the requested operation on this input.
+    ///
+    /// Returned by `StreamingParser::stream_tail` for exports that cannot
+    /// be read from the end, such as a single JSON document. Callers can
+    /// fall back to a full parse.
+    #[error("{operation} is not supported by {parser}")]
+    UnsupportedOperation {
+        /// The operation that was requested
+        operation: &'static str,
+        /// The parser, and the input kind where it matters
+        parser: String,
+    },
 }
 
 /// Kinds of parse errors that can occur.
@@ -154,9 +298,12 @@ pub enum ParseErrorKind {
     /// JSON parsing error
     #[cfg(any(
         feature = "telegram",
+        feature = "whatsapp",
         feature = "instagram",
-        feature = "discord",
-        feature = "json-output"
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
     ))]
     #[error("{0}")]
     Json(#[from] serde_json::Error),
@@ -177,9 +324,12 @@ pub enum StreamingErrorKind {
     /// JSON parsing error during streaming
     #[cfg(any(
         feature = "telegram",
+        feature = "whatsapp",
         feature = "instagram",
-        feature = "discord",
-        feature = "json-output"
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
     ))]
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
@@ -194,6 +344,86 @@ pub enum StreamingErrorKind {
     UnexpectedEof,
 }
 
+/// Problems found by [`FilterConfig::validate`](crate::core::FilterConfig::validate).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum FilterConfigError {
+    /// The start bound is later than the end bound, so nothing can match.
+    #[error("start {after} is after end {before}; no message can match")]
+    InvertedDateRange {
+        /// The start bound
+        after: DateTime<Utc>,
+        /// The end bound
+        before: DateTime<Utc>,
+    },
+    /// The sender filter is empty or whitespace-only, so nothing can match.
+    #[error("sender filter {0:?} is blank; no message can match")]
+    BlankSender(String),
+}
+
+/// Problems found by [`parse_duration`](crate::core::units::parse_duration)
+/// and [`parse_size`](crate::core::units::parse_size).
+///
+/// Each message names the grammar that was expected.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum UnitParseError {
+    /// The value is empty or whitespace-only.
+    #[error("empty value; expected {expected}")]
+    Empty {
+        /// The accepted grammar
+        expected: &'static str,
+    },
+    /// The value is negative.
+    #[error("{input:?} is negative; expected {expected}")]
+    Negative {
+        /// The value as given
+        input: String,
+        /// The accepted grammar
+        expected: &'static str,
+    },
+    /// The value uses a comma as its decimal separator.
+    #[error("{input:?} uses a comma as decimal separator; write {suggestion:?} instead")]
+    CommaDecimal {
+        /// The value as given
+        input: String,
+        /// The value with a dot as decimal separator
+        suggestion: String,
+    },
+    /// A number is missing or malformed.
+    #[error("{input:?} is not a valid number; expected {expected}")]
+    InvalidNumber {
+        /// The value as given
+        input: String,
+        /// The accepted grammar
+        expected: &'static str,
+    },
+    /// A number has no unit where one is required.
+    #[error("{input:?} has no unit; expected {expected}")]
+    MissingUnit {
+        /// The value as given
+        input: String,
+        /// The accepted grammar
+        expected: &'static str,
+    },
+    /// The unit is not one of the accepted ones.
+    #[error("unknown unit {unit:?} in {input:?}; expected {expected}")]
+    UnknownUnit {
+        /// The unit as given
+        unit: String,
+        /// The value as given
+        input: String,
+        /// The accepted grammar
+        expected: &'static str,
+    },
+    /// The value does not fit the result type.
+    #[error("{input:?} is too large")]
+    Overflow {
+        /// The value as given
+        input: String,
+    },
+}
+
 impl From<std::string::FromUtf8Error> for ChatpackError {
     fn from(err: std::string::FromUtf8Error) -> Self {
         ChatpackError::Utf8 {
@@ -238,7 +468,7 @@ impl ChatpackError {
     }
 
     /// Creates a parse error for Discord format.
-    #[cfg(feature = "discord")]
+    #[cfg(feature = "discord-json")]
     pub fn discord_parse(source: serde_json::Error, path: Option<PathBuf>) -> Self {
         ChatpackError::Parse {
             format: "Discord",
@@ -263,6 +493,14 @@ impl ChatpackError {
         }
     }
 
+    /// Creates an invalid time error.
+    pub fn invalid_time(input: impl Into<String>) -> Self {
+        ChatpackError::InvalidTime {
+            input: input.into(),
+            expected: "YYYY-MM-DD HH:MM[:SS]",
+        }
+    }
+
     /// Creates a streaming error from components.
     pub fn streaming(kind: StreamingErrorKind) -> Self {
         ChatpackError::Streaming(kind)
@@ -283,6 +521,61 @@ impl ChatpackError {
         }
     }
 
+    /// Creates a file-too-large error suggesting streaming.
+    pub fn file_too_large(size: u64, limit: u64) -> Self {
+        ChatpackError::FileTooLarge {
+            size,
+            limit,
+            hint: "Use streaming (Parser::stream with streaming enabled) or raise max_file_size",
+        }
+    }
+
+    /// Creates an error for an output file that must not be replaced.
+    pub fn output_exists(path: impl Into<PathBuf>) -> Self {
+        ChatpackError::OutputExists { path: path.into() }
+    }
+
+    /// Creates an error for an output that would have no messages.
+    pub fn empty_output(path: impl Into<PathBuf>) -> Self {
+        ChatpackError::EmptyOutput { path: path.into() }
+    }
+
+    /// Creates an error for an output that failed verification.
+    pub fn verification_failed(path: impl Into<PathBuf>, mismatches: Vec<String>) -> Self {
+        ChatpackError::VerificationFailed {
+            path: path.into(),
+            mismatches,
+        }
+    }
+
+    /// Creates an error for an output format whose feature is disabled.
+    pub fn unsupported_format(format: OutputFormat) -> Self {
+        ChatpackError::UnsupportedFormat {
+            format,
+            feature: format.required_feature(),
+        }
+    }
+
+    /// Creates an error for an input format whose feature is disabled.
+    pub fn unsupported_input_format(format: &'static str, feature: &'static str) -> Self {
+        ChatpackError::UnsupportedInputFormat { format, feature }
+    }
+
+    /// Creates an error for a rejected configuration update.
+    pub fn invalid_config(message: impl Into<String>) -> Self {
+        ChatpackError::InvalidConfig {
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error for an operation a parser does not support.
+    pub fn unsupported_operation(operation: &'static str, parser: impl Into<String>) -> Self {
+        ChatpackError::UnsupportedOperation {
+            operation,
+            parser: parser.into(),
+        }
+    }
+
     /// Returns `true` if this is an IO error.
     pub fn is_io(&self) -> bool {
         matches!(self, ChatpackError::Io(_))
@@ -299,8 +592,189 @@ impl ChatpackError {
     }
 
     /// Returns `true` if this is a date-related error.
+    ///
+    /// This includes invalid time-of-day errors from datetime filters.
     pub fn is_invalid_date(&self) -> bool {
-        matches!(self, ChatpackError::InvalidDate { .. })
+        matches!(
+            self,
+            ChatpackError::InvalidDate { .. } | ChatpackError::InvalidTime { .. }
+        )
+    }
+
+    /// Returns `true` if the time part of a datetime filter was invalid.
+    pub fn is_invalid_time(&self) -> bool {
+        matches!(self, ChatpackError::InvalidTime { .. })
+    }
+
+    /// Returns `true` if the input exceeded the in-memory size limit.
+    pub fn is_file_too_large(&self) -> bool {
+        matches!(self, ChatpackError::FileTooLarge { .. })
+    }
+
+    /// Returns `true` if a filter configuration was rejected.
+    pub fn is_invalid_filter(&self) -> bool {
+        matches!(self, ChatpackError::InvalidFilter(_))
+    }
+
+    /// Returns `true` if a duration or size could not be read.
+    pub fn is_invalid_unit(&self) -> bool {
+        matches!(self, ChatpackError::InvalidUnit(_))
+    }
+
+    /// Returns `true` if an existing output file stopped the write.
+    pub fn is_output_exists(&self) -> bool {
+        matches!(self, ChatpackError::OutputExists { .. })
+    }
+
+    /// Returns `true` if an empty output stopped the write.
+    pub fn is_empty_output(&self) -> bool {
+        matches!(self, ChatpackError::EmptyOutput { .. })
+    }
+
+    /// Returns `true` if an output failed verification.
+    pub fn is_verification_failed(&self) -> bool {
+        matches!(self, ChatpackError::VerificationFailed { .. })
+    }
+
+    /// Returns `true` if the requested output format is not compiled in.
+    pub fn is_unsupported_format(&self) -> bool {
+        matches!(self, ChatpackError::UnsupportedFormat { .. })
+    }
+
+    /// Returns `true` if the input format is not compiled in.
+    pub fn is_unsupported_input_format(&self) -> bool {
+        matches!(self, ChatpackError::UnsupportedInputFormat { .. })
+    }
+
+    /// Returns `true` if a configuration update was rejected.
+    pub fn is_invalid_config(&self) -> bool {
+        matches!(self, ChatpackError::InvalidConfig { .. })
+    }
+
+    /// Returns `true` if the parser does not support the operation.
+    pub fn is_unsupported_operation(&self) -> bool {
+        matches!(self, ChatpackError::UnsupportedOperation { .. })
+    }
+
+    /// Returns `true` if the operation was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, ChatpackError::Cancelled)
+    }
+}
+
+// ============================================================================
+// Process exit codes
+// ============================================================================
+
+/// Exit code for invalid arguments: bad dates or times, invalid filters,
+/// inputs over the size limit, and outputs that must not be replaced.
+pub const EXIT_USAGE: i32 = 2;
+
+/// Exit code for an input file that does not exist.
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+/// Exit code for input that could not be parsed.
+pub const EXIT_PARSE: i32 = 4;
+
+/// Exit code for any other I/O failure, typically writing the output.
+pub const EXIT_IO: i32 = 5;
+
+/// Exit code for a conversion that had no messages to write and was told
+/// to fail instead of writing an empty output.
+pub const EXIT_EMPTY: i32 = 6;
+
+/// Exit code for an output that did not pass verification after it was
+/// written.
+pub const EXIT_VERIFY: i32 = 7;
+
+/// Exit code for errors without a more specific category.
+pub const EXIT_INTERNAL: i32 = 10;
+
+/// Maps an error to a stable process exit code.
+///
+/// Only [`EXIT_IO`] is worth retrying; the other codes fail the same way
+/// until the input or arguments change.
+///
+/// | Code | Errors |
+/// |------|--------|
+/// | [`EXIT_USAGE`] (2) | `InvalidDate`, `InvalidTime`, `InvalidFilter`, `InvalidUnit`, `FileTooLarge`, `OutputExists`, `UnsupportedFormat`, `UnsupportedInputFormat`, `InvalidConfig`, `UnsupportedOperation` |
+/// | [`EXIT_NOT_FOUND`] (3) | I/O errors of kind [`NotFound`](io::ErrorKind::NotFound) |
+/// | [`EXIT_PARSE`] (4) | `Parse`, `InvalidFormat`, `Utf8`, `BufferOverflow`, `UnexpectedEof`, and CSV, JSON, or streaming errors not caused by I/O |
+/// | [`EXIT_IO`] (5) | Other I/O errors |
+/// | [`EXIT_EMPTY`] (6) | `EmptyOutput` |
+/// | [`EXIT_VERIFY`] (7) | `VerificationFailed` |
+/// | [`EXIT_INTERNAL`] (10) | `Cancelled` and anything else |
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::error::{EXIT_USAGE, error_exit_code};
+/// use chatpack::core::FilterConfig;
+///
+/// let err = FilterConfig::new().with_date_from("yesterday-ish").unwrap_err();
+/// assert_eq!(error_exit_code(&err), EXIT_USAGE);
+/// ```
+pub fn error_exit_code(err: &ChatpackError) -> i32 {
+    match err {
+        ChatpackError::InvalidDate { .. }
+        | ChatpackError::InvalidTime { .. }
+        | ChatpackError::InvalidFilter(_)
+        | ChatpackError::InvalidUnit(_)
+        | ChatpackError::FileTooLarge { .. }
+        | ChatpackError::OutputExists { .. }
+        | ChatpackError::UnsupportedFormat { .. }
+        | ChatpackError::UnsupportedInputFormat { .. }
+        | ChatpackError::InvalidConfig { .. }
+        | ChatpackError::UnsupportedOperation { .. } => EXIT_USAGE,
+        ChatpackError::Io(e) | ChatpackError::Streaming(StreamingErrorKind::Io(e)) => {
+            io_exit_code(e)
+        }
+        #[cfg(any(feature = "csv-output", feature = "discord-csv"))]
+        ChatpackError::Csv(e) => match e.kind() {
+            csv::ErrorKind::Io(e) => io_exit_code(e),
+            _ => EXIT_PARSE,
+        },
+        #[cfg(any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json",
+            feature = "json-output",
+            feature = "fixtures",
+            feature = "ffi"
+        ))]
+        ChatpackError::Json(e) | ChatpackError::Streaming(StreamingErrorKind::Json(e))
+            if e.is_io() =>
+        {
+            EXIT_IO
+        }
+        ChatpackError::Parse { .. }
+        | ChatpackError::InvalidFormat { .. }
+        | ChatpackError::Utf8 { .. }
+        | ChatpackError::BufferOverflow { .. }
+        | ChatpackError::UnexpectedEof { .. }
+        | ChatpackError::Streaming(_) => EXIT_PARSE,
+        #[cfg(any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json",
+            feature = "json-output",
+            feature = "fixtures",
+            feature = "ffi"
+        ))]
+        ChatpackError::Json(_) => EXIT_PARSE,
+        ChatpackError::EmptyOutput { .. } => EXIT_EMPTY,
+        ChatpackError::VerificationFailed { .. } => EXIT_VERIFY,
+        ChatpackError::Cancelled => EXIT_INTERNAL,
+    }
+}
+
+fn io_exit_code(err: &io::Error) -> i32 {
+    if err.kind() == io::ErrorKind::NotFound {
+        EXIT_NOT_FOUND
+    } else {
+        EXIT_IO
     }
 }
 
@@ -314,7 +788,7 @@ impl ChatpackError {
         feature = "telegram",
         feature = "whatsapp",
         feature = "instagram",
-        feature = "discord"
+        feature = "discord-json"
     )
 ))]
 impl From<crate::streaming::StreamingError> for ChatpackError {
@@ -324,7 +798,7 @@ impl From<crate::streaming::StreamingError> for ChatpackError {
             crate::streaming::StreamingError::Io(e) => {
                 ChatpackError::Streaming(StreamingErrorKind::Io(e))
             }
-            #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord"))]
+            #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
             crate::streaming::StreamingError::Json(e) => {
                 ChatpackError::Streaming(StreamingErrorKind::Json(e))
             }
@@ -372,9 +846,12 @@ mod tests {
 
     #[cfg(any(
         feature = "telegram",
+        feature = "whatsapp",
         feature = "instagram",
-        feature = "discord",
-        feature = "json-output"
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
     ))]
     #[test]
     fn test_parse_error_with_path() {
@@ -431,6 +908,17 @@ mod tests {
         assert!(display.contains("YYYY-MM-DD"));
     }
 
+    #[test]
+    fn test_invalid_time_display() {
+        let err = ChatpackError::invalid_time("2024-01-15 25:00");
+        let display = err.to_string();
+        assert!(display.contains("Invalid time"));
+        assert!(display.contains("2024-01-15 25:00"));
+        assert!(err.is_invalid_date());
+        assert!(err.is_invalid_time());
+        assert!(!ChatpackError::invalid_date("bad").is_invalid_time());
+    }
+
     #[test]
     fn test_buffer_overflow_display() {
         let err = ChatpackError::buffer_overflow(1024, 2048);
@@ -447,6 +935,184 @@ mod tests {
         assert!(display.contains("parsing JSON array"));
     }
 
+    #[test]
+    fn test_file_too_large_display() {
+        let err = ChatpackError::file_too_large(4096, 1024);
+        let display = err.to_string();
+        assert!(display.contains("4096 bytes"));
+        assert!(display.contains("limit: 1024 bytes"));
+        assert!(display.contains("streaming"));
+        assert!(err.is_file_too_large());
+        assert!(!err.is_io());
+    }
+
+    #[test]
+    fn test_output_exists_display() {
+        let err = ChatpackError::output_exists("out/chat.csv");
+        assert_eq!(
+            err.to_string(),
+            "Output file out/chat.csv already exists; choose another path or allow overwriting"
+        );
+        assert!(err.is_output_exists());
+        assert!(!err.is_io());
+    }
+
+    #[test]
+    fn test_empty_output_display() {
+        let err = ChatpackError::empty_output("out/chat.csv");
+        assert!(
+            err.to_string()
+                .contains("No messages to write to out/chat.csv")
+        );
+        assert!(err.is_empty_output());
+        assert!(!err.is_output_exists());
+    }
+
+    #[test]
+    fn test_verification_failed_display() {
+        let err = ChatpackError::verification_failed(
+            "out.csv",
+            vec![
+                "expected 3 entries, found 2".into(),
+                "entry 2 has no sender".into(),
+            ],
+        );
+        assert_eq!(
+            err.to_string(),
+            "Output out.csv failed verification:\n  expected 3 entries, found 2\n  entry 2 has no sender"
+        );
+        assert!(err.is_verification_failed());
+    }
+
+    #[test]
+    fn test_unsupported_format_display() {
+        let err = ChatpackError::unsupported_format(OutputFormat::Jsonl);
+        assert_eq!(
+            err.to_string(),
+            "Output format JSONL is not available in this build; enable the 'json-output' feature"
+        );
+        assert!(err.is_unsupported_format());
+        assert!(!err.is_invalid_format());
+    }
+
+    #[test]
+    fn test_unsupported_input_format_display() {
+        let err = ChatpackError::unsupported_input_format("Discord TXT", "discord-txt");
+        assert_eq!(
+            err.to_string(),
+            "Input format Discord TXT is not available in this build; enable the 'discord-txt' feature"
+        );
+        assert!(err.is_unsupported_input_format());
+        assert!(!err.is_unsupported_format());
+    }
+
+    #[test]
+    fn test_unsupported_operation_display() {
+        let err = ChatpackError::unsupported_operation("tail reading", "Discord (Streaming) JSON");
+        assert_eq!(
+            err.to_string(),
+            "tail reading is not supported by Discord (Streaming) JSON"
+        );
+        assert!(err.is_unsupported_operation());
+        assert!(!err.is_unsupported_format());
+    }
+
+    #[test]
+    fn test_cancelled_display() {
+        let err = ChatpackError::Cancelled;
+        assert_eq!(err.to_string(), "Operation cancelled");
+        assert!(err.is_cancelled());
+        assert!(!err.is_io());
+    }
+
+    #[test]
+    fn test_error_exit_codes() {
+        let not_found = io::Error::new(io::ErrorKind::NotFound, "missing");
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        let cases = [
+            (ChatpackError::invalid_date("x"), EXIT_USAGE),
+            (ChatpackError::invalid_time("x"), EXIT_USAGE),
+            (
+                FilterConfigError::BlankSender(String::new()).into(),
+                EXIT_USAGE,
+            ),
+            (ChatpackError::file_too_large(2, 1), EXIT_USAGE),
+            (ChatpackError::output_exists("out.csv"), EXIT_USAGE),
+            (
+                ChatpackError::invalid_config("unknown option 'x'"),
+                EXIT_USAGE,
+            ),
+            (
+                ChatpackError::unsupported_format(OutputFormat::Json),
+                EXIT_USAGE,
+            ),
+            (
+                ChatpackError::unsupported_input_format("Discord CSV", "discord-csv"),
+                EXIT_USAGE,
+            ),
+            (
+                ChatpackError::unsupported_operation("tail reading", "Telegram (Streaming)"),
+                EXIT_USAGE,
+            ),
+            (not_found.into(), EXIT_NOT_FOUND),
+            (denied.into(), EXIT_IO),
+            (ChatpackError::invalid_format("Test", "bad"), EXIT_PARSE),
+            (ChatpackError::unexpected_eof("reading"), EXIT_PARSE),
+            (
+                ChatpackError::streaming(StreamingErrorKind::UnexpectedEof),
+                EXIT_PARSE,
+            ),
+            (ChatpackError::empty_output("out.csv"), EXIT_EMPTY),
+            (
+                ChatpackError::verification_failed("out.csv", vec!["x".into()]),
+                EXIT_VERIFY,
+            ),
+            (ChatpackError::Cancelled, EXIT_INTERNAL),
+        ];
+        for (err, code) in cases {
+            assert_eq!(error_exit_code(&err), code, "{err}");
+        }
+    }
+
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
+    ))]
+    #[test]
+    fn test_error_exit_code_json() {
+        let syntax = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert_eq!(error_exit_code(&syntax.into()), EXIT_PARSE);
+
+        let io = serde_json::Error::io(io::Error::other("disk full"));
+        assert_eq!(error_exit_code(&io.into()), EXIT_IO);
+    }
+
+    #[test]
+    fn test_invalid_filter_display() {
+        let err = ChatpackError::from(FilterConfigError::BlankSender(" ".into()));
+        assert_eq!(
+            err.to_string(),
+            "Invalid filter: sender filter \" \" is blank; no message can match"
+        );
+        assert!(err.is_invalid_filter());
+        assert!(!err.is_invalid_date());
+    }
+
+    #[test]
+    fn test_invalid_unit_display() {
+        let err = ChatpackError::from(UnitParseError::Overflow {
+            input: "20EiB".into(),
+        });
+        assert_eq!(err.to_string(), "Invalid value: \"20EiB\" is too large");
+        assert!(err.is_invalid_unit());
+        assert_eq!(error_exit_code(&err), EXIT_USAGE);
+    }
+
     #[test]
     fn test_streaming_error_display() {
         let err =
@@ -571,7 +1237,7 @@ mod tests {
         assert!(err.to_string().contains("Instagram JSON"));
     }
 
-    #[cfg(feature = "discord")]
+    #[cfg(feature = "discord-json")]
     #[test]
     fn test_discord_parse_constructor() {
         let json_err = serde_json::from_str::<serde_json::Value>("invalid").unwrap_err();
@@ -598,7 +1264,7 @@ mod tests {
         assert!(err.is_io());
     }
 
-    #[cfg(any(feature = "csv-output", feature = "discord"))]
+    #[cfg(any(feature = "csv-output", feature = "discord-csv"))]
     #[test]
     fn test_from_csv_error() {
         // Create a CSV error by using a writer and forcing an error
@@ -626,9 +1292,12 @@ mod tests {
 
     #[cfg(any(
         feature = "telegram",
+        feature = "whatsapp",
         feature = "instagram",
-        feature = "discord",
-        feature = "json-output"
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
     ))]
     #[test]
     fn test_from_json_error() {
@@ -658,9 +1327,12 @@ mod tests {
 
     #[cfg(any(
         feature = "telegram",
+        feature = "whatsapp",
         feature = "instagram",
-        feature = "discord",
-        feature = "json-output"
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
     ))]
     #[test]
     fn test_streaming_error_kind_json() {
@@ -712,9 +1384,12 @@ mod tests {
 
     #[cfg(any(
         feature = "telegram",
+        feature = "whatsapp",
         feature = "instagram",
-        feature = "discord",
-        feature = "json-output"
+        feature = "discord-json",
+        feature = "json-output",
+        feature = "fixtures",
+        feature = "ffi"
     ))]
     #[test]
     fn test_parse_error_kind_json() {
@@ -0,0 +1,588 @@
+//! C ABI for embedding chatpack in other languages.
+//!
+//! Enabled with the `ffi` feature. Build a shared library with:
+//!
+//! ```bash
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! The matching header is `include/chatpack.h`. Update it with any change
+//! to the functions here; `cbindgen --config cbindgen.toml` regenerates it.
+//!
+//! # Conventions
+//!
+//! - Every function returns a status code: [`CHATPACK_OK`] on success, a
+//!   negative `CHATPACK_ERR_*` value on failure.
+//! - On failure, [`chatpack_last_error`] returns a description of the error.
+//! - Strings returned through `out_*` parameters are owned by the caller and
+//!   must be released with [`chatpack_free_string`].
+//! - Panics never cross the boundary; they are reported as
+//!   [`CHATPACK_ERR_PANIC`].
+//!
+//! # Example (C)
+//!
+//! ```c
+//! char *json = NULL;
+//! if (chatpack_parse("telegram", "result.json", &json) == CHATPACK_OK) {
+//!     puts(json);
+//!     chatpack_free_string(json);
+//! } else {
+//!     fprintf(stderr, "chatpack: %s\n", chatpack_last_error());
+//! }
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+use serde::Deserialize;
+
+use crate::core::filter::{FilterConfig, apply_filters};
+use crate::core::models::OutputConfig;
+use crate::core::processor::{
+    DialogueConfig, SenderMap, extract_dialogue, map_senders, merge_consecutive,
+};
+use crate::error::ChatpackError;
+use crate::format::{OutputFormat, to_format_string};
+use crate::parser::{Platform, create_parser};
+
+/// The call succeeded.
+pub const CHATPACK_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const CHATPACK_ERR_NULL_POINTER: i32 = -1;
+/// A string argument was not valid UTF-8.
+pub const CHATPACK_ERR_INVALID_UTF8: i32 = -2;
+/// An argument was invalid (unknown platform, malformed config, bad date).
+pub const CHATPACK_ERR_INVALID_ARGUMENT: i32 = -3;
+/// The input file could not be read.
+pub const CHATPACK_ERR_IO: i32 = -4;
+/// The export could not be parsed or the output could not be produced.
+pub const CHATPACK_ERR_PARSE: i32 = -5;
+/// A panic was caught at the boundary.
+pub const CHATPACK_ERR_PANIC: i32 = -6;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Error carried to the boundary, where it becomes a status code.
+struct FfiError {
+    code: i32,
+    message: String,
+}
+
+impl FfiError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ChatpackError> for FfiError {
+    fn from(err: ChatpackError) -> Self {
+        let code = if err.is_io() {
+            CHATPACK_ERR_IO
+        } else if err.is_invalid_date() {
+            CHATPACK_ERR_INVALID_ARGUMENT
+        } else {
+            CHATPACK_ERR_PARSE
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+/// Pipeline configuration accepted by [`chatpack_convert`] as JSON.
+///
+/// Every field is optional:
+///
+/// ```json
+/// {
+///   "format": "jsonl",
+///   "merge": true,
+///   "after": "2024-01-01",
+///   "before": "2024-06-30 18:00",
+///   "sender": "Alice",
+///   "sender_map": {"aliases": {"Alice Smith": "Alice"}},
+///   "dialogue": ["Alice", "Bob"],
+///   "output": {"include_timestamps": true}
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConvertConfig {
+    format: OutputFormat,
+    merge: bool,
+    after: Option<String>,
+    before: Option<String>,
+    sender: Option<String>,
+    sender_map: SenderMap,
+    dialogue: Option<(String, String)>,
+    output: OutputConfig,
+}
+
+impl Default for ConvertConfig {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::default(),
+            merge: true,
+            after: None,
+            before: None,
+            sender: None,
+            sender_map: SenderMap::default(),
+            dialogue: None,
+            output: OutputConfig::default(),
+        }
+    }
+}
+
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|m| {
+        // Interior NULs would truncate the message on the C side
+        CString::new(m.replace('\0', "\\0")).unwrap_or_default()
+    });
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = message);
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Runs `f`, converting its error or panic into a status code.
+fn guard(f: impl FnOnce() -> Result<(), FfiError>) -> i32 {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => {
+            set_last_error(None);
+            CHATPACK_OK
+        }
+        Ok(Err(err)) => {
+            set_last_error(Some(err.message));
+            err.code
+        }
+        Err(payload) => {
+            set_last_error(Some(format!("panic: {}", panic_message(&*payload))));
+            CHATPACK_ERR_PANIC
+        }
+    }
+}
+
+/// Borrows a C string argument as `&str`.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that outlives `'a`.
+unsafe fn read_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::new(
+            CHATPACK_ERR_NULL_POINTER,
+            format!("{name} is null"),
+        ));
+    }
+    // SAFETY: non-null and NUL-terminated per the caller's contract
+    unsafe { CStr::from_ptr(ptr) }.to_str().map_err(|e| {
+        FfiError::new(
+            CHATPACK_ERR_INVALID_UTF8,
+            format!("{name} is not valid UTF-8: {e}"),
+        )
+    })
+}
+
+fn parse_platform(name: &str) -> Result<Platform, FfiError> {
+    name.parse()
+        .map_err(|e: String| FfiError::new(CHATPACK_ERR_INVALID_ARGUMENT, e))
+}
+
+/// Hands `value` to the caller through `out`.
+///
+/// # Safety
+///
+/// `out` must be non-null and valid for writes.
+unsafe fn write_out(out: *mut *mut c_char, value: String) -> Result<(), FfiError> {
+    let value = CString::new(value)
+        .map_err(|_| FfiError::new(CHATPACK_ERR_PARSE, "output contains an interior NUL byte"))?;
+    // SAFETY: checked non-null by the caller
+    unsafe { *out = value.into_raw() };
+    Ok(())
+}
+
+/// Validates `out` and resets it to null so it is never left dangling.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+unsafe fn reset_out(out: *mut *mut c_char) -> Result<(), FfiError> {
+    if out.is_null() {
+        return Err(FfiError::new(
+            CHATPACK_ERR_NULL_POINTER,
+            "out pointer is null",
+        ));
+    }
+    // SAFETY: non-null and writable per the caller's contract
+    unsafe { *out = ptr::null_mut() };
+    Ok(())
+}
+
+/// Parses an export file and returns its messages as a JSON array.
+///
+/// `platform` accepts the same names as [`Platform`] (`"telegram"`, `"tg"`,
+/// `"whatsapp"`, ...). Each message is serialized with the field names of
+/// [`Message`](crate::Message); absent optional fields are omitted.
+///
+/// # Safety
+///
+/// - `platform` and `path` must be null or NUL-terminated strings.
+/// - `out_json` must be null or valid for writes. On success it receives a
+///   string that must be released with [`chatpack_free_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chatpack_parse(
+    platform: *const c_char,
+    path: *const c_char,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    guard(|| {
+        // SAFETY: forwarded from this function's contract
+        unsafe { reset_out(out_json)? };
+        let platform = parse_platform(unsafe { read_str(platform, "platform")? })?;
+        let path = unsafe { read_str(path, "path")? };
+
+        let messages = create_parser(platform).parse(Path::new(path))?;
+        let json = serde_json::to_string(&messages).map_err(ChatpackError::from)?;
+        unsafe { write_out(out_json, json) }
+    })
+}
+
+/// Runs the full pipeline: parse, filter, merge, and format.
+///
+/// `config_json` is a JSON object with optional fields `format`
+/// (`"csv"`, `"json"`, `"jsonl"`; default `"csv"`), `merge` (default
+/// `true`), `after`, `before`, `sender`, `sender_map` (a [`SenderMap`],
+/// applied before filtering), `dialogue` (a pair of names for
+/// [`extract_dialogue`] with default windows), and `output` (an
+/// [`OutputConfig`]). Pass null for the defaults.
+///
+/// # Safety
+///
+/// - `platform` and `path` must be null or NUL-terminated strings.
+/// - `config_json` may be null; otherwise it must be NUL-terminated.
+/// - `out_output` must be null or valid for writes. On success it receives a
+///   string that must be released with [`chatpack_free_string`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chatpack_convert(
+    platform: *const c_char,
+    path: *const c_char,
+    config_json: *const c_char,
+    out_output: *mut *mut c_char,
+) -> i32 {
+    guard(|| {
+        // SAFETY: forwarded from this function's contract
+        unsafe { reset_out(out_output)? };
+        let platform = parse_platform(unsafe { read_str(platform, "platform")? })?;
+        let path = unsafe { read_str(path, "path")? };
+        let config: ConvertConfig = if config_json.is_null() {
+            ConvertConfig::default()
+        } else {
+            let json = unsafe { read_str(config_json, "config_json")? };
+            serde_json::from_str(json).map_err(|e| {
+                FfiError::new(
+                    CHATPACK_ERR_INVALID_ARGUMENT,
+                    format!("invalid config: {e}"),
+                )
+            })?
+        };
+
+        let mut filter = FilterConfig::new();
+        if let Some(after) = &config.after {
+            filter = filter.after_datetime(after)?;
+        }
+        if let Some(before) = &config.before {
+            filter = filter.before_datetime(before)?;
+        }
+        if let Some(sender) = config.sender {
+            filter = filter.with_sender(sender);
+        }
+
+        // Aliases are resolved first so `sender` matches canonical names
+        let mut messages = create_parser(platform).parse(Path::new(path))?;
+        map_senders(&mut messages, &config.sender_map);
+        let mut messages = apply_filters(messages, &filter);
+        if let Some((a, b)) = &config.dialogue {
+            messages = extract_dialogue(&messages, a, b, &DialogueConfig::default());
+        }
+        if config.merge {
+            messages = merge_consecutive(messages);
+        }
+
+        let output = to_format_string(&messages, config.format, &config.output)?;
+        unsafe { write_out(out_output, output) }
+    })
+}
+
+/// Releases a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a pointer previously returned through an `out_*`
+/// parameter of this library, not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chatpack_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: allocated by `CString::into_raw` in `write_out`
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Returns the error message of the last failed call on this thread.
+///
+/// Returns null if the last call succeeded. The pointer stays valid until
+/// the next chatpack call on the same thread and must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn chatpack_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| slot.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const TELEGRAM: &str = r#"{"name":"Chat","messages":[
+        {"id":1,"type":"message","date_unixtime":"1705314600","from":"Alice","text":"Hello"},
+        {"id":2,"type":"message","date_unixtime":"1705314660","from":"Alice","text":"again"},
+        {"id":3,"type":"message","date_unixtime":"1705401000","from":"Bob","text":"Hi"}
+    ]}"#;
+
+    fn fixture() -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(TELEGRAM.as_bytes()).unwrap();
+        file
+    }
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    /// Takes ownership of an output string, freeing it through the C API.
+    fn take(ptr: *mut c_char) -> String {
+        assert!(!ptr.is_null());
+        let s = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_owned();
+        unsafe { chatpack_free_string(ptr) };
+        s
+    }
+
+    fn last_error() -> String {
+        let ptr = chatpack_last_error();
+        assert!(!ptr.is_null());
+        unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_owned()
+    }
+
+    // =========================================================================
+    // chatpack_parse tests
+    // =========================================================================
+
+    #[test]
+    fn test_parse_returns_json() {
+        let file = fixture();
+        let path = c(file.path().to_str().unwrap());
+        let mut out = ptr::null_mut();
+
+        let code = unsafe { chatpack_parse(c("telegram").as_ptr(), path.as_ptr(), &raw mut out) };
+        assert_eq!(code, CHATPACK_OK);
+        assert!(chatpack_last_error().is_null());
+
+        let messages: Vec<crate::Message> = serde_json::from_str(&take(out)).unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[0].id, Some(1));
+    }
+
+    #[test]
+    fn test_parse_unknown_platform() {
+        let mut out = ptr::null_mut();
+        let code = unsafe { chatpack_parse(c("myspace").as_ptr(), c("x").as_ptr(), &raw mut out) };
+
+        assert_eq!(code, CHATPACK_ERR_INVALID_ARGUMENT);
+        assert!(out.is_null());
+        assert!(last_error().contains("Unknown platform"));
+    }
+
+    #[test]
+    fn test_parse_missing_file() {
+        let mut out = ptr::null_mut();
+        let path = c("/nonexistent/chatpack/result.json");
+        let code = unsafe { chatpack_parse(c("telegram").as_ptr(), path.as_ptr(), &raw mut out) };
+
+        assert_eq!(code, CHATPACK_ERR_IO);
+        assert!(last_error().starts_with("IO error"));
+    }
+
+    #[test]
+    fn test_parse_null_arguments() {
+        let mut out = ptr::null_mut();
+        let code = unsafe { chatpack_parse(ptr::null(), c("x").as_ptr(), &raw mut out) };
+        assert_eq!(code, CHATPACK_ERR_NULL_POINTER);
+        assert_eq!(last_error(), "platform is null");
+
+        let code = unsafe { chatpack_parse(c("tg").as_ptr(), c("x").as_ptr(), ptr::null_mut()) };
+        assert_eq!(code, CHATPACK_ERR_NULL_POINTER);
+    }
+
+    #[test]
+    fn test_parse_invalid_utf8() {
+        let bad = CString::new(vec![0xFF, 0xFE]).unwrap();
+        let mut out = ptr::null_mut();
+        let code = unsafe { chatpack_parse(c("tg").as_ptr(), bad.as_ptr(), &raw mut out) };
+
+        assert_eq!(code, CHATPACK_ERR_INVALID_UTF8);
+        assert!(last_error().contains("path is not valid UTF-8"));
+    }
+
+    // =========================================================================
+    // chatpack_convert tests
+    // =========================================================================
+
+    #[test]
+    fn test_convert_defaults() {
+        let file = fixture();
+        let path = c(file.path().to_str().unwrap());
+        let mut out = ptr::null_mut();
+
+        let code = unsafe {
+            chatpack_convert(
+                c("telegram").as_ptr(),
+                path.as_ptr(),
+                ptr::null(),
+                &raw mut out,
+            )
+        };
+        assert_eq!(code, CHATPACK_OK);
+
+        // Default CSV output with consecutive messages merged
+        let csv = take(out);
+        assert!(csv.contains("Hello\nagain"));
+        assert_eq!(csv.matches("Alice").count(), 1);
+    }
+
+    #[test]
+    fn test_convert_with_config() {
+        let file = fixture();
+        let path = c(file.path().to_str().unwrap());
+        let config = c(
+            r#"{"format":"jsonl","merge":false,"after":"2024-01-16","output":{"include_ids":true}}"#,
+        );
+        let mut out = ptr::null_mut();
+
+        let code = unsafe {
+            chatpack_convert(
+                c("tg").as_ptr(),
+                path.as_ptr(),
+                config.as_ptr(),
+                &raw mut out,
+            )
+        };
+        assert_eq!(code, CHATPACK_OK);
+        assert_eq!(
+            take(out),
+            "{\"id\":3,\"sender\":\"Bob\",\"content\":\"Hi\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_sender_map_before_filter() {
+        let file = fixture();
+        let path = c(file.path().to_str().unwrap());
+        let config =
+            c(r#"{"format":"jsonl","sender":"Robert","sender_map":{"aliases":{"bob":"Robert"}}}"#);
+        let mut out = ptr::null_mut();
+
+        let code = unsafe {
+            chatpack_convert(
+                c("tg").as_ptr(),
+                path.as_ptr(),
+                config.as_ptr(),
+                &raw mut out,
+            )
+        };
+        assert_eq!(code, CHATPACK_OK);
+        assert_eq!(take(out), "{\"sender\":\"Robert\",\"content\":\"Hi\"}\n");
+    }
+
+    #[test]
+    fn test_convert_dialogue() {
+        let file = fixture();
+        let path = c(file.path().to_str().unwrap());
+        // Bob replies a day later and one position away
+        let config = c(r#"{"format":"jsonl","merge":false,"dialogue":["alice","bob"]}"#);
+        let mut out = ptr::null_mut();
+
+        let code = unsafe {
+            chatpack_convert(
+                c("tg").as_ptr(),
+                path.as_ptr(),
+                config.as_ptr(),
+                &raw mut out,
+            )
+        };
+        assert_eq!(code, CHATPACK_OK);
+        assert_eq!(take(out).lines().count(), 3);
+    }
+
+    #[test]
+    fn test_convert_invalid_config() {
+        let file = fixture();
+        let path = c(file.path().to_str().unwrap());
+        let mut out = ptr::null_mut();
+
+        let config = c(r#"{"fromat":"json"}"#);
+        let code = unsafe {
+            chatpack_convert(
+                c("tg").as_ptr(),
+                path.as_ptr(),
+                config.as_ptr(),
+                &raw mut out,
+            )
+        };
+        assert_eq!(code, CHATPACK_ERR_INVALID_ARGUMENT);
+        assert!(last_error().contains("invalid config"));
+
+        let config = c(r#"{"after":"yesterday-ish"}"#);
+        let code = unsafe {
+            chatpack_convert(
+                c("tg").as_ptr(),
+                path.as_ptr(),
+                config.as_ptr(),
+                &raw mut out,
+            )
+        };
+        assert_eq!(code, CHATPACK_ERR_INVALID_ARGUMENT);
+        assert!(out.is_null());
+    }
+
+    // =========================================================================
+    // Boundary tests
+    // =========================================================================
+
+    #[test]
+    fn test_guard_catches_panic() {
+        let code = guard(|| panic!("boom"));
+        assert_eq!(code, CHATPACK_ERR_PANIC);
+        assert_eq!(last_error(), "panic: boom");
+
+        // A later success clears the error
+        assert_eq!(guard(|| Ok(())), CHATPACK_OK);
+        assert!(chatpack_last_error().is_null());
+    }
+
+    #[test]
+    fn test_free_null_is_noop() {
+        unsafe { chatpack_free_string(ptr::null_mut()) };
+    }
+}
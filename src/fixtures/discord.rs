@@ -0,0 +1,313 @@
+//! DiscordChatExporter exports in each of its formats.
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Value, json};
+
+use super::{Clock, intern};
+
+/// Base of the CDN URLs written for attachments.
+const CDN: &str = "https://cdn.discordapp.com/attachments/123/456";
+
+/// Builds a DiscordChatExporter export of one channel, as JSON, JSONL,
+/// TXT, or CSV.
+///
+/// Messages get ids from 1 up, and [`reply`](Self::reply) takes the id of
+/// the message it answers. JSON and JSONL keep that id as the reply
+/// target; TXT has no ids, so the parser resolves the reply to the
+/// position of the quoted message instead, and CSV has no replies.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::fixtures::DiscordExportBuilder;
+///
+/// let builder = DiscordExportBuilder::new("Test Server", "general")
+///     .message("Alice", "Hello Discord!")
+///     .reply("bob", "Hi Alice!", 1)
+///     .attachment("image.png");
+///
+/// assert!(builder.build_txt().contains("[1/15/2024 10:31 AM] bob"));
+/// assert!(builder.build_csv().starts_with("AuthorID,Author,Date,Content,Attachments,Reactions\n"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiscordExportBuilder {
+    guild: String,
+    channel: String,
+    clock: Clock,
+    authors: Vec<String>,
+    messages: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    id: u64,
+    author: String,
+    author_id: String,
+    time: DateTime<Utc>,
+    edited: Option<DateTime<Utc>>,
+    content: String,
+    reply_to: Option<u64>,
+    attachments: Vec<String>,
+}
+
+impl DiscordExportBuilder {
+    /// Creates an empty export of `channel` in server `guild`.
+    pub fn new(guild: impl Into<String>, channel: impl Into<String>) -> Self {
+        Self {
+            guild: guild.into(),
+            channel: channel.into(),
+            clock: Clock::new(),
+            authors: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Places the next message at `time`.
+    #[must_use]
+    pub fn at(mut self, time: DateTime<Utc>) -> Self {
+        self.clock.set(time);
+        self
+    }
+
+    /// Returns the id the next message will get.
+    pub fn next_id(&self) -> u64 {
+        self.messages.len() as u64 + 1
+    }
+
+    /// Adds a message from `author`.
+    #[must_use]
+    pub fn message(mut self, author: &str, content: &str) -> Self {
+        let entry = self.entry(author, content);
+        self.messages.push(entry);
+        self
+    }
+
+    /// Adds a message from `author` replying to message `reply_to`.
+    #[must_use]
+    pub fn reply(mut self, author: &str, content: &str, reply_to: u64) -> Self {
+        let mut entry = self.entry(author, content);
+        entry.reply_to = Some(reply_to);
+        self.messages.push(entry);
+        self
+    }
+
+    /// Attaches a file called `file_name` to the last message.
+    #[must_use]
+    pub fn attachment(mut self, file_name: &str) -> Self {
+        if let Some(entry) = self.messages.last_mut() {
+            entry.attachments.push(file_name.to_string());
+        }
+        self
+    }
+
+    /// Marks the last message as edited a minute after it was sent.
+    #[must_use]
+    pub fn edited(mut self) -> Self {
+        if let Some(entry) = self.messages.last_mut() {
+            entry.edited = Some(entry.time + Duration::minutes(1));
+        }
+        self
+    }
+
+    /// Returns the export as pretty-printed JSON.
+    pub fn build_json(&self) -> String {
+        let export = json!({
+            "guild": {"id": "123456789", "name": self.guild, "iconUrl": null},
+            "channel": {"id": "987654321", "type": "GuildTextChat", "name": self.channel, "topic": null},
+            "messages": self.messages.iter().map(json_message).collect::<Vec<_>>(),
+            "messageCount": self.messages.len(),
+        });
+        serde_json::to_string_pretty(&export).unwrap_or_default()
+    }
+
+    /// Returns the export as JSONL, one message object per line.
+    pub fn build_jsonl(&self) -> String {
+        let mut out = String::new();
+        for entry in &self.messages {
+            out.push_str(&json_message(entry).to_string());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Returns the export as plain text.
+    ///
+    /// The export has DiscordChatExporter's header but not its closing
+    /// `Exported N message(s)` block, which the parser would read as part
+    /// of the last message.
+    pub fn build_txt(&self) -> String {
+        let rule = "=".repeat(62);
+        let mut out = format!(
+            "{rule}\nGuild: {}\nChannel: {}\n{rule}\n\n",
+            self.guild, self.channel
+        );
+        for entry in &self.messages {
+            let quoted = entry
+                .reply_to
+                .and_then(|id| self.messages.iter().find(|m| m.id == id));
+            if let Some(quoted) = quoted {
+                let first_line = quoted.content.lines().next().unwrap_or_default();
+                out.push_str(&format!(
+                    "  > Replying to {}: {first_line}\n",
+                    quoted.author
+                ));
+            }
+            out.push_str(&format!(
+                "{} {}\n",
+                entry.time.format("[%-m/%-d/%Y %-I:%M %p]"),
+                entry.author
+            ));
+            out.push_str(&entry.content);
+            out.push('\n');
+            if !entry.attachments.is_empty() {
+                out.push_str("\n{Attachments}\n");
+                for name in &entry.attachments {
+                    out.push_str(&format!("{CDN}/{name}\n"));
+                }
+            }
+            out.push_str("\n\n");
+        }
+        out
+    }
+
+    /// Returns the export as CSV.
+    pub fn build_csv(&self) -> String {
+        let mut out = String::from("AuthorID,Author,Date,Content,Attachments,Reactions\n");
+        for entry in &self.messages {
+            let attachments: Vec<String> = entry
+                .attachments
+                .iter()
+                .map(|name| format!("{CDN}/{name}"))
+                .collect();
+            let cells = [
+                entry.author_id.as_str(),
+                &entry.author,
+                &entry.time.to_rfc3339(),
+                &entry.content,
+                &attachments.join(","),
+                "",
+            ];
+            let row: Vec<String> = cells
+                .iter()
+                .map(|cell| format!("\"{}\"", cell.replace('"', "\"\"")))
+                .collect();
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    fn entry(&mut self, author: &str, content: &str) -> Entry {
+        let author_id = (intern(&mut self.authors, author) + 1) * 111;
+        Entry {
+            id: self.next_id(),
+            author: author.to_string(),
+            author_id: author_id.to_string(),
+            time: self.clock.tick(),
+            edited: None,
+            content: content.to_string(),
+            reply_to: None,
+            attachments: Vec::new(),
+        }
+    }
+}
+
+/// Returns `entry` as a DiscordChatExporter JSON message.
+fn json_message(entry: &Entry) -> Value {
+    let mut message = json!({
+        "id": entry.id.to_string(),
+        "type": if entry.reply_to.is_some() { "Reply" } else { "Default" },
+        "timestamp": entry.time.to_rfc3339(),
+        "timestampEdited": entry.edited.map(|t| t.to_rfc3339()),
+        "content": entry.content,
+        "author": {"id": entry.author_id, "name": entry.author, "nickname": entry.author},
+        "attachments": entry.attachments.iter()
+            .map(|name| json!({"url": format!("{CDN}/{name}"), "fileName": name}))
+            .collect::<Vec<_>>(),
+        "stickers": [],
+        "embeds": [],
+    });
+    if let Some(reply_to) = entry.reply_to {
+        message["reference"] = json!({"messageId": reply_to.to_string()});
+    }
+    message
+}
+
+#[cfg(all(test, feature = "discord-json"))]
+mod tests {
+    use super::*;
+    use crate::fixtures::START;
+    use crate::parser::Parser;
+    use crate::parsers::DiscordParser;
+
+    fn builder() -> DiscordExportBuilder {
+        DiscordExportBuilder::new("Test Server", "general")
+            .message("Alice", "Hello Discord!")
+            .reply("bob", "Hi Alice!", 1)
+            .attachment("image.png")
+            .edited()
+            .message("Alice", "She said \"hi\", then\nleft")
+    }
+
+    fn check(messages: &[crate::Message]) {
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[0].content, "Hello Discord!");
+        assert_eq!(messages[0].timestamp, Some(START));
+        assert_eq!(messages[1].sender, "bob");
+        assert!(messages[1].content.starts_with("Hi Alice!"));
+        assert!(messages[1].content.contains("[Attachment: image.png]"));
+        assert_eq!(messages[2].content, "She said \"hi\", then\nleft");
+    }
+
+    #[test]
+    fn test_build_json_parses() {
+        let messages = DiscordParser::new()
+            .parse_str(&builder().build_json())
+            .unwrap();
+        check(&messages);
+        assert_eq!(messages[0].id, Some(1));
+        assert_eq!(messages[1].reply_to, Some(1));
+        assert_eq!(messages[1].edited, Some(START + Duration::minutes(2)));
+    }
+
+    #[test]
+    fn test_build_jsonl_parses() {
+        let messages = DiscordParser::new()
+            .parse_str(&builder().build_jsonl())
+            .unwrap();
+        check(&messages);
+        assert_eq!(messages[1].reply_to, Some(1));
+    }
+
+    #[cfg(feature = "discord-txt")]
+    #[test]
+    fn test_build_txt_parses() {
+        let messages = DiscordParser::new()
+            .parse_str(&builder().build_txt())
+            .unwrap();
+        check(&messages);
+        // TXT replies resolve to the quoted message's position
+        assert_eq!(messages[1].reply_to, Some(0));
+    }
+
+    #[cfg(feature = "discord-csv")]
+    #[test]
+    fn test_build_csv_parses() {
+        let messages = DiscordParser::new()
+            .parse_str(&builder().build_csv())
+            .unwrap();
+        check(&messages);
+    }
+
+    #[test]
+    fn test_build_json_shape() {
+        let value: Value = serde_json::from_str(&builder().build_json()).unwrap();
+        assert_eq!(value["messageCount"], 3);
+        assert_eq!(value["channel"]["name"], "general");
+        assert_eq!(value["messages"][1]["type"], "Reply");
+        assert_eq!(value["messages"][1]["reference"]["messageId"], "1");
+        assert_eq!(value["messages"][0]["author"]["id"], "111");
+        assert_eq!(value["messages"][2]["author"]["id"], "111");
+    }
+}
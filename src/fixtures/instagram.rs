@@ -0,0 +1,196 @@
+//! Instagram JSON exports.
+
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+
+use super::{Clock, intern};
+
+/// Builds an Instagram `message_1.json` export.
+///
+/// Messages are added oldest first and written newest first, as Instagram
+/// does. With [`with_mojibake`](Self::with_mojibake), text is written the
+/// way Meta's exports corrupt it: each UTF-8 byte as a separate Latin-1
+/// character, so `"é"` becomes `"Ã©"`. Without it, parse non-ASCII
+/// exports with [`fix_encoding`](crate::config::InstagramConfig::fix_encoding)
+/// off.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::fixtures::InstagramExportBuilder;
+///
+/// let export = InstagramExportBuilder::new("Trip")
+///     .message("user_one", "Café?")
+///     .with_mojibake(true)
+///     .build();
+/// assert!(export.contains("CafÃ©?"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct InstagramExportBuilder {
+    title: String,
+    clock: Clock,
+    participants: Vec<String>,
+    messages: Vec<Value>,
+    mojibake: bool,
+}
+
+impl InstagramExportBuilder {
+    /// Creates an empty export of a thread called `title`.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            clock: Clock::new(),
+            participants: Vec::new(),
+            messages: Vec::new(),
+            mojibake: false,
+        }
+    }
+
+    /// Places the next message at `time`.
+    #[must_use]
+    pub fn at(mut self, time: DateTime<Utc>) -> Self {
+        self.clock.set(time);
+        self
+    }
+
+    /// Sets whether text is written with Meta's mojibake.
+    #[must_use]
+    pub fn with_mojibake(mut self, mojibake: bool) -> Self {
+        self.mojibake = mojibake;
+        self
+    }
+
+    /// Adds a text message from `sender`.
+    #[must_use]
+    pub fn message(mut self, sender: &str, content: &str) -> Self {
+        let mut entry = self.entry(sender);
+        entry["content"] = json!(content);
+        self.messages.push(entry);
+        self
+    }
+
+    /// Adds a shared link from `sender`, with `share_text` as its text.
+    #[must_use]
+    pub fn share(mut self, sender: &str, share_text: &str, link: &str) -> Self {
+        let mut entry = self.entry(sender);
+        entry["share"] = json!({"link": link, "share_text": share_text});
+        self.messages.push(entry);
+        self
+    }
+
+    /// Adds a photo from `sender` stored at `uri`.
+    #[must_use]
+    pub fn photo(mut self, sender: &str, uri: &str) -> Self {
+        let mut entry = self.entry(sender);
+        entry["photos"] = json!([{"uri": uri, "creation_timestamp": entry["timestamp_ms"]}]);
+        self.messages.push(entry);
+        self
+    }
+
+    /// Returns the export as pretty-printed JSON.
+    pub fn build(&self) -> String {
+        let text = |s: &str| {
+            if self.mojibake {
+                s.bytes().map(char::from).collect()
+            } else {
+                s.to_string()
+            }
+        };
+        let messages: Vec<Value> = self
+            .messages
+            .iter()
+            .rev()
+            .map(|entry| {
+                let mut entry = entry.clone();
+                for key in ["sender_name", "content"] {
+                    if let Some(s) = entry[key].as_str() {
+                        entry[key] = json!(text(s));
+                    }
+                }
+                if let Some(s) = entry["share"]["share_text"].as_str() {
+                    entry["share"]["share_text"] = json!(text(s));
+                }
+                entry
+            })
+            .collect();
+        let participants: Vec<Value> = self
+            .participants
+            .iter()
+            .map(|name| json!({"name": text(name)}))
+            .collect();
+
+        let export = json!({
+            "participants": participants,
+            "messages": messages,
+            "title": text(&self.title),
+            "is_still_participant": true,
+            "thread_path": "inbox/fixture_123",
+            "magic_words": [],
+        });
+        serde_json::to_string_pretty(&export).unwrap_or_default()
+    }
+
+    fn entry(&mut self, sender: &str) -> Value {
+        intern(&mut self.participants, sender);
+        json!({
+            "sender_name": sender,
+            "timestamp_ms": self.clock.tick().timestamp_millis(),
+            "is_geoblocked_for_viewer": false,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "instagram"))]
+mod tests {
+    use super::*;
+    use crate::config::InstagramConfig;
+    use crate::fixtures::START;
+    use crate::parser::Parser;
+    use crate::parsers::InstagramParser;
+
+    fn builder() -> InstagramExportBuilder {
+        InstagramExportBuilder::new("Чат")
+            .message("user_one", "Hey! How are you?")
+            .message("Борис", "Привет, всё хорошо 🎉")
+            .share("user_one", "Check this out", "https://instagram.com/p/xyz")
+    }
+
+    #[test]
+    fn test_build_parses() {
+        let config = InstagramConfig::new().with_fix_encoding(false);
+        let messages = InstagramParser::with_config(config)
+            .parse_str(&builder().build())
+            .unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].sender, "user_one");
+        assert_eq!(messages[0].timestamp, Some(START));
+        assert_eq!(messages[1].sender, "Борис");
+        assert_eq!(messages[1].content, "Привет, всё хорошо 🎉");
+        assert_eq!(messages[2].content, "Check this out");
+    }
+
+    #[test]
+    fn test_mojibake_is_fixed_by_parser() {
+        let export = builder().with_mojibake(true).build();
+        assert!(!export.contains("Борис"));
+
+        let messages = InstagramParser::new().parse_str(&export).unwrap();
+        assert_eq!(messages[1].sender, "Борис");
+        assert_eq!(messages[1].content, "Привет, всё хорошо 🎉");
+
+        let raw = InstagramParser::with_config(InstagramConfig::new().with_fix_encoding(false))
+            .parse_str(&export)
+            .unwrap();
+        assert_ne!(raw[1].content, "Привет, всё хорошо 🎉");
+    }
+
+    #[test]
+    fn test_newest_first() {
+        let value: Value = serde_json::from_str(&builder().build()).unwrap();
+        let first = value["messages"][0]["timestamp_ms"].as_i64().unwrap();
+        let last = value["messages"][2]["timestamp_ms"].as_i64().unwrap();
+        assert!(first > last);
+        assert_eq!(value["participants"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,93 @@
+//! Builders for small, valid chat exports, for tests and documentation.
+//!
+//! Each builder writes an export the way the platform does, so a test can
+//! describe the conversation it needs instead of hand-writing JSON or
+//! dates in the right locale. The output is meant to be written to a file
+//! or passed to [`Parser::parse_str`](crate::parser::Parser::parse_str).
+//!
+//! | Builder | Output |
+//! |---------|--------|
+//! | [`TelegramExportBuilder`] | Telegram Desktop `result.json` |
+//! | [`WhatsAppExportBuilder`] | WhatsApp `.txt`, in any [`DateFormat`](crate::parsing::whatsapp::DateFormat) (`whatsapp` feature) |
+//! | [`InstagramExportBuilder`] | Instagram `message_1.json`, optionally with Meta's mojibake |
+//! | [`DiscordExportBuilder`] | DiscordChatExporter JSON, JSONL, TXT, and CSV |
+//!
+//! Messages are one minute apart, starting at [`START`]. Call `at` on a
+//! builder to place the next message at another time; later messages
+//! follow it a minute apart.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "telegram")]
+//! # fn main() -> chatpack::Result<()> {
+//! use chatpack::fixtures::TelegramExportBuilder;
+//! use chatpack::parser::Parser;
+//! use chatpack::parsers::TelegramParser;
+//!
+//! let export = TelegramExportBuilder::new("Chat name")
+//!     .message("Alice", "Hi")
+//!     .reply("Bob", "Hey", 1)
+//!     .service_pin()
+//!     .build();
+//!
+//! let messages = TelegramParser::new().parse_str(&export)?;
+//! assert_eq!(messages.len(), 2);
+//! assert_eq!(messages[1].reply_to, Some(1));
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "telegram"))]
+//! # fn main() {}
+//! ```
+
+mod discord;
+mod instagram;
+mod telegram;
+#[cfg(feature = "whatsapp")]
+mod whatsapp;
+
+pub use discord::DiscordExportBuilder;
+pub use instagram::InstagramExportBuilder;
+pub use telegram::TelegramExportBuilder;
+#[cfg(feature = "whatsapp")]
+pub use whatsapp::WhatsAppExportBuilder;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Time of the first message of every builder: 2024-01-15 10:30:00 UTC.
+pub const START: DateTime<Utc> = match DateTime::from_timestamp(1_705_314_600, 0) {
+    Some(time) => time,
+    None => panic!("START is in range"),
+};
+
+/// Hands out message times one minute apart.
+#[derive(Debug, Clone)]
+struct Clock {
+    next: DateTime<Utc>,
+}
+
+impl Clock {
+    fn new() -> Self {
+        Self { next: START }
+    }
+
+    /// Places the next message at `time`.
+    fn set(&mut self, time: DateTime<Utc>) {
+        self.next = time;
+    }
+
+    /// Returns the time of the next message and moves on a minute.
+    fn tick(&mut self) -> DateTime<Utc> {
+        let time = self.next;
+        self.next = time + Duration::minutes(1);
+        time
+    }
+}
+
+/// Returns the position of `name` in `names`, adding it if it is new.
+fn intern(names: &mut Vec<String>, name: &str) -> usize {
+    names.iter().position(|n| n == name).unwrap_or_else(|| {
+        names.push(name.to_string());
+        names.len() - 1
+    })
+}
@@ -0,0 +1,264 @@
+//! Telegram Desktop JSON exports.
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Map, Value, json};
+
+use super::{Clock, intern};
+
+/// Builds a Telegram Desktop `result.json` export.
+///
+/// Messages and service entries share one id sequence starting at 1, as
+/// in real exports, so the first message is id 1 and
+/// [`reply`](Self::reply) takes the id of the message it answers.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::fixtures::TelegramExportBuilder;
+///
+/// let export = TelegramExportBuilder::new("Family")
+///     .message("Alice", "Dinner at 7?")
+///     .reply("Bob", "Sure", 1)
+///     .edited()
+///     .build();
+/// assert!(export.contains("\"reply_to_message_id\": 1"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TelegramExportBuilder {
+    name: String,
+    clock: Clock,
+    senders: Vec<String>,
+    messages: Vec<Map<String, Value>>,
+}
+
+impl TelegramExportBuilder {
+    /// Creates an empty export of a chat called `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            clock: Clock::new(),
+            senders: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Places the next entry at `time`.
+    #[must_use]
+    pub fn at(mut self, time: DateTime<Utc>) -> Self {
+        self.clock.set(time);
+        self
+    }
+
+    /// Returns the id the next entry will get.
+    pub fn next_id(&self) -> u64 {
+        self.messages.len() as u64 + 1
+    }
+
+    /// Adds a text message from `from`.
+    #[must_use]
+    pub fn message(mut self, from: &str, text: &str) -> Self {
+        let entry = self.entry(from, text);
+        self.messages.push(entry);
+        self
+    }
+
+    /// Adds a text message from `from` replying to message `reply_to`.
+    #[must_use]
+    pub fn reply(mut self, from: &str, text: &str, reply_to: u64) -> Self {
+        let mut entry = self.entry(from, text);
+        entry.insert("reply_to_message_id".into(), json!(reply_to));
+        self.messages.push(entry);
+        self
+    }
+
+    /// Adds a photo from `from` stored at `path`, with `caption` as its
+    /// text.
+    #[must_use]
+    pub fn photo(mut self, from: &str, path: &str, caption: &str) -> Self {
+        let mut entry = self.entry(from, caption);
+        entry.insert("photo".into(), json!(path));
+        entry.insert("width".into(), json!(800));
+        entry.insert("height".into(), json!(600));
+        self.messages.push(entry);
+        self
+    }
+
+    /// Marks the last entry as edited 30 seconds after it was sent.
+    #[must_use]
+    pub fn edited(mut self) -> Self {
+        if let Some(entry) = self.messages.last_mut() {
+            let sent: i64 = entry["date_unixtime"]
+                .as_str()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default();
+            let edited =
+                DateTime::from_timestamp(sent, 0).unwrap_or_default() + Duration::seconds(30);
+            entry.insert("edited".into(), json!(local_date(edited)));
+            entry.insert(
+                "edited_unixtime".into(),
+                json!(edited.timestamp().to_string()),
+            );
+        }
+        self
+    }
+
+    /// Adds a service entry for the last message's sender pinning it.
+    #[must_use]
+    pub fn service_pin(self) -> Self {
+        let last = self.messages.last();
+        let actor = last
+            .and_then(|m| m.get("from"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let pinned = last
+            .and_then(|m| m.get("id"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        let mut builder = self.service(&actor, "pin_message");
+        if let Some(entry) = builder.messages.last_mut() {
+            entry.insert("message_id".into(), pinned);
+        }
+        builder
+    }
+
+    /// Adds a service entry for `actor` doing `action`, such as
+    /// `"join_group_by_link"`.
+    #[must_use]
+    pub fn service(mut self, actor: &str, action: &str) -> Self {
+        let time = self.clock.tick();
+        let actor_id = self.user_id(actor);
+        let mut entry = Map::new();
+        entry.insert("id".into(), json!(self.next_id()));
+        entry.insert("type".into(), json!("service"));
+        insert_date(&mut entry, time);
+        entry.insert("actor".into(), json!(actor));
+        entry.insert("actor_id".into(), json!(actor_id));
+        entry.insert("action".into(), json!(action));
+        entry.insert("text".into(), json!(""));
+        self.messages.push(entry);
+        self
+    }
+
+    /// Returns the export as pretty-printed JSON.
+    pub fn build(&self) -> String {
+        let export = json!({
+            "name": self.name,
+            "type": "personal_chat",
+            "id": 123_456_789,
+            "messages": self.messages,
+        });
+        serde_json::to_string_pretty(&export).unwrap_or_default()
+    }
+
+    fn entry(&mut self, from: &str, text: &str) -> Map<String, Value> {
+        let time = self.clock.tick();
+        let from_id = self.user_id(from);
+        let mut entry = Map::new();
+        entry.insert("id".into(), json!(self.next_id()));
+        entry.insert("type".into(), json!("message"));
+        insert_date(&mut entry, time);
+        entry.insert("from".into(), json!(from));
+        entry.insert("from_id".into(), json!(from_id));
+        entry.insert("text".into(), json!(text));
+        entry.insert(
+            "text_entities".into(),
+            if text.is_empty() {
+                json!([])
+            } else {
+                json!([{"type": "plain", "text": text}])
+            },
+        );
+        entry
+    }
+
+    fn user_id(&mut self, name: &str) -> String {
+        format!("user{}", intern(&mut self.senders, name) + 1)
+    }
+}
+
+/// Adds the local `date` and `date_unixtime` of an entry sent at `time`.
+fn insert_date(entry: &mut Map<String, Value>, time: DateTime<Utc>) {
+    entry.insert("date".into(), json!(local_date(time)));
+    entry.insert("date_unixtime".into(), json!(time.timestamp().to_string()));
+}
+
+/// Formats `time` like Telegram's local dates, `2024-01-15T10:30:00`.
+fn local_date(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+#[cfg(all(test, feature = "telegram"))]
+mod tests {
+    use super::*;
+    use crate::config::TelegramConfig;
+    use crate::fixtures::START;
+    use crate::parser::Parser;
+    use crate::parsers::TelegramParser;
+
+    #[test]
+    fn test_build_parses() {
+        let export = TelegramExportBuilder::new("Chat name")
+            .message("Alice", "Hi")
+            .reply("Bob", "Hey", 1)
+            .service_pin()
+            .message("Alice", "Привет 🎉")
+            .build();
+
+        let messages = TelegramParser::new().parse_str(&export).unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[0].content, "Hi");
+        assert_eq!(messages[0].id, Some(1));
+        assert_eq!(messages[0].timestamp, Some(START));
+        assert_eq!(messages[1].reply_to, Some(1));
+        assert_eq!(messages[2].id, Some(4));
+        assert_eq!(messages[2].content, "Привет 🎉");
+        assert_eq!(messages[2].timestamp, Some(START + Duration::minutes(3)));
+    }
+
+    #[test]
+    fn test_service_pin_points_at_last_message() {
+        let export = TelegramExportBuilder::new("Chat")
+            .message("Alice", "Pin me")
+            .service_pin()
+            .build();
+        let value: Value = serde_json::from_str(&export).unwrap();
+        let pin = &value["messages"][1];
+        assert_eq!(pin["type"], "service");
+        assert_eq!(pin["actor"], "Alice");
+        assert_eq!(pin["actor_id"], "user1");
+        assert_eq!(pin["message_id"], 1);
+    }
+
+    #[test]
+    fn test_edited_and_photo() {
+        let export = TelegramExportBuilder::new("Chat")
+            .message("Alice", "Typo")
+            .edited()
+            .photo("Bob", "photos/photo_1.jpg", "")
+            .build();
+
+        let config = TelegramConfig::new().with_collect_attachment_refs(true);
+        let messages = TelegramParser::with_config(config)
+            .parse_str(&export)
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].edited, Some(START + Duration::seconds(30)));
+        assert_eq!(messages[1].attachments.len(), 1);
+    }
+
+    #[test]
+    fn test_at_moves_clock() {
+        let later = START + Duration::days(1);
+        let export = TelegramExportBuilder::new("Chat")
+            .at(later)
+            .message("Alice", "One")
+            .message("Bob", "Two")
+            .build();
+
+        let messages = TelegramParser::new().parse_str(&export).unwrap();
+        assert_eq!(messages[0].timestamp, Some(later));
+        assert_eq!(messages[1].timestamp, Some(later + Duration::minutes(1)));
+    }
+}
@@ -0,0 +1,153 @@
+//! WhatsApp TXT exports.
+
+use chrono::{DateTime, Utc};
+
+use super::Clock;
+use crate::parsing::whatsapp::DateFormat;
+
+/// Builds a WhatsApp `.txt` export with timestamps in one of the
+/// [`DateFormat`]s the parser detects.
+///
+/// Content that spans several lines is written as continuation lines, as
+/// WhatsApp does. Formats without seconds in their timestamps
+/// ([`DateFormat::EuDotNoBracket`], [`DateFormat::EuSlash`], and
+/// [`DateFormat::MonthName`]) drop them.
+///
+/// # Example
+///
+/// ```
+/// use chatpack::fixtures::WhatsAppExportBuilder;
+/// use chatpack::parsing::whatsapp::DateFormat;
+///
+/// let export = WhatsAppExportBuilder::new(DateFormat::EuSlash)
+///     .message("Alice", "Hi")
+///     .media_omitted("Bob")
+///     .build();
+/// assert_eq!(
+///     export,
+///     "15/01/2024, 10:30 - Alice: Hi\n15/01/2024, 10:31 - Bob: <Media omitted>\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct WhatsAppExportBuilder {
+    format: DateFormat,
+    clock: Clock,
+    export: String,
+}
+
+impl WhatsAppExportBuilder {
+    /// Creates an empty export with timestamps in `format`.
+    pub fn new(format: DateFormat) -> Self {
+        Self {
+            format,
+            clock: Clock::new(),
+            export: String::new(),
+        }
+    }
+
+    /// Places the next line at `time`.
+    #[must_use]
+    pub fn at(mut self, time: DateTime<Utc>) -> Self {
+        self.clock.set(time);
+        self
+    }
+
+    /// Adds a message from `sender`.
+    #[must_use]
+    pub fn message(mut self, sender: &str, content: &str) -> Self {
+        let header = self.header();
+        self.export
+            .push_str(&format!("{header} {sender}: {content}\n"));
+        self
+    }
+
+    /// Adds a media message from `sender` that was left out of the export.
+    #[must_use]
+    pub fn media_omitted(self, sender: &str) -> Self {
+        self.message(sender, "<Media omitted>")
+    }
+
+    /// Adds a system line without a sender, such as `"Alice joined"`.
+    #[must_use]
+    pub fn system(mut self, text: &str) -> Self {
+        let header = self.header();
+        self.export.push_str(&format!("{header} {text}\n"));
+        self
+    }
+
+    /// Returns the export.
+    pub fn build(&self) -> String {
+        self.export.clone()
+    }
+
+    /// Returns the timestamp part of the next line, up to the space before
+    /// the sender.
+    fn header(&mut self) -> String {
+        let time = self.clock.tick();
+        match self.format {
+            DateFormat::US => time.format("[%-m/%-d/%y, %-I:%M:%S %p]").to_string(),
+            DateFormat::EuDotBracketed => time.format("[%d.%m.%y, %H:%M:%S]").to_string(),
+            DateFormat::EuDotNoBracket => time.format("%d.%m.%Y, %H:%M -").to_string(),
+            DateFormat::EuSlash => time.format("%d/%m/%Y, %H:%M -").to_string(),
+            DateFormat::EuSlashBracketed => time.format("[%d/%m/%Y, %H:%M:%S]").to_string(),
+            DateFormat::MonthName => time.format("%-d %b %Y, %H:%M -").to_string(),
+            DateFormat::MonthNameBracketed => time.format("[%-d %b %Y, %H:%M:%S]").to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::START;
+    use crate::parser::Parser;
+    use crate::parsers::WhatsAppParser;
+    use chrono::Duration;
+
+    const FORMATS: [DateFormat; 7] = [
+        DateFormat::US,
+        DateFormat::EuDotBracketed,
+        DateFormat::EuDotNoBracket,
+        DateFormat::EuSlash,
+        DateFormat::EuSlashBracketed,
+        DateFormat::MonthName,
+        DateFormat::MonthNameBracketed,
+    ];
+
+    #[test]
+    fn test_build_parses_in_every_format() {
+        for format in FORMATS {
+            let export = WhatsAppExportBuilder::new(format)
+                .system("Messages and calls are end-to-end encrypted")
+                .message("Alice", "Hi")
+                .message("Bob", "Line one\nLine two")
+                .media_omitted("Alice")
+                .build();
+
+            let messages = WhatsAppParser::new().parse_str(&export).unwrap();
+            assert_eq!(messages.len(), 3, "{format:?}\n{export}");
+            assert_eq!(messages[0].sender, "Alice", "{format:?}");
+            assert_eq!(messages[0].content, "Hi", "{format:?}");
+            assert_eq!(
+                messages[0].timestamp,
+                Some(START + Duration::minutes(1)),
+                "{format:?}"
+            );
+            assert_eq!(messages[1].content, "Line one\nLine two", "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_us_format_lines() {
+        let export = WhatsAppExportBuilder::new(DateFormat::US)
+            .message("Alice", "Hi")
+            .at(START + Duration::hours(3))
+            .message("Bob", "Hey")
+            .build();
+
+        assert_eq!(
+            export,
+            "[1/15/24, 10:30:00 AM] Alice: Hi\n[1/15/24, 1:30:00 PM] Bob: Hey\n"
+        );
+    }
+}
@@ -28,10 +28,13 @@
 //! # }
 //! ```
 
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
 use crate::Message;
 use crate::core::models::OutputConfig;
+use crate::core::output::{OnExists, WriteOptions};
 use crate::error::ChatpackError;
 
 /// Output format for chat exports.
@@ -40,6 +43,7 @@ use crate::error::ChatpackError;
 /// - [`Csv`](OutputFormat::Csv) - Best for LLM context (13x token compression)
 /// - [`Json`](OutputFormat::Json) - Structured array, good for APIs
 /// - [`Jsonl`](OutputFormat::Jsonl) - One JSON per line, ideal for RAG/ML pipelines
+/// - [`HfText`](OutputFormat::HfText) - Hugging Face `datasets` text records
 ///
 /// # Example
 ///
@@ -72,6 +76,14 @@ pub enum OutputFormat {
     /// Ideal for streaming, RAG pipelines, and ML applications.
     /// Also known as NDJSON.
     Jsonl,
+
+    /// Hugging Face `datasets` text records, one JSON object per line
+    ///
+    /// Each message becomes `{"text": "Sender: content", "meta": {...}}`;
+    /// see [`write_hf_text`](crate::core::output::write_hf_text). Named
+    /// `hf`, and written with the `jsonl` extension.
+    #[serde(rename = "hf")]
+    HfText,
 }
 
 impl OutputFormat {
@@ -90,18 +102,67 @@ impl OutputFormat {
         match self {
             OutputFormat::Csv => "csv",
             OutputFormat::Json => "json",
-            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Jsonl | OutputFormat::HfText => "jsonl",
+        }
+    }
+
+    /// Returns the Cargo feature that provides this format's writer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use chatpack::format::OutputFormat;
+    ///
+    /// assert_eq!(OutputFormat::Csv.required_feature(), "csv-output");
+    /// assert_eq!(OutputFormat::Jsonl.required_feature(), "json-output");
+    /// ```
+    pub fn required_feature(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv-output",
+            OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::HfText => "json-output",
+        }
+    }
+
+    /// Returns whether this build can write this format.
+    ///
+    /// Every variant exists in every build; writing a format whose
+    /// [feature](OutputFormat::required_feature) is disabled returns
+    /// [`ChatpackError::UnsupportedFormat`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use chatpack::format::OutputFormat;
+    ///
+    /// let available: Vec<_> = OutputFormat::all()
+    ///     .iter()
+    ///     .filter(|f| f.is_enabled())
+    ///     .collect();
+    /// # #[cfg(feature = "csv-output")]
+    /// assert!(available.contains(&&OutputFormat::Csv));
+    /// ```
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            OutputFormat::Csv => cfg!(feature = "csv-output"),
+            OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::HfText => {
+                cfg!(feature = "json-output")
+            }
         }
     }
 
     /// Returns all supported format names.
     pub fn all_names() -> &'static [&'static str] {
-        &["csv", "json", "jsonl", "ndjson"]
+        &["csv", "json", "jsonl", "ndjson", "hf"]
     }
 
     /// Returns all available formats.
     pub fn all() -> &'static [OutputFormat] {
-        &[OutputFormat::Csv, OutputFormat::Json, OutputFormat::Jsonl]
+        &[
+            OutputFormat::Csv,
+            OutputFormat::Json,
+            OutputFormat::Jsonl,
+            OutputFormat::HfText,
+        ]
     }
 
     /// Returns the MIME type for this format.
@@ -117,7 +178,7 @@ impl OutputFormat {
         match self {
             OutputFormat::Csv => "text/csv",
             OutputFormat::Json => "application/json",
-            OutputFormat::Jsonl => "application/x-ndjson",
+            OutputFormat::Jsonl | OutputFormat::HfText => "application/x-ndjson",
         }
     }
 
@@ -155,6 +216,7 @@ impl std::fmt::Display for OutputFormat {
             OutputFormat::Csv => write!(f, "CSV"),
             OutputFormat::Json => write!(f, "JSON"),
             OutputFormat::Jsonl => write!(f, "JSONL"),
+            OutputFormat::HfText => write!(f, "HF text"),
         }
     }
 }
@@ -167,6 +229,7 @@ impl std::str::FromStr for OutputFormat {
             "csv" => Ok(OutputFormat::Csv),
             "json" => Ok(OutputFormat::Json),
             "jsonl" | "ndjson" => Ok(OutputFormat::Jsonl),
+            "hf" => Ok(OutputFormat::HfText),
             _ => Err(format!(
                 "Unknown format: '{}'. Expected one of: {}",
                 s,
@@ -179,7 +242,8 @@ impl std::str::FromStr for OutputFormat {
 /// Writes messages to a file in the specified format.
 ///
 /// This is a convenience function that selects the appropriate writer
-/// based on the format enum.
+/// based on the format enum. An existing file is replaced; use
+/// [`write_to_format_with`] to refuse or rename instead.
 ///
 /// # Example
 ///
@@ -204,33 +268,129 @@ impl std::str::FromStr for OutputFormat {
 ///
 /// Returns an error if:
 /// - The required feature for the format is not enabled
+///   ([`ChatpackError::UnsupportedFormat`])
 /// - The file cannot be written
-#[allow(unused_variables)]
 pub fn write_to_format(
     messages: &[Message],
     path: &str,
     format: OutputFormat,
     config: &OutputConfig,
 ) -> Result<(), ChatpackError> {
+    write_iter_to_format(messages, path, format, config, OnExists::Overwrite)?;
+    Ok(())
+}
+
+/// Writes messages to a file in the specified format, handling an existing
+/// file as `options` says.
+///
+/// Returns the path written, which differs from `path` when
+/// [`OnExists::Rename`] picked a new name.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "csv-output")]
+/// # fn example() -> chatpack::Result<()> {
+/// use chatpack::format::{OutputFormat, write_to_format_with};
+/// use chatpack::core::models::OutputConfig;
+/// use chatpack::core::output::{OnExists, WriteOptions};
+/// use chatpack::Message;
+///
+/// let messages = vec![Message::new("Alice", "Hello!")];
+/// let options = WriteOptions::new().with_on_exists(OnExists::Error);
+///
+/// match write_to_format_with(&messages, "output.csv", OutputFormat::Csv, &OutputConfig::new(), &options) {
+///     Ok(path) => println!("wrote {}", path.display()),
+///     Err(e) if e.is_output_exists() => eprintln!("{e}"),
+///     Err(e) => return Err(e),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::OutputExists`] if the file exists and
+/// `options.on_exists` is [`OnExists::Error`], and otherwise the same
+/// errors as [`write_to_format`].
+pub fn write_to_format_with(
+    messages: &[Message],
+    path: &str,
+    format: OutputFormat,
+    config: &OutputConfig,
+    options: &WriteOptions,
+) -> Result<PathBuf, ChatpackError> {
+    write_iter_to_format(messages, path, format, config, options.on_exists)
+}
+
+/// Writes messages to a file in `format` as they arrive from `messages`,
+/// returning the path written.
+///
+/// Produces the same bytes as [`write_to_format`] without collecting the
+/// messages first.
+#[allow(unused_variables, clippy::needless_pass_by_value)]
+pub(crate) fn write_iter_to_format<I>(
+    messages: I,
+    path: &str,
+    format: OutputFormat,
+    config: &OutputConfig,
+    on_exists: OnExists,
+) -> Result<PathBuf, ChatpackError>
+where
+    I: IntoIterator,
+    I::Item: std::borrow::Borrow<Message>,
+{
     match format {
         #[cfg(feature = "csv-output")]
-        OutputFormat::Csv => crate::core::output::write_csv(messages, path, config),
+        OutputFormat::Csv => crate::core::output::write_csv_iter(messages, path, config, on_exists),
         #[cfg(feature = "json-output")]
-        OutputFormat::Json => crate::core::output::write_json(messages, path, config),
+        OutputFormat::Json => {
+            crate::core::output::write_json_iter(messages, path, config, on_exists)
+        }
         #[cfg(feature = "json-output")]
-        OutputFormat::Jsonl => crate::core::output::write_jsonl(messages, path, config),
+        OutputFormat::Jsonl => {
+            crate::core::output::write_jsonl_iter(messages, path, config, on_exists)
+        }
+        #[cfg(feature = "json-output")]
+        OutputFormat::HfText => {
+            crate::core::output::write_hf_text_iter(messages, path, config, on_exists)
+        }
         #[allow(unreachable_patterns)]
-        _ => Err(ChatpackError::InvalidFormat {
-            format: "output",
-            message: format!(
-                "Output format {:?} requires the '{}' feature to be enabled",
-                format,
-                match format {
-                    OutputFormat::Csv => "csv-output",
-                    OutputFormat::Json | OutputFormat::Jsonl => "json-output",
-                }
-            ),
-        }),
+        _ => Err(ChatpackError::unsupported_format(format)),
+    }
+}
+
+/// Reads a file written in `format` back into messages.
+///
+/// Dispatches to [`read_csv`](crate::core::output::read_csv),
+/// [`read_json`](crate::core::output::read_json), or
+/// [`read_jsonl`](crate::core::output::read_jsonl); see those for what
+/// comes back.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::UnsupportedFormat`] if the format's feature is
+/// not enabled, [`ChatpackError::UnsupportedOperation`] for
+/// [`OutputFormat::HfText`], which does not keep enough to rebuild
+/// messages, or the reader's error.
+#[allow(unused_variables)]
+pub fn read_from_format(
+    path: impl AsRef<std::path::Path>,
+    format: OutputFormat,
+) -> Result<Vec<Message>, ChatpackError> {
+    match format {
+        #[cfg(feature = "csv-output")]
+        OutputFormat::Csv => crate::core::output::read_csv(path),
+        #[cfg(feature = "json-output")]
+        OutputFormat::Json => crate::core::output::read_json(path),
+        #[cfg(feature = "json-output")]
+        OutputFormat::Jsonl => crate::core::output::read_jsonl(path),
+        OutputFormat::HfText => Err(ChatpackError::unsupported_operation(
+            "reading back",
+            "HF text output",
+        )),
+        #[allow(unreachable_patterns)]
+        _ => Err(ChatpackError::unsupported_format(format)),
     }
 }
 
@@ -253,6 +413,11 @@ pub fn write_to_format(
 /// # Ok(())
 /// # }
 /// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::UnsupportedFormat`] if the required feature for
+/// the format is not enabled, or an error from the serializer.
 #[allow(unused_variables)]
 pub fn to_format_string(
     messages: &[Message],
@@ -266,18 +431,10 @@ pub fn to_format_string(
         OutputFormat::Json => crate::core::output::to_json(messages, config),
         #[cfg(feature = "json-output")]
         OutputFormat::Jsonl => crate::core::output::to_jsonl(messages, config),
+        #[cfg(feature = "json-output")]
+        OutputFormat::HfText => crate::core::output::to_hf_text(messages, config),
         #[allow(unreachable_patterns)]
-        _ => Err(ChatpackError::InvalidFormat {
-            format: "output",
-            message: format!(
-                "Output format {:?} requires the '{}' feature to be enabled",
-                format,
-                match format {
-                    OutputFormat::Csv => "csv-output",
-                    OutputFormat::Json | OutputFormat::Jsonl => "json-output",
-                }
-            ),
-        }),
+        _ => Err(ChatpackError::unsupported_format(format)),
     }
 }
 
@@ -312,6 +469,8 @@ mod tests {
             OutputFormat::from_str("NDJSON").unwrap(),
             OutputFormat::Jsonl
         );
+        assert_eq!(OutputFormat::from_str("hf").unwrap(), OutputFormat::HfText);
+        assert_eq!(OutputFormat::from_str("HF").unwrap(), OutputFormat::HfText);
     }
 
     #[test]
@@ -336,6 +495,7 @@ mod tests {
         assert_eq!(OutputFormat::Csv.to_string(), "CSV");
         assert_eq!(OutputFormat::Json.to_string(), "JSON");
         assert_eq!(OutputFormat::Jsonl.to_string(), "JSONL");
+        assert_eq!(OutputFormat::HfText.to_string(), "HF text");
     }
 
     // =========================================================================
@@ -347,6 +507,7 @@ mod tests {
         assert_eq!(OutputFormat::Csv.extension(), "csv");
         assert_eq!(OutputFormat::Json.extension(), "json");
         assert_eq!(OutputFormat::Jsonl.extension(), "jsonl");
+        assert_eq!(OutputFormat::HfText.extension(), "jsonl");
     }
 
     // =========================================================================
@@ -411,10 +572,11 @@ mod tests {
     #[test]
     fn test_format_all() {
         let all = OutputFormat::all();
-        assert_eq!(all.len(), 3);
+        assert_eq!(all.len(), 4);
         assert!(all.contains(&OutputFormat::Csv));
         assert!(all.contains(&OutputFormat::Json));
         assert!(all.contains(&OutputFormat::Jsonl));
+        assert!(all.contains(&OutputFormat::HfText));
     }
 
     #[test]
@@ -424,6 +586,7 @@ mod tests {
         assert!(names.contains(&"json"));
         assert!(names.contains(&"jsonl"));
         assert!(names.contains(&"ndjson"));
+        assert!(names.contains(&"hf"));
     }
 
     // =========================================================================
@@ -447,6 +610,9 @@ mod tests {
 
         let parsed: OutputFormat = serde_json::from_str("\"csv\"").unwrap();
         assert_eq!(parsed, OutputFormat::Csv);
+
+        let json = serde_json::to_string(&OutputFormat::HfText).unwrap();
+        assert_eq!(json, "\"hf\"");
     }
 
     #[test]
@@ -491,6 +657,56 @@ mod tests {
     // to_format_string tests
     // =========================================================================
 
+    #[test]
+    fn test_format_is_enabled() {
+        assert_eq!(OutputFormat::Csv.is_enabled(), cfg!(feature = "csv-output"));
+        assert_eq!(
+            OutputFormat::Json.is_enabled(),
+            cfg!(feature = "json-output")
+        );
+        assert_eq!(
+            OutputFormat::Jsonl.is_enabled(),
+            OutputFormat::Json.is_enabled()
+        );
+    }
+
+    #[cfg(not(feature = "json-output"))]
+    #[test]
+    fn test_json_without_feature() {
+        let messages = vec![Message::new("Alice", "Hello!")];
+        let config = OutputConfig::new();
+
+        let err = to_format_string(&messages, OutputFormat::Jsonl, &config).unwrap_err();
+        assert!(err.is_unsupported_format());
+        assert!(err.to_string().contains("json-output"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.json");
+        let err = write_to_format(
+            &messages,
+            path.to_str().unwrap(),
+            OutputFormat::Json,
+            &config,
+        )
+        .unwrap_err();
+        assert!(err.is_unsupported_format());
+        assert!(!path.exists());
+    }
+
+    #[cfg(not(feature = "csv-output"))]
+    #[test]
+    fn test_csv_without_feature() {
+        let messages = vec![Message::new("Alice", "Hello!")];
+        let err = to_format_string(&messages, OutputFormat::Csv, &OutputConfig::new()).unwrap_err();
+        assert!(matches!(
+            err,
+            ChatpackError::UnsupportedFormat {
+                format: OutputFormat::Csv,
+                feature: "csv-output"
+            }
+        ));
+    }
+
     #[cfg(all(feature = "csv-output", feature = "json-output"))]
     #[test]
     fn test_to_format_string() {
@@ -509,5 +725,27 @@ mod tests {
             to_format_string(&messages, OutputFormat::Jsonl, &config).expect("jsonl failed");
         assert!(jsonl.contains("Alice"));
         assert!(jsonl.contains("Hello"));
+
+        let hf = to_format_string(&messages, OutputFormat::HfText, &config).expect("hf failed");
+        assert!(hf.contains(r#""text":"Alice: Hello!""#));
+    }
+
+    #[cfg(feature = "json-output")]
+    #[test]
+    fn test_write_hf_text_format() {
+        let messages = vec![Message::new("Alice", "Hello!")];
+        let config = OutputConfig::new();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.jsonl");
+        let path = path.to_str().unwrap();
+
+        write_to_format(&messages, path, OutputFormat::HfText, &config).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            to_format_string(&messages, OutputFormat::HfText, &config).unwrap()
+        );
+
+        let err = read_from_format(path, OutputFormat::HfText).unwrap_err();
+        assert!(err.is_unsupported_operation());
     }
 }
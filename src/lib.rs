@@ -23,14 +23,45 @@
 //!
 //! # #[cfg(all(feature = "telegram", feature = "csv-output"))]
 //! # fn main() -> chatpack::Result<()> {
-//! // Parse Telegram export
-//! let parser = create_parser(Platform::Telegram);
-//! let messages = parser.parse("export.json".as_ref())?;
-//!
-//! // Filter, merge, and export
+//! let messages = read("export.json")?;
 //! let filtered = apply_filters(messages, &FilterConfig::new().with_sender("Alice"));
-//! let merged = merge_consecutive(filtered);
-//! write_csv(&merged, "output.csv", &OutputConfig::default())?;
+//! write_csv(&merge_consecutive(filtered), "output.csv", &OutputConfig::default())?;
+//! # Ok(())
+//! # }
+//! # #[cfg(not(all(feature = "telegram", feature = "csv-output")))]
+//! # fn main() {}
+//! ```
+//!
+//! [`read`] detects the platform from the file; use [`read_as`] when you
+//! know it, or [`read_bytes`] for an export already in memory:
+//!
+//! ```
+//! # #[cfg(feature = "whatsapp")]
+//! # fn main() -> chatpack::Result<()> {
+//! use chatpack::prelude::*;
+//!
+//! let export = "[1/15/24, 10:30:00 AM] Alice: Hello\n[1/15/24, 10:31:00 AM] Bob: Hi!";
+//! let messages = read_bytes(export)?;
+//! assert_eq!(messages[0].sender, "Alice");
+//!
+//! let messages = read_bytes_as(Platform::WhatsApp, export)?;
+//! assert_eq!(messages.len(), 2);
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "whatsapp"))]
+//! # fn main() {}
+//! ```
+//!
+//! Or do the same in one call, with the platform detected from the input and
+//! the format inferred from the output extension:
+//!
+//! ```no_run
+//! # #[cfg(all(feature = "telegram", feature = "csv-output"))]
+//! # fn main() -> chatpack::Result<()> {
+//! use chatpack::prelude::*;
+//!
+//! let options = ConvertOptions::new().with_filter(FilterConfig::new().with_sender("Alice"));
+//! convert("export.json", "output.csv", options)?;
 //! # Ok(())
 //! # }
 //! # #[cfg(not(all(feature = "telegram", feature = "csv-output")))]
@@ -166,11 +197,19 @@
 //!
 //! | Module | Description |
 //! |--------|-------------|
-//! | [`parser`] | Unified parser API with [`Parser`](parser::Parser) trait and [`Platform`](parser::Platform) enum |
+//! | [`parser`] | Unified parser API with [`Parser`](parser::Parser) trait and parser selection by platform |
+//! | [`platform`] | The [`Platform`](platform::Platform) enum naming each export format |
 //! | [`parsers`] | Platform-specific implementations: [`TelegramParser`](parsers::TelegramParser), [`WhatsAppParser`](parsers::WhatsAppParser), etc. |
 //! | [`config`] | Parser configurations: [`TelegramConfig`](config::TelegramConfig), [`WhatsAppConfig`](config::WhatsAppConfig), etc. |
 //! | [`core`] | Core types: [`Message`], [`OutputConfig`](core::OutputConfig), [`FilterConfig`](core::FilterConfig) |
 //! | [`streaming`] | Memory-efficient streaming parsers for large files |
+//! | [`reader`] | One-call [`read`] of an export with platform detection |
+//! | `convert` | One-call `convert()` with platform detection and format inference |
+//! | `pipeline` | Parse, filter/merge, and write on separate threads, and build corpora from many exports (`parallel` feature) |
+//! | `testvectors` | Golden export/output pairs for conformance checks (`test-vectors` feature) |
+//! | `archive` | Single-file archives of messages, metadata, and stats (`archive` feature) |
+//! | `fixtures` | Builders for valid exports of every platform, for tests (`fixtures` feature) |
+//! | [`report`] | Human-readable run summaries: [`format_summary`](report::format_summary), [`format_performance`](report::format_performance) |
 //! | [`mod@format`] | Output formats: [`OutputFormat`](format::OutputFormat), [`write_to_format`](format::write_to_format) |
 //! | [`error`] | Error types: [`ChatpackError`], [`Result`] |
 //! | [`prelude`] | Convenient re-exports for common usage |
@@ -185,10 +224,18 @@
 //! | `whatsapp` | WhatsApp TXT parser | `regex` |
 //! | `instagram` | Instagram JSON parser | `serde_json` |
 //! | `discord` | Discord multi-format parser | `serde_json`, `regex`, `csv` |
+//! | `discord-json` | Discord JSON/JSONL only | `serde_json` |
+//! | `discord-txt` | Adds Discord TXT | `regex` |
+//! | `discord-csv` | Adds Discord CSV | `csv` |
 //! | `csv-output` | CSV output writer | `csv` |
 //! | `json-output` | JSON/JSONL output writers | `serde_json` |
 //! | `streaming` | Streaming parsers for large files | - |
-//! | `async` | Async parser support | `tokio` |
+//! | `parallel` | Threaded parse/filter/write pipeline | - |
+//! | `preview` | Terminal preview of messages | `unicode-width` |
+//! | `async` | Async parsers and writers | `tokio` |
+//! | `test-vectors` | Golden export/output pairs for every platform | - |
+//! | `archive` | Zip archives of a processed chat | `zip`, `serde_json` |
+//! | `fixtures` | Export builders for tests and docs | `serde_json` |
 //! | `full` | All features (default) | all above |
 //!
 //! ```toml
@@ -216,14 +263,16 @@ pub mod core;
 pub mod error;
 pub mod format;
 pub mod message;
+pub mod platform;
 pub mod progress;
+pub mod report;
 
 // Shared parsing utilities (DRY - used by both parsers and streaming)
 #[cfg(any(
     feature = "telegram",
     feature = "whatsapp",
     feature = "instagram",
-    feature = "discord"
+    feature = "discord-json"
 ))]
 pub mod parsing;
 
@@ -232,7 +281,7 @@ pub mod parsing;
     feature = "telegram",
     feature = "whatsapp",
     feature = "instagram",
-    feature = "discord"
+    feature = "discord-json"
 ))]
 pub mod parser;
 
@@ -240,10 +289,19 @@ pub mod parser;
     feature = "telegram",
     feature = "whatsapp",
     feature = "instagram",
-    feature = "discord"
+    feature = "discord-json"
 ))]
 pub mod parsers;
 
+// Visitor-style parsing into a MessageSink
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+pub mod sink;
+
 // Streaming module (requires streaming feature and at least one parser)
 #[cfg(all(
     feature = "streaming",
@@ -251,18 +309,95 @@ pub mod parsers;
         feature = "telegram",
         feature = "whatsapp",
         feature = "instagram",
-        feature = "discord"
+        feature = "discord-json"
     )
 ))]
 pub mod streaming;
 
+// One-call reading of an export (requires a parser)
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+pub mod reader;
+
+// One-call file conversion (requires a parser and an output writer)
+#[cfg(all(
+    any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ),
+    any(feature = "csv-output", feature = "json-output")
+))]
+pub mod convert;
+
+// Threaded parse -> filter/merge -> write pipeline
+#[cfg(all(
+    feature = "parallel",
+    any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ),
+    any(feature = "csv-output", feature = "json-output")
+))]
+pub mod pipeline;
+
+// Golden input/output pairs (enables every parser and writer)
+#[cfg(feature = "test-vectors")]
+pub mod testvectors;
+
+// Builders for valid exports, for tests and documentation
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+
+// Single-file zip archives of a processed chat
+#[cfg(feature = "archive")]
+pub mod archive;
+
 // Async parser module (requires async feature and at least one parser)
 #[cfg(all(feature = "async", feature = "telegram"))]
 pub mod async_parser;
 
+// C ABI (requires ffi feature and at least one parser)
+#[cfg(all(
+    feature = "ffi",
+    any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    )
+))]
+pub mod ffi;
+
 // Re-export the main types at the crate root for convenience
 pub use error::{ChatpackError, Result};
-pub use message::Message;
+pub use message::{AttachmentRef, Message};
+
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+pub use reader::{read, read_as, read_bytes, read_bytes_as, read_from};
+
+#[cfg(all(
+    any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ),
+    any(feature = "csv-output", feature = "json-output")
+))]
+pub use convert::{ConvertOptions, convert, convert_many};
 
 /// Convenient re-exports for common usage patterns.
 ///
@@ -277,7 +412,8 @@ pub use message::Message;
 /// // Now you have access to:
 /// // - Message, ChatpackError, Result
 /// // - Platform, Parser, create_parser, create_streaming_parser
-/// // - FilterConfig, apply_filters
+/// // - read, read_as, read_bytes, read_bytes_as, read_from
+/// // - FilterConfig, FilterExpr, apply_filters
 /// // - OutputConfig, merge_consecutive
 /// // - write_csv, write_json, write_jsonl (with features)
 /// // - All platform parsers (with features)
@@ -286,7 +422,8 @@ pub use message::Message;
 /// ```
 pub mod prelude {
     // Core message type
-    pub use crate::Message;
+    pub use crate::platform::Platform;
+    pub use crate::{AttachmentRef, Message};
 
     // Error types
     pub use crate::error::{ChatpackError, Result};
@@ -296,9 +433,30 @@ pub mod prelude {
         feature = "telegram",
         feature = "whatsapp",
         feature = "instagram",
-        feature = "discord"
+        feature = "discord-json"
+    ))]
+    pub use crate::parser::{Parser, create_parser, create_streaming_parser};
+
+    // One-call reading
+    #[cfg(any(
+        feature = "telegram",
+        feature = "whatsapp",
+        feature = "instagram",
+        feature = "discord-json"
+    ))]
+    pub use crate::reader::{read, read_as, read_bytes, read_bytes_as, read_from};
+
+    // One-call conversion
+    #[cfg(all(
+        any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json"
+        ),
+        any(feature = "csv-output", feature = "json-output")
     ))]
-    pub use crate::parser::{Parser, Platform, create_parser, create_streaming_parser};
+    pub use crate::convert::{ConvertOptions, convert, convert_many};
 
     // Platform configs
     pub use crate::config::{DiscordConfig, InstagramConfig, TelegramConfig, WhatsAppConfig};
@@ -307,10 +465,19 @@ pub mod prelude {
     pub use crate::core::models::OutputConfig;
 
     // Filtering
-    pub use crate::core::filter::{FilterConfig, apply_filters};
+    pub use crate::core::filter::{FilterConfig, FilterExpr, apply_filters};
 
     // Processing
-    pub use crate::core::processor::{ProcessingStats, merge_consecutive};
+    pub use crate::core::loss::LossReport;
+    pub use crate::core::processor::{
+        ActivityConfig, ContextWindow, DialogueConfig, MergeConfig, NearDupConfig, OrderingReport,
+        ProcessingStats, RelabelConfig, SenderMap, SenderMapReport, SenderMatch, anonymize_senders,
+        balance_senders, check_ordering, extract_dialogue, map_senders, merge_consecutive,
+        near_dedupe, partition_by_topic, relabel_perspective, sample_messages, sort_messages,
+        suggest_title, trim_to_active_spans, with_context,
+    };
+    pub use crate::core::rand::Seed;
+    pub use crate::core::stats::{ChatStats, ResponseStat, SenderStats, response_times};
 
     // Output format
     pub use crate::format::OutputFormat;
@@ -337,6 +504,6 @@ pub mod prelude {
     #[cfg(feature = "instagram")]
     pub use crate::parsers::InstagramParser;
 
-    #[cfg(feature = "discord")]
+    #[cfg(feature = "discord-json")]
     pub use crate::parsers::DiscordParser;
 }
@@ -52,6 +52,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::platform::Platform;
+
+/// Content of a message that stands for one its sender deleted.
+///
+/// The WhatsApp parser writes it in place of localized placeholders such as
+/// `This message was deleted`. See [`Message::is_deleted`].
+pub const MESSAGE_DELETED: &str = "[Message deleted]";
+
 /// A normalized chat message from any supported platform.
 ///
 /// This struct is the core data type in chatpack. All platform-specific parsers
@@ -66,8 +74,15 @@ use serde::{Deserialize, Serialize};
 /// | `content` | `String` | Text content of the message |
 /// | `timestamp` | `Option<DateTime<Utc>>` | When the message was sent |
 /// | `id` | `Option<u64>` | Platform-specific message identifier |
-/// | `reply_to` | `Option<u64>` | ID of the parent message (for replies) |
+/// | `reply_to` | `Option<u64>` | ID (or index, see below) of the parent message |
 /// | `edited` | `Option<DateTime<Utc>>` | When the message was last edited |
+/// | `original_sender` | `Option<String>` | Sender before relabeling |
+/// | `forwarded_from` | `Option<String>` | Original author of a forwarded message |
+/// | `topic` | `Option<String>` | Forum topic the message was posted in |
+/// | `source` | `Option<String>` | Label of the conversation it came from |
+/// | `attachments` | `Vec<AttachmentRef>` | Referenced media files (opt-in, see below) |
+/// | `source_index` | `Option<u64>` | Position in the parsed source (not serialized) |
+/// | `platform` | `Option<Platform>` | Platform of the parser that produced it (not serialized) |
 ///
 /// # Construction
 ///
@@ -86,11 +101,25 @@ use serde::{Deserialize, Serialize};
 ///     .with_id(12345);
 /// ```
 ///
+/// # Ordering
+///
+/// Parsers number messages with [`source_index`](Self::source_index) in the
+/// order they produce them. Sorting with
+/// [`cmp_chronological`](Self::cmp_chronological) breaks timestamp ties by
+/// that index, so messages sent in the same minute keep their file order.
+/// Equality (`==`) ignores `source_index`, `source_file`, `platform`, and
+/// `raw`.
+///
 /// # Serialization
 ///
 /// Implements `Serialize` and `Deserialize` with these behaviors:
 /// - Optional fields are omitted from JSON when `None`
+/// - `attachments` is omitted when empty
+/// - `source_index`, `source_file`, `platform`, and `raw` are never serialized
 /// - Timestamps use RFC 3339 format
+/// - Unknown keys are ignored when deserializing, so records from newer
+///   writers still load (see
+///   [`SCHEMA_VERSION`](crate::core::models::SCHEMA_VERSION))
 /// - Suitable for storage, IPC, and RAG pipelines
 ///
 /// ```
@@ -104,7 +133,7 @@ use serde::{Deserialize, Serialize};
 /// assert!(json.contains("123"));
 /// # Ok::<(), serde_json::Error>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     /// Display name or username of the message author.
     pub sender: String,
@@ -135,6 +164,11 @@ pub struct Message {
     /// ID of the message this is replying to.
     ///
     /// Enables reconstruction of reply chains and conversation threads.
+    /// WhatsApp and Discord TXT exports have no message ids, so their
+    /// parsers set this to the quoted message's
+    /// [`source_index`](Self::source_index) instead. Writers only include
+    /// that index with
+    /// [`OutputConfig::with_index`](crate::core::models::OutputConfig::with_index).
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub reply_to: Option<u64>,
@@ -145,6 +179,149 @@ pub struct Message {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub edited: Option<DateTime<Utc>>,
+
+    /// The sender's name before
+    /// [`relabel_perspective`](crate::core::processor::relabel_perspective)
+    /// replaced it with a role label, or before the Instagram parser's
+    /// `canonicalize_participants` replaced an old username.
+    ///
+    /// Written with
+    /// [`OutputConfig::include_metadata`](crate::core::models::OutputConfig::include_metadata).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub original_sender: Option<String>,
+
+    /// Original author of a forwarded message.
+    ///
+    /// Set by the Telegram parser from the export's `forwarded_from`.
+    /// Written with
+    /// [`OutputConfig::include_metadata`](crate::core::models::OutputConfig::include_metadata).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub forwarded_from: Option<String>,
+
+    /// Title of the forum topic the message was posted in.
+    ///
+    /// Set by the Telegram parser for forum group exports (see
+    /// [`TelegramConfig::topic_filter`](crate::config::TelegramConfig::topic_filter)).
+    /// Written with
+    /// [`OutputConfig::include_metadata`](crate::core::models::OutputConfig::include_metadata).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub topic: Option<String>,
+
+    /// Label of the conversation the message came from, when several are
+    /// combined into one corpus.
+    ///
+    /// Set by [`build_corpus`](crate::pipeline::build_corpus) (`parallel`
+    /// feature). Written with
+    /// [`OutputConfig::include_metadata`](crate::core::models::OutputConfig::include_metadata).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub source: Option<String>,
+
+    /// Position of the message in the parser's output, starting at 0.
+    ///
+    /// Assigned by every parser (batch, streaming, and async) and kept
+    /// through filtering and merging. Orders messages, and is what
+    /// [`reply_to`](Self::reply_to) refers to on platforms without message
+    /// ids. It is not serialized with the message and does not take part in
+    /// `==`; writers include it with
+    /// [`OutputConfig::with_index`](crate::core::models::OutputConfig::with_index).
+    #[serde(skip)]
+    pub source_index: Option<u64>,
+
+    /// Position of the input file the message came from, when several
+    /// exports are converted into one output.
+    ///
+    /// Set by [`convert_many`](crate::convert::convert_many); writers use it
+    /// to mark where each conversation starts (see
+    /// [`OutputConfig::conversation_boundaries`](crate::core::models::OutputConfig::conversation_boundaries)).
+    /// Like `source_index`, it is not serialized and does not take part in `==`.
+    #[serde(skip)]
+    pub source_file: Option<usize>,
+
+    /// Platform of the parser that produced the message.
+    ///
+    /// Set by every built-in parser, so messages from different exports
+    /// stay distinguishable once combined. Not serialized (writers include
+    /// it with
+    /// [`OutputConfig::include_platform`](crate::core::models::OutputConfig::include_platform))
+    /// and not part of `==`. [`merge_consecutive`](crate::core::processor::merge_consecutive)
+    /// never merges messages from different platforms.
+    #[serde(skip)]
+    pub platform: Option<Platform>,
+
+    /// The source record the message was parsed from: the JSON object for
+    /// Telegram, Instagram and Discord, the lines for WhatsApp.
+    ///
+    /// Only set when the platform config's `capture_raw` is enabled. Not
+    /// serialized (writers include it with
+    /// [`OutputConfig::include_raw`](crate::core::models::OutputConfig::include_raw)),
+    /// not part of `==`, and not concatenated when messages are merged.
+    #[serde(skip)]
+    pub raw: Option<String>,
+
+    /// Media files the message references.
+    ///
+    /// Empty unless the parser was configured to collect them (e.g.
+    /// [`TelegramConfig::with_collect_attachment_refs`](crate::config::TelegramConfig::with_collect_attachment_refs)).
+    /// The `[Attachment: ...]` markers in `content` are written either way.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentRef>,
+}
+
+/// A media file referenced by a message.
+///
+/// Collected by the Telegram, Discord, and Instagram parsers when enabled in
+/// their config. Use [`write_attachment_manifest`](crate::core::output::write_attachment_manifest)
+/// to list every file an export references.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentRef {
+    /// File name, as shown in the `[Attachment: ...]` marker.
+    pub file_name: String,
+
+    /// Where the export points to the file: a path relative to the export
+    /// (Telegram, Instagram) or a URL (Discord). `None` if the export
+    /// doesn't include the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl AttachmentRef {
+    /// Creates a reference to `file_name`, without a path.
+    pub fn new(file_name: impl Into<String>) -> Self {
+        Self {
+            file_name: file_name.into(),
+            path: None,
+        }
+    }
+
+    /// Creates a reference from a path or URL, using its last segment as
+    /// the file name.
+    pub fn from_path(path: impl Into<String>) -> Self {
+        let path = path.into();
+        Self {
+            file_name: path.rsplit('/').next().unwrap_or(&path).to_string(),
+            path: Some(path),
+        }
+    }
+}
+
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        self.sender == other.sender
+            && self.content == other.content
+            && self.timestamp == other.timestamp
+            && self.id == other.id
+            && self.reply_to == other.reply_to
+            && self.edited == other.edited
+            && self.original_sender == other.original_sender
+            && self.forwarded_from == other.forwarded_from
+            && self.topic == other.topic
+            && self.source == other.source
+            && self.attachments == other.attachments
+    }
 }
 
 impl Message {
@@ -170,6 +347,15 @@ impl Message {
             id: None,
             reply_to: None,
             edited: None,
+            original_sender: None,
+            forwarded_from: None,
+            topic: None,
+            source: None,
+            source_index: None,
+            source_file: None,
+            platform: None,
+            raw: None,
+            attachments: Vec::new(),
         }
     }
 
@@ -192,6 +378,15 @@ impl Message {
             id,
             reply_to,
             edited,
+            original_sender: None,
+            forwarded_from: None,
+            topic: None,
+            source: None,
+            source_index: None,
+            source_file: None,
+            platform: None,
+            raw: None,
+            attachments: Vec::new(),
         }
     }
 
@@ -269,6 +464,82 @@ impl Message {
         self
     }
 
+    /// Builder method to add a referenced media file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use chatpack::{AttachmentRef, Message};
+    ///
+    /// let msg = Message::new("Alice", "[Attachment: cat.png]")
+    ///     .with_attachment(AttachmentRef::from_path("photos/cat.png"));
+    /// assert_eq!(msg.attachments[0].file_name, "cat.png");
+    /// ```
+    #[must_use]
+    pub fn with_attachment(mut self, attachment: AttachmentRef) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// Builder method to set the source index.
+    ///
+    /// Parsers set this themselves; use it when building messages from
+    /// another source that should sort deterministically.
+    #[must_use]
+    pub fn with_source_index(mut self, index: u64) -> Self {
+        self.source_index = Some(index);
+        self
+    }
+
+    /// Builder method to set the source file.
+    #[must_use]
+    pub fn with_source_file(mut self, index: usize) -> Self {
+        self.source_file = Some(index);
+        self
+    }
+
+    /// Builder method to set the platform.
+    #[must_use]
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+
+    /// Builder method to set the source record.
+    #[must_use]
+    pub fn with_raw(mut self, raw: impl Into<String>) -> Self {
+        self.raw = Some(raw.into());
+        self
+    }
+
+    /// Builder method to set the sender's name before relabeling.
+    #[must_use]
+    pub fn with_original_sender(mut self, sender: impl Into<String>) -> Self {
+        self.original_sender = Some(sender.into());
+        self
+    }
+
+    /// Builder method to set the original author of a forwarded message.
+    #[must_use]
+    pub fn with_forwarded_from(mut self, author: impl Into<String>) -> Self {
+        self.forwarded_from = Some(author.into());
+        self
+    }
+
+    /// Builder method to set the forum topic.
+    #[must_use]
+    pub fn with_topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    /// Builder method to set the label of the conversation it came from.
+    #[must_use]
+    pub fn with_source(mut self, label: impl Into<String>) -> Self {
+        self.source = Some(label.into());
+        self
+    }
+
     // =========================================================================
     // Accessor methods
     // =========================================================================
@@ -303,6 +574,51 @@ impl Message {
         self.edited
     }
 
+    /// Returns the forum topic, if known.
+    pub fn topic(&self) -> Option<&str> {
+        self.topic.as_deref()
+    }
+
+    /// Returns the label of the conversation it came from, if set.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Returns the position in the parsed source, if assigned.
+    pub fn source_index(&self) -> Option<u64> {
+        self.source_index
+    }
+
+    /// Returns the position of the input file, if assigned.
+    pub fn source_file(&self) -> Option<usize> {
+        self.source_file
+    }
+
+    /// Returns the source record, if captured.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    /// Returns the platform, if assigned.
+    pub fn platform(&self) -> Option<Platform> {
+        self.platform
+    }
+
+    /// Returns the lowercase name of the platform, as writers emit it.
+    #[cfg(any(feature = "csv-output", feature = "json-output"))]
+    pub(crate) fn platform_name(&self) -> Option<&'static str> {
+        self.platform.map(|platform| platform.name())
+    }
+
+    /// Returns `true` if `other` comes from the same input file, platform,
+    /// forum topic, and labeled conversation, so the two may be merged.
+    pub(crate) fn same_source(&self, other: &Self) -> bool {
+        self.platform == other.platform
+            && self.source_file == other.source_file
+            && self.topic == other.topic
+            && self.source == other.source
+    }
+
     // =========================================================================
     // Utility methods
     // =========================================================================
@@ -319,6 +635,57 @@ impl Message {
     pub fn is_empty(&self) -> bool {
         self.content.trim().is_empty()
     }
+
+    /// Returns `true` if this message only marks a deleted message, its
+    /// content being [`MESSAGE_DELETED`].
+    ///
+    /// ```
+    /// use chatpack::Message;
+    /// use chatpack::message::MESSAGE_DELETED;
+    ///
+    /// assert!(Message::new("Alice", MESSAGE_DELETED).is_deleted());
+    /// assert!(!Message::new("Alice", "Hi").is_deleted());
+    /// ```
+    pub fn is_deleted(&self) -> bool {
+        self.content == MESSAGE_DELETED
+    }
+
+    /// Orders messages by timestamp, then by [`source_index`](Self::source_index).
+    ///
+    /// Messages without a timestamp sort first. This is the ordering every
+    /// sort in chatpack uses; with a stable sort it keeps file order for
+    /// messages that tie on both keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use chatpack::Message;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// let ts = Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap();
+    /// let mut messages = vec![
+    ///     Message::new("Bob", "second").with_timestamp(ts).with_source_index(1),
+    ///     Message::new("Alice", "first").with_timestamp(ts).with_source_index(0),
+    /// ];
+    ///
+    /// messages.sort_by(Message::cmp_chronological);
+    /// assert_eq!(messages[0].content, "first");
+    /// ```
+    pub fn cmp_chronological(&self, other: &Self) -> std::cmp::Ordering {
+        self.timestamp
+            .cmp(&other.timestamp)
+            .then_with(|| self.source_index.cmp(&other.source_index))
+    }
+}
+
+/// Numbers messages with [`Message::source_index`] in slice order and tags
+/// them with `platform`.
+#[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
+pub(crate) fn assign_source_indices(messages: &mut [Message], platform: Platform) {
+    for (index, msg) in (0u64..).zip(messages.iter_mut()) {
+        msg.source_index = Some(index);
+        msg.platform = Some(platform);
+    }
 }
 
 impl Default for Message {
@@ -392,6 +759,13 @@ mod tests {
         assert!(msg.timestamp().is_none());
     }
 
+    #[test]
+    fn test_message_deserialization_ignores_unknown_keys() {
+        let json = r#"{"_v":1,"sender":"Bob","content":"Hi","channel":"general"}"#;
+        let msg: Message = serde_json::from_str(json).unwrap();
+        assert_eq!(msg, Message::new("Bob", "Hi"));
+    }
+
     #[test]
     fn test_message_accessors() {
         let ts = Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
@@ -408,4 +782,37 @@ mod tests {
         assert_eq!(msg.reply_to(), Some(122));
         assert_eq!(msg.edited(), Some(ts));
     }
+
+    #[test]
+    fn test_source_index_not_serialized_or_compared() {
+        let msg = Message::new("Alice", "Hello").with_source_index(7);
+        assert_eq!(msg.source_index(), Some(7));
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(!json.contains("source_index"));
+        let parsed: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.source_index(), None);
+        assert_eq!(parsed, msg);
+    }
+
+    #[test]
+    fn test_cmp_chronological_tie_break() {
+        let ts = Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+        let mut messages = [
+            Message::new("C", "c")
+                .with_timestamp(ts)
+                .with_source_index(2),
+            Message::new("A", "a")
+                .with_timestamp(ts)
+                .with_source_index(0),
+            Message::new("N", "no ts").with_source_index(5),
+            Message::new("B", "b")
+                .with_timestamp(ts)
+                .with_source_index(1),
+        ];
+        messages.sort_by(Message::cmp_chronological);
+
+        let order: Vec<_> = messages.iter().map(|m| m.sender.as_str()).collect();
+        assert_eq!(order, ["N", "A", "B", "C"]);
+    }
 }
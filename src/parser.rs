@@ -11,6 +11,7 @@
 //! - [`Platform`] - Enum for dynamic parser selection
 //! - [`create_parser`] - Factory function for standard parsers
 //! - [`create_streaming_parser`] - Factory function for memory-efficient streaming
+//! - [`register_parser`] / [`create_parser_by_name`] - Plug in custom formats
 //!
 //! # Examples
 //!
@@ -72,189 +73,353 @@
 //! # #[cfg(not(all(feature = "telegram", feature = "streaming")))]
 //! # fn main() {}
 //! ```
+//!
+//! ## Custom Platforms
+//!
+//! In-house formats plug in with [`register_parser`] and then work anywhere
+//! a platform name is accepted:
+//!
+//! ```
+//! use chatpack::parser::{Parser, Platform, create_parser_by_name, register_parser};
+//! use chatpack::{Message, Result};
+//! use std::path::Path;
+//!
+//! struct PipeParser;
+//!
+//! impl Parser for PipeParser {
+//!     fn name(&self) -> &'static str {
+//!         "Pipe"
+//!     }
+//!
+//!     fn platform(&self) -> Platform {
+//!         Platform::Custom("pipe")
+//!     }
+//!
+//!     fn parse(&self, path: &Path) -> Result<Vec<Message>> {
+//!         self.parse_str(&std::fs::read_to_string(path)?)
+//!     }
+//!
+//!     fn parse_str(&self, content: &str) -> Result<Vec<Message>> {
+//!         Ok(content
+//!             .lines()
+//!             .filter_map(|line| line.split_once('|'))
+//!             .map(|(sender, text)| Message::new(sender, text))
+//!             .collect())
+//!     }
+//! }
+//!
+//! register_parser("pipe", &["pp"], || Box::new(PipeParser))?;
+//!
+//! let parser = create_parser_by_name("PP")?;
+//! let messages = parser.parse_str("Alice|Hi\nBob|Hey")?;
+//! assert_eq!(messages[1].sender, "Bob");
+//! # Ok::<(), chatpack::ChatpackError>(())
+//! ```
 
+use std::collections::HashMap;
 use std::path::Path;
-
-use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, PoisonError, RwLock};
 
 use crate::Message;
+use crate::config::ConfigField;
 use crate::error::ChatpackError;
+use crate::parsing::encoding::{read_text_prefix, strip_bom};
+use crate::sink::{MessageRef, MessageSink};
 
 #[cfg(feature = "streaming")]
-use crate::streaming::MessageIterator;
+use crate::progress::{Progress, ProgressCallback};
+#[cfg(feature = "streaming")]
+use crate::streaming::{MessageIterator, StreamingResult};
+
+pub use crate::platform::Platform;
+
+impl Platform {
+    /// Returns the names and aliases added with [`register_parser`], sorted.
+    pub fn registered_names() -> Vec<String> {
+        let mut names: Vec<String> = read_registry().keys().cloned().collect();
+        names.sort_unstable();
+        names
+    }
+}
 
-/// Supported messaging platforms for chat export parsing.
+// ============================================================================
+// Custom parser registry
+// ============================================================================
+
+/// Constructor for a custom parser, passed to [`register_parser`].
+pub type ParserFactory = fn() -> Box<dyn Parser>;
+
+struct Registration {
+    name: &'static str,
+    factory: ParserFactory,
+}
+
+/// Lowercase name or alias -> registration. Entries are never removed.
+static REGISTRY: LazyLock<RwLock<HashMap<String, Registration>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+// Writers never panic while holding the lock, so a poisoned lock still
+// holds a consistent map.
+fn read_registry() -> std::sync::RwLockReadGuard<'static, HashMap<String, Registration>> {
+    REGISTRY.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn registered_factory(name: &str) -> Option<ParserFactory> {
+    read_registry()
+        .get(&name.to_lowercase())
+        .map(|entry| entry.factory)
+}
+
+/// Looks up a registered name or alias, already lowercased.
+pub(crate) fn registered_platform(lowercase: &str) -> Option<Platform> {
+    read_registry()
+        .get(lowercase)
+        .map(|entry| Platform::Custom(entry.name))
+}
+
+/// Registers a parser for a custom format under `name` and `aliases`.
+///
+/// Afterwards the format resolves like a built-in platform: through
+/// [`create_parser_by_name`], [`Platform::from_str`](std::str::FromStr),
+/// deserialization, and [`create_parser`] with the returned
+/// [`Platform::Custom`]. Names and aliases match case-insensitively, and the
+/// name is stored lowercase. The parser's [`Parser::platform`] should return
+/// `Platform::Custom` with that name.
+///
+/// # Thread Safety
 ///
-/// Each variant corresponds to a specific export format and parser implementation.
-/// Use with [`create_parser`] or [`create_streaming_parser`] for dynamic parser selection.
+/// The registry is process-wide and guarded by a lock, so parsers can be
+/// registered and looked up from any thread. Registrations are permanent:
+/// register once at startup rather than per request.
 ///
-/// # Aliases
+/// # Errors
 ///
-/// All platforms support short aliases for convenience:
-/// - `telegram` / `tg`
-/// - `whatsapp` / `wa`
-/// - `instagram` / `ig`
-/// - `discord` / `dc`
+/// Returns [`ChatpackError::InvalidFormat`] if the name is empty, or if the
+/// name or an alias is a built-in platform name or already registered.
+/// Nothing is registered in that case; existing entries are never replaced.
 ///
 /// # Examples
 ///
 /// ```
-/// use chatpack::parser::Platform;
-/// use std::str::FromStr;
-///
-/// // Parse from string (case-insensitive)
-/// let platform = Platform::from_str("telegram")?;
-/// assert_eq!(platform, Platform::Telegram);
+/// # #[cfg(feature = "whatsapp")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::parser::{Platform, register_parser};
+/// use chatpack::parsers::WhatsAppParser;
 ///
-/// // Aliases work too
-/// let platform = Platform::from_str("tg")?;
-/// assert_eq!(platform, Platform::Telegram);
+/// let platform = register_parser("Signal", &["sg"], || Box::new(WhatsAppParser::new()))?;
+/// assert_eq!(platform, Platform::Custom("signal"));
+/// assert_eq!("SG".parse::<Platform>(), Ok(platform));
 ///
-/// // Get file extension
-/// assert_eq!(Platform::WhatsApp.default_extension(), "txt");
-/// assert_eq!(Platform::Telegram.default_extension(), "json");
-/// # Ok::<(), String>(())
+/// // Names are first come, first served
+/// assert!(register_parser("signal", &[], || Box::new(WhatsAppParser::new())).is_err());
+/// assert!(register_parser("other", &["tg"], || Box::new(WhatsAppParser::new())).is_err());
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "whatsapp"))]
+/// # fn main() {}
 /// ```
+pub fn register_parser(
+    name: &str,
+    aliases: &[&str],
+    factory: ParserFactory,
+) -> Result<Platform, ChatpackError> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() {
+        return Err(ChatpackError::invalid_format(
+            "platform",
+            "Custom platform name must not be empty",
+        ));
+    }
+
+    let mut keys = vec![name.clone()];
+    for alias in aliases {
+        let alias = alias.trim().to_lowercase();
+        if !alias.is_empty() && !keys.contains(&alias) {
+            keys.push(alias);
+        }
+    }
+
+    let mut registry = REGISTRY.write().unwrap_or_else(PoisonError::into_inner);
+    if let Some(taken) = keys
+        .iter()
+        .find(|key| crate::platform::builtin_platform(key).is_some() || registry.contains_key(*key))
+    {
+        return Err(ChatpackError::invalid_format(
+            "platform",
+            format!("Platform name '{taken}' is already in use"),
+        ));
+    }
+
+    // Leaked once per registration so Platform stays Copy
+    let name: &'static str = Box::leak(name.into_boxed_str());
+    for key in keys {
+        registry.insert(key, Registration { name, factory });
+    }
+    Ok(Platform::Custom(name))
+}
+
+/// Creates a parser from a platform name or alias.
 ///
-/// # Serialization
+/// Built-in names are checked first, then names added with
+/// [`register_parser`]. Unlike [`create_parser`], this never panics.
 ///
-/// Serializes to lowercase strings, deserializes with alias support:
+/// # Examples
 ///
 /// ```
-/// use chatpack::parser::Platform;
-///
-/// let json = serde_json::to_string(&Platform::Telegram)?;
-/// assert_eq!(json, "\"telegram\"");
+/// # #[cfg(feature = "telegram")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::parser::create_parser_by_name;
 ///
-/// // Deserialize with alias
-/// let platform: Platform = serde_json::from_str("\"tg\"")?;
-/// assert_eq!(platform, Platform::Telegram);
-/// # Ok::<(), serde_json::Error>(())
+/// let parser = create_parser_by_name("tg")?;
+/// assert_eq!(parser.name(), "Telegram");
+/// assert!(create_parser_by_name("fax").is_err());
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "telegram"))]
+/// # fn main() {}
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-#[non_exhaustive]
-pub enum Platform {
-    /// Telegram Desktop JSON exports.
-    ///
-    /// Parses the `result.json` file from "Export chat history" feature.
-    /// Handles service messages, forwarded messages, and reply chains.
-    #[serde(alias = "tg")]
-    Telegram,
-
-    /// WhatsApp TXT exports from iOS and Android.
-    ///
-    /// Auto-detects locale-specific date formats (US, EU, RU variants).
-    /// Handles multiline messages and system notifications.
-    #[serde(alias = "wa")]
-    WhatsApp,
-
-    /// Instagram JSON exports from Meta's data download.
-    ///
-    /// Automatically fixes Mojibake encoding issues in non-ASCII text.
-    /// Parses direct messages from the `messages/` directory.
-    #[serde(alias = "ig")]
-    Instagram,
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::InvalidFormat`] if the name is unknown or the
+/// platform's feature is disabled.
+pub fn create_parser_by_name(name: &str) -> Result<Box<dyn Parser>, ChatpackError> {
+    let platform: Platform = name
+        .parse()
+        .map_err(|message: String| ChatpackError::invalid_format("platform", message))?;
+    try_create_parser(platform)
+}
 
-    /// Discord exports from DiscordChatExporter tool.
-    ///
-    /// Supports multiple formats: JSON, TXT, and CSV.
-    /// Preserves attachments, stickers, and reply references.
-    #[serde(alias = "dc")]
-    Discord,
+/// Like [`create_parser`], but returns an error instead of panicking when
+/// the platform's feature is disabled or a custom platform is not registered.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::InvalidFormat`] if no parser is available for
+/// `platform`.
+pub fn try_create_parser(platform: Platform) -> Result<Box<dyn Parser>, ChatpackError> {
+    new_parser(platform).ok_or_else(|| {
+        ChatpackError::invalid_format("platform", unavailable_parser_message(platform))
+    })
 }
 
-impl Platform {
-    /// Returns the default file extension for exports from this platform.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use chatpack::parser::Platform;
-    ///
-    /// assert_eq!(Platform::Telegram.default_extension(), "json");
-    /// assert_eq!(Platform::WhatsApp.default_extension(), "txt");
-    /// ```
-    pub fn default_extension(&self) -> &'static str {
-        match self {
-            Platform::WhatsApp => "txt",
-            Platform::Telegram | Platform::Instagram | Platform::Discord => "json",
-        }
-    }
+/// Number of leading bytes [`detect_platform`] inspects.
+const DETECTION_PREFIX_LEN: usize = 64 * 1024;
 
-    /// Returns all valid platform names and aliases.
-    ///
-    /// Useful for CLI help text or validation messages.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use chatpack::parser::Platform;
-    ///
-    /// let names = Platform::all_names();
-    /// assert!(names.contains(&"telegram"));
-    /// assert!(names.contains(&"tg")); // alias
-    /// ```
-    pub fn all_names() -> &'static [&'static str] {
-        &[
-            "telegram",
-            "tg",
-            "whatsapp",
-            "wa",
-            "instagram",
-            "ig",
-            "discord",
-            "dc",
-        ]
+/// Guesses which platform produced an export.
+///
+/// `.csv` and `.jsonl` files are Discord exports. Anything else is sniffed
+/// from its first 64KB: JSON is told apart by its keys (`participants` for
+/// Instagram, `messages` with `from_id` or `date_unixtime` for Telegram,
+/// then `guild` or `author` for Discord and `messages` for Telegram),
+/// a `Guild:` header marks Discord TXT, and other text is assumed to be
+/// WhatsApp. Custom platforms are never detected.
+///
+/// # Examples
+///
+/// ```no_run
+/// use chatpack::parser::{Platform, detect_platform};
+///
+/// # fn main() -> chatpack::Result<()> {
+/// assert_eq!(detect_platform("result.json")?, Platform::Telegram);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an I/O error if the file cannot be read, or
+/// [`ChatpackError::InvalidFormat`] saying why it matches no platform and
+/// listing the built-in ones.
+pub fn detect_platform(path: impl AsRef<Path>) -> Result<Platform, ChatpackError> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("csv" | "jsonl" | "ndjson") => Ok(Platform::Discord),
+        _ => sniff_platform(&read_text_prefix(path, DETECTION_PREFIX_LEN as u64)?)
+            .map_err(|reason| undetected_platform(&format!("'{}'", path.display()), reason)),
     }
+}
 
-    /// Returns all available platform variants.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use chatpack::parser::Platform;
-    ///
-    /// for platform in Platform::all() {
-    ///     println!("{}: .{}", platform, platform.default_extension());
-    /// }
-    /// ```
-    pub fn all() -> &'static [Platform] {
-        &[
-            Platform::Telegram,
-            Platform::WhatsApp,
-            Platform::Instagram,
-            Platform::Discord,
-        ]
+/// Guesses which platform produced an export already in memory.
+///
+/// Works like [`detect_platform`] without a file extension to go by, so
+/// Discord CSV and JSONL exports are recognized by their header row and
+/// `author` key.
+///
+/// # Examples
+///
+/// ```
+/// use chatpack::parser::{Platform, detect_platform_from_str};
+///
+/// let content = "[1/15/24, 10:30 AM] Alice: Hi";
+/// assert_eq!(detect_platform_from_str(content)?, Platform::WhatsApp);
+/// assert!(detect_platform_from_str(r#"{"rows": []}"#).is_err());
+/// # Ok::<(), chatpack::ChatpackError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::InvalidFormat`] saying why the content matches
+/// no platform and listing the built-in ones.
+pub fn detect_platform_from_str(content: &str) -> Result<Platform, ChatpackError> {
+    let mut end = content.len().min(DETECTION_PREFIX_LEN);
+    while !content.is_char_boundary(end) {
+        end -= 1;
     }
+    sniff_platform(strip_bom(&content[..end]))
+        .map_err(|reason| undetected_platform("the content", reason))
 }
 
-impl std::fmt::Display for Platform {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Platform::Telegram => write!(f, "Telegram"),
-            Platform::WhatsApp => write!(f, "WhatsApp"),
-            Platform::Instagram => write!(f, "Instagram"),
-            Platform::Discord => write!(f, "Discord"),
-        }
-    }
+/// Error for an export that matches no platform, saying why.
+fn undetected_platform(subject: &str, reason: &str) -> ChatpackError {
+    let names: Vec<&str> = Platform::all().iter().map(Platform::name).collect();
+    ChatpackError::invalid_format(
+        "platform",
+        format!(
+            "Could not detect the platform of {subject}: {reason}. \
+             Specify it explicitly as one of: {}.",
+            names.join(", ")
+        ),
+    )
 }
 
-impl std::str::FromStr for Platform {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "telegram" | "tg" => Ok(Platform::Telegram),
-            "whatsapp" | "wa" => Ok(Platform::WhatsApp),
-            "instagram" | "ig" => Ok(Platform::Instagram),
-            "discord" | "dc" => Ok(Platform::Discord),
-            _ => Err(format!(
-                "Unknown platform: '{}'. Expected one of: {}",
-                s,
-                Platform::all_names().join(", ")
-            )),
+/// Guesses the platform from the start of an export, or says why it
+/// cannot.
+fn sniff_platform(head: &str) -> Result<Platform, &'static str> {
+    let head = head.trim_start();
+    // WhatsApp lines also start with '['
+    let is_json = head.starts_with('{')
+        || head
+            .strip_prefix('[')
+            .is_some_and(|rest| rest.trim_start().starts_with(['{', ']']));
+    if is_json {
+        // Telegram channels with signed posts also have "author" keys
+        let telegram = head.contains("\"messages\"")
+            && (head.contains("\"from_id\"") || head.contains("\"date_unixtime\""));
+        if head.contains("\"participants\"") || head.contains("\"sender_name\"") {
+            Ok(Platform::Instagram)
+        } else if telegram {
+            Ok(Platform::Telegram)
+        } else if head.contains("\"guild\"") || head.contains("\"author\"") {
+            Ok(Platform::Discord)
+        } else if head.contains("\"messages\"") {
+            Ok(Platform::Telegram)
+        } else {
+            Err(
+                "it is JSON without the \"messages\" key of Telegram, the \"participants\" \
+                 key of Instagram, or the \"guild\" or \"author\" key of Discord",
+            )
         }
+    } else if head.starts_with("AuthorID,") || head.lines().any(|l| l.starts_with("Guild: ")) {
+        Ok(Platform::Discord)
+    } else if head.is_empty() {
+        Err("it is empty")
+    } else {
+        Ok(Platform::WhatsApp)
     }
 }
 
@@ -285,16 +450,89 @@ impl std::str::FromStr for Platform {
 /// # #[cfg(not(all(feature = "telegram", feature = "streaming")))]
 /// # fn main() {}
 /// ```
+///
+/// To report progress while iterating, attach a callback with
+/// [`with_progress`](ParseIterator::with_progress):
+///
+/// ```no_run
+/// # #[cfg(all(feature = "telegram", feature = "streaming"))]
+/// # fn main() -> chatpack::Result<()> {
+/// use std::sync::Arc;
+/// use chatpack::parser::{Parser, Platform, create_streaming_parser};
+///
+/// let parser = create_streaming_parser(Platform::Telegram);
+/// let messages = parser
+///     .stream_with_progress("export.json".as_ref())?
+///     .with_progress(10_000, Arc::new(|p| {
+///         eprintln!("{} messages, {} skipped", p.items_processed, p.skipped);
+///     }));
+///
+/// for result in messages {
+///     let msg = result?;
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(all(feature = "telegram", feature = "streaming")))]
+/// # fn main() {}
+/// ```
 #[cfg(feature = "streaming")]
 pub struct ParseIterator {
     inner: Box<dyn MessageIterator>,
+    messages: usize,
+    errors: usize,
+    every: usize,
+    callback: Option<ProgressCallback>,
+    finished: bool,
 }
 
 #[cfg(feature = "streaming")]
 impl ParseIterator {
     /// Creates a new parse iterator from a message iterator.
     pub fn new(inner: Box<dyn MessageIterator>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            messages: 0,
+            errors: 0,
+            every: 1,
+            callback: None,
+            finished: false,
+        }
+    }
+
+    /// Wraps messages that were parsed up front, reporting the file at
+    /// `path` as fully read.
+    pub(crate) fn loaded(messages: Vec<Message>, path: &Path) -> Self {
+        let total_bytes = std::fs::metadata(path).map_or(0, |m| m.len());
+        Self::new(Box::new(Loaded {
+            messages: messages.into_iter(),
+            total_bytes,
+        }))
+    }
+
+    /// Calls `callback` after every `every` messages and once more when the
+    /// iterator is exhausted.
+    ///
+    /// An `every` of 0 is treated as 1.
+    #[must_use]
+    pub fn with_progress(mut self, every: usize, callback: ProgressCallback) -> Self {
+        self.every = every.max(1);
+        self.callback = Some(callback);
+        self
+    }
+
+    /// Returns the progress so far.
+    ///
+    /// [`Progress::skipped`] counts both records the parser skipped and
+    /// errors yielded by this iterator.
+    pub fn snapshot(&self) -> Progress {
+        let mut progress = Progress::new(self.bytes_processed(), self.total_bytes(), self.messages);
+        progress.skipped = self.skipped() + self.errors;
+        progress
+    }
+
+    /// Returns the number of errors yielded so far.
+    pub fn errors(&self) -> usize {
+        self.errors
     }
 
     /// Returns the progress as a percentage (0.0 to 100.0).
@@ -313,6 +551,16 @@ impl ParseIterator {
     pub fn total_bytes(&self) -> Option<u64> {
         self.inner.total_bytes()
     }
+
+    /// Returns the number of invalid records skipped so far.
+    pub fn skipped(&self) -> usize {
+        self.inner.skipped()
+    }
+
+    /// Returns the number of system and service messages left out so far.
+    pub fn system_messages(&self) -> usize {
+        self.inner.system_messages()
+    }
 }
 
 #[cfg(feature = "streaming")]
@@ -320,9 +568,56 @@ impl Iterator for ParseIterator {
     type Item = Result<Message, ChatpackError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner
+        let item = self
+            .inner
             .next()
-            .map(|result| result.map_err(ChatpackError::from))
+            .map(|result| result.map_err(ChatpackError::from));
+        let report = match &item {
+            Some(Ok(_)) => {
+                self.messages += 1;
+                self.messages.is_multiple_of(self.every)
+            }
+            Some(Err(_)) => {
+                self.errors += 1;
+                false
+            }
+            None => !std::mem::replace(&mut self.finished, true),
+        };
+        if let Some(callback) = self.callback.as_ref().filter(|_| report) {
+            callback(self.snapshot());
+        }
+        item
+    }
+}
+
+/// Messages parsed before iteration started.
+#[cfg(feature = "streaming")]
+struct Loaded {
+    messages: std::vec::IntoIter<Message>,
+    total_bytes: u64,
+}
+
+#[cfg(feature = "streaming")]
+impl Iterator for Loaded {
+    type Item = StreamingResult<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.messages.next().map(Ok)
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl MessageIterator for Loaded {
+    fn progress(&self) -> Option<f64> {
+        Some(100.0)
+    }
+
+    fn bytes_processed(&self) -> u64 {
+        self.total_bytes
+    }
+
+    fn total_bytes(&self) -> Option<u64> {
+        Some(self.total_bytes)
     }
 }
 
@@ -345,6 +640,7 @@ impl Iterator for ParseIterator {
 /// | Method | Default | Description |
 /// |--------|---------|-------------|
 /// | [`stream`](Parser::stream) | Falls back to `parse` | Memory-efficient streaming |
+/// | [`stream_with_progress`](Parser::stream_with_progress) | Buffers `stream` | Streaming with progress tracking |
 /// | [`supports_streaming`](Parser::supports_streaming) | `false` | Native streaming support |
 /// | [`recommended_buffer_size`](Parser::recommended_buffer_size) | 64KB | Buffer size hint |
 ///
@@ -440,6 +736,85 @@ pub trait Parser: Send + Sync {
     /// Returns [`ChatpackError::Parse`] if content cannot be parsed.
     fn parse_str(&self, content: &str) -> Result<Vec<Message>, ChatpackError>;
 
+    /// Parses a file and reports how many invalid records were skipped.
+    ///
+    /// With `skip_invalid` enabled (the default), [`parse`](Parser::parse)
+    /// drops malformed records silently. This variant also returns how many
+    /// were dropped, so callers can warn when the count is non-zero. Messages
+    /// flagged by the config's `clamp_timestamps` check are counted too,
+    /// whether or not they were dropped.
+    ///
+    /// The default implementation reports `0`; parsers that can skip records
+    /// override it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "telegram")]
+    /// # fn main() -> chatpack::Result<()> {
+    /// use chatpack::parser::Parser;
+    /// use chatpack::parsers::TelegramParser;
+    /// use std::path::Path;
+    ///
+    /// let (messages, skipped) = TelegramParser::new().parse_counted(Path::new("result.json"))?;
+    /// if skipped > 0 {
+    ///     eprintln!("warning: skipped {skipped} invalid records");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "telegram"))]
+    /// # fn main() {}
+    /// ```
+    fn parse_counted(&self, path: &Path) -> Result<(Vec<Message>, usize), ChatpackError> {
+        Ok((self.parse(path)?, 0))
+    }
+
+    /// Parses a file into a [`MessageSink`], one message at a time.
+    ///
+    /// The sink receives each message as a [`MessageRef`] borrowing the
+    /// parser's buffers, and can return
+    /// [`ControlFlow::Break`](std::ops::ControlFlow::Break) to stop early.
+    /// Discord JSONL and WhatsApp exports are read line by line, so breaking
+    /// stops reading the file and no [`Message`] is allocated per record.
+    ///
+    /// The default implementation iterates [`stream`](Parser::stream).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "whatsapp")]
+    /// # fn main() -> chatpack::Result<()> {
+    /// use std::ops::ControlFlow;
+    /// use std::path::Path;
+    ///
+    /// use chatpack::parser::Parser;
+    /// use chatpack::parsers::WhatsAppParser;
+    /// use chatpack::sink::MessageRef;
+    ///
+    /// // Find the first message from Alice without reading the rest
+    /// let mut first = None;
+    /// let mut sink = |msg: MessageRef<'_>| {
+    ///     if msg.sender == "Alice" {
+    ///         first = Some(msg.to_message());
+    ///         return ControlFlow::Break(());
+    ///     }
+    ///     ControlFlow::Continue(())
+    /// };
+    /// WhatsAppParser::new().parse_into(Path::new("_chat.txt"), &mut sink)?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "whatsapp"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`parse`](Parser::parse). Records skipped
+    /// under `skip_invalid` are reported to [`MessageSink::on_skip`] instead.
+    fn parse_into(&self, path: &Path, sink: &mut dyn MessageSink) -> Result<(), ChatpackError> {
+        stream_into(self, path, sink)
+    }
+
     /// Parses a chat export file (convenience method accepting &str path).
     ///
     /// This is equivalent to `parse(Path::new(path))`.
@@ -491,6 +866,22 @@ pub trait Parser: Send + Sync {
         Ok(Box::new(messages.into_iter().map(Ok)))
     }
 
+    /// Streams messages like [`stream`](Parser::stream), with progress
+    /// tracking.
+    ///
+    /// Parsers with native streaming report bytes read as they go. The
+    /// default implementation collects [`stream`](Parser::stream) first and
+    /// reports the whole file as read, stopping at the first error.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`stream`](Parser::stream).
+    #[cfg(feature = "streaming")]
+    fn stream_with_progress(&self, path: &Path) -> Result<ParseIterator, ChatpackError> {
+        let messages = self.stream(path)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(ParseIterator::loaded(messages, path))
+    }
+
     /// Streams messages (convenience method accepting &str path).
     fn stream_file(
         &self,
@@ -514,6 +905,104 @@ pub trait Parser: Send + Sync {
     fn recommended_buffer_size(&self) -> usize {
         64 * 1024 // 64KB default
     }
+
+    /// Lists the options of this parser's configuration.
+    ///
+    /// Built-in parsers return their config's
+    /// [`ConfigSchema::schema`](crate::config::ConfigSchema::schema). The
+    /// default implementation returns an empty list, for parsers without
+    /// options.
+    fn config_schema(&self) -> Vec<ConfigField> {
+        Vec::new()
+    }
+
+    /// Returns the current configuration in its JSON form.
+    ///
+    /// The keys are the names in [`config_schema`](Parser::config_schema).
+    /// The default implementation returns an empty object.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "telegram")]
+    /// # fn main() -> chatpack::Result<()> {
+    /// use chatpack::parser::{Platform, create_parser};
+    /// use serde_json::json;
+    ///
+    /// let mut parser = create_parser(Platform::Telegram);
+    /// assert_eq!(parser.config_json()["include_calls"], json!(false));
+    ///
+    /// parser.set_config_json(json!({ "include_calls": true }))?;
+    /// assert_eq!(parser.config_json()["include_calls"], json!(true));
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "telegram"))]
+    /// # fn main() {}
+    /// ```
+    fn config_json(&self) -> serde_json::Value {
+        serde_json::Value::Object(serde_json::Map::new())
+    }
+
+    /// Updates the options named in `config`, a JSON object.
+    ///
+    /// Options not named keep their values, so a settings panel can send
+    /// just the ones that changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::InvalidConfig`] if `config` is not an
+    /// object, names an unknown option, or has a value of the wrong type.
+    /// The configuration is then left unchanged. The default
+    /// implementation accepts only an empty object.
+    fn set_config_json(&mut self, config: serde_json::Value) -> Result<(), ChatpackError> {
+        match config {
+            serde_json::Value::Object(map) if map.is_empty() => Ok(()),
+            other => Err(ChatpackError::invalid_config(format!(
+                "{} has no options to set, got {other}",
+                self.name()
+            ))),
+        }
+    }
+}
+
+/// Feeds [`Parser::stream`] into `sink`; the default `parse_into`.
+pub(crate) fn stream_into<P: Parser + ?Sized>(
+    parser: &P,
+    path: &Path,
+    sink: &mut dyn MessageSink,
+) -> Result<(), ChatpackError> {
+    for message in parser.stream(path)? {
+        if sink.on_message(MessageRef::from(&message?)).is_break() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Feeds `messages` into `sink`, reporting each system message the
+/// iterator left out as a [`SkipReason::SystemMessage`](crate::sink::SkipReason::SystemMessage)
+/// before the message that follows it.
+#[cfg(all(feature = "streaming", feature = "telegram"))]
+pub(crate) fn feed_into(
+    mut messages: ParseIterator,
+    sink: &mut dyn MessageSink,
+) -> Result<(), ChatpackError> {
+    let mut reported = 0;
+    let mut report = |messages: &ParseIterator, sink: &mut dyn MessageSink| {
+        for _ in reported..messages.system_messages() {
+            sink.on_skip(&crate::sink::SkipReason::SystemMessage);
+        }
+        reported = reported.max(messages.system_messages());
+    };
+    while let Some(message) = messages.next() {
+        let message = message?;
+        report(&messages, sink);
+        if sink.on_message(MessageRef::from(&message)).is_break() {
+            return Ok(());
+        }
+    }
+    report(&messages, sink);
+    Ok(())
 }
 
 /// Creates a parser for the specified platform with default configuration.
@@ -541,29 +1030,44 @@ pub trait Parser: Send + Sync {
 ///
 /// # Panics
 ///
-/// Panics if the corresponding feature is not enabled. Enable features in `Cargo.toml`:
+/// Panics if the corresponding feature is not enabled, or if `platform` is a
+/// [`Platform::Custom`] that was never registered with [`register_parser`].
+/// Use [`try_create_parser`] to get an error instead. Enable features in `Cargo.toml`:
 ///
 /// ```toml
 /// [dependencies]
 /// chatpack = { version = "0.6.0", features = ["telegram"] }
 /// ```
 pub fn create_parser(platform: Platform) -> Box<dyn Parser> {
+    new_parser(platform).unwrap_or_else(|| panic!("{}", unavailable_parser_message(platform)))
+}
+
+fn unavailable_parser_message(platform: Platform) -> String {
     match platform {
+        Platform::Custom(name) => format!(
+            "Custom platform '{name}' is not registered. Call register_parser first, \
+             or use try_create_parser to handle this as an error."
+        ),
+        _ => format!("Parser for {platform} is not enabled. Enable the corresponding feature."),
+    }
+}
+
+fn new_parser(platform: Platform) -> Option<Box<dyn Parser>> {
+    let parser: Box<dyn Parser> = match platform {
         #[cfg(feature = "telegram")]
         Platform::Telegram => Box::new(crate::parsers::TelegramParser::new()),
         #[cfg(feature = "whatsapp")]
         Platform::WhatsApp => Box::new(crate::parsers::WhatsAppParser::new()),
         #[cfg(feature = "instagram")]
         Platform::Instagram => Box::new(crate::parsers::InstagramParser::new()),
-        #[cfg(feature = "discord")]
+        #[cfg(feature = "discord-json")]
         Platform::Discord => Box::new(crate::parsers::DiscordParser::new()),
+        Platform::Custom(name) => registered_factory(name)?(),
         // Fallback for when features are disabled
         #[allow(unreachable_patterns)]
-        _ => panic!(
-            "Parser for {:?} is not enabled. Enable the corresponding feature.",
-            platform
-        ),
-    }
+        _ => return None,
+    };
+    Some(parser)
 }
 
 /// Creates a parser optimized for streaming large files.
@@ -604,9 +1108,13 @@ pub fn create_parser(platform: Platform) -> Box<dyn Parser> {
 /// # fn main() {}
 /// ```
 ///
+/// Custom platforms get the parser from their registered factory, which
+/// streams through its own [`Parser::stream`].
+///
 /// # Panics
 ///
-/// Panics if the corresponding feature is not enabled.
+/// Panics if the corresponding feature is not enabled, or for an
+/// unregistered custom platform.
 pub fn create_streaming_parser(platform: Platform) -> Box<dyn Parser> {
     match platform {
         #[cfg(feature = "telegram")]
@@ -615,8 +1123,9 @@ pub fn create_streaming_parser(platform: Platform) -> Box<dyn Parser> {
         Platform::WhatsApp => Box::new(crate::parsers::WhatsAppParser::with_streaming()),
         #[cfg(feature = "instagram")]
         Platform::Instagram => Box::new(crate::parsers::InstagramParser::with_streaming()),
-        #[cfg(feature = "discord")]
+        #[cfg(feature = "discord-json")]
         Platform::Discord => Box::new(crate::parsers::DiscordParser::with_streaming()),
+        Platform::Custom(_) => create_parser(platform),
         // Fallback for when features are disabled
         #[allow(unreachable_patterns)]
         _ => panic!(
@@ -821,7 +1330,7 @@ mod tests {
         assert_eq!(parser.platform(), Platform::Instagram);
     }
 
-    #[cfg(feature = "discord")]
+    #[cfg(feature = "discord-json")]
     #[test]
     fn test_create_parser_discord() {
         let parser = create_parser(Platform::Discord);
@@ -858,7 +1367,7 @@ mod tests {
         assert!(parser.supports_streaming());
     }
 
-    #[cfg(feature = "discord")]
+    #[cfg(feature = "discord-json")]
     #[test]
     fn test_create_streaming_parser_discord() {
         let parser = create_streaming_parser(Platform::Discord);
@@ -982,6 +1491,39 @@ mod tests {
         assert!(parse_iter.next().is_none());
     }
 
+    #[cfg(all(feature = "telegram", feature = "streaming"))]
+    #[test]
+    fn test_parse_iterator_reports_progress() {
+        use std::sync::{Arc, Mutex};
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("result.json");
+        let records: Vec<String> = (0..5)
+            .map(|i| {
+                format!(
+                    r#"{{"id": {i}, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "m{i}"}}"#
+                )
+            })
+            .collect();
+        std::fs::write(
+            &path,
+            format!("{{\n\"messages\": [\n{}\n]\n}}", records.join(",\n")),
+        )
+        .expect("write");
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&reports);
+        let iter = create_streaming_parser(Platform::Telegram)
+            .stream_with_progress(&path)
+            .expect("stream")
+            .with_progress(2, Arc::new(move |p| seen.lock().unwrap().push(p)));
+        assert_eq!(iter.count(), 5);
+
+        let reports = reports.lock().unwrap();
+        let items: Vec<usize> = reports.iter().map(|p| p.items_processed).collect();
+        assert_eq!(items, [2, 4, 5]);
+    }
+
     #[cfg(feature = "telegram")]
     #[test]
     fn test_parser_stream_default_impl() {
@@ -1001,4 +1543,208 @@ mod tests {
         assert_eq!(messages.len(), 1);
         assert_eq!(messages[0].sender, "Bob");
     }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_parser_config_json_through_dyn() {
+        let mut parser = create_parser(Platform::WhatsApp);
+        let schema = parser.config_schema();
+        let config = parser.config_json();
+        assert!(schema.iter().all(|f| config.get(f.name).is_some()));
+
+        parser
+            .set_config_json(serde_json::json!({"skip_system_messages": false}))
+            .expect("update");
+        assert_eq!(
+            parser.config_json()["skip_system_messages"],
+            serde_json::json!(false)
+        );
+        let content = "[1/15/24, 10:30:45 AM] Alice: Hi\n[1/15/24, 10:30:50 AM] System: Messages and calls are end-to-end encrypted";
+        let messages = parser.parse_str(content).expect("parse");
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn test_parser_config_json_default_impl() {
+        let mut parser = LineParser("line-config");
+        assert!(parser.config_schema().is_empty());
+        assert_eq!(parser.config_json(), serde_json::json!({}));
+        assert!(parser.set_config_json(serde_json::json!({})).is_ok());
+        let err = parser
+            .set_config_json(serde_json::json!({"streaming": true}))
+            .unwrap_err();
+        assert!(err.is_invalid_config());
+    }
+
+    // =========================================================================
+    // Custom registry tests
+    // =========================================================================
+
+    // The registry is process-wide, so every test registers its own names
+    struct LineParser(&'static str);
+
+    impl Parser for LineParser {
+        fn name(&self) -> &'static str {
+            "Line"
+        }
+
+        fn platform(&self) -> Platform {
+            Platform::Custom(self.0)
+        }
+
+        fn parse(&self, path: &Path) -> Result<Vec<Message>, ChatpackError> {
+            self.parse_str(&std::fs::read_to_string(path)?)
+        }
+
+        fn parse_str(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
+            Ok(content
+                .lines()
+                .filter_map(|line| line.split_once(": "))
+                .map(|(sender, text)| Message::new(sender, text))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_register_parser_resolves_name_and_aliases() {
+        let platform = register_parser("LineChat", &["lc", "LINES"], || {
+            Box::new(LineParser("linechat"))
+        })
+        .unwrap();
+
+        assert_eq!(platform, Platform::Custom("linechat"));
+        assert_eq!(Platform::from_str("linechat").unwrap(), platform);
+        assert_eq!(Platform::from_str("LC").unwrap(), platform);
+        assert_eq!(Platform::from_str("lines").unwrap(), platform);
+        assert!(Platform::registered_names().contains(&"lc".to_string()));
+
+        let parser = create_parser(platform);
+        assert_eq!(parser.platform(), platform);
+        let messages = parser.parse_str("Alice: Hi\nBob: Hey").unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].sender, "Bob");
+    }
+
+    #[test]
+    fn test_register_parser_rejects_taken_names() {
+        register_parser("dupchat", &["dup"], || Box::new(LineParser("dupchat"))).unwrap();
+
+        for (name, aliases) in [
+            ("dupchat", &[][..]),
+            ("DUP", &[][..]),
+            ("fresh", &["dup"][..]),
+            ("telegram", &[][..]),
+            ("fresh", &["wa"][..]),
+            ("  ", &[][..]),
+        ] {
+            let err = register_parser(name, aliases, || Box::new(LineParser("other"))).unwrap_err();
+            assert!(err.is_invalid_format(), "{name}: {err}");
+        }
+
+        // Failed registrations leave nothing behind
+        assert!(Platform::from_str("fresh").is_err());
+        assert!(matches!(
+            Platform::from_str("telegram").unwrap(),
+            Platform::Telegram
+        ));
+    }
+
+    #[test]
+    fn test_custom_platform_serde_and_display() {
+        let platform =
+            register_parser("SerdeChat", &["sc"], || Box::new(LineParser("serdechat"))).unwrap();
+
+        assert_eq!(platform.to_string(), "serdechat");
+        assert_eq!(platform.default_extension(), "txt");
+        assert_eq!(serde_json::to_string(&platform).unwrap(), "\"serdechat\"");
+        let parsed: Platform = serde_json::from_str("\"sc\"").unwrap();
+        assert_eq!(parsed, platform);
+        assert!(serde_json::from_str::<Platform>("\"nochat\"").is_err());
+    }
+
+    #[test]
+    fn test_create_parser_by_name() {
+        register_parser("byname", &[], || Box::new(LineParser("byname"))).unwrap();
+
+        let parser = create_parser_by_name("ByName").unwrap();
+        assert_eq!(parser.name(), "Line");
+
+        let err = create_parser_by_name("nochat").err().unwrap();
+        assert!(err.is_invalid_format());
+        assert!(err.to_string().contains("Unknown platform: 'nochat'"));
+        assert!(err.to_string().contains("byname"));
+    }
+
+    #[cfg(feature = "telegram")]
+    #[test]
+    fn test_create_parser_by_name_builtin() {
+        let parser = create_parser_by_name("TG").unwrap();
+        assert_eq!(parser.platform(), Platform::Telegram);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_create_parser_unregistered_custom_panics() {
+        let _ = create_parser(Platform::Custom("never-registered"));
+    }
+
+    #[test]
+    fn test_sniff_platform() {
+        let cases = [
+            (
+                r#"{"name": "Chat", "type": "personal_chat", "messages": []}"#,
+                Some(Platform::Telegram),
+            ),
+            (
+                r#"{"participants": [{"name": "a"}], "messages": []}"#,
+                Some(Platform::Instagram),
+            ),
+            (
+                r#"{"guild": {"id": "1"}, "messages": []}"#,
+                Some(Platform::Discord),
+            ),
+            (
+                r#"{"id":"1","author":{"name":"a"},"content":"hi"}"#,
+                Some(Platform::Discord),
+            ),
+            (
+                r#"{"name": "News", "type": "public_channel", "messages": [{"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "News", "from_id": "channel1", "author": "Alice", "text": "Hi"}]}"#,
+                Some(Platform::Telegram),
+            ),
+            ("AuthorID,Author,Date,Content\n", Some(Platform::Discord)),
+            (
+                "=====\nGuild: Server\nChannel: general\n",
+                Some(Platform::Discord),
+            ),
+            ("[1/15/24, 10:30 AM] Alice: Hi", Some(Platform::WhatsApp)),
+            (r#"{"rows": []}"#, None),
+            ("  \n", None),
+        ];
+        for (head, expected) in cases {
+            assert_eq!(sniff_platform(head).ok(), expected, "{head}");
+        }
+    }
+
+    #[test]
+    fn test_detect_platform_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("channel.JSONL");
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(detect_platform(&path).unwrap(), Platform::Discord);
+
+        let path = dir.path().join("empty.json");
+        std::fs::write(&path, "").unwrap();
+        let err = detect_platform(&path).unwrap_err();
+        assert!(err.is_invalid_format());
+        assert!(err.to_string().contains("empty.json': it is empty"));
+        assert!(
+            err.to_string()
+                .contains("telegram, whatsapp, instagram, discord")
+        );
+        assert!(
+            detect_platform(dir.path().join("missing.txt"))
+                .unwrap_err()
+                .is_io()
+        );
+    }
 }
@@ -2,21 +2,44 @@
 //!
 //! Parses exports from the DiscordChatExporter tool in JSON, TXT, or CSV format.
 
-use std::fs::{self, File};
-use std::io::BufReader;
+#[cfg(feature = "discord-txt")]
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::ops::ControlFlow;
 use std::path::Path;
 
-use chrono::{DateTime, NaiveDateTime, Utc};
-use regex::Regex;
-use serde::Deserialize;
-
 use crate::Message;
-use crate::config::DiscordConfig;
+use crate::config::{
+    ConfigField, ConfigSchema, ContentPolicy, DiscordConfig, TimestampCheck, config_to_json,
+    update_config_json,
+};
 use crate::error::ChatpackError;
-use crate::parser::{Parser, Platform};
+use crate::message::assign_source_indices;
+use crate::parser::{Parser, Platform, stream_into};
+use crate::parsing::content::{normalize_content, normalize_messages};
+#[cfg(feature = "discord-csv")]
+use crate::parsing::discord::csv;
+#[cfg(feature = "discord-txt")]
+use crate::parsing::discord::txt;
+use crate::parsing::discord::{
+    DiscordExport, DiscordRawMessage, DiscordScratch, SenderNaming, is_jsonl,
+    parse_discord_message_with_config, read_discord_jsonl_record,
+};
+#[cfg(feature = "streaming")]
+use crate::parsing::encoding::exceeds_file_size;
+use crate::parsing::encoding::{
+    check_file_size, for_each_line, open_text_reader, read_text_file, strip_bom,
+};
+use crate::parsing::json_scan::decode_tolerating_truncation;
+use crate::parsing::raw::decode_with_raw;
+use crate::parsing::timestamps::{check_messages, keep_timestamp};
+use crate::sink::{CollectingSink, MessageSink, SkipReason};
 
 #[cfg(feature = "streaming")]
-use crate::streaming::{DiscordStreamingParser, StreamingConfig, StreamingParser};
+use crate::parser::ParseIterator;
+#[cfg(feature = "streaming")]
+use crate::streaming::{DiscordStreamingParser, StreamingParser};
 
 /// Parser for Discord channel exports.
 ///
@@ -28,13 +51,19 @@ use crate::streaming::{DiscordStreamingParser, StreamingConfig, StreamingParser}
 /// | Extension | Format | Notes |
 /// |-----------|--------|-------|
 /// | `.json` | JSON | Full metadata, recommended |
-/// | `.txt` | Plain text | Basic, regex-parsed |
-/// | `.csv` | CSV | Tabular format |
+/// | `.jsonl` | JSON Lines | One message object per line |
+/// | `.txt` | Plain text | Basic, regex-parsed; needs `discord-txt` |
+/// | `.csv` | CSV | Tabular format; needs `discord-csv` |
+///
+/// Without the feature for a format, parsing it returns
+/// [`ChatpackError::UnsupportedInputFormat`].
 ///
 /// # Message Types
 ///
 /// - Regular messages
-/// - Replies (preserves reference)
+/// - Replies (preserves reference). TXT exports have no message ids, so
+///   their `reply_to` is the quoted message's `source_index`; write it with
+///   [`OutputConfig::with_index`](crate::core::models::OutputConfig::with_index)
 /// - Attachments (as placeholders)
 /// - Stickers
 /// - Embeds (text only)
@@ -94,6 +123,8 @@ impl DiscordParser {
         path.extension().and_then(|ext| {
             if ext.eq_ignore_ascii_case("json") {
                 Some(DiscordFormat::Json)
+            } else if ext.eq_ignore_ascii_case("jsonl") {
+                Some(DiscordFormat::Jsonl)
             } else if ext.eq_ignore_ascii_case("csv") {
                 Some(DiscordFormat::Csv)
             } else if ext.eq_ignore_ascii_case("txt") {
@@ -104,10 +135,30 @@ impl DiscordParser {
         })
     }
 
+    /// Detects the format of the export at `path` from its extension, or
+    /// else from its first line: JSONL or JSON when it opens an object,
+    /// `None` for TXT and CSV.
+    fn detect_format_from_file(&self, path: &Path) -> Result<Option<DiscordFormat>, ChatpackError> {
+        if let Some(format) = Self::detect_format_from_ext(path.to_str().unwrap_or_default()) {
+            return Ok(Some(format));
+        }
+        let mut first_line = String::new();
+        open_text_reader(File::open(path)?, self.config.buffer_size)?.read_line(&mut first_line)?;
+        Ok(if is_jsonl(&first_line) {
+            Some(DiscordFormat::Jsonl)
+        } else if first_line.trim_start().starts_with('{') {
+            Some(DiscordFormat::Json)
+        } else {
+            None
+        })
+    }
+
     /// Detect format from content
     fn detect_format_from_content(content: &str) -> DiscordFormat {
         let trimmed = content.trim();
-        if trimmed.starts_with('{') {
+        if is_jsonl(trimmed.lines().next().unwrap_or_default()) {
+            DiscordFormat::Jsonl
+        } else if trimmed.starts_with('{') {
             DiscordFormat::Json
         } else if trimmed.starts_with("AuthorID,") || trimmed.contains("\",\"") {
             DiscordFormat::Csv
@@ -116,264 +167,42 @@ impl DiscordParser {
         }
     }
 
-    #[allow(clippy::unused_self)]
     fn parse_json(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        let export: DiscordExport = serde_json::from_str(content)?;
-
-        let messages = export
-            .messages
-            .iter()
-            .filter_map(|msg| {
-                // Skip empty messages without attachments/stickers
-                if msg.content.trim().is_empty()
-                    && msg.attachments.as_ref().is_none_or(|a| a.is_empty())
-                    && msg.stickers.as_ref().is_none_or(|s| s.is_empty())
-                {
-                    return None;
-                }
-
-                // Build content: text + attachment/sticker info
-                let mut content = msg.content.clone();
-
-                // Append attachment filenames
-                if let Some(attachments) = &msg.attachments {
-                    for att in attachments {
-                        if !content.is_empty() {
-                            content.push('\n');
-                        }
-                        content.push_str(&format!("[Attachment: {}]", att.file_name));
-                    }
-                }
-
-                // Append sticker names
-                if let Some(stickers) = &msg.stickers {
-                    for sticker in stickers {
-                        if !content.is_empty() {
-                            content.push('\n');
-                        }
-                        content.push_str(&format!("[Sticker: {}]", sticker.name));
-                    }
-                }
+        let mut messages: Vec<Message> = if self.config.capture_raw {
+            decode_with_raw::<DiscordRawMessage>(content, false)?
+                .0
+                .iter()
+                .filter_map(|(msg, raw)| {
+                    parse_discord_message_with_config(msg, &self.config).map(|m| m.with_raw(*raw))
+                })
+                .collect()
+        } else {
+            let export: DiscordExport = serde_json::from_str(content)?;
 
-                // Use nickname if available, fallback to username
-                let sender = msg
-                    .author
-                    .nickname
-                    .as_ref()
-                    .unwrap_or(&msg.author.name)
-                    .clone();
-
-                // Parse timestamp (ISO 8601)
-                let timestamp = DateTime::parse_from_rfc3339(&msg.timestamp)
-                    .ok()
-                    .map(|dt| dt.to_utc());
-
-                // Parse edited timestamp
-                let edited = msg
-                    .timestamp_edited
-                    .as_ref()
-                    .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
-                    .map(|dt| dt.to_utc());
-
-                // Parse message ID (Discord snowflake)
-                let id = msg.id.parse::<u64>().ok();
-
-                // Parse reply reference
-                let reply_to = msg
-                    .reference
-                    .as_ref()
-                    .and_then(|r| r.message_id.as_ref())
-                    .and_then(|id_str| id_str.parse::<u64>().ok());
-
-                Some(Message::with_metadata(
-                    sender, content, timestamp, id, reply_to, edited,
-                ))
-            })
-            .collect();
+            // Use shared parsing logic
+            export
+                .messages
+                .iter()
+                .filter_map(|msg| parse_discord_message_with_config(msg, &self.config))
+                .collect()
+        };
+        normalize_messages(&mut messages, self.config.content);
 
         Ok(messages)
     }
 
-    #[allow(clippy::unused_self)]
-    fn parse_txt(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        let mut messages = Vec::new();
-
-        // Pattern: [M/D/YYYY H:MM AM] sender OR [M/D/YYYY H:MM:SS] sender
-        let header_re = Regex::new(
-            r"^\[(\d{1,2}/\d{1,2}/\d{4}\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?)\]\s+(.+)$",
-        )
-        .map_err(|e| ChatpackError::invalid_format("Discord TXT", e.to_string()))?;
-
-        let mut current_sender: Option<String> = None;
-        let mut current_timestamp: Option<DateTime<Utc>> = None;
-        let mut current_content = String::new();
-        let mut in_attachments = false;
-        let mut in_stickers = false;
-
+    /// Parses a JSONL export (one message object per line).
+    ///
+    /// Returns the messages and the number of invalid lines skipped.
+    fn parse_jsonl(&self, content: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let mut sink = CollectingSink::new();
+        let mut visitor = JsonlVisitor::new(&self.config);
         for line in content.lines() {
-            // Check for message header
-            if let Some(caps) = header_re.captures(line) {
-                // Save previous message if exists
-                if let Some(sender) = current_sender.take() {
-                    if !current_content.trim().is_empty() {
-                        messages.push(Message::with_metadata(
-                            sender,
-                            current_content.trim().to_string(),
-                            current_timestamp,
-                            None,
-                            None,
-                            None,
-                        ));
-                    }
-                }
-
-                // Parse new message header
-                let timestamp_str = caps.get(1).unwrap().as_str();
-                let sender = caps.get(2).unwrap().as_str().to_string();
-
-                current_timestamp = Self::parse_txt_timestamp(timestamp_str);
-                current_sender = Some(sender);
-                current_content = String::new();
-                in_attachments = false;
-                in_stickers = false;
-            } else if current_sender.is_some() {
-                // Check for special sections
-                if line == "{Attachments}" {
-                    in_attachments = true;
-                    in_stickers = false;
-                    continue;
-                }
-                if line == "{Stickers}" {
-                    in_stickers = true;
-                    in_attachments = false;
-                    continue;
-                }
-
-                // Handle content
-                if in_attachments || in_stickers {
-                    let trimmed = line.trim();
-                    if !trimmed.is_empty() {
-                        // Extract filename from URL or use as-is
-                        let name = if trimmed.starts_with("http") {
-                            trimmed.rsplit('/').next().unwrap_or(trimmed)
-                        } else {
-                            trimmed
-                        };
-
-                        if !current_content.is_empty() {
-                            current_content.push('\n');
-                        }
-                        if in_attachments {
-                            current_content.push_str(&format!("[Attachment: {}]", name));
-                        } else {
-                            current_content.push_str(&format!("[Sticker: {}]", name));
-                        }
-                    }
-                } else {
-                    // Regular message content
-                    if !current_content.is_empty() {
-                        current_content.push('\n');
-                    }
-                    current_content.push_str(line);
-                }
-            }
-        }
-
-        // Don't forget the last message
-        if let Some(sender) = current_sender {
-            if !current_content.trim().is_empty() {
-                messages.push(Message::with_metadata(
-                    sender,
-                    current_content.trim().to_string(),
-                    current_timestamp,
-                    None,
-                    None,
-                    None,
-                ));
-            }
-        }
-
-        Ok(messages)
-    }
-
-    fn parse_txt_timestamp(s: &str) -> Option<DateTime<Utc>> {
-        // Try formats: "M/D/YYYY H:MM AM", "M/D/YYYY H:MM:SS"
-        let formats = [
-            "%m/%d/%Y %I:%M %p",
-            "%m/%d/%Y %I:%M:%S %p",
-            "%m/%d/%Y %H:%M",
-            "%m/%d/%Y %H:%M:%S",
-        ];
-
-        for fmt in &formats {
-            if let Ok(dt) = NaiveDateTime::parse_from_str(s.trim(), fmt) {
-                return Some(dt.and_utc());
-            }
-        }
-        None
-    }
-
-    #[allow(clippy::unused_self)]
-    fn parse_csv_file(&self, file_path: &str) -> Result<Vec<Message>, ChatpackError> {
-        let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
-        self.parse_csv_reader(reader)
-    }
-
-    #[allow(clippy::unused_self)]
-    fn parse_csv_str(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        let reader = content.as_bytes();
-        self.parse_csv_reader(reader)
-    }
-
-    #[allow(clippy::unused_self)]
-    fn parse_csv_reader<R: std::io::Read>(&self, reader: R) -> Result<Vec<Message>, ChatpackError> {
-        let mut csv_reader = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .flexible(true)
-            .from_reader(reader);
-
-        let mut messages = Vec::new();
-
-        for result in csv_reader.records() {
-            let record = result?;
-
-            // CSV columns: AuthorID, Author, Date, Content, Attachments, Reactions
-            let sender = record.get(1).unwrap_or("").to_string();
-            let timestamp_str = record.get(2).unwrap_or("");
-            let mut content = record.get(3).unwrap_or("").to_string();
-            let attachments = record.get(4).unwrap_or("");
-
-            // Skip empty messages
-            if content.trim().is_empty() && attachments.trim().is_empty() {
-                continue;
-            }
-
-            // Parse attachments (comma-separated URLs)
-            if !attachments.trim().is_empty() {
-                for url in attachments.split(',') {
-                    let url = url.trim();
-                    if !url.is_empty() {
-                        let filename = url.rsplit('/').next().unwrap_or(url);
-                        if !content.is_empty() {
-                            content.push('\n');
-                        }
-                        content.push_str(&format!("[Attachment: {}]", filename));
-                    }
-                }
-            }
-
-            // Parse timestamp
-            let timestamp = DateTime::parse_from_rfc3339(timestamp_str)
-                .ok()
-                .map(|dt| dt.to_utc());
-
-            messages.push(Message::with_metadata(
-                sender, content, timestamp, None, None, None,
-            ));
+            // A collecting sink never breaks
+            let _ = visitor.push_line(line, &mut sink)?;
         }
-
-        Ok(messages)
+        visitor.finish(&mut sink);
+        Ok((sink.messages, sink.skipped))
     }
 }
 
@@ -386,88 +215,262 @@ impl Default for DiscordParser {
 #[derive(Debug, Clone, Copy)]
 enum DiscordFormat {
     Json,
+    Jsonl,
     Txt,
     Csv,
 }
 
-// Internal structures for deserializing Discord JSON
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DiscordExport {
-    messages: Vec<DiscordMessage>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DiscordMessage {
-    id: String,
-    timestamp: String,
-    timestamp_edited: Option<String>,
-    content: String,
-    author: DiscordAuthor,
-    reference: Option<DiscordReference>,
-    attachments: Option<Vec<DiscordAttachment>>,
-    stickers: Option<Vec<DiscordSticker>>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DiscordAuthor {
-    name: String,
-    nickname: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-#[allow(clippy::struct_field_names)]
-#[serde(rename_all = "camelCase")]
-struct DiscordReference {
-    message_id: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DiscordAttachment {
-    file_name: String,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DiscordSticker {
-    name: String,
-}
-
 impl DiscordParser {
     /// Parses content from file path (internal implementation).
-    fn parse_file_internal(&self, file_path: &str) -> Result<Vec<Message>, ChatpackError> {
+    ///
+    /// Returns the messages and the number of invalid records skipped or
+    /// flagged by the timestamp check.
+    fn parse_file_internal(&self, file_path: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        check_file_size(file_path, self.config.max_file_size)?;
+
         // Try to detect format from extension first
         if let Some(format) = Self::detect_format_from_ext(file_path) {
             return match format {
-                DiscordFormat::Csv => self.parse_csv_file(file_path),
+                DiscordFormat::Csv => {
+                    let content = read_text_file(file_path)?;
+                    self.parse_csv_export(&content)
+                }
                 DiscordFormat::Json => {
-                    let content = fs::read_to_string(file_path)?;
-                    self.parse_json(&content)
+                    let content = read_text_file(file_path)?;
+                    self.parse_json_export(strip_bom(&content))
+                }
+                DiscordFormat::Jsonl => {
+                    let content = read_text_file(file_path)?;
+                    self.parse_jsonl(strip_bom(&content))
                 }
                 DiscordFormat::Txt => {
-                    let content = fs::read_to_string(file_path)?;
-                    self.parse_txt(&content)
+                    let content = read_text_file(file_path)?;
+                    self.parse_txt_export(&content)
                 }
             };
         }
 
         // Fallback: read content and detect from it
-        let content = fs::read_to_string(file_path)?;
+        let content = read_text_file(file_path)?;
         self.parse_content(&content)
     }
 
     /// Parses content from a string (internal implementation).
-    fn parse_content(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
+    ///
+    /// Returns the messages and the number of invalid records skipped or
+    /// flagged by the timestamp check.
+    fn parse_content(&self, content: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let content = strip_bom(content);
         let format = Self::detect_format_from_content(content);
 
         match format {
-            DiscordFormat::Json => self.parse_json(content),
-            DiscordFormat::Txt => self.parse_txt(content),
-            DiscordFormat::Csv => self.parse_csv_str(content),
+            DiscordFormat::Json => self.parse_json_export(content),
+            DiscordFormat::Jsonl => self.parse_jsonl(content),
+            DiscordFormat::Txt => self.parse_txt_export(content),
+            DiscordFormat::Csv => self.parse_csv_export(content),
+        }
+    }
+
+    /// Parses a JSON export, keeping the messages before the cut of a
+    /// truncated one with `tolerate_truncation`.
+    ///
+    /// Returns the messages and the number flagged by the timestamp check,
+    /// counting a record cut off by truncation.
+    fn parse_json_export(&self, content: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let (messages, truncated) =
+            decode_tolerating_truncation(content, self.config.tolerate_truncation, |content| {
+                self.parse_json(content)
+            })?;
+        let (messages, flagged) = self.checked(messages);
+        Ok((messages, flagged + usize::from(truncated)))
+    }
+
+    /// Parses a TXT export.
+    ///
+    /// Returns the messages and the number of over-long lines skipped and
+    /// messages flagged by the timestamp check.
+    #[cfg(feature = "discord-txt")]
+    fn parse_txt_export(&self, content: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let (messages, skipped) = txt::parse_txt(content, &self.config)?;
+        let (messages, flagged) = self.checked_txt(messages);
+        Ok((messages, skipped + flagged))
+    }
+
+    #[cfg(not(feature = "discord-txt"))]
+    #[allow(clippy::unused_self)]
+    fn parse_txt_export(&self, _content: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        Err(ChatpackError::unsupported_input_format(
+            "Discord TXT",
+            "discord-txt",
+        ))
+    }
+
+    /// Parses a CSV export.
+    ///
+    /// Returns the messages and the number with an unparseable date or
+    /// flagged by the timestamp check.
+    #[cfg(feature = "discord-csv")]
+    fn parse_csv_export(&self, content: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let (messages, unparsed) = csv::parse_csv(content.as_bytes(), &self.config)?;
+        let (messages, flagged) = self.checked(messages);
+        Ok((messages, unparsed + flagged))
+    }
+
+    #[cfg(not(feature = "discord-csv"))]
+    #[allow(clippy::unused_self)]
+    fn parse_csv_export(&self, _content: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        Err(ChatpackError::unsupported_input_format(
+            "Discord CSV",
+            "discord-csv",
+        ))
+    }
+
+    /// Applies the timestamp check to a whole export, returning the
+    /// messages kept and the number flagged.
+    fn checked(&self, mut messages: Vec<Message>) -> (Vec<Message>, usize) {
+        let flagged = check_messages(&mut messages, self.config.clamp_timestamps.as_ref());
+        (messages, flagged)
+    }
+
+    /// Applies the timestamp check to a TXT export, whose `reply_to` values
+    /// are positions in the export: replies to a dropped message lose their
+    /// `reply_to`, and the rest follow their target to its new position.
+    #[cfg(feature = "discord-txt")]
+    fn checked_txt(&self, mut messages: Vec<Message>) -> (Vec<Message>, usize) {
+        if self.config.clamp_timestamps.is_none() {
+            return (messages, 0);
+        }
+        assign_source_indices(&mut messages, Platform::Discord);
+        let (mut messages, flagged) = self.checked(messages);
+        if flagged > 0 {
+            let kept: HashMap<u64, u64> = messages
+                .iter()
+                .zip(0u64..)
+                .filter_map(|(msg, position)| Some((msg.source_index?, position)))
+                .collect();
+            for msg in &mut messages {
+                msg.reply_to = msg.reply_to.and_then(|target| kept.get(&target).copied());
+            }
+        }
+        (messages, flagged)
+    }
+}
+
+/// Feeds JSONL lines to a sink, reusing one set of buffers for every line.
+struct JsonlVisitor {
+    naming: SenderNaming,
+    collect_attachments: bool,
+    skip_invalid: bool,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    senders: Option<Vec<String>>,
+    include_events: bool,
+    policy: ContentPolicy,
+    scratch: DiscordScratch,
+    content: String,
+    line: usize,
+    next_index: u64,
+    tolerate_truncation: bool,
+    decoded: bool,
+    /// A line that ended early, held until we know whether it is the last
+    cut: Option<(usize, serde_json::Error)>,
+}
+
+impl JsonlVisitor {
+    fn new(config: &DiscordConfig) -> Self {
+        Self {
+            naming: config.into(),
+            collect_attachments: config.collect_attachment_refs,
+            skip_invalid: config.skip_invalid,
+            clamp_timestamps: config.clamp_timestamps.clone(),
+            capture_raw: config.capture_raw,
+            senders: config.sender_allowlist.clone(),
+            include_events: config.include_system_events,
+            policy: config.content,
+            scratch: DiscordScratch::default(),
+            content: String::new(),
+            line: 0,
+            next_index: 0,
+            tolerate_truncation: config.tolerate_truncation,
+            decoded: false,
+            cut: None,
+        }
+    }
+
+    fn push_line(
+        &mut self,
+        line: &str,
+        sink: &mut dyn MessageSink,
+    ) -> Result<ControlFlow<()>, ChatpackError> {
+        self.line += 1;
+        let record = read_discord_jsonl_record(line);
+        if !matches!(record, Ok(None)) {
+            // More records follow, so the held line was not cut off
+            if let Some((line, e)) = self.cut.take() {
+                self.invalid(line, &e, sink)?;
+            }
+        }
+        match record {
+            Ok(Some(record)) => {
+                self.decoded = true;
+                if record.is_system_event() && !self.include_events {
+                    sink.on_skip(&SkipReason::SystemMessage);
+                    return Ok(ControlFlow::Continue(()));
+                }
+                let built = record.message_ref(
+                    self.naming,
+                    self.collect_attachments,
+                    self.senders.as_deref(),
+                    self.include_events,
+                    &mut self.scratch,
+                );
+                if let Some(mut msg) = built {
+                    if !keep_timestamp(&mut msg.timestamp, self.clamp_timestamps.as_ref(), sink) {
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    msg.content = normalize_content(msg.content, self.policy, &mut self.content);
+                    msg.source_index = Some(self.next_index);
+                    msg.raw = self.capture_raw.then(|| line.trim());
+                    self.next_index += 1;
+                    return Ok(sink.on_message(msg));
+                }
+            }
+            Ok(None) => {}
+            Err(e) if self.tolerate_truncation && self.decoded && e.is_eof() => {
+                self.cut = Some((self.line, e));
+            }
+            Err(e) => self.invalid(self.line, &e, sink)?,
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Skips or rejects an invalid line, as `skip_invalid` says.
+    fn invalid(
+        &self,
+        line: usize,
+        e: &serde_json::Error,
+        sink: &mut dyn MessageSink,
+    ) -> Result<(), ChatpackError> {
+        if !self.skip_invalid {
+            return Err(ChatpackError::invalid_format(
+                "Discord JSONL",
+                format!("line {line}: {e}"),
+            ));
+        }
+        sink.on_skip(&SkipReason::InvalidRecord {
+            line: Some(line),
+            error: e.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Reports a last line cut off by the end of the file.
+    fn finish(&mut self, sink: &mut dyn MessageSink) {
+        if let Some((line, e)) = self.cut.take() {
+            sink.on_skip(&SkipReason::Truncated {
+                line: Some(line),
+                error: e.to_string(),
+            });
         }
     }
 }
@@ -483,11 +486,40 @@ impl Parser for DiscordParser {
     }
 
     fn parse(&self, path: &Path) -> Result<Vec<Message>, ChatpackError> {
-        self.parse_file_internal(path.to_str().unwrap_or_default())
+        Ok(self.parse_counted(path)?.0)
     }
 
     fn parse_str(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        self.parse_content(content)
+        let (mut messages, _) = self.parse_content(content)?;
+        assign_source_indices(&mut messages, Platform::Discord);
+        Ok(messages)
+    }
+
+    fn parse_counted(&self, path: &Path) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let (mut messages, skipped) =
+            self.parse_file_internal(path.to_str().unwrap_or_default())?;
+        assign_source_indices(&mut messages, Platform::Discord);
+        Ok((messages, skipped))
+    }
+
+    /// JSONL files are read line by line; other formats are parsed first
+    /// and then fed to the sink. Without a known extension, the format is
+    /// detected from the first line.
+    fn parse_into(&self, path: &Path, sink: &mut dyn MessageSink) -> Result<(), ChatpackError> {
+        if !matches!(
+            self.detect_format_from_file(path)?,
+            Some(DiscordFormat::Jsonl)
+        ) {
+            return stream_into(self, path, sink);
+        }
+
+        let mut reader = open_text_reader(File::open(path)?, self.config.buffer_size)?;
+        let mut visitor = JsonlVisitor::new(&self.config);
+        let flow = for_each_line(reader.as_mut(), |line| visitor.push_line(line, sink))?;
+        if flow.is_continue() {
+            visitor.finish(sink);
+        }
+        Ok(())
     }
 
     #[cfg(feature = "streaming")]
@@ -496,24 +528,30 @@ impl Parser for DiscordParser {
         path: &Path,
     ) -> Result<Box<dyn Iterator<Item = Result<Message, ChatpackError>> + Send>, ChatpackError>
     {
-        if self.config.streaming {
-            // Use native streaming parser
-            let streaming_config = StreamingConfig::new()
-                .with_buffer_size(self.config.buffer_size)
-                .with_max_message_size(self.config.max_message_size)
-                .with_skip_invalid(self.config.skip_invalid);
-
-            let streaming_parser = DiscordStreamingParser::with_config(streaming_config);
+        Ok(Box::new(self.stream_with_progress(path)?))
+    }
+
+    #[cfg(feature = "streaming")]
+    fn stream_with_progress(&self, path: &Path) -> Result<ParseIterator, ChatpackError> {
+        // Files above max_file_size are streamed even when streaming is off,
+        // since parse() would refuse them.
+        // Only JSON and JSONL exports have a native streaming parser
+        let streams = self.config.streaming || exceeds_file_size(path, self.config.max_file_size);
+        if streams
+            && matches!(
+                self.detect_format_from_file(path)?,
+                Some(DiscordFormat::Json | DiscordFormat::Jsonl)
+            )
+        {
+            let streaming_parser = DiscordStreamingParser::from_discord_config(&self.config);
             let iterator =
                 StreamingParser::stream(&streaming_parser, path.to_str().unwrap_or_default())?;
 
-            Ok(Box::new(
-                iterator.map(|result| result.map_err(ChatpackError::from)),
-            ))
+            Ok(ParseIterator::new(iterator))
         } else {
             // Fallback: load everything into memory
             let messages = Parser::parse(self, path)?;
-            Ok(Box::new(messages.into_iter().map(Ok)))
+            Ok(ParseIterator::loaded(messages, path))
         }
     }
 
@@ -526,11 +564,24 @@ impl Parser for DiscordParser {
     fn recommended_buffer_size(&self) -> usize {
         self.config.buffer_size
     }
+
+    fn config_schema(&self) -> Vec<ConfigField> {
+        DiscordConfig::schema()
+    }
+
+    fn config_json(&self) -> serde_json::Value {
+        config_to_json(&self.config)
+    }
+
+    fn set_config_json(&mut self, config: serde_json::Value) -> Result<(), ChatpackError> {
+        update_config_json(&mut self.config, config)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::TimestampAction;
 
     // =========================================================================
     // DiscordParser construction tests
@@ -590,6 +641,10 @@ mod tests {
             DiscordParser::detect_format_from_ext("test.txt"),
             Some(DiscordFormat::Txt)
         ));
+        assert!(matches!(
+            DiscordParser::detect_format_from_ext("test.jsonl"),
+            Some(DiscordFormat::Jsonl)
+        ));
 
         // Case insensitive
         assert!(matches!(
@@ -616,6 +671,16 @@ mod tests {
             DiscordParser::detect_format_from_content(r#"{"messages":[]}"#),
             DiscordFormat::Json
         ));
+        assert!(matches!(
+            DiscordParser::detect_format_from_content("{\n  \"guild\": {}\n}"),
+            DiscordFormat::Json
+        ));
+        assert!(matches!(
+            DiscordParser::detect_format_from_content(
+                r#"{"id":"1","timestamp":"2024-01-01T00:00:00Z","content":"hi","author":{"name":"bob"}}"#
+            ),
+            DiscordFormat::Jsonl
+        ));
         assert!(matches!(
             DiscordParser::detect_format_from_content("AuthorID,Author,Date"),
             DiscordFormat::Csv
@@ -794,108 +859,57 @@ mod tests {
     // TXT parsing tests
     // =========================================================================
 
+    #[cfg(feature = "discord-txt")]
     #[test]
-    fn test_txt_timestamp_parsing() {
-        let ts = DiscordParser::parse_txt_timestamp("1/15/2024 10:30 AM");
-        assert!(ts.is_some());
-
-        let ts = DiscordParser::parse_txt_timestamp("12/31/2024 11:59 PM");
-        assert!(ts.is_some());
-
-        let ts = DiscordParser::parse_txt_timestamp("1/1/2024 1:00 AM");
-        assert!(ts.is_some());
-
-        // 24-hour format
-        let ts = DiscordParser::parse_txt_timestamp("1/15/2024 14:30");
-        assert!(ts.is_some());
-    }
-
-    #[test]
-    fn test_parse_txt_basic() {
-        let parser = DiscordParser::new();
-        let txt = "[1/15/2024 10:30 AM] alice\nHello world\n[1/15/2024 10:31 AM] bob\nHi there";
-
-        let messages = parser.parse_txt(txt).unwrap();
-        assert_eq!(messages.len(), 2);
-        assert_eq!(messages[0].sender, "alice");
-        assert_eq!(messages[0].content, "Hello world");
-        assert_eq!(messages[1].sender, "bob");
-        assert_eq!(messages[1].content, "Hi there");
-    }
-
-    #[test]
-    fn test_parse_txt_multiline() {
-        let parser = DiscordParser::new();
-        let txt = "[1/15/2024 10:30 AM] alice\nLine 1\nLine 2\nLine 3";
-
-        let messages = parser.parse_txt(txt).unwrap();
-        assert_eq!(messages.len(), 1);
-        assert_eq!(messages[0].content, "Line 1\nLine 2\nLine 3");
-    }
-
-    #[test]
-    fn test_parse_txt_with_attachments() {
-        let parser = DiscordParser::new();
-        let txt =
-            "[1/15/2024 10:30 AM] alice\nMessage\n{Attachments}\nhttps://cdn.discord.com/image.png";
-
-        let messages = parser.parse_txt(txt).unwrap();
-        assert_eq!(messages.len(), 1);
-        assert!(messages[0].content.contains("Message"));
-        assert!(messages[0].content.contains("[Attachment: image.png]"));
-    }
-
-    #[test]
-    fn test_parse_txt_with_stickers() {
-        let parser = DiscordParser::new();
-        let txt = "[1/15/2024 10:30 AM] alice\n{Stickers}\ncool_sticker";
-
-        let messages = parser.parse_txt(txt).unwrap();
-        assert_eq!(messages.len(), 1);
-        assert!(messages[0].content.contains("[Sticker: cool_sticker]"));
-    }
-
-    #[test]
-    fn test_parse_txt_empty() {
-        let parser = DiscordParser::new();
-        let txt = "";
-        let messages = parser.parse_txt(txt).unwrap();
-        assert!(messages.is_empty());
+    fn test_parse_txt_reply_to_dropped_message() {
+        let txt = "[1/15/2024 10:30 AM] alice\nFirst\n\
+                   [1/15/1024 10:31 AM] bob\nSecond\n\
+                   [1/15/2024 10:32 AM] carol\n> Replying to alice: First\nThird\n\
+                   [1/15/2024 10:33 AM] dave\n> Replying to bob: Second\nFourth";
+        let config =
+            DiscordConfig::new().with_clamp_timestamps(TimestampCheck::new(TimestampAction::Drop));
+        let (messages, flagged) = DiscordParser::with_config(config)
+            .parse_content(txt)
+            .unwrap();
+        assert_eq!(flagged, 1);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].reply_to, Some(0));
+        assert_eq!(messages[2].reply_to, None);
     }
 
     // =========================================================================
-    // CSV parsing tests
+    // JSONL parsing tests
     // =========================================================================
 
-    #[test]
-    fn test_parse_csv_basic() {
-        let parser = DiscordParser::new();
-        let csv = "AuthorID,Author,Date,Content,Attachments,Reactions\n123,alice,2024-01-15T10:30:00+00:00,Hello world,,";
+    const JSONL: &str = r#"{"id":"1","timestamp":"2024-01-15T10:30:00+00:00","content":"Hello","author":{"name":"alice","nickname":"Alice"}}
+{"id":"2","timestamp":"2024-01-15T10:31:00+00:00","content":"","author":{"name":"bob"},"attachments":[{"fileName":"cat.png"}],"reference":{"messageId":"1"}}
 
-        let messages = parser.parse_csv_str(csv).unwrap();
-        assert_eq!(messages.len(), 1);
-        assert_eq!(messages[0].sender, "alice");
-        assert_eq!(messages[0].content, "Hello world");
-    }
+{"id":"3","timestamp":"2024-01-15T10:32:00+00:00","content":"","author":{"name":"bob"}}"#;
 
     #[test]
-    fn test_parse_csv_with_attachments() {
+    fn test_parse_jsonl_str() {
         let parser = DiscordParser::new();
-        let csv = "AuthorID,Author,Date,Content,Attachments,Reactions\n123,alice,2024-01-15T10:30:00+00:00,Check this,https://cdn.discord.com/image.png,";
+        let messages = parser.parse_str(JSONL).unwrap();
 
-        let messages = parser.parse_csv_str(csv).unwrap();
-        assert_eq!(messages.len(), 1);
-        assert!(messages[0].content.contains("Check this"));
-        assert!(messages[0].content.contains("[Attachment: image.png]"));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[1].content, "[Attachment: cat.png]");
+        assert_eq!(messages[1].reply_to, Some(1));
     }
 
     #[test]
-    fn test_parse_csv_skips_empty() {
-        let parser = DiscordParser::new();
-        let csv = "AuthorID,Author,Date,Content,Attachments,Reactions\n123,alice,2024-01-15T10:30:00+00:00,Hello,,\n124,bob,2024-01-15T10:31:00+00:00,,,";
+    fn test_parse_jsonl_invalid_line() {
+        let content = format!("{JSONL}\n{{broken");
 
-        let messages = parser.parse_csv_str(csv).unwrap();
-        assert_eq!(messages.len(), 1);
+        let lenient = DiscordParser::new();
+        let (messages, skipped) = lenient.parse_content(&content).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(skipped, 1);
+
+        let strict = DiscordParser::with_config(DiscordConfig::new().with_skip_invalid(false));
+        let err = strict.parse_str(&content).unwrap_err();
+        assert!(err.is_invalid_format());
+        assert!(err.to_string().contains("line 5"));
     }
 
     // =========================================================================
@@ -912,6 +926,7 @@ mod tests {
         assert_eq!(messages[0].sender, "bob");
     }
 
+    #[cfg(feature = "discord-csv")]
     #[test]
     fn test_parse_str_csv() {
         let parser = DiscordParser::new();
@@ -923,6 +938,7 @@ mod tests {
         assert_eq!(messages[0].sender, "alice");
     }
 
+    #[cfg(feature = "discord-txt")]
     #[test]
     fn test_parse_str_txt() {
         let parser = DiscordParser::new();
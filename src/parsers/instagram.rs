@@ -3,14 +3,28 @@
 //! Parses JSON exports from Meta's "Download Your Data" feature with
 //! automatic Mojibake encoding fix.
 
-use std::fs;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 
 use crate::Message;
-use crate::config::InstagramConfig;
+use crate::config::{
+    ConfigField, ConfigSchema, InstagramConfig, config_to_json, update_config_json,
+};
 use crate::error::ChatpackError;
+use crate::message::assign_source_indices;
 use crate::parser::{Parser, Platform};
-use crate::parsing::instagram::{InstagramExport, parse_instagram_message_owned};
+use crate::parsing::content::normalize_messages;
+#[cfg(feature = "streaming")]
+use crate::parsing::encoding::exceeds_file_size;
+use crate::parsing::encoding::{read_text_file_with_limit, strip_bom};
+use crate::parsing::instagram::{
+    InstagramExport, InstagramParticipants, InstagramRawMessage, canonicalize_instagram_senders,
+    instagram_sender_allowed, mark_instagram_call, parse_instagram_message_owned,
+    parse_instagram_message_with_attachments,
+};
+use crate::parsing::json_scan::decode_tolerating_truncation;
+use crate::parsing::raw::decode_with_raw;
+use crate::parsing::timestamps::check_messages;
 
 #[cfg(feature = "streaming")]
 use crate::streaming::{InstagramStreamingParser, StreamingConfig, StreamingParser};
@@ -63,6 +77,24 @@ pub struct InstagramParser {
     config: InstagramConfig,
 }
 
+/// What [`InstagramParser::parse_with_report`] found besides the messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstagramParseReport {
+    /// Records skipped as invalid or flagged by the timestamp check, as
+    /// counted by `Parser::parse_counted`.
+    pub flagged: usize,
+
+    /// Old names mapped to a current participant by
+    /// [`InstagramConfig::canonicalize_participants`], old to current.
+    pub renamed: BTreeMap<String, String>,
+
+    /// Senders missing from the export's `participants` list and not
+    /// renamed, such as old usernames in a group thread. Map them with a
+    /// [`SenderMap`](crate::core::processor::SenderMap). Empty if the
+    /// export lists no participants.
+    pub unknown_senders: BTreeSet<String>,
+}
+
 impl InstagramParser {
     /// Creates a new parser with default configuration.
     pub fn new() -> Self {
@@ -88,22 +120,131 @@ impl InstagramParser {
         &self.config
     }
 
-    /// Parses content from a string (internal implementation).
-    fn parse_content(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        let export: InstagramExport = serde_json::from_str(content)?;
+    /// Parses the export at `path`, also reporting senders missing from
+    /// its participant list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chatpack::config::InstagramConfig;
+    /// use chatpack::parsers::InstagramParser;
+    ///
+    /// # fn main() -> chatpack::Result<()> {
+    /// let export = r#"{
+    ///   "participants": [{"name": "alice"}, {"name": "bob"}, {"name": "carol"}],
+    ///   "messages": [
+    ///     {"sender_name": "bob", "timestamp_ms": 1705315860000, "content": "Hi"},
+    ///     {"sender_name": "bobby", "timestamp_ms": 1705315800000, "content": "Hey"}
+    ///   ]
+    /// }"#;
+    /// let parser = InstagramParser::with_config(
+    ///     InstagramConfig::new().with_canonicalize_participants(true),
+    /// );
+    /// let (messages, report) = parser.parse_str_with_report(export)?;
+    ///
+    /// // A group thread: the old name is reported, not guessed
+    /// assert_eq!(messages[0].sender, "bobby");
+    /// assert!(report.unknown_senders.contains("bobby"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn parse_with_report(
+        &self,
+        path: &Path,
+    ) -> Result<(Vec<Message>, InstagramParseReport), ChatpackError> {
+        let content = read_text_file_with_limit(path, self.config.max_file_size)?;
+        self.parse_content(&content, true)
+    }
+
+    /// Parses an export from a string, also reporting senders missing
+    /// from its participant list. See [`parse_with_report`](Self::parse_with_report).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content cannot be parsed.
+    pub fn parse_str_with_report(
+        &self,
+        content: &str,
+    ) -> Result<(Vec<Message>, InstagramParseReport), ChatpackError> {
+        self.parse_content(content, true)
+    }
 
+    /// Parses content from a string (internal implementation).
+    ///
+    /// Returns the messages and a report whose `flagged` counts messages
+    /// flagged by the timestamp check and a record cut off by a truncated
+    /// export. Participants are only read with `report` or
+    /// `canonicalize_participants`.
+    fn parse_content(
+        &self,
+        content: &str,
+        report: bool,
+    ) -> Result<(Vec<Message>, InstagramParseReport), ChatpackError> {
+        let content = strip_bom(content);
         let fix = self.config.fix_encoding;
-        // Use into_iter() with owned version to avoid allocations
-        let mut messages: Vec<Message> = export
-            .messages
-            .into_iter()
-            .filter_map(|msg| parse_instagram_message_owned(msg, fix))
-            .collect();
+        let collect = if self.config.collect_attachment_refs {
+            parse_instagram_message_with_attachments
+        } else {
+            parse_instagram_message_owned
+        };
+        let senders = self.config.sender_allowlist.as_deref();
+        let parse = |mut msg: InstagramRawMessage, fix| {
+            if !instagram_sender_allowed(&msg, fix, senders) {
+                return None;
+            }
+            if self.config.include_calls {
+                mark_instagram_call(&mut msg);
+            }
+            collect(msg, fix)
+        };
+
+        let decode = |content: &str| -> Result<Vec<Message>, ChatpackError> {
+            if self.config.capture_raw {
+                return Ok(decode_with_raw::<InstagramRawMessage>(content, false)?
+                    .0
+                    .into_iter()
+                    .filter_map(|(msg, raw)| parse(msg, fix).map(|m| m.with_raw(raw)))
+                    .collect());
+            }
+            let export: InstagramExport = serde_json::from_str(content)?;
+
+            // Use into_iter() with owned version to avoid allocations
+            Ok(export
+                .messages
+                .into_iter()
+                .filter_map(|msg| parse(msg, fix))
+                .collect())
+        };
+        let (mut messages, truncated) =
+            decode_tolerating_truncation(content, self.config.tolerate_truncation, decode)?;
+        let flagged = check_messages(&mut messages, self.config.clamp_timestamps.as_ref())
+            + usize::from(truncated);
+        normalize_messages(&mut messages, self.config.content);
 
         // Instagram stores messages newest-first, reverse for chronological order
         messages.reverse();
+        assign_source_indices(&mut messages, Platform::Instagram);
 
-        Ok(messages)
+        let rename = self.config.canonicalize_participants;
+        let (renamed, unknown_senders) = if report || rename {
+            let participants = InstagramParticipants::read(content).names(fix);
+            canonicalize_instagram_senders(&mut messages, &participants, rename)
+        } else {
+            Default::default()
+        };
+
+        Ok((
+            messages,
+            InstagramParseReport {
+                flagged,
+                renamed,
+                unknown_senders,
+            },
+        ))
     }
 }
 
@@ -124,12 +265,17 @@ impl Parser for InstagramParser {
     }
 
     fn parse(&self, path: &Path) -> Result<Vec<Message>, ChatpackError> {
-        let content = fs::read_to_string(path)?;
-        self.parse_content(&content)
+        Ok(self.parse_counted(path)?.0)
     }
 
     fn parse_str(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        self.parse_content(content)
+        Ok(self.parse_content(content, false)?.0)
+    }
+
+    fn parse_counted(&self, path: &Path) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let content = read_text_file_with_limit(path, self.config.max_file_size)?;
+        let (messages, report) = self.parse_content(&content, false)?;
+        Ok((messages, report.flagged))
     }
 
     #[cfg(feature = "streaming")]
@@ -138,14 +284,22 @@ impl Parser for InstagramParser {
         path: &Path,
     ) -> Result<Box<dyn Iterator<Item = Result<Message, ChatpackError>> + Send>, ChatpackError>
     {
-        if self.config.streaming {
+        // Files above max_file_size are streamed even when streaming is off,
+        // since parse() would refuse them.
+        if self.config.streaming || exceeds_file_size(path, self.config.max_file_size) {
             // Use native streaming parser
             let streaming_config = StreamingConfig::new()
                 .with_buffer_size(self.config.buffer_size)
                 .with_max_message_size(self.config.max_message_size)
-                .with_skip_invalid(self.config.skip_invalid);
-
-            let streaming_parser = InstagramStreamingParser::with_config(streaming_config);
+                .with_skip_invalid(self.config.skip_invalid)
+                .with_tolerate_truncation(self.config.tolerate_truncation);
+
+            let streaming_parser = InstagramStreamingParser::with_config(streaming_config)
+                .with_clamp_timestamps(self.config.clamp_timestamps.clone())
+                .with_capture_raw(self.config.capture_raw)
+                .with_include_calls(self.config.include_calls)
+                .with_sender_allowlist(self.config.sender_allowlist.clone())
+                .with_content_policy(self.config.content);
             let iterator =
                 StreamingParser::stream(&streaming_parser, path.to_str().unwrap_or_default())?;
 
@@ -155,6 +309,7 @@ impl Parser for InstagramParser {
                 .map(|result| result.map_err(ChatpackError::from))
                 .collect::<Result<Vec<_>, _>>()?;
             messages.reverse();
+            assign_source_indices(&mut messages, Platform::Instagram);
 
             Ok(Box::new(messages.into_iter().map(Ok)))
         } else {
@@ -173,6 +328,18 @@ impl Parser for InstagramParser {
     fn recommended_buffer_size(&self) -> usize {
         self.config.buffer_size
     }
+
+    fn config_schema(&self) -> Vec<ConfigField> {
+        InstagramConfig::schema()
+    }
+
+    fn config_json(&self) -> serde_json::Value {
+        config_to_json(&self.config)
+    }
+
+    fn set_config_json(&mut self, config: serde_json::Value) -> Result<(), ChatpackError> {
+        update_config_json(&mut self.config, config)
+    }
 }
 
 #[cfg(test)]
@@ -332,6 +499,89 @@ mod tests {
         assert_eq!(messages.len(), 1);
     }
 
+    // =========================================================================
+    // Participant canonicalization tests
+    // =========================================================================
+
+    /// bob wrote as "bob.old" before renaming himself; newest first.
+    const RENAMED_DM: &str = r#"{
+        "participants": [{"name": "alice"}, {"name": "bob"}],
+        "messages": [
+            {"sender_name": "alice", "content": "Nice name", "timestamp_ms": 1705316100000},
+            {"sender_name": "bob", "content": "Renamed!", "timestamp_ms": 1705316040000},
+            {"sender_name": "bob.old", "content": "Brb", "timestamp_ms": 1705315980000},
+            {"sender_name": "alice", "content": "How are you?", "timestamp_ms": 1705315920000},
+            {"sender_name": "bob.old", "content": "Hey", "timestamp_ms": 1705315860000},
+            {"sender_name": "alice", "content": "Hi", "timestamp_ms": 1705315800000}
+        ]
+    }"#;
+
+    #[test]
+    fn test_canonicalize_participants_off_by_default() {
+        let parser = InstagramParser::new();
+        let messages = parser.parse_str(RENAMED_DM).unwrap();
+        assert_eq!(messages[1].sender, "bob.old");
+
+        let (_, report) = parser.parse_str_with_report(RENAMED_DM).unwrap();
+        assert!(report.renamed.is_empty());
+        assert!(report.unknown_senders.contains("bob.old"));
+    }
+
+    #[test]
+    fn test_canonicalize_participants_two_person_thread() {
+        let config = InstagramConfig::new().with_canonicalize_participants(true);
+        let parser = InstagramParser::with_config(config);
+
+        let (messages, report) = parser.parse_str_with_report(RENAMED_DM).unwrap();
+        let senders: Vec<&str> = messages.iter().map(|m| m.sender.as_str()).collect();
+        assert_eq!(senders, ["alice", "bob", "alice", "bob", "bob", "alice"]);
+        assert_eq!(messages[1].original_sender.as_deref(), Some("bob.old"));
+        assert_eq!(report.renamed["bob.old"], "bob");
+        assert!(report.unknown_senders.is_empty());
+
+        // The merge now sees one bob
+        let merged = crate::core::processor::merge_consecutive(messages);
+        assert_eq!(merged.len(), 5);
+        assert_eq!(parser.parse_str(RENAMED_DM).unwrap()[1].sender, "bob");
+    }
+
+    #[test]
+    fn test_canonicalize_participants_reports_group_unknowns() {
+        let json = r#"{
+            "participants": [{"name": "alice"}, {"name": "bob"}, {"name": "carol"}],
+            "messages": [
+                {"sender_name": "carol", "content": "Welcome", "timestamp_ms": 1705315980000},
+                {"sender_name": "bob", "content": "Renamed!", "timestamp_ms": 1705315920000},
+                {"sender_name": "bob.old", "content": "Hey", "timestamp_ms": 1705315860000},
+                {"sender_name": "alice", "content": "Hi", "timestamp_ms": 1705315800000}
+            ]
+        }"#;
+        let config = InstagramConfig::new().with_canonicalize_participants(true);
+        let parser = InstagramParser::with_config(config);
+
+        let (messages, report) = parser.parse_str_with_report(json).unwrap();
+        assert_eq!(messages[1].sender, "bob.old");
+        assert!(report.renamed.is_empty());
+        assert_eq!(
+            report.unknown_senders.into_iter().collect::<Vec<_>>(),
+            ["bob.old"]
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_participants_fixes_participant_encoding() {
+        // "Алиса" as Meta writes it
+        let json = r#"{
+            "participants": [{"name": "\u00d0\u0090\u00d0\u00bb\u00d0\u00b8\u00d1\u0081\u00d0\u00b0"}, {"name": "bob"}],
+            "messages": [
+                {"sender_name": "\u00d0\u0090\u00d0\u00bb\u00d0\u00b8\u00d1\u0081\u00d0\u00b0", "content": "Hi", "timestamp_ms": 1705315800000}
+            ]
+        }"#;
+        let (messages, report) = InstagramParser::new().parse_str_with_report(json).unwrap();
+        assert_eq!(messages[0].sender, "Алиса");
+        assert!(report.unknown_senders.is_empty());
+    }
+
     // =========================================================================
     // Streaming support tests
     // =========================================================================
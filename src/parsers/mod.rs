@@ -65,7 +65,7 @@
 //! # fn main() {}
 //! ```
 
-#[cfg(feature = "discord")]
+#[cfg(feature = "discord-json")]
 mod discord;
 #[cfg(feature = "instagram")]
 mod instagram;
@@ -74,10 +74,10 @@ mod telegram;
 #[cfg(feature = "whatsapp")]
 mod whatsapp;
 
-#[cfg(feature = "discord")]
+#[cfg(feature = "discord-json")]
 pub use discord::DiscordParser;
 #[cfg(feature = "instagram")]
-pub use instagram::InstagramParser;
+pub use instagram::{InstagramParseReport, InstagramParser};
 #[cfg(feature = "telegram")]
 pub use telegram::TelegramParser;
 #[cfg(feature = "whatsapp")]
@@ -2,15 +2,30 @@
 //!
 //! Parses JSON exports from Telegram Desktop's "Export chat history" feature.
 
-use std::fs;
 use std::path::Path;
 
+use serde::Deserialize;
+
 use crate::Message;
-use crate::config::TelegramConfig;
+use crate::config::{
+    ConfigField, ConfigSchema, TelegramConfig, config_to_json, update_config_json,
+};
 use crate::error::ChatpackError;
+use crate::message::assign_source_indices;
 use crate::parser::{Parser, Platform};
-use crate::parsing::telegram::{TelegramExport, parse_telegram_message};
+use crate::parsing::content::normalize_messages;
+use crate::parsing::encoding::{read_text_file, read_text_file_with_limit, strip_bom};
+use crate::parsing::json_scan::decode_tolerating_truncation;
+use crate::parsing::raw::decode_with_raw;
+use crate::parsing::telegram::{
+    ChunkOverlap, TelegramExport, TelegramRawMessage, TopicTracker, check_export_size, message_len,
+    telegram_export_chunks,
+};
+use crate::parsing::timestamps::check_messages;
+use crate::sink::{MessageRef, MessageSink, SkipReason};
 
+#[cfg(feature = "streaming")]
+use crate::parser::{ParseIterator, feed_into};
 #[cfg(feature = "streaming")]
 use crate::streaming::{StreamingConfig, StreamingParser, TelegramStreamingParser};
 
@@ -44,6 +59,15 @@ use crate::streaming::{StreamingConfig, StreamingParser, TelegramStreamingParser
 /// }
 /// ```
 ///
+/// # Split Exports
+///
+/// Very large exports are split into `result.json`, `result(1).json`, …
+/// Given their folder or `result.json`, the parser reads every chunk in
+/// order (see [`telegram_export_chunks`]) and drops the messages a chunk
+/// repeats from the end of the previous one. Streaming opens each chunk
+/// only when the previous one is done. The size limit applies to the
+/// chunks together.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -89,20 +113,160 @@ impl TelegramParser {
         &self.config
     }
 
+    /// Parses every chunk of the export at `path`.
+    ///
+    /// Forum topics are followed across chunks, and
+    /// [`max_file_size`](TelegramConfig::max_file_size) applies to the
+    /// chunks together.
+    fn parse_path(&self, path: &Path) -> Result<Decoded, ChatpackError> {
+        let chunks = telegram_export_chunks(path)?;
+        let mut topics = TopicTracker::default();
+        if let [chunk] = chunks.as_slice() {
+            let content = read_text_file_with_limit(chunk, self.config.max_file_size)?;
+            return self.parse_content(&content, &mut topics);
+        }
+
+        check_export_size(&chunks, self.config.max_file_size)?;
+        let (mut all, mut overlap) = (Decoded::default(), ChunkOverlap::default());
+        for chunk in &chunks {
+            let content = read_text_file(chunk)?;
+            let decoded = self.parse_content(&content, &mut topics)?;
+            all.messages
+                .extend(decoded.messages.into_iter().filter(|m| overlap.keep(m)));
+            overlap.next_chunk();
+            all.skipped += decoded.skipped;
+            all.service += decoded.service;
+        }
+        assign_source_indices(&mut all.messages, Platform::Telegram);
+        Ok(all)
+    }
+
+    /// Returns `true` if the export at `path`, all chunks together, is
+    /// known to exceed [`max_file_size`](TelegramConfig::max_file_size).
+    ///
+    /// Errors return `false` so that parsing reports them.
+    #[cfg(feature = "streaming")]
+    fn exceeds_max_file_size(&self, path: &Path) -> bool {
+        telegram_export_chunks(path).is_ok_and(|chunks| {
+            check_export_size(&chunks, self.config.max_file_size)
+                .is_err_and(|e| e.is_file_too_large())
+        })
+    }
+
     /// Parses content from a string (internal implementation).
-    #[allow(clippy::unused_self)] // Keep &self for consistency with other parsers and future config use
-    fn parse_content(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        let export: TelegramExport = serde_json::from_str(content)?;
+    ///
+    /// The skipped count includes a record cut off by a truncated export
+    /// and messages flagged by the timestamp check.
+    fn parse_content(
+        &self,
+        content: &str,
+        topics: &mut TopicTracker,
+    ) -> Result<Decoded, ChatpackError> {
+        let content = strip_bom(content);
+
+        let (mut decoded, truncated) =
+            decode_tolerating_truncation(content, self.config.tolerate_truncation, |content| {
+                self.decode_records(content, topics)
+            })?;
+        let flagged = check_messages(&mut decoded.messages, self.config.clamp_timestamps.as_ref());
+        normalize_messages(&mut decoded.messages, self.config.content);
+        assign_source_indices(&mut decoded.messages, Platform::Telegram);
+
+        decoded.skipped += usize::from(truncated) + flagged;
+        Ok(decoded)
+    }
+
+    /// Decodes the messages of a whole export, keeping their source text
+    /// with `capture_raw`.
+    fn decode_records(
+        &self,
+        content: &str,
+        topics: &mut TopicTracker,
+    ) -> Result<Decoded, ChatpackError> {
+        if !self.config.capture_raw {
+            return self.decode(content, topics);
+        }
+        let (records, skipped) =
+            decode_with_raw::<TelegramRawMessage>(content, self.config.skip_invalid)?;
+        let mut decoded = Decoded::with_skipped(skipped);
+        let mut rest = records.as_slice();
+        while let [(record, raw), ..] = rest {
+            let len = message_len(rest, self.config.merge_albums, |(record, _)| record);
+            let album: Vec<&TelegramRawMessage> = rest[..len].iter().map(|(r, _)| r).collect();
+            let msg = topics.convert(&album, &self.config);
+            decoded.push(record, msg.map(|m| m.with_raw(*raw)));
+            rest = &rest[len..];
+        }
+        Ok(decoded)
+    }
+
+    /// Decodes and converts every message, counting the invalid entries
+    /// skipped.
+    fn decode(&self, content: &str, topics: &mut TopicTracker) -> Result<Decoded, ChatpackError> {
+        let (raw, skipped) = match serde_json::from_str::<TelegramExport>(content) {
+            Ok(export) => (export.messages, 0),
+            Err(e) if !self.config.skip_invalid => return Err(e.into()),
+            // Decode entry by entry so one malformed record doesn't fail the export
+            Err(e) => match serde_json::from_str::<LenientExport>(content) {
+                Ok(export) => {
+                    let total = export.messages.len();
+                    let raw: Vec<TelegramRawMessage> = export
+                        .messages
+                        .into_iter()
+                        .filter_map(|value| serde_json::from_value(value).ok())
+                        .collect();
+                    let skipped = total - raw.len();
+                    (raw, skipped)
+                }
+                Err(_) => return Err(e.into()),
+            },
+        };
 
         // Use shared parsing logic
-        let messages = export
-            .messages
-            .iter()
-            .filter_map(parse_telegram_message)
-            .collect();
+        let mut decoded = Decoded::with_skipped(skipped);
+        let mut rest = raw.as_slice();
+        while let [record, ..] = rest {
+            let len = message_len(rest, self.config.merge_albums, |record| record);
+            let album: Vec<&TelegramRawMessage> = rest[..len].iter().collect();
+            decoded.push(record, topics.convert(&album, &self.config));
+            rest = &rest[len..];
+        }
+        Ok(decoded)
+    }
+}
 
-        Ok(messages)
+/// Messages decoded from an export and the records left out of them.
+#[derive(Default)]
+struct Decoded {
+    messages: Vec<Message>,
+    /// Invalid or flagged records
+    skipped: usize,
+    /// Service records that did not become messages
+    service: usize,
+}
+
+impl Decoded {
+    fn with_skipped(skipped: usize) -> Self {
+        Self {
+            skipped,
+            ..Self::default()
+        }
     }
+
+    /// Adds the message converted from `record`, if any.
+    fn push(&mut self, record: &TelegramRawMessage, msg: Option<Message>) {
+        match msg {
+            Some(msg) => self.messages.push(msg),
+            None if record.msg_type != "message" => self.service += 1,
+            None => {}
+        }
+    }
+}
+
+/// Export wrapper that defers decoding of individual messages.
+#[derive(Deserialize)]
+struct LenientExport {
+    messages: Vec<serde_json::Value>,
 }
 
 impl Default for TelegramParser {
@@ -122,12 +286,38 @@ impl Parser for TelegramParser {
     }
 
     fn parse(&self, path: &Path) -> Result<Vec<Message>, ChatpackError> {
-        let content = fs::read_to_string(path)?;
-        self.parse_content(&content)
+        Ok(self.parse_counted(path)?.0)
     }
 
     fn parse_str(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        self.parse_content(content)
+        Ok(self
+            .parse_content(content, &mut TopicTracker::default())?
+            .messages)
+    }
+
+    fn parse_counted(&self, path: &Path) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let decoded = self.parse_path(path)?;
+        Ok((decoded.messages, decoded.skipped))
+    }
+
+    /// Reports the service messages left out: before the messages when the
+    /// export is loaded whole, or as they are passed when it is streamed.
+    fn parse_into(&self, path: &Path, sink: &mut dyn MessageSink) -> Result<(), ChatpackError> {
+        #[cfg(feature = "streaming")]
+        if self.config.streaming || self.exceeds_max_file_size(path) {
+            return feed_into(self.stream_with_progress(path)?, sink);
+        }
+
+        let decoded = self.parse_path(path)?;
+        for _ in 0..decoded.service {
+            sink.on_skip(&SkipReason::SystemMessage);
+        }
+        for msg in &decoded.messages {
+            if sink.on_message(MessageRef::from(msg)).is_break() {
+                break;
+            }
+        }
+        Ok(())
     }
 
     #[cfg(feature = "streaming")]
@@ -136,24 +326,39 @@ impl Parser for TelegramParser {
         path: &Path,
     ) -> Result<Box<dyn Iterator<Item = Result<Message, ChatpackError>> + Send>, ChatpackError>
     {
-        if self.config.streaming {
+        Ok(Box::new(self.stream_with_progress(path)?))
+    }
+
+    #[cfg(feature = "streaming")]
+    fn stream_with_progress(&self, path: &Path) -> Result<ParseIterator, ChatpackError> {
+        // Exports above max_file_size are streamed even when streaming is off,
+        // since parse() would refuse them.
+        if self.config.streaming || self.exceeds_max_file_size(path) {
             // Use native streaming parser
             let streaming_config = StreamingConfig::new()
                 .with_buffer_size(self.config.buffer_size)
                 .with_max_message_size(self.config.max_message_size)
-                .with_skip_invalid(self.config.skip_invalid);
-
-            let streaming_parser = TelegramStreamingParser::with_config(streaming_config);
+                .with_skip_invalid(self.config.skip_invalid)
+                .with_tolerate_truncation(self.config.tolerate_truncation);
+
+            let streaming_parser = TelegramStreamingParser::with_config(streaming_config)
+                .with_assume_timezone(self.config.assume_timezone)
+                .with_mention_format(self.config.mention_format)
+                .with_clamp_timestamps(self.config.clamp_timestamps.clone())
+                .with_capture_raw(self.config.capture_raw)
+                .with_include_calls(self.config.include_calls)
+                .with_sender_allowlist(self.config.sender_allowlist.clone())
+                .with_topic_filter(self.config.topic_filter.clone())
+                .with_merge_albums(self.config.merge_albums)
+                .with_content_policy(self.config.content);
             let iterator =
                 StreamingParser::stream(&streaming_parser, path.to_str().unwrap_or_default())?;
 
-            Ok(Box::new(
-                iterator.map(|result| result.map_err(ChatpackError::from)),
-            ))
+            Ok(ParseIterator::new(iterator))
         } else {
             // Fallback: load everything into memory
             let messages = Parser::parse(self, path)?;
-            Ok(Box::new(messages.into_iter().map(Ok)))
+            Ok(ParseIterator::loaded(messages, path))
         }
     }
 
@@ -166,12 +371,25 @@ impl Parser for TelegramParser {
     fn recommended_buffer_size(&self) -> usize {
         self.config.buffer_size
     }
+
+    fn config_schema(&self) -> Vec<ConfigField> {
+        TelegramConfig::schema()
+    }
+
+    fn config_json(&self) -> serde_json::Value {
+        config_to_json(&self.config)
+    }
+
+    fn set_config_json(&mut self, config: serde_json::Value) -> Result<(), ChatpackError> {
+        update_config_json(&mut self.config, config)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parsing::telegram::extract_telegram_text;
+    use crate::config::MentionFormat;
+    use crate::parsing::telegram::{extract_telegram_text, extract_telegram_text_with};
     use serde_json::json;
 
     // =========================================================================
@@ -226,6 +444,44 @@ mod tests {
         assert_eq!(extract_telegram_text(&value), "");
     }
 
+    #[test]
+    fn test_extract_text_mentions() {
+        let value = json!([
+            "Ping ",
+            {"type": "mention_name", "text": "John", "user_id": 12345},
+            " and ",
+            {"type": "mention", "text": "@alice"},
+            ", call ",
+            {"type": "phone", "text": "+1 555 0100"}
+        ]);
+        assert_eq!(
+            extract_telegram_text(&value),
+            "Ping John and @alice, call +1 555 0100"
+        );
+        assert_eq!(
+            extract_telegram_text_with(&value, MentionFormat::At),
+            "Ping @John and @alice, call +1 555 0100"
+        );
+        assert_eq!(
+            extract_telegram_text_with(&value, MentionFormat::Plain),
+            "Ping John and alice, call +1 555 0100"
+        );
+    }
+
+    #[test]
+    fn test_extract_text_empty_mention_falls_back_to_id() {
+        let value = json!([
+            {"type": "mention_name", "text": "", "user_id": 12345},
+            " joined",
+            {"type": "mention_name", "text": ""}
+        ]);
+        assert_eq!(extract_telegram_text(&value), "@id12345 joined");
+        assert_eq!(
+            extract_telegram_text_with(&value, MentionFormat::Plain),
+            "@id12345 joined"
+        );
+    }
+
     // =========================================================================
     // TelegramParser tests
     // =========================================================================
@@ -350,6 +606,26 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_str_skips_invalid_entries() {
+        let json = r#"{"messages": [
+            {"id": "not-a-number", "type": "message", "from": "Eve", "text": "bad"},
+            {"id": 2, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "Good"},
+            {"from": "Bob", "text": "no type"}
+        ]}"#;
+
+        let parser = TelegramParser::new();
+        let decoded = parser
+            .parse_content(json, &mut TopicTracker::default())
+            .expect("parse failed");
+        assert_eq!(decoded.messages.len(), 1);
+        assert_eq!(decoded.messages[0].content, "Good");
+        assert_eq!(decoded.skipped, 2);
+
+        let strict = TelegramParser::with_config(TelegramConfig::new().with_skip_invalid(false));
+        assert!(strict.parse_str(json).is_err());
+    }
+
     #[test]
     fn test_parse_str_missing_messages() {
         let parser = TelegramParser::new();
@@ -3,21 +3,37 @@
 //! Parses plain text exports from WhatsApp's "Export Chat" feature.
 //! Auto-detects locale-specific date formats.
 
-use std::fs;
+use std::fs::File;
+use std::ops::ControlFlow;
 use std::path::Path;
 
+use chrono::{DateTime, Utc};
 use regex::Regex;
 
 use crate::Message;
-use crate::config::WhatsAppConfig;
+use crate::config::{
+    ConfigField, ConfigSchema, WhatsAppConfig, config_to_json, update_config_json,
+};
 use crate::error::ChatpackError;
 use crate::parser::{Parser, Platform};
+use crate::parsing::content::normalize_content;
+#[cfg(feature = "streaming")]
+use crate::parsing::encoding::exceeds_file_size;
+use crate::parsing::encoding::{
+    CappedLine, check_file_size, for_each_capped_line, long_line, open_text_reader,
+    read_text_file_with_limit, strip_bom,
+};
+use crate::parsing::timestamps::keep_timestamp;
 use crate::parsing::whatsapp::{
-    detect_whatsapp_format, is_whatsapp_system_message, parse_whatsapp_timestamp,
+    DETECTION_SAMPLE, DateFormat, KnownSenders, LineKind, LineRules, QuoteResolver, classify_line,
+    detect_whatsapp_format, learn_senders, strip_direction_marks,
 };
+use crate::sink::{CollectingSink, MessageRef, MessageSink, SkipReason};
 
 #[cfg(feature = "streaming")]
-use crate::streaming::{StreamingConfig, StreamingParser, WhatsAppStreamingParser};
+use crate::parser::ParseIterator;
+#[cfg(feature = "streaming")]
+use crate::streaming::{StreamingParser, WhatsAppStreamingParser};
 
 /// Parser for WhatsApp TXT exports.
 ///
@@ -38,7 +54,15 @@ use crate::streaming::{StreamingConfig, StreamingParser, WhatsAppStreamingParser
 ///
 /// - Multiline messages are properly joined
 /// - System messages (joins, leaves) are filtered out
-/// - Attachments are represented as `[Attachment]` placeholders
+/// - Localized media placeholders are normalized to `[Media omitted]`
+/// - Quoted replies (`> ` lines before the reply text) are stripped and
+///   resolved to the quoted message's `source_index` in `reply_to`, since
+///   WhatsApp has no message ids; write the index with
+///   [`OutputConfig::with_index`](crate::core::models::OutputConfig::with_index)
+///   to follow replies in the output
+/// - Senders containing `: ` are split off correctly with
+///   [`strict_sender_detection`](WhatsAppConfig::strict_sender_detection),
+///   which reads the export twice
 ///
 /// # Examples
 ///
@@ -92,16 +116,101 @@ impl Default for WhatsAppParser {
 
 impl WhatsAppParser {
     /// Parses content from a string (internal implementation).
-    fn parse_content(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        let lines: Vec<&str> = content.lines().collect();
+    ///
+    /// Returns the messages and the number of over-long lines skipped and
+    /// messages flagged by the timestamp check.
+    fn parse_content(&self, content: &str) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let mut sink = CollectingSink::new();
+        // `None` marks an over-long line that was skipped
+        let mut lines: Vec<Option<&str>> = Vec::new();
+        for (number, line) in (1..).zip(strip_bom(content).lines()) {
+            if line.len() > self.config.max_line_length {
+                sink.on_skip(&self.long_line(number, line.len())?);
+                lines.push(None);
+                continue;
+            }
+            lines.push(Some(strip_direction_marks(line)));
+        }
 
-        if lines.is_empty() {
-            return Ok(vec![]);
+        let sample: Vec<&str> = lines
+            .iter()
+            .flatten()
+            .take(DETECTION_SAMPLE)
+            .copied()
+            .collect();
+        if sample.is_empty() {
+            return Ok((vec![], sink.skipped));
         }
 
         // Step 1: Auto-detect format from first 20 lines
-        let sample_size = std::cmp::min(20, lines.len());
-        let format = detect_whatsapp_format(&lines[..sample_size]).ok_or_else(|| {
+        let mut visitor = LineVisitor::new(&self.config, &sample)?;
+        if self.config.strict_sender_detection {
+            let mut known = KnownSenders::new();
+            for line in lines.iter().flatten() {
+                known.observe_line(line, &visitor.regex);
+            }
+            visitor.rules.known_senders = Some(known);
+        }
+
+        // Step 2: Parse all lines
+        // A collecting sink never breaks
+        let _ = visitor.push_lines(&lines, &mut sink);
+        let _ = visitor.flush(&mut sink);
+        Ok((sink.messages, sink.skipped))
+    }
+
+    /// Detects the format from the lines read so far and feeds them to a
+    /// new visitor. `None` marks an over-long line that was skipped.
+    fn start_visitor(
+        &self,
+        sample: &[Option<String>],
+        known_senders: Option<KnownSenders>,
+        sink: &mut dyn MessageSink,
+    ) -> Result<(LineVisitor<'_>, ControlFlow<()>), ChatpackError> {
+        let lines: Vec<Option<&str>> = sample.iter().map(Option::as_deref).collect();
+        let detect: Vec<&str> = lines.iter().flatten().copied().collect();
+        let mut visitor = LineVisitor::new(&self.config, &detect)?;
+        visitor.rules.known_senders = known_senders;
+        let flow = visitor.push_lines(&lines, sink);
+        Ok((visitor, flow))
+    }
+
+    /// Handles line `number`, `length` bytes long and over `max_line_length`.
+    fn long_line(&self, number: usize, length: usize) -> Result<SkipReason, ChatpackError> {
+        long_line(
+            number,
+            length,
+            self.config.max_line_length,
+            self.config.skip_invalid,
+        )
+    }
+}
+
+/// Line-driven WhatsApp parser.
+///
+/// A message is held back until the next header arrives, since any lines in
+/// between are continuations of it. Sender and content buffers are reused
+/// across messages, as is the scratch buffer for content normalization.
+/// Emitted messages are recorded in `quotes` so later replies can resolve.
+struct LineVisitor<'c> {
+    config: &'c WhatsAppConfig,
+    format: DateFormat,
+    regex: Regex,
+    sender: String,
+    content: String,
+    scratch: String,
+    raw: String,
+    timestamp: Option<DateTime<Utc>>,
+    pending: bool,
+    next_index: u64,
+    quotes: QuoteResolver,
+    rules: LineRules,
+}
+
+impl<'c> LineVisitor<'c> {
+    /// Detects the date format from `sample` and compiles its pattern.
+    fn new(config: &'c WhatsAppConfig, sample: &[&str]) -> Result<Self, ChatpackError> {
+        let format = detect_whatsapp_format(sample).ok_or_else(|| {
             ChatpackError::invalid_format(
                 "WhatsApp",
                 "Could not detect WhatsApp export format. \
@@ -109,55 +218,128 @@ impl WhatsAppParser {
             )
         })?;
 
-        // Step 2: Compile regex for detected format
         let regex = Regex::new(format.pattern())
             .map_err(|e| ChatpackError::invalid_format("WhatsApp", e.to_string()))?;
 
-        // Step 3: Parse all lines
-        let mut messages: Vec<Message> = Vec::new();
-
-        for line in &lines {
-            if line.trim().is_empty() {
-                continue;
+        Ok(Self {
+            config,
+            format,
+            regex,
+            sender: String::new(),
+            content: String::new(),
+            scratch: String::new(),
+            raw: String::new(),
+            timestamp: None,
+            pending: false,
+            next_index: 0,
+            quotes: QuoteResolver::new(),
+            rules: LineRules {
+                normalize_media_placeholders: config.normalize_media_placeholders,
+                normalize_deleted_messages: config.normalize_deleted_messages,
+                skip_system_messages: config.skip_system_messages,
+                sender_allowlist: config.sender_allowlist.clone(),
+                known_senders: None,
+            },
+        })
+    }
+
+    /// Feeds `lines`, where `None` is an over-long line that was skipped.
+    fn push_lines(
+        &mut self,
+        lines: &[Option<&str>],
+        sink: &mut dyn MessageSink,
+    ) -> ControlFlow<()> {
+        for line in lines {
+            match line {
+                Some(line) => self.push_line(line, sink)?,
+                None => self.skip_line(sink)?,
             }
-
-            if let Some(caps) = regex.captures(line) {
-                // New message starts
-                let date_str = caps.get(1).map_or("", |m| m.as_str());
-                let time_str = caps.get(2).map_or("", |m| m.as_str());
-                let sender = caps.get(3).map_or("", |m| m.as_str().trim());
-                let msg_content = caps.get(4).map_or("", |m| m.as_str());
-
-                // Skip system messages (if configured)
-                if self.config.skip_system_messages
-                    && is_whatsapp_system_message(sender, msg_content)
-                {
-                    continue;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Ends the current message at an over-long line that was skipped.
+    ///
+    /// The skipped line may have been the header of the next message, so
+    /// the lines after it, up to the next header, are dropped rather than
+    /// appended to the message before.
+    fn skip_line(&mut self, sink: &mut dyn MessageSink) -> ControlFlow<()> {
+        self.flush(sink)
+    }
+
+    /// Feeds one line (without direction marks), emitting the previous
+    /// message if this line starts a new one.
+    fn push_line(&mut self, line: &str, sink: &mut dyn MessageSink) -> ControlFlow<()> {
+        match classify_line(line, self.format, &self.regex, &self.rules) {
+            LineKind::Blank => {}
+            LineKind::SystemMessage => sink.on_skip(&SkipReason::SystemMessage),
+            LineKind::Excluded => self.flush(sink)?,
+            LineKind::Header {
+                sender,
+                content,
+                timestamp,
+            } => {
+                self.flush(sink)?;
+                self.sender.clear();
+                self.sender.push_str(sender);
+                self.content.clear();
+                self.content.push_str(content);
+                self.timestamp = timestamp;
+                self.pending = true;
+                if self.config.capture_raw {
+                    self.raw.clear();
+                    self.raw.push_str(line);
                 }
-
-                let timestamp = parse_whatsapp_timestamp(date_str, time_str, format);
-
-                let msg = Message::with_metadata(
-                    sender,
-                    msg_content,
-                    timestamp,
-                    None, // WhatsApp doesn't have message IDs in export
-                    None, // No reply references in text export
-                    None, // No edit timestamps
-                );
-
-                messages.push(msg);
-            } else {
-                // Continuation of previous message (multiline)
-                if let Some(last_msg) = messages.last_mut() {
-                    last_msg.content.push('\n');
-                    last_msg.content.push_str(line);
+            }
+            LineKind::Continuation if self.pending => {
+                self.content.push('\n');
+                self.content.push_str(line);
+                if self.config.capture_raw {
+                    self.raw.push('\n');
+                    self.raw.push_str(line);
                 }
-                // If no previous message, skip orphan line
             }
+            // Orphan line before the first message
+            LineKind::Continuation => {}
         }
+        ControlFlow::Continue(())
+    }
 
-        Ok(messages)
+    /// Emits the held-back message, if any, reporting it as skipped if it
+    /// is blank.
+    fn flush(&mut self, sink: &mut dyn MessageSink) -> ControlFlow<()> {
+        if !std::mem::take(&mut self.pending) {
+            return ControlFlow::Continue(());
+        }
+        if self.content.trim().is_empty() {
+            sink.on_skip(&SkipReason::BlankMessage);
+            return ControlFlow::Continue(());
+        }
+        let check = self.config.clamp_timestamps.as_ref();
+        if !keep_timestamp(&mut self.timestamp, check, sink) {
+            return ControlFlow::Continue(());
+        }
+        let index = self.next_index;
+        self.next_index += 1;
+        let reply_to = self
+            .quotes
+            .take_quote(&mut self.content, self.config.mark_unresolved_quotes);
+        let content = normalize_content(&self.content, self.config.content, &mut self.scratch);
+        self.quotes.record(index, content);
+        sink.on_message(MessageRef {
+            sender: &self.sender,
+            content,
+            timestamp: self.timestamp,
+            id: None, // WhatsApp doesn't have message IDs in export
+            reply_to,
+            edited: None, // No edit timestamps
+            forwarded_from: None,
+            topic: None,
+            attachments: &[],
+            source_index: Some(index),
+            platform: Some(Platform::WhatsApp),
+            raw: self.config.capture_raw.then_some(self.raw.as_str()),
+        })
     }
 }
 
@@ -172,12 +354,79 @@ impl Parser for WhatsAppParser {
     }
 
     fn parse(&self, path: &Path) -> Result<Vec<Message>, ChatpackError> {
-        let content = fs::read_to_string(path)?;
-        self.parse_content(&content)
+        Ok(self.parse_counted(path)?.0)
     }
 
     fn parse_str(&self, content: &str) -> Result<Vec<Message>, ChatpackError> {
-        self.parse_content(content)
+        Ok(self.parse_content(content)?.0)
+    }
+
+    fn parse_counted(&self, path: &Path) -> Result<(Vec<Message>, usize), ChatpackError> {
+        let content = read_text_file_with_limit(path, self.config.max_file_size)?;
+        self.parse_content(&content)
+    }
+
+    /// Reads the file line by line; only the first 20 lines are buffered,
+    /// for format detection, and no line longer than `max_line_length` is
+    /// held in memory. With `strict_sender_detection` the file is
+    /// read once more beforehand to learn its senders.
+    fn parse_into(&self, path: &Path, sink: &mut dyn MessageSink) -> Result<(), ChatpackError> {
+        check_file_size(path, self.config.max_file_size)?;
+        let mut known_senders = if self.config.strict_sender_detection {
+            let mut reader = open_text_reader(File::open(path)?, self.config.buffer_size)?;
+            Some(learn_senders(reader.as_mut(), self.config.max_line_length)?)
+        } else {
+            None
+        };
+        let mut reader = open_text_reader(File::open(path)?, self.config.buffer_size)?;
+        // Lines up to the end of the detection sample; `None` marks an
+        // over-long line that was skipped
+        let mut sample: Vec<Option<String>> = Vec::with_capacity(DETECTION_SAMPLE);
+        let mut sampled = 0;
+        let mut visitor: Option<LineVisitor<'_>> = None;
+
+        let max_line_length = self.config.max_line_length;
+        let flow = for_each_capped_line(reader.as_mut(), max_line_length, |number, line| {
+            let line = match line {
+                CappedLine::Line(line) => Some(strip_direction_marks(line)),
+                CappedLine::TooLong(length) => {
+                    sink.on_skip(&self.long_line(number, length)?);
+                    None
+                }
+            };
+            if let Some(visitor) = visitor.as_mut() {
+                return Ok(match line {
+                    Some(line) => visitor.push_line(line, sink),
+                    None => visitor.skip_line(sink),
+                });
+            }
+            sampled += usize::from(line.is_some());
+            sample.push(line.map(str::to_string));
+            if sampled < DETECTION_SAMPLE {
+                return Ok(ControlFlow::Continue(()));
+            }
+            let (started, flow) = self.start_visitor(&sample, known_senders.take(), sink)?;
+            visitor = Some(started);
+            Ok(flow)
+        })?;
+        if flow.is_break() {
+            return Ok(());
+        }
+
+        let mut visitor = match visitor {
+            Some(visitor) => visitor,
+            // Shorter than the detection sample
+            None if sampled == 0 => return Ok(()),
+            None => {
+                let (visitor, flow) = self.start_visitor(&sample, known_senders, sink)?;
+                if flow.is_break() {
+                    return Ok(());
+                }
+                visitor
+            }
+        };
+        let _ = visitor.flush(sink);
+        Ok(())
     }
 
     #[cfg(feature = "streaming")]
@@ -186,23 +435,24 @@ impl Parser for WhatsAppParser {
         path: &Path,
     ) -> Result<Box<dyn Iterator<Item = Result<Message, ChatpackError>> + Send>, ChatpackError>
     {
-        if self.config.streaming {
-            // Use native streaming parser
-            let streaming_config = StreamingConfig::new()
-                .with_buffer_size(self.config.buffer_size)
-                .with_skip_invalid(self.config.skip_invalid);
+        Ok(Box::new(self.stream_with_progress(path)?))
+    }
 
-            let streaming_parser = WhatsAppStreamingParser::with_config(streaming_config);
+    #[cfg(feature = "streaming")]
+    fn stream_with_progress(&self, path: &Path) -> Result<ParseIterator, ChatpackError> {
+        // Files above max_file_size are streamed even when streaming is off,
+        // since parse() would refuse them.
+        if self.config.streaming || exceeds_file_size(path, self.config.max_file_size) {
+            // Use native streaming parser
+            let streaming_parser = WhatsAppStreamingParser::from_whatsapp_config(&self.config);
             let iterator =
                 StreamingParser::stream(&streaming_parser, path.to_str().unwrap_or_default())?;
 
-            Ok(Box::new(
-                iterator.map(|result| result.map_err(ChatpackError::from)),
-            ))
+            Ok(ParseIterator::new(iterator))
         } else {
             // Fallback: load everything into memory
             let messages = Parser::parse(self, path)?;
-            Ok(Box::new(messages.into_iter().map(Ok)))
+            Ok(ParseIterator::loaded(messages, path))
         }
     }
 
@@ -215,12 +465,24 @@ impl Parser for WhatsAppParser {
     fn recommended_buffer_size(&self) -> usize {
         self.config.buffer_size
     }
+
+    fn config_schema(&self) -> Vec<ConfigField> {
+        WhatsAppConfig::schema()
+    }
+
+    fn config_json(&self) -> serde_json::Value {
+        config_to_json(&self.config)
+    }
+
+    fn set_config_json(&mut self, config: serde_json::Value) -> Result<(), ChatpackError> {
+        update_config_json(&mut self.config, config)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parsing::whatsapp::DateFormat;
+    use crate::parsing::whatsapp::{is_whatsapp_system_message, parse_whatsapp_timestamp};
 
     // =========================================================================
     // WhatsAppParser construction tests
@@ -384,7 +646,53 @@ mod tests {
             "[1/15/24, 10:30:45 AM] Alice: <Media omitted>\n[1/15/24, 10:31:00 AM] Bob: Hi";
         let messages = parser.parse_str(content).expect("parse failed");
         assert_eq!(messages.len(), 2);
-        assert_eq!(messages[0].content, "<Media omitted>");
+        assert_eq!(messages[0].content, "[Media omitted]");
+    }
+
+    #[test]
+    fn test_parse_str_media_placeholders_kept_when_disabled() {
+        let config = WhatsAppConfig::new().with_normalize_media_placeholders(false);
+        let parser = WhatsAppParser::with_config(config);
+        let content = "[15.01.24, 10:30:45] Alice: <Medien ausgeschlossen>";
+        let messages = parser.parse_str(content).expect("parse failed");
+        assert_eq!(messages[0].content, "<Medien ausgeschlossen>");
+    }
+
+    #[test]
+    fn test_parse_str_deleted_messages() {
+        let content = "[15.01.24, 10:30:45] Alice: Diese Nachricht wurde gelöscht\n[15.01.24, 10:31:00] Bob: Hallo";
+        let messages = WhatsAppParser::new()
+            .parse_str(content)
+            .expect("parse failed");
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].is_deleted());
+        assert!(!messages[1].is_deleted());
+
+        let config = WhatsAppConfig::new().with_normalize_deleted_messages(false);
+        let messages = WhatsAppParser::with_config(config)
+            .parse_str(content)
+            .expect("parse failed");
+        assert_eq!(messages[0].content, "Diese Nachricht wurde gelöscht");
+    }
+
+    #[test]
+    fn test_parse_str_strips_direction_marks() {
+        let parser = WhatsAppParser::new();
+        let content = "\u{200E}[1/15/24, 10:30:45 AM] \u{200E}Alice: \u{200E}image omitted\n[1/15/24, 10:31:00 AM] Bob\u{200F}: Hi";
+        let messages = parser.parse_str(content).expect("parse failed");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[0].content, "[Media omitted]");
+        assert_eq!(messages[1].sender, "Bob");
+    }
+
+    #[test]
+    fn test_parse_str_null_sender_is_system() {
+        let parser = WhatsAppParser::new();
+        let content = "15/01/2024, 10:30 - null: Alice joined\n15/01/2024, 10:31 - Bob: Hi";
+        let messages = parser.parse_str(content).expect("parse failed");
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].sender, "Bob");
     }
 
     #[test]
@@ -508,6 +816,16 @@ mod tests {
         assert!(is_whatsapp_system_message("   ", "Some message"));
     }
 
+    #[test]
+    fn test_parse_str_with_bom() {
+        let parser = WhatsAppParser::new();
+        let messages = parser
+            .parse_str("\u{FEFF}[1/15/24, 10:30:00 AM] Alice: Hello")
+            .unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].sender, "Alice");
+    }
+
     // =========================================================================
     // Streaming support tests
     // =========================================================================
@@ -0,0 +1,30 @@
+//! Sender allowlist checked by the parsers before building a message.
+
+use crate::core::textutil::senders_match;
+
+/// Returns whether `sender` passes `allowlist`, comparing names with
+/// [`senders_match`] like
+/// [`FilterConfig::with_sender`](crate::core::filter::FilterConfig::with_sender).
+///
+/// With no allowlist, every sender passes.
+pub(crate) fn allows(allowlist: Option<&[String]>, sender: &str) -> bool {
+    allowlist.is_none_or(|senders| senders.iter().any(|s| senders_match(s, sender)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows() {
+        let senders = vec!["Alice".to_string(), "bob".to_string()];
+        assert!(allows(None, "Anyone"));
+        assert!(allows(Some(&senders), "alice"));
+        assert!(allows(Some(&senders), "BOB"));
+        assert!(!allows(Some(&senders), "Carol"));
+        assert!(!allows(Some(&[]), "Alice"));
+
+        let senders = vec!["Алия".to_string()];
+        assert!(allows(Some(&senders), "алия\u{200B}"));
+    }
+}
@@ -0,0 +1,64 @@
+//! Placeholders for calls and voice messages.
+//!
+//! Telegram and Instagram record calls as records without text. With
+//! `include_calls` set, both parsers render them with the markers below so
+//! the conversation keeps a trace of them.
+
+/// Content of a voice message without a caption.
+pub const VOICE_MESSAGE: &str = "[Voice message]";
+
+/// Content of a call that was missed, declined, or never connected.
+pub const MISSED_CALL: &str = "[Missed call]";
+
+/// Formats a call duration as `1h 2m 3s`, leaving out zero units.
+///
+/// ```rust
+/// use chatpack::parsing::calls::format_call_duration;
+///
+/// assert_eq!(format_call_duration(750), "12m 30s");
+/// assert_eq!(format_call_duration(3600), "1h");
+/// assert_eq!(format_call_duration(0), "0s");
+/// ```
+pub fn format_call_duration(seconds: u64) -> String {
+    let (hours, minutes, secs) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let parts: Vec<String> = [(hours, "h"), (minutes, "m"), (secs, "s")]
+        .into_iter()
+        .filter(|&(value, _)| value > 0)
+        .map(|(value, unit)| format!("{value}{unit}"))
+        .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Returns `[Call: …]` for a call that lasted `duration` seconds, or
+/// [`MISSED_CALL`] when it has no duration.
+pub fn call_marker(duration: Option<u64>) -> String {
+    match duration {
+        Some(seconds) if seconds > 0 => format!("[Call: {}]", format_call_duration(seconds)),
+        _ => MISSED_CALL.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_call_duration() {
+        assert_eq!(format_call_duration(0), "0s");
+        assert_eq!(format_call_duration(45), "45s");
+        assert_eq!(format_call_duration(300), "5m");
+        assert_eq!(format_call_duration(3723), "1h 2m 3s");
+        assert_eq!(format_call_duration(90_005), "25h 5s");
+    }
+
+    #[test]
+    fn test_call_marker() {
+        assert_eq!(call_marker(Some(750)), "[Call: 12m 30s]");
+        assert_eq!(call_marker(Some(0)), "[Missed call]");
+        assert_eq!(call_marker(None), "[Missed call]");
+    }
+}
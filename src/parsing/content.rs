@@ -0,0 +1,225 @@
+//! Post-parse whitespace normalization of message content.
+//!
+//! Every parser passes the text it extracted through [`normalize_content`]
+//! under the platform config's [`ContentPolicy`], so batch and streaming
+//! parsing produce byte-identical content.
+
+#[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
+use crate::Message;
+use crate::config::ContentPolicy;
+
+/// Line prefixes of the placeholders parsers emit for media.
+const MARKER_PREFIXES: [&str; 2] = ["[Attachment: ", "[Sticker: "];
+
+fn is_marker(line: &str) -> bool {
+    let line = line.trim();
+    line.ends_with(']') && MARKER_PREFIXES.iter().any(|p| line.starts_with(p))
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// Applies `policy` to `content`.
+///
+/// Rules, in order:
+///
+/// 1. `ensure_single_space_before_markers`: a line holding only an
+///    `[Attachment: …]` or `[Sticker: …]` marker is trimmed and follows the
+///    previous non-blank line after exactly one `\n`, with that line's
+///    trailing whitespace removed.
+/// 2. `collapse_internal_blank_lines`: each run of blank (empty or
+///    whitespace-only) lines becomes one empty line.
+/// 3. `trim_content`: leading and trailing whitespace is stripped.
+///
+/// Returns a slice of `content` when only trimming applies; otherwise the
+/// result is built in `scratch`.
+///
+/// ```rust
+/// use chatpack::config::ContentPolicy;
+/// use chatpack::parsing::content::normalize_content;
+///
+/// let mut scratch = String::new();
+/// let content = normalize_content(" look  \n\n[Attachment: a.png]", ContentPolicy::new(), &mut scratch);
+/// assert_eq!(content, "look\n[Attachment: a.png]");
+/// ```
+pub fn normalize_content<'a>(
+    content: &'a str,
+    policy: ContentPolicy,
+    scratch: &'a mut String,
+) -> &'a str {
+    let text = if needs_rewrite(content, policy) {
+        rewrite(content, policy, scratch);
+        scratch.as_str()
+    } else {
+        content
+    };
+    if policy.trim_content {
+        text.trim()
+    } else {
+        text
+    }
+}
+
+/// Applies `policy` to an owned string, allocating only when lines change.
+pub fn normalize_content_in_place(content: &mut String, policy: ContentPolicy) {
+    if needs_rewrite(content, policy) {
+        let mut out = String::with_capacity(content.len());
+        rewrite(content, policy, &mut out);
+        *content = out;
+    }
+    if policy.trim_content {
+        let end = content.trim_end().len();
+        content.truncate(end);
+        let start = content.len() - content.trim_start().len();
+        content.drain(..start);
+    }
+}
+
+/// Returns whether the line-level rules change anything.
+fn needs_rewrite(content: &str, policy: ContentPolicy) -> bool {
+    if !policy.collapse_internal_blank_lines && !policy.ensure_single_space_before_markers {
+        return false;
+    }
+    let mut prev: Option<&str> = None;
+    for line in content.split('\n') {
+        if let Some(prev) = prev {
+            if policy.collapse_internal_blank_lines && is_blank(prev) && is_blank(line) {
+                return true;
+            }
+            if policy.ensure_single_space_before_markers
+                && is_marker(line)
+                && (is_blank(prev) || prev.trim_end() != prev)
+            {
+                return true;
+            }
+        }
+        if policy.ensure_single_space_before_markers && is_marker(line) && line.trim() != line {
+            return true;
+        }
+        prev = Some(line);
+    }
+    false
+}
+
+/// Rebuilds `content` into `out` under the line-level rules.
+fn rewrite(content: &str, policy: ContentPolicy, out: &mut String) {
+    out.clear();
+    let mut blanks: Vec<&str> = Vec::new();
+    let mut first = true;
+
+    for line in content.split('\n') {
+        if is_blank(line) {
+            blanks.push(line);
+        } else if policy.ensure_single_space_before_markers && is_marker(line) {
+            blanks.clear();
+            out.truncate(out.trim_end().len());
+            first = out.is_empty();
+            push_line(out, line.trim(), &mut first);
+        } else {
+            flush_blanks(out, &mut blanks, policy, &mut first);
+            push_line(out, line, &mut first);
+        }
+    }
+    flush_blanks(out, &mut blanks, policy, &mut first);
+}
+
+fn push_line(out: &mut String, line: &str, first: &mut bool) {
+    if !std::mem::take(first) {
+        out.push('\n');
+    }
+    out.push_str(line);
+}
+
+fn flush_blanks(out: &mut String, blanks: &mut Vec<&str>, policy: ContentPolicy, first: &mut bool) {
+    if blanks.is_empty() {
+        return;
+    }
+    if policy.collapse_internal_blank_lines {
+        push_line(out, "", first);
+    } else {
+        for line in &*blanks {
+            push_line(out, line, first);
+        }
+    }
+    blanks.clear();
+}
+
+/// Applies `policy` to the content of every message.
+#[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
+pub(crate) fn normalize_messages(messages: &mut [Message], policy: ContentPolicy) {
+    for msg in messages {
+        normalize_content_in_place(&mut msg.content, policy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(content: &str, policy: ContentPolicy) -> String {
+        let mut owned = content.to_string();
+        normalize_content_in_place(&mut owned, policy);
+        let mut scratch = String::new();
+        assert_eq!(normalize_content(content, policy, &mut scratch), owned);
+        owned
+    }
+
+    #[test]
+    fn test_default_trims() {
+        let policy = ContentPolicy::default();
+        assert_eq!(normalize("  hello \n", policy), "hello");
+        assert_eq!(normalize("a\n\n\nb", policy), "a\n\n\nb");
+        assert_eq!(normalize("plain", policy), "plain");
+    }
+
+    #[test]
+    fn test_raw_is_identity() {
+        let raw = "  a  \n\n \n[Attachment: x.png]  \n";
+        assert_eq!(normalize(raw, ContentPolicy::raw()), raw);
+    }
+
+    #[test]
+    fn test_collapse_blank_lines() {
+        let policy = ContentPolicy::raw().with_collapse_internal_blank_lines(true);
+        assert_eq!(normalize("a\n\n \n\t\nb", policy), "a\n\nb");
+        assert_eq!(normalize("a\n\nb", policy), "a\n\nb");
+        assert_eq!(normalize("\n\n\na", policy), "\na");
+    }
+
+    #[test]
+    fn test_markers() {
+        let policy = ContentPolicy::raw().with_ensure_single_space_before_markers(true);
+        assert_eq!(
+            normalize("hi   \n\n  [Attachment: a.png] \n[Sticker: Wave]", policy),
+            "hi\n[Attachment: a.png]\n[Sticker: Wave]"
+        );
+        assert_eq!(
+            normalize("\n[Attachment: a.png]", policy),
+            "[Attachment: a.png]"
+        );
+        // Inline mentions and unterminated brackets are not markers
+        assert_eq!(
+            normalize("see  \n[Attachment: x] here", policy),
+            "see  \n[Attachment: x] here"
+        );
+    }
+
+    #[test]
+    fn test_all_rules() {
+        let policy = ContentPolicy::new().with_collapse_internal_blank_lines(true);
+        assert_eq!(
+            normalize("\n  one \n\n\n two  \n\n[Attachment: a.png]\n\n", policy),
+            "one \n\n two\n[Attachment: a.png]"
+        );
+    }
+
+    #[test]
+    fn test_crlf_lines() {
+        let policy = ContentPolicy::new();
+        assert_eq!(
+            normalize("hi \r\n[Attachment: a.png]\r\n", policy),
+            "hi\n[Attachment: a.png]"
+        );
+    }
+}
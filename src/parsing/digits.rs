@@ -0,0 +1,43 @@
+//! Digit normalization for timestamps matched in text exports.
+//!
+//! WhatsApp and Discord TXT lines are matched with patterns whose `\d`
+//! accepts any Unicode digit, so exports from phones set to Arabic or
+//! Hindi locales match with their native digits. chrono only reads ASCII
+//! digits, so the matched timestamp text goes through [`ascii_digits`]
+//! before parsing. Message content is never normalized.
+
+use std::borrow::Cow;
+
+/// Returns the ASCII digit for an Arabic-Indic (U+0660–0669), Extended
+/// Arabic-Indic (U+06F0–06F9), or Devanagari (U+0966–096F) digit.
+fn ascii_digit(c: char) -> Option<char> {
+    let zero = match c {
+        '\u{0660}'..='\u{0669}' => 0x0660,
+        '\u{06F0}'..='\u{06F9}' => 0x06F0,
+        '\u{0966}'..='\u{096F}' => 0x0966,
+        _ => return None,
+    };
+    char::from_digit(u32::from(c) - zero, 10)
+}
+
+/// Replaces Arabic-Indic, Extended Arabic-Indic, and Devanagari digits in
+/// `s` with ASCII digits, borrowing `s` when it has none.
+pub(crate) fn ascii_digits(s: &str) -> Cow<'_, str> {
+    if !s.chars().any(|c| ascii_digit(c).is_some()) {
+        return Cow::Borrowed(s);
+    }
+    Cow::Owned(s.chars().map(|c| ascii_digit(c).unwrap_or(c)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_digits() {
+        assert_eq!(ascii_digits("١٥/٠١/٢٤, ١٠:٣٠:٤٥"), "15/01/24, 10:30:45");
+        assert_eq!(ascii_digits("۲۰۲۴"), "2024");
+        assert_eq!(ascii_digits("१२:०५"), "12:05");
+        assert!(matches!(ascii_digits("1/15/24"), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,192 @@
+//! DiscordChatExporter CSV exports.
+//!
+//! Built with the `discord-csv` feature, which brings in `csv`.
+
+use std::io::Read;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use super::text_event;
+use crate::config::DiscordConfig;
+use crate::error::ChatpackError;
+use crate::parsing::allowlist::allows;
+use crate::parsing::content::normalize_messages;
+use crate::parsing::digits::ascii_digits;
+use crate::parsing::encoding::normalize_newlines;
+use crate::{AttachmentRef, Message};
+
+/// `Date` formats written by DiscordChatExporter versions and locales other
+/// than RFC 3339, read as UTC.
+const DATE_FORMATS: [&str; 6] = [
+    "%d-%b-%y %I:%M %p",    // 15-Jan-24 10:30 AM
+    "%d.%m.%Y %H:%M",       // 15.01.2024 22:30
+    "%d.%m.%Y %H:%M:%S",    // 15.01.2024 22:30:00
+    "%m/%d/%Y %I:%M %p",    // 1/15/2024 10:30 AM
+    "%m/%d/%Y %I:%M:%S %p", // 1/15/2024 10:30:00 AM
+    "%Y-%m-%d %H:%M:%S",    // 2024-01-15 10:30:00
+];
+
+/// Parses a DiscordChatExporter CSV export with columns `AuthorID`,
+/// `Author`, `Date`, `Content`, `Attachments` and `Reactions`.
+///
+/// Returns the messages and the number whose `Date` matched no format;
+/// those are kept without a timestamp.
+pub(crate) fn parse_csv<R: Read>(
+    reader: R,
+    config: &DiscordConfig,
+) -> Result<(Vec<Message>, usize), ChatpackError> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(reader);
+
+    let mut messages = Vec::new();
+    let mut unparsed_dates = 0;
+
+    for result in csv_reader.records() {
+        let record = result?;
+
+        // CSV columns: AuthorID, Author, Date, Content, Attachments, Reactions.
+        // Quoted cells keep the exporter's line endings.
+        let sender = normalize_newlines(record.get(1).unwrap_or(""));
+        if !allows(config.sender_allowlist.as_deref(), &sender) {
+            continue;
+        }
+        let timestamp_str = record.get(2).unwrap_or("");
+        let content = normalize_newlines(record.get(3).unwrap_or(""));
+        let attachments = record.get(4).unwrap_or("");
+
+        // Skip empty messages
+        if content.trim().is_empty() && attachments.trim().is_empty() {
+            continue;
+        }
+        let Some(mut content) = text_event(config, &sender, &content) else {
+            continue;
+        };
+
+        // Parse attachments (comma-separated URLs)
+        let mut refs = Vec::new();
+        if !attachments.trim().is_empty() {
+            for url in attachments.split(',') {
+                let url = url.trim();
+                if !url.is_empty() {
+                    let filename = url.rsplit('/').next().unwrap_or(url);
+                    if !content.is_empty() {
+                        content.push('\n');
+                    }
+                    content.push_str(&format!("[Attachment: {}]", filename));
+                    if config.collect_attachment_refs {
+                        refs.push(AttachmentRef::from_path(url));
+                    }
+                }
+            }
+        }
+
+        let timestamp = parse_csv_date(timestamp_str, config.csv_date_format.as_deref());
+        unparsed_dates += usize::from(timestamp.is_none());
+
+        let mut msg = Message::with_metadata(sender, content, timestamp, None, None, None);
+        msg.attachments = refs;
+        messages.push(msg);
+    }
+    normalize_messages(&mut messages, config.content);
+
+    Ok((messages, unparsed_dates))
+}
+
+/// Parses a `Date` cell as RFC 3339, then in each of [`DATE_FORMATS`], then
+/// in `custom`, a chrono format with or without an offset.
+fn parse_csv_date(s: &str, custom: Option<&str>) -> Option<DateTime<Utc>> {
+    let s = ascii_digits(s.trim());
+    if let Ok(dt) = DateTime::parse_from_rfc3339(&s) {
+        return Some(dt.to_utc());
+    }
+    for fmt in DATE_FORMATS {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(&s, fmt) {
+            return Some(dt.and_utc());
+        }
+    }
+    let custom = custom?;
+    if let Ok(dt) = DateTime::parse_from_str(&s, custom) {
+        return Some(dt.to_utc());
+    }
+    NaiveDateTime::parse_from_str(&s, custom)
+        .ok()
+        .map(|dt| dt.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_basic() {
+        let csv = "AuthorID,Author,Date,Content,Attachments,Reactions\n123,alice,2024-01-15T10:30:00+00:00,Hello world,,";
+
+        let (messages, _) = parse_csv(csv.as_bytes(), &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].sender, "alice");
+        assert_eq!(messages[0].content, "Hello world");
+    }
+
+    #[test]
+    fn test_parse_csv_with_attachments() {
+        let csv = "AuthorID,Author,Date,Content,Attachments,Reactions\n123,alice,2024-01-15T10:30:00+00:00,Check this,https://cdn.discord.com/image.png,";
+
+        let (messages, _) = parse_csv(csv.as_bytes(), &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].content.contains("Check this"));
+        assert!(messages[0].content.contains("[Attachment: image.png]"));
+    }
+
+    #[test]
+    fn test_parse_csv_skips_empty() {
+        let csv = "AuthorID,Author,Date,Content,Attachments,Reactions\n123,alice,2024-01-15T10:30:00+00:00,Hello,,\n124,bob,2024-01-15T10:31:00+00:00,,,";
+
+        let (messages, _) = parse_csv(csv.as_bytes(), &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_csv_date_known_formats() {
+        let expected = DateTime::parse_from_rfc3339("2024-01-15T10:30:00Z")
+            .unwrap()
+            .to_utc();
+        for date in [
+            "2024-01-15T10:30:00+00:00",
+            "2024-01-15T12:30:00+02:00",
+            "15-Jan-24 10:30 AM",
+            "15.01.2024 10:30",
+            "15.01.2024 10:30:00",
+            "1/15/2024 10:30 AM",
+            "01/15/2024 10:30:00 AM",
+            "2024-01-15 10:30:00",
+        ] {
+            assert_eq!(parse_csv_date(date, None), Some(expected), "{date}");
+        }
+        assert_eq!(
+            parse_csv_date("15.01.2024 22:30", None),
+            Some(expected + chrono::Duration::hours(12))
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_date_custom_format() {
+        assert_eq!(parse_csv_date("2024/01/15 10h30", None), None);
+        let naive = parse_csv_date("2024/01/15 10h30", Some("%Y/%m/%d %Hh%M"));
+        assert_eq!(naive.unwrap().to_rfc3339(), "2024-01-15T10:30:00+00:00");
+        let offset = parse_csv_date("2024/01/15 12h30 +0200", Some("%Y/%m/%d %Hh%M %z"));
+        assert_eq!(offset, naive);
+    }
+
+    #[test]
+    fn test_parse_csv_counts_unparsed_dates() {
+        let csv = "AuthorID,Author,Date,Content,Attachments,Reactions\n1,alice,15-Jan-24 10:30 AM,Hi,,\n2,bob,yesterday,Hey,,\n3,bob,,Again,,";
+
+        let (messages, unparsed) = parse_csv(csv.as_bytes(), &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].timestamp.is_some());
+        assert_eq!(messages[1].timestamp, None);
+        assert_eq!(unparsed, 2);
+    }
+}
@@ -0,0 +1,1389 @@
+//! Shared Discord parsing utilities.
+//!
+//! This module contains types and functions shared between the standard
+//! and streaming Discord parsers.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::config::DiscordConfig;
+#[cfg(feature = "streaming")]
+use crate::config::Projection;
+use crate::parser::Platform;
+use crate::parsing::allowlist::allows;
+use crate::sink::MessageRef;
+use crate::{AttachmentRef, Message};
+
+#[cfg(feature = "discord-csv")]
+pub(crate) mod csv;
+#[cfg(feature = "discord-txt")]
+pub(crate) mod txt;
+
+/// Raw Discord message structure for (de)serialization.
+///
+/// Serializing produces the DiscordChatExporter JSON message shape.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordRawMessage {
+    pub id: String,
+    /// Message type, e.g. `"Default"`, `"Reply"` or `"GuildMemberJoin"`;
+    /// see [`DiscordEvent::from_type`].
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub msg_type: Option<String>,
+    pub timestamp: String,
+    pub timestamp_edited: Option<String>,
+    pub content: String,
+    pub author: DiscordAuthor,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<DiscordReference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<DiscordAttachment>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stickers: Option<Vec<DiscordSticker>>,
+}
+
+/// Converts a [`Message`] back into the DiscordChatExporter shape.
+///
+/// The sender becomes the author name. Attachment and sticker markers are
+/// already part of `content`, so they are written verbatim rather than as
+/// separate arrays. Missing IDs and timestamps become empty strings, which
+/// the parser reads back as `None`.
+impl From<&Message> for DiscordRawMessage {
+    fn from(msg: &Message) -> Self {
+        let rfc3339 = |ts: DateTime<Utc>| ts.to_rfc3339_opts(SecondsFormat::AutoSi, false);
+        Self {
+            id: msg.id.map(|id| id.to_string()).unwrap_or_default(),
+            msg_type: None,
+            timestamp: msg.timestamp.map(rfc3339).unwrap_or_default(),
+            timestamp_edited: msg.edited.map(rfc3339),
+            content: msg.content.clone(),
+            author: DiscordAuthor {
+                name: msg.sender.clone(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: msg.reply_to.map(|id| DiscordReference {
+                message_id: Some(id.to_string()),
+            }),
+            attachments: None,
+            stickers: None,
+        }
+    }
+}
+
+/// Discord author structure.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordAuthor {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+    /// Legacy four-digit tag; `"0000"` or `"0"` for migrated usernames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discriminator: Option<String>,
+    /// User snowflake.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+impl DiscordAuthor {
+    /// Returns the sender name for this author.
+    ///
+    /// The discriminator is appended to whichever name is chosen, and only
+    /// if it is a real legacy tag (not all zeros).
+    pub fn sender_name(&self, naming: SenderNaming) -> String {
+        let mut scratch = String::new();
+        select_sender(
+            &self.name,
+            self.nickname.as_deref(),
+            self.discriminator.as_deref(),
+            naming,
+            &mut scratch,
+        )
+        .to_string()
+    }
+}
+
+/// Picks the sender name, borrowing it unless a discriminator is appended.
+fn select_sender<'a>(
+    name: &'a str,
+    nickname: Option<&'a str>,
+    discriminator: Option<&str>,
+    naming: SenderNaming,
+    scratch: &'a mut String,
+) -> &'a str {
+    let sender = match nickname {
+        Some(nickname) if naming.prefer_nickname => nickname,
+        _ => name,
+    };
+    match discriminator.filter(|tag| {
+        naming.append_discriminator && !tag.is_empty() && tag.bytes().any(|b| b != b'0')
+    }) {
+        Some(tag) => {
+            scratch.clear();
+            scratch.push_str(sender);
+            scratch.push('#');
+            scratch.push_str(tag);
+            scratch
+        }
+        None => sender,
+    }
+}
+
+/// How a [`DiscordAuthor`] becomes `Message::sender`.
+///
+/// Mirrors [`DiscordConfig::prefer_nickname`] and
+/// [`DiscordConfig::append_discriminator`]. The default prefers nicknames
+/// and omits discriminators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SenderNaming {
+    /// Use the server nickname when the author has one.
+    pub prefer_nickname: bool,
+    /// Append `#discriminator`, e.g. `alice#1234`.
+    pub append_discriminator: bool,
+}
+
+impl Default for SenderNaming {
+    fn default() -> Self {
+        Self {
+            prefer_nickname: true,
+            append_discriminator: false,
+        }
+    }
+}
+
+impl From<&DiscordConfig> for SenderNaming {
+    fn from(config: &DiscordConfig) -> Self {
+        Self {
+            prefer_nickname: config.prefer_nickname,
+            append_discriminator: config.append_discriminator,
+        }
+    }
+}
+
+/// A Discord system notification, such as a member joining or a pin.
+///
+/// DiscordChatExporter records these with a non-default `type` in JSON
+/// exports, and with a fixed sentence as their content in TXT and CSV
+/// exports. Parsers skip them unless
+/// [`DiscordConfig::include_system_events`] is set, in which case they
+/// become messages like `[Alice pinned a message]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscordEvent {
+    /// `GuildMemberJoin`: the author joined the server.
+    MemberJoin,
+    /// `ChannelPinnedMessage`: the author pinned a message.
+    PinnedMessage,
+    /// `UserPremiumGuildSubscription` and its tiers: the author boosted the
+    /// server.
+    Boost,
+    /// `RecipientAdd`: the author added someone to a group DM.
+    RecipientAdd,
+    /// `RecipientRemove`: the author left or removed someone from a group DM.
+    RecipientRemove,
+    /// `Call`: the author started a call.
+    Call,
+    /// `ChannelNameChange`: the author renamed the channel.
+    ChannelNameChange,
+    /// `ChannelIconChange`: the author changed the channel icon.
+    ChannelIconChange,
+    /// `ThreadCreated`: the author started a thread.
+    ThreadCreated,
+}
+
+impl DiscordEvent {
+    /// Maps a message `type` to the event it records.
+    ///
+    /// `Default`, `Reply`, and types not listed here (such as slash command
+    /// responses) are regular messages and yield `None`.
+    ///
+    /// ```rust
+    /// use chatpack::parsing::discord::DiscordEvent;
+    ///
+    /// assert_eq!(DiscordEvent::from_type("GuildMemberJoin"), Some(DiscordEvent::MemberJoin));
+    /// assert_eq!(DiscordEvent::from_type("Reply"), None);
+    /// ```
+    pub fn from_type(msg_type: &str) -> Option<Self> {
+        let event = match msg_type {
+            "GuildMemberJoin" => Self::MemberJoin,
+            "ChannelPinnedMessage" => Self::PinnedMessage,
+            "UserPremiumGuildSubscription"
+            | "UserPremiumGuildSubscriptionTier1"
+            | "UserPremiumGuildSubscriptionTier2"
+            | "UserPremiumGuildSubscriptionTier3" => Self::Boost,
+            "RecipientAdd" => Self::RecipientAdd,
+            "RecipientRemove" => Self::RecipientRemove,
+            "Call" => Self::Call,
+            "ChannelNameChange" => Self::ChannelNameChange,
+            "ChannelIconChange" => Self::ChannelIconChange,
+            "ThreadCreated" => Self::ThreadCreated,
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    /// Maps the sentence DiscordChatExporter writes as the content of an
+    /// event in TXT and CSV exports, e.g. `Joined the server.`
+    pub fn from_fallback(content: &str) -> Option<Self> {
+        let event = match content.trim() {
+            "Joined the server." => Self::MemberJoin,
+            "Pinned a message." => Self::PinnedMessage,
+            "Added a recipient." => Self::RecipientAdd,
+            "Left the group." => Self::RecipientRemove,
+            "Changed the channel name." => Self::ChannelNameChange,
+            "Changed the channel icon." => Self::ChannelIconChange,
+            "Started a thread." => Self::ThreadCreated,
+            text if text.starts_with("Added ") && text.ends_with(" to the group.") => {
+                Self::RecipientAdd
+            }
+            text if text.starts_with("Removed ") && text.ends_with(" from the group.") => {
+                Self::RecipientRemove
+            }
+            text if text.starts_with("Changed the channel name: ") => Self::ChannelNameChange,
+            text if text.starts_with("Started a call that lasted ") => Self::Call,
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    /// Returns the content of the message representing this event by
+    /// `sender`, e.g. `[Alice joined the server]`.
+    pub fn marker(self, sender: &str) -> String {
+        let action = match self {
+            Self::MemberJoin => "joined the server",
+            Self::PinnedMessage => "pinned a message",
+            Self::Boost => "boosted the server",
+            Self::RecipientAdd => "added someone to the group",
+            Self::RecipientRemove => "left or removed someone from the group",
+            Self::Call => "started a call",
+            Self::ChannelNameChange => "changed the channel name",
+            Self::ChannelIconChange => "changed the channel icon",
+            Self::ThreadCreated => "started a thread",
+        };
+        format!("[{sender} {action}]")
+    }
+}
+
+/// Returns whether a message `type` records a [`DiscordEvent`].
+pub(crate) fn is_event(msg_type: Option<&str>) -> bool {
+    msg_type.and_then(DiscordEvent::from_type).is_some()
+}
+
+/// Returns the content of a TXT or CSV message, which carries a system
+/// event as DiscordChatExporter's sentence for it: `None` skips the
+/// event, and [`include_system_events`](DiscordConfig::include_system_events)
+/// turns it into its marker.
+#[cfg(any(feature = "discord-txt", feature = "discord-csv"))]
+pub(crate) fn text_event(config: &DiscordConfig, sender: &str, content: &str) -> Option<String> {
+    match DiscordEvent::from_fallback(content) {
+        None => Some(content.to_string()),
+        Some(_) if !config.include_system_events => None,
+        Some(event) => Some(event.marker(sender)),
+    }
+}
+
+/// Discord message reference (for replies).
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordReference {
+    pub message_id: Option<String>,
+}
+
+/// Discord attachment structure.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordAttachment {
+    pub file_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Discord sticker structure.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordSticker {
+    pub name: String,
+}
+
+/// Discord export wrapper.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordExport {
+    pub messages: Vec<DiscordRawMessage>,
+}
+
+/// Parses a raw Discord message into a `Message`.
+///
+/// Returns `None` if the message has no content and no attachments/stickers,
+/// or is a system event. The sender is the nickname when there is one; see
+/// [`parse_discord_message_with_config`] for other naming options.
+pub fn parse_discord_message(msg: &DiscordRawMessage) -> Option<Message> {
+    parse_discord_message_named(msg, SenderNaming::default(), None, false)
+}
+
+/// Parses a raw Discord message honoring `config`'s sender naming,
+/// [`sender_allowlist`](DiscordConfig::sender_allowlist),
+/// [`include_system_events`](DiscordConfig::include_system_events), and
+/// [`collect_attachment_refs`](DiscordConfig::collect_attachment_refs).
+pub fn parse_discord_message_with_config(
+    msg: &DiscordRawMessage,
+    config: &DiscordConfig,
+) -> Option<Message> {
+    DiscordRecord::from(msg)
+        .message_ref(
+            config.into(),
+            config.collect_attachment_refs,
+            config.sender_allowlist.as_deref(),
+            config.include_system_events,
+            &mut DiscordScratch::default(),
+        )
+        .map(|msg| msg.to_message())
+}
+
+fn parse_discord_message_named(
+    msg: &DiscordRawMessage,
+    naming: SenderNaming,
+    senders: Option<&[String]>,
+    include_events: bool,
+) -> Option<Message> {
+    DiscordRecord::from(msg)
+        .message_ref(
+            naming,
+            false,
+            senders,
+            include_events,
+            &mut DiscordScratch::default(),
+        )
+        .map(|msg| msg.to_message())
+}
+
+/// Returns the attachments a raw Discord message references, with their
+/// URLs when the export has them.
+pub fn discord_attachment_refs(msg: &DiscordRawMessage) -> Vec<AttachmentRef> {
+    msg.attachments
+        .iter()
+        .flatten()
+        .map(|att| AttachmentRef {
+            file_name: att.file_name.clone(),
+            path: att.url.clone(),
+        })
+        .collect()
+}
+
+/// Returns `true` if `first_line` looks like one record of a JSONL export.
+///
+/// A JSONL line is a complete object on its own; the first line of a regular
+/// export is either a lone `{` (pretty-printed) or contains the
+/// `"guild"`/`"messages"` keys.
+pub fn is_jsonl(first_line: &str) -> bool {
+    let trimmed = first_line.trim();
+    trimmed.starts_with('{')
+        && trimmed.ends_with('}')
+        && !trimmed.contains("\"messages\"")
+        && !trimmed.contains("\"guild\"")
+}
+
+/// Parses one line of a JSONL export.
+///
+/// Blank lines and messages skipped by [`parse_discord_message`] yield
+/// `Ok(None)`.
+pub fn parse_discord_jsonl_line(line: &str) -> Result<Option<Message>, serde_json::Error> {
+    parse_jsonl_line(line, parse_discord_message)
+}
+
+/// Parses one line of a JSONL export like [`parse_discord_message_with_config`].
+pub fn parse_discord_jsonl_line_with_config(
+    line: &str,
+    config: &DiscordConfig,
+) -> Result<Option<Message>, serde_json::Error> {
+    parse_jsonl_line(line, |msg| parse_discord_message_with_config(msg, config))
+}
+
+/// Parses one line of a JSONL export with the given sender naming,
+/// skipping senders outside `senders`, and system events unless
+/// `include_events` is set. Only the fields `projection` keeps are copied
+/// out of the line.
+#[cfg(feature = "streaming")]
+pub(crate) fn parse_discord_jsonl_line_named(
+    line: &str,
+    naming: SenderNaming,
+    senders: Option<&[String]>,
+    include_events: bool,
+    projection: Projection,
+) -> Result<Option<Message>, serde_json::Error> {
+    let Some(record) = read_discord_jsonl_record(line)? else {
+        return Ok(None);
+    };
+    Ok(record
+        .message_ref(
+            naming,
+            false,
+            senders,
+            include_events,
+            &mut DiscordScratch::default(),
+        )
+        .map(|msg| msg.projected_message(projection)))
+}
+
+fn parse_jsonl_line(
+    line: &str,
+    parse: impl FnOnce(&DiscordRawMessage) -> Option<Message>,
+) -> Result<Option<Message>, serde_json::Error> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    let msg: DiscordRawMessage = serde_json::from_str(trimmed)?;
+    Ok(parse(&msg))
+}
+
+/// Discord message borrowing its strings from the JSON it was read from.
+///
+/// Strings without escapes are borrowed; the rest are unescaped into owned
+/// copies. Used by the sink path so a JSONL line can be turned into a
+/// [`MessageRef`] without allocating per field.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiscordRecord<'a> {
+    #[serde(borrow)]
+    id: Cow<'a, str>,
+    #[serde(rename = "type", default, borrow, deserialize_with = "borrow_opt")]
+    msg_type: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    timestamp: Cow<'a, str>,
+    #[serde(default, borrow, deserialize_with = "borrow_opt")]
+    timestamp_edited: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    content: Cow<'a, str>,
+    #[serde(borrow)]
+    author: DiscordRecordAuthor<'a>,
+    #[serde(default, borrow)]
+    reference: Option<DiscordRecordReference<'a>>,
+    #[serde(default, borrow)]
+    attachments: Option<Vec<DiscordRecordAttachment<'a>>>,
+    #[serde(default, borrow)]
+    stickers: Option<Vec<DiscordRecordSticker<'a>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscordRecordAuthor<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    #[serde(default, borrow, deserialize_with = "borrow_opt")]
+    nickname: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrow_opt")]
+    discriminator: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscordRecordReference<'a> {
+    #[serde(default, borrow, deserialize_with = "borrow_opt")]
+    message_id: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscordRecordAttachment<'a> {
+    #[serde(borrow)]
+    file_name: Cow<'a, str>,
+    #[serde(default, borrow, deserialize_with = "borrow_opt")]
+    url: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscordRecordSticker<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+}
+
+/// Deserializes an optional string, borrowing it when possible.
+///
+/// `Option<Cow<str>>` would otherwise always be owned.
+fn borrow_opt<'de, D>(deserializer: D) -> Result<Option<Cow<'de, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    Ok(Option::<Borrowed<'de>>::deserialize(deserializer)?.map(|b| b.0))
+}
+
+impl<'a> From<&'a DiscordRawMessage> for DiscordRecord<'a> {
+    fn from(msg: &'a DiscordRawMessage) -> Self {
+        let borrow = |s: &'a String| Cow::Borrowed(s.as_str());
+        Self {
+            id: borrow(&msg.id),
+            msg_type: msg.msg_type.as_ref().map(borrow),
+            timestamp: borrow(&msg.timestamp),
+            timestamp_edited: msg.timestamp_edited.as_ref().map(borrow),
+            content: borrow(&msg.content),
+            author: DiscordRecordAuthor {
+                name: borrow(&msg.author.name),
+                nickname: msg.author.nickname.as_ref().map(borrow),
+                discriminator: msg.author.discriminator.as_ref().map(borrow),
+            },
+            reference: msg.reference.as_ref().map(|r| DiscordRecordReference {
+                message_id: r.message_id.as_ref().map(borrow),
+            }),
+            attachments: msg.attachments.as_ref().map(|atts| {
+                atts.iter()
+                    .map(|att| DiscordRecordAttachment {
+                        file_name: borrow(&att.file_name),
+                        url: att.url.as_ref().map(borrow),
+                    })
+                    .collect()
+            }),
+            stickers: msg.stickers.as_ref().map(|stickers| {
+                stickers
+                    .iter()
+                    .map(|sticker| DiscordRecordSticker {
+                        name: borrow(&sticker.name),
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// Buffers reused across records when a field cannot be borrowed.
+#[derive(Debug, Default)]
+pub(crate) struct DiscordScratch {
+    sender: String,
+    content: String,
+    attachments: Vec<AttachmentRef>,
+}
+
+impl DiscordRecord<'_> {
+    /// Returns `true` if this record is a system notification.
+    pub(crate) fn is_system_event(&self) -> bool {
+        self.msg_type
+            .as_deref()
+            .and_then(DiscordEvent::from_type)
+            .is_some()
+    }
+
+    /// Builds the message this record represents.
+    ///
+    /// Returns `None` if the message has no content and no
+    /// attachments/stickers, if its sender is not in `senders`, or if it is a
+    /// system event and `include_events` is false. Sender
+    /// and content borrow from the record unless a discriminator or
+    /// attachment markers have to be added.
+    pub(crate) fn message_ref<'s>(
+        &'s self,
+        naming: SenderNaming,
+        collect_attachments: bool,
+        senders: Option<&[String]>,
+        include_events: bool,
+        scratch: &'s mut DiscordScratch,
+    ) -> Option<MessageRef<'s>> {
+        let attachments = self.attachments.as_deref().unwrap_or_default();
+        let stickers = self.stickers.as_deref().unwrap_or_default();
+
+        let event = self.msg_type.as_deref().and_then(DiscordEvent::from_type);
+        if event.is_some() && !include_events {
+            return None;
+        }
+        // Skip empty messages without attachments/stickers
+        if event.is_none()
+            && self.content.trim().is_empty()
+            && attachments.is_empty()
+            && stickers.is_empty()
+        {
+            return None;
+        }
+
+        let sender = select_sender(
+            &self.author.name,
+            self.author.nickname.as_deref(),
+            self.author.discriminator.as_deref(),
+            naming,
+            &mut scratch.sender,
+        );
+        if !allows(senders, sender) {
+            return None;
+        }
+
+        // Build content: event marker, or text + attachment/sticker info
+        let content: &str = if let Some(event) = event {
+            let content = &mut scratch.content;
+            content.clear();
+            content.push_str(&event.marker(sender));
+            content
+        } else if attachments.is_empty() && stickers.is_empty() {
+            &self.content
+        } else {
+            let content = &mut scratch.content;
+            content.clear();
+            content.push_str(&self.content);
+            let markers = attachments
+                .iter()
+                .map(|att| ("Attachment", &att.file_name))
+                .chain(stickers.iter().map(|sticker| ("Sticker", &sticker.name)));
+            for (kind, name) in markers {
+                if !content.is_empty() {
+                    content.push('\n');
+                }
+                let _ = write!(content, "[{kind}: {name}]");
+            }
+            content
+        };
+
+        scratch.attachments.clear();
+        if collect_attachments {
+            scratch
+                .attachments
+                .extend(attachments.iter().map(|att| AttachmentRef {
+                    file_name: att.file_name.to_string(),
+                    path: att.url.as_deref().map(str::to_string),
+                }));
+        }
+
+        // Parse timestamps (ISO 8601 / RFC3339)
+        let parse_ts = |ts: &str| DateTime::parse_from_rfc3339(ts).ok().map(|dt| dt.to_utc());
+
+        Some(MessageRef {
+            sender,
+            content,
+            timestamp: parse_ts(&self.timestamp),
+            // Discord snowflakes
+            id: self.id.parse::<u64>().ok(),
+            reply_to: self
+                .reference
+                .as_ref()
+                .and_then(|r| r.message_id.as_deref())
+                .and_then(|id| id.parse::<u64>().ok()),
+            edited: self.timestamp_edited.as_deref().and_then(parse_ts),
+            forwarded_from: None,
+            topic: None,
+            attachments: &scratch.attachments,
+            source_index: None,
+            platform: Some(Platform::Discord),
+            raw: None,
+        })
+    }
+}
+
+/// Reads one JSONL line as a borrowed record.
+///
+/// Blank lines yield `Ok(None)`.
+pub(crate) fn read_discord_jsonl_record(
+    line: &str,
+) -> Result<Option<DiscordRecord<'_>>, serde_json::Error> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(trimmed).map(Some)
+}
+
+/// Lightweight Discord message for streaming (without attachments/stickers).
+///
+/// Used by JSONL streaming where each line is a complete message.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordStreamMessage {
+    pub id: String,
+    #[serde(rename = "type", default)]
+    pub msg_type: Option<String>,
+    pub timestamp: String,
+    pub timestamp_edited: Option<String>,
+    pub content: String,
+    pub author: DiscordAuthor,
+    pub reference: Option<DiscordReference>,
+}
+
+/// Parses a streaming Discord message (simpler, no attachments).
+///
+/// System events are skipped.
+pub fn parse_discord_stream_message(msg: &DiscordStreamMessage) -> Option<Message> {
+    if msg.content.trim().is_empty() || is_event(msg.msg_type.as_deref()) {
+        return None;
+    }
+
+    let sender = msg.author.sender_name(SenderNaming::default());
+
+    let timestamp = DateTime::parse_from_rfc3339(&msg.timestamp)
+        .ok()
+        .map(|dt| dt.to_utc());
+
+    let edited = msg
+        .timestamp_edited
+        .as_ref()
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|dt| dt.to_utc());
+
+    let id = msg.id.parse::<u64>().ok();
+
+    let reply_to = msg
+        .reference
+        .as_ref()
+        .and_then(|r| r.message_id.as_ref())
+        .and_then(|id_str| id_str.parse::<u64>().ok());
+
+    Some(Message::with_metadata(
+        sender,
+        msg.content.clone(),
+        timestamp,
+        id,
+        reply_to,
+        edited,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // =========================================================================
+    // parse_discord_message tests
+    // =========================================================================
+
+    #[test]
+    fn test_parse_discord_message_basic() {
+        let msg = DiscordRawMessage {
+            id: "123456789".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Hello world".to_string(),
+            author: DiscordAuthor {
+                name: "alice".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+            attachments: None,
+            stickers: None,
+        };
+
+        let result = parse_discord_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert_eq!(parsed.sender, "alice");
+        assert_eq!(parsed.content, "Hello world");
+        assert!(parsed.timestamp.is_some());
+    }
+
+    #[test]
+    fn test_parse_discord_message_with_nickname() {
+        let msg = DiscordRawMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Hi".to_string(),
+            author: DiscordAuthor {
+                name: "alice123".to_string(),
+                nickname: Some("Alice".to_string()),
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+            attachments: None,
+            stickers: None,
+        };
+
+        let result = parse_discord_message(&msg);
+        assert!(result.is_some());
+        assert_eq!(result.unwrap().sender, "Alice");
+    }
+
+    #[test]
+    fn test_parse_discord_message_with_attachments() {
+        let msg = DiscordRawMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Check this".to_string(),
+            author: DiscordAuthor {
+                name: "bob".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+            attachments: Some(vec![DiscordAttachment {
+                file_name: "image.png".to_string(),
+                url: None,
+            }]),
+            stickers: None,
+        };
+
+        let result = parse_discord_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert!(parsed.content.contains("[Attachment: image.png]"));
+    }
+
+    #[test]
+    fn test_parse_discord_message_with_stickers() {
+        let msg = DiscordRawMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Look at this sticker".to_string(),
+            author: DiscordAuthor {
+                name: "charlie".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+            attachments: None,
+            stickers: Some(vec![DiscordSticker {
+                name: "CoolSticker".to_string(),
+            }]),
+        };
+
+        let result = parse_discord_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert!(parsed.content.contains("[Sticker: CoolSticker]"));
+    }
+
+    #[test]
+    fn test_parse_discord_message_sticker_only() {
+        // Empty content but with sticker should be kept
+        let msg = DiscordRawMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: String::new(),
+            author: DiscordAuthor {
+                name: "charlie".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+            attachments: None,
+            stickers: Some(vec![DiscordSticker {
+                name: "Reaction".to_string(),
+            }]),
+        };
+
+        let result = parse_discord_message(&msg);
+        assert!(result.is_some());
+        assert!(result.unwrap().content.contains("[Sticker: Reaction]"));
+    }
+
+    #[test]
+    fn test_parse_discord_message_attachment_only() {
+        // Empty content but with attachment should be kept
+        let msg = DiscordRawMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: String::new(),
+            author: DiscordAuthor {
+                name: "bob".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+            attachments: Some(vec![DiscordAttachment {
+                file_name: "photo.jpg".to_string(),
+                url: None,
+            }]),
+            stickers: None,
+        };
+
+        let result = parse_discord_message(&msg);
+        assert!(result.is_some());
+        assert!(result.unwrap().content.contains("[Attachment: photo.jpg]"));
+    }
+
+    #[test]
+    fn test_parse_discord_message_empty() {
+        let msg = DiscordRawMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: String::new(),
+            author: DiscordAuthor {
+                name: "bob".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+            attachments: None,
+            stickers: None,
+        };
+
+        assert!(parse_discord_message(&msg).is_none());
+    }
+
+    #[test]
+    fn test_parse_discord_message_with_reply() {
+        let msg = DiscordRawMessage {
+            id: "456".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:31:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "This is a reply".to_string(),
+            author: DiscordAuthor {
+                name: "alice".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: Some(DiscordReference {
+                message_id: Some("123".to_string()),
+            }),
+            attachments: None,
+            stickers: None,
+        };
+
+        let result = parse_discord_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert_eq!(parsed.reply_to, Some(123));
+    }
+
+    #[test]
+    fn test_parse_discord_message_with_edited() {
+        let msg = DiscordRawMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: Some("2024-01-15T10:35:00+00:00".to_string()),
+            content: "Edited message".to_string(),
+            author: DiscordAuthor {
+                name: "alice".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+            attachments: None,
+            stickers: None,
+        };
+
+        let result = parse_discord_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert!(parsed.edited.is_some());
+    }
+
+    // =========================================================================
+    // parse_discord_stream_message tests
+    // =========================================================================
+
+    #[test]
+    fn test_parse_stream_message_basic() {
+        let msg = DiscordStreamMessage {
+            id: "123456789".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Hello from stream".to_string(),
+            author: DiscordAuthor {
+                name: "alice".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+        };
+
+        let result = parse_discord_stream_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert_eq!(parsed.sender, "alice");
+        assert_eq!(parsed.content, "Hello from stream");
+        assert!(parsed.timestamp.is_some());
+        assert_eq!(parsed.id, Some(123456789));
+    }
+
+    #[test]
+    fn test_parse_stream_message_with_nickname() {
+        let msg = DiscordStreamMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Hi".to_string(),
+            author: DiscordAuthor {
+                name: "alice123".to_string(),
+                nickname: Some("Alice Display".to_string()),
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+        };
+
+        let result = parse_discord_stream_message(&msg);
+        assert!(result.is_some());
+        assert_eq!(result.unwrap().sender, "Alice Display");
+    }
+
+    #[test]
+    fn test_parse_stream_message_empty() {
+        let msg = DiscordStreamMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "   ".to_string(), // Whitespace only
+            author: DiscordAuthor {
+                name: "bob".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+        };
+
+        assert!(parse_discord_stream_message(&msg).is_none());
+    }
+
+    #[test]
+    fn test_parse_stream_message_with_reply() {
+        let msg = DiscordStreamMessage {
+            id: "456".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:31:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Reply to something".to_string(),
+            author: DiscordAuthor {
+                name: "charlie".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: Some(DiscordReference {
+                message_id: Some("789".to_string()),
+            }),
+        };
+
+        let result = parse_discord_stream_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert_eq!(parsed.reply_to, Some(789));
+    }
+
+    #[test]
+    fn test_parse_stream_message_with_edited() {
+        let msg = DiscordStreamMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: Some("2024-01-15T10:40:00+00:00".to_string()),
+            content: "Edited stream message".to_string(),
+            author: DiscordAuthor {
+                name: "alice".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+        };
+
+        let result = parse_discord_stream_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert!(parsed.edited.is_some());
+    }
+
+    #[test]
+    fn test_parse_stream_message_invalid_id() {
+        let msg = DiscordStreamMessage {
+            id: "not_a_number".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Has invalid ID".to_string(),
+            author: DiscordAuthor {
+                name: "alice".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: None,
+        };
+
+        let result = parse_discord_stream_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert!(parsed.id.is_none()); // Should be None since parsing fails
+    }
+
+    #[test]
+    fn test_parse_stream_message_reference_without_id() {
+        let msg = DiscordStreamMessage {
+            id: "123".to_string(),
+            msg_type: None,
+            timestamp: "2024-01-15T10:30:00+00:00".to_string(),
+            timestamp_edited: None,
+            content: "Has reference but no id".to_string(),
+            author: DiscordAuthor {
+                name: "alice".to_string(),
+                nickname: None,
+                discriminator: None,
+                id: None,
+            },
+            reference: Some(DiscordReference { message_id: None }),
+        };
+
+        let result = parse_discord_stream_message(&msg);
+        assert!(result.is_some());
+
+        let parsed = result.unwrap();
+        assert!(parsed.reply_to.is_none());
+    }
+
+    // =========================================================================
+    // Serde deserialization tests
+    // =========================================================================
+
+    #[test]
+    fn test_discord_export_deserialize() {
+        let json = r#"{
+            "messages": [
+                {
+                    "id": "1",
+                    "timestamp": "2024-01-15T10:30:00+00:00",
+                    "content": "Hello",
+                    "author": {"name": "alice"}
+                }
+            ]
+        }"#;
+
+        let export: DiscordExport = serde_json::from_str(json).expect("deserialize");
+        assert_eq!(export.messages.len(), 1);
+        assert_eq!(export.messages[0].content, "Hello");
+    }
+
+    #[test]
+    fn test_discord_stream_message_deserialize() {
+        let json = r#"{
+            "id": "12345",
+            "timestamp": "2024-01-15T10:30:00+00:00",
+            "content": "Stream test",
+            "author": {"name": "bob", "nickname": "Bobby"}
+        }"#;
+
+        let msg: DiscordStreamMessage = serde_json::from_str(json).expect("deserialize");
+        assert_eq!(msg.id, "12345");
+        assert_eq!(msg.content, "Stream test");
+        assert_eq!(msg.author.name, "bob");
+        assert_eq!(msg.author.nickname, Some("Bobby".to_string()));
+    }
+
+    // =========================================================================
+    // From<&Message> tests
+    // =========================================================================
+
+    #[test]
+    fn test_from_message_round_trip() {
+        let ts = DateTime::from_timestamp(1705314600, 0);
+        let original = Message::with_metadata(
+            "Alice",
+            "Hi\n[Attachment: a.png]",
+            ts,
+            Some(42),
+            Some(41),
+            ts,
+        );
+
+        let raw = DiscordRawMessage::from(&original);
+        assert_eq!(raw.id, "42");
+        assert_eq!(raw.timestamp, "2024-01-15T10:30:00+00:00");
+        assert_eq!(parse_discord_message(&raw).unwrap(), original);
+    }
+
+    #[test]
+    fn test_from_message_without_metadata() {
+        let raw = DiscordRawMessage::from(&Message::new("Bob", "Hello"));
+        let json = serde_json::to_string(&raw).unwrap();
+        assert!(json.contains(r#""author":{"name":"Bob"}"#));
+        assert!(!json.contains("reference"));
+
+        let parsed = parse_discord_message(&raw).unwrap();
+        assert_eq!(parsed, Message::new("Bob", "Hello"));
+    }
+
+    // =========================================================================
+    // JSONL tests
+    // =========================================================================
+
+    #[test]
+    fn test_is_jsonl() {
+        assert!(is_jsonl(
+            r#"{"id":"1","timestamp":"2024-01-01T00:00:00Z","content":"hi","author":{"name":"bob"}}"#
+        ));
+        assert!(!is_jsonl("{"));
+        assert!(!is_jsonl(r#"{"guild":{"id":"1"},"messages":[]}"#));
+        assert!(!is_jsonl("[1/15/2024 10:30 AM] alice"));
+    }
+
+    #[test]
+    fn test_parse_discord_jsonl_line() {
+        let line = r#"{"id":"5","timestamp":"2024-01-01T00:00:00Z","content":"","author":{"name":"bob"},"attachments":[{"fileName":"a.png"}],"reference":{"messageId":"4"}}"#;
+        let msg = parse_discord_jsonl_line(line).unwrap().unwrap();
+        assert_eq!(msg.content, "[Attachment: a.png]");
+        assert_eq!(msg.reply_to, Some(4));
+
+        assert!(parse_discord_jsonl_line("   ").unwrap().is_none());
+        assert!(parse_discord_jsonl_line("{not json}").is_err());
+    }
+
+    #[test]
+    fn test_author_sender_name() {
+        let author = |discriminator: &str| DiscordAuthor {
+            name: "alice".to_string(),
+            nickname: Some("Alice".to_string()),
+            discriminator: Some(discriminator.to_string()),
+            id: Some("111".to_string()),
+        };
+        let naming = |prefer_nickname, append_discriminator| SenderNaming {
+            prefer_nickname,
+            append_discriminator,
+        };
+
+        assert_eq!(author("1234").sender_name(SenderNaming::default()), "Alice");
+        assert_eq!(author("1234").sender_name(naming(false, false)), "alice");
+        assert_eq!(author("1234").sender_name(naming(true, true)), "Alice#1234");
+        assert_eq!(
+            author("1234").sender_name(naming(false, true)),
+            "alice#1234"
+        );
+        assert_eq!(author("0000").sender_name(naming(false, true)), "alice");
+        assert_eq!(author("0").sender_name(naming(true, true)), "Alice");
+    }
+
+    #[test]
+    fn test_record_borrows_plain_fields() {
+        let line = r#"{"id":"7","timestamp":"2024-01-15T10:30:00+00:00","timestampEdited":null,"content":"Hi","author":{"name":"alice","nickname":"Alice","discriminator":"1234"}}"#;
+        let record = read_discord_jsonl_record(line).unwrap().unwrap();
+        assert!(matches!(record.content, Cow::Borrowed(_)));
+        assert!(matches!(record.author.nickname, Some(Cow::Borrowed(_))));
+
+        let mut scratch = DiscordScratch::default();
+        let msg = record
+            .message_ref(SenderNaming::default(), false, None, false, &mut scratch)
+            .unwrap();
+        assert_eq!(msg.sender, "Alice");
+        assert_eq!(msg.content, "Hi");
+        assert_eq!(msg.id, Some(7));
+    }
+
+    #[test]
+    fn test_record_matches_owned_parse() {
+        let line = r#"{"id":"8","timestamp":"2024-01-15T10:30:00+00:00","content":"say \"hi\"","author":{"name":"alice","discriminator":"1234"},"reference":{"messageId":"7"},"attachments":[{"fileName":"a.png","url":"https://cdn/a.png"}],"stickers":[{"name":"Wave"}]}"#;
+        let config = DiscordConfig::new()
+            .with_append_discriminator(true)
+            .with_collect_attachment_refs(true);
+
+        let record = read_discord_jsonl_record(line).unwrap().unwrap();
+        let mut scratch = DiscordScratch::default();
+        let msg = record
+            .message_ref((&config).into(), true, None, false, &mut scratch)
+            .unwrap();
+        assert_eq!(msg.sender, "alice#1234");
+        assert_eq!(
+            msg.content,
+            "say \"hi\"\n[Attachment: a.png]\n[Sticker: Wave]"
+        );
+        assert_eq!(msg.reply_to, Some(7));
+        assert_eq!(msg.attachments.len(), 1);
+
+        let owned = parse_discord_jsonl_line_with_config(line, &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(msg.to_message(), owned);
+    }
+
+    #[test]
+    fn test_record_blank_line() {
+        assert!(read_discord_jsonl_record("  ").unwrap().is_none());
+        assert!(read_discord_jsonl_record("{").is_err());
+    }
+
+    #[test]
+    fn test_event_from_type() {
+        assert_eq!(
+            DiscordEvent::from_type("ChannelPinnedMessage"),
+            Some(DiscordEvent::PinnedMessage)
+        );
+        assert_eq!(
+            DiscordEvent::from_type("UserPremiumGuildSubscriptionTier2"),
+            Some(DiscordEvent::Boost)
+        );
+        for regular in ["Default", "Reply", "ChatInputCommand"] {
+            assert_eq!(DiscordEvent::from_type(regular), None);
+        }
+    }
+
+    #[test]
+    fn test_event_from_fallback() {
+        assert_eq!(
+            DiscordEvent::from_fallback("Joined the server."),
+            Some(DiscordEvent::MemberJoin)
+        );
+        assert_eq!(
+            DiscordEvent::from_fallback("Changed the channel name: general"),
+            Some(DiscordEvent::ChannelNameChange)
+        );
+        assert_eq!(
+            DiscordEvent::from_fallback("Added Bob to the group."),
+            Some(DiscordEvent::RecipientAdd)
+        );
+        assert_eq!(DiscordEvent::from_fallback("Joined the server. Hi!"), None);
+    }
+
+    #[test]
+    fn test_event_marker() {
+        assert_eq!(
+            DiscordEvent::MemberJoin.marker("Alice"),
+            "[Alice joined the server]"
+        );
+        assert_eq!(
+            DiscordEvent::Boost.marker("Bob"),
+            "[Bob boosted the server]"
+        );
+    }
+
+    #[test]
+    fn test_record_event() {
+        let line = r#"{"id":"9","type":"GuildMemberJoin","timestamp":"2024-01-15T10:30:00+00:00","content":"","author":{"name":"alice"}}"#;
+        let record = read_discord_jsonl_record(line).unwrap().unwrap();
+        let mut scratch = DiscordScratch::default();
+        let naming = SenderNaming::default();
+
+        assert!(
+            record
+                .message_ref(naming, false, None, false, &mut scratch)
+                .is_none()
+        );
+        let msg = record
+            .message_ref(naming, false, None, true, &mut scratch)
+            .unwrap();
+        assert_eq!(msg.content, "[alice joined the server]");
+        assert_eq!(msg.id, Some(9));
+    }
+}
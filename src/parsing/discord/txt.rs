@@ -0,0 +1,384 @@
+//! DiscordChatExporter plain-text exports.
+//!
+//! Built with the `discord-txt` feature, which brings in `regex` for the
+//! message header pattern.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use regex::Regex;
+
+use super::text_event;
+use crate::config::DiscordConfig;
+use crate::error::ChatpackError;
+use crate::parsing::allowlist::allows;
+use crate::parsing::content::normalize_messages;
+use crate::parsing::digits::ascii_digits;
+use crate::parsing::encoding::long_line;
+use crate::parsing::months::numeric_month;
+use crate::parsing::quotes::{
+    QUOTE_WINDOW, REPLYING_TO_PREFIX, starts_with_words, trim_snippet, unresolved_marker,
+};
+use crate::{AttachmentRef, Message};
+
+/// Parses a DiscordChatExporter TXT export.
+///
+/// Returns the messages and the number of over-long lines skipped.
+pub(crate) fn parse_txt(
+    content: &str,
+    config: &DiscordConfig,
+) -> Result<(Vec<Message>, usize), ChatpackError> {
+    let mut messages = Vec::new();
+    let mut skipped = 0;
+
+    // Pattern: [M/D/YYYY H:MM AM] sender OR [M/D/YYYY H:MM:SS] sender,
+    // or [D-Mon-YY H:MM PM] sender in older exports
+    let header_re = Regex::new(
+        r"^\[((?:\d{1,2}/\d{1,2}/\d{4}|\d{1,2}-\p{L}{3,}\.?-\d{2,4})\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?)\]\s+(.+)$",
+    )
+    .map_err(|e| ChatpackError::invalid_format("Discord TXT", e.to_string()))?;
+
+    let senders = config.sender_allowlist.as_deref();
+    let mut current_sender: Option<String> = None;
+    let mut current_timestamp: Option<DateTime<Utc>> = None;
+    let mut current_content = String::new();
+    let mut current_attachments: Vec<AttachmentRef> = Vec::new();
+    let mut current_reply: Option<u64> = None;
+    let mut in_attachments = false;
+    let mut in_stickers = false;
+    // A reply context line may open a message's content or stand above
+    // its header; one that follows content is held until the next line
+    // shows which.
+    let mut reply_open = false;
+    let mut held_reply: Option<&str> = None;
+
+    let max_len = config.max_line_length;
+    for (number, line) in (1..).zip(content.lines()) {
+        if line.len() > max_len {
+            long_line(number, line.len(), max_len, config.skip_invalid)?;
+            skipped += 1;
+            continue;
+        }
+
+        // Check for message header
+        if let Some(caps) = header_re.captures(line) {
+            // Save previous message if exists
+            if let Some(sender) = current_sender.take() {
+                let content = text_event(config, &sender, current_content.trim());
+                if let Some(content) = content.filter(|c| !c.is_empty()) {
+                    let mut msg = Message::with_metadata(
+                        sender,
+                        content,
+                        current_timestamp,
+                        None,
+                        current_reply,
+                        None,
+                    );
+                    msg.attachments = std::mem::take(&mut current_attachments);
+                    messages.push(msg);
+                }
+            }
+            current_attachments.clear();
+
+            // Parse new message header. Lines of a message from a
+            // sender outside the allowlist are skipped with it.
+            let timestamp_str = caps.get(1).unwrap().as_str();
+            let sender = caps.get(2).unwrap().as_str();
+
+            current_sender = allows(senders, sender).then(|| sender.to_string());
+            current_timestamp = parse_txt_timestamp(timestamp_str);
+            current_content = String::new();
+            current_reply = None;
+            in_attachments = false;
+            in_stickers = false;
+            reply_open = true;
+            if let Some(reply) = held_reply.take() {
+                current_reply = take_reply(config, &messages, reply, &mut current_content);
+                reply_open = false;
+            }
+            continue;
+        }
+
+        if reply_context(line).is_some() && held_reply.is_none() {
+            if reply_open {
+                if current_sender.is_some() {
+                    current_reply = take_reply(config, &messages, line, &mut current_content);
+                }
+                reply_open = false;
+            } else {
+                held_reply = Some(line);
+            }
+            continue;
+        }
+        reply_open = false;
+
+        // A held reply context line followed by more content was part
+        // of the message.
+        if let Some(held) = held_reply.take() {
+            if current_sender.is_some() && !(in_attachments || in_stickers) {
+                if !current_content.is_empty() {
+                    current_content.push('\n');
+                }
+                current_content.push_str(held);
+            }
+            if reply_context(line).is_some() {
+                held_reply = Some(line);
+                continue;
+            }
+        }
+
+        if current_sender.is_some() {
+            // Check for special sections
+            if line == "{Attachments}" {
+                in_attachments = true;
+                in_stickers = false;
+                continue;
+            }
+            if line == "{Stickers}" {
+                in_stickers = true;
+                in_attachments = false;
+                continue;
+            }
+
+            // Handle content
+            if in_attachments || in_stickers {
+                let trimmed = line.trim();
+                if !trimmed.is_empty() {
+                    // Extract filename from URL or use as-is
+                    let name = if trimmed.starts_with("http") {
+                        trimmed.rsplit('/').next().unwrap_or(trimmed)
+                    } else {
+                        trimmed
+                    };
+
+                    if !current_content.is_empty() {
+                        current_content.push('\n');
+                    }
+                    if in_attachments {
+                        current_content.push_str(&format!("[Attachment: {}]", name));
+                        if config.collect_attachment_refs {
+                            current_attachments.push(AttachmentRef::from_path(trimmed));
+                        }
+                    } else {
+                        current_content.push_str(&format!("[Sticker: {}]", name));
+                    }
+                }
+            } else {
+                // Regular message content
+                if !current_content.is_empty() {
+                    current_content.push('\n');
+                }
+                current_content.push_str(line);
+            }
+        }
+    }
+
+    // Don't forget the last message
+    if let Some(held) = held_reply.filter(|_| !(in_attachments || in_stickers)) {
+        if !current_content.is_empty() {
+            current_content.push('\n');
+        }
+        current_content.push_str(held);
+    }
+    if let Some(sender) = current_sender {
+        let content = text_event(config, &sender, current_content.trim());
+        if let Some(content) = content.filter(|c| !c.is_empty()) {
+            let mut msg = Message::with_metadata(
+                sender,
+                content,
+                current_timestamp,
+                None,
+                current_reply,
+                None,
+            );
+            msg.attachments = current_attachments;
+            messages.push(msg);
+        }
+    }
+    normalize_messages(&mut messages, config.content);
+
+    Ok((messages, skipped))
+}
+
+/// Resolves a TXT reply context `line` against the messages parsed so
+/// far, returning the position of the most recent one among the last
+/// 500 by the quoted author that starts with the quoted text.
+///
+/// An unresolved reply leaves a `[Replying to: author: text]` line in
+/// `content` when [`mark_unresolved_quotes`](DiscordConfig::mark_unresolved_quotes)
+/// is set.
+fn take_reply(
+    config: &DiscordConfig,
+    messages: &[Message],
+    line: &str,
+    content: &mut String,
+) -> Option<u64> {
+    let (author, text) = reply_context(line)?;
+    let snippet = trim_snippet(text);
+    let position = messages
+        .iter()
+        .enumerate()
+        .rev()
+        .take(QUOTE_WINDOW)
+        .find(|(_, msg)| {
+            !snippet.is_empty()
+                && msg.sender == author
+                && starts_with_words(unmarked(&msg.content), snippet)
+        })
+        .map(|(position, _)| position as u64);
+    if position.is_none() && config.mark_unresolved_quotes {
+        content.push_str(&unresolved_marker(&format!("{author}: {text}")));
+    }
+    position
+}
+
+fn parse_txt_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    // Try formats: "M/D/YYYY H:MM AM", "M/D/YYYY H:MM:SS", and "D-Mon-YY
+    // H:MM PM" once its month name is replaced by its number
+    let formats = [
+        "%m/%d/%Y %I:%M %p",
+        "%m/%d/%Y %I:%M:%S %p",
+        "%m/%d/%Y %H:%M",
+        "%m/%d/%Y %H:%M:%S",
+        "%d-%m-%y %I:%M %p",
+        "%d-%m-%y %I:%M:%S %p",
+        "%d-%m-%y %H:%M",
+        "%d-%m-%Y %I:%M %p",
+        "%d-%m-%Y %H:%M",
+    ];
+
+    let (date, time) = s.trim().split_once(char::is_whitespace)?;
+    let date = ascii_digits(date);
+    let s = format!("{} {}", numeric_month(&date)?, ascii_digits(time));
+    for fmt in &formats {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(&s, fmt) {
+            return Some(dt.and_utc());
+        }
+    }
+    None
+}
+
+/// Splits a TXT reply context line, `> Replying to author: text`, into the
+/// quoted author and text.
+fn reply_context(line: &str) -> Option<(&str, &str)> {
+    line.trim().strip_prefix("> Replying to ")?.split_once(": ")
+}
+
+/// Returns `content` without a leading `[Replying to: …]` line.
+fn unmarked(content: &str) -> &str {
+    match content.strip_prefix(REPLYING_TO_PREFIX) {
+        Some(marked) => marked.split_once('\n').map_or("", |(_, rest)| rest),
+        None => content,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_txt_timestamp_parsing() {
+        let ts = parse_txt_timestamp("1/15/2024 10:30 AM");
+        assert!(ts.is_some());
+
+        let ts = parse_txt_timestamp("12/31/2024 11:59 PM");
+        assert!(ts.is_some());
+
+        let ts = parse_txt_timestamp("1/1/2024 1:00 AM");
+        assert!(ts.is_some());
+
+        // 24-hour format
+        let ts = parse_txt_timestamp("1/15/2024 14:30");
+        assert!(ts.is_some());
+
+        // Month names in older exports
+        let ts = parse_txt_timestamp("15-Jan-24 10:30 PM");
+        assert_eq!(ts, parse_txt_timestamp("1/15/2024 22:30"));
+        let ts = parse_txt_timestamp("15-jan.-2024 22:30");
+        assert_eq!(ts, parse_txt_timestamp("1/15/2024 22:30"));
+    }
+
+    #[test]
+    fn test_parse_txt_month_name_headers() {
+        let txt = "[15-Jan-24 10:30 PM] alice\nHello\n[15-Jan-24 10:31 PM] bob\nHi";
+
+        let (messages, _) = parse_txt(txt, &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].sender, "bob");
+        assert_eq!(
+            messages[1].timestamp.unwrap().to_rfc3339(),
+            "2024-01-15T22:31:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_txt_basic() {
+        let txt = "[1/15/2024 10:30 AM] alice\nHello world\n[1/15/2024 10:31 AM] bob\nHi there";
+
+        let (messages, _) = parse_txt(txt, &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender, "alice");
+        assert_eq!(messages[0].content, "Hello world");
+        assert_eq!(messages[1].sender, "bob");
+        assert_eq!(messages[1].content, "Hi there");
+    }
+
+    #[test]
+    fn test_parse_txt_multiline() {
+        let txt = "[1/15/2024 10:30 AM] alice\nLine 1\nLine 2\nLine 3";
+
+        let (messages, _) = parse_txt(txt, &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "Line 1\nLine 2\nLine 3");
+    }
+
+    #[test]
+    fn test_parse_txt_with_attachments() {
+        let txt =
+            "[1/15/2024 10:30 AM] alice\nMessage\n{Attachments}\nhttps://cdn.discord.com/image.png";
+
+        let (messages, _) = parse_txt(txt, &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].content.contains("Message"));
+        assert!(messages[0].content.contains("[Attachment: image.png]"));
+    }
+
+    #[test]
+    fn test_parse_txt_with_stickers() {
+        let txt = "[1/15/2024 10:30 AM] alice\n{Stickers}\ncool_sticker";
+
+        let (messages, _) = parse_txt(txt, &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].content.contains("[Sticker: cool_sticker]"));
+    }
+
+    #[test]
+    fn test_parse_txt_reply_context() {
+        let txt = "[1/15/2024 10:30 AM] alice\nAre we still on for lunch tomorrow?\n\
+                   > Replying to alice: Are we still on for lunch…\n\
+                   [1/15/2024 10:31 AM] bob\nYes!\n\
+                   [1/15/2024 10:32 AM] carol\n> Replying to dave: Where is it?\nNo idea\n\
+                   [1/15/2024 10:33 AM] alice\n> Not a reply context";
+
+        let (messages, _) = parse_txt(txt, &DiscordConfig::new()).unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].content, "Are we still on for lunch tomorrow?");
+        assert_eq!(messages[1].content, "Yes!");
+        assert_eq!(messages[1].reply_to, Some(0));
+        assert_eq!(
+            messages[2].content,
+            "[Replying to: dave: Where is it?]\nNo idea"
+        );
+        assert_eq!(messages[2].reply_to, None);
+        assert_eq!(messages[3].content, "> Not a reply context");
+
+        let config = DiscordConfig::new().with_mark_unresolved_quotes(false);
+        let (messages, _) = parse_txt(txt, &config).unwrap();
+        assert_eq!(messages[2].content, "No idea");
+    }
+
+    #[test]
+    fn test_parse_txt_empty() {
+        let txt = "";
+        let (messages, _) = parse_txt(txt, &DiscordConfig::new()).unwrap();
+        assert!(messages.is_empty());
+    }
+}
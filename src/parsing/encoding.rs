@@ -0,0 +1,730 @@
+//! Text encoding detection and decoding for chat exports.
+//!
+//! Exports produced on Windows frequently start with a UTF-8 byte order mark,
+//! and some tools save WhatsApp chats as UTF-16. This module normalizes all of
+//! these into plain UTF-8 strings before the platform parsers see them.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "whatsapp")]
+//! # fn main() -> chatpack::Result<()> {
+//! use chatpack::parsing::encoding::{TextEncoding, decode_text, detect_encoding};
+//!
+//! let bytes = b"\xEF\xBB\xBF[1/15/24, 10:30 AM] Alice: Hi".to_vec();
+//! assert_eq!(detect_encoding(&bytes), TextEncoding::Utf8Bom);
+//!
+//! let text = decode_text(bytes)?;
+//! assert!(text.starts_with("[1/15/24"));
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "whatsapp"))]
+//! # fn main() {}
+//! ```
+
+use std::fs;
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+use std::io::{self, BufRead, BufReader, Read};
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::error::ChatpackError;
+#[cfg(any(feature = "whatsapp", feature = "discord-txt"))]
+use crate::sink::SkipReason;
+
+/// UTF-8 byte order mark.
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// UTF-16 little-endian byte order mark.
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+
+/// UTF-16 big-endian byte order mark.
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Number of leading bytes inspected by the null-byte heuristic.
+const SNIFF_LEN: usize = 512;
+
+/// Text encodings recognized in chat exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextEncoding {
+    /// Plain UTF-8 without a byte order mark.
+    Utf8,
+    /// UTF-8 prefixed with a byte order mark (`EF BB BF`).
+    Utf8Bom,
+    /// UTF-16 little-endian (BOM `FF FE` or detected heuristically).
+    Utf16Le,
+    /// UTF-16 big-endian (BOM `FE FF` or detected heuristically).
+    Utf16Be,
+}
+
+impl TextEncoding {
+    /// Returns the number of BOM bytes to skip for this encoding in `bytes`.
+    fn bom_len(self, bytes: &[u8]) -> usize {
+        match self {
+            TextEncoding::Utf8 => 0,
+            TextEncoding::Utf8Bom => UTF8_BOM.len(),
+            TextEncoding::Utf16Le if bytes.starts_with(UTF16_LE_BOM) => 2,
+            TextEncoding::Utf16Be if bytes.starts_with(UTF16_BE_BOM) => 2,
+            TextEncoding::Utf16Le | TextEncoding::Utf16Be => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextEncoding::Utf8 => write!(f, "UTF-8"),
+            TextEncoding::Utf8Bom => write!(f, "UTF-8 (BOM)"),
+            TextEncoding::Utf16Le => write!(f, "UTF-16LE"),
+            TextEncoding::Utf16Be => write!(f, "UTF-16BE"),
+        }
+    }
+}
+
+/// Detects the encoding of a byte buffer.
+///
+/// A byte order mark wins if present. Otherwise a null-byte heuristic is
+/// applied to the first bytes: text that is mostly ASCII encoded as UTF-16
+/// has a zero in every other byte.
+pub fn detect_encoding(bytes: &[u8]) -> TextEncoding {
+    if bytes.starts_with(UTF8_BOM) {
+        return TextEncoding::Utf8Bom;
+    }
+    if bytes.starts_with(UTF16_LE_BOM) {
+        return TextEncoding::Utf16Le;
+    }
+    if bytes.starts_with(UTF16_BE_BOM) {
+        return TextEncoding::Utf16Be;
+    }
+
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return TextEncoding::Utf8;
+    }
+
+    let (mut even_zeros, mut odd_zeros) = (0usize, 0usize);
+    for pair in sample.chunks_exact(2) {
+        if pair[0] == 0 {
+            even_zeros += 1;
+        }
+        if pair[1] == 0 {
+            odd_zeros += 1;
+        }
+    }
+
+    // Require at least a third of the code units to look like ASCII-in-UTF-16
+    // and no zeros on the other side, which rules out binary garbage.
+    let threshold = pairs.div_ceil(3);
+    if odd_zeros >= threshold && even_zeros == 0 {
+        TextEncoding::Utf16Le
+    } else if even_zeros >= threshold && odd_zeros == 0 {
+        TextEncoding::Utf16Be
+    } else {
+        TextEncoding::Utf8
+    }
+}
+
+/// Strips a leading UTF-8 byte order mark (`U+FEFF`) from a string.
+pub fn strip_bom(content: &str) -> &str {
+    content.strip_prefix('\u{FEFF}').unwrap_or(content)
+}
+
+/// Decodes raw file bytes into a UTF-8 string.
+///
+/// Handles plain UTF-8, UTF-8 with BOM, and UTF-16 (LE/BE). The BOM is
+/// removed from the result.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::InvalidFormat`] naming the detected encoding
+/// if the bytes cannot be decoded.
+pub fn decode_text(bytes: Vec<u8>) -> Result<String, ChatpackError> {
+    let encoding = detect_encoding(&bytes);
+    let skip = encoding.bom_len(&bytes);
+
+    match encoding {
+        TextEncoding::Utf8 | TextEncoding::Utf8Bom => {
+            let mut bytes = bytes;
+            bytes.drain(..skip);
+            String::from_utf8(bytes).map_err(|e| {
+                undecodable(
+                    encoding,
+                    format!(
+                        "invalid byte sequence at offset {}",
+                        e.utf8_error().valid_up_to() + skip
+                    ),
+                )
+            })
+        }
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+            let body = &bytes[skip..];
+            if !body.len().is_multiple_of(2) {
+                return Err(undecodable(encoding, "odd number of bytes"));
+            }
+            let big_endian = encoding == TextEncoding::Utf16Be;
+            let units = body
+                .chunks_exact(2)
+                .map(|pair| decode_unit([pair[0], pair[1]], big_endian));
+            char::decode_utf16(units)
+                .collect::<Result<String, _>>()
+                .map_err(|e| undecodable(encoding, e.to_string()))
+        }
+    }
+}
+
+/// Reads a text file, transparently handling BOMs and UTF-16.
+///
+/// This is what the platform parsers use instead of
+/// [`std::fs::read_to_string`].
+pub fn read_text_file(path: impl AsRef<Path>) -> Result<String, ChatpackError> {
+    decode_text(fs::read(path)?)
+}
+
+/// Like [`read_text_file`], but refuses files larger than `limit` bytes.
+///
+/// The size is checked from metadata before anything is read.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::FileTooLarge`] when the file exceeds `limit`.
+pub fn read_text_file_with_limit(
+    path: impl AsRef<Path>,
+    limit: u64,
+) -> Result<String, ChatpackError> {
+    check_file_size(path.as_ref(), limit)?;
+    read_text_file(path)
+}
+
+/// Reads and decodes at most `limit` leading bytes of a text file.
+///
+/// Meant for sniffing: a character cut off by the limit, or any undecodable
+/// tail, is dropped instead of reported.
+pub(crate) fn read_text_prefix(
+    path: impl AsRef<Path>,
+    limit: u64,
+) -> Result<String, ChatpackError> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    fs::File::open(path)?.take(limit).read_to_end(&mut bytes)?;
+    let encoding = detect_encoding(&bytes);
+    let body = &bytes[encoding.bom_len(&bytes)..];
+
+    Ok(match encoding {
+        TextEncoding::Utf8 | TextEncoding::Utf8Bom => match std::str::from_utf8(body) {
+            Ok(text) => text.to_string(),
+            Err(e) => String::from_utf8_lossy(&body[..e.valid_up_to()]).into_owned(),
+        },
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+            let big_endian = encoding == TextEncoding::Utf16Be;
+            let units = body
+                .chunks_exact(2)
+                .map(|pair| decode_unit([pair[0], pair[1]], big_endian));
+            char::decode_utf16(units).map_while(Result::ok).collect()
+        }
+    })
+}
+
+/// Returns an error if the file at `path` is larger than `limit` bytes.
+pub fn check_file_size(path: impl AsRef<Path>, limit: u64) -> Result<(), ChatpackError> {
+    let size = fs::metadata(path)?.len();
+    if size > limit {
+        return Err(ChatpackError::file_too_large(size, limit));
+    }
+    Ok(())
+}
+
+/// Returns `true` if the file at `path` is known to exceed `limit` bytes.
+///
+/// Metadata errors return `false` so the caller's own open reports them.
+#[cfg(all(
+    feature = "streaming",
+    any(feature = "whatsapp", feature = "instagram", feature = "discord-json")
+))]
+pub(crate) fn exceeds_file_size(path: impl AsRef<Path>, limit: u64) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.len() > limit)
+}
+
+/// Wraps a reader so that it yields UTF-8 regardless of the source encoding.
+///
+/// The encoding is detected from the first buffered bytes. A UTF-8 BOM is
+/// skipped; UTF-16 input is transcoded on the fly.
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+pub(crate) fn open_text_reader<R>(
+    reader: R,
+    buffer_size: usize,
+) -> io::Result<Box<dyn BufRead + Send>>
+where
+    R: Read + Send + 'static,
+{
+    let mut reader = BufReader::with_capacity(buffer_size, reader);
+    let encoding = detect_encoding(reader.fill_buf()?);
+    let skip = encoding.bom_len(reader.buffer());
+    reader.consume(skip);
+
+    Ok(match encoding {
+        TextEncoding::Utf8 | TextEncoding::Utf8Bom => Box::new(reader),
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => Box::new(BufReader::with_capacity(
+            buffer_size,
+            Utf16Reader::new(reader, encoding == TextEncoding::Utf16Be),
+        )),
+    })
+}
+
+/// Strips a trailing `\n` or `\r\n`, matching [`str::lines`].
+#[cfg(feature = "discord-json")]
+pub(crate) fn trim_line_ending(line: &str) -> &str {
+    line.strip_suffix('\n')
+        .map_or(line, |line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+/// Converts `\r\n` and lone `\r` line breaks inside `text` to `\n`.
+///
+/// For fields that can span lines, such as quoted CSV cells, which keep
+/// the line endings of the exporting system.
+#[cfg(feature = "discord-csv")]
+pub(crate) fn normalize_newlines(text: &str) -> String {
+    if text.contains('\r') {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Calls `f` with each line of `reader`, without its line ending.
+///
+/// One buffer is reused for every line. Stops as soon as `f` breaks.
+#[cfg(feature = "discord-json")]
+pub(crate) fn for_each_line<F>(
+    reader: &mut dyn BufRead,
+    mut f: F,
+) -> Result<ControlFlow<()>, ChatpackError>
+where
+    F: FnMut(&str) -> Result<ControlFlow<()>, ChatpackError>,
+{
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            return Ok(ControlFlow::Continue(()));
+        }
+        if f(trim_line_ending(&buf))?.is_break() {
+            return Ok(ControlFlow::Break(()));
+        }
+    }
+}
+
+/// A line read by [`read_capped_line`].
+#[cfg(feature = "whatsapp")]
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum CappedLine<'a> {
+    /// The line, without its line ending.
+    Line(&'a str),
+    /// A line longer than the cap, read past without being kept. Holds its
+    /// length in bytes, line ending included.
+    TooLong(usize),
+}
+
+/// Reads the next line of `reader` into `buf`, keeping at most `max_len`
+/// bytes of it (line ending aside) in memory.
+///
+/// Returns the line and the number of bytes consumed, or `None` at the end
+/// of the input.
+#[cfg(feature = "whatsapp")]
+pub(crate) fn read_capped_line<'a, R: BufRead + ?Sized>(
+    reader: &mut R,
+    buf: &'a mut Vec<u8>,
+    max_len: usize,
+) -> io::Result<Option<(CappedLine<'a>, usize)>> {
+    buf.clear();
+    // Room for a `\r\n`, so a line of exactly `max_len` bytes is read whole
+    let limit = max_len.saturating_add(2);
+    let read = (&mut *reader)
+        .take(u64::try_from(limit).unwrap_or(u64::MAX))
+        .read_until(b'\n', buf)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read == limit && !buf.ends_with(b"\n") {
+        let consumed = read + reader.skip_until(b'\n')?;
+        return Ok(Some((CappedLine::TooLong(consumed), consumed)));
+    }
+
+    let mut line = buf.strip_suffix(b"\n").unwrap_or(buf);
+    if buf.ends_with(b"\n") {
+        line = line.strip_suffix(b"\r").unwrap_or(line);
+    }
+    if line.len() > max_len {
+        return Ok(Some((CappedLine::TooLong(read), read)));
+    }
+    let line =
+        std::str::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(Some((CappedLine::Line(line), read)))
+}
+
+/// Calls `f` with the 1-based number and the contents of each line of
+/// `reader`. Lines longer than `max_len` bytes are passed as
+/// [`CappedLine::TooLong`] instead of being read into memory.
+///
+/// One buffer is reused for every line. Stops as soon as `f` breaks.
+#[cfg(feature = "whatsapp")]
+pub(crate) fn for_each_capped_line<F>(
+    reader: &mut dyn BufRead,
+    max_len: usize,
+    mut f: F,
+) -> Result<ControlFlow<()>, ChatpackError>
+where
+    F: FnMut(usize, CappedLine<'_>) -> Result<ControlFlow<()>, ChatpackError>,
+{
+    let mut buf = Vec::new();
+    let mut number = 0;
+    while let Some((line, _)) = read_capped_line(reader, &mut buf, max_len)? {
+        number += 1;
+        if f(number, line)?.is_break() {
+            return Ok(ControlFlow::Break(()));
+        }
+    }
+    Ok(ControlFlow::Continue(()))
+}
+
+/// Handles line `number`, `length` bytes long and over `max_len`: with
+/// `skip_invalid` it is skipped and the returned reason reported, otherwise
+/// parsing fails with [`ChatpackError::BufferOverflow`].
+#[cfg(any(feature = "whatsapp", feature = "discord-txt"))]
+pub(crate) fn long_line(
+    number: usize,
+    length: usize,
+    max_len: usize,
+    skip_invalid: bool,
+) -> Result<SkipReason, ChatpackError> {
+    if !skip_invalid {
+        return Err(ChatpackError::buffer_overflow(max_len, length));
+    }
+    Ok(SkipReason::InvalidRecord {
+        line: Some(number),
+        error: format!("line of {length} bytes is longer than max_line_length ({max_len})"),
+    })
+}
+
+fn decode_unit(pair: [u8; 2], big_endian: bool) -> u16 {
+    if big_endian {
+        u16::from_be_bytes(pair)
+    } else {
+        u16::from_le_bytes(pair)
+    }
+}
+
+fn undecodable(encoding: TextEncoding, detail: impl std::fmt::Display) -> ChatpackError {
+    ChatpackError::invalid_format(
+        "text",
+        format!(
+            "Input could not be decoded (detected encoding: {}): {}",
+            encoding, detail
+        ),
+    )
+}
+
+/// Streaming UTF-16 to UTF-8 transcoder.
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+struct Utf16Reader<R> {
+    inner: R,
+    big_endian: bool,
+    /// Transcoded UTF-8 bytes not yet handed out.
+    out: Vec<u8>,
+    pos: usize,
+    /// Odd trailing byte from the previous read.
+    carry: Option<u8>,
+    /// High surrogate waiting for its pair.
+    surrogate: Option<u16>,
+}
+
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+impl<R: Read> Utf16Reader<R> {
+    fn new(inner: R, big_endian: bool) -> Self {
+        Self {
+            inner,
+            big_endian,
+            out: Vec::new(),
+            pos: 0,
+            carry: None,
+            surrogate: None,
+        }
+    }
+
+    /// Refills the output buffer. Returns `false` at end of input.
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut raw = [0u8; 8192];
+        let n = self.inner.read(&mut raw)?;
+        if n == 0 {
+            if self.carry.is_some() || self.surrogate.is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "truncated UTF-16 sequence at end of input",
+                ));
+            }
+            return Ok(false);
+        }
+
+        let mut bytes = Vec::with_capacity(n + 1);
+        bytes.extend(self.carry.take());
+        bytes.extend_from_slice(&raw[..n]);
+
+        let mut units: Vec<u16> = self.surrogate.take().into_iter().collect();
+        let chunks = bytes.chunks_exact(2);
+        self.carry = chunks.remainder().first().copied();
+        units.extend(chunks.map(|pair| decode_unit([pair[0], pair[1]], self.big_endian)));
+
+        if units.last().is_some_and(|u| (0xD800..0xDC00).contains(u)) {
+            self.surrogate = units.pop();
+        }
+
+        self.out.clear();
+        self.pos = 0;
+        let mut utf8 = [0u8; 4];
+        for ch in char::decode_utf16(units) {
+            let ch = ch.map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("invalid UTF-16: {e}"))
+            })?;
+            self.out
+                .extend_from_slice(ch.encode_utf8(&mut utf8).as_bytes());
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+impl<R: Read> Read for Utf16Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.out.len() {
+            if !self.fill()? {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(s: &str, bom: bool) -> Vec<u8> {
+        let mut out = if bom { UTF16_LE_BOM.to_vec() } else { vec![] };
+        for unit in s.encode_utf16() {
+            out.extend_from_slice(&unit.to_le_bytes());
+        }
+        out
+    }
+
+    fn utf16be(s: &str) -> Vec<u8> {
+        let mut out = UTF16_BE_BOM.to_vec();
+        for unit in s.encode_utf16() {
+            out.extend_from_slice(&unit.to_be_bytes());
+        }
+        out
+    }
+
+    // =========================================================================
+    // Detection tests
+    // =========================================================================
+
+    #[test]
+    fn test_detect_plain_utf8() {
+        assert_eq!(detect_encoding(b"hello world"), TextEncoding::Utf8);
+        assert_eq!(detect_encoding("Привет".as_bytes()), TextEncoding::Utf8);
+        assert_eq!(detect_encoding(b""), TextEncoding::Utf8);
+    }
+
+    #[test]
+    fn test_detect_boms() {
+        assert_eq!(detect_encoding(b"\xEF\xBB\xBFhi"), TextEncoding::Utf8Bom);
+        assert_eq!(detect_encoding(&utf16le("hi", true)), TextEncoding::Utf16Le);
+        assert_eq!(detect_encoding(&utf16be("hi")), TextEncoding::Utf16Be);
+    }
+
+    #[test]
+    fn test_detect_utf16_without_bom() {
+        let bytes = utf16le("[1/15/24, 10:30 AM] Alice: Hello", false);
+        assert_eq!(detect_encoding(&bytes), TextEncoding::Utf16Le);
+
+        let mut be = utf16be("[1/15/24, 10:30 AM] Alice: Hello");
+        be.drain(..2);
+        assert_eq!(detect_encoding(&be), TextEncoding::Utf16Be);
+    }
+
+    #[test]
+    fn test_encoding_display() {
+        assert_eq!(TextEncoding::Utf16Le.to_string(), "UTF-16LE");
+        assert_eq!(TextEncoding::Utf8Bom.to_string(), "UTF-8 (BOM)");
+    }
+
+    // =========================================================================
+    // Decoding tests
+    // =========================================================================
+
+    #[test]
+    fn test_decode_strips_utf8_bom() {
+        let text = decode_text(b"\xEF\xBB\xBFHello".to_vec()).unwrap();
+        assert_eq!(text, "Hello");
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let text = decode_text(utf16le("Привет 👋", true)).unwrap();
+        assert_eq!(text, "Привет 👋");
+
+        let text = decode_text(utf16be("Hello")).unwrap();
+        assert_eq!(text, "Hello");
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8_names_encoding() {
+        let err = decode_text(vec![b'a', 0xFF, 0xFE, b'b']).unwrap_err();
+        assert!(err.is_invalid_format());
+        assert!(err.to_string().contains("UTF-8"));
+        assert!(err.to_string().contains("offset 1"));
+    }
+
+    #[test]
+    fn test_decode_invalid_utf16_names_encoding() {
+        // Lone low surrogate
+        let err = decode_text(vec![0xFF, 0xFE, 0x00, 0xDC]).unwrap_err();
+        assert!(err.to_string().contains("UTF-16LE"));
+
+        let err = decode_text(vec![0xFF, 0xFE, b'a']).unwrap_err();
+        assert!(err.to_string().contains("odd number of bytes"));
+    }
+
+    #[test]
+    fn test_strip_bom() {
+        assert_eq!(strip_bom("\u{FEFF}abc"), "abc");
+        assert_eq!(strip_bom("abc"), "abc");
+    }
+
+    // =========================================================================
+    // Reader tests
+    // =========================================================================
+
+    #[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+    #[test]
+    fn test_open_text_reader_utf16() {
+        let source = "line one 🎉\nline two\n".repeat(1000);
+        let bytes = utf16le(&source, true);
+        let mut reader = open_text_reader(io::Cursor::new(bytes), 64).unwrap();
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        assert_eq!(out, source);
+    }
+
+    #[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+    #[test]
+    fn test_open_text_reader_skips_bom() {
+        let mut reader =
+            open_text_reader(io::Cursor::new(b"\xEF\xBB\xBFabc".to_vec()), 64).unwrap();
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        assert_eq!(out, "abc");
+    }
+
+    #[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+    #[test]
+    fn test_utf16_reader_truncated() {
+        let mut reader = Utf16Reader::new(io::Cursor::new(vec![b'a', 0, b'b']), false);
+        let mut out = String::new();
+        assert!(reader.read_to_string(&mut out).is_err());
+    }
+
+    // =========================================================================
+    // Size limit tests
+    // =========================================================================
+
+    #[test]
+    fn test_read_text_file_with_limit() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        // Sparse file: 4MB on disk metadata, nothing actually written
+        file.as_file().set_len(4 * 1024 * 1024).unwrap();
+
+        let err = read_text_file_with_limit(file.path(), 1024 * 1024).unwrap_err();
+        assert!(matches!(
+            err,
+            ChatpackError::FileTooLarge { size, limit, .. }
+                if size == 4 * 1024 * 1024 && limit == 1024 * 1024
+        ));
+
+        let content = read_text_file_with_limit(file.path(), 8 * 1024 * 1024).unwrap();
+        assert_eq!(content.len(), 4 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_read_text_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chat.txt");
+
+        // Limit falls inside the two-byte 'é'
+        fs::write(&path, "\u{FEFF}caf\u{e9} au lait").unwrap();
+        assert_eq!(read_text_prefix(&path, 7).unwrap(), "caf");
+        assert_eq!(read_text_prefix(&path, 100).unwrap(), "caf\u{e9} au lait");
+
+        fs::write(&path, utf16le("[1/15/24] Alice", true)).unwrap();
+        assert_eq!(read_text_prefix(&path, 10).unwrap(), "[1/1");
+        // Half a code unit is dropped
+        assert_eq!(read_text_prefix(&path, 9).unwrap(), "[1/");
+    }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_read_capped_line() {
+        let input = "short\r\nexactly10!\n0123456789A\nlast";
+        let mut reader = io::Cursor::new(input.as_bytes());
+        let mut buf = Vec::new();
+        let mut lines = Vec::new();
+        while let Some((line, read)) = read_capped_line(&mut reader, &mut buf, 10).unwrap() {
+            lines.push((format!("{line:?}"), read));
+        }
+        assert_eq!(
+            lines,
+            [
+                (r#"Line("short")"#.to_string(), 7),
+                (r#"Line("exactly10!")"#.to_string(), 11),
+                ("TooLong(12)".to_string(), 12),
+                (r#"Line("last")"#.to_string(), 4),
+            ]
+        );
+    }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_read_capped_line_keeps_buffer_small() {
+        let input = format!("{}\nafter", "x".repeat(100_000));
+        let mut reader = BufReader::with_capacity(64, io::Cursor::new(input.into_bytes()));
+        let mut buf = Vec::new();
+        let (line, read) = read_capped_line(&mut reader, &mut buf, 16)
+            .unwrap()
+            .unwrap();
+        assert_eq!(line, CappedLine::TooLong(100_001));
+        assert_eq!(read, 100_001);
+        assert!(buf.capacity() < 1024);
+        let (line, _) = read_capped_line(&mut reader, &mut buf, 16)
+            .unwrap()
+            .unwrap();
+        assert_eq!(line, CappedLine::Line("after"));
+    }
+
+    #[cfg(feature = "discord-csv")]
+    #[test]
+    fn test_normalize_newlines() {
+        assert_eq!(normalize_newlines("a\r\nb\rc\nd"), "a\nb\nc\nd");
+        assert_eq!(normalize_newlines("plain"), "plain");
+    }
+
+    #[test]
+    fn test_check_file_size_missing_file() {
+        let err = check_file_size("/nonexistent/chatpack/file", 0).unwrap_err();
+        assert!(err.is_io());
+    }
+}
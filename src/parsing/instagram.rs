@@ -3,10 +3,14 @@
 //! This module contains types and functions shared between the standard
 //! and streaming Instagram parsers.
 
+use std::collections::{BTreeMap, BTreeSet};
+
 use chrono::{DateTime, TimeZone, Utc};
 use serde::Deserialize;
 
-use crate::Message;
+use crate::parsing::allowlist::allows;
+use crate::parsing::calls::{VOICE_MESSAGE, call_marker};
+use crate::{AttachmentRef, Message};
 
 /// Raw Instagram message structure for deserialization.
 #[derive(Debug, Deserialize)]
@@ -21,6 +25,9 @@ pub struct InstagramRawMessage {
     pub videos: Option<Vec<InstagramMedia>>,
     #[serde(default)]
     pub audio_files: Option<Vec<InstagramMedia>>,
+    /// Length of a call in seconds; 0 for a missed call
+    #[serde(default)]
+    pub call_duration: Option<u64>,
 }
 
 /// Instagram share structure.
@@ -42,6 +49,43 @@ pub struct InstagramExport {
     pub messages: Vec<InstagramRawMessage>,
 }
 
+/// The participant list of an Instagram export, read without its
+/// messages.
+#[derive(Debug, Default, Deserialize)]
+pub struct InstagramParticipants {
+    #[serde(default)]
+    pub participants: Vec<InstagramParticipant>,
+}
+
+/// One entry of an export's `participants` array.
+#[derive(Debug, Deserialize)]
+pub struct InstagramParticipant {
+    pub name: String,
+}
+
+impl InstagramParticipants {
+    /// Reads the participant list of `content`, or an empty list if the
+    /// export has none or is not valid JSON.
+    pub fn read(content: &str) -> Self {
+        serde_json::from_str(content).unwrap_or_default()
+    }
+
+    /// Returns the current participant names, with the mojibake fix
+    /// applied when `fix_encoding` is set.
+    pub fn names(&self, fix_encoding: bool) -> Vec<String> {
+        self.participants
+            .iter()
+            .map(|p| {
+                if fix_encoding && !p.name.is_ascii() {
+                    fix_mojibake_encoding(&p.name)
+                } else {
+                    p.name.clone()
+                }
+            })
+            .collect()
+    }
+}
+
 /// Fix Meta's broken encoding (Mojibake).
 ///
 /// Meta exports UTF-8 text encoded as if it were ISO-8859-1.
@@ -126,6 +170,148 @@ pub fn parse_instagram_message_owned(
     ))
 }
 
+/// Parses a raw Instagram message like [`parse_instagram_message_owned`],
+/// also collecting its photos, videos, and audio into `Message::attachments`.
+///
+/// Messages with media but no text are kept, with an `[Attachment: name]`
+/// marker as their content.
+pub fn parse_instagram_message_with_attachments(
+    mut msg: InstagramRawMessage,
+    fix_encoding: bool,
+) -> Option<Message> {
+    let attachments = instagram_attachment_refs(&msg);
+
+    if !has_text(&msg) && !attachments.is_empty() {
+        let markers: Vec<String> = attachments
+            .iter()
+            .map(|a| format!("[Attachment: {}]", a.file_name))
+            .collect();
+        msg.content = Some(markers.join("\n"));
+    }
+
+    let mut message = parse_instagram_message_owned(msg, fix_encoding)?;
+    message.attachments = attachments;
+    Some(message)
+}
+
+/// Rewrites a call record's content to `[Call: 12m 30s]` or
+/// `[Missed call]`, and gives an audio message without text
+/// `[Voice message]` as its content.
+///
+/// Parsers apply this before parsing when
+/// [`InstagramConfig::include_calls`](crate::config::InstagramConfig::include_calls)
+/// is set.
+pub fn mark_instagram_call(msg: &mut InstagramRawMessage) {
+    if msg.call_duration.is_some() {
+        msg.content = Some(call_marker(msg.call_duration));
+    } else if !has_text(msg) && msg.audio_files.as_ref().is_some_and(|a| !a.is_empty()) {
+        msg.content = Some(VOICE_MESSAGE.to_string());
+    }
+}
+
+/// Finds the senders of `messages` missing from `participants`, the
+/// export's current names, and with `rename` maps old names of a
+/// two-person thread onto a current participant.
+///
+/// An old name is mapped to the one participant whose messages all come
+/// before its first message or after its last, since a renamed person
+/// never writes under both names at once. If neither or both
+/// participants fit, it stays unknown. Renamed messages keep the old name
+/// in `original_sender`. Group threads are never renamed.
+///
+/// Returns the names mapped, old to current, and the senders left
+/// unknown. Both are empty when `participants` is.
+///
+/// `messages` must be in chronological order.
+pub fn canonicalize_instagram_senders(
+    messages: &mut [Message],
+    participants: &[String],
+    rename: bool,
+) -> (BTreeMap<String, String>, BTreeSet<String>) {
+    let mut renamed = BTreeMap::new();
+    if participants.is_empty() {
+        return (renamed, BTreeSet::new());
+    }
+
+    // First and last position of each sender
+    let mut spans: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for (i, msg) in messages.iter().enumerate() {
+        match spans.get_mut(&msg.sender) {
+            Some(span) => span.1 = i,
+            None => {
+                spans.insert(msg.sender.clone(), (i, i));
+            }
+        }
+    }
+    let mut unknown: BTreeSet<String> = spans
+        .keys()
+        .filter(|sender| !participants.contains(sender))
+        .cloned()
+        .collect();
+
+    if rename && participants.len() == 2 {
+        for old in &unknown {
+            let (first, last) = spans[old];
+            let apart = |name: &&String| {
+                spans
+                    .get(name.as_str())
+                    .is_none_or(|&(f, l)| l < first || last < f)
+            };
+            let mut candidates = participants.iter().filter(apart);
+            if let (Some(current), None) = (candidates.next(), candidates.next()) {
+                renamed.insert(old.clone(), current.clone());
+            }
+        }
+        for msg in messages.iter_mut() {
+            if let Some(current) = renamed.get(&msg.sender) {
+                let old = std::mem::replace(&mut msg.sender, current.clone());
+                msg.original_sender.get_or_insert(old);
+            }
+        }
+        unknown.retain(|sender| !renamed.contains_key(sender));
+    }
+    (renamed, unknown)
+}
+
+/// Returns whether the sender of `msg` passes `allowlist`.
+///
+/// Names are compared after the mojibake fix when `fix_encoding` is set,
+/// so the allowlist holds names as they appear in parsed messages. The fix
+/// is only run for non-ASCII names and only when there is an allowlist.
+pub(crate) fn instagram_sender_allowed(
+    msg: &InstagramRawMessage,
+    fix_encoding: bool,
+    allowlist: Option<&[String]>,
+) -> bool {
+    if allowlist.is_none() {
+        return true;
+    }
+    if fix_encoding && !msg.sender_name.is_ascii() {
+        allows(allowlist, &fix_mojibake_encoding(&msg.sender_name))
+    } else {
+        allows(allowlist, &msg.sender_name)
+    }
+}
+
+fn has_text(msg: &InstagramRawMessage) -> bool {
+    msg.content
+        .as_ref()
+        .or_else(|| msg.share.as_ref().and_then(|s| s.share_text.as_ref()))
+        .is_some_and(|c| !c.trim().is_empty())
+}
+
+/// Returns the media files a raw Instagram message references, as paths
+/// relative to the export root.
+pub fn instagram_attachment_refs(msg: &InstagramRawMessage) -> Vec<AttachmentRef> {
+    [&msg.photos, &msg.videos, &msg.audio_files]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|media| media.uri.as_deref())
+        .map(AttachmentRef::from_path)
+        .collect()
+}
+
 /// Parses a raw Instagram message into a `Message` (reference version).
 ///
 /// Use this version when you need to borrow the message (e.g., streaming).
@@ -198,6 +384,7 @@ mod tests {
             photos: None,
             videos: None,
             audio_files: None,
+            call_duration: None,
         };
 
         let result = parse_instagram_message(&msg, false);
@@ -221,6 +408,7 @@ mod tests {
             photos: None,
             videos: None,
             audio_files: None,
+            call_duration: None,
         };
 
         let result = parse_instagram_message(&msg, false);
@@ -240,8 +428,103 @@ mod tests {
             photos: None,
             videos: None,
             audio_files: None,
+            call_duration: None,
         };
 
         assert!(parse_instagram_message(&msg, false).is_none());
     }
+
+    #[test]
+    fn test_parse_with_attachments_keeps_media_only() {
+        let media = |uri: &str| InstagramMedia {
+            uri: Some(uri.to_string()),
+        };
+        let msg = InstagramRawMessage {
+            sender_name: "user".to_string(),
+            timestamp_ms: 1705315800000,
+            content: None,
+            share: None,
+            photos: Some(vec![media("messages/inbox/chat_1/photos/1.jpg")]),
+            videos: Some(vec![media("messages/inbox/chat_1/videos/2.mp4")]),
+            audio_files: None,
+            call_duration: None,
+        };
+
+        let parsed = parse_instagram_message_with_attachments(msg, true).unwrap();
+
+        assert_eq!(parsed.content, "[Attachment: 1.jpg]\n[Attachment: 2.mp4]");
+        assert_eq!(parsed.attachments.len(), 2);
+        assert_eq!(
+            parsed.attachments[1].path.as_deref(),
+            Some("messages/inbox/chat_1/videos/2.mp4")
+        );
+    }
+
+    fn senders(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.sender.as_str()).collect()
+    }
+
+    #[test]
+    fn test_canonicalize_maps_old_name_in_two_person_thread() {
+        let mut messages = vec![
+            Message::new("alice", "hi"),
+            Message::new("bob_old", "hey"),
+            Message::new("alice", "new name?"),
+            Message::new("bob_old", "soon"),
+            Message::new("bob", "done"),
+            Message::new("alice", "nice"),
+        ];
+        let participants = ["alice".to_string(), "bob".to_string()];
+
+        let (renamed, unknown) = canonicalize_instagram_senders(&mut messages, &participants, true);
+
+        assert_eq!(renamed["bob_old"], "bob");
+        assert!(unknown.is_empty());
+        assert_eq!(
+            senders(&messages),
+            ["alice", "bob", "alice", "bob", "bob", "alice"]
+        );
+        assert_eq!(messages[1].original_sender.as_deref(), Some("bob_old"));
+        assert_eq!(messages[4].original_sender, None);
+    }
+
+    #[test]
+    fn test_canonicalize_keeps_ambiguous_names() {
+        // Both participants write while "someone" does
+        let mut messages = vec![
+            Message::new("alice", "hi"),
+            Message::new("someone", "hey"),
+            Message::new("bob", "who?"),
+            Message::new("alice", "yes, who?"),
+            Message::new("someone", "me"),
+        ];
+        let participants = ["alice".to_string(), "bob".to_string()];
+
+        let (renamed, unknown) = canonicalize_instagram_senders(&mut messages, &participants, true);
+
+        assert!(renamed.is_empty());
+        assert_eq!(unknown.into_iter().collect::<Vec<_>>(), ["someone"]);
+        assert_eq!(messages[1].sender, "someone");
+    }
+
+    #[test]
+    fn test_canonicalize_only_reports_without_rename() {
+        let mut messages = vec![Message::new("bob_old", "hey"), Message::new("alice", "hi")];
+        let participants = ["alice".to_string(), "bob".to_string()];
+
+        let (renamed, unknown) =
+            canonicalize_instagram_senders(&mut messages, &participants, false);
+
+        assert!(renamed.is_empty());
+        assert!(unknown.contains("bob_old"));
+        assert_eq!(messages[0].sender, "bob_old");
+    }
+
+    #[test]
+    fn test_canonicalize_without_participants() {
+        let mut messages = vec![Message::new("alice", "hi")];
+        let (renamed, unknown) = canonicalize_instagram_senders(&mut messages, &[], true);
+        assert!(renamed.is_empty());
+        assert!(unknown.is_empty());
+    }
 }
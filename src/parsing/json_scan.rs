@@ -0,0 +1,206 @@
+//! Object scanning for JSON exports with a top-level `messages` array.
+//!
+//! The streaming parsers find record boundaries with
+//! [`update_json_object_state`] instead of a full JSON parser, so they can
+//! hand each `{...}` record to serde on its own. The batch parsers use the
+//! same scanner to recover exports cut off partway through the array, such
+//! as those left by an interrupted download.
+
+use crate::error::ChatpackError;
+
+/// Advances the record scanner by one character.
+///
+/// Tracks whether `ch` is inside a string (and whether the next character
+/// is escaped) and the depth of `{` nesting outside strings.
+pub(crate) fn update_json_object_state(
+    ch: char,
+    in_string: &mut bool,
+    escaped: &mut bool,
+    brace_depth: &mut i32,
+) {
+    if *in_string {
+        if *escaped {
+            *escaped = false;
+        } else if ch == '\\' {
+            *escaped = true;
+        } else if ch == '"' {
+            *in_string = false;
+        }
+    } else {
+        match ch {
+            '"' => *in_string = true,
+            '{' => *brace_depth += 1,
+            '}' => *brace_depth -= 1,
+            _ => {}
+        }
+    }
+}
+
+/// Returns the offset just past the `key` of the top-level object, ignoring
+/// the same text in strings and nested objects.
+fn find_top_level_key(content: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{key}\"");
+    let (mut in_string, mut escaped, mut depth) = (false, false, 0);
+    for (offset, ch) in content.char_indices() {
+        if ch == '"' && !in_string && depth == 1 && content[offset..].starts_with(&quoted) {
+            let key_end = offset + quoted.len();
+            if content[key_end..].trim_start().starts_with(':') {
+                return Some(key_end);
+            }
+        }
+        update_json_object_state(ch, &mut in_string, &mut escaped, &mut depth);
+    }
+    None
+}
+
+/// Closes a JSON document that ends inside the `array_name` array of its
+/// top-level object.
+///
+/// Returns `content` up to the end of the last complete record in the
+/// array, followed by the `]}` that closes it, and `true` for the cut-off
+/// array. If the array is closed but the object around it isn't, returns
+/// `content` through the `]`, followed by `}`, and `false`. Returns `None`
+/// if the array cannot be found or has no complete record.
+pub(crate) fn close_truncated_array(content: &str, array_name: &str) -> Option<(String, bool)> {
+    let key_end = find_top_level_key(content, array_name)?;
+    let array_start = key_end + content[key_end..].find('[')? + 1;
+
+    let (mut in_string, mut escaped, mut depth) = (false, false, 0);
+    let mut end = None;
+    for (offset, ch) in content[array_start..].char_indices() {
+        if depth == 0 {
+            match ch {
+                '{' => depth = 1,
+                ']' => {
+                    let end = array_start + offset + 1;
+                    return Some((format!("{}}}", &content[..end]), false));
+                }
+                c if c.is_whitespace() || c == ',' => {}
+                _ => return None,
+            }
+            continue;
+        }
+        update_json_object_state(ch, &mut in_string, &mut escaped, &mut depth);
+        if depth == 0 {
+            end = Some(array_start + offset + 1);
+        }
+    }
+
+    let end = end?;
+    let mut closed = String::with_capacity(end + 8);
+    closed.push_str(&content[..end]);
+    closed.push_str("]}");
+    Some((closed, true))
+}
+
+/// Runs `decode` on `content`, recovering from truncation if `tolerate` is
+/// set.
+///
+/// When `decode` fails because `content` ends inside its `messages` array,
+/// the complete records before the cut are decoded instead. An export cut
+/// after the array is decoded whole. Returns the decoded value and whether
+/// a record may have been cut off. Exports with no complete record keep
+/// the original error.
+pub(crate) fn decode_tolerating_truncation<T>(
+    content: &str,
+    tolerate: bool,
+    mut decode: impl FnMut(&str) -> Result<T, ChatpackError>,
+) -> Result<(T, bool), ChatpackError> {
+    match decode(content) {
+        Err(ChatpackError::Json(e)) if tolerate && e.is_eof() => {
+            match close_truncated_array(content, "messages") {
+                Some((closed, cut)) => Ok((decode(&closed)?, cut)),
+                None => Err(e.into()),
+            }
+        }
+        result => result.map(|value| (value, false)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_close_truncated_array() {
+        let head = r#"{"name": "x", "meta": {"a": 1}, "messages": [
+            {"id": 1, "text": "a } b"},
+            {"id": 2, "text": "c"},"#;
+
+        let mid_object = format!(r#"{head} {{"id": 3, "te"#);
+        let expected = (format!("{}]}}", head.trim_end_matches(',')), true);
+        assert_eq!(
+            close_truncated_array(&mid_object, "messages"),
+            Some(expected.clone())
+        );
+        let mid_string = format!(r#"{head} {{"id": 3, "text": "unfinished {{"#);
+        assert_eq!(
+            close_truncated_array(&mid_string, "messages"),
+            Some(expected)
+        );
+
+        // The key is only matched in the top-level object
+        let decoys = r#"{"chat": {"messages": [{"id": 0}]}, "name": "\"messages\": [", "messages": [{"id": 1}, {"id""#;
+        assert_eq!(
+            close_truncated_array(decoys, "messages"),
+            Some((
+                r#"{"chat": {"messages": [{"id": 0}]}, "name": "\"messages\": [", "messages": [{"id": 1}]}"#
+                    .to_string(),
+                true
+            ))
+        );
+        let nested_only = r#"{"chat": {"messages": [{"id": 1}, {"id""#;
+        assert_eq!(close_truncated_array(nested_only, "messages"), None);
+
+        // A cut after the array only loses the end of the object around it
+        let after_array = format!("{}\n]", head.trim_end_matches(','));
+        assert_eq!(
+            close_truncated_array(&after_array, "messages"),
+            Some((format!("{after_array}}}"), false))
+        );
+        let after_empty = r#"{"meta": {"messages": [{"id": 0}]}, "messages": [],"#;
+        assert_eq!(
+            close_truncated_array(after_empty, "messages"),
+            Some((
+                r#"{"meta": {"messages": [{"id": 0}]}, "messages": []}"#.to_string(),
+                false
+            ))
+        );
+    }
+
+    #[test]
+    fn test_close_truncated_array_without_records() {
+        assert_eq!(
+            close_truncated_array(r#"{"messages": [{"id": 1"#, "messages"),
+            None
+        );
+        assert_eq!(close_truncated_array(r#"{"name": "x""#, "messages"), None);
+    }
+
+    #[test]
+    fn test_decode_tolerating_truncation() {
+        let decode = |s: &str| -> Result<usize, ChatpackError> {
+            let value: serde_json::Value = serde_json::from_str(s)?;
+            Ok(value["messages"].as_array().map_or(0, Vec::len))
+        };
+        let truncated = r#"{"messages": [{"id": 1}, {"id": 2}, {"id"#;
+
+        assert_eq!(
+            decode_tolerating_truncation(truncated, true, decode).unwrap(),
+            (2, true)
+        );
+        assert!(decode_tolerating_truncation(truncated, false, decode).is_err());
+        assert_eq!(
+            decode_tolerating_truncation(r#"{"messages": [{"id": 1}]}"#, true, decode).unwrap(),
+            (1, false)
+        );
+        assert_eq!(
+            decode_tolerating_truncation(r#"{"messages": [{"id": 1}]"#, true, decode).unwrap(),
+            (1, false)
+        );
+        // Syntax errors other than a cut-off end are not recovered
+        assert!(
+            decode_tolerating_truncation(r#"{"messages": [{"id": 1}, x]}"#, true, decode).is_err()
+        );
+    }
+}
@@ -3,6 +3,35 @@
 //! This module contains common types and functions used by both
 //! standard (in-memory) and streaming parsers to avoid code duplication.
 
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+pub(crate) mod allowlist;
+#[cfg(any(feature = "telegram", feature = "instagram"))]
+pub mod calls;
+pub mod content;
+#[cfg(any(feature = "whatsapp", feature = "discord-txt", feature = "discord-csv"))]
+pub(crate) mod digits;
+pub mod encoding;
+#[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
+pub(crate) mod json_scan;
+#[cfg(any(feature = "whatsapp", feature = "discord-txt"))]
+pub(crate) mod months;
+#[cfg(any(feature = "whatsapp", feature = "discord-txt"))]
+pub(crate) mod quotes;
+#[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
+pub(crate) mod raw;
+#[cfg(any(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json"
+))]
+pub(crate) mod timestamps;
+
 #[cfg(feature = "telegram")]
 pub mod telegram;
 
@@ -12,16 +41,25 @@ pub mod instagram;
 #[cfg(feature = "whatsapp")]
 pub mod whatsapp;
 
-#[cfg(feature = "discord")]
+#[cfg(feature = "discord-json")]
 pub mod discord;
 
 // Re-export commonly used items
+pub use encoding::{
+    TextEncoding, check_file_size, decode_text, read_text_file, read_text_file_with_limit,
+    strip_bom,
+};
+
 #[cfg(feature = "telegram")]
-pub use telegram::{TelegramRawMessage, extract_telegram_text, parse_telegram_message};
+pub use telegram::{
+    GENERAL_TOPIC, TelegramRawMessage, extract_telegram_text, extract_telegram_text_with,
+    extract_text_entities, extract_text_entities_with, parse_local_date, parse_telegram_message,
+    parse_telegram_message_with_config, parse_telegram_message_with_offset, telegram_export_chunks,
+};
 
 #[cfg(feature = "instagram")]
 pub use instagram::{
-    InstagramRawMessage, fix_mojibake_encoding, parse_instagram_message,
+    InstagramRawMessage, fix_mojibake_encoding, mark_instagram_call, parse_instagram_message,
     parse_instagram_message_owned,
 };
 
@@ -31,5 +69,5 @@ pub use whatsapp::{
     parse_whatsapp_timestamp,
 };
 
-#[cfg(feature = "discord")]
-pub use discord::{DiscordRawMessage, parse_discord_message};
+#[cfg(feature = "discord-json")]
+pub use discord::{DiscordRawMessage, is_jsonl, parse_discord_jsonl_line, parse_discord_message};
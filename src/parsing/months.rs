@@ -0,0 +1,94 @@
+//! Month names in timestamps matched in text exports.
+//!
+//! Some WhatsApp and Discord TXT exports spell the month out, as in
+//! `15 Jan 2024` or `15-Jan-24`. chrono only reads English names, and not
+//! with a trailing period, so the matched date goes through
+//! [`numeric_month`] before parsing, which swaps the name for its number.
+//! Names are looked up in [`MONTH_NAMES`], one row per language.
+
+use std::borrow::Cow;
+
+/// Full month names by language, January first, in lowercase.
+///
+/// A name matches its full form or any abbreviation of it at least three
+/// letters long, so `"Jan"`, `"jan."`, `"Sept"`, and `"January"` all match
+/// in English. Languages are tried in order.
+const MONTH_NAMES: &[(&str, [&str; 12])] = &[(
+    "en",
+    [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ],
+)];
+
+/// Returns the number, from 1, of the month named `name`, ignoring case
+/// and a trailing period.
+fn month_number(name: &str) -> Option<usize> {
+    let name = name.strip_suffix('.').unwrap_or(name).to_lowercase();
+    if name.chars().count() < 3 {
+        return None;
+    }
+    MONTH_NAMES.iter().find_map(|(_, months)| {
+        months
+            .iter()
+            .position(|month| month.starts_with(&name))
+            .map(|i| i + 1)
+    })
+}
+
+/// Replaces the month name in `date` with its two-digit number, borrowing
+/// `date` when it has no letters. Returns `None` for an unknown name.
+///
+/// `"15 Jan. 2024"` becomes `"15 01 2024"` and `"15-jan-24"` becomes
+/// `"15-01-24"`.
+pub(crate) fn numeric_month(date: &str) -> Option<Cow<'_, str>> {
+    let Some(start) = date.find(char::is_alphabetic) else {
+        return Some(Cow::Borrowed(date));
+    };
+    let len = date[start..]
+        .find(|c: char| !c.is_alphabetic() && c != '.')
+        .unwrap_or(date.len() - start);
+    let month = month_number(&date[start..start + len])?;
+    Some(Cow::Owned(format!(
+        "{}{month:02}{}",
+        &date[..start],
+        &date[start + len..]
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_month_number() {
+        assert_eq!(month_number("Jan"), Some(1));
+        assert_eq!(month_number("jan."), Some(1));
+        assert_eq!(month_number("SEPT"), Some(9));
+        assert_eq!(month_number("December"), Some(12));
+        assert_eq!(month_number("ma"), None);
+        assert_eq!(month_number("Janvier"), None);
+    }
+
+    #[test]
+    fn test_numeric_month() {
+        assert_eq!(numeric_month("15 Jan. 2024").unwrap(), "15 01 2024");
+        assert_eq!(numeric_month("15-jan-24").unwrap(), "15-01-24");
+        assert_eq!(numeric_month("3 Oct 2024").unwrap(), "3 10 2024");
+        assert!(matches!(
+            numeric_month("15/01/2024"),
+            Some(Cow::Borrowed(_))
+        ));
+        assert_eq!(numeric_month("15 Foo 2024"), None);
+    }
+}
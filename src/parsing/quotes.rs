@@ -0,0 +1,70 @@
+//! Matching quoted snippets to the messages they quote.
+//!
+//! WhatsApp and Discord TXT exports carry no message ids, so a reply shows
+//! the start of the message it answers instead. Both parsers match that
+//! snippet against recent messages, set `reply_to` to the match's
+//! [`source_index`](crate::Message::source_index), and keep a
+//! `[Replying to: …]` line in place of a snippet that matches nothing.
+
+/// Prefix of the line kept in place of a quote that matches no earlier
+/// message.
+pub const REPLYING_TO_PREFIX: &str = "[Replying to: ";
+
+/// Number of recent messages a quoted snippet is matched against.
+pub(crate) const QUOTE_WINDOW: usize = 500;
+
+/// Returns the line kept in place of an unresolved `snippet`.
+pub(crate) fn unresolved_marker(snippet: &str) -> String {
+    format!("{REPLYING_TO_PREFIX}{snippet}]")
+}
+
+/// Removes the trailing ellipsis exporters add to cut-off snippets.
+pub(crate) fn trim_snippet(snippet: &str) -> &str {
+    snippet.trim_end_matches(|c: char| c == '…' || c == '.' || c.is_whitespace())
+}
+
+/// Returns `true` if `content` starts with the words of `snippet`; the last
+/// snippet word may be cut off.
+pub(crate) fn starts_with_words(content: &str, snippet: &str) -> bool {
+    let mut words = content.split_whitespace();
+    let mut quoted = snippet.split_whitespace().peekable();
+    while let Some(q) = quoted.next() {
+        let Some(word) = words.next() else {
+            return false;
+        };
+        let matched = if quoted.peek().is_some() {
+            word == q
+        } else {
+            word.starts_with(q)
+        };
+        if !matched {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starts_with_words() {
+        assert!(starts_with_words(
+            "Are we still on  for lunch?",
+            "Are we still o"
+        ));
+        assert!(!starts_with_words("Are we", "Are we still"));
+        assert!(!starts_with_words("Arena we", "Are we"));
+    }
+
+    #[test]
+    fn test_trim_snippet() {
+        assert_eq!(
+            trim_snippet("Are we still on for lunch…"),
+            "Are we still on for lunch"
+        );
+        assert_eq!(trim_snippet("Hi... "), "Hi");
+        assert_eq!(unresolved_marker("Hi"), "[Replying to: Hi]");
+    }
+}
@@ -0,0 +1,65 @@
+//! Decoding JSON exports while keeping each record's source text.
+//!
+//! Used when a platform config enables `capture_raw`: the `messages` array
+//! is read as borrowed [`RawValue`]s, so every record's text is exactly the
+//! `{...}` slice the streaming parsers read.
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+
+/// Export wrapper that defers decoding of individual messages.
+#[derive(Deserialize)]
+struct RawExport<'a> {
+    #[serde(borrow)]
+    messages: Vec<&'a RawValue>,
+}
+
+/// Decoded records paired with their source text, and the number skipped.
+type Decoded<'a, T> = (Vec<(T, &'a str)>, usize);
+
+/// Decodes the `messages` array of `content`, pairing each record with its
+/// source text.
+///
+/// With `skip_invalid`, records that fail to decode are dropped and counted;
+/// otherwise the first failure is returned.
+pub(crate) fn decode_with_raw<T: DeserializeOwned>(
+    content: &str,
+    skip_invalid: bool,
+) -> Result<Decoded<'_, T>, serde_json::Error> {
+    let export: RawExport<'_> = serde_json::from_str(content)?;
+    let mut records = Vec::with_capacity(export.messages.len());
+    let mut skipped = 0;
+    for raw in export.messages {
+        match serde_json::from_str(raw.get()) {
+            Ok(record) => records.push((record, raw.get())),
+            Err(_) if skip_invalid => skipped += 1,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((records, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Record {
+        id: u64,
+    }
+
+    #[test]
+    fn test_decode_with_raw() {
+        let content = r#"{"name": "x", "messages": [ {"id": 1,  "extra": [1, {"a": "}"}]},
+            {"id": "bad"}, {"id": 2}]}"#;
+
+        let (records, skipped) = decode_with_raw::<Record>(content, true).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(records[0].0.id, 1);
+        assert_eq!(records[0].1, r#"{"id": 1,  "extra": [1, {"a": "}"}]}"#);
+        assert_eq!(records[1].1, r#"{"id": 2}"#);
+
+        assert!(decode_with_raw::<Record>(content, false).is_err());
+    }
+}
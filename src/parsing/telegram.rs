@@ -3,32 +3,135 @@
 //! This module contains types and functions shared between the standard
 //! and streaming Telegram parsers.
 
-use chrono::{DateTime, Utc};
-use serde::Deserialize;
-use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
-use crate::Message;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-/// Raw Telegram message structure for deserialization.
+use crate::config::{MentionFormat, Projection, TelegramConfig};
+use crate::error::ChatpackError;
+use crate::parsing::allowlist::allows;
+use crate::parsing::calls::{MISSED_CALL, VOICE_MESSAGE, call_marker};
+use crate::{AttachmentRef, Message};
+
+/// Prefix Telegram writes instead of a path when media was not exported.
+const FILE_NOT_INCLUDED: &str = "(File not included";
+
+/// File name of the first chunk of an export.
+const FIRST_CHUNK: &str = "result.json";
+
+/// Raw Telegram message structure for (de)serialization.
 ///
-/// Used by both standard and streaming parsers.
-#[derive(Debug, Deserialize)]
+/// Used by both standard and streaming parsers. Serializing produces the
+/// Telegram Desktop export shape; `None` fields are omitted.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TelegramRawMessage {
     /// Message ID
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<u64>,
     /// Message type (we only care about "message")
     #[serde(rename = "type")]
     pub msg_type: String,
+    /// Local date (`2019-05-04T12:30:00`), the only timestamp in old exports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
     /// Unix timestamp as string
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub date_unixtime: Option<String>,
     /// Sender name
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub from: Option<String>,
     /// Message text (can be string or array)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<Value>,
-    /// Reply reference
+    /// Structured text entities emitted by newer Telegram Desktop versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_entities: Option<Vec<Value>>,
+    /// Reply reference; in forum groups, also the id of the topic a
+    /// message that replies to nothing was posted in
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_to_message_id: Option<u64>,
+    /// Id of the forum topic the message was posted in, where exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_top_message_id: Option<u64>,
+    /// Local edit date, the fallback when `edited_unixtime` is missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited: Option<String>,
     /// Edit timestamp as string (if message was edited)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub edited_unixtime: Option<String>,
+    /// Relative path of an attached photo (`photos/photo_1@...jpg`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo: Option<String>,
+    /// Relative path of an attached file, video, or voice message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Original name of the attached file, in newer exports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// Kind of attached media (`voice_message`, `video_file`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    /// Who performed a service action, such as the caller of a phone call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    /// Service action (`phone_call`, `pin_message`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// Length of a call or voice message in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<u64>,
+    /// Why a call ended (`missed`, `busy`, `hangup`, `disconnect`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discard_reason: Option<String>,
+    /// Title of the forum topic a `topic_created` service record creates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Original author of a forwarded message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forwarded_from: Option<String>,
+    /// Album the message's media belongs to, shared by every item of it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grouped_id: Option<i64>,
+}
+
+/// Converts a [`Message`] back into the Telegram export shape.
+///
+/// Timestamps are written both as `date_unixtime` and as a UTC `date`;
+/// sub-second precision is dropped, matching what Telegram exports contain.
+impl From<&Message> for TelegramRawMessage {
+    fn from(msg: &Message) -> Self {
+        let local = |ts: DateTime<Utc>| ts.format("%Y-%m-%dT%H:%M:%S").to_string();
+        Self {
+            id: msg.id,
+            msg_type: "message".to_string(),
+            date: msg.timestamp.map(local),
+            date_unixtime: msg.timestamp.map(|ts| ts.timestamp().to_string()),
+            from: Some(msg.sender.clone()),
+            text: Some(Value::String(msg.content.clone())),
+            text_entities: None,
+            reply_to_message_id: msg.reply_to,
+            reply_to_top_message_id: None,
+            edited: msg.edited.map(local),
+            edited_unixtime: msg.edited.map(|ts| ts.timestamp().to_string()),
+            photo: None,
+            file: None,
+            file_name: None,
+            media_type: None,
+            actor: None,
+            action: None,
+            duration_seconds: None,
+            discard_reason: None,
+            title: None,
+            forwarded_from: msg.forwarded_from.clone(),
+            grouped_id: None,
+        }
+    }
 }
 
 /// Telegram export wrapper.
@@ -43,7 +146,8 @@ pub struct TelegramExport {
 /// - A simple string: `"Hello"`
 /// - An array with strings and objects: `["Text", {"type": "link", "text": "url"}]`
 ///
-/// This function handles both cases and returns a single string.
+/// This function handles both cases and returns a single string. Mentions
+/// are written as exported; see [`extract_telegram_text_with`].
 ///
 /// # Example
 ///
@@ -61,23 +165,127 @@ pub struct TelegramExport {
 /// assert_eq!(extract_telegram_text(&complex), "Check this: https://example.com");
 /// ```
 pub fn extract_telegram_text(text_value: &Value) -> String {
+    extract_telegram_text_with(text_value, MentionFormat::AsExported)
+}
+
+/// Extracts text like [`extract_telegram_text`], writing `mention` and
+/// `mention_name` entities in `mentions` format.
+///
+/// A mention with empty text falls back to `@id<user_id>`.
+pub fn extract_telegram_text_with(text_value: &Value, mentions: MentionFormat) -> String {
     match text_value {
         Value::String(s) => s.clone(),
-        Value::Array(arr) => arr
-            .iter()
-            .filter_map(|item| match item {
-                Value::String(s) => Some(s.clone()),
-                Value::Object(obj) => obj
-                    .get("text")
-                    .and_then(|v| v.as_str())
-                    .map(ToString::to_string),
-                _ => None,
-            })
-            .collect::<String>(),
+        Value::Array(arr) => extract_text_entities_with(arr, mentions),
         _ => String::new(),
     }
 }
 
+/// Returns the visible text of one entity object.
+///
+/// Mentions are matched explicitly so an empty mention still names its
+/// user; phone numbers and other types contribute their `text` as is.
+fn entity_text(entity: &Map<String, Value>, mentions: MentionFormat) -> Option<Cow<'_, str>> {
+    let text = entity.get("text").and_then(Value::as_str);
+    match entity.get("type").and_then(Value::as_str) {
+        Some("mention" | "mention_name") => match text {
+            Some(text) if !text.is_empty() => Some(mentions.render(text)),
+            _ => entity.get("user_id").and_then(|id| match id {
+                Value::Number(n) => Some(Cow::Owned(format!("@id{n}"))),
+                Value::String(s) if !s.is_empty() => Some(Cow::Owned(format!("@id{s}"))),
+                _ => None,
+            }),
+        },
+        // Phone numbers, links, formatting, and everything else
+        _ => text.map(Cow::Borrowed),
+    }
+}
+
+/// Reconstructs message text from Telegram's `text_entities` array.
+///
+/// Each entity is an object like `{"type": "bold", "text": "Hi"}`. The
+/// `text` fields are concatenated in order, exactly like the object parts
+/// of the legacy `text` array, so links, mentions, blockquotes and spoilers
+/// all contribute their visible text.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "telegram")]
+/// # fn main() {
+/// use serde_json::json;
+/// use chatpack::parsing::telegram::extract_text_entities;
+///
+/// let entities = vec![
+///     json!({"type": "plain", "text": "See "}),
+///     json!({"type": "mention", "text": "@alice"}),
+/// ];
+/// assert_eq!(extract_text_entities(&entities), "See @alice");
+/// # }
+/// # #[cfg(not(feature = "telegram"))]
+/// # fn main() {}
+/// ```
+pub fn extract_text_entities(entities: &[Value]) -> String {
+    extract_text_entities_with(entities, MentionFormat::AsExported)
+}
+
+/// Reconstructs message text like [`extract_text_entities`], writing
+/// mentions in `mentions` format.
+pub fn extract_text_entities_with(entities: &[Value], mentions: MentionFormat) -> String {
+    let mut text = String::new();
+    for entity in entities {
+        match entity {
+            Value::String(s) => text.push_str(s),
+            Value::Object(obj) => {
+                if let Some(part) = entity_text(obj, mentions) {
+                    text.push_str(&part);
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Returns whether the joined `entities` hold anything but whitespace,
+/// without joining them.
+fn entities_have_text(entities: &[Value], mentions: MentionFormat) -> bool {
+    entities.iter().any(|entity| match entity {
+        Value::String(s) => !s.trim().is_empty(),
+        Value::Object(obj) => {
+            entity_text(obj, mentions).is_some_and(|part| !part.trim().is_empty())
+        }
+        _ => false,
+    })
+}
+
+/// Returns whether [`message_text`] would return anything but whitespace,
+/// without building the text.
+fn has_message_text(msg: &TelegramRawMessage, mentions: MentionFormat) -> bool {
+    let text = match &msg.text {
+        Some(Value::String(s)) => !s.trim().is_empty(),
+        Some(Value::Array(parts)) => entities_have_text(parts, mentions),
+        _ => false,
+    };
+    text || msg
+        .text_entities
+        .as_deref()
+        .is_some_and(|entities| entities_have_text(entities, mentions))
+}
+
+/// Returns the text of a message, preferring `text` and falling back to
+/// `text_entities` when `text` is missing or empty.
+fn message_text(msg: &TelegramRawMessage, mentions: MentionFormat) -> Option<String> {
+    let text = msg
+        .text
+        .as_ref()
+        .map(|text| extract_telegram_text_with(text, mentions));
+    match (text, &msg.text_entities) {
+        (Some(text), _) if !text.trim().is_empty() => Some(text),
+        (_, Some(entities)) => Some(extract_text_entities_with(entities, mentions)),
+        (text, None) => text,
+    }
+}
+
 /// Parses a Unix timestamp string to DateTime.
 ///
 /// Telegram stores timestamps as strings like "1234567890".
@@ -88,44 +296,576 @@ pub fn parse_unix_timestamp(ts_str: &str) -> Option<DateTime<Utc>> {
         .and_then(|ts| DateTime::from_timestamp(ts, 0))
 }
 
+/// Parses Telegram's local `date` field (`2019-05-04T12:30:00`).
+///
+/// The value carries no offset, so `offset` is the timezone the export was
+/// made in.
+pub fn parse_local_date(date_str: &str, offset: FixedOffset) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%dT%H:%M:%S").ok()?;
+    offset
+        .from_local_datetime(&naive)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Resolves a timestamp from the unixtime field, falling back to the local date.
+fn resolve_timestamp(
+    unixtime: Option<&String>,
+    local: Option<&String>,
+    offset: FixedOffset,
+) -> Option<DateTime<Utc>> {
+    unixtime
+        .and_then(|ts| parse_unix_timestamp(ts))
+        .or_else(|| local.and_then(|d| parse_local_date(d, offset)))
+}
+
 /// Parses a raw Telegram message into a `Message`.
 ///
 /// Returns `None` if:
 /// - The message type is not "message"
 /// - The sender is missing
-/// - The content is empty
+/// - The content is empty (after falling back to `text_entities`)
+///
+/// Local `date`/`edited` fields are interpreted as UTC; use
+/// [`parse_telegram_message_with_offset`] for other timezones.
 ///
 /// This is the core parsing logic shared between standard and streaming parsers.
 pub fn parse_telegram_message(msg: &TelegramRawMessage) -> Option<Message> {
-    // Skip non-message types
+    parse_telegram_message_with_offset(msg, FixedOffset::east_opt(0).unwrap())
+}
+
+/// Parses a raw Telegram message, interpreting local dates in `offset`.
+///
+/// `date_unixtime` and `edited_unixtime` take precedence; the local `date`
+/// and `edited` fields are only used when they are missing, as in exports
+/// made before 2021.
+pub fn parse_telegram_message_with_offset(
+    msg: &TelegramRawMessage,
+    offset: FixedOffset,
+) -> Option<Message> {
+    parse_message(
+        msg,
+        offset,
+        false,
+        false,
+        MentionFormat::AsExported,
+        None,
+        Projection::all(),
+    )
+}
+
+/// Parses a raw Telegram message like [`parse_telegram_message_with_offset`],
+/// also collecting its photo and file into `Message::attachments`.
+///
+/// Messages with media but no caption are kept, with an
+/// `[Attachment: name]` marker as their content.
+pub fn parse_telegram_message_with_attachments(
+    msg: &TelegramRawMessage,
+    offset: FixedOffset,
+) -> Option<Message> {
+    parse_message(
+        msg,
+        offset,
+        true,
+        false,
+        MentionFormat::AsExported,
+        None,
+        Projection::all(),
+    )
+}
+
+/// Parses a raw Telegram message under `config`: its timezone, mention
+/// format, sender allowlist, and whether to collect attachments and include
+/// calls.
+pub fn parse_telegram_message_with_config(
+    msg: &TelegramRawMessage,
+    config: &TelegramConfig,
+) -> Option<Message> {
+    parse_message(
+        msg,
+        config.assume_timezone,
+        config.collect_attachment_refs,
+        config.include_calls,
+        config.mention_format,
+        config.sender_allowlist.as_deref(),
+        Projection::all(),
+    )
+}
+
+/// Returns the photo and file a raw Telegram message references.
+///
+/// Media that was left out of the export has a `None` path.
+pub fn telegram_attachment_refs(msg: &TelegramRawMessage) -> Vec<AttachmentRef> {
+    let to_ref = |path: &str, name: Option<&String>, fallback: &str| {
+        let mut attachment = if path.starts_with(FILE_NOT_INCLUDED) {
+            AttachmentRef::new(fallback)
+        } else {
+            AttachmentRef::from_path(path)
+        };
+        if let Some(name) = name {
+            attachment.file_name.clone_from(name);
+        }
+        attachment
+    };
+
+    let mut refs = Vec::new();
+    if let Some(photo) = &msg.photo {
+        refs.push(to_ref(photo, None, "photo"));
+    }
+    if let Some(file) = &msg.file {
+        refs.push(to_ref(file, msg.file_name.as_ref(), "file"));
+    }
+    refs
+}
+
+/// Returns the files of the Telegram export at `path`, in order.
+///
+/// Telegram Desktop splits very large exports into `result.json`,
+/// `result(1).json`, `result(2).json`, … in one folder. Given that folder
+/// or its `result.json`, this returns every chunk present, ordered by
+/// number. Any other file is returned alone.
+///
+/// A `result.json` whose folder cannot be listed is returned alone.
+///
+/// # Errors
+///
+/// Returns an error if the folder cannot be read, or if `path` is a folder
+/// without any chunk.
+pub fn telegram_export_chunks(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let (dir, first) = if path.is_dir() {
+        let first = path.join(FIRST_CHUNK);
+        (path, first.is_file().then_some(first))
+    } else if path.file_name().is_some_and(|name| name == FIRST_CHUNK) {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        (dir, Some(path.to_path_buf()))
+    } else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) if !path.is_dir() => return Ok(vec![path.to_path_buf()]),
+        Err(e) => return Err(e),
+    };
+    let mut numbered: Vec<(u32, PathBuf)> = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let number = entry.file_name().to_str().and_then(|name| {
+            name.strip_prefix("result(")?
+                .strip_suffix(").json")?
+                .parse()
+                .ok()
+        });
+        if let Some(number) = number {
+            numbered.push((number, entry.path()));
+        }
+    }
+    numbered.sort_unstable_by_key(|(number, _)| *number);
+
+    let chunks: Vec<PathBuf> = first
+        .into_iter()
+        .chain(numbered.into_iter().map(|(_, path)| path))
+        .collect();
+    if chunks.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no {FIRST_CHUNK} in {}", dir.display()),
+        ));
+    }
+    Ok(chunks)
+}
+
+/// Checks the combined size of the chunks of an export against `limit`.
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::FileTooLarge`] if the chunks together exceed
+/// `limit`, or an I/O error if one cannot be read.
+pub(crate) fn check_export_size(chunks: &[PathBuf], limit: u64) -> Result<(), ChatpackError> {
+    let mut size = 0u64;
+    for chunk in chunks {
+        size = size.saturating_add(fs::metadata(chunk)?.len());
+    }
+    if size > limit {
+        return Err(ChatpackError::file_too_large(size, limit));
+    }
+    Ok(())
+}
+
+/// Drops the messages a chunk repeats from the end of the previous one.
+///
+/// Telegram may start a chunk with the last messages of the chunk before
+/// it. A message is a repeat if its id was seen in the previous chunk;
+/// ids from older chunks are not remembered, so only the ids of two chunks
+/// are held at a time.
+#[derive(Debug, Default)]
+pub(crate) struct ChunkOverlap {
+    previous: HashSet<u64>,
+    current: HashSet<u64>,
+}
+
+impl ChunkOverlap {
+    /// Records `msg` as part of the current chunk and returns whether it
+    /// is new, i.e. not a repeat from the previous chunk.
+    pub(crate) fn keep(&mut self, msg: &Message) -> bool {
+        let Some(id) = msg.id else {
+            return true;
+        };
+        self.current.insert(id);
+        !self.previous.contains(&id)
+    }
+
+    /// Moves on to the next chunk.
+    pub(crate) fn next_chunk(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+}
+
+/// Title of the topic forum messages outside any created topic belong to.
+pub const GENERAL_TOPIC: &str = "General";
+
+/// Follows the forum topics of a Telegram export, record by record.
+///
+/// A forum export has a `topic_created` service record per topic. A
+/// message posted in a topic replies to that record, or to another message
+/// in the topic; newer exports may name the topic in
+/// `reply_to_top_message_id` instead. Messages that reply to nothing are
+/// in the [`GENERAL_TOPIC`].
+///
+/// Until the first `topic_created` record, or the first record with a
+/// `reply_to_top_message_id`, the export is not treated as a forum and
+/// messages have no topic. From then on the topic of the last
+/// [`MAX_TRACKED_MESSAGES`] messages is remembered, so replies can be
+/// followed to their topic. A topic with no `topic_created` record is
+/// titled by its id, as `#40`.
+#[derive(Debug, Default)]
+pub(crate) struct TopicTracker {
+    /// Whether the export has been seen to be a forum
+    forum: bool,
+    /// Titles by the id of the record that created the topic
+    titles: HashMap<u64, String>,
+    /// Topic id of recent messages in the forum; `None` for the general topic
+    message_topics: HashMap<u64, Option<u64>>,
+    /// Keys of `message_topics`, oldest first
+    tracked: VecDeque<u64>,
+}
+
+/// How many messages a [`TopicTracker`] remembers the topic of. A reply to
+/// an older message without `reply_to_top_message_id` is put in the
+/// [`GENERAL_TOPIC`].
+const MAX_TRACKED_MESSAGES: usize = 100_000;
+
+impl TopicTracker {
+    /// Records `record` and returns the title of the topic it was posted
+    /// in, or `None` if the export is not a forum.
+    fn observe(&mut self, record: &TelegramRawMessage) -> Option<String> {
+        if record.action.as_deref() == Some("topic_created") {
+            if let Some(id) = record.id {
+                let title = record.title.clone().unwrap_or_else(|| format!("#{id}"));
+                self.titles.insert(id, title);
+                self.forum = true;
+            }
+        }
+        self.forum |= record.reply_to_top_message_id.is_some();
+        if !self.forum {
+            return None;
+        }
+
+        // A `topic_created` record is the first record of its topic
+        let created = record.id.filter(|id| self.titles.contains_key(id));
+        let topic = created.or(record.reply_to_top_message_id).or_else(|| {
+            let parent = record.reply_to_message_id?;
+            if self.titles.contains_key(&parent) {
+                Some(parent)
+            } else {
+                self.message_topics.get(&parent).copied().flatten()
+            }
+        });
+        if let Some(id) = record.id {
+            self.track(id, topic);
+        }
+        Some(topic.map_or_else(
+            || GENERAL_TOPIC.to_string(),
+            |id| {
+                self.titles
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("#{id}"))
+            },
+        ))
+    }
+
+    /// Remembers the topic of message `id`, forgetting the oldest message
+    /// past [`MAX_TRACKED_MESSAGES`].
+    fn track(&mut self, id: u64, topic: Option<u64>) {
+        if self.message_topics.insert(id, topic).is_some() {
+            return;
+        }
+        self.tracked.push_back(id);
+        if self.tracked.len() > MAX_TRACKED_MESSAGES {
+            if let Some(oldest) = self.tracked.pop_front() {
+                self.message_topics.remove(&oldest);
+            }
+        }
+    }
+
+    /// Sets the topic of `msg`, the message converted from `record`, and
+    /// drops it if its topic is not in `filter`.
+    ///
+    /// Every record must be passed, even those that did not become a
+    /// message, so replies to them can be followed.
+    pub(crate) fn assign(
+        &mut self,
+        record: &TelegramRawMessage,
+        msg: Option<Message>,
+        filter: Option<&[String]>,
+    ) -> Option<Message> {
+        let topic = self.observe(record);
+        let mut msg = msg?;
+        if filter.is_some() && !topic.as_deref().is_some_and(|t| allows(filter, t)) {
+            return None;
+        }
+        msg.topic = topic;
+        Some(msg)
+    }
+
+    /// Converts one record, or the records of an album, into a message
+    /// under `config`, in its forum topic.
+    pub(crate) fn convert(
+        &mut self,
+        records: &[&TelegramRawMessage],
+        config: &TelegramConfig,
+    ) -> Option<Message> {
+        let (first, rest) = records.split_first()?;
+        let msg = if rest.is_empty() {
+            parse_telegram_message_with_config(first, config)
+        } else {
+            parse_album(
+                records,
+                config.assume_timezone,
+                config.collect_attachment_refs,
+                config.mention_format,
+                config.sender_allowlist.as_deref(),
+                Projection::all(),
+            )
+        };
+        let filter = config.topic_filter.as_deref();
+        let msg = self.assign(first, msg, filter);
+        for record in rest {
+            self.assign(record, None, filter);
+        }
+        msg
+    }
+}
+
+/// Parses one record into a message, building only the fields
+/// `projection` asks for. Which records yield a message does not depend on
+/// the projection.
+pub(crate) fn parse_message(
+    msg: &TelegramRawMessage,
+    offset: FixedOffset,
+    collect_attachments: bool,
+    include_calls: bool,
+    mentions: MentionFormat,
+    senders: Option<&[String]>,
+    projection: Projection,
+) -> Option<Message> {
+    // Skip non-message types, except calls when they are included
     if msg.msg_type != "message" {
-        return None;
+        let is_call = msg.msg_type == "service" && msg.action.as_deref() == Some("phone_call");
+        return if include_calls && is_call {
+            parse_call(msg, offset, senders)
+        } else {
+            None
+        };
     }
 
-    let sender = msg.from.as_ref()?;
-    let text_value = msg.text.as_ref()?;
-    let content = extract_telegram_text(text_value);
+    let sender = msg.from.as_ref().filter(|from| allows(senders, from))?;
+    let attachments = if collect_attachments {
+        telegram_attachment_refs(msg)
+    } else {
+        Vec::new()
+    };
 
-    if content.trim().is_empty() {
+    let content = if projection.need_content {
+        let mut content = message_text(msg, mentions).unwrap_or_default();
+        if content.trim().is_empty() && include_calls && is_voice_message(msg) {
+            content = VOICE_MESSAGE.to_string();
+        } else if content.trim().is_empty() {
+            if attachments.is_empty() {
+                return None;
+            }
+            content = attachments
+                .iter()
+                .map(|a| format!("[Attachment: {}]", a.file_name))
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        content
+    } else if has_message_text(msg, mentions)
+        || (include_calls && is_voice_message(msg))
+        || !attachments.is_empty()
+    {
+        String::new()
+    } else {
         return None;
-    }
+    };
 
-    let timestamp = msg
-        .date_unixtime
-        .as_ref()
-        .and_then(|ts| parse_unix_timestamp(ts));
-    let edited = msg
-        .edited_unixtime
-        .as_ref()
-        .and_then(|ts| parse_unix_timestamp(ts));
+    let mut message = record_message(msg, sender, content, offset, projection);
+    message.attachments = attachments;
+    Some(message)
+}
 
-    Some(Message::with_metadata(
+/// Builds a message from `sender` and `content` with the id, timestamps,
+/// reply reference, and, if `projection` keeps metadata, forward origin of
+/// `msg`.
+fn record_message(
+    msg: &TelegramRawMessage,
+    sender: &str,
+    content: String,
+    offset: FixedOffset,
+    projection: Projection,
+) -> Message {
+    let timestamp = resolve_timestamp(msg.date_unixtime.as_ref(), msg.date.as_ref(), offset);
+    let edited = resolve_timestamp(msg.edited_unixtime.as_ref(), msg.edited.as_ref(), offset);
+
+    let mut message = Message::with_metadata(
         sender,
         content,
         timestamp,
         msg.id,
         msg.reply_to_message_id,
         edited,
+    );
+    if projection.need_metadata {
+        message.forwarded_from.clone_from(&msg.forwarded_from);
+    }
+    message
+}
+
+/// Returns how many of `records`, from the first, form one album: records
+/// from one sender sharing a `grouped_id`. A record outside any album is an
+/// album of one.
+pub(crate) fn album_len<T>(records: &[T], raw: impl Fn(&T) -> &TelegramRawMessage) -> usize {
+    let Some(first) = records.first() else {
+        return 0;
+    };
+    1 + records[1..]
+        .iter()
+        .take_while(|next| continues_album(raw(first), raw(next)))
+        .count()
+}
+
+/// Returns how many of `records`, from the first, are converted into one
+/// message: an album's worth when `merge_albums` is set, otherwise one.
+pub(crate) fn message_len<T>(
+    records: &[T],
+    merge_albums: bool,
+    raw: impl Fn(&T) -> &TelegramRawMessage,
+) -> usize {
+    if merge_albums {
+        album_len(records, raw)
+    } else {
+        records.len().min(1)
+    }
+}
+
+/// Returns whether `next` belongs to the album `first` starts.
+pub(crate) fn continues_album(first: &TelegramRawMessage, next: &TelegramRawMessage) -> bool {
+    first.grouped_id.is_some()
+        && next.grouped_id == first.grouped_id
+        && next.from == first.from
+        && next.msg_type == "message"
+}
+
+/// Parses the records of an album, two or more records found by
+/// [`continues_album`], into one message.
+///
+/// The message has the first record's id, timestamps, and reply
+/// reference. Its content counts the media, as in
+/// `[Album: 4 photos, 1 video]`, followed by the caption, which Telegram
+/// puts on one of the records.
+pub(crate) fn parse_album(
+    items: &[&TelegramRawMessage],
+    offset: FixedOffset,
+    collect_attachments: bool,
+    mentions: MentionFormat,
+    senders: Option<&[String]>,
+    projection: Projection,
+) -> Option<Message> {
+    let first = items.first()?;
+    let sender = first.from.as_ref().filter(|from| allows(senders, from))?;
+
+    let (mut photos, mut videos, mut files) = (0, 0, 0);
+    for item in items {
+        photos += usize::from(item.photo.is_some());
+        if item.file.is_some() {
+            match item.media_type.as_deref() {
+                Some("video_file" | "animation") => videos += 1,
+                _ => files += 1,
+            }
+        }
+    }
+    let content = if projection.need_content {
+        let counts: Vec<String> = [(photos, "photo"), (videos, "video"), (files, "file")]
+            .into_iter()
+            .filter(|&(count, _)| count > 0)
+            .map(|(count, noun)| {
+                let plural = if count == 1 { "" } else { "s" };
+                format!("{count} {noun}{plural}")
+            })
+            .collect();
+        let caption = items
+            .iter()
+            .filter_map(|item| message_text(item, mentions))
+            .find(|text| !text.trim().is_empty());
+
+        match (counts.is_empty(), caption) {
+            (true, None) => return None,
+            (true, Some(caption)) => caption,
+            (false, None) => format!("[Album: {}]", counts.join(", ")),
+            (false, Some(caption)) => format!("[Album: {}]\n{caption}", counts.join(", ")),
+        }
+    } else if photos + videos + files > 0
+        || items.iter().any(|item| has_message_text(item, mentions))
+    {
+        String::new()
+    } else {
+        return None;
+    };
+
+    let mut message = record_message(first, sender, content, offset, projection);
+    if collect_attachments && projection.need_attachments {
+        message.attachments = items
+            .iter()
+            .flat_map(|item| telegram_attachment_refs(item))
+            .collect();
+    }
+    Some(message)
+}
+
+fn is_voice_message(msg: &TelegramRawMessage) -> bool {
+    msg.media_type.as_deref() == Some("voice_message")
+}
+
+/// Parses a `phone_call` service record into a message from the caller.
+fn parse_call(
+    msg: &TelegramRawMessage,
+    offset: FixedOffset,
+    senders: Option<&[String]>,
+) -> Option<Message> {
+    let caller = msg.actor.as_ref().filter(|actor| allows(senders, actor))?;
+    let content = match msg.discard_reason.as_deref() {
+        Some("missed" | "busy") => MISSED_CALL.to_string(),
+        _ => call_marker(msg.duration_seconds),
+    };
+    let timestamp = resolve_timestamp(msg.date_unixtime.as_ref(), msg.date.as_ref(), offset);
+
+    Some(Message::with_metadata(
+        caller, content, timestamp, msg.id, None, None,
     ))
 }
 
@@ -172,6 +912,88 @@ mod tests {
         assert!(parse_unix_timestamp("").is_none());
     }
 
+    #[test]
+    fn test_topic_tracker() {
+        let record =
+            |value: Value| -> TelegramRawMessage { serde_json::from_value(value).unwrap() };
+        let mut topics = TopicTracker::default();
+        let mut topic_of = |value: Value| topics.observe(&record(value));
+
+        // Not a forum until a topic is created
+        assert_eq!(topic_of(json!({"id": 1, "type": "message"})), None);
+        assert_eq!(
+            topic_of(
+                json!({"id": 2, "type": "service", "action": "topic_created", "title": "Bugs"})
+            ),
+            Some("Bugs".to_string())
+        );
+        let cases = [
+            (
+                json!({"id": 3, "type": "message", "reply_to_message_id": 2}),
+                "Bugs",
+            ),
+            (
+                json!({"id": 4, "type": "message", "reply_to_message_id": 3}),
+                "Bugs",
+            ),
+            (json!({"id": 5, "type": "message"}), GENERAL_TOPIC),
+            (
+                json!({"id": 6, "type": "message", "reply_to_message_id": 5}),
+                GENERAL_TOPIC,
+            ),
+            (
+                json!({"id": 7, "type": "message", "reply_to_top_message_id": 2}),
+                "Bugs",
+            ),
+            (
+                json!({"id": 8, "type": "message", "reply_to_top_message_id": 40}),
+                "#40",
+            ),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(topic_of(value).as_deref(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_topic_tracker_without_created_topics() {
+        let record =
+            |value: Value| -> TelegramRawMessage { serde_json::from_value(value).unwrap() };
+        let mut topics = TopicTracker::default();
+        let mut topic_of = |value: Value| topics.observe(&record(value));
+
+        assert_eq!(
+            topic_of(json!({"id": 1, "type": "message", "reply_to_top_message_id": 40})),
+            Some("#40".to_string())
+        );
+        assert_eq!(
+            topic_of(json!({"id": 2, "type": "message", "reply_to_message_id": 1})),
+            Some("#40".to_string())
+        );
+        assert_eq!(
+            topic_of(json!({"id": 3, "type": "message"})),
+            Some(GENERAL_TOPIC.to_string())
+        );
+    }
+
+    #[test]
+    fn test_topic_tracker_forgets_old_messages() {
+        let record =
+            |value: Value| -> TelegramRawMessage { serde_json::from_value(value).unwrap() };
+        let mut topics = TopicTracker::default();
+        topics.observe(&record(
+            json!({"id": 0, "type": "service", "action": "topic_created", "title": "Bugs"}),
+        ));
+        for id in 1..=MAX_TRACKED_MESSAGES as u64 + 1 {
+            topics.observe(&record(
+                json!({"id": id, "type": "message", "reply_to_message_id": 0}),
+            ));
+        }
+        assert_eq!(topics.message_topics.len(), MAX_TRACKED_MESSAGES);
+        assert!(!topics.message_topics.contains_key(&1));
+        assert!(topics.message_topics.contains_key(&2));
+    }
+
     #[test]
     fn test_parse_telegram_message_basic() {
         let msg = TelegramRawMessage {
@@ -180,8 +1002,7 @@ mod tests {
             date_unixtime: Some("1705314600".to_string()),
             from: Some("Alice".to_string()),
             text: Some(json!("Hello!")),
-            reply_to_message_id: None,
-            edited_unixtime: None,
+            ..Default::default()
         };
 
         let result = parse_telegram_message(&msg);
@@ -193,6 +1014,22 @@ mod tests {
         assert!(parsed.timestamp.is_some());
     }
 
+    #[test]
+    fn test_parse_telegram_message_forwarded() {
+        let msg: TelegramRawMessage = serde_json::from_value(json!({
+            "id": 7, "type": "message", "date_unixtime": "1705314600",
+            "from": "Alice", "forwarded_from": "Rust News", "text": "Rust 1.80 is out"
+        }))
+        .unwrap();
+
+        let parsed = parse_telegram_message(&msg).unwrap();
+        assert_eq!(parsed.forwarded_from.as_deref(), Some("Rust News"));
+        assert_eq!(
+            TelegramRawMessage::from(&parsed).forwarded_from.as_deref(),
+            Some("Rust News")
+        );
+    }
+
     #[test]
     fn test_parse_telegram_message_skip_service() {
         let msg = TelegramRawMessage {
@@ -201,8 +1038,7 @@ mod tests {
             date_unixtime: Some("1705314600".to_string()),
             from: Some("Alice".to_string()),
             text: Some(json!("pinned a message")),
-            reply_to_message_id: None,
-            edited_unixtime: None,
+            ..Default::default()
         };
 
         assert!(parse_telegram_message(&msg).is_none());
@@ -216,10 +1052,291 @@ mod tests {
             date_unixtime: Some("1705314600".to_string()),
             from: Some("Alice".to_string()),
             text: Some(json!("   ")),
-            reply_to_message_id: None,
-            edited_unixtime: None,
+            ..Default::default()
+        };
+
+        assert!(parse_telegram_message(&msg).is_none());
+    }
+
+    // =========================================================================
+    // text_entities fallback tests
+    // =========================================================================
+
+    #[test]
+    fn test_text_entities_used_when_text_empty() {
+        let msg = TelegramRawMessage {
+            msg_type: "message".to_string(),
+            from: Some("Alice".to_string()),
+            text: Some(json!("")),
+            text_entities: Some(vec![
+                json!({"type": "blockquote", "text": "quoted"}),
+                json!({"type": "plain", "text": " and "}),
+                json!({"type": "spoiler", "text": "hidden"}),
+            ]),
+            ..Default::default()
+        };
+
+        let parsed = parse_telegram_message(&msg).unwrap();
+        assert_eq!(parsed.content, "quoted and hidden");
+    }
+
+    #[test]
+    fn test_text_entities_used_when_text_missing() {
+        let msg = TelegramRawMessage {
+            msg_type: "message".to_string(),
+            from: Some("Alice".to_string()),
+            text_entities: Some(vec![json!({"type": "link", "text": "https://example.com"})]),
+            ..Default::default()
+        };
+
+        let parsed = parse_telegram_message(&msg).unwrap();
+        assert_eq!(parsed.content, "https://example.com");
+    }
+
+    #[test]
+    fn test_text_preferred_over_entities() {
+        let msg = TelegramRawMessage {
+            msg_type: "message".to_string(),
+            from: Some("Alice".to_string()),
+            text: Some(json!("legacy")),
+            text_entities: Some(vec![json!({"type": "plain", "text": "entities"})]),
+            ..Default::default()
+        };
+
+        assert_eq!(parse_telegram_message(&msg).unwrap().content, "legacy");
+    }
+
+    #[test]
+    fn test_empty_text_and_entities_skipped() {
+        let msg = TelegramRawMessage {
+            msg_type: "message".to_string(),
+            from: Some("Alice".to_string()),
+            text: Some(json!("")),
+            text_entities: Some(vec![]),
+            ..Default::default()
         };
 
         assert!(parse_telegram_message(&msg).is_none());
     }
+
+    // =========================================================================
+    // Local date fallback tests
+    // =========================================================================
+
+    #[test]
+    fn test_date_fallback_when_unixtime_missing() {
+        let msg = TelegramRawMessage {
+            msg_type: "message".to_string(),
+            from: Some("Alice".to_string()),
+            text: Some(json!("Old export")),
+            date: Some("2019-05-04T12:30:00".to_string()),
+            edited: Some("2019-05-04T12:35:00".to_string()),
+            ..Default::default()
+        };
+
+        let parsed = parse_telegram_message(&msg).unwrap();
+        assert_eq!(
+            parsed.timestamp.unwrap().to_rfc3339(),
+            "2019-05-04T12:30:00+00:00"
+        );
+        assert_eq!(
+            parsed.edited.unwrap().to_rfc3339(),
+            "2019-05-04T12:35:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_date_fallback_with_offset() {
+        let msg = TelegramRawMessage {
+            msg_type: "message".to_string(),
+            from: Some("Alice".to_string()),
+            text: Some(json!("Old export")),
+            date: Some("2019-05-04T12:30:00".to_string()),
+            ..Default::default()
+        };
+
+        let offset = FixedOffset::east_opt(3 * 3600).unwrap();
+        let parsed = parse_telegram_message_with_offset(&msg, offset).unwrap();
+        assert_eq!(
+            parsed.timestamp.unwrap().to_rfc3339(),
+            "2019-05-04T09:30:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_unixtime_preferred_over_date() {
+        let msg = TelegramRawMessage {
+            msg_type: "message".to_string(),
+            from: Some("Alice".to_string()),
+            text: Some(json!("Hi")),
+            date_unixtime: Some("1705314600".to_string()),
+            date: Some("2019-05-04T12:30:00".to_string()),
+            ..Default::default()
+        };
+
+        let parsed = parse_telegram_message(&msg).unwrap();
+        assert_eq!(parsed.timestamp.unwrap().timestamp(), 1705314600);
+    }
+
+    #[test]
+    fn test_parse_local_date_invalid() {
+        let utc = FixedOffset::east_opt(0).unwrap();
+        assert!(parse_local_date("2019-05-04", utc).is_none());
+        assert!(parse_local_date("garbage", utc).is_none());
+    }
+
+    // =========================================================================
+    // From<&Message> tests
+    // =========================================================================
+
+    #[test]
+    fn test_from_message_round_trip() {
+        let ts = DateTime::from_timestamp(1705314600, 0);
+        let edited = DateTime::from_timestamp(1705314700, 0);
+        let original = Message::with_metadata("Alice", "Hello", ts, Some(7), Some(3), edited);
+
+        let raw = TelegramRawMessage::from(&original);
+        assert_eq!(raw.msg_type, "message");
+        assert_eq!(raw.date_unixtime.as_deref(), Some("1705314600"));
+        assert_eq!(raw.date.as_deref(), Some("2024-01-15T10:30:00"));
+
+        assert_eq!(parse_telegram_message(&raw).unwrap(), original);
+    }
+
+    #[test]
+    fn test_from_message_omits_missing_fields() {
+        let raw = TelegramRawMessage::from(&Message::new("Bob", "Hi"));
+        let json = serde_json::to_string(&raw).unwrap();
+        assert_eq!(json, r#"{"type":"message","from":"Bob","text":"Hi"}"#);
+    }
+
+    // =========================================================================
+    // Album tests
+    // =========================================================================
+
+    fn album_item(id: i64, from: &str, grouped_id: Option<i64>, media: &str) -> TelegramRawMessage {
+        let mut value = json!({
+            "id": id,
+            "type": "message",
+            "date_unixtime": (1705314600 + id).to_string(),
+            "from": from,
+            "text": "",
+        });
+        match media {
+            "video" => {
+                value["file"] = "video.mp4".into();
+                value["media_type"] = "video_file".into();
+            }
+            _ => value["photo"] = "photo.jpg".into(),
+        }
+        if let Some(grouped_id) = grouped_id {
+            value["grouped_id"] = grouped_id.into();
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_album_caption_on_third_item() {
+        let mut items: Vec<TelegramRawMessage> = (1..=4)
+            .map(|id| album_item(id, "Alice", Some(42), "photo"))
+            .collect();
+        items.push(album_item(5, "Alice", Some(42), "video"));
+        items[2].text = Some(Value::from("Our trip"));
+        assert_eq!(album_len(&items, |r| r), 5);
+
+        let refs: Vec<&TelegramRawMessage> = items.iter().collect();
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let msg = parse_album(
+            &refs,
+            utc,
+            true,
+            MentionFormat::default(),
+            None,
+            Projection::all(),
+        )
+        .unwrap();
+        assert_eq!(msg.content, "[Album: 4 photos, 1 video]\nOur trip");
+        assert_eq!(msg.id, Some(1));
+        assert_eq!(msg.timestamp, DateTime::from_timestamp(1705314601, 0));
+        assert_eq!(msg.attachments.len(), 5);
+    }
+
+    #[test]
+    fn test_album_len_stops_at_other_sender_or_group() {
+        let items = [
+            album_item(1, "Alice", Some(42), "photo"),
+            album_item(2, "Alice", Some(42), "photo"),
+            album_item(3, "Bob", Some(42), "photo"),
+            album_item(4, "Alice", Some(42), "photo"),
+        ];
+        assert_eq!(album_len(&items, |r| r), 2);
+        assert_eq!(album_len(&items[2..], |r| r), 1);
+
+        let ungrouped = [
+            album_item(1, "Alice", None, "photo"),
+            album_item(2, "Alice", None, "photo"),
+        ];
+        assert_eq!(album_len(&ungrouped, |r| r), 1);
+        assert_eq!(album_len::<TelegramRawMessage>(&[], |r| r), 0);
+    }
+
+    #[test]
+    fn test_projection_keeps_same_records() {
+        let records: Vec<TelegramRawMessage> = [
+            json!({"type": "message", "from": "A", "text": "hi"}),
+            json!({"type": "message", "from": "A", "text": " \n"}),
+            json!({"type": "message", "from": "A", "text": ["", {"type": "bold", "text": " "}]}),
+            json!({"type": "message", "from": "A", "text": "", "text_entities": [{"type": "plain", "text": "x"}]}),
+            json!({"type": "message", "from": "A", "text": [{"type": "mention_name", "text": "", "user_id": 7}]}),
+            json!({"type": "message", "from": "A", "text": "", "photo": "photos/p.jpg"}),
+            json!({"type": "message", "from": "A", "text": "", "media_type": "voice_message"}),
+        ]
+        .into_iter()
+        .map(|value| serde_json::from_value(value).unwrap())
+        .collect();
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let parse = |record, collect, projection| {
+            parse_message(
+                record,
+                utc,
+                collect,
+                true,
+                MentionFormat::default(),
+                None,
+                projection,
+            )
+        };
+
+        for collect in [false, true] {
+            let kept: Vec<bool> = records
+                .iter()
+                .map(|r| parse(r, collect, Projection::all()).is_some())
+                .collect();
+            let projected: Vec<Option<Message>> = records
+                .iter()
+                .map(|r| parse(r, collect, Projection::minimal()))
+                .collect();
+            assert_eq!(
+                projected.iter().map(Option::is_some).collect::<Vec<_>>(),
+                kept
+            );
+            assert!(projected.iter().flatten().all(|m| m.content.is_empty()));
+        }
+
+        let album: Vec<TelegramRawMessage> = (1..=2)
+            .map(|id| album_item(id, "Alice", Some(42), "photo"))
+            .collect();
+        let refs: Vec<&TelegramRawMessage> = album.iter().collect();
+        let msg = parse_album(
+            &refs,
+            utc,
+            true,
+            MentionFormat::default(),
+            None,
+            Projection::minimal(),
+        )
+        .unwrap();
+        assert!(msg.content.is_empty());
+        assert!(msg.attachments.is_empty());
+    }
 }
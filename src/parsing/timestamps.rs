@@ -0,0 +1,121 @@
+//! Clock sanity checks on parsed timestamps.
+//!
+//! Parsers run each message through [`keep_message`] under the platform
+//! config's `clamp_timestamps` before numbering it, so dropped messages
+//! leave no gap in `source_index`.
+
+#[cfg(any(
+    feature = "telegram",
+    feature = "instagram",
+    feature = "discord-json",
+    feature = "streaming"
+))]
+use crate::Message;
+use crate::config::{TimestampAction, TimestampCheck};
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+use crate::sink::{MessageSink, SkipReason};
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+use chrono::{DateTime, Utc};
+
+/// Applies `check` to one message, counting it in `flagged` if its
+/// timestamp fails.
+///
+/// Returns `false` if the message should be dropped.
+#[cfg(any(
+    feature = "telegram",
+    feature = "instagram",
+    feature = "discord-json",
+    feature = "streaming"
+))]
+pub(crate) fn keep_message(
+    msg: &mut Message,
+    check: Option<&TimestampCheck>,
+    flagged: &mut usize,
+) -> bool {
+    match check.and_then(|check| check.apply(&mut msg.timestamp)) {
+        None => true,
+        Some(action) => {
+            *flagged += 1;
+            action != TimestampAction::Drop
+        }
+    }
+}
+
+/// Applies `check` to the timestamp of a message about to be handed to
+/// `sink`, reporting a failure with [`SkipReason::ImplausibleTimestamp`].
+///
+/// Returns `false` if the message should be dropped.
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+pub(crate) fn keep_timestamp(
+    timestamp: &mut Option<DateTime<Utc>>,
+    check: Option<&TimestampCheck>,
+    sink: &mut dyn MessageSink,
+) -> bool {
+    let Some(parsed) = *timestamp else {
+        return true;
+    };
+    match check.and_then(|check| check.apply(timestamp)) {
+        None => true,
+        Some(action) => {
+            sink.on_skip(&SkipReason::ImplausibleTimestamp {
+                timestamp: parsed,
+                action,
+            });
+            action != TimestampAction::Drop
+        }
+    }
+}
+
+/// Applies `check` to every message, removing the dropped ones.
+///
+/// Returns how many messages failed the check.
+#[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
+pub(crate) fn check_messages(messages: &mut Vec<Message>, check: Option<&TimestampCheck>) -> usize {
+    let mut flagged = 0;
+    if check.is_some() {
+        messages.retain_mut(|msg| keep_message(msg, check, &mut flagged));
+    }
+    flagged
+}
+
+#[cfg(all(
+    test,
+    any(feature = "telegram", feature = "instagram", feature = "discord-json")
+))]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn messages() -> Vec<Message> {
+        [0, 1_705_314_600, 4_861_000_000]
+            .into_iter()
+            .map(|secs| {
+                Message::new("A", "x").with_timestamp(DateTime::from_timestamp(secs, 0).unwrap())
+            })
+            .chain([Message::new("A", "no timestamp")])
+            .collect()
+    }
+
+    #[test]
+    fn test_check_messages_actions() {
+        let mut dropped = messages();
+        let check = TimestampCheck::new(TimestampAction::Drop);
+        assert_eq!(check_messages(&mut dropped, Some(&check)), 2);
+        assert_eq!(dropped.len(), 2);
+
+        let mut nullified = messages();
+        let check = TimestampCheck::new(TimestampAction::Nullify);
+        assert_eq!(check_messages(&mut nullified, Some(&check)), 2);
+        assert_eq!(
+            nullified.iter().filter(|m| m.timestamp.is_none()).count(),
+            3
+        );
+
+        let mut warned = messages();
+        let check = TimestampCheck::new(TimestampAction::Warn);
+        assert_eq!(check_messages(&mut warned, Some(&check)), 2);
+        assert_eq!(warned, messages());
+
+        assert_eq!(check_messages(&mut warned, None), 0);
+    }
+}
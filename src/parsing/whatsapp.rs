@@ -3,8 +3,27 @@
 //! This module contains types and functions shared between the standard
 //! and streaming WhatsApp parsers.
 
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, BufRead};
+
 use chrono::{DateTime, NaiveDateTime, Utc};
-use regex::Regex;
+use regex::{Captures, Regex};
+
+use super::allowlist::allows;
+use super::digits::ascii_digits;
+use super::encoding::{CappedLine, read_capped_line};
+use super::months::numeric_month;
+pub use super::quotes::REPLYING_TO_PREFIX;
+use super::quotes::{QUOTE_WINDOW, starts_with_words, trim_snippet, unresolved_marker};
+use crate::core::textutil::prefix_graphemes;
+pub use crate::message::MESSAGE_DELETED;
+
+/// Number of leading lines used to detect the date format.
+pub(crate) const DETECTION_SAMPLE: usize = 20;
+
+/// Number of leading bytes of a line matched during format detection:
+/// room for the date, the time, and a long sender name.
+const DETECTION_PREFIX_LEN: usize = 256;
 
 /// Detected date format variants for WhatsApp exports.
 ///
@@ -26,6 +45,12 @@ pub enum DateFormat {
     /// Bracketed EU with slashes
     /// Example: [15/01/2024, 10:30:45]
     EuSlashBracketed,
+    /// Month name, no brackets: D Mon YYYY
+    /// Example: 15 Jan 2024, 10:30 - Sender: Message
+    MonthName,
+    /// Month name in brackets: D Mon YYYY
+    /// Example: [15 Jan 2024, 10:30:45]
+    MonthNameBracketed,
 }
 
 impl DateFormat {
@@ -52,6 +77,14 @@ impl DateFormat {
             DateFormat::EuSlashBracketed => {
                 r"^\[(\d{2}/\d{2}/\d{2,4}),\s(\d{2}:\d{2}(?::\d{2})?)\]\s([^:]+):\s?(.*)"
             }
+            // 15 Jan 2024, 10:30 - Sender: Message
+            DateFormat::MonthName => {
+                r"^(\d{1,2}\s\p{L}{3,}\.?\s\d{2,4}),\s(\d{1,2}:\d{2}(?::\d{2})?(?:\s?[APap][Mm])?)\s-\s([^:]+):\s?(.*)"
+            }
+            // [15 Jan 2024, 10:30:45] Sender: Message
+            DateFormat::MonthNameBracketed => {
+                r"^\[(\d{1,2}\s\p{L}{3,}\.?\s\d{2,4}),\s(\d{1,2}:\d{2}(?::\d{2})?(?:\s?[APap][Mm])?)\]\s([^:]+):\s?(.*)"
+            }
         }
     }
 
@@ -80,10 +113,23 @@ impl DateFormat {
                 "%d/%m/%Y, %H:%M:%S",
                 "%d/%m/%Y, %H:%M",
             ],
+            // The month name is replaced by its number before parsing
+            DateFormat::MonthName | DateFormat::MonthNameBracketed => &[
+                "%d %m %y, %H:%M:%S",
+                "%d %m %y, %H:%M",
+                "%d %m %Y, %H:%M:%S",
+                "%d %m %Y, %H:%M",
+                "%d %m %y, %I:%M:%S %p",
+                "%d %m %y, %I:%M %p",
+                "%d %m %Y, %I:%M:%S %p",
+                "%d %m %Y, %I:%M %p",
+            ],
         }
     }
 
     /// Returns all format variants.
+    ///
+    /// Numeric formats come first, so detection prefers them on a tie.
     pub fn all() -> &'static [DateFormat] {
         &[
             DateFormat::US,
@@ -91,6 +137,8 @@ impl DateFormat {
             DateFormat::EuDotNoBracket,
             DateFormat::EuSlash,
             DateFormat::EuSlashBracketed,
+            DateFormat::MonthName,
+            DateFormat::MonthNameBracketed,
         ]
     }
 }
@@ -101,7 +149,9 @@ pub fn parse_whatsapp_timestamp(
     time_str: &str,
     format: DateFormat,
 ) -> Option<DateTime<Utc>> {
-    let datetime_str = format!("{date_str}, {time_str}");
+    // Locales such as Arabic and Hindi write timestamps in their own digits
+    let date_str = ascii_digits(date_str);
+    let datetime_str = format!("{}, {}", numeric_month(&date_str)?, ascii_digits(time_str));
 
     for parse_format in format.date_parse_formats() {
         if let Ok(naive) = NaiveDateTime::parse_from_str(&datetime_str, parse_format) {
@@ -112,9 +162,529 @@ pub fn parse_whatsapp_timestamp(
     None
 }
 
+/// Canonical marker that localized media placeholders are normalized to.
+pub const MEDIA_OMITTED: &str = "[Media omitted]";
+
+/// Media placeholders written by WhatsApp in place of omitted attachments.
+///
+/// Android writes a single bracketed placeholder per locale; iOS writes one
+/// per media kind, prefixed with a left-to-right mark.
+const MEDIA_PLACEHOLDERS: &[&str] = &[
+    // English
+    "<Media omitted>",
+    "image omitted",
+    "video omitted",
+    "audio omitted",
+    "sticker omitted",
+    "GIF omitted",
+    "document omitted",
+    // German
+    "<Medien ausgeschlossen>",
+    "<Medien weggelassen>",
+    "Bild weggelassen",
+    "Video weggelassen",
+    "Audio weggelassen",
+    "Sticker weggelassen",
+    "GIF weggelassen",
+    // French
+    "<Médias omis>",
+    "image absente",
+    "vidéo absente",
+    "audio omis",
+    "sticker omis",
+    "GIF retiré",
+    // Spanish
+    "<Multimedia omitido>",
+    "imagen omitida",
+    "video omitido",
+    "audio omitido",
+    "sticker omitido",
+    "GIF omitido",
+    // Portuguese
+    "<Mídia oculta>",
+    "<Arquivo de mídia oculto>",
+    "imagem ocultada",
+    "vídeo omitido",
+    "áudio ocultado",
+    "figurinha omitida",
+    // Russian
+    "<Без медиафайлов>",
+    "изображение отсутствует",
+    "видео отсутствует",
+    "аудиофайл отсутствует",
+    "стикер отсутствует",
+    // Turkish
+    "<Medya dahil edilmedi>",
+    "<Medya atlandı>",
+    "görüntü dahil edilmedi",
+    "video dahil edilmedi",
+    "ses dahil edilmedi",
+    "çıkartma dahil edilmedi",
+];
+
+/// Lines WhatsApp writes in place of a deleted message, by the sender
+/// (`You deleted this message`) and for everyone else, per locale.
+///
+/// Matched ignoring case, directionality marks, and a trailing period.
+const DELETED_PLACEHOLDERS: &[&str] = &[
+    // English
+    "This message was deleted",
+    "You deleted this message",
+    // German
+    "Diese Nachricht wurde gelöscht",
+    "Du hast diese Nachricht gelöscht",
+    // French
+    "Ce message a été supprimé",
+    "Vous avez supprimé ce message",
+    // Spanish
+    "Se eliminó este mensaje",
+    "Eliminaste este mensaje",
+    // Portuguese
+    "Esta mensagem foi apagada",
+    "Você apagou esta mensagem",
+    // Russian
+    "Данное сообщение удалено",
+    "Это сообщение удалено",
+    "Вы удалили данное сообщение",
+    "Вы удалили это сообщение",
+    // Turkish
+    "Bu mesaj silindi",
+    "Bu mesajı sildiniz",
+];
+
+/// Strips leading directionality marks (U+200E LRM, U+200F RLM).
+///
+/// iOS exports prefix media lines and some senders with these invisible
+/// characters, which would otherwise break line matching and leak into
+/// sender names.
+pub fn strip_direction_marks(s: &str) -> &str {
+    s.trim_start_matches(['\u{200E}', '\u{200F}'])
+}
+
+/// Trims whitespace and directionality marks from both ends of a sender.
+pub fn trim_whatsapp_sender(sender: &str) -> &str {
+    sender.trim_matches(|c: char| c.is_whitespace() || c == '\u{200E}' || c == '\u{200F}')
+}
+
+/// Returns [`MEDIA_OMITTED`] if `content` is a known localized media
+/// placeholder, ignoring case and leading directionality marks.
+pub fn normalize_media_placeholder(content: &str) -> Option<&'static str> {
+    let content = strip_direction_marks(content.trim()).trim();
+    is_placeholder(MEDIA_PLACEHOLDERS, content).then_some(MEDIA_OMITTED)
+}
+
+/// Returns [`MESSAGE_DELETED`] if `content` is a known localized
+/// deleted-message placeholder, ignoring case, directionality marks, and a
+/// trailing period.
+pub fn normalize_deleted_placeholder(content: &str) -> Option<&'static str> {
+    let content = strip_direction_marks(content.trim())
+        .trim_end_matches(['\u{200E}', '\u{200F}'])
+        .trim();
+    let content = content.strip_suffix('.').unwrap_or(content);
+    is_placeholder(DELETED_PLACEHOLDERS, content).then_some(MESSAGE_DELETED)
+}
+
+/// Returns whether `content` is one of `placeholders`, ignoring case.
+fn is_placeholder(placeholders: &[&str], content: &str) -> bool {
+    let lower = || content.chars().flat_map(char::to_lowercase);
+    placeholders
+        .iter()
+        .any(|placeholder| placeholder.chars().flat_map(char::to_lowercase).eq(lower()))
+}
+
+/// Grapheme clusters of each recent message kept for matching quoted
+/// snippets.
+const QUOTE_PREFIX_LEN: usize = 512;
+
+/// Returns the text of a quote-block line (`> text`, or a bare `>`).
+fn quote_line(line: &str) -> Option<&str> {
+    if line == ">" {
+        Some("")
+    } else {
+        line.strip_prefix("> ")
+    }
+}
+
+/// Splits a leading quote block from message content.
+///
+/// Some iOS exports put the snippet a reply quotes before the reply text,
+/// one `> `-prefixed line per quoted line:
+///
+/// ```text
+/// [1/15/24, 10:31:00 AM] Bob: > Are we still on for
+/// > lunch tomorrow?
+/// Yes, 12:30 works
+/// ```
+///
+/// Returns the snippet, with its lines joined by spaces, and the content
+/// after the block. Returns `None` if there is no block or nothing follows
+/// it.
+///
+/// ```rust
+/// use chatpack::parsing::whatsapp::split_quote;
+///
+/// let (snippet, rest) = split_quote("> Are we still on for\n> lunch?\nYes").unwrap();
+/// assert_eq!(snippet, "Are we still on for lunch?");
+/// assert_eq!(rest, "Yes");
+/// assert!(split_quote("> just a quote").is_none());
+/// ```
+pub fn split_quote(content: &str) -> Option<(String, &str)> {
+    let mut snippet = String::new();
+    let mut rest = content;
+    loop {
+        let (line, tail) = rest.split_once('\n').unwrap_or((rest, ""));
+        let Some(quoted) = quote_line(line) else {
+            break;
+        };
+        if !snippet.is_empty() {
+            snippet.push(' ');
+        }
+        snippet.push_str(quoted.trim());
+        rest = tail;
+    }
+    if snippet.is_empty() || rest.trim().is_empty() {
+        return None;
+    }
+    Some((snippet, rest))
+}
+
+/// Matches quoted snippets against recently parsed messages.
+///
+/// Parsers [`record`](Self::record) each message as they emit it and call
+/// [`take_quote`](Self::take_quote) on the next one. Replies resolve to the
+/// [`source_index`](crate::Message::source_index) of the most recent
+/// message among the last 500 whose content starts with the snippet, since
+/// WhatsApp exports carry no message ids.
+#[derive(Debug, Default)]
+pub struct QuoteResolver {
+    recent: VecDeque<(u64, String)>,
+}
+
+impl QuoteResolver {
+    /// Creates a resolver with no recorded messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the index of the most recent message that starts with
+    /// `snippet`, ignoring a trailing ellipsis and differences in whitespace.
+    pub fn resolve(&self, snippet: &str) -> Option<u64> {
+        let snippet = trim_snippet(snippet);
+        if snippet.is_empty() {
+            return None;
+        }
+        self.recent
+            .iter()
+            .rev()
+            .find(|(_, content)| starts_with_words(content, snippet))
+            .map(|&(index, _)| index)
+    }
+
+    /// Records an emitted message so later quotes can resolve to it.
+    pub fn record(&mut self, index: u64, content: &str) {
+        let content = match content.strip_prefix(REPLYING_TO_PREFIX) {
+            Some(marked) => marked.split_once('\n').map_or("", |(_, rest)| rest),
+            None => content,
+        };
+        let prefix = prefix_graphemes(content, QUOTE_PREFIX_LEN);
+
+        let mut slot = if self.recent.len() == QUOTE_WINDOW {
+            self.recent.pop_front().map(|(_, s)| s).unwrap_or_default()
+        } else {
+            String::new()
+        };
+        slot.clear();
+        slot.push_str(prefix);
+        self.recent.push_back((index, slot));
+    }
+
+    /// Removes a leading quote block from `content` and returns the index of
+    /// the quoted message.
+    ///
+    /// An unresolved quote is replaced by a `[Replying to: …]` line when
+    /// `mark_unresolved` is set, and dropped otherwise.
+    pub fn take_quote(&self, content: &mut String, mark_unresolved: bool) -> Option<u64> {
+        let (snippet, rest) = split_quote(content)?;
+        let block_len = content.len() - rest.len();
+        let reply_to = self.resolve(&snippet);
+        if reply_to.is_none() && mark_unresolved {
+            content.replace_range(..block_len, &format!("{}\n", unresolved_marker(&snippet)));
+        } else {
+            content.drain(..block_len);
+        }
+        reply_to
+    }
+}
+
+/// Senders learned from a whole export, for headers that can be split into
+/// sender and content in more than one place.
+///
+/// The date format patterns take everything up to the first `:` as the
+/// sender, so a contact saved as `Mom: work` becomes the sender `Mom` with
+/// content starting `work: `. Every later `: ` in a header is another
+/// possible split. After every header of an export has been
+/// [observed](Self::observe), [`split`](Self::split) picks the longest
+/// candidate sender that is known, preferring
+///
+/// 1. senders of headers that can only be split one way, then
+/// 2. the longest candidate of at least two headers, which catches senders
+///    whose every header is ambiguous.
+///
+/// Headers with no known candidate keep the first split, so content such
+/// as `Note: remember` stays with the sender before it.
+#[derive(Debug, Clone, Default)]
+pub struct KnownSenders {
+    /// Senders of headers with a single possible split
+    unambiguous: HashSet<String>,
+    /// Number of ambiguous headers each candidate is the longest split of
+    repeated: HashMap<String, usize>,
+}
+
+impl KnownSenders {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the text of a header after its date, such as
+    /// `Mom: work: Hi`.
+    pub fn observe(&mut self, header: &str) {
+        match sender_splits(header).as_slice() {
+            [] => {}
+            [only] => {
+                self.unambiguous
+                    .insert(trim_whatsapp_sender(&header[..*only]).to_string());
+            }
+            [.., last] => {
+                *self
+                    .repeated
+                    .entry(trim_whatsapp_sender(&header[..*last]).to_string())
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    /// Records the header of `line` if `regex` matches it.
+    pub fn observe_line(&mut self, line: &str, regex: &Regex) {
+        if let Some(sender) = regex.captures(line).and_then(|caps| caps.get(3)) {
+            self.observe(&line[sender.start()..]);
+        }
+    }
+
+    /// Returns the sender and content of `header` when a known sender ends
+    /// after the first possible split, or `None` to keep the first split.
+    pub fn split<'h>(&self, header: &'h str) -> Option<(&'h str, &'h str)> {
+        let splits = sender_splits(header);
+        if splits.len() < 2 {
+            return None;
+        }
+        let sender = |end: &usize| trim_whatsapp_sender(&header[..*end]);
+        let end = splits
+            .iter()
+            .rev()
+            .find(|end| self.unambiguous.contains(sender(end)))
+            .or_else(|| {
+                splits
+                    .iter()
+                    .rev()
+                    .find(|end| self.repeated.get(sender(end)).is_some_and(|&n| n >= 2))
+            })?;
+        if *end == splits[0] {
+            return None;
+        }
+
+        // Like the patterns, drop one whitespace character after the colon
+        let content = &header[end + 1..];
+        let mut chars = content.chars();
+        let content = if chars.next().is_some_and(char::is_whitespace) {
+            chars.as_str()
+        } else {
+            content
+        };
+        Some((sender(end), content))
+    }
+}
+
+/// Returns the offsets of the colons a header could be split at: the first
+/// `:`, as the patterns split, and every later `: `, as long as the text
+/// before them is a [plausible sender](is_plausible_sender).
+fn sender_splits(header: &str) -> Vec<usize> {
+    let Some(first) = header.find(':') else {
+        return Vec::new();
+    };
+    let rest = first + 1;
+    let mut splits = vec![first];
+    splits.extend(header[rest..].match_indices(": ").map(|(i, _)| rest + i));
+    // Later splits only make the sender longer
+    splits.retain(|&end| is_plausible_sender(trim_whatsapp_sender(&header[..end])));
+    splits
+}
+
+/// Longest sender, in characters, a header is read as having.
+///
+/// Contact names are far shorter. A longer "sender" means the colon
+/// belongs to the text of a line without one, such as a system line
+/// quoting a long group description that contains `: `.
+pub const MAX_SENDER_CHARS: usize = 64;
+
+/// Returns `true` if `sender` could be a contact name: one line of at most
+/// [`MAX_SENDER_CHARS`] characters.
+pub fn is_plausible_sender(sender: &str) -> bool {
+    !sender.contains('\n') && sender.chars().count() <= MAX_SENDER_CHARS
+}
+
+/// Reads a whole export from `reader` and learns its senders, detecting the
+/// date format from the first lines as the parsers do. Lines longer than
+/// `max_line_length` bytes are ignored.
+///
+/// Returns an empty set if the format cannot be detected.
+pub(crate) fn learn_senders(
+    reader: &mut dyn BufRead,
+    max_line_length: usize,
+) -> io::Result<KnownSenders> {
+    let mut known = KnownSenders::new();
+    let mut sample: Vec<String> = Vec::with_capacity(DETECTION_SAMPLE);
+    let mut regex: Option<Regex> = None;
+    let mut buf = Vec::new();
+
+    while let Some((line, _)) = read_capped_line(reader, &mut buf, max_line_length)? {
+        let CappedLine::Line(line) = line else {
+            continue;
+        };
+        let line = strip_direction_marks(line);
+        if let Some(regex) = &regex {
+            known.observe_line(line, regex);
+            continue;
+        }
+        sample.push(line.to_string());
+        if sample.len() == DETECTION_SAMPLE {
+            regex = observe_sample(&mut known, &sample);
+            if regex.is_none() {
+                return Ok(known);
+            }
+        }
+    }
+    if regex.is_none() {
+        observe_sample(&mut known, &sample);
+    }
+    Ok(known)
+}
+
+/// Detects the date format of `sample` and observes its headers, returning
+/// the format's pattern.
+fn observe_sample(known: &mut KnownSenders, sample: &[String]) -> Option<Regex> {
+    let regex = Regex::new(detect_whatsapp_format_owned(sample)?.pattern()).ok()?;
+    for line in sample {
+        known.observe_line(line, &regex);
+    }
+    Some(regex)
+}
+
+/// Returns the sender and content of a header matched by a date format's
+/// pattern, split after a known sender if `known` is given.
+///
+/// The patterns take everything between the date and the first `:` as the
+/// sender, so a contact saved as `Alice - Bob` keeps its ` - `. When that
+/// text is not a [plausible sender](is_plausible_sender), the line has no
+/// sender: it comes back empty, with everything after the date as content.
+pub(crate) fn header_parts<'l>(
+    line: &'l str,
+    caps: &Captures<'l>,
+    known: Option<&KnownSenders>,
+) -> (&'l str, &'l str) {
+    let content = caps.get(4).map_or("", |m| m.as_str());
+    let Some(sender) = caps.get(3) else {
+        return ("", content);
+    };
+    if let Some(known) = known {
+        if let Some(parts) = known.split(&line[sender.start()..]) {
+            return parts;
+        }
+    }
+    let name = trim_whatsapp_sender(sender.as_str());
+    if !is_plausible_sender(name) {
+        return ("", line[sender.start()..].trim_end());
+    }
+    (name, content)
+}
+
+/// What a line of an export does to the message being read, as decided by
+/// [`classify_line`].
+///
+/// Both WhatsApp parsers hold a message open until the next header and
+/// act on each line the same way, so they produce the same messages from
+/// the same file.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum LineKind<'l> {
+    /// A blank line: ignored, also inside a multi-line message.
+    Blank,
+    /// A header: ends the open message and starts a new one.
+    Header {
+        sender: &'l str,
+        content: &'l str,
+        timestamp: Option<DateTime<Utc>>,
+    },
+    /// The header of a system message that is being skipped. The open
+    /// message stays open, so lines after it continue that message.
+    SystemMessage,
+    /// The header of a message from a sender outside the allowlist: ends
+    /// the open message, and its own continuation lines are dropped.
+    Excluded,
+    /// Any other line: continues the open message, if there is one.
+    Continuation,
+}
+
+/// Options deciding how [`classify_line`] reads headers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LineRules {
+    pub(crate) normalize_media_placeholders: bool,
+    pub(crate) normalize_deleted_messages: bool,
+    pub(crate) skip_system_messages: bool,
+    pub(crate) sender_allowlist: Option<Vec<String>>,
+    /// Senders learned in a first pass, with strict sender detection
+    pub(crate) known_senders: Option<KnownSenders>,
+}
+
+/// Classifies one line (without direction marks) of an export in `format`,
+/// whose header pattern `regex` is.
+pub(crate) fn classify_line<'l>(
+    line: &'l str,
+    format: DateFormat,
+    regex: &Regex,
+    rules: &LineRules,
+) -> LineKind<'l> {
+    if line.trim().is_empty() {
+        return LineKind::Blank;
+    }
+    let Some(caps) = regex.captures(line) else {
+        return LineKind::Continuation;
+    };
+    let (sender, mut content) = header_parts(line, &caps, rules.known_senders.as_ref());
+    if rules.normalize_media_placeholders {
+        content = normalize_media_placeholder(content).unwrap_or(content);
+    }
+    if rules.normalize_deleted_messages {
+        content = normalize_deleted_placeholder(content).unwrap_or(content);
+    }
+    if rules.skip_system_messages && is_whatsapp_system_message(sender, content) {
+        return LineKind::SystemMessage;
+    }
+    if !allows(rules.sender_allowlist.as_deref(), sender) {
+        return LineKind::Excluded;
+    }
+    let date_str = caps.get(1).map_or("", |m| m.as_str());
+    let time_str = caps.get(2).map_or("", |m| m.as_str());
+    LineKind::Header {
+        sender,
+        content,
+        timestamp: parse_whatsapp_timestamp(date_str, time_str, format),
+    }
+}
+
 /// Check if a line is a system message (no actual sender).
 ///
 /// System messages include: group created, user added/left, encryption notice, etc.
+/// Some exports attribute these to a literal `null` sender.
 pub fn is_whatsapp_system_message(sender: &str, content: &str) -> bool {
     // English system indicators
     let system_indicators_en = [
@@ -176,7 +746,10 @@ pub fn is_whatsapp_system_message(sender: &str, content: &str) -> bool {
     }
 
     // Check if sender is empty or system-like
-    sender.trim().is_empty() || sender_lower.contains("whatsapp") || sender_lower.contains("system")
+    sender.trim().is_empty()
+        || sender.trim() == "null"
+        || sender_lower.contains("whatsapp")
+        || sender_lower.contains("system")
 }
 
 /// Detection result for format auto-detection.
@@ -193,34 +766,57 @@ impl FormatDetector {
         }
     }
 
-    fn matches(&self, line: &str) -> bool {
-        self.regex.is_match(line)
+    /// Scores `line` as `(parsed, matched)`: whether the line matches the
+    /// pattern and whether its timestamp then parses in this format.
+    ///
+    /// Only the start of the line is matched, since the header is all that
+    /// tells the formats apart.
+    fn score(&self, line: &str) -> (usize, usize) {
+        let Some(caps) = self.regex.captures(detection_prefix(line)) else {
+            return (0, 0);
+        };
+        let parsed = parse_whatsapp_timestamp(&caps[1], &caps[2], self.format).is_some();
+        (usize::from(parsed), 1)
     }
 }
 
+/// Returns the first [`DETECTION_PREFIX_LEN`] bytes of `line`, shortened to
+/// a character boundary.
+fn detection_prefix(line: &str) -> &str {
+    if line.len() <= DETECTION_PREFIX_LEN {
+        return line;
+    }
+    let mut end = DETECTION_PREFIX_LEN;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    &line[..end]
+}
+
 /// Auto-detect date format by analyzing sample lines.
 ///
-/// Analyzes the provided lines and returns the most likely format.
-/// Returns `None` if no format matches any lines.
+/// Analyzes the provided lines and returns the most likely format: the one
+/// whose timestamps parse on the most lines, then the one matching the most
+/// lines. Returns `None` if no format matches any lines.
 pub fn detect_whatsapp_format(lines: &[&str]) -> Option<DateFormat> {
     let detectors: Vec<FormatDetector> = DateFormat::all()
         .iter()
         .map(|&f| FormatDetector::new(f))
         .collect();
 
-    let mut scores = vec![0usize; detectors.len()];
+    let mut scores = vec![(0usize, 0usize); detectors.len()];
 
     for line in lines {
         for (i, detector) in detectors.iter().enumerate() {
-            if detector.matches(line) {
-                scores[i] += 1;
-            }
+            let (parsed, matched) = detector.score(line);
+            scores[i].0 += parsed;
+            scores[i].1 += matched;
         }
     }
 
-    // Find the winner (highest score)
+    // Find the winner (highest score, earliest format on ties)
     let max_score = *scores.iter().max()?;
-    if max_score == 0 {
+    if max_score.1 == 0 {
         return None;
     }
 
@@ -237,6 +833,7 @@ pub fn detect_whatsapp_format_owned(lines: &[String]) -> Option<DateFormat> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::DEFAULT_MAX_LINE_LENGTH;
 
     #[test]
     fn test_detect_format_us() {
@@ -247,6 +844,13 @@ mod tests {
         assert_eq!(detect_whatsapp_format(&lines), Some(DateFormat::US));
     }
 
+    #[test]
+    fn test_detect_format_long_line() {
+        let long = format!("[1/15/24, 10:30:45 AM] Alicia: {}", "é".repeat(1000));
+        assert_eq!(detection_prefix(&long).len(), DETECTION_PREFIX_LEN - 1);
+        assert_eq!(detect_whatsapp_format(&[&long]), Some(DateFormat::US));
+    }
+
     #[test]
     fn test_detect_format_eu_dot_bracketed() {
         let lines = vec![
@@ -280,6 +884,48 @@ mod tests {
         assert_eq!(detect_whatsapp_format(&lines), Some(DateFormat::EuSlash));
     }
 
+    #[test]
+    fn test_detect_format_month_name() {
+        let lines = vec![
+            "15 Jan 2024, 10:30 - Alice: Hello",
+            "15 jan. 2024, 10:31 - Bob: Hi there",
+        ];
+        assert_eq!(detect_whatsapp_format(&lines), Some(DateFormat::MonthName));
+
+        let lines = vec![
+            "[15 Jan 2024, 10:30:45] Alice: Hello",
+            "[3 Sept 2024, 9:05:00 PM] Bob: Hi there",
+        ];
+        assert_eq!(
+            detect_whatsapp_format(&lines),
+            Some(DateFormat::MonthNameBracketed)
+        );
+    }
+
+    #[test]
+    fn test_detect_format_prefers_parsing_timestamps() {
+        // Both patterns match; only DD/MM parses a day of 15
+        let lines = vec![
+            "[15/01/2024, 10:30:45] Alice: Hello",
+            "[15/01/2024, 10:31:00] Bob: Hi there",
+        ];
+        assert_eq!(
+            detect_whatsapp_format(&lines),
+            Some(DateFormat::EuSlashBracketed)
+        );
+    }
+
+    #[test]
+    fn test_detect_format_arabic_indic_digits() {
+        let lines = vec!["[١٥/٠١/٢٤, ١٠:٣٠:٤٥] Alice: مرحبا"];
+        assert_eq!(
+            detect_whatsapp_format(&lines),
+            Some(DateFormat::EuSlashBracketed)
+        );
+        let lines = vec!["١٥/٠١/٢٠٢٤, ١٠:٣٠ - Bob: أهلا"];
+        assert_eq!(detect_whatsapp_format(&lines), Some(DateFormat::EuSlash));
+    }
+
     #[test]
     fn test_is_system_message_english() {
         assert!(is_whatsapp_system_message(
@@ -320,9 +966,276 @@ mod tests {
         assert!(ts2.is_some());
     }
 
+    #[test]
+    fn test_parse_timestamp_month_name() {
+        let expected = parse_whatsapp_timestamp("15/01/2024", "10:30", DateFormat::EuSlash);
+        assert!(expected.is_some());
+        for date in ["15 Jan 2024", "15 jan. 2024", "15 JANUARY 24"] {
+            let ts = parse_whatsapp_timestamp(date, "10:30", DateFormat::MonthName);
+            assert_eq!(ts, expected, "{date}");
+        }
+        let pm = parse_whatsapp_timestamp("15 Jan 2024", "10:30 PM", DateFormat::MonthName);
+        assert_eq!(pm, expected.map(|t| t + chrono::Duration::hours(12)));
+        assert!(parse_whatsapp_timestamp("15 Foo 2024", "10:30", DateFormat::MonthName).is_none());
+    }
+
+    #[test]
+    fn test_parse_timestamp_native_digits() {
+        let ascii = parse_whatsapp_timestamp("15/01/2024", "10:30", DateFormat::EuSlash);
+        assert!(ascii.is_some());
+        let arabic = parse_whatsapp_timestamp("١٥/٠١/٢٠٢٤", "١٠:٣٠", DateFormat::EuSlash);
+        let devanagari = parse_whatsapp_timestamp("१५/०१/२०२४", "१०:३०", DateFormat::EuSlash);
+        assert_eq!(arabic, ascii);
+        assert_eq!(devanagari, ascii);
+    }
+
     #[test]
     fn test_empty_sender_is_system() {
         assert!(is_whatsapp_system_message("", "Some message"));
         assert!(is_whatsapp_system_message("   ", "Some message"));
     }
+
+    #[test]
+    fn test_normalize_media_placeholder() {
+        assert_eq!(
+            normalize_media_placeholder("<Medien ausgeschlossen>"),
+            Some(MEDIA_OMITTED)
+        );
+        assert_eq!(
+            normalize_media_placeholder("\u{200E}image omitted"),
+            Some(MEDIA_OMITTED)
+        );
+        assert_eq!(
+            normalize_media_placeholder("<MÉDIAS OMIS>"),
+            Some(MEDIA_OMITTED)
+        );
+        assert_eq!(normalize_media_placeholder("image omitted, sadly"), None);
+        assert_eq!(normalize_media_placeholder("Hello"), None);
+    }
+
+    #[test]
+    fn test_normalize_deleted_placeholder() {
+        for content in [
+            "This message was deleted",
+            "\u{200E}You deleted this message.",
+            "Diese Nachricht wurde gelöscht.",
+            "Это сообщение удалено",
+            "  ce message a été supprimé  ",
+        ] {
+            assert_eq!(
+                normalize_deleted_placeholder(content),
+                Some(MESSAGE_DELETED),
+                "{content}"
+            );
+        }
+        assert_eq!(
+            normalize_deleted_placeholder("This message was deleted, sadly"),
+            None
+        );
+        assert_eq!(normalize_deleted_placeholder("Hello"), None);
+    }
+
+    #[test]
+    fn test_trim_whatsapp_sender() {
+        assert_eq!(trim_whatsapp_sender(" \u{200E}Alice\u{200F} "), "Alice");
+        assert_eq!(
+            strip_direction_marks("\u{200F}\u{200E}[1/15/24"),
+            "[1/15/24"
+        );
+    }
+
+    #[test]
+    fn test_split_quote() {
+        assert_eq!(
+            split_quote("> See you at\n>\n> noon…\nSure\nmore"),
+            Some(("See you at  noon…".to_string(), "Sure\nmore"))
+        );
+        assert_eq!(split_quote("Sure"), None);
+        assert_eq!(split_quote(">_< oops\nline"), None);
+        assert_eq!(split_quote("> only\n> quotes"), None);
+    }
+
+    #[test]
+    fn test_quote_resolver_matches_prefix() {
+        let mut quotes = QuoteResolver::new();
+        quotes.record(0, "Are we still on for lunch tomorrow?");
+        quotes.record(1, "Yes");
+        quotes.record(2, "Are we  still on?");
+
+        assert_eq!(quotes.resolve("Are we still on for lun…"), Some(0));
+        assert_eq!(quotes.resolve("Are we still"), Some(2));
+        assert_eq!(quotes.resolve("Are we going"), None);
+        assert_eq!(quotes.resolve("..."), None);
+    }
+
+    #[test]
+    fn test_take_quote() {
+        let mut quotes = QuoteResolver::new();
+        quotes.record(4, "Lunch tomorrow?");
+
+        let mut resolved = "> Lunch tomorrow?\nYes".to_string();
+        assert_eq!(quotes.take_quote(&mut resolved, true), Some(4));
+        assert_eq!(resolved, "Yes");
+
+        let mut marked = "> Dinner?\nNo".to_string();
+        assert_eq!(quotes.take_quote(&mut marked, true), None);
+        assert_eq!(marked, "[Replying to: Dinner?]\nNo");
+
+        let mut dropped = "> Dinner?\nNo".to_string();
+        assert_eq!(quotes.take_quote(&mut dropped, false), None);
+        assert_eq!(dropped, "No");
+
+        let mut plain = "No quote".to_string();
+        assert_eq!(quotes.take_quote(&mut plain, true), None);
+        assert_eq!(plain, "No quote");
+    }
+
+    #[test]
+    fn test_quote_resolver_records_reply_text_only() {
+        let mut quotes = QuoteResolver::new();
+        quotes.record(0, "[Replying to: gone]\nFine by me");
+        assert_eq!(quotes.resolve("Fine by"), Some(0));
+    }
+
+    #[test]
+    fn test_quote_resolver_window() {
+        let mut quotes = QuoteResolver::new();
+        for i in 0..=QUOTE_WINDOW as u64 {
+            quotes.record(i, &format!("message {i} of many"));
+        }
+        assert_eq!(quotes.resolve("message 0 of"), None);
+        assert_eq!(quotes.resolve("message 1 of"), Some(1));
+    }
+
+    #[test]
+    fn test_null_sender_is_system() {
+        assert!(is_whatsapp_system_message("null", "Alice joined"));
+        assert!(!is_whatsapp_system_message("Nullah", "Hi"));
+    }
+
+    // =========================================================================
+    // KnownSenders tests
+    // =========================================================================
+
+    #[test]
+    fn test_header_parts_dash_in_sender() {
+        for format in [DateFormat::EuDotNoBracket, DateFormat::EuSlash] {
+            let regex = Regex::new(format.pattern()).unwrap();
+            let date = if format == DateFormat::EuSlash {
+                "15/01/2024"
+            } else {
+                "15.01.2024"
+            };
+            let line = format!("{date}, 10:30 - Alice - Bob: Hello - world: again");
+            let caps = regex.captures(&line).unwrap();
+            assert_eq!(
+                header_parts(&line, &caps, None),
+                ("Alice - Bob", "Hello - world: again")
+            );
+        }
+    }
+
+    #[test]
+    fn test_header_parts_implausible_sender() {
+        let regex = Regex::new(DateFormat::EuDotNoBracket.pattern()).unwrap();
+        let text = format!("Alice changed the subject to \"{}: 2024\"", "x".repeat(60));
+        let line = format!("15.01.2024, 10:30 - {text}");
+        let caps = regex.captures(&line).unwrap();
+        assert_eq!(header_parts(&line, &caps, None), ("", text.as_str()));
+
+        let mut known = KnownSenders::new();
+        known.observe(&text);
+        assert_eq!(known.split(&text), None);
+        assert!(sender_splits(&text).is_empty());
+    }
+
+    #[test]
+    fn test_is_plausible_sender() {
+        assert!(is_plausible_sender("Alice - Bob"));
+        assert!(is_plausible_sender(&"ж".repeat(MAX_SENDER_CHARS)));
+        assert!(!is_plausible_sender(&"ж".repeat(MAX_SENDER_CHARS + 1)));
+        assert!(!is_plausible_sender("Alice\nBob"));
+    }
+
+    #[test]
+    fn test_known_senders_prefers_unambiguous() {
+        let mut known = KnownSenders::new();
+        known.observe("Alice: Hi");
+        known.observe("Alice: Note: milk");
+        known.observe("Alice: Note: eggs");
+        known.observe("Mom: work: Hello");
+        known.observe("Mom: work: See you");
+
+        // `Alice: Note` repeats, but `Alice` is known to be a whole sender
+        assert_eq!(known.split("Alice: Note: bread"), None);
+        assert_eq!(
+            known.split("Mom: work: Note: bread"),
+            Some(("Mom: work", "Note: bread"))
+        );
+        assert_eq!(known.split("Alice: Hi"), None);
+    }
+
+    #[test]
+    fn test_known_senders_repeated() {
+        let mut known = KnownSenders::new();
+        known.observe("Mom: work: Hello");
+        assert_eq!(known.split("Mom: work: See you"), None);
+
+        known.observe("Mom: work: See you");
+        assert_eq!(
+            known.split("Mom: work: See you"),
+            Some(("Mom: work", "See you"))
+        );
+        // Content of unknown senders keeps the first split
+        assert_eq!(known.split("Bob: work: later"), None);
+    }
+
+    #[test]
+    fn test_sender_splits() {
+        assert_eq!(sender_splits("Alice: Hi"), vec![5]);
+        assert_eq!(sender_splits("Mom: work: Hi: there"), vec![3, 9, 13]);
+        assert_eq!(sender_splits("Alice:12:30"), vec![5]);
+        assert!(sender_splits("no colon").is_empty());
+    }
+
+    #[test]
+    fn test_learn_senders() {
+        let export = "[1/15/24, 10:30:00 AM] Mom: work: Hello\n\
+                      [1/15/24, 10:31:00 AM] Mom: work: See you\n\
+                      [1/15/24, 10:32:00 AM] Alice: Note: remember\n";
+        let known = learn_senders(&mut export.as_bytes(), DEFAULT_MAX_LINE_LENGTH).unwrap();
+        assert_eq!(known.split("Mom: work: Bye"), Some(("Mom: work", "Bye")));
+        assert_eq!(known.split("Alice: Note: remember"), None);
+    }
+
+    #[test]
+    fn test_classify_line() {
+        let format = DateFormat::US;
+        let regex = Regex::new(format.pattern()).unwrap();
+        let mut rules = LineRules {
+            skip_system_messages: true,
+            ..LineRules::default()
+        };
+        let classify = |line, rules: &LineRules| classify_line(line, format, &regex, rules);
+
+        assert_eq!(classify("  ", &rules), LineKind::Blank);
+        assert_eq!(classify("more text", &rules), LineKind::Continuation);
+        assert_eq!(
+            classify("[1/15/24, 10:30:00 AM] Bob: left", &rules),
+            LineKind::SystemMessage
+        );
+        let LineKind::Header {
+            sender, content, ..
+        } = classify("[1/15/24, 10:30:00 AM] Alice: Hi", &rules)
+        else {
+            panic!("not a header");
+        };
+        assert_eq!((sender, content), ("Alice", "Hi"));
+
+        rules.sender_allowlist = Some(vec!["Bob".into()]);
+        assert_eq!(
+            classify("[1/15/24, 10:30:00 AM] Alice: Hi", &rules),
+            LineKind::Excluded
+        );
+    }
 }
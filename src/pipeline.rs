@@ -0,0 +1,1764 @@
+//! Parse, filter/merge, and write on separate threads.
+//!
+//! [`run_sequential`] does each step in turn on the calling thread.
+//! [`run_pipelined`] overlaps them: one thread parses, one filters and
+//! merges, and one writes, connected by bounded channels. The channel
+//! capacity bounds how many messages are in flight, so a slow writer holds
+//! back the parser instead of letting memory grow.
+//!
+//! Both produce byte-identical output for the same [`PipelineConfig`].
+//!
+//! [`build_corpus`] runs the pipeline over many exports in turn, labeling
+//! each record with the export it came from, and writes them all to one
+//! file.
+//!
+//! # Errors and cancellation
+//!
+//! An error in any stage stops the others: a failed writer closes its
+//! channel, which makes the upstream sends fail, and a failed parser closes
+//! its channel, which lets the downstream stages finish what was already
+//! sent. A parse error takes precedence over a write error.
+//!
+//! Cancelling the [`CancellationToken`] stops the parser at the next
+//! message. Messages already in the channels are still written, so the
+//! output is complete up to that point, and the run returns
+//! [`ChatpackError::Cancelled`]. Hook the token up to Ctrl-C to make an
+//! interrupted run leave a valid file behind.
+//!
+//! # Verification
+//!
+//! [`verify`] reads a finished output back and checks it against the
+//! [`ProcessingStats`] of the run that wrote it, as a last guard after
+//! filtering, merging, and transforms.
+//!
+//! # Memory
+//!
+//! [`run_pipelined`] holds at most about three times
+//! [`channel_capacity`](PipelineConfig::channel_capacity) messages.
+//! [`run_sequential`] holds the whole export, unless
+//! [`PipelineConfig::with_max_memory`] sets a budget: it then parses,
+//! filters, merges, and writes one window of messages at a time, sizing
+//! the windows from the average message size seen so far. The last run of
+//! messages from one sender is carried into the next window, so merging
+//! gives exactly the output of an unbounded run. The export is read with
+//! the platform's [streaming parser](create_streaming_parser), which
+//! loads Discord TXT and CSV exports whole, and the token cancels a
+//! windowed run as it does a pipelined one.
+//!
+//! # Throughput
+//!
+//! [`PipelineConfig::with_max_messages_per_sec`] and
+//! [`with_max_bytes_per_sec`](PipelineConfig::with_max_bytes_per_sec) slow
+//! [`run_pipelined`] down so it leaves CPU and disk to others on a shared
+//! machine. The parser thread sleeps between messages to stay under the
+//! limit, waking early when the token is cancelled; the parser itself is
+//! unchanged.
+//! [`with_progress`](PipelineConfig::with_progress) reports messages and
+//! megabytes per second, limited or not.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[cfg(feature = "csv-output")]
+//! # fn main() -> chatpack::Result<()> {
+//! use chatpack::parser::Platform;
+//! use chatpack::pipeline::{PipelineConfig, run_pipelined};
+//!
+//! let config = PipelineConfig::new(Platform::Telegram, "result.json", "out.csv")
+//!     .with_merge(true)
+//!     .with_channel_capacity(4096);
+//! let stats = run_pipelined(&config)?;
+//! println!("{} -> {} messages", stats.original_count, stats.merged_count);
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "csv-output"))]
+//! # fn main() {}
+//! ```
+
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::time::{Duration, Instant};
+use std::{fmt, thread};
+
+use serde::Deserialize;
+
+use crate::Message;
+use crate::convert::{self, ConvertOptions, processing_stats};
+use crate::core::filter::{FilterConfig, apply_filters};
+use crate::core::loss::LossReport;
+use crate::core::models::{BoundaryStyle, OutputConfig, SortKey};
+use crate::core::output::{OnEmpty, OnExists};
+use crate::core::processor::{
+    MergeConfig, OrderingCheck, OrderingReport, ProcessingStats, append_merged,
+    merge_consecutive_with_config,
+};
+use crate::core::textutil::senders_match;
+use crate::core::transform::{ContentTransform, TransformChain};
+use crate::error::ChatpackError;
+use crate::format::{OutputFormat, read_from_format, write_iter_to_format};
+use crate::parser::{Platform, create_parser, create_streaming_parser, detect_platform};
+use crate::progress::{Progress, ProgressCallback};
+use crate::sink::{MessageRef, MessageSink, SkipReason};
+
+/// Default number of messages each channel can hold.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Cancels a running pipeline from another thread.
+///
+/// Cloning shares the flag, so keep one clone in the signal handler and
+/// pass another to [`PipelineConfig::with_cancellation`].
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// What to parse, how to process it, and where to write it.
+#[derive(Clone)]
+pub struct PipelineConfig {
+    /// Platform of the input export.
+    pub platform: Platform,
+    /// Export file to parse.
+    pub input: PathBuf,
+    /// File to write.
+    pub output: PathBuf,
+    /// Output format.
+    pub format: OutputFormat,
+    /// Which fields to write.
+    pub output_config: OutputConfig,
+    /// Messages to keep.
+    pub filter: FilterConfig,
+    /// Merge consecutive messages from the same sender.
+    pub merge: bool,
+    /// How merging compares senders.
+    pub merge_config: MergeConfig,
+    /// Content rewrites run after filtering and before merging.
+    pub transforms: TransformChain,
+    /// Capacity of each channel between stages, in messages.
+    pub channel_capacity: usize,
+    /// What to do when no messages are left to write.
+    pub on_empty: OnEmpty,
+    /// Stops the run early when cancelled.
+    pub cancellation: CancellationToken,
+    /// Most messages to parse per second; `None` for no limit.
+    pub max_messages_per_sec: Option<u64>,
+    /// Most bytes of message text to parse per second; `None` for no limit.
+    pub max_bytes_per_sec: Option<u64>,
+    /// Called with the progress of parsing.
+    pub on_progress: Option<ProgressCallback>,
+    /// Memory [`run_sequential`] may use for messages, in bytes; `None`
+    /// for no limit.
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl PipelineConfig {
+    /// Creates a config that parses `input` and writes CSV to `output`,
+    /// without filtering or merging.
+    pub fn new(platform: Platform, input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Self {
+        Self {
+            platform,
+            input: input.into(),
+            output: output.into(),
+            format: OutputFormat::default(),
+            output_config: OutputConfig::default(),
+            filter: FilterConfig::default(),
+            merge: false,
+            merge_config: MergeConfig::default(),
+            transforms: TransformChain::default(),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            on_empty: OnEmpty::default(),
+            cancellation: CancellationToken::default(),
+            max_messages_per_sec: None,
+            max_bytes_per_sec: None,
+            on_progress: None,
+            max_memory_bytes: None,
+        }
+    }
+
+    /// Sets the output format.
+    #[must_use]
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets which fields to write.
+    #[must_use]
+    pub fn with_output_config(mut self, config: OutputConfig) -> Self {
+        self.output_config = config;
+        self
+    }
+
+    /// Sets the filter.
+    #[must_use]
+    pub fn with_filter(mut self, filter: FilterConfig) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Enables or disables merging consecutive messages.
+    #[must_use]
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Sets how merging compares senders.
+    #[must_use]
+    pub fn with_merge_config(mut self, config: MergeConfig) -> Self {
+        self.merge_config = config;
+        self
+    }
+
+    /// Adds a transform to run after filtering and before merging.
+    #[must_use]
+    pub fn with_transform(mut self, transform: impl ContentTransform + 'static) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Sets the transforms to run after filtering and before merging.
+    #[must_use]
+    pub fn with_transforms(mut self, transforms: TransformChain) -> Self {
+        self.transforms = transforms;
+        self
+    }
+
+    /// Sets the capacity of each channel; `0` is treated as `1`.
+    #[must_use]
+    pub fn with_channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity;
+        self
+    }
+
+    /// Sets what to do when no messages are left to write.
+    #[must_use]
+    pub fn with_on_empty(mut self, on_empty: OnEmpty) -> Self {
+        self.on_empty = on_empty;
+        self
+    }
+
+    /// Sets the token that cancels the run.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Limits parsing to `limit` messages per second; `0` means no limit.
+    ///
+    /// Only [`run_pipelined`] is limited.
+    #[must_use]
+    pub fn with_max_messages_per_sec(mut self, limit: u64) -> Self {
+        self.max_messages_per_sec = (limit > 0).then_some(limit);
+        self
+    }
+
+    /// Limits parsing to `limit` bytes of sender and content text per
+    /// second; `0` means no limit.
+    ///
+    /// Only [`run_pipelined`] is limited.
+    #[must_use]
+    pub fn with_max_bytes_per_sec(mut self, limit: u64) -> Self {
+        self.max_bytes_per_sec = (limit > 0).then_some(limit);
+        self
+    }
+
+    /// Sets a callback that gets the [`Progress`] of parsing about every
+    /// [`PROGRESS_INTERVAL`] and once more when parsing ends.
+    ///
+    /// [`Progress::items_processed`] counts the messages parsed and
+    /// [`Progress::bytes_processed`] their sender and content text, and
+    /// [`Progress::elapsed`] is set, so the callback can show
+    /// [`items_per_sec`](Progress::items_per_sec) and
+    /// [`megabytes_per_sec`](Progress::megabytes_per_sec). The total size
+    /// is not known.
+    ///
+    /// The callback runs on the parser thread of [`run_pipelined`], so it
+    /// should return quickly. [`run_sequential`] does not call it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::sync::Arc;
+    ///
+    /// use chatpack::parser::Platform;
+    /// use chatpack::pipeline::PipelineConfig;
+    ///
+    /// let config = PipelineConfig::new(Platform::WhatsApp, "chat.txt", "out.csv")
+    ///     .with_max_messages_per_sec(50_000)
+    ///     .with_progress(Arc::new(|p| {
+    ///         eprint!("\r{:.0} msg/s, {:.1} MB/s", p.items_per_sec(), p.megabytes_per_sec());
+    ///     }));
+    /// ```
+    #[must_use]
+    pub fn with_progress(mut self, callback: ProgressCallback) -> Self {
+        self.on_progress = Some(callback);
+        self
+    }
+
+    /// Makes [`run_sequential`] process the input in windows that keep
+    /// the messages it holds under about `bytes`; `0` means no limit.
+    ///
+    /// A merged message is held until its run ends, so a long run from one
+    /// sender can still go over the budget, as can sorted output
+    /// ([`OutputConfig::sort_by`]), which needs every message at once.
+    /// [`run_pipelined`] is bounded by
+    /// [`channel_capacity`](Self::channel_capacity) instead.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> chatpack::Result<()> {
+    /// use chatpack::core::units::parse_size;
+    /// use chatpack::parser::Platform;
+    /// use chatpack::pipeline::{PipelineConfig, run_sequential};
+    ///
+    /// let config = PipelineConfig::new(Platform::Telegram, "result.json", "out.jsonl")
+    ///     .with_merge(true)
+    ///     .with_max_memory(parse_size("256MB")?);
+    /// run_sequential(&config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_max_memory(mut self, bytes: u64) -> Self {
+        self.max_memory_bytes = (bytes > 0).then_some(bytes);
+        self
+    }
+
+    fn stats(&self, original: usize, filtered: usize, merged: usize) -> ProcessingStats {
+        processing_stats(&self.filter, original, filtered, merged)
+    }
+}
+
+impl fmt::Debug for PipelineConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipelineConfig")
+            .field("platform", &self.platform)
+            .field("input", &self.input)
+            .field("output", &self.output)
+            .field("format", &self.format)
+            .field("output_config", &self.output_config)
+            .field("filter", &self.filter)
+            .field("merge", &self.merge)
+            .field("merge_config", &self.merge_config)
+            .field("transforms", &self.transforms)
+            .field("channel_capacity", &self.channel_capacity)
+            .field("on_empty", &self.on_empty)
+            .field("cancellation", &self.cancellation)
+            .field("max_messages_per_sec", &self.max_messages_per_sec)
+            .field("max_bytes_per_sec", &self.max_bytes_per_sec)
+            .field("on_progress", &self.on_progress.is_some())
+            .field("max_memory_bytes", &self.max_memory_bytes)
+            .finish()
+    }
+}
+
+/// Parses, filters, merges, and writes on the calling thread.
+///
+/// Shares its implementation with [`convert`](crate::convert()). With a
+/// [memory budget](PipelineConfig::with_max_memory), writes on a second
+/// thread as each window of messages is processed; the output is the same.
+///
+/// # Errors
+///
+/// Returns any parse or write error, or [`ChatpackError::EmptyOutput`] if
+/// no messages are left and [`on_empty`](PipelineConfig::on_empty) is
+/// [`OnEmpty::Error`].
+pub fn run_sequential(config: &PipelineConfig) -> Result<ProcessingStats, ChatpackError> {
+    if let Some(budget) = config.max_memory_bytes {
+        return run_windowed(config, budget);
+    }
+    let options = ConvertOptions::new()
+        .with_filter(config.filter.clone())
+        .with_merge(config.merge)
+        .with_merge_config(config.merge_config)
+        .with_transforms(config.transforms.clone())
+        .with_output_config(config.output_config.clone())
+        .with_on_empty(config.on_empty);
+    convert::run(
+        create_parser(config.platform).as_ref(),
+        &config.input,
+        &config.output,
+        config.format,
+        &options,
+    )
+}
+
+/// Runs parse, filter/merge, and write on three threads.
+///
+/// Output is identical to [`run_sequential`]; see the
+/// [module docs](self) for error and cancellation behavior.
+///
+/// # Errors
+///
+/// Returns the first parse or write error, or
+/// [`ChatpackError::Cancelled`] if the token was cancelled.
+///
+/// # Panics
+///
+/// Re-raises a panic from any stage.
+pub fn run_pipelined(config: &PipelineConfig) -> Result<ProcessingStats, ChatpackError> {
+    run_stages(config, |rx| {
+        write_stage(
+            rx,
+            &config.output,
+            config.format,
+            &config.output_config,
+            config.on_empty,
+            &config.cancellation,
+        )
+    })
+}
+
+/// Runs the pipeline with `write` as the final stage.
+fn run_stages<W>(config: &PipelineConfig, write: W) -> Result<ProcessingStats, ChatpackError>
+where
+    W: FnOnce(Receiver<Message>) -> Result<(), ChatpackError> + Send,
+{
+    let capacity = config.channel_capacity.max(1);
+    let (parsed_tx, parsed_rx) = sync_channel(capacity);
+    let (processed_tx, processed_rx) = sync_channel(capacity);
+
+    let (parsed, processed, written) = thread::scope(|scope| {
+        let parse = scope.spawn(|| parse_stage(config, parsed_tx));
+        let process = scope.spawn(|| process_stage(config, parsed_rx, processed_tx));
+        let write = scope.spawn(|| write(processed_rx));
+        (join(parse), join(process), join(write))
+    });
+
+    let (original, skipped, ordering) = parsed?;
+    let (filtered, merged, mut loss) = processed;
+    written?;
+
+    if config.cancellation.is_cancelled() {
+        return Err(ChatpackError::Cancelled);
+    }
+    loss.add(&skipped);
+    Ok(config
+        .stats(original, filtered, merged)
+        .with_loss(loss)
+        .with_ordering(ordering))
+}
+
+fn join<T>(handle: thread::ScopedJoinHandle<'_, T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// Sends parsed messages downstream, counting the system and blank
+/// messages the parser left out.
+struct ChannelSink<'a> {
+    tx: SyncSender<Message>,
+    cancellation: &'a CancellationToken,
+    sent: usize,
+    skipped: LossReport,
+    ordering: OrderingCheck,
+    meter: Meter<'a>,
+}
+
+impl MessageSink for ChannelSink<'_> {
+    fn on_message(&mut self, msg: MessageRef<'_>) -> ControlFlow<()> {
+        if self.cancellation.is_cancelled() {
+            return ControlFlow::Break(());
+        }
+        self.meter
+            .pass((msg.sender.len() + msg.content.len()) as u64);
+        let msg = msg.to_message();
+        self.ordering.observe(&msg);
+        if self.tx.send(msg).is_err() {
+            return ControlFlow::Break(());
+        }
+        self.sent += 1;
+        ControlFlow::Continue(())
+    }
+
+    fn on_skip(&mut self, reason: &SkipReason) {
+        count_skip(&mut self.skipped, reason);
+    }
+}
+
+/// Parses the input, sending each message downstream.
+///
+/// Stops early when cancelled or when the next stage has gone away. Takes
+/// the sender by value so returning closes the channel. Returns the number
+/// of messages sent, the system and blank messages skipped, and how far
+/// the messages were out of order.
+fn parse_stage(
+    config: &PipelineConfig,
+    tx: SyncSender<Message>,
+) -> Result<(usize, LossReport, OrderingReport), ChatpackError> {
+    let parser = create_parser(config.platform);
+    let mut sink = ChannelSink {
+        tx,
+        cancellation: &config.cancellation,
+        sent: 0,
+        skipped: LossReport::new(),
+        ordering: OrderingCheck::new(),
+        meter: Meter::new(config),
+    };
+    let parsed = parser.parse_into(&config.input, &mut sink);
+    sink.meter.report();
+    parsed?;
+    Ok((sink.sent, sink.skipped, sink.ordering.report()))
+}
+
+/// Counts a system or blank message the parser left out in `loss`.
+fn count_skip(loss: &mut LossReport, reason: &SkipReason) {
+    match reason {
+        SkipReason::SystemMessage => loss.system_messages_skipped += 1,
+        SkipReason::BlankMessage => loss.blank_messages_skipped += 1,
+        _ => {}
+    }
+}
+
+/// Filters, transforms, and merges messages as they arrive.
+///
+/// A merged message is held back until a message from another sender (or
+/// the end of input) shows it is complete. Returns the filtered and merged
+/// counts and what filtering, merging, and writing leave out.
+#[allow(clippy::needless_pass_by_value)]
+fn process_stage(
+    config: &PipelineConfig,
+    rx: Receiver<Message>,
+    tx: SyncSender<Message>,
+) -> (usize, usize, LossReport) {
+    let mut filtered = 0;
+    let mut merged = 0;
+    let mut loss = LossReport::new();
+    let mut pending: Option<Message> = None;
+
+    for mut msg in rx {
+        if config.filter.is_active() && !config.filter.matches(&msg) {
+            loss.messages_filtered += 1;
+            continue;
+        }
+        filtered += 1;
+        config.transforms.apply(&mut msg);
+
+        if !config.merge {
+            merged += 1;
+            loss.count_unwritten(&msg, &config.output_config);
+            if tx.send(msg).is_err() {
+                return (filtered, merged, loss);
+            }
+            continue;
+        }
+
+        match &mut pending {
+            Some(last) if config.merge_config.continues(last, &msg) => {
+                append_merged(last, msg, &mut loss);
+            }
+            _ => {
+                if let Some(done) = pending.replace(msg) {
+                    merged += 1;
+                    loss.count_unwritten(&done, &config.output_config);
+                    if tx.send(done).is_err() {
+                        return (filtered, merged, loss);
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(done) = pending {
+        merged += 1;
+        loss.count_unwritten(&done, &config.output_config);
+        // The writer may already have failed; its error is reported instead
+        let _ = tx.send(done);
+    }
+    (filtered, merged, loss)
+}
+
+/// Writes messages as they arrive.
+///
+/// The file is created once the first message arrives, or once the input
+/// turns out to be empty and `on_empty` allows it. A cancelled run that
+/// wrote nothing creates an empty file as before.
+fn write_stage(
+    messages: impl IntoIterator<Item = Message>,
+    output: &Path,
+    format: OutputFormat,
+    output_config: &OutputConfig,
+    on_empty: OnEmpty,
+    cancellation: &CancellationToken,
+) -> Result<(), ChatpackError> {
+    let mut messages = messages.into_iter().peekable();
+    if messages.peek().is_none()
+        && !cancellation.is_cancelled()
+        && !convert::write_empty(on_empty, output)?
+    {
+        return Ok(());
+    }
+    write_iter_to_format(
+        messages,
+        output.to_str().unwrap_or_default(),
+        format,
+        output_config,
+        OnExists::Overwrite,
+    )?;
+    Ok(())
+}
+
+// ============================================================================
+// Verification
+// ============================================================================
+
+/// Most entry numbers listed for one kind of mismatch.
+const LISTED_ENTRIES: usize = 5;
+
+/// Reads the output of a finished run back and checks it against the
+/// run's `stats`.
+///
+/// The output at [`config.output`](PipelineConfig::output) is read with
+/// [`read_from_format`] and must have:
+///
+/// - as many entries as [`stats.merged_count`](ProcessingStats::merged_count),
+///   not counting conversation boundary markers;
+/// - a sender on every entry;
+/// - timestamps that never go back, if they were written in
+///   [`SortKey::Chronological`] order;
+/// - only the filtered sender, if the [filter](PipelineConfig::filter)
+///   picked one.
+///
+/// A missing output passes if there was nothing to write and
+/// [`on_empty`](PipelineConfig::on_empty) is [`OnEmpty::SkipFile`].
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::parser::Platform;
+/// use chatpack::pipeline::{PipelineConfig, run_pipelined, verify};
+///
+/// let config = PipelineConfig::new(Platform::Telegram, "result.json", "out.csv").with_merge(true);
+/// let stats = run_pipelined(&config)?;
+/// verify(&config, &stats)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`ChatpackError::VerificationFailed`] listing every check that
+/// failed, or the error of reading the output back.
+pub fn verify(config: &PipelineConfig, stats: &ProcessingStats) -> Result<(), ChatpackError> {
+    let path = &config.output;
+    if stats.merged_count == 0 && config.on_empty == OnEmpty::SkipFile && !path.exists() {
+        return Ok(());
+    }
+    let records = read_from_format(path, config.format)?;
+    let marker = match &config.output_config.conversation_boundaries {
+        Some(BoundaryStyle::Marker(marker)) => Some(marker.as_str()),
+        _ => None,
+    };
+    // Entries are numbered by their position in the file, from 1
+    let entries: Vec<(usize, &Message)> = records
+        .iter()
+        .enumerate()
+        .filter(|(_, msg)| !(msg.sender.is_empty() && Some(msg.content.as_str()) == marker))
+        .map(|(i, msg)| (i + 1, msg))
+        .collect();
+
+    let mut mismatches = Vec::new();
+    if entries.len() != stats.merged_count {
+        mismatches.push(format!(
+            "expected {} entries, found {}",
+            stats.merged_count,
+            entries.len()
+        ));
+    }
+
+    let blank: Vec<usize> = entries
+        .iter()
+        .filter(|(_, msg)| msg.sender.trim().is_empty())
+        .map(|(n, _)| *n)
+        .collect();
+    if !blank.is_empty() {
+        mismatches.push(format!("no sender on {}", list_entries(&blank)));
+    }
+
+    if config.output_config.include_timestamps
+        && config.output_config.sort_by == SortKey::Chronological
+    {
+        let timed: Vec<_> = entries
+            .iter()
+            .filter_map(|(n, msg)| msg.timestamp.map(|ts| (*n, ts)))
+            .collect();
+        let backwards: Vec<usize> = timed
+            .windows(2)
+            .filter(|pair| pair[1].1 < pair[0].1)
+            .map(|pair| pair[1].0)
+            .collect();
+        if !backwards.is_empty() {
+            mismatches.push(format!(
+                "timestamp earlier than the one before on {}",
+                list_entries(&backwards)
+            ));
+        }
+    }
+
+    if let Some(from) = &config.filter.from {
+        let others: Vec<usize> = entries
+            .iter()
+            .filter(|(_, msg)| !senders_match(&msg.sender, from))
+            .map(|(n, _)| *n)
+            .collect();
+        if !others.is_empty() {
+            mismatches.push(format!(
+                "sender other than the filtered \"{from}\" on {}",
+                list_entries(&others)
+            ));
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(ChatpackError::verification_failed(path, mismatches))
+    }
+}
+
+/// Formats entry numbers as `entry 3` or `3 entries (2, 5, 9)`, listing
+/// at most [`LISTED_ENTRIES`].
+fn list_entries(entries: &[usize]) -> String {
+    if let [entry] = entries {
+        return format!("entry {entry}");
+    }
+    let listed: Vec<String> = entries
+        .iter()
+        .take(LISTED_ENTRIES)
+        .map(ToString::to_string)
+        .collect();
+    let more = if entries.len() > LISTED_ENTRIES {
+        ", ..."
+    } else {
+        ""
+    };
+    format!("{} entries ({}{more})", entries.len(), listed.join(", "))
+}
+
+// ============================================================================
+// Memory budget
+// ============================================================================
+
+/// Windows alive at once under a memory budget: one being filled, one
+/// queued for the writer, and one being written.
+const WINDOWS_IN_FLIGHT: u64 = 3;
+
+/// Runs [`run_sequential`] within `budget` bytes, one window at a time.
+///
+/// Uses the streaming parser, so the export itself is never loaded whole,
+/// except for Discord TXT and CSV exports, which have no streaming parser.
+fn run_windowed(config: &PipelineConfig, budget: u64) -> Result<ProcessingStats, ChatpackError> {
+    let (tx, rx) = sync_channel::<Vec<Message>>(1);
+    let mut sink = WindowSink {
+        config,
+        tx,
+        window: Vec::new(),
+        window_budget: usize::try_from(budget / WINDOWS_IN_FLIGHT).unwrap_or(usize::MAX),
+        seen: 0,
+        seen_bytes: 0,
+        pending: None,
+        original: 0,
+        filtered: 0,
+        merged: 0,
+        loss: LossReport::new(),
+        ordering: OrderingCheck::new(),
+        writer_gone: false,
+    };
+
+    let (parsed, written) = thread::scope(|scope| {
+        let write = scope.spawn(|| {
+            write_stage(
+                rx.into_iter().flatten(),
+                &config.output,
+                config.format,
+                &config.output_config,
+                config.on_empty,
+                &config.cancellation,
+            )
+        });
+        let parsed = create_streaming_parser(config.platform).parse_into(&config.input, &mut sink);
+        if parsed.is_ok() && !config.cancellation.is_cancelled() {
+            sink.finish();
+        }
+        let WindowSink {
+            tx,
+            original,
+            filtered,
+            merged,
+            loss,
+            ordering,
+            ..
+        } = sink;
+        // Closes the channel, so the writer finishes even after an error
+        drop(tx);
+        let parsed = parsed.map(|()| {
+            config
+                .stats(original, filtered, merged)
+                .with_loss(loss)
+                .with_ordering(ordering.report())
+        });
+        (parsed, join(write))
+    });
+
+    let stats = parsed?;
+    written?;
+    if config.cancellation.is_cancelled() {
+        return Err(ChatpackError::Cancelled);
+    }
+    Ok(stats)
+}
+
+/// Collects parsed messages into windows and sends each one, filtered,
+/// transformed, and merged, to the writer.
+struct WindowSink<'a> {
+    config: &'a PipelineConfig,
+    tx: SyncSender<Vec<Message>>,
+    window: Vec<Message>,
+    window_budget: usize,
+    /// Messages parsed and their estimated size, for the average.
+    seen: usize,
+    seen_bytes: usize,
+    /// The last merged message of the previous window, which the next
+    /// window's messages may still be merged into.
+    pending: Option<Message>,
+    original: usize,
+    filtered: usize,
+    merged: usize,
+    loss: LossReport,
+    ordering: OrderingCheck,
+    writer_gone: bool,
+}
+
+impl WindowSink<'_> {
+    /// Returns how many messages fit a window, going by the average size
+    /// seen so far.
+    fn window_len(&self) -> usize {
+        let average = self.seen_bytes / self.seen.max(1);
+        (self.window_budget / average.max(1)).max(1)
+    }
+
+    /// Processes the current window and sends it to the writer, keeping
+    /// back its last message if the run it ends may continue.
+    fn flush(&mut self) {
+        let config = self.config;
+        let parsed = std::mem::take(&mut self.window);
+        let count = parsed.len();
+        let mut kept = apply_filters(parsed, &config.filter);
+        self.filtered += kept.len();
+        self.loss.messages_filtered += count - kept.len();
+        config.transforms.apply_all(&mut kept);
+
+        let mut out = if config.merge {
+            let run = self.pending.take().into_iter().chain(kept).collect();
+            let mut out = merge_consecutive_with_config(run, &config.merge_config, &mut self.loss);
+            self.pending = out.pop();
+            out
+        } else {
+            kept
+        };
+        self.send(&mut out);
+    }
+
+    /// Flushes the last window and the run carried past it.
+    fn finish(&mut self) {
+        self.flush();
+        let mut last: Vec<Message> = self.pending.take().into_iter().collect();
+        self.send(&mut last);
+    }
+
+    fn send(&mut self, messages: &mut Vec<Message>) {
+        if messages.is_empty() {
+            return;
+        }
+        self.merged += messages.len();
+        for msg in messages.iter() {
+            self.loss.count_unwritten(msg, &self.config.output_config);
+        }
+        if self.tx.send(std::mem::take(messages)).is_err() {
+            // The writer failed; its error is reported instead
+            self.writer_gone = true;
+        }
+    }
+}
+
+impl MessageSink for WindowSink<'_> {
+    fn on_message(&mut self, msg: MessageRef<'_>) -> ControlFlow<()> {
+        if self.config.cancellation.is_cancelled() {
+            return ControlFlow::Break(());
+        }
+        let msg = msg.to_message();
+        self.ordering.observe(&msg);
+        self.original += 1;
+        self.seen += 1;
+        self.seen_bytes += approx_size(&msg);
+        self.window.push(msg);
+        if self.window.len() >= self.window_len() {
+            self.flush();
+        }
+        if self.writer_gone {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+
+    fn on_skip(&mut self, reason: &SkipReason) {
+        count_skip(&mut self.loss, reason);
+    }
+}
+
+/// Estimates the memory `msg` takes, counting its text but not allocator
+/// overhead.
+fn approx_size(msg: &Message) -> usize {
+    let text = |s: Option<&String>| s.map_or(0, String::len);
+    std::mem::size_of::<Message>()
+        + msg.sender.len()
+        + msg.content.len()
+        + text(msg.original_sender.as_ref())
+        + text(msg.forwarded_from.as_ref())
+        + text(msg.topic.as_ref())
+        + text(msg.source.as_ref())
+        + text(msg.raw.as_ref())
+        + msg.attachments.len() * std::mem::size_of::<crate::AttachmentRef>()
+}
+
+// ============================================================================
+// Throughput
+// ============================================================================
+
+/// How often [`PipelineConfig::with_progress`] callbacks run while parsing.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Token bucket that refills at `rate` tokens per second.
+///
+/// The bucket may go into debt; [`take`](Self::take) sleeps the debt off
+/// once it is worth more than [`MIN_SLEEP`], so fast callers sleep in
+/// batches rather than once per message.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// Shortest sleep [`TokenBucket`] takes; shorter ones oversleep too much.
+const MIN_SLEEP: Duration = Duration::from_millis(10);
+
+/// Longest stretch [`Meter`] sleeps before checking for cancellation.
+const CANCEL_POLL: Duration = Duration::from_millis(20);
+
+impl TokenBucket {
+    /// Starts empty, so the first second runs at `rate` too. Up to
+    /// [`MIN_SLEEP`] worth of unused tokens are kept for a later burst.
+    fn new(rate: u64, now: Instant) -> Self {
+        let rate = rate as f64;
+        Self {
+            rate,
+            burst: rate * MIN_SLEEP.as_secs_f64(),
+            tokens: 0.0,
+            refilled: now,
+        }
+    }
+
+    /// Takes `tokens`, returning how long to sleep to pay off the debt.
+    fn take(&mut self, tokens: f64, now: Instant) -> Option<Duration> {
+        let refill = now.saturating_duration_since(self.refilled).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refill).min(self.burst) - tokens;
+        self.refilled = now;
+        let debt = Duration::from_secs_f64(-self.tokens.min(0.0) / self.rate);
+        (debt >= MIN_SLEEP).then_some(debt)
+    }
+}
+
+/// Counts what the parser sends downstream, throttles it to the configured
+/// rates, and reports progress.
+struct Meter<'a> {
+    cancellation: &'a CancellationToken,
+    started: Instant,
+    progress: Progress,
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+    on_progress: Option<&'a ProgressCallback>,
+    reported: Instant,
+}
+
+impl<'a> Meter<'a> {
+    fn new(config: &'a PipelineConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            cancellation: &config.cancellation,
+            started: now,
+            progress: Progress::default(),
+            messages: config
+                .max_messages_per_sec
+                .map(|r| TokenBucket::new(r, now)),
+            bytes: config.max_bytes_per_sec.map(|r| TokenBucket::new(r, now)),
+            on_progress: config.on_progress.as_ref(),
+            reported: now,
+        }
+    }
+
+    /// Counts one message of `bytes` bytes, sleeping first if it would go
+    /// over a limit.
+    fn pass(&mut self, bytes: u64) {
+        let now = Instant::now();
+        let waits = [
+            self.messages.as_mut().and_then(|b| b.take(1.0, now)),
+            self.bytes.as_mut().and_then(|b| b.take(bytes as f64, now)),
+        ];
+        if let Some(wait) = waits.into_iter().flatten().max() {
+            self.sleep(now + wait);
+        }
+
+        self.progress.items_processed += 1;
+        self.progress.bytes_processed += bytes;
+        if self.on_progress.is_some() && self.reported.elapsed() >= PROGRESS_INTERVAL {
+            self.report();
+        }
+    }
+
+    /// Sleeps until `deadline` in slices of at most [`CANCEL_POLL`],
+    /// returning early once the run is cancelled.
+    fn sleep(&self, deadline: Instant) {
+        while !self.cancellation.is_cancelled() {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return;
+            }
+            thread::sleep(left.min(CANCEL_POLL));
+        }
+    }
+
+    /// Calls the progress callback with the progress so far.
+    fn report(&mut self) {
+        if let Some(callback) = self.on_progress {
+            self.reported = Instant::now();
+            self.progress.elapsed = self.reported - self.started;
+            callback(self.progress);
+        }
+    }
+}
+
+// ============================================================================
+// Corpus building
+// ============================================================================
+
+/// One export to add to a corpus built with [`build_corpus`].
+///
+/// Deserializes from a manifest entry, so a list of sources can be read
+/// from a file; `platform` may be left out to detect it.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::parser::Platform;
+/// use chatpack::pipeline::CorpusSource;
+///
+/// let source: CorpusSource =
+///     serde_json::from_str(r#"{"path": "alice.json", "platform": "telegram", "label": "alice"}"#)?;
+/// assert_eq!(
+///     source,
+///     CorpusSource::new("alice.json", "alice").with_platform(Platform::Telegram)
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CorpusSource {
+    /// Export file to parse.
+    pub path: PathBuf,
+    /// Platform of the export; `None` detects it with [`detect_platform`]
+    /// (default: `None`).
+    #[serde(default)]
+    pub platform: Option<Platform>,
+    /// Written as the [`source`](Message::source) of every record from
+    /// this export.
+    pub label: String,
+}
+
+impl CorpusSource {
+    /// Creates a source for the export at `path`, with its platform
+    /// detected.
+    pub fn new(path: impl Into<PathBuf>, label: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            platform: None,
+            label: label.into(),
+        }
+    }
+
+    /// Sets the platform instead of detecting it.
+    #[must_use]
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+}
+
+/// How [`build_corpus`] processes its sources and writes the corpus.
+///
+/// Filtering, transforms, and merging apply to each source on its own, as
+/// [`run_pipelined`] would; messages from different sources are never
+/// merged.
+#[derive(Debug, Clone)]
+pub struct CorpusConfig {
+    /// Output format (default: JSONL).
+    pub format: OutputFormat,
+    /// Which fields to write (default: sender, content, and
+    /// [metadata](OutputConfig::include_metadata), which holds the source
+    /// label).
+    pub output_config: OutputConfig,
+    /// Messages to keep (default: all).
+    pub filter: FilterConfig,
+    /// Merge consecutive messages from the same sender (default: false).
+    pub merge: bool,
+    /// How merging compares senders (default: ignoring case).
+    pub merge_config: MergeConfig,
+    /// Content rewrites run after filtering and before merging (default:
+    /// none).
+    pub transforms: TransformChain,
+    /// Stop at the first source that fails and return its error, instead
+    /// of recording it in [`CorpusReport::failed`] and moving on (default:
+    /// false).
+    pub fail_fast: bool,
+    /// What to do when no messages are left to write (default: write an
+    /// empty output).
+    pub on_empty: OnEmpty,
+    /// Capacity of each channel between stages, in messages.
+    pub channel_capacity: usize,
+    /// Stops the run early when cancelled.
+    pub cancellation: CancellationToken,
+}
+
+impl Default for CorpusConfig {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Jsonl,
+            output_config: OutputConfig::new().with_metadata(),
+            filter: FilterConfig::default(),
+            merge: false,
+            merge_config: MergeConfig::default(),
+            transforms: TransformChain::default(),
+            fail_fast: false,
+            on_empty: OnEmpty::default(),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            cancellation: CancellationToken::default(),
+        }
+    }
+}
+
+impl CorpusConfig {
+    /// Creates a config with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the output format.
+    #[must_use]
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets which fields to write.
+    #[must_use]
+    pub fn with_output_config(mut self, config: OutputConfig) -> Self {
+        self.output_config = config;
+        self
+    }
+
+    /// Sets the filter.
+    #[must_use]
+    pub fn with_filter(mut self, filter: FilterConfig) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Enables or disables merging consecutive messages.
+    #[must_use]
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Sets how merging compares senders.
+    #[must_use]
+    pub fn with_merge_config(mut self, config: MergeConfig) -> Self {
+        self.merge_config = config;
+        self
+    }
+
+    /// Adds a transform to run after filtering and before merging.
+    #[must_use]
+    pub fn with_transform(mut self, transform: impl ContentTransform + 'static) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Sets the transforms to run after filtering and before merging.
+    #[must_use]
+    pub fn with_transforms(mut self, transforms: TransformChain) -> Self {
+        self.transforms = transforms;
+        self
+    }
+
+    /// Enables or disables stopping at the first failed source.
+    #[must_use]
+    pub fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Sets what to do when no messages are left to write.
+    #[must_use]
+    pub fn with_on_empty(mut self, on_empty: OnEmpty) -> Self {
+        self.on_empty = on_empty;
+        self
+    }
+
+    /// Sets the capacity of each channel; `0` is treated as `1`.
+    #[must_use]
+    pub fn with_channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity;
+        self
+    }
+
+    /// Sets the token that cancels the run.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Returns the pipeline config that processes `source`.
+    fn pipeline(&self, platform: Platform, source: &CorpusSource) -> PipelineConfig {
+        PipelineConfig::new(platform, &source.path, PathBuf::new())
+            .with_output_config(self.output_config.clone())
+            .with_filter(self.filter.clone())
+            .with_merge(self.merge)
+            .with_merge_config(self.merge_config)
+            .with_transforms(self.transforms.clone())
+            .with_channel_capacity(self.channel_capacity)
+            .with_cancellation(self.cancellation.clone())
+    }
+}
+
+/// A source [`build_corpus`] wrote to the corpus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertedSource {
+    /// Label of the source.
+    pub label: String,
+    /// What processing the source did.
+    pub stats: ProcessingStats,
+}
+
+/// A source [`build_corpus`] could not convert.
+#[derive(Debug)]
+pub struct FailedSource {
+    /// Label of the source.
+    pub label: String,
+    /// Export file of the source.
+    pub path: PathBuf,
+    /// Why it failed.
+    pub error: ChatpackError,
+}
+
+/// What [`build_corpus`] did with each source, in manifest order.
+#[derive(Debug, Default)]
+pub struct CorpusReport {
+    /// Sources written to the corpus.
+    pub converted: Vec<ConvertedSource>,
+    /// Sources that failed.
+    pub failed: Vec<FailedSource>,
+}
+
+impl CorpusReport {
+    /// Returns `true` if every source was converted.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Sums the stats of every converted source.
+    pub fn total(&self) -> ProcessingStats {
+        let mut total = ProcessingStats::new(0, 0);
+        for ConvertedSource { stats, .. } in &self.converted {
+            total.original_count += stats.original_count;
+            total.merged_count += stats.merged_count;
+            if let Some(filtered) = stats.filtered_count {
+                total.filtered_count = Some(total.filtered_count.unwrap_or(0) + filtered);
+            }
+            total.loss.add(&stats.loss);
+            total.ordering.add(&stats.ordering);
+        }
+        total
+    }
+}
+
+/// Converts many exports into one corpus at `output`, labeling every
+/// record with its source.
+///
+/// Each source runs through the parse, filter/merge, and write stages of
+/// [`run_pipelined`] in turn, and its messages get the source's label as
+/// [`source`](Message::source) and its position in `sources` as
+/// [`source_file`](Message::source_file), so
+/// [`OutputConfig::conversation_boundaries`] marks where each one starts.
+/// One writer takes every source's messages as they come, so memory stays
+/// bounded by the channel capacity however large the corpus grows.
+///
+/// A source that cannot be detected, read, or parsed is recorded in
+/// [`CorpusReport::failed`] and the next one starts, unless
+/// [`fail_fast`](CorpusConfig::fail_fast) is set. Messages a source
+/// produced before failing have already been written and stay in the
+/// corpus.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::pipeline::{CorpusConfig, CorpusSource, build_corpus};
+///
+/// let sources = [
+///     CorpusSource::new("alice/result.json", "alice"),
+///     CorpusSource::new("family.txt", "family"),
+/// ];
+/// let report = build_corpus(&sources, &CorpusConfig::new().with_merge(true), "corpus.jsonl")?;
+/// for failed in &report.failed {
+///     eprintln!("skipped {}: {}", failed.label, failed.error);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns a write error, [`ChatpackError::EmptyOutput`] as
+/// [`on_empty`](CorpusConfig::on_empty) asks, the error of the first
+/// failed source if [`fail_fast`](CorpusConfig::fail_fast) is set, or
+/// [`ChatpackError::Cancelled`] if the token was cancelled.
+///
+/// # Panics
+///
+/// Re-raises a panic from any stage.
+pub fn build_corpus(
+    sources: &[CorpusSource],
+    config: &CorpusConfig,
+    output: impl AsRef<Path>,
+) -> Result<CorpusReport, ChatpackError> {
+    let output = output.as_ref();
+    let (tx, rx) = sync_channel(config.channel_capacity.max(1));
+    let mut report = CorpusReport::default();
+
+    let written = thread::scope(|scope| {
+        let writer = scope.spawn(|| {
+            write_stage(
+                rx,
+                output,
+                config.format,
+                &config.output_config,
+                config.on_empty,
+                &config.cancellation,
+            )
+        });
+
+        let mut writer_gone = false;
+        for (index, source) in sources.iter().enumerate() {
+            match add_source(config, index, source, &tx, &mut writer_gone) {
+                Ok(stats) => report.converted.push(ConvertedSource {
+                    label: source.label.clone(),
+                    stats,
+                }),
+                Err(e) if e.is_cancelled() => break,
+                Err(error) => {
+                    report.failed.push(FailedSource {
+                        label: source.label.clone(),
+                        path: source.path.clone(),
+                        error,
+                    });
+                    if config.fail_fast {
+                        break;
+                    }
+                }
+            }
+            if writer_gone {
+                break;
+            }
+        }
+        // Closes the channel, so the writer finishes even after an error
+        drop(tx);
+        join(writer)
+    });
+
+    written?;
+    if config.cancellation.is_cancelled() {
+        return Err(ChatpackError::Cancelled);
+    }
+    if config.fail_fast {
+        if let Some(failed) = report.failed.pop() {
+            return Err(failed.error);
+        }
+    }
+    Ok(report)
+}
+
+/// Runs one source through the pipeline, forwarding its labeled messages
+/// to the corpus writer. Sets `writer_gone` if the writer stopped taking
+/// them.
+fn add_source(
+    config: &CorpusConfig,
+    index: usize,
+    source: &CorpusSource,
+    tx: &SyncSender<Message>,
+    writer_gone: &mut bool,
+) -> Result<ProcessingStats, ChatpackError> {
+    let platform = match source.platform {
+        Some(platform) => platform,
+        None => detect_platform(&source.path)?,
+    };
+    run_stages(&config.pipeline(platform, source), |rx| {
+        for mut msg in rx {
+            msg.source = Some(source.label.clone());
+            msg.source_file = Some(index);
+            if tx.send(msg).is_err() {
+                *writer_gone = true;
+                break;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::processor::merge_consecutive;
+
+    #[test]
+    fn test_cancellation_token_shared() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn test_config_builders() {
+        let config = PipelineConfig::new(Platform::Telegram, "in.json", "out.jsonl")
+            .with_format(OutputFormat::Jsonl)
+            .with_merge(true)
+            .with_channel_capacity(8);
+        assert_eq!(config.format, OutputFormat::Jsonl);
+        assert!(config.merge);
+        assert_eq!(config.channel_capacity, 8);
+        assert_eq!(config.output, Path::new("out.jsonl"));
+    }
+
+    #[test]
+    fn test_process_stage_merges_like_merge_consecutive() {
+        let messages = vec![
+            Message::new("A", "1"),
+            Message::new("A", "2"),
+            Message::new("B", "3"),
+            Message::new("A", "4"),
+        ];
+        let config = PipelineConfig::new(Platform::Telegram, "", "").with_merge(true);
+        let (in_tx, in_rx) = sync_channel(16);
+        let (out_tx, out_rx) = sync_channel(16);
+        for msg in messages.clone() {
+            in_tx.send(msg).unwrap();
+        }
+        drop(in_tx);
+
+        let (filtered, merged, loss) = process_stage(&config, in_rx, out_tx);
+        let out: Vec<Message> = out_rx.iter().collect();
+        assert_eq!(out, merge_consecutive(messages));
+        assert_eq!((filtered, merged), (4, 3));
+        assert_eq!(loss.messages_merged, 1);
+    }
+
+    #[cfg(feature = "whatsapp")]
+    fn whatsapp_export(count: usize) -> tempfile::NamedTempFile {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for i in 0..count {
+            writeln!(file, "[1/15/24, 10:30:00 AM] User{}: message {i}", i % 3).unwrap();
+        }
+        file.flush().unwrap();
+        file
+    }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_slow_writer_bounds_and_orders() {
+        use std::time::Duration;
+
+        let input = whatsapp_export(300);
+        let config = PipelineConfig::new(Platform::WhatsApp, input.path(), "unused")
+            .with_channel_capacity(2);
+
+        let mut received = Vec::new();
+        let stats = run_stages(&config, |rx| {
+            for msg in rx {
+                thread::sleep(Duration::from_micros(200));
+                received.push(msg.content);
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        let expected: Vec<String> = (0..300).map(|i| format!("message {i}")).collect();
+        assert_eq!(received, expected);
+        assert_eq!(stats.original_count, 300);
+    }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_writer_error_stops_pipeline() {
+        let input = whatsapp_export(10_000);
+        let config = PipelineConfig::new(Platform::WhatsApp, input.path(), "unused")
+            .with_channel_capacity(1);
+
+        let err = run_stages(&config, |rx| {
+            let _first = rx.recv();
+            Err(ChatpackError::invalid_format("test", "writer failed"))
+        })
+        .unwrap_err();
+        assert!(err.is_invalid_format());
+    }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_cancel_drains_without_deadlock() {
+        let input = whatsapp_export(10_000);
+        let token = CancellationToken::new();
+        let config = PipelineConfig::new(Platform::WhatsApp, input.path(), "unused")
+            .with_channel_capacity(4)
+            .with_cancellation(token.clone());
+
+        let mut received = 0;
+        let err = run_stages(&config, |rx| {
+            for msg in rx {
+                assert_eq!(msg.content, format!("message {received}"));
+                received += 1;
+                if received == 10 {
+                    token.cancel();
+                }
+            }
+            Ok(())
+        })
+        .unwrap_err();
+
+        assert!(err.is_cancelled());
+        // Everything already queued when the token fired was still written
+        assert!((10..10_000).contains(&received));
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000, start);
+        // Debt under MIN_SLEEP is carried instead of slept
+        assert_eq!(bucket.take(5.0, start), None);
+        assert_eq!(bucket.take(5.0, start), Some(Duration::from_millis(10)));
+        // Time pays the debt off, and unused time only buys a small burst
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.take(10.0, later), None);
+        assert_eq!(bucket.take(10.0, later), Some(Duration::from_millis(10)));
+    }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_rate_limits() {
+        use std::time::Instant;
+
+        let input = whatsapp_export(1000);
+        // "UserN" and "message i" are 14 to 16 bytes
+        let limits = [
+            PipelineConfig::new(Platform::WhatsApp, input.path(), "unused")
+                .with_max_messages_per_sec(5000),
+            PipelineConfig::new(Platform::WhatsApp, input.path(), "unused")
+                .with_max_bytes_per_sec(70_000),
+        ];
+        for config in limits {
+            let started = Instant::now();
+            let stats = run_stages(&config, |rx| {
+                rx.into_iter().for_each(drop);
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(stats.original_count, 1000);
+            // At least 0.2 seconds, within 10%
+            assert!(
+                started.elapsed() >= Duration::from_millis(180),
+                "{config:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_cancel_interrupts_throttle_sleep() {
+        let input = whatsapp_export(100);
+        let token = CancellationToken::new();
+        // The first message alone owes half a second
+        let config = PipelineConfig::new(Platform::WhatsApp, input.path(), "unused")
+            .with_max_messages_per_sec(2)
+            .with_cancellation(token.clone());
+
+        let started = Instant::now();
+        let err = thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(50));
+                token.cancel();
+            });
+            run_stages(&config, |rx| {
+                rx.into_iter().for_each(drop);
+                Ok(())
+            })
+            .unwrap_err()
+        });
+
+        assert!(err.is_cancelled());
+        assert!(started.elapsed() < Duration::from_millis(400));
+    }
+
+    #[cfg(feature = "whatsapp")]
+    #[test]
+    fn test_progress_reports_throughput() {
+        use std::sync::Mutex;
+
+        let input = whatsapp_export(1000);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let config = PipelineConfig::new(Platform::WhatsApp, input.path(), "unused")
+            .with_progress(Arc::new(move |p| sink.lock().unwrap().push(p)));
+        run_stages(&config, |rx| {
+            rx.into_iter().for_each(drop);
+            Ok(())
+        })
+        .unwrap();
+
+        let seen = seen.lock().unwrap();
+        let last = seen.last().unwrap();
+        let bytes: usize = (0..1000)
+            .map(|i| format!("User{}message {i}", i % 3).len())
+            .sum();
+        assert_eq!(last.items_processed, 1000);
+        assert_eq!(last.bytes_processed, bytes as u64);
+        assert!(last.items_per_sec() > 0.0 && last.megabytes_per_sec() > 0.0);
+        assert!((last.items_per_sec() * last.elapsed.as_secs_f64() - 1000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_list_entries() {
+        assert_eq!(list_entries(&[4]), "entry 4");
+        assert_eq!(list_entries(&[1, 2]), "2 entries (1, 2)");
+        assert_eq!(
+            list_entries(&[1, 2, 3, 4, 5, 6, 7]),
+            "7 entries (1, 2, 3, 4, 5, ...)"
+        );
+    }
+
+    #[cfg(all(feature = "whatsapp", feature = "json-output"))]
+    #[test]
+    fn test_windows_merge_like_unbounded() {
+        use std::io::Write;
+
+        // Runs of 1 to 6 messages from each sender, so they cross windows
+        let mut input = tempfile::NamedTempFile::new().unwrap();
+        let mut sender = 0;
+        for i in 0..500 {
+            if i % 7 == 0 || i % 11 == 0 {
+                sender = (sender + 1) % 3;
+            }
+            writeln!(input, "[1/15/24, 10:30:00 AM] User{sender}: message {i}").unwrap();
+        }
+        input.flush().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        for filter in [
+            FilterConfig::new(),
+            FilterConfig::new().with_sender("User1"),
+        ] {
+            let config = |out: &str| {
+                PipelineConfig::new(Platform::WhatsApp, input.path(), dir.path().join(out))
+                    .with_format(OutputFormat::Jsonl)
+                    .with_filter(filter.clone())
+                    .with_merge(true)
+            };
+            let unbounded = run_sequential(&config("unbounded.jsonl")).unwrap();
+            let expected = std::fs::read(dir.path().join("unbounded.jsonl")).unwrap();
+            assert!(unbounded.loss.messages_merged > 0);
+
+            // From one message per window up to a few dozen
+            for budget in [1, 2_000, 10_000, 50_000] {
+                let windowed = config("windowed.jsonl").with_max_memory(budget);
+                assert_eq!(run_sequential(&windowed).unwrap(), unbounded, "{budget}");
+                let written = std::fs::read(dir.path().join("windowed.jsonl")).unwrap();
+                assert_eq!(written, expected, "budget {budget}");
+            }
+        }
+    }
+
+    #[cfg(all(feature = "whatsapp", feature = "csv-output"))]
+    #[test]
+    fn test_windowed_run_stops_when_cancelled() {
+        let input = whatsapp_export(1000);
+        let dir = tempfile::tempdir().unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let config = PipelineConfig::new(Platform::WhatsApp, input.path(), dir.path().join("out"))
+            .with_max_memory(2_000)
+            .with_cancellation(token);
+
+        assert!(run_sequential(&config).unwrap_err().is_cancelled());
+    }
+
+    #[cfg(all(feature = "whatsapp", feature = "csv-output"))]
+    #[test]
+    fn test_on_empty() {
+        let input = whatsapp_export(10);
+        let dir = tempfile::tempdir().unwrap();
+        let nobody = FilterConfig::new().with_sender("Nobody");
+
+        for run in [run_sequential, run_pipelined] {
+            let out = dir.path().join("out.csv");
+            let config = PipelineConfig::new(Platform::WhatsApp, input.path(), &out)
+                .with_filter(nobody.clone())
+                .with_on_empty(OnEmpty::Error);
+            assert!(run(&config).unwrap_err().is_empty_output());
+            assert!(!out.exists());
+
+            let stats = run(&config.clone().with_on_empty(OnEmpty::SkipFile)).unwrap();
+            assert_eq!(stats.merged_count, 0);
+            assert!(!out.exists());
+
+            run(&config.with_on_empty(OnEmpty::WriteEmpty)).unwrap();
+            assert!(out.exists());
+            std::fs::remove_file(&out).unwrap();
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! The [`Platform`] enum naming the export format a message came from.
+//!
+//! Always compiled, so [`Message::platform`](crate::Message::platform) has
+//! the same type whichever parser features are enabled. Parser selection
+//! by platform lives in [`parser`](crate::parser).
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Supported messaging platforms for chat export parsing.
+///
+/// Each variant corresponds to a specific export format and parser implementation.
+/// Use with [`create_parser`](crate::parser::create_parser) or [`create_streaming_parser`](crate::parser::create_streaming_parser) for dynamic parser selection.
+///
+/// # Aliases
+///
+/// All platforms support short aliases for convenience:
+/// - `telegram` / `tg`
+/// - `whatsapp` / `wa`
+/// - `instagram` / `ig`
+/// - `discord` / `dc`
+///
+/// # Examples
+///
+/// ```
+/// use chatpack::platform::Platform;
+/// use std::str::FromStr;
+///
+/// // Parse from string (case-insensitive)
+/// let platform = Platform::from_str("telegram")?;
+/// assert_eq!(platform, Platform::Telegram);
+///
+/// // Aliases work too
+/// let platform = Platform::from_str("tg")?;
+/// assert_eq!(platform, Platform::Telegram);
+///
+/// // Get file extension
+/// assert_eq!(Platform::WhatsApp.default_extension(), "txt");
+/// assert_eq!(Platform::Telegram.default_extension(), "json");
+/// # Ok::<(), String>(())
+/// ```
+///
+/// # Custom Platforms
+///
+/// [`Platform::Custom`] identifies a format added with [`register_parser`](crate::parser::register_parser).
+/// Once registered, its name and aliases parse like the built-in ones.
+///
+/// # Serialization
+///
+/// Serializes to lowercase strings, deserializes with alias support:
+///
+/// ```
+/// use chatpack::platform::Platform;
+///
+/// let json = serde_json::to_string(&Platform::Telegram)?;
+/// assert_eq!(json, "\"telegram\"");
+///
+/// // Deserialize with alias
+/// let platform: Platform = serde_json::from_str("\"tg\"")?;
+/// assert_eq!(platform, Platform::Telegram);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Platform {
+    /// Telegram Desktop JSON exports.
+    ///
+    /// Parses the `result.json` file from "Export chat history" feature.
+    /// Handles service messages, forwarded messages, and reply chains.
+    Telegram,
+
+    /// WhatsApp TXT exports from iOS and Android.
+    ///
+    /// Auto-detects locale-specific date formats (US, EU, RU variants).
+    /// Handles multiline messages and system notifications.
+    WhatsApp,
+
+    /// Instagram JSON exports from Meta's data download.
+    ///
+    /// Automatically fixes Mojibake encoding issues in non-ASCII text.
+    /// Parses direct messages from the `messages/` directory.
+    Instagram,
+
+    /// Discord exports from DiscordChatExporter tool.
+    ///
+    /// Supports multiple formats: JSON, TXT, and CSV.
+    /// Preserves attachments, stickers, and reply references.
+    Discord,
+
+    /// A format registered with [`register_parser`](crate::parser::register_parser), by its registered name.
+    ///
+    /// [`create_parser`](crate::parser::create_parser) panics for names that were never registered.
+    Custom(&'static str),
+}
+
+impl Platform {
+    /// Returns the default file extension for exports from this platform.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::platform::Platform;
+    ///
+    /// assert_eq!(Platform::Telegram.default_extension(), "json");
+    /// assert_eq!(Platform::WhatsApp.default_extension(), "txt");
+    /// ```
+    ///
+    /// Custom platforms report `"txt"`.
+    pub fn default_extension(&self) -> &'static str {
+        match self {
+            Platform::WhatsApp | Platform::Custom(_) => "txt",
+            Platform::Telegram | Platform::Instagram | Platform::Discord => "json",
+        }
+    }
+
+    /// Returns all valid built-in platform names and aliases.
+    ///
+    /// Useful for CLI help text or validation messages. Names added with
+    /// [`register_parser`](crate::parser::register_parser) are listed by [`registered_names`](Self::registered_names).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::platform::Platform;
+    ///
+    /// let names = Platform::all_names();
+    /// assert!(names.contains(&"telegram"));
+    /// assert!(names.contains(&"tg")); // alias
+    /// ```
+    pub fn all_names() -> &'static [&'static str] {
+        &[
+            "telegram",
+            "tg",
+            "whatsapp",
+            "wa",
+            "instagram",
+            "ig",
+            "discord",
+            "dc",
+        ]
+    }
+
+    /// Returns all built-in platform variants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::platform::Platform;
+    ///
+    /// for platform in Platform::all() {
+    ///     println!("{}: .{}", platform, platform.default_extension());
+    /// }
+    /// ```
+    pub fn all() -> &'static [Platform] {
+        &[
+            Platform::Telegram,
+            Platform::WhatsApp,
+            Platform::Instagram,
+            Platform::Discord,
+        ]
+    }
+
+    /// Returns the canonical lowercase name, as used for serialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chatpack::platform::Platform;
+    ///
+    /// assert_eq!(Platform::WhatsApp.name(), "whatsapp");
+    /// assert_eq!(Platform::Custom("slack").name(), "slack");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Telegram => "telegram",
+            Platform::WhatsApp => "whatsapp",
+            Platform::Instagram => "instagram",
+            Platform::Discord => "discord",
+            Platform::Custom(name) => name,
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Platform::Telegram => write!(f, "Telegram"),
+            Platform::WhatsApp => write!(f, "WhatsApp"),
+            Platform::Instagram => write!(f, "Instagram"),
+            Platform::Discord => write!(f, "Discord"),
+            Platform::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl std::str::FromStr for Platform {
+    type Err = String;
+
+    /// Built-in names and aliases take precedence over registered ones.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key = s.to_lowercase();
+        if let Some(platform) = builtin_platform(&key) {
+            return Ok(platform);
+        }
+        #[cfg(any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json"
+        ))]
+        if let Some(platform) = crate::parser::registered_platform(&key) {
+            return Ok(platform);
+        }
+
+        #[allow(unused_mut)]
+        let mut names: Vec<String> = Platform::all_names()
+            .iter()
+            .map(|name| (*name).to_string())
+            .collect();
+        #[cfg(any(
+            feature = "telegram",
+            feature = "whatsapp",
+            feature = "instagram",
+            feature = "discord-json"
+        ))]
+        names.extend(Platform::registered_names());
+        Err(format!(
+            "Unknown platform: '{}'. Expected one of: {}",
+            s,
+            names.join(", ")
+        ))
+    }
+}
+
+pub(crate) fn builtin_platform(lowercase: &str) -> Option<Platform> {
+    match lowercase {
+        "telegram" | "tg" => Some(Platform::Telegram),
+        "whatsapp" | "wa" => Some(Platform::WhatsApp),
+        "instagram" | "ig" => Some(Platform::Instagram),
+        "discord" | "dc" => Some(Platform::Discord),
+        _ => None,
+    }
+}
+
+impl Serialize for Platform {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Platform {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
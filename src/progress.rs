@@ -31,8 +31,18 @@
 //! - `progress()` - Returns percentage (0.0-100.0) if known
 //! - `bytes_processed()` - Returns bytes read so far
 //! - `total_bytes()` - Returns total file size if known
+//!
+//! `Parser::stream_with_progress()` returns a `ParseIterator` that can call
+//! a [`ProgressCallback`] every N messages while it is consumed.
+//!
+//! When several files are processed as one job, [`MultiFileProgress`] maps
+//! each file's bytes to a percentage of all of them, so a bar does not jump
+//! back to 0% at every file boundary.
 
+use std::io;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Progress information for long-running operations.
 ///
@@ -51,6 +61,12 @@ pub struct Progress {
 
     /// Total items to process, if known.
     pub total_items: Option<usize>,
+
+    /// Number of items skipped or failed so far.
+    pub skipped: usize,
+
+    /// Time since the operation started, or zero if not tracked.
+    pub elapsed: Duration,
 }
 
 impl Progress {
@@ -61,6 +77,8 @@ impl Progress {
             total_bytes,
             items_processed,
             total_items: None,
+            skipped: 0,
+            elapsed: Duration::ZERO,
         }
     }
 
@@ -109,6 +127,40 @@ impl Progress {
         })
     }
 
+    /// Returns the average items processed per second.
+    ///
+    /// Returns `0.0` if [`elapsed`](Self::elapsed) is zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use chatpack::progress::Progress;
+    /// use std::time::Duration;
+    ///
+    /// let mut progress = Progress::new(3_000_000, None, 500);
+    /// progress.elapsed = Duration::from_secs(2);
+    /// assert_eq!(progress.items_per_sec(), 250.0);
+    /// assert_eq!(progress.megabytes_per_sec(), 1.5);
+    /// ```
+    pub fn items_per_sec(&self) -> f64 {
+        self.per_sec(self.items_processed as f64)
+    }
+
+    /// Returns the average megabytes (10^6 bytes) processed per second.
+    ///
+    /// Returns `0.0` if [`elapsed`](Self::elapsed) is zero.
+    pub fn megabytes_per_sec(&self) -> f64 {
+        self.per_sec(self.bytes_processed as f64 / 1e6)
+    }
+
+    fn per_sec(&self, amount: f64) -> f64 {
+        if self.elapsed.is_zero() {
+            0.0
+        } else {
+            amount / self.elapsed.as_secs_f64()
+        }
+    }
+
     /// Returns whether the operation is complete.
     ///
     /// An operation is considered complete when bytes_processed equals total_bytes
@@ -128,6 +180,121 @@ impl Progress {
     }
 }
 
+/// Progress across several files processed as one job.
+///
+/// File sizes are taken once, up front, and each file's
+/// [`bytes_processed`](Progress::bytes_processed) counts towards the total
+/// in proportion to its size. A 10 KB file followed by a 90 KB one reports
+/// 10% once the first is done, instead of 100% then 0%.
+///
+/// Bytes a file gains after its size was taken are not counted, so the
+/// percentage never goes over 100.
+///
+/// # Example
+///
+/// ```rust
+/// use chatpack::progress::MultiFileProgress;
+///
+/// let mut progress = MultiFileProgress::new([10_000, 30_000, 60_000]);
+/// progress.update(0, 5_000);
+/// assert_eq!(progress.percentage(), 5.0);
+///
+/// progress.finish_file(0);
+/// progress.update(1, 30_000);
+/// assert_eq!(progress.percentage(), 40.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MultiFileProgress {
+    sizes: Vec<u64>,
+    processed: Vec<u64>,
+    total: u64,
+    done: u64,
+}
+
+impl MultiFileProgress {
+    /// Creates a tracker for files of the given sizes, in bytes.
+    pub fn new(sizes: impl IntoIterator<Item = u64>) -> Self {
+        let sizes: Vec<u64> = sizes.into_iter().collect();
+        Self {
+            processed: vec![0; sizes.len()],
+            total: sizes.iter().sum(),
+            sizes,
+            done: 0,
+        }
+    }
+
+    /// Creates a tracker from the current sizes of the files at `paths`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first file whose metadata cannot be read.
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P]) -> io::Result<Self> {
+        let sizes = paths
+            .iter()
+            .map(|path| std::fs::metadata(path).map(|m| m.len()))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(Self::new(sizes))
+    }
+
+    /// Returns the number of files tracked.
+    pub fn file_count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// Returns the size of file `file` as it was when tracking started.
+    pub fn file_size(&self, file: usize) -> Option<u64> {
+        self.sizes.get(file).copied()
+    }
+
+    /// Records that `bytes` bytes of file `file` have been processed.
+    ///
+    /// `bytes` is the file's own running total, such as
+    /// `MessageIterator::bytes_processed`. It is capped at the file's size,
+    /// and an out-of-range `file` is ignored.
+    pub fn update(&mut self, file: usize, bytes: u64) {
+        if let Some(processed) = self.processed.get_mut(file) {
+            let bytes = bytes.min(self.sizes[file]);
+            self.done = self.done - *processed + bytes;
+            *processed = bytes;
+        }
+    }
+
+    /// Records file `file` as fully processed, including when it shrank
+    /// or could not be read.
+    pub fn finish_file(&mut self, file: usize) {
+        if let Some(&size) = self.sizes.get(file) {
+            self.update(file, size);
+        }
+    }
+
+    /// Returns the bytes processed across all files.
+    pub fn bytes_processed(&self) -> u64 {
+        self.done
+    }
+
+    /// Returns the combined size of all files.
+    pub fn total_bytes(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the progress across all files as a percentage (0.0 - 100.0).
+    ///
+    /// Returns 100.0 if every file is empty.
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            (self.done as f64 / self.total as f64) * 100.0
+        }
+    }
+
+    /// Returns a [`Progress`] for a [`ProgressCallback`], with
+    /// `items_processed` items done so far.
+    pub fn progress(&self, items_processed: usize) -> Progress {
+        Progress::new(self.done, Some(self.total), items_processed)
+    }
+}
+
 /// Callback type for receiving progress updates.
 ///
 /// This is a thread-safe callback that receives [`Progress`] updates
@@ -236,6 +403,92 @@ mod tests {
         assert_eq!(progress.items_percentage(), Some(50.0));
     }
 
+    // =========================================================================
+    // MultiFileProgress
+    // =========================================================================
+
+    fn assert_percentage(progress: &MultiFileProgress, expected: f64) {
+        let actual = progress.percentage();
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_multi_file_progress_weights_by_size() {
+        let mut progress = MultiFileProgress::new([10_000, 30_000, 60_000]);
+        assert_eq!(progress.total_bytes(), 100_000);
+        assert_percentage(&progress, 0.0);
+
+        progress.finish_file(0);
+        assert_percentage(&progress, 10.0);
+
+        progress.update(1, 15_000);
+        assert_percentage(&progress, 25.0);
+        progress.finish_file(1);
+        assert_percentage(&progress, 40.0);
+
+        progress.update(2, 60_000);
+        assert_percentage(&progress, 100.0);
+        assert_eq!(progress.bytes_processed(), 100_000);
+    }
+
+    #[test]
+    fn test_multi_file_progress_clamps_grown_file() {
+        let mut progress = MultiFileProgress::new([100, 100]);
+        progress.update(0, 250);
+        assert_eq!(progress.bytes_processed(), 100);
+        progress.update(1, 1_000);
+        assert_percentage(&progress, 100.0);
+    }
+
+    #[test]
+    fn test_multi_file_progress_finish_shrunk_file() {
+        let mut progress = MultiFileProgress::new([100, 300]);
+        progress.update(0, 40);
+        progress.finish_file(0);
+        assert_percentage(&progress, 25.0);
+    }
+
+    #[test]
+    fn test_multi_file_progress_empty() {
+        let progress = MultiFileProgress::new([0, 0]);
+        assert_percentage(&progress, 100.0);
+        assert_eq!(MultiFileProgress::default().file_count(), 0);
+    }
+
+    #[test]
+    fn test_multi_file_progress_out_of_range() {
+        let mut progress = MultiFileProgress::new([100]);
+        progress.update(5, 50);
+        progress.finish_file(5);
+        assert_eq!(progress.bytes_processed(), 0);
+        assert_eq!(progress.file_size(5), None);
+    }
+
+    #[test]
+    fn test_multi_file_progress_from_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<_> = [10_000usize, 30_000, 60_000]
+            .iter()
+            .enumerate()
+            .map(|(i, &len)| {
+                let path = dir.path().join(format!("{i}.txt"));
+                std::fs::write(&path, vec![b'x'; len]).unwrap();
+                path
+            })
+            .collect();
+
+        let mut progress = MultiFileProgress::from_paths(&paths).unwrap();
+        assert_eq!(progress.file_count(), 3);
+        assert_eq!(progress.file_size(1), Some(30_000));
+        progress.finish_file(0);
+
+        let snapshot = progress.progress(7);
+        assert_eq!(snapshot.percentage(), Some(10.0));
+        assert_eq!(snapshot.items_processed, 7);
+
+        assert!(MultiFileProgress::from_paths(&[dir.path().join("missing")]).is_err());
+    }
+
     #[test]
     fn test_no_progress_callback() {
         let callback = no_progress();
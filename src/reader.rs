@@ -0,0 +1,102 @@
+//! One-call reading of an export into messages.
+//!
+//! [`read`] detects the platform of a file and parses it with that
+//! platform's default parser; [`read_as`] skips the detection. For
+//! exports already in memory, such as uploads in a WASM build,
+//! [`read_bytes`] and [`read_from`] sniff the platform from the content.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "whatsapp")]
+//! # fn main() -> chatpack::Result<()> {
+//! let export = "[1/15/24, 10:30:00 AM] Alice: Hello\n[1/15/24, 10:31:00 AM] Bob: Hi!";
+//! let messages = chatpack::read_bytes(export)?;
+//! assert_eq!(messages.len(), 2);
+//! assert_eq!(messages[1].sender, "Bob");
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "whatsapp"))]
+//! # fn main() {}
+//! ```
+
+use std::io::Read;
+use std::path::Path;
+
+use crate::Message;
+use crate::error::ChatpackError;
+use crate::parser::{Platform, detect_platform, detect_platform_from_str, try_create_parser};
+use crate::parsing::encoding::decode_text;
+
+/// Reads the export at `path`, detecting its platform.
+///
+/// Equivalent to [`detect_platform`] followed by [`read_as`].
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> chatpack::Result<()> {
+/// let messages = chatpack::read("result.json")?;
+/// println!("{} messages", messages.len());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the platform cannot be detected or is not enabled,
+/// or the file cannot be read or parsed.
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<Message>, ChatpackError> {
+    let path = path.as_ref();
+    read_as(detect_platform(path)?, path)
+}
+
+/// Reads the export at `path` with the default parser for `platform`.
+///
+/// # Errors
+///
+/// Returns an error if the platform is not enabled, or the file cannot be
+/// read or parsed.
+pub fn read_as(platform: Platform, path: impl AsRef<Path>) -> Result<Vec<Message>, ChatpackError> {
+    try_create_parser(platform)?.parse(path.as_ref())
+}
+
+/// Reads an export from memory, detecting its platform from the content.
+///
+/// UTF-8 and UTF-16 content are both accepted, with or without a BOM.
+/// See [`detect_platform_from_str`] for how the platform is recognized.
+///
+/// # Errors
+///
+/// Returns an error if the bytes cannot be decoded, the platform cannot
+/// be detected or is not enabled, or the content cannot be parsed.
+pub fn read_bytes(bytes: impl Into<Vec<u8>>) -> Result<Vec<Message>, ChatpackError> {
+    let content = decode_text(bytes.into())?;
+    try_create_parser(detect_platform_from_str(&content)?)?.parse_str(&content)
+}
+
+/// Reads an export from memory with the default parser for `platform`.
+///
+/// # Errors
+///
+/// Returns an error if the bytes cannot be decoded, the platform is not
+/// enabled, or the content cannot be parsed.
+pub fn read_bytes_as(
+    platform: Platform,
+    bytes: impl Into<Vec<u8>>,
+) -> Result<Vec<Message>, ChatpackError> {
+    let content = decode_text(bytes.into())?;
+    try_create_parser(platform)?.parse_str(&content)
+}
+
+/// Reads an export from `reader` to its end, detecting its platform from
+/// the content.
+///
+/// # Errors
+///
+/// Returns an error if reading fails, or whatever [`read_bytes`] returns.
+pub fn read_from(mut reader: impl Read) -> Result<Vec<Message>, ChatpackError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    read_bytes(bytes)
+}
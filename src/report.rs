@@ -0,0 +1,331 @@
+//! Human-readable summaries of a conversion.
+//!
+//! [`format_summary`] and [`format_performance`] render what a run did and
+//! how long it took as short aligned tables, for a CLI or a log. They only
+//! build strings: printing them, and choosing stderr so they never mix with
+//! data written to stdout, is up to the caller.
+//!
+//! [`ordering_warning`] is the one line a caller should print even when it
+//! shows no summary: merging an unsorted export silently groups the wrong
+//! messages.
+//!
+//! [`ReportStyle::Unicode`] heads each table with an emoji;
+//! [`ReportStyle::Plain`] is ASCII only, for log files and terminals that
+//! cannot show Unicode.
+//!
+//! # Example
+//!
+//! ```
+//! use chatpack::core::processor::ProcessingStats;
+//! use chatpack::report::{ReportStyle, format_summary};
+//!
+//! let stats = ProcessingStats::new(1200, 480);
+//! let summary = format_summary(&stats, ReportStyle::Plain);
+//! assert_eq!(
+//!     summary,
+//!     "Summary\n   Parsed:  1200 messages\n   Written:  480 messages (60.0% reduction)\n"
+//! );
+//! ```
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::core::processor::{OrderingReport, ProcessingStats};
+use crate::core::stats::format_gap;
+
+/// How [`format_summary`] and [`format_performance`] decorate their output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportStyle {
+    /// Emoji headings (default).
+    #[default]
+    Unicode,
+    /// ASCII only.
+    Plain,
+}
+
+impl ReportStyle {
+    fn heading(self, emoji: &str, title: &str) -> String {
+        match self {
+            ReportStyle::Unicode => format!("{emoji} {title}"),
+            ReportStyle::Plain => title.to_string(),
+        }
+    }
+}
+
+/// How long each stage of a run took, for [`format_performance`].
+///
+/// Stages left at `None` are not shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Performance {
+    /// Messages the run handled, for the speed line.
+    pub messages: usize,
+    /// Bytes of input read, if known.
+    pub bytes: Option<u64>,
+    /// Time spent parsing.
+    pub parse: Option<Duration>,
+    /// Time spent filtering, merging, and transforming.
+    pub process: Option<Duration>,
+    /// Time spent writing output.
+    pub write: Option<Duration>,
+    /// Wall time of the whole run.
+    pub total: Duration,
+}
+
+impl Performance {
+    /// Creates timings for `messages` handled in `total`.
+    pub fn new(messages: usize, total: Duration) -> Self {
+        Self {
+            messages,
+            total,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the bytes of input read.
+    #[must_use]
+    pub fn with_bytes(mut self, bytes: u64) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    /// Sets the time spent parsing.
+    #[must_use]
+    pub fn with_parse(mut self, elapsed: Duration) -> Self {
+        self.parse = Some(elapsed);
+        self
+    }
+
+    /// Sets the time spent filtering, merging, and transforming.
+    #[must_use]
+    pub fn with_process(mut self, elapsed: Duration) -> Self {
+        self.process = Some(elapsed);
+        self
+    }
+
+    /// Sets the time spent writing output.
+    #[must_use]
+    pub fn with_write(mut self, elapsed: Duration) -> Self {
+        self.write = Some(elapsed);
+        self
+    }
+}
+
+/// One line of a table: label, right-aligned number, and unit.
+struct Row {
+    label: &'static str,
+    number: String,
+    unit: String,
+}
+
+impl Row {
+    fn new(label: &'static str, number: &impl ToString, unit: impl Into<String>) -> Self {
+        Self {
+            label,
+            number: number.to_string(),
+            unit: unit.into(),
+        }
+    }
+}
+
+/// Renders `rows` under `heading`, lining up the labels and numbers.
+fn table(out: &mut String, heading: &str, rows: &[Row]) {
+    let label_width = rows.iter().map(|r| r.label.len() + 1).max().unwrap_or(0);
+    let number_width = rows.iter().map(|r| r.number.len()).max().unwrap_or(0);
+    out.push_str(heading);
+    out.push('\n');
+    for row in rows {
+        let label = format!("{}:", row.label);
+        let line = format!(
+            "   {label:<label_width$} {:>number_width$} {}",
+            row.number, row.unit
+        );
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+}
+
+/// Renders what a run parsed, filtered, and wrote.
+///
+/// Lists the parsed count, the filtered count when a filter ran, the
+/// written count with the reduction from merging, and the seed of
+/// randomized steps when there was one. A non-empty
+/// [`loss`](ProcessingStats::loss) adds a "Not written" section with its
+/// non-zero counts, and input that [appears unsorted](OrderingReport::appears_unsorted)
+/// adds an "Out of order" section with its [`ordering_warning`].
+pub fn format_summary(stats: &ProcessingStats, style: ReportStyle) -> String {
+    let mut rows = vec![Row::new("Parsed", &stats.original_count, "messages")];
+    if let Some(filtered) = stats.filtered_count {
+        rows.push(Row::new("Filtered", &filtered, "messages"));
+    }
+    rows.push(Row::new(
+        "Written",
+        &stats.merged_count,
+        format!("messages ({:.1}% reduction)", stats.compression_ratio()),
+    ));
+    if let Some(seed) = stats.seed {
+        rows.push(Row::new("Seed", &seed, ""));
+    }
+
+    let mut out = String::new();
+    table(&mut out, &style.heading("📊", "Summary"), &rows);
+    let loss = stats.loss.entries();
+    if !loss.is_empty() {
+        let width = loss.iter().map(|(count, _)| count.to_string().len()).max();
+        let width = width.unwrap_or(0);
+        out.push_str(&style.heading("⚠️", "Not written"));
+        out.push('\n');
+        for (count, what) in loss {
+            let _ = writeln!(out, "   {count:>width$} {what}");
+        }
+    }
+    if let Some(warning) = ordering_warning(&stats.ordering) {
+        out.push_str(&style.heading("⚠️", "Out of order"));
+        let _ = writeln!(out, "\n   {warning}");
+    }
+    out
+}
+
+/// Returns a warning if `ordering` shows the input
+/// [appears unsorted](OrderingReport::appears_unsorted), or `None`.
+///
+/// ```
+/// use chatpack::core::processor::OrderingReport;
+/// use chatpack::report::ordering_warning;
+/// use chrono::Duration;
+///
+/// let ordering = OrderingReport {
+///     inversions: 4,
+///     large_inversions: 3,
+///     max_backward_jump: Duration::minutes(125),
+/// };
+/// assert_eq!(
+///     ordering_warning(&ordering).unwrap(),
+///     "input appears unsorted (4 messages go back in time, by up to 2h 05m); consider sorting it"
+/// );
+/// assert_eq!(ordering_warning(&OrderingReport::default()), None);
+/// ```
+pub fn ordering_warning(ordering: &OrderingReport) -> Option<String> {
+    if !ordering.appears_unsorted() {
+        return None;
+    }
+    let messages = match ordering.inversions {
+        1 => "1 message goes".to_string(),
+        n => format!("{n} messages go"),
+    };
+    Some(format!(
+        "input appears unsorted ({messages} back in time, by up to {}); consider sorting it",
+        format_gap(ordering.max_backward_jump.num_seconds() as f64)
+    ))
+}
+
+/// Renders how long a run took, stage by stage, and its throughput.
+///
+/// Times under a second are shown in milliseconds, longer ones in seconds.
+/// The speed line is left out when the total time is zero.
+pub fn format_performance(perf: &Performance, style: ReportStyle) -> String {
+    let stages = [
+        ("Parse", perf.parse),
+        ("Process", perf.process),
+        ("Write", perf.write),
+        ("Total", Some(perf.total)),
+    ];
+    let mut rows: Vec<Row> = stages
+        .into_iter()
+        .filter_map(|(label, elapsed)| {
+            let (number, unit) = duration(elapsed?);
+            Some(Row::new(label, &number, unit))
+        })
+        .collect();
+
+    let secs = perf.total.as_secs_f64();
+    if secs > 0.0 {
+        let mut unit = "messages/s".to_string();
+        if let Some(bytes) = perf.bytes {
+            let _ = write!(unit, " ({:.1} MB/s)", bytes as f64 / 1_000_000.0 / secs);
+        }
+        rows.push(Row::new(
+            "Speed",
+            &format!("{:.0}", perf.messages as f64 / secs),
+            unit,
+        ));
+    }
+
+    let mut out = String::new();
+    table(&mut out, &style.heading("⏱️", "Performance"), &rows);
+    out
+}
+
+/// Splits `elapsed` into a number and its unit.
+fn duration(elapsed: Duration) -> (String, &'static str) {
+    if elapsed < Duration::from_secs(1) {
+        (elapsed.as_millis().to_string(), "ms")
+    } else {
+        (format!("{:.2}", elapsed.as_secs_f64()), "s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rand::Seed;
+
+    #[test]
+    fn test_plain_is_ascii() {
+        let mut stats = ProcessingStats::new(100, 40).with_seed(Seed::new(7));
+        stats.loss.messages_merged = 60;
+        let perf = Performance::new(100, Duration::from_secs(2)).with_bytes(1_000);
+
+        assert!(format_summary(&stats, ReportStyle::Plain).is_ascii());
+        assert!(format_performance(&perf, ReportStyle::Plain).is_ascii());
+        assert!(!format_summary(&stats, ReportStyle::Unicode).is_ascii());
+    }
+
+    #[test]
+    fn test_numbers_right_aligned() {
+        let stats = ProcessingStats::new(1_000_000, 3).with_filtered(45);
+        let summary = format_summary(&stats, ReportStyle::Plain);
+        let ends: Vec<usize> = summary
+            .lines()
+            .skip(1)
+            .map(|line| line.find(" messages").unwrap())
+            .collect();
+
+        assert_eq!(ends.len(), 3);
+        assert!(ends.iter().all(|&end| end == ends[0]), "{summary}");
+    }
+
+    #[test]
+    fn test_summary_warns_when_unsorted() {
+        let mut stats = ProcessingStats::new(10, 10);
+        stats.ordering.inversions = 3;
+        stats.ordering.max_backward_jump = chrono::Duration::seconds(20);
+        assert!(!format_summary(&stats, ReportStyle::Plain).contains("Out of order"));
+
+        stats.ordering.large_inversions = 1;
+        stats.ordering.max_backward_jump = chrono::Duration::hours(3);
+        let summary = format_summary(&stats, ReportStyle::Plain);
+        assert!(
+            summary.ends_with(
+                "Out of order\n   input appears unsorted (3 messages go back in time, by up to 3h 00m); consider sorting it\n"
+            ),
+            "{summary}"
+        );
+    }
+
+    #[test]
+    fn test_duration_units() {
+        assert_eq!(
+            duration(Duration::from_micros(999_999)),
+            ("999".into(), "ms")
+        );
+        assert_eq!(duration(Duration::from_millis(1_005)), ("1.00".into(), "s"));
+        assert_eq!(duration(Duration::ZERO), ("0".into(), "ms"));
+    }
+
+    #[test]
+    fn test_zero_total_has_no_speed() {
+        let perf = Performance::new(10, Duration::ZERO);
+        let output = format_performance(&perf, ReportStyle::Plain);
+        assert_eq!(output, "Performance\n   Total: 0 ms\n");
+    }
+}
@@ -0,0 +1,351 @@
+//! Visitor-style parsing without building [`Message`]s.
+//!
+//! [`Parser::parse_into`](crate::parser::Parser::parse_into) walks an export
+//! and hands each message to a [`MessageSink`] as a [`MessageRef`], which
+//! borrows its text from the parser's buffers. Sinks that build their own
+//! structures (indexes, counters, custom records) avoid allocating a
+//! `Message` per record, and can stop the parse early by returning
+//! [`ControlFlow::Break`].
+//!
+//! Discord JSONL and WhatsApp exports are read incrementally, so breaking
+//! also stops reading the file. Other formats are parsed first and then
+//! replayed into the sink.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[cfg(feature = "discord-json")]
+//! # fn main() -> chatpack::Result<()> {
+//! use std::collections::HashMap;
+//! use std::ops::ControlFlow;
+//! use std::path::Path;
+//!
+//! use chatpack::parser::Parser;
+//! use chatpack::parsers::DiscordParser;
+//! use chatpack::sink::MessageRef;
+//!
+//! let mut words: HashMap<String, usize> = HashMap::new();
+//! let mut sink = |msg: MessageRef<'_>| {
+//!     *words.entry(msg.sender.to_string()).or_default() += msg.content.split_whitespace().count();
+//!     ControlFlow::Continue(())
+//! };
+//! DiscordParser::new().parse_into(Path::new("channel.jsonl"), &mut sink)?;
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "discord-json"))]
+//! # fn main() {}
+//! ```
+
+use std::fmt;
+use std::ops::ControlFlow;
+
+use chrono::{DateTime, Utc};
+
+use crate::config::{Projection, TimestampAction};
+use crate::parser::Platform;
+use crate::{AttachmentRef, Message};
+
+/// A parsed message borrowing its text from the parser.
+///
+/// Valid only for the duration of [`MessageSink::on_message`]; call
+/// [`to_message`](Self::to_message) to keep it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MessageRef<'a> {
+    /// Display name or username of the message author.
+    pub sender: &'a str,
+    /// Text content of the message.
+    pub content: &'a str,
+    /// When the message was sent.
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Platform-specific message identifier.
+    pub id: Option<u64>,
+    /// ID of the message this is replying to.
+    pub reply_to: Option<u64>,
+    /// When the message was last edited.
+    pub edited: Option<DateTime<Utc>>,
+    /// Original author, if the message is a forward.
+    pub forwarded_from: Option<&'a str>,
+    /// Forum topic the message was posted in.
+    pub topic: Option<&'a str>,
+    /// Media files the message references, if the parser collects them.
+    pub attachments: &'a [AttachmentRef],
+    /// Position of the message in the parser's output, starting at 0.
+    pub source_index: Option<u64>,
+    /// Platform of the parser that produced the message.
+    pub platform: Option<Platform>,
+    /// The source record, if the parser was configured to capture it.
+    pub raw: Option<&'a str>,
+}
+
+impl MessageRef<'_> {
+    /// Copies the message into an owned [`Message`].
+    pub fn to_message(&self) -> Message {
+        self.projected_message(Projection::all())
+    }
+
+    /// Copies the fields `projection` keeps into an owned [`Message`],
+    /// leaving the others empty without copying them.
+    pub(crate) fn projected_message(&self, projection: Projection) -> Message {
+        let content = if projection.need_content {
+            self.content
+        } else {
+            ""
+        };
+        let mut msg = Message::with_metadata(
+            self.sender,
+            content,
+            self.timestamp,
+            self.id.filter(|_| projection.need_ids),
+            self.reply_to.filter(|_| projection.need_ids),
+            self.edited.filter(|_| projection.need_metadata),
+        );
+        if projection.need_metadata {
+            msg.forwarded_from = self.forwarded_from.map(str::to_string);
+            msg.topic = self.topic.map(str::to_string);
+        }
+        if projection.need_attachments {
+            msg.attachments = self.attachments.to_vec();
+        }
+        msg.source_index = self.source_index;
+        msg.platform = self.platform;
+        if projection.need_content {
+            msg.raw = self.raw.map(str::to_string);
+        }
+        msg
+    }
+}
+
+impl<'a> From<&'a Message> for MessageRef<'a> {
+    fn from(msg: &'a Message) -> Self {
+        Self {
+            sender: &msg.sender,
+            content: &msg.content,
+            timestamp: msg.timestamp,
+            id: msg.id,
+            reply_to: msg.reply_to,
+            edited: msg.edited,
+            forwarded_from: msg.forwarded_from.as_deref(),
+            topic: msg.topic.as_deref(),
+            attachments: &msg.attachments,
+            source_index: msg.source_index,
+            platform: msg.platform,
+            raw: msg.raw.as_deref(),
+        }
+    }
+}
+
+/// Why a record was skipped or flagged instead of delivered as parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// The record could not be parsed and `skip_invalid` is enabled.
+    InvalidRecord {
+        /// 1-based line number, when the format is line-oriented.
+        line: Option<usize>,
+        /// Description of the parse error.
+        error: String,
+    },
+    /// The message's timestamp failed the config's
+    /// [`TimestampCheck`](crate::config::TimestampCheck). The message is
+    /// still delivered unless `action` is [`TimestampAction::Drop`].
+    ImplausibleTimestamp {
+        /// The timestamp as parsed.
+        timestamp: DateTime<Utc>,
+        /// What the parser did with the message.
+        action: TimestampAction,
+    },
+    /// The export ends partway through this record and
+    /// `tolerate_truncation` is enabled. Reported once, after the last
+    /// message.
+    Truncated {
+        /// 1-based line number, when the format is line-oriented.
+        line: Option<usize>,
+        /// Description of the parse error.
+        error: String,
+    },
+    /// A system or service message (a join, a pin, an encryption notice)
+    /// was left out. Not an error, and not counted as skipped by
+    /// [`CollectingSink`] or `parse_counted`.
+    ///
+    /// Reported by the Telegram and WhatsApp parsers and for Discord JSONL
+    /// exports; parsers that load the whole export first report these
+    /// before the messages. Of the streaming parsers, only Telegram's
+    /// reports them, as it passes them.
+    SystemMessage,
+    /// A message with no text was left out. Not an error, and not counted
+    /// as skipped by [`CollectingSink`] or `parse_counted`.
+    ///
+    /// Reported by the WhatsApp parser outside streaming mode.
+    BlankMessage,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::InvalidRecord {
+                line: Some(line),
+                error,
+            } => write!(f, "invalid record on line {line}: {error}"),
+            SkipReason::InvalidRecord { line: None, error } => {
+                write!(f, "invalid record: {error}")
+            }
+            SkipReason::ImplausibleTimestamp { timestamp, .. } => {
+                write!(f, "implausible timestamp: {}", timestamp.to_rfc3339())
+            }
+            SkipReason::Truncated {
+                line: Some(line),
+                error,
+            } => write!(f, "export truncated on line {line}: {error}"),
+            SkipReason::Truncated { line: None, error } => {
+                write!(f, "export truncated: {error}")
+            }
+            SkipReason::SystemMessage => write!(f, "system message"),
+            SkipReason::BlankMessage => write!(f, "blank message"),
+        }
+    }
+}
+
+/// Receives messages as a parser walks an export.
+///
+/// Implemented for closures taking a [`MessageRef`], and for `Vec<Message>`,
+/// which collects every message.
+pub trait MessageSink {
+    /// Called for each message, in export order.
+    ///
+    /// Return [`ControlFlow::Break`] to stop parsing.
+    fn on_message(&mut self, message: MessageRef<'_>) -> ControlFlow<()>;
+
+    /// Called for each record skipped because it could not be parsed, for
+    /// each message flagged by a timestamp check, and for each system
+    /// or blank message left out.
+    fn on_skip(&mut self, reason: &SkipReason) {
+        let _ = reason;
+    }
+}
+
+impl<F> MessageSink for F
+where
+    F: FnMut(MessageRef<'_>) -> ControlFlow<()>,
+{
+    fn on_message(&mut self, message: MessageRef<'_>) -> ControlFlow<()> {
+        self(message)
+    }
+}
+
+impl MessageSink for Vec<Message> {
+    fn on_message(&mut self, message: MessageRef<'_>) -> ControlFlow<()> {
+        self.push(message.to_message());
+        ControlFlow::Continue(())
+    }
+}
+
+/// Sink that collects messages and counts skipped records.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollectingSink {
+    /// Messages received so far.
+    pub messages: Vec<Message>,
+    /// Number of records skipped as invalid or flagged by a timestamp check.
+    pub skipped: usize,
+    /// Number of system messages left out.
+    pub system_messages: usize,
+    /// Number of blank messages left out.
+    pub blank_messages: usize,
+}
+
+impl CollectingSink {
+    /// Creates an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MessageSink for CollectingSink {
+    fn on_message(&mut self, message: MessageRef<'_>) -> ControlFlow<()> {
+        self.messages.on_message(message)
+    }
+
+    fn on_skip(&mut self, reason: &SkipReason) {
+        match reason {
+            SkipReason::SystemMessage => self.system_messages += 1,
+            SkipReason::BlankMessage => self.blank_messages += 1,
+            _ => self.skipped += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_ref_round_trip() {
+        let msg = Message::new("Alice", "Hi")
+            .with_id(1)
+            .with_reply_to(0)
+            .with_attachment(AttachmentRef::new("a.png"))
+            .with_source_index(3)
+            .with_raw("{}");
+        let copy = MessageRef::from(&msg).to_message();
+
+        assert_eq!(copy, msg);
+        assert_eq!(copy.source_index, Some(3));
+        assert_eq!(copy.raw(), Some("{}"));
+    }
+
+    #[test]
+    fn test_closure_sink() {
+        let messages: Vec<Message> = (0..5).map(|i| Message::new("A", i.to_string())).collect();
+        let mut seen = Vec::new();
+        let mut sink = |msg: MessageRef<'_>| {
+            seen.push(msg.content.to_string());
+            if seen.len() == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        };
+
+        for msg in &messages {
+            if sink.on_message(MessageRef::from(msg)).is_break() {
+                break;
+            }
+        }
+        assert_eq!(seen, ["0", "1"]);
+    }
+
+    #[test]
+    fn test_collecting_sink_counts_skips() {
+        let mut sink = CollectingSink::new();
+        let _ = sink.on_message(MessageRef::from(&Message::new("A", "x")));
+        sink.on_skip(&SkipReason::InvalidRecord {
+            line: Some(2),
+            error: "bad".into(),
+        });
+
+        sink.on_skip(&SkipReason::SystemMessage);
+        sink.on_skip(&SkipReason::BlankMessage);
+
+        assert_eq!(sink.messages.len(), 1);
+        assert_eq!(sink.skipped, 1);
+        assert_eq!(sink.system_messages, 1);
+        assert_eq!(sink.blank_messages, 1);
+    }
+
+    #[test]
+    fn test_skip_reason_display() {
+        let reason = SkipReason::InvalidRecord {
+            line: Some(4),
+            error: "EOF".into(),
+        };
+        assert_eq!(reason.to_string(), "invalid record on line 4: EOF");
+
+        let reason = SkipReason::Truncated {
+            line: None,
+            error: "EOF while parsing a string".into(),
+        };
+        assert_eq!(
+            reason.to_string(),
+            "export truncated: EOF while parsing a string"
+        );
+    }
+}
@@ -6,13 +6,18 @@ use std::fs::File;
 use std::io::{BufRead, BufReader, Seek};
 use std::path::Path;
 
-use chrono::DateTime;
-use serde::Deserialize;
-
 use crate::Message;
+use crate::config::{ContentPolicy, DiscordConfig, TimestampCheck};
 use crate::error::ChatpackError;
+use crate::parser::Platform;
+use crate::parsing::content::normalize_content_in_place;
+use crate::parsing::discord::{SenderNaming, is_jsonl, parse_discord_jsonl_line_named};
+use crate::parsing::timestamps::keep_message;
 
-use super::{MessageIterator, StreamingConfig, StreamingError, StreamingParser, StreamingResult};
+use super::traits::SourceIndexer;
+use super::{
+    MessageIterator, Projection, StreamingConfig, StreamingError, StreamingParser, StreamingResult,
+};
 
 /// Streaming parser for Discord exports.
 ///
@@ -20,29 +25,117 @@ use super::{MessageIterator, StreamingConfig, StreamingError, StreamingParser, S
 /// Also handles standard JSON format by falling back to object-by-object parsing.
 pub struct DiscordStreamingParser {
     config: StreamingConfig,
+    naming: SenderNaming,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    sender_allowlist: Option<Vec<String>>,
+    include_system_events: bool,
+    content: ContentPolicy,
 }
 
 impl DiscordStreamingParser {
     /// Creates a new streaming parser with default configuration.
     pub fn new() -> Self {
-        Self {
-            config: StreamingConfig::default(),
-        }
+        Self::with_config(StreamingConfig::default())
     }
 
     /// Creates a new streaming parser with custom configuration.
     pub fn with_config(config: StreamingConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            naming: SenderNaming::default(),
+            clamp_timestamps: None,
+            capture_raw: false,
+            sender_allowlist: None,
+            include_system_events: false,
+            content: ContentPolicy::default(),
+        }
+    }
+
+    /// Creates a streaming parser from a [`DiscordConfig`], honoring its
+    /// buffer sizes, `skip_invalid`, `tolerate_truncation`, sender naming
+    /// options, sender allowlist, system event handling, timestamp check, and
+    /// content policy.
+    pub fn from_discord_config(config: &DiscordConfig) -> Self {
+        let streaming_config = StreamingConfig::new()
+            .with_buffer_size(config.buffer_size)
+            .with_max_message_size(config.max_message_size)
+            .with_skip_invalid(config.skip_invalid)
+            .with_tolerate_truncation(config.tolerate_truncation);
+        Self::with_config(streaming_config)
+            .with_naming(config.into())
+            .with_clamp_timestamps(config.clamp_timestamps.clone())
+            .with_capture_raw(config.capture_raw)
+            .with_sender_allowlist(config.sender_allowlist.clone())
+            .with_include_system_events(config.include_system_events)
+            .with_content_policy(config.content)
+    }
+
+    /// Sets how authors become sender names (default: prefer nicknames).
+    #[must_use]
+    pub fn with_naming(mut self, naming: SenderNaming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Sets the clock sanity check applied to message timestamps.
+    ///
+    /// See [`DiscordConfig::clamp_timestamps`].
+    #[must_use]
+    pub fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
+
+    /// Keeps each message's source record in [`Message::raw`].
+    ///
+    /// See [`DiscordConfig::capture_raw`].
+    #[must_use]
+    pub fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    /// Keeps only messages from the given senders, ignoring ASCII case.
+    ///
+    /// See [`DiscordConfig::sender_allowlist`].
+    #[must_use]
+    pub fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.sender_allowlist = senders;
+        self
+    }
+
+    /// Keeps system notifications as messages instead of skipping them.
+    ///
+    /// See [`DiscordConfig::include_system_events`].
+    #[must_use]
+    pub fn with_include_system_events(mut self, include: bool) -> Self {
+        self.include_system_events = include;
+        self
+    }
+
+    /// Sets the whitespace rules applied to message content.
+    ///
+    /// See [`DiscordConfig::content`].
+    #[must_use]
+    pub fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
     }
 
     /// Detects if the file is JSONL format.
     fn is_jsonl(first_line: &str) -> bool {
-        let trimmed = first_line.trim();
-        // JSONL: each line is a complete JSON object
-        // Regular JSON: starts with { and has nested structure
-        trimmed.starts_with('{')
-            && !trimmed.contains("\"messages\"")
-            && !trimmed.contains("\"guild\"")
+        is_jsonl(first_line)
+    }
+
+    /// Creates a JSONL iterator with this parser's options.
+    fn jsonl_iterator<R: BufRead>(&self, reader: R, file_size: u64) -> DiscordJsonlIterator<R> {
+        DiscordJsonlIterator::new(reader, file_size, self.config, self.naming)
+            .with_clamp_timestamps(self.clamp_timestamps.clone())
+            .with_capture_raw(self.capture_raw && self.config.projection.need_content)
+            .with_sender_allowlist(self.sender_allowlist.clone())
+            .with_include_system_events(self.include_system_events)
+            .with_content_policy(self.content)
     }
 }
 
@@ -70,14 +163,43 @@ impl StreamingParser for DiscordStreamingParser {
         reader.seek(std::io::SeekFrom::Start(0))?;
 
         if Self::is_jsonl(&first_line) {
-            let iterator = DiscordJsonlIterator::new(reader, file_size, self.config);
-            Ok(Box::new(iterator))
+            Ok(Box::new(self.jsonl_iterator(reader, file_size)))
         } else {
             // For regular JSON, use a similar approach to Telegram
-            let iterator = DiscordJsonIterator::new(reader, file_size, self.config)?;
+            let iterator = DiscordJsonIterator::new(reader, file_size, self.config, self.naming)?
+                .with_clamp_timestamps(self.clamp_timestamps.clone())
+                .with_capture_raw(self.capture_raw && self.config.projection.need_content)
+                .with_sender_allowlist(self.sender_allowlist.clone())
+                .with_include_system_events(self.include_system_events)
+                .with_content_policy(self.content);
             Ok(Box::new(iterator))
         }
     }
+
+    /// Reads the last `n` messages of a JSONL export from the end of the
+    /// file. Returns [`ChatpackError::UnsupportedOperation`] for JSON
+    /// exports, which are a single document.
+    fn stream_tail(&self, file_path: &str, n: usize) -> Result<Vec<Message>, ChatpackError> {
+        let mut first_line = String::new();
+        BufReader::new(File::open(file_path)?).read_line(&mut first_line)?;
+        if !Self::is_jsonl(&first_line) {
+            return Err(ChatpackError::unsupported_operation(
+                "tail reading",
+                "Discord (Streaming) for JSON exports",
+            ));
+        }
+
+        let is_record = |line: &[u8]| !line.trim_ascii().is_empty();
+        super::tail::read_tail(file_path, n, false, is_record, |file| {
+            let file_size = file.metadata()?.len();
+            let reader = BufReader::with_capacity(self.config.buffer_size, file);
+            Ok(Box::new(self.jsonl_iterator(reader, file_size)) as Box<dyn MessageIterator>)
+        })
+    }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 /// Iterator for JSONL Discord exports.
@@ -86,54 +208,89 @@ pub struct DiscordJsonlIterator<R: BufRead> {
     file_size: u64,
     bytes_read: u64,
     config: StreamingConfig,
+    naming: SenderNaming,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    sender_allowlist: Option<Vec<String>>,
+    include_system_events: bool,
+    content: ContentPolicy,
+    skipped: usize,
+    indexer: SourceIndexer,
     line_buffer: String,
+    decoded: bool,
 }
 
 impl<R: BufRead> DiscordJsonlIterator<R> {
-    fn new(reader: R, file_size: u64, config: StreamingConfig) -> Self {
+    fn new(reader: R, file_size: u64, config: StreamingConfig, naming: SenderNaming) -> Self {
         Self {
             reader,
             file_size,
             bytes_read: 0,
             config,
+            naming,
+            clamp_timestamps: None,
+            capture_raw: false,
+            sender_allowlist: None,
+            include_system_events: false,
+            content: ContentPolicy::default(),
+            skipped: 0,
+            indexer: SourceIndexer::new(Platform::Discord),
             line_buffer: String::with_capacity(4096),
+            decoded: false,
         }
     }
 
-    fn parse_line(line: &str) -> StreamingResult<Option<Message>> {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            return Ok(None);
-        }
-
-        let msg: DiscordRawMessage = serde_json::from_str(trimmed)?;
+    fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
 
-        if msg.content.trim().is_empty() {
-            return Ok(None);
-        }
+    fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
 
-        let sender = msg.author.nickname.unwrap_or(msg.author.name);
+    fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.sender_allowlist = senders;
+        self
+    }
 
-        let timestamp = DateTime::parse_from_rfc3339(&msg.timestamp)
-            .ok()
-            .map(|dt| dt.to_utc());
+    fn with_include_system_events(mut self, include: bool) -> Self {
+        self.include_system_events = include;
+        self
+    }
 
-        let edited = msg
-            .timestamp_edited
-            .as_ref()
-            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
-            .map(|dt| dt.to_utc());
+    fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
 
-        let id = msg.id.parse::<u64>().ok();
+    /// Returns whether `e` comes from a last line cut off by the end of the
+    /// file, which `tolerate_truncation` turns into the end of the stream.
+    fn is_cut_off(&self, e: &serde_json::Error) -> bool {
+        // Only the last line can lack its newline
+        self.config.tolerate_truncation
+            && self.decoded
+            && e.is_eof()
+            && !self.line_buffer.ends_with('\n')
+    }
 
-        Ok(Some(Message::with_metadata(
-            sender,
-            msg.content,
-            timestamp,
-            id,
-            None,
-            edited,
-        )))
+    /// Parses a line using the shared logic, so attachments, stickers and
+    /// replies match [`DiscordParser`](crate::parsers::DiscordParser).
+    fn parse_line(
+        line: &str,
+        naming: SenderNaming,
+        senders: Option<&[String]>,
+        include_events: bool,
+        projection: Projection,
+    ) -> StreamingResult<Option<Message>> {
+        Ok(parse_discord_jsonl_line_named(
+            line,
+            naming,
+            senders,
+            include_events,
+            projection,
+        )?)
     }
 }
 
@@ -152,6 +309,14 @@ impl<R: BufRead + Send> MessageIterator for DiscordJsonlIterator<R> {
     fn total_bytes(&self) -> Option<u64> {
         Some(self.file_size)
     }
+
+    fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 impl<R: BufRead + Send> Iterator for DiscordJsonlIterator<R> {
@@ -164,10 +329,32 @@ impl<R: BufRead + Send> Iterator for DiscordJsonlIterator<R> {
                 Ok(0) => return None, // EOF
                 Ok(n) => {
                     self.bytes_read += n as u64;
-                    match Self::parse_line(&self.line_buffer) {
-                        Ok(Some(msg)) => return Some(Ok(msg)),
-                        Ok(None) => {}
-                        Err(_) if self.config.skip_invalid => {}
+                    match Self::parse_line(
+                        &self.line_buffer,
+                        self.naming,
+                        self.sender_allowlist.as_deref(),
+                        self.include_system_events,
+                        self.config.projection,
+                    ) {
+                        Ok(Some(mut msg)) => {
+                            self.decoded = true;
+                            let check = self.clamp_timestamps.as_ref();
+                            if !keep_message(&mut msg, check, &mut self.skipped) {
+                                continue;
+                            }
+                            if self.capture_raw {
+                                msg.raw = Some(self.line_buffer.trim().to_string());
+                            }
+                            self.config.projection.apply(&mut msg);
+                            normalize_content_in_place(&mut msg.content, self.content);
+                            return Some(Ok(self.indexer.stamp(msg)));
+                        }
+                        Ok(None) => self.decoded |= !self.line_buffer.trim().is_empty(),
+                        Err(StreamingError::Json(e)) if self.is_cut_off(&e) => {
+                            self.skipped += 1; // The cut-off record
+                            return None;
+                        }
+                        Err(_) if self.config.skip_invalid => self.skipped += 1,
                         Err(e) => return Some(Err(e)),
                     }
                 }
@@ -183,13 +370,27 @@ pub struct DiscordJsonIterator<R: BufRead + Seek> {
     file_size: u64,
     bytes_read: u64,
     config: StreamingConfig,
+    naming: SenderNaming,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    sender_allowlist: Option<Vec<String>>,
+    include_system_events: bool,
+    content: ContentPolicy,
+    skipped: usize,
+    indexer: SourceIndexer,
     buffer: String,
     finished: bool,
     brace_depth: i32,
+    objects_read: usize,
 }
 
 impl<R: BufRead + Seek> DiscordJsonIterator<R> {
-    fn new(mut reader: R, file_size: u64, config: StreamingConfig) -> StreamingResult<Self> {
+    fn new(
+        mut reader: R,
+        file_size: u64,
+        config: StreamingConfig,
+        naming: SenderNaming,
+    ) -> StreamingResult<Self> {
         let mut buffer = String::with_capacity(config.buffer_size);
         let mut total_read = 0u64;
 
@@ -220,12 +421,46 @@ impl<R: BufRead + Seek> DiscordJsonIterator<R> {
             file_size,
             bytes_read: total_read,
             config,
+            naming,
             buffer: String::with_capacity(config.max_message_size),
             finished: false,
             brace_depth: 0,
+            objects_read: 0,
+            clamp_timestamps: None,
+            capture_raw: false,
+            sender_allowlist: None,
+            include_system_events: false,
+            content: ContentPolicy::default(),
+            skipped: 0,
+            indexer: SourceIndexer::new(Platform::Discord),
         })
     }
 
+    fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
+
+    fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.sender_allowlist = senders;
+        self
+    }
+
+    fn with_include_system_events(mut self, include: bool) -> Self {
+        self.include_system_events = include;
+        self
+    }
+
+    fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
+
     fn read_next_object(&mut self) -> StreamingResult<Option<String>> {
         self.buffer.clear();
         self.brace_depth = 0;
@@ -236,8 +471,9 @@ impl<R: BufRead + Seek> DiscordJsonIterator<R> {
             let bytes = self.reader.read_line(&mut line)?;
 
             if bytes == 0 {
+                // The file ended before the array was closed
                 self.finished = true;
-                return Ok(None);
+                return Err(StreamingError::UnexpectedEof);
             }
 
             self.bytes_read += bytes as u64;
@@ -276,44 +512,23 @@ impl<R: BufRead + Seek> DiscordJsonIterator<R> {
                 }
 
                 if self.brace_depth == 0 {
+                    self.objects_read += 1;
                     return Ok(Some(self.buffer.trim().trim_end_matches(',').to_string()));
                 }
             }
         }
     }
 
-    fn parse_message(json_str: &str) -> StreamingResult<Option<Message>> {
-        let msg: DiscordRawMessage = serde_json::from_str(json_str)?;
-
-        let content = msg.content;
-
-        // Skip empty content without attachments
-        if content.trim().is_empty() {
-            return Ok(None);
-        }
-
-        let sender = msg.author.nickname.unwrap_or(msg.author.name);
-
-        let timestamp = DateTime::parse_from_rfc3339(&msg.timestamp)
-            .ok()
-            .map(|dt| dt.to_utc());
-
-        let edited = msg
-            .timestamp_edited
-            .as_ref()
-            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
-            .map(|dt| dt.to_utc());
-
-        let id = msg.id.parse::<u64>().ok();
-
-        let reply_to = msg
-            .reference
-            .and_then(|r| r.message_id)
-            .and_then(|id| id.parse::<u64>().ok());
-
-        Ok(Some(Message::with_metadata(
-            sender, content, timestamp, id, reply_to, edited,
-        )))
+    /// Parses a record using the shared logic, so attachments, stickers
+    /// and events match [`DiscordParser`](crate::parsers::DiscordParser).
+    fn parse_message(&self, json_str: &str) -> StreamingResult<Option<Message>> {
+        Ok(parse_discord_jsonl_line_named(
+            json_str,
+            self.naming,
+            self.sender_allowlist.as_deref(),
+            self.include_system_events,
+            self.config.projection,
+        )?)
     }
 }
 
@@ -332,6 +547,14 @@ impl<R: BufRead + Seek + Send> MessageIterator for DiscordJsonIterator<R> {
     fn total_bytes(&self) -> Option<u64> {
         Some(self.file_size)
     }
+
+    fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 impl<R: BufRead + Seek + Send> Iterator for DiscordJsonIterator<R> {
@@ -344,45 +567,37 @@ impl<R: BufRead + Seek + Send> Iterator for DiscordJsonIterator<R> {
 
         loop {
             match self.read_next_object() {
-                Ok(Some(json_str)) => match Self::parse_message(&json_str) {
-                    Ok(Some(msg)) => return Some(Ok(msg)),
+                Ok(Some(json_str)) => match self.parse_message(&json_str) {
+                    Ok(Some(mut msg)) => {
+                        let check = self.clamp_timestamps.as_ref();
+                        if !keep_message(&mut msg, check, &mut self.skipped) {
+                            continue;
+                        }
+                        if self.capture_raw {
+                            msg.raw = Some(json_str);
+                        }
+                        self.config.projection.apply(&mut msg);
+                        normalize_content_in_place(&mut msg.content, self.content);
+                        return Some(Ok(self.indexer.stamp(msg)));
+                    }
                     Ok(None) => {}
-                    Err(_) if self.config.skip_invalid => {}
+                    Err(_) if self.config.skip_invalid => self.skipped += 1,
                     Err(e) => return Some(Err(e)),
                 },
                 Ok(None) => return None,
-                Err(_) if self.config.skip_invalid => {}
+                Err(StreamingError::UnexpectedEof)
+                    if self.config.tolerate_truncation && self.objects_read > 0 =>
+                {
+                    self.skipped += 1; // The cut-off record
+                    return None;
+                }
+                Err(_) if self.config.skip_invalid => self.skipped += 1,
                 Err(e) => return Some(Err(e)),
             }
         }
     }
 }
 
-/// Raw Discord message for deserialization.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DiscordRawMessage {
-    id: String,
-    timestamp: String,
-    timestamp_edited: Option<String>,
-    content: String,
-    author: DiscordAuthor,
-    reference: Option<DiscordReference>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DiscordAuthor {
-    name: String,
-    nickname: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DiscordReference {
-    message_id: Option<String>,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -469,7 +684,8 @@ mod tests {
 
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let config = StreamingConfig::default();
-        let mut iter = DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config);
+        let mut iter =
+            DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config, SenderNaming::default());
 
         let msg1 = iter.next().expect("should have message").expect("parse ok");
         assert_eq!(msg1.sender, "Alice");
@@ -488,7 +704,8 @@ mod tests {
 
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let config = StreamingConfig::default();
-        let mut iter = DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config);
+        let mut iter =
+            DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config, SenderNaming::default());
 
         let msg = iter.next().expect("should have message").expect("parse ok");
         assert_eq!(msg.sender, "Alice"); // Should prefer nickname
@@ -502,7 +719,8 @@ mod tests {
 
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let config = StreamingConfig::default();
-        let iter = DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config);
+        let iter =
+            DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config, SenderNaming::default());
 
         let messages: Vec<_> = iter.filter_map(|r| r.ok()).collect();
         assert_eq!(messages.len(), 2);
@@ -518,7 +736,8 @@ mod tests {
 
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let config = StreamingConfig::default();
-        let iter = DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config);
+        let iter =
+            DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config, SenderNaming::default());
 
         let messages: Vec<_> = iter.filter_map(|r| r.ok()).collect();
         assert_eq!(messages.len(), 2);
@@ -530,7 +749,8 @@ mod tests {
 
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let config = StreamingConfig::default();
-        let mut iter = DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config);
+        let mut iter =
+            DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config, SenderNaming::default());
 
         let msg = iter.next().expect("should have message").expect("parse ok");
         assert!(msg.edited.is_some());
@@ -543,7 +763,7 @@ mod tests {
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let file_size = jsonl.len() as u64;
         let config = StreamingConfig::default();
-        let iter = DiscordJsonlIterator::new(cursor, file_size, config);
+        let iter = DiscordJsonlIterator::new(cursor, file_size, config, SenderNaming::default());
 
         assert_eq!(iter.total_bytes(), Some(file_size));
         assert_eq!(iter.bytes_processed(), 0);
@@ -556,7 +776,7 @@ mod tests {
         let jsonl = "";
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let config = StreamingConfig::default();
-        let iter = DiscordJsonlIterator::new(cursor, 0, config);
+        let iter = DiscordJsonlIterator::new(cursor, 0, config, SenderNaming::default());
 
         assert!(iter.progress().is_none());
     }
@@ -569,10 +789,12 @@ invalid json line
 
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let config = StreamingConfig::new().with_skip_invalid(true);
-        let iter = DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config);
+        let mut iter =
+            DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config, SenderNaming::default());
 
-        let messages: Vec<_> = iter.filter_map(|r| r.ok()).collect();
+        let messages: Vec<_> = iter.by_ref().filter_map(|r| r.ok()).collect();
         assert_eq!(messages.len(), 2);
+        assert_eq!(iter.skipped(), 1);
     }
 
     #[test]
@@ -582,7 +804,8 @@ invalid json line"#;
 
         let cursor = Cursor::new(jsonl.as_bytes().to_vec());
         let config = StreamingConfig::new().with_skip_invalid(false);
-        let mut iter = DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config);
+        let mut iter =
+            DiscordJsonlIterator::new(cursor, jsonl.len() as u64, config, SenderNaming::default());
 
         let _ = iter.next(); // First message OK
         let result = iter.next();
@@ -604,7 +827,8 @@ invalid json line"#;
         let cursor = Cursor::new(json.as_bytes().to_vec());
         let file_size = json.len() as u64;
         let config = StreamingConfig::default();
-        let iter = DiscordJsonIterator::new(cursor, file_size, config).expect("create iterator");
+        let iter = DiscordJsonIterator::new(cursor, file_size, config, SenderNaming::default())
+            .expect("create iterator");
 
         let messages: Vec<_> = iter.filter_map(|r| r.ok()).collect();
         assert_eq!(messages.len(), 2);
@@ -612,6 +836,29 @@ invalid json line"#;
         assert_eq!(messages[1].sender, "Bob");
     }
 
+    #[test]
+    fn test_json_iterator_truncated() {
+        let json = r#"{"guild":{"id":"123"},"messages":[
+{"id":"1","timestamp":"2024-01-01T00:00:00Z","content":"Hello","author":{"name":"Alice"}},
+{"id":"2","timestamp":"2024-01-01T00:01:00Z","content":"Hi","author":{"name":"Bob"}},
+{"id":"3","timestamp":"2024-01-01T00:02:00Z","content":"Cut"#;
+
+        let stream = |config: StreamingConfig| {
+            let cursor = Cursor::new(json.as_bytes().to_vec());
+            DiscordJsonIterator::new(cursor, json.len() as u64, config, SenderNaming::default())
+                .expect("create iterator")
+        };
+
+        let config = StreamingConfig::default().with_skip_invalid(false);
+        let results: Vec<_> = stream(config).collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[2], Err(StreamingError::UnexpectedEof)));
+
+        let mut iter = stream(config.with_tolerate_truncation(true));
+        assert_eq!(iter.by_ref().map(Result::unwrap).count(), 2);
+        assert_eq!(iter.skipped(), 1);
+    }
+
     #[test]
     fn test_json_iterator_with_reference() {
         let json = r#"{"messages":[
@@ -622,7 +869,8 @@ invalid json line"#;
         let cursor = Cursor::new(json.as_bytes().to_vec());
         let file_size = json.len() as u64;
         let config = StreamingConfig::default();
-        let iter = DiscordJsonIterator::new(cursor, file_size, config).expect("create iterator");
+        let iter = DiscordJsonIterator::new(cursor, file_size, config, SenderNaming::default())
+            .expect("create iterator");
 
         let messages: Vec<_> = iter.filter_map(|r| r.ok()).collect();
         assert_eq!(messages.len(), 2);
@@ -640,7 +888,8 @@ invalid json line"#;
         let cursor = Cursor::new(json.as_bytes().to_vec());
         let file_size = json.len() as u64;
         let config = StreamingConfig::default();
-        let iter = DiscordJsonIterator::new(cursor, file_size, config).expect("create iterator");
+        let iter = DiscordJsonIterator::new(cursor, file_size, config, SenderNaming::default())
+            .expect("create iterator");
 
         let messages: Vec<_> = iter.filter_map(|r| r.ok()).collect();
         assert_eq!(messages.len(), 2);
@@ -653,7 +902,7 @@ invalid json line"#;
         let cursor = Cursor::new(json.as_bytes().to_vec());
         let file_size = json.len() as u64;
         let config = StreamingConfig::default();
-        let result = DiscordJsonIterator::new(cursor, file_size, config);
+        let result = DiscordJsonIterator::new(cursor, file_size, config, SenderNaming::default());
         assert!(result.is_err());
     }
 
@@ -666,7 +915,8 @@ invalid json line"#;
         let cursor = Cursor::new(json.as_bytes().to_vec());
         let file_size = json.len() as u64;
         let config = StreamingConfig::default();
-        let iter = DiscordJsonIterator::new(cursor, file_size, config).expect("create iterator");
+        let iter = DiscordJsonIterator::new(cursor, file_size, config, SenderNaming::default())
+            .expect("create iterator");
 
         assert_eq!(iter.total_bytes(), Some(file_size));
         assert!(iter.bytes_processed() > 0); // Header was read
@@ -678,7 +928,8 @@ invalid json line"#;
 
         let cursor = Cursor::new(json.as_bytes().to_vec());
         let config = StreamingConfig::default();
-        let iter = DiscordJsonIterator::new(cursor, 0, config).expect("create iterator");
+        let iter = DiscordJsonIterator::new(cursor, 0, config, SenderNaming::default())
+            .expect("create iterator");
 
         assert!(iter.progress().is_none());
     }
@@ -690,7 +941,13 @@ invalid json line"#;
     #[test]
     fn test_parse_line_valid() {
         let line = r#"{"id":"1","timestamp":"2024-01-01T00:00:00Z","content":"Hello","author":{"name":"Alice"}}"#;
-        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line(line);
+        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line(
+            line,
+            SenderNaming::default(),
+            None,
+            false,
+            Projection::all(),
+        );
         assert!(result.is_ok());
         let msg = result.unwrap();
         assert!(msg.is_some());
@@ -699,14 +956,26 @@ invalid json line"#;
 
     #[test]
     fn test_parse_line_empty() {
-        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line("");
+        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line(
+            "",
+            SenderNaming::default(),
+            None,
+            false,
+            Projection::all(),
+        );
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
 
     #[test]
     fn test_parse_line_whitespace_only() {
-        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line("   ");
+        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line(
+            "   ",
+            SenderNaming::default(),
+            None,
+            false,
+            Projection::all(),
+        );
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
@@ -714,14 +983,26 @@ invalid json line"#;
     #[test]
     fn test_parse_line_empty_content() {
         let line = r#"{"id":"1","timestamp":"2024-01-01T00:00:00Z","content":"","author":{"name":"Alice"}}"#;
-        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line(line);
+        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line(
+            line,
+            SenderNaming::default(),
+            None,
+            false,
+            Projection::all(),
+        );
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
 
     #[test]
     fn test_parse_line_invalid_json() {
-        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line("not json");
+        let result = DiscordJsonlIterator::<Cursor<Vec<u8>>>::parse_line(
+            "not json",
+            SenderNaming::default(),
+            None,
+            false,
+            Projection::all(),
+        );
         assert!(result.is_err());
     }
 }
@@ -14,7 +14,7 @@ pub enum StreamingError {
     Io(io::Error),
 
     /// JSON parsing error (only available with JSON-using parsers)
-    #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord"))]
+    #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
     Json(serde_json::Error),
 
     /// Invalid file format (missing expected structure)
@@ -31,7 +31,7 @@ impl fmt::Display for StreamingError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             StreamingError::Io(e) => write!(f, "IO error: {e}"),
-            #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord"))]
+            #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
             StreamingError::Json(e) => write!(f, "JSON error: {e}"),
             StreamingError::InvalidFormat(msg) => write!(f, "Invalid format: {msg}"),
             StreamingError::UnexpectedEof => write!(f, "Unexpected end of file"),
@@ -52,7 +52,7 @@ impl Error for StreamingError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             StreamingError::Io(e) => Some(e),
-            #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord"))]
+            #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
             StreamingError::Json(e) => Some(e),
             _ => None,
         }
@@ -65,7 +65,7 @@ impl From<io::Error> for StreamingError {
     }
 }
 
-#[cfg(any(feature = "telegram", feature = "instagram", feature = "discord"))]
+#[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
 impl From<serde_json::Error> for StreamingError {
     fn from(err: serde_json::Error) -> Self {
         StreamingError::Json(err)
@@ -114,7 +114,7 @@ mod tests {
         assert!(msg.contains("too large"));
     }
 
-    #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord"))]
+    #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
     #[test]
     fn test_error_display_json() {
         let json_err = serde_json::from_str::<serde_json::Value>("invalid").unwrap_err();
@@ -133,7 +133,7 @@ mod tests {
         assert!(matches!(streaming_err, StreamingError::Io(_)));
     }
 
-    #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord"))]
+    #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
     #[test]
     fn test_error_from_json() {
         let json_err = serde_json::from_str::<serde_json::Value>("invalid").unwrap_err();
@@ -152,7 +152,7 @@ mod tests {
         assert!(err.source().is_some());
     }
 
-    #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord"))]
+    #[cfg(any(feature = "telegram", feature = "instagram", feature = "discord-json"))]
     #[test]
     fn test_error_source_json() {
         let json_err = serde_json::from_str::<serde_json::Value>("invalid").unwrap_err();
@@ -19,13 +19,21 @@ use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 use crate::Message;
+use crate::config::{ContentPolicy, TimestampCheck};
 use crate::error::ChatpackError;
-use crate::parsing::instagram::{InstagramRawMessage, parse_instagram_message};
+use crate::parser::Platform;
+use crate::parsing::content::normalize_content_in_place;
+use crate::parsing::instagram::{
+    InstagramRawMessage, instagram_sender_allowed, mark_instagram_call,
+    parse_instagram_message_owned,
+};
+use crate::parsing::timestamps::keep_message;
 
-#[cfg(test)]
-use super::StreamingError;
 use super::json_array::JsonArrayObjectReader;
-use super::{MessageIterator, StreamingConfig, StreamingParser, StreamingResult};
+use super::traits::SourceIndexer;
+use super::{
+    MessageIterator, Projection, StreamingConfig, StreamingError, StreamingParser, StreamingResult,
+};
 
 /// Streaming parser for Instagram JSON exports.
 ///
@@ -52,19 +60,74 @@ use super::{MessageIterator, StreamingConfig, StreamingParser, StreamingResult};
 /// ```
 pub struct InstagramStreamingParser {
     config: StreamingConfig,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    include_calls: bool,
+    sender_allowlist: Option<Vec<String>>,
+    content: ContentPolicy,
 }
 
 impl InstagramStreamingParser {
     /// Creates a new streaming parser with default configuration.
     pub fn new() -> Self {
-        Self {
-            config: StreamingConfig::default(),
-        }
+        Self::with_config(StreamingConfig::default())
     }
 
     /// Creates a new streaming parser with custom configuration.
     pub fn with_config(config: StreamingConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            clamp_timestamps: None,
+            capture_raw: false,
+            include_calls: false,
+            sender_allowlist: None,
+            content: ContentPolicy::default(),
+        }
+    }
+
+    /// Sets the clock sanity check applied to message timestamps.
+    ///
+    /// See [`InstagramConfig::clamp_timestamps`](crate::config::InstagramConfig::clamp_timestamps).
+    #[must_use]
+    pub fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
+
+    /// Keeps each message's source record in [`Message::raw`].
+    ///
+    /// See [`InstagramConfig::capture_raw`](crate::config::InstagramConfig::capture_raw).
+    #[must_use]
+    pub fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    /// Represents calls and audio messages with placeholder content.
+    ///
+    /// See [`InstagramConfig::include_calls`](crate::config::InstagramConfig::include_calls).
+    #[must_use]
+    pub fn with_include_calls(mut self, include: bool) -> Self {
+        self.include_calls = include;
+        self
+    }
+
+    /// Keeps only messages from the given senders, ignoring ASCII case.
+    ///
+    /// See [`InstagramConfig::sender_allowlist`](crate::config::InstagramConfig::sender_allowlist).
+    #[must_use]
+    pub fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.sender_allowlist = senders;
+        self
+    }
+
+    /// Sets the whitespace rules applied to message content.
+    ///
+    /// See [`InstagramConfig::content`](crate::config::InstagramConfig::content).
+    #[must_use]
+    pub fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
     }
 }
 
@@ -85,7 +148,12 @@ impl StreamingParser for InstagramStreamingParser {
         let file_size = file.metadata()?.len();
 
         let reader = BufReader::with_capacity(self.config.buffer_size, file);
-        let iterator = InstagramMessageIterator::new(reader, file_size, self.config)?;
+        let iterator = InstagramMessageIterator::new(reader, file_size, self.config)?
+            .with_clamp_timestamps(self.clamp_timestamps.clone())
+            .with_capture_raw(self.capture_raw)
+            .with_include_calls(self.include_calls)
+            .with_sender_allowlist(self.sender_allowlist.clone())
+            .with_content_policy(self.content);
 
         Ok(Box::new(iterator))
     }
@@ -93,6 +161,10 @@ impl StreamingParser for InstagramStreamingParser {
     fn recommended_buffer_size(&self) -> usize {
         self.config.buffer_size
     }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 /// Iterator over Instagram messages.
@@ -100,6 +172,13 @@ pub struct InstagramMessageIterator<R: BufRead> {
     objects: JsonArrayObjectReader<R>,
     file_size: u64,
     config: StreamingConfig,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    include_calls: bool,
+    sender_allowlist: Option<Vec<String>>,
+    content: ContentPolicy,
+    skipped: usize,
+    indexer: SourceIndexer,
 }
 
 impl<R: BufRead> InstagramMessageIterator<R> {
@@ -114,14 +193,56 @@ impl<R: BufRead> InstagramMessageIterator<R> {
             )?,
             file_size,
             config,
+            clamp_timestamps: None,
+            capture_raw: false,
+            include_calls: false,
+            sender_allowlist: None,
+            content: ContentPolicy::default(),
+            skipped: 0,
+            indexer: SourceIndexer::new(Platform::Instagram),
         })
     }
 
+    fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
+
+    fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    fn with_include_calls(mut self, include: bool) -> Self {
+        self.include_calls = include;
+        self
+    }
+
+    fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.sender_allowlist = senders;
+        self
+    }
+
+    fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
+
     /// Parses a JSON string into a Message using shared parsing logic.
-    fn parse_message_from_json(json_str: &str) -> StreamingResult<Option<Message>> {
-        let msg: InstagramRawMessage = serde_json::from_str(json_str)?;
+    fn parse_message_from_json(
+        json_str: &str,
+        include_calls: bool,
+        senders: Option<&[String]>,
+    ) -> StreamingResult<Option<Message>> {
+        let mut msg: InstagramRawMessage = serde_json::from_str(json_str)?;
+        if !instagram_sender_allowed(&msg, true, senders) {
+            return Ok(None);
+        }
+        if include_calls {
+            mark_instagram_call(&mut msg);
+        }
         // Streaming always fixes encoding
-        Ok(parse_instagram_message(&msg, true))
+        Ok(parse_instagram_message_owned(msg, true))
     }
 }
 
@@ -140,6 +261,14 @@ impl<R: BufRead + Send> MessageIterator for InstagramMessageIterator<R> {
     fn total_bytes(&self) -> Option<u64> {
         Some(self.file_size)
     }
+
+    fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 impl<R: BufRead + Send> Iterator for InstagramMessageIterator<R> {
@@ -149,15 +278,36 @@ impl<R: BufRead + Send> Iterator for InstagramMessageIterator<R> {
         loop {
             match self.objects.next_object() {
                 Ok(Some(json_str)) => {
-                    match Self::parse_message_from_json(&json_str) {
-                        Ok(Some(msg)) => return Some(Ok(msg)),
+                    match Self::parse_message_from_json(
+                        &json_str,
+                        self.include_calls,
+                        self.sender_allowlist.as_deref(),
+                    ) {
+                        Ok(Some(mut msg)) => {
+                            let check = self.clamp_timestamps.as_ref();
+                            if !keep_message(&mut msg, check, &mut self.skipped) {
+                                continue;
+                            }
+                            if self.capture_raw {
+                                msg.raw = Some(json_str);
+                            }
+                            self.config.projection.apply(&mut msg);
+                            normalize_content_in_place(&mut msg.content, self.content);
+                            return Some(Ok(self.indexer.stamp(msg)));
+                        }
                         Ok(None) => {} // Skip messages without content, try next
-                        Err(_) if self.config.skip_invalid => {} // Skip invalid
+                        Err(_) if self.config.skip_invalid => self.skipped += 1,
                         Err(e) => return Some(Err(e)),
                     }
                 }
                 Ok(None) => return None, // End of array
-                Err(_) if self.config.skip_invalid => {}
+                Err(StreamingError::UnexpectedEof)
+                    if self.config.tolerate_truncation && self.objects.objects_read() > 0 =>
+                {
+                    self.skipped += 1; // The cut-off record
+                    return None;
+                }
+                Err(_) if self.config.skip_invalid => self.skipped += 1,
                 Err(e) => return Some(Err(e)),
             }
         }
@@ -4,6 +4,7 @@ use std::collections::VecDeque;
 use std::io::BufRead;
 
 use super::{StreamingError, StreamingResult};
+use crate::parsing::json_scan::update_json_object_state;
 
 const HEADER_SEARCH_LIMIT: u64 = 10 * 1024 * 1024;
 
@@ -13,6 +14,7 @@ pub(crate) struct JsonArrayObjectReader<R: BufRead> {
     pending: VecDeque<char>,
     buffer: String,
     max_object_size: usize,
+    objects_read: usize,
     finished: bool,
 }
 
@@ -29,6 +31,7 @@ impl<R: BufRead> JsonArrayObjectReader<R> {
             pending: VecDeque::new(),
             buffer: String::with_capacity(max_object_size.min(buffer_size)),
             max_object_size,
+            objects_read: 0,
             finished: false,
         };
 
@@ -49,12 +52,9 @@ impl<R: BufRead> JsonArrayObjectReader<R> {
 
         loop {
             let Some(ch) = self.next_char()? else {
+                // The file ended before the array was closed
                 self.finished = true;
-                return if found_start {
-                    Err(StreamingError::UnexpectedEof)
-                } else {
-                    Ok(None)
-                };
+                return Err(StreamingError::UnexpectedEof);
             };
 
             if found_start {
@@ -69,6 +69,7 @@ impl<R: BufRead> JsonArrayObjectReader<R> {
                 }
 
                 if brace_depth == 0 {
+                    self.objects_read += 1;
                     return Ok(Some(self.buffer.clone()));
                 }
             } else {
@@ -97,9 +98,17 @@ impl<R: BufRead> JsonArrayObjectReader<R> {
         self.bytes_read
     }
 
+    /// Returns the number of complete objects read so far.
+    pub(crate) fn objects_read(&self) -> usize {
+        self.objects_read
+    }
+
+    /// Reads up to the opening `[` of the `array_name` array of the
+    /// top-level object, ignoring the same key in strings and nested objects.
     fn seek_to_array(&mut self, array_name: &str, buffer_size: usize) -> StreamingResult<()> {
         let key = format!("\"{array_name}\"");
         let mut found_key = false;
+        let (mut in_string, mut escaped, mut depth) = (false, false, 0);
         let mut line = String::with_capacity(buffer_size);
 
         loop {
@@ -114,9 +123,11 @@ impl<R: BufRead> JsonArrayObjectReader<R> {
             self.bytes_read += bytes as u64;
             let search_start = if found_key {
                 0
-            } else if let Some(key_pos) = line.find(&key) {
+            } else if let Some(key_end) =
+                find_key(&line, &key, &mut in_string, &mut escaped, &mut depth)
+            {
                 found_key = true;
-                key_pos + key.len()
+                key_end
             } else {
                 self.check_header_limit(array_name)?;
                 continue;
@@ -159,26 +170,49 @@ impl<R: BufRead> JsonArrayObjectReader<R> {
     }
 }
 
-fn update_json_object_state(
-    ch: char,
+/// Returns the offset just past `key` in `line` if it is a key of the
+/// top-level object, carrying the scanner state over to the next line.
+fn find_key(
+    line: &str,
+    key: &str,
     in_string: &mut bool,
     escaped: &mut bool,
-    brace_depth: &mut i32,
-) {
-    if *in_string {
-        if *escaped {
-            *escaped = false;
-        } else if ch == '\\' {
-            *escaped = true;
-        } else if ch == '"' {
-            *in_string = false;
-        }
-    } else {
-        match ch {
-            '"' => *in_string = true,
-            '{' => *brace_depth += 1,
-            '}' => *brace_depth -= 1,
-            _ => {}
+    depth: &mut i32,
+) -> Option<usize> {
+    for (offset, ch) in line.char_indices() {
+        if ch == '"' && !*in_string && *depth == 1 && line[offset..].starts_with(key) {
+            let key_end = offset + key.len();
+            if line[key_end..].trim_start().starts_with(':') {
+                return Some(key_end);
+            }
         }
+        update_json_object_state(ch, in_string, escaped, depth);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(json: &str) -> Vec<String> {
+        let mut reader = JsonArrayObjectReader::new(json.as_bytes(), "messages", 64, 1024).unwrap();
+        std::iter::from_fn(|| reader.next_object().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_seek_to_top_level_array() {
+        let json = r#"{
+  "kind": "messages",
+  "chat": {"name": "\"messages\": [", "messages": [{"id": 99}]},
+  "messages": [{"id": 1}, {"id": 2}]
+}"#;
+        assert_eq!(ids(json), [r#"{"id": 1}"#, r#"{"id": 2}"#]);
+    }
+
+    #[test]
+    fn test_seek_without_top_level_array() {
+        let json = r#"{"chat": {"messages": [{"id": 99}]}}"#;
+        assert!(JsonArrayObjectReader::new(json.as_bytes(), "messages", 64, 1024).is_err());
     }
 }
@@ -25,6 +25,7 @@
 //! | [`StreamingParser`] | Trait for parsers that produce message iterators |
 //! | [`MessageIterator`] | Iterator over messages with progress tracking |
 //! | [`StreamingConfig`] | Configuration for buffer sizes and behavior |
+//! | [`MultiFileIterator`] | Several files as one iterator, with overall progress |
 //!
 //! # Examples
 //!
@@ -83,24 +84,29 @@
 //! | [`InstagramStreamingParser`] | `instagram` | JSON |
 //! | [`DiscordStreamingParser`] | `discord` | JSON/JSONL/CSV |
 
-#[cfg(feature = "discord")]
+#[cfg(feature = "discord-json")]
 mod discord;
 mod error;
 #[cfg(feature = "instagram")]
 mod instagram;
 #[cfg(any(feature = "telegram", feature = "instagram"))]
 mod json_array;
+mod multi;
+#[cfg(any(feature = "whatsapp", feature = "discord-json"))]
+mod tail;
 #[cfg(feature = "telegram")]
 mod telegram;
 mod traits;
 #[cfg(feature = "whatsapp")]
 mod whatsapp;
 
-#[cfg(feature = "discord")]
+pub use crate::config::Projection;
+#[cfg(feature = "discord-json")]
 pub use discord::DiscordStreamingParser;
 pub use error::{StreamingError, StreamingResult};
 #[cfg(feature = "instagram")]
 pub use instagram::InstagramStreamingParser;
+pub use multi::MultiFileIterator;
 #[cfg(feature = "telegram")]
 pub use telegram::TelegramStreamingParser;
 pub use traits::{MessageIterator, StreamingConfig, StreamingParser};
@@ -133,17 +139,21 @@ use crate::parser::Platform;
 ///
 /// # Panics
 ///
-/// Panics if the corresponding parser feature is not enabled.
+/// Panics if the corresponding parser feature is not enabled, or for
+/// [`Platform::Custom`].
 pub fn create_streaming_parser(platform: Platform) -> Box<dyn StreamingParser> {
     match platform {
         #[cfg(feature = "telegram")]
         Platform::Telegram => Box::new(TelegramStreamingParser::new()),
-        #[cfg(feature = "discord")]
+        #[cfg(feature = "discord-json")]
         Platform::Discord => Box::new(DiscordStreamingParser::new()),
         #[cfg(feature = "instagram")]
         Platform::Instagram => Box::new(InstagramStreamingParser::new()),
         #[cfg(feature = "whatsapp")]
         Platform::WhatsApp => Box::new(WhatsAppStreamingParser::new()),
+        Platform::Custom(name) => panic!(
+            "Custom platform '{name}' has no StreamingParser. Stream it with parser::create_streaming_parser instead."
+        ),
         // Fallback for when features are disabled
         #[allow(unreachable_patterns)]
         _ => panic!(
@@ -164,7 +174,7 @@ mod tests {
         assert_eq!(parser.name(), "Telegram (Streaming)");
     }
 
-    #[cfg(feature = "discord")]
+    #[cfg(feature = "discord-json")]
     #[test]
     fn test_create_streaming_parser_discord() {
         let parser = create_streaming_parser(Platform::Discord);
@@ -0,0 +1,311 @@
+//! Streaming several files as one iterator.
+
+use std::path::PathBuf;
+
+use crate::Message;
+use crate::error::ChatpackError;
+use crate::progress::MultiFileProgress;
+
+use super::{MessageIterator, Projection, StreamingError, StreamingParser, StreamingResult};
+
+/// Streams several files one after another, with progress across all of
+/// them.
+///
+/// File sizes are taken when the iterator is created, and
+/// [`progress`](MessageIterator::progress) weighs each file by its size,
+/// so three files of 10, 30, and 60 KB report 10% once the first is done.
+/// Each file is opened only when the previous one is exhausted.
+///
+/// Every message gets its file's position in `paths` as
+/// [`source_file`](Message::source_file). A file that cannot be opened is
+/// yielded as one error and counted as done; iteration continues with the
+/// next file.
+///
+/// For push-based updates, wrap the iterator in a
+/// [`ParseIterator`](crate::parser::ParseIterator) and attach a
+/// [`ProgressCallback`](crate::progress::ProgressCallback).
+///
+/// # Example
+///
+/// ```no_run
+/// # #[cfg(feature = "whatsapp")]
+/// # fn main() -> chatpack::Result<()> {
+/// use chatpack::streaming::{MessageIterator, MultiFileIterator, WhatsAppStreamingParser};
+///
+/// let paths = ["family.txt", "work.txt", "friends.txt"];
+/// let mut iter = MultiFileIterator::new(Box::new(WhatsAppStreamingParser::new()), paths)?;
+///
+/// while let Some(result) = iter.next() {
+///     let msg = result?;
+///     eprintln!("{:.1}% (file {:?})", iter.progress().unwrap_or(0.0), msg.source_file);
+/// }
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "whatsapp"))]
+/// # fn main() {}
+/// ```
+pub struct MultiFileIterator {
+    parser: Box<dyn StreamingParser>,
+    paths: Vec<PathBuf>,
+    tracker: MultiFileProgress,
+    file: usize,
+    current: Option<Box<dyn MessageIterator>>,
+    skipped: usize,
+}
+
+impl MultiFileIterator {
+    /// Creates an iterator over the messages of every file in `paths`, in
+    /// order, read with `parser`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::Io`] if the size of any file cannot be read.
+    pub fn new<P: Into<PathBuf>>(
+        parser: Box<dyn StreamingParser>,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<Self, ChatpackError> {
+        let paths: Vec<PathBuf> = paths.into_iter().map(Into::into).collect();
+        let tracker = MultiFileProgress::from_paths(&paths)?;
+        Ok(Self {
+            parser,
+            paths,
+            tracker,
+            file: 0,
+            current: None,
+            skipped: 0,
+        })
+    }
+
+    /// Returns the progress tracker across all files.
+    pub fn tracker(&self) -> &MultiFileProgress {
+        &self.tracker
+    }
+
+    /// Returns the index of the file being read, or `None` once every file
+    /// is done.
+    pub fn current_file(&self) -> Option<usize> {
+        (self.file < self.paths.len()).then_some(self.file)
+    }
+
+    /// Moves on to the next file, counting the current one as done.
+    fn advance(&mut self) {
+        if let Some(iter) = self.current.take() {
+            self.skipped += iter.skipped();
+        }
+        self.tracker.finish_file(self.file);
+        self.file += 1;
+    }
+}
+
+impl Iterator for MultiFileIterator {
+    type Item = StreamingResult<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.file < self.paths.len() {
+            let Some(iter) = self.current.as_mut() else {
+                let path = &self.paths[self.file];
+                match self.parser.stream(&path.to_string_lossy()) {
+                    Ok(iter) => self.current = Some(iter),
+                    Err(e) => {
+                        let error = match e {
+                            ChatpackError::Io(e) => StreamingError::Io(e),
+                            e => StreamingError::InvalidFormat(format!("{}: {e}", path.display())),
+                        };
+                        self.advance();
+                        return Some(Err(error));
+                    }
+                }
+                continue;
+            };
+
+            match iter.next() {
+                Some(item) => {
+                    self.tracker.update(self.file, iter.bytes_processed());
+                    return Some(item.map(|mut msg| {
+                        msg.source_file = Some(self.file);
+                        msg
+                    }));
+                }
+                None => self.advance(),
+            }
+        }
+        None
+    }
+}
+
+impl MessageIterator for MultiFileIterator {
+    fn progress(&self) -> Option<f64> {
+        Some(self.tracker.percentage())
+    }
+
+    fn bytes_processed(&self) -> u64 {
+        self.tracker.bytes_processed()
+    }
+
+    fn total_bytes(&self) -> Option<u64> {
+        Some(self.tracker.total_bytes())
+    }
+
+    fn skipped(&self) -> usize {
+        self.skipped + self.current.as_ref().map_or(0, |iter| iter.skipped())
+    }
+
+    fn projection(&self) -> Projection {
+        self.current
+            .as_ref()
+            .map_or_else(|| self.parser.projection(), |iter| iter.projection())
+    }
+}
+
+#[cfg(all(test, feature = "whatsapp"))]
+mod tests {
+    use super::*;
+    use crate::parser::ParseIterator;
+    use crate::progress::Progress;
+    use crate::streaming::{StreamingConfig, WhatsAppStreamingParser};
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    /// Writes a WhatsApp export of about `len` bytes to `dir/name`.
+    fn export(dir: &Path, name: &str, len: usize) -> PathBuf {
+        let line = "[1/15/24, 10:30:00 AM] Alice: hello there\n";
+        let path = dir.join(name);
+        std::fs::write(&path, line.repeat(len / line.len())).unwrap();
+        path
+    }
+
+    fn iter(paths: &[PathBuf]) -> MultiFileIterator {
+        MultiFileIterator::new(Box::new(WhatsAppStreamingParser::new()), paths.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_progress_across_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [
+            export(dir.path(), "a.txt", 10_000),
+            export(dir.path(), "b.txt", 30_000),
+            export(dir.path(), "c.txt", 60_000),
+        ];
+        let mut iter = iter(&paths);
+        assert_eq!(iter.progress(), Some(0.0));
+
+        // Progress seen on the first message of each later file
+        let mut at_boundary = Vec::new();
+        let mut last_file = 0;
+        let mut last = 0.0;
+        while let Some(result) = iter.next() {
+            let file = result.unwrap().source_file.unwrap();
+            let progress = iter.progress().unwrap();
+            assert!(progress >= last && progress <= 100.0);
+            if file != last_file {
+                at_boundary.push(last);
+                last_file = file;
+            }
+            last = progress;
+        }
+
+        assert_eq!(at_boundary.len(), 2);
+        assert!((at_boundary[0] - 10.0).abs() < 1.0, "{at_boundary:?}");
+        assert!((at_boundary[1] - 40.0).abs() < 1.0, "{at_boundary:?}");
+        assert_eq!(iter.progress(), Some(100.0));
+        assert_eq!(iter.current_file(), None);
+    }
+
+    #[test]
+    fn test_sets_source_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [
+            export(dir.path(), "a.txt", 100),
+            export(dir.path(), "b.txt", 100),
+        ];
+        let files: Vec<_> = iter(&paths)
+            .map(|m| m.unwrap().source_file.unwrap())
+            .collect();
+
+        assert_eq!(files, [0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_grown_file_stays_under_100() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [
+            export(dir.path(), "a.txt", 1_000),
+            export(dir.path(), "b.txt", 1_000),
+        ];
+        let mut iter = iter(&paths);
+        export(dir.path(), "a.txt", 5_000);
+
+        while let Some(result) = iter.next() {
+            result.unwrap();
+            assert!(iter.progress().unwrap() <= 100.0);
+            assert!(iter.bytes_processed() <= iter.total_bytes().unwrap());
+        }
+        assert_eq!(iter.progress(), Some(100.0));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.txt");
+
+        let result = MultiFileIterator::new(Box::new(WhatsAppStreamingParser::new()), [&missing]);
+        assert!(result.is_err_and(|e| e.is_io()));
+    }
+
+    #[test]
+    fn test_file_removed_after_discovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [
+            export(dir.path(), "a.txt", 100),
+            export(dir.path(), "b.txt", 100),
+        ];
+        let mut iter = iter(&paths);
+        std::fs::remove_file(&paths[0]).unwrap();
+
+        assert!(iter.next().unwrap().is_err());
+        assert_eq!(iter.progress(), Some(50.0));
+        assert_eq!(iter.flatten().count(), 2);
+    }
+
+    #[test]
+    fn test_forwards_projection() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [export(dir.path(), "a.txt", 100)];
+        let config = StreamingConfig::new().with_projection(Projection::minimal());
+        let mut iter = MultiFileIterator::new(
+            Box::new(WhatsAppStreamingParser::with_config(config)),
+            paths.to_vec(),
+        )
+        .unwrap();
+
+        assert_eq!(iter.projection(), Projection::minimal());
+        let msg = iter.next().unwrap().unwrap();
+        assert!(msg.content.is_empty());
+        assert_eq!(iter.projection(), Projection::minimal());
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = [
+            export(dir.path(), "a.txt", 10_000),
+            export(dir.path(), "b.txt", 30_000),
+            export(dir.path(), "c.txt", 60_000),
+        ];
+        let seen: Arc<Mutex<Vec<Progress>>> = Arc::default();
+        let sink = Arc::clone(&seen);
+
+        let count = ParseIterator::new(Box::new(iter(&paths)))
+            .with_progress(100, Arc::new(move |p| sink.lock().unwrap().push(p)))
+            .count();
+
+        let seen = seen.lock().unwrap();
+        let last = seen.last().unwrap();
+        assert_eq!(last.items_processed, count);
+        assert_eq!(last.percentage(), Some(100.0));
+        assert!(
+            seen.windows(2)
+                .all(|w| w[0].bytes_processed <= w[1].bytes_processed)
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Reading the last messages of line-oriented exports.
+//!
+//! WhatsApp TXT and Discord JSONL exports start every message on a new line,
+//! so [`StreamingParser::stream_tail`](super::StreamingParser::stream_tail)
+//! can read them backwards in blocks to find where the last messages start
+//! and parse forward from there, without touching the rest of the file.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::Message;
+use crate::error::ChatpackError;
+
+use super::MessageIterator;
+
+/// Size of the blocks read backwards from the end of the file.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Returns the offset of the `n`th-last line for which `is_start` holds.
+///
+/// Reads `reader` backwards in blocks of `block_size` bytes. Lines are
+/// passed to `is_start` without their `\n`. Returns 0 if fewer than `n`
+/// lines match.
+pub(crate) fn find_tail_start<R: Read + Seek>(
+    reader: &mut R,
+    n: usize,
+    block_size: usize,
+    is_start: &mut impl FnMut(&[u8]) -> bool,
+) -> io::Result<u64> {
+    let mut pos = reader.seek(SeekFrom::End(0))?;
+    let mut found = 0;
+    // The earliest line seen so far, whose start is in an unread block
+    let mut partial = Vec::new();
+
+    while pos > 0 {
+        #[allow(clippy::cast_possible_truncation)] // At most block_size
+        let len = pos.min(block_size as u64) as usize;
+        pos -= len as u64;
+        let mut block = vec![0; len];
+        reader.seek(SeekFrom::Start(pos))?;
+        reader.read_exact(&mut block)?;
+        block.extend_from_slice(&partial);
+
+        let mut line_end = block.len();
+        while let Some(newline) = block[..line_end].iter().rposition(|&b| b == b'\n') {
+            if is_start(&block[newline + 1..line_end]) {
+                found += 1;
+                if found == n {
+                    return Ok(pos + newline as u64 + 1);
+                }
+            }
+            line_end = newline;
+        }
+        block.truncate(line_end);
+        partial = block;
+    }
+
+    // The first line starts at 0 whether or not it matched
+    Ok(0)
+}
+
+/// Collects the last `n` messages of `file_path` in chronological order.
+///
+/// Parsing starts at the `n`th-last line accepted by `is_start`, using the
+/// iterator `open` builds from the file positioned there. If the parser
+/// drops some of those lines (system messages, filtered senders, invalid
+/// records), the start moves further back until `n` messages are found or
+/// the whole file has been read.
+///
+/// `source_index` is cleared on the returned messages, and so is
+/// `reply_to` if `replies_by_index` is set, as for WhatsApp, whose replies
+/// refer to `source_index`.
+pub(crate) fn read_tail(
+    file_path: &str,
+    n: usize,
+    replies_by_index: bool,
+    mut is_start: impl FnMut(&[u8]) -> bool,
+    open: impl Fn(File) -> Result<Box<dyn MessageIterator>, ChatpackError>,
+) -> Result<Vec<Message>, ChatpackError> {
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut file = File::open(file_path)?;
+    let mut lines = n;
+    loop {
+        let start = find_tail_start(&mut file, lines, BLOCK_SIZE, &mut is_start)?;
+        let mut reader = file.try_clone()?;
+        reader.seek(SeekFrom::Start(start))?;
+
+        let mut tail = VecDeque::with_capacity(n + 1);
+        for msg in open(reader)? {
+            tail.push_back(msg?);
+            if tail.len() > n {
+                tail.pop_front();
+            }
+        }
+
+        if tail.len() == n || start == 0 {
+            // Positions are relative to `start`, not the whole export
+            return Ok(tail
+                .into_iter()
+                .map(|mut msg| {
+                    msg.source_index = None;
+                    if replies_by_index {
+                        msg.reply_to = None;
+                    }
+                    msg
+                })
+                .collect());
+        }
+        lines = lines.saturating_mul(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn tail_start(text: &str, n: usize, block_size: usize) -> u64 {
+        let mut is_start = |line: &[u8]| line.starts_with(b"#");
+        find_tail_start(
+            &mut Cursor::new(text.as_bytes()),
+            n,
+            block_size,
+            &mut is_start,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_find_tail_start() {
+        let text = "#one\ncontinued\n#two\n#three\nmore\nlines\n";
+        for block_size in [1, 3, 7, 64] {
+            assert_eq!(tail_start(text, 1, block_size), 20, "block {block_size}");
+            assert_eq!(tail_start(text, 2, block_size), 15, "block {block_size}");
+            assert_eq!(tail_start(text, 3, block_size), 0, "block {block_size}");
+            assert_eq!(tail_start(text, 10, block_size), 0, "block {block_size}");
+        }
+    }
+
+    #[test]
+    fn test_find_tail_start_without_final_newline() {
+        let text = "#one\n#two\n#three";
+        for block_size in [2, 5, 64] {
+            assert_eq!(tail_start(text, 1, block_size), 10);
+            assert_eq!(tail_start(text, 2, block_size), 5);
+        }
+        assert_eq!(tail_start("", 1, 4), 0);
+    }
+}
@@ -13,19 +13,30 @@
 //! ```
 //!
 //! This parser streams the messages array without loading the entire file.
+//! Exports split into `result.json`, `result(1).json`, … are read chunk
+//! by chunk.
 
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::Message;
+use crate::config::{ContentPolicy, MentionFormat, TimestampCheck};
 use crate::error::ChatpackError;
-use crate::parsing::telegram::{TelegramRawMessage, parse_telegram_message};
+use crate::parser::Platform;
+use crate::parsing::content::normalize_content_in_place;
+use crate::parsing::telegram::{
+    ChunkOverlap, TelegramRawMessage, TopicTracker, continues_album, parse_album, parse_message,
+    telegram_export_chunks,
+};
+use crate::parsing::timestamps::keep_message;
+use chrono::FixedOffset;
 
-#[cfg(test)]
-use super::StreamingError;
 use super::json_array::JsonArrayObjectReader;
-use super::{MessageIterator, StreamingConfig, StreamingParser, StreamingResult};
+use super::traits::SourceIndexer;
+use super::{
+    MessageIterator, Projection, StreamingConfig, StreamingError, StreamingParser, StreamingResult,
+};
 
 /// Streaming parser for Telegram JSON exports.
 ///
@@ -46,21 +57,147 @@ use super::{MessageIterator, StreamingConfig, StreamingParser, StreamingResult};
 ///     }
 /// }
 /// ```
+#[derive(Clone)]
 pub struct TelegramStreamingParser {
     config: StreamingConfig,
+    assume_timezone: FixedOffset,
+    mention_format: MentionFormat,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    include_calls: bool,
+    sender_allowlist: Option<Vec<String>>,
+    topic_filter: Option<Vec<String>>,
+    merge_albums: bool,
+    content: ContentPolicy,
 }
 
 impl TelegramStreamingParser {
     /// Creates a new streaming parser with default configuration.
     pub fn new() -> Self {
-        Self {
-            config: StreamingConfig::default(),
-        }
+        Self::with_config(StreamingConfig::default())
     }
 
     /// Creates a new streaming parser with custom configuration.
     pub fn with_config(config: StreamingConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            assume_timezone: FixedOffset::east_opt(0).unwrap(),
+            mention_format: MentionFormat::default(),
+            clamp_timestamps: None,
+            capture_raw: false,
+            include_calls: false,
+            sender_allowlist: None,
+            topic_filter: None,
+            merge_albums: true,
+            content: ContentPolicy::default(),
+        }
+    }
+
+    /// Sets the timezone assumed for local `date` fields (default: UTC).
+    ///
+    /// See [`TelegramConfig::assume_timezone`](crate::config::TelegramConfig::assume_timezone).
+    #[must_use]
+    pub fn with_assume_timezone(mut self, offset: FixedOffset) -> Self {
+        self.assume_timezone = offset;
+        self
+    }
+
+    /// Sets how mentions are written into message content.
+    ///
+    /// See [`TelegramConfig::mention_format`](crate::config::TelegramConfig::mention_format).
+    #[must_use]
+    pub fn with_mention_format(mut self, format: MentionFormat) -> Self {
+        self.mention_format = format;
+        self
+    }
+
+    /// Sets the clock sanity check applied to message timestamps.
+    ///
+    /// See [`TelegramConfig::clamp_timestamps`](crate::config::TelegramConfig::clamp_timestamps).
+    #[must_use]
+    pub fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
+
+    /// Keeps each message's source record in [`Message::raw`].
+    ///
+    /// See [`TelegramConfig::capture_raw`](crate::config::TelegramConfig::capture_raw).
+    #[must_use]
+    pub fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    /// Keeps phone calls and voice messages as placeholder messages.
+    ///
+    /// See [`TelegramConfig::include_calls`](crate::config::TelegramConfig::include_calls).
+    #[must_use]
+    pub fn with_include_calls(mut self, include: bool) -> Self {
+        self.include_calls = include;
+        self
+    }
+
+    /// Keeps only messages from the given senders, ignoring ASCII case.
+    ///
+    /// See [`TelegramConfig::sender_allowlist`](crate::config::TelegramConfig::sender_allowlist).
+    #[must_use]
+    pub fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.sender_allowlist = senders;
+        self
+    }
+
+    /// Keeps only messages posted in the given forum topics, ignoring ASCII
+    /// case.
+    ///
+    /// See [`TelegramConfig::topic_filter`](crate::config::TelegramConfig::topic_filter).
+    #[must_use]
+    pub fn with_topic_filter(mut self, topics: Option<Vec<String>>) -> Self {
+        self.topic_filter = topics;
+        self
+    }
+
+    /// Sets whether each photo or video album becomes one message.
+    ///
+    /// See [`TelegramConfig::merge_albums`](crate::config::TelegramConfig::merge_albums).
+    #[must_use]
+    pub fn with_merge_albums(mut self, merge: bool) -> Self {
+        self.merge_albums = merge;
+        self
+    }
+
+    /// Sets the whitespace rules applied to message content.
+    ///
+    /// See [`TelegramConfig::content`](crate::config::TelegramConfig::content).
+    #[must_use]
+    pub fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
+}
+
+impl TelegramStreamingParser {
+    /// Opens one export file, filling in the fields `projection` asks for.
+    fn open_chunk(
+        &self,
+        path: &Path,
+        projection: Projection,
+    ) -> StreamingResult<TelegramMessageIterator<BufReader<File>>> {
+        let file = File::open(path)?;
+        let file_size = file.metadata()?.len();
+
+        let reader = BufReader::with_capacity(self.config.buffer_size, file);
+        let config = self.config.with_projection(projection);
+        Ok(TelegramMessageIterator::new(reader, file_size, config)?
+            .with_assume_timezone(self.assume_timezone)
+            .with_mention_format(self.mention_format)
+            .with_clamp_timestamps(self.clamp_timestamps.clone())
+            .with_capture_raw(self.capture_raw)
+            .with_include_calls(self.include_calls)
+            .with_sender_allowlist(self.sender_allowlist.clone())
+            .with_topic_filter(self.topic_filter.clone())
+            .with_merge_albums(self.merge_albums)
+            .with_content_policy(self.content))
     }
 }
 
@@ -76,19 +213,20 @@ impl StreamingParser for TelegramStreamingParser {
     }
 
     fn stream(&self, file_path: &str) -> Result<Box<dyn MessageIterator>, ChatpackError> {
-        let path = Path::new(file_path);
-        let file = File::open(path)?;
-        let file_size = file.metadata()?.len();
-
-        let reader = BufReader::with_capacity(self.config.buffer_size, file);
-        let iterator = TelegramMessageIterator::new(reader, file_size, self.config)?;
-
-        Ok(Box::new(iterator))
+        let chunks = telegram_export_chunks(Path::new(file_path))?;
+        if let [chunk] = chunks.as_slice() {
+            return Ok(Box::new(self.open_chunk(chunk, self.config.projection)?));
+        }
+        Ok(Box::new(TelegramChunkIterator::new(self.clone(), chunks)?))
     }
 
     fn recommended_buffer_size(&self) -> usize {
         self.config.buffer_size
     }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 /// Iterator over Telegram messages.
@@ -96,6 +234,21 @@ pub struct TelegramMessageIterator<R: BufRead> {
     objects: JsonArrayObjectReader<R>,
     file_size: u64,
     config: StreamingConfig,
+    assume_timezone: FixedOffset,
+    mention_format: MentionFormat,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    include_calls: bool,
+    sender_allowlist: Option<Vec<String>>,
+    topic_filter: Option<Vec<String>>,
+    topics: TopicTracker,
+    merge_albums: bool,
+    /// The record read past the end of an album, with its JSON
+    held: Option<StreamingResult<(TelegramRawMessage, String)>>,
+    content: ContentPolicy,
+    skipped: usize,
+    system_messages: usize,
+    indexer: SourceIndexer,
 }
 
 impl<R: BufRead> TelegramMessageIterator<R> {
@@ -110,13 +263,146 @@ impl<R: BufRead> TelegramMessageIterator<R> {
             )?,
             file_size,
             config,
+            assume_timezone: FixedOffset::east_opt(0).unwrap(),
+            mention_format: MentionFormat::default(),
+            clamp_timestamps: None,
+            capture_raw: false,
+            include_calls: false,
+            sender_allowlist: None,
+            topic_filter: None,
+            topics: TopicTracker::default(),
+            merge_albums: true,
+            held: None,
+            content: ContentPolicy::default(),
+            skipped: 0,
+            system_messages: 0,
+            indexer: SourceIndexer::new(Platform::Telegram),
         })
     }
 
-    /// Parses a JSON string into a Message using shared parsing logic.
-    fn parse_message_from_json(json_str: &str) -> StreamingResult<Option<Message>> {
-        let msg: TelegramRawMessage = serde_json::from_str(json_str)?;
-        Ok(parse_telegram_message(&msg))
+    fn with_assume_timezone(mut self, offset: FixedOffset) -> Self {
+        self.assume_timezone = offset;
+        self
+    }
+
+    fn with_mention_format(mut self, format: MentionFormat) -> Self {
+        self.mention_format = format;
+        self
+    }
+
+    fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
+
+    fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    fn with_include_calls(mut self, include: bool) -> Self {
+        self.include_calls = include;
+        self
+    }
+
+    fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.sender_allowlist = senders;
+        self
+    }
+
+    fn with_topic_filter(mut self, topics: Option<Vec<String>>) -> Self {
+        self.topic_filter = topics;
+        self
+    }
+
+    fn with_merge_albums(mut self, merge: bool) -> Self {
+        self.merge_albums = merge;
+        self
+    }
+
+    fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
+
+    /// Reads and decodes the next record, starting with the one held back
+    /// after an album. Returns `None` at the end of the array.
+    fn next_record(&mut self) -> Option<StreamingResult<(TelegramRawMessage, String)>> {
+        if let Some(held) = self.held.take() {
+            return Some(held);
+        }
+        loop {
+            match self.objects.next_object() {
+                Ok(Some(json_str)) => match serde_json::from_str(&json_str) {
+                    Ok(record) => return Some(Ok((record, json_str))),
+                    Err(_) if self.config.skip_invalid => self.skipped += 1,
+                    Err(e) => return Some(Err(e.into())),
+                },
+                Ok(None) => return None, // End of array
+                Err(StreamingError::UnexpectedEof)
+                    if self.config.tolerate_truncation && self.objects.objects_read() > 0 =>
+                {
+                    self.skipped += 1; // The cut-off record
+                    return None;
+                }
+                Err(_) if self.config.skip_invalid => self.skipped += 1,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Reads the records of the album `first` starts, holding back the
+    /// record after it.
+    fn read_album(&mut self, first: &TelegramRawMessage) -> Vec<TelegramRawMessage> {
+        let mut rest = Vec::new();
+        if !self.merge_albums {
+            return rest;
+        }
+        loop {
+            match self.next_record() {
+                Some(Ok((next, _))) if continues_album(first, &next) => rest.push(next),
+                next => {
+                    self.held = next;
+                    return rest;
+                }
+            }
+        }
+    }
+
+    /// Converts a record, and the rest of its album, into a message using
+    /// shared parsing logic.
+    fn convert(
+        &mut self,
+        first: &TelegramRawMessage,
+        rest: &[TelegramRawMessage],
+    ) -> Option<Message> {
+        let msg = if rest.is_empty() {
+            parse_message(
+                first,
+                self.assume_timezone,
+                false,
+                self.include_calls,
+                self.mention_format,
+                self.sender_allowlist.as_deref(),
+                self.config.projection,
+            )
+        } else {
+            let items: Vec<&TelegramRawMessage> = std::iter::once(first).chain(rest).collect();
+            parse_album(
+                &items,
+                self.assume_timezone,
+                false,
+                self.mention_format,
+                self.sender_allowlist.as_deref(),
+                self.config.projection,
+            )
+        };
+        let filter = self.topic_filter.as_deref();
+        let msg = self.topics.assign(first, msg, filter);
+        for record in rest {
+            self.topics.assign(record, None, filter);
+        }
+        msg
     }
 }
 
@@ -135,6 +421,18 @@ impl<R: BufRead + Send> MessageIterator for TelegramMessageIterator<R> {
     fn total_bytes(&self) -> Option<u64> {
         Some(self.file_size)
     }
+
+    fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    fn system_messages(&self) -> usize {
+        self.system_messages
+    }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 impl<R: BufRead + Send> Iterator for TelegramMessageIterator<R> {
@@ -142,18 +440,142 @@ impl<R: BufRead + Send> Iterator for TelegramMessageIterator<R> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match self.objects.next_object() {
-                Ok(Some(json_str)) => {
-                    match Self::parse_message_from_json(&json_str) {
-                        Ok(Some(msg)) => return Some(Ok(msg)),
-                        Ok(None) => {} // Skip non-messages, try next
-                        Err(_) if self.config.skip_invalid => {} // Skip invalid
-                        Err(e) => return Some(Err(e)),
+            let (record, json_str) = match self.next_record()? {
+                Ok(next) => next,
+                Err(e) => return Some(Err(e)),
+            };
+            let album = self.read_album(&record);
+            // Skip non-messages, try next
+            let Some(mut msg) = self.convert(&record, &album) else {
+                if record.msg_type != "message" {
+                    self.system_messages += 1;
+                }
+                continue;
+            };
+            let check = self.clamp_timestamps.as_ref();
+            if !keep_message(&mut msg, check, &mut self.skipped) {
+                continue;
+            }
+            if self.capture_raw {
+                msg.raw = Some(json_str);
+            }
+            self.config.projection.apply(&mut msg);
+            normalize_content_in_place(&mut msg.content, self.content);
+            return Some(Ok(self.indexer.stamp(msg)));
+        }
+    }
+}
+
+/// Iterator over an export split into several files.
+///
+/// Opens each chunk once the previous one is exhausted, drops the messages
+/// a chunk repeats from the previous one, and numbers `source_index` across
+/// all of them.
+struct TelegramChunkIterator {
+    parser: TelegramStreamingParser,
+    pending: std::vec::IntoIter<PathBuf>,
+    current: Option<TelegramMessageIterator<BufReader<File>>>,
+    total_bytes: u64,
+    finished_bytes: u64,
+    finished_skipped: usize,
+    finished_system_messages: usize,
+    overlap: ChunkOverlap,
+    /// Forum topics of the chunks read so far
+    topics: TopicTracker,
+    indexer: SourceIndexer,
+}
+
+impl TelegramChunkIterator {
+    fn new(parser: TelegramStreamingParser, chunks: Vec<PathBuf>) -> StreamingResult<Self> {
+        let mut total_bytes = 0;
+        for chunk in &chunks {
+            total_bytes += std::fs::metadata(chunk)?.len();
+        }
+        Ok(Self {
+            parser,
+            pending: chunks.into_iter(),
+            current: None,
+            total_bytes,
+            finished_bytes: 0,
+            finished_skipped: 0,
+            finished_system_messages: 0,
+            overlap: ChunkOverlap::default(),
+            topics: TopicTracker::default(),
+            indexer: SourceIndexer::new(Platform::Telegram),
+        })
+    }
+
+    /// Records the exhausted chunk and moves on to the next one.
+    fn finish_chunk(&mut self) {
+        if let Some(chunk) = self.current.take() {
+            self.finished_bytes += chunk.bytes_processed();
+            self.finished_skipped += chunk.skipped();
+            self.finished_system_messages += chunk.system_messages();
+            self.topics = chunk.topics;
+        }
+        self.overlap.next_chunk();
+    }
+}
+
+impl MessageIterator for TelegramChunkIterator {
+    fn progress(&self) -> Option<f64> {
+        if self.total_bytes == 0 {
+            return None;
+        }
+        Some((self.bytes_processed() as f64 / self.total_bytes as f64) * 100.0)
+    }
+
+    fn bytes_processed(&self) -> u64 {
+        self.finished_bytes + self.current.as_ref().map_or(0, |c| c.bytes_processed())
+    }
+
+    fn total_bytes(&self) -> Option<u64> {
+        Some(self.total_bytes)
+    }
+
+    fn skipped(&self) -> usize {
+        self.finished_skipped + self.current.as_ref().map_or(0, |c| c.skipped())
+    }
+
+    fn system_messages(&self) -> usize {
+        self.finished_system_messages + self.current.as_ref().map_or(0, |c| c.system_messages())
+    }
+
+    fn projection(&self) -> Projection {
+        self.parser.config.projection
+    }
+}
+
+impl Iterator for TelegramChunkIterator {
+    type Item = StreamingResult<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.current.is_none() {
+                let path = self.pending.next()?;
+                // Chunks overlap, so repeats are dropped by ID before the
+                // projection is applied.
+                let projection = Projection {
+                    need_ids: true,
+                    ..self.parser.config.projection
+                };
+                match self.parser.open_chunk(&path, projection) {
+                    Ok(mut chunk) => {
+                        chunk.topics = std::mem::take(&mut self.topics);
+                        self.current = Some(chunk);
                     }
+                    Err(e) => return Some(Err(e)),
                 }
-                Ok(None) => return None, // End of array
-                Err(_) if self.config.skip_invalid => {}
-                Err(e) => return Some(Err(e)),
+            }
+            match self.current.as_mut().and_then(Iterator::next) {
+                Some(Ok(mut msg)) => {
+                    if self.overlap.keep(&msg) {
+                        self.parser.config.projection.apply(&mut msg);
+                        return Some(Ok(self.indexer.stamp(msg)));
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => self.finish_chunk(),
             }
         }
     }
@@ -234,6 +656,27 @@ mod tests {
         assert_eq!(messages[2].content, "Bye!");
     }
 
+    #[test]
+    fn test_local_date_fallback_with_timezone() {
+        let json = r#"{"name":"Chat","messages":[{"id":1,"type":"message","date":"2019-05-04T12:30:00","edited":"2019-05-04T12:35:00","from":"Alice","text":"Old"}]}"#;
+        let reader = BufReader::new(Cursor::new(json.as_bytes().to_vec()));
+
+        let iterator =
+            TelegramMessageIterator::new(reader, json.len() as u64, StreamingConfig::default())
+                .unwrap()
+                .with_assume_timezone(FixedOffset::east_opt(2 * 3600).unwrap());
+
+        let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
+        assert_eq!(
+            messages[0].timestamp.unwrap().to_rfc3339(),
+            "2019-05-04T10:30:00+00:00"
+        );
+        assert_eq!(
+            messages[0].edited.unwrap().to_rfc3339(),
+            "2019-05-04T10:35:00+00:00"
+        );
+    }
+
     #[test]
     fn test_empty_messages_array() {
         let json = r#"{"name": "Chat", "messages": []}"#;
@@ -369,6 +812,7 @@ mod tests {
         // Should skip invalid and return valid message
         assert_eq!(messages.len(), 1);
         assert_eq!(messages[0].content, "Valid!");
+        assert_eq!(iterator.skipped(), 1);
     }
 
     #[test]
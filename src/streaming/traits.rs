@@ -4,12 +4,39 @@
 //! - [`MessageIterator`] - Iterator with progress tracking
 //! - [`StreamingParser`] - Parser that produces iterators
 //! - [`StreamingConfig`] - Configuration options
+//! - [`Projection`] - Which message fields an iterator fills in (defined in
+//!   [`config`](crate::config))
 
 use crate::Message;
+use crate::config::{DEFAULT_MAX_LINE_LENGTH, Projection};
 use crate::error::ChatpackError;
+use crate::parser::Platform;
 
 use super::StreamingResult;
 
+/// Numbers messages with [`Message::source_index`] as an iterator yields
+/// them, and tags them with the iterator's platform.
+#[derive(Debug)]
+pub(crate) struct SourceIndexer {
+    next: u64,
+    platform: Platform,
+}
+
+impl SourceIndexer {
+    /// Creates an indexer starting at 0.
+    pub(crate) fn new(platform: Platform) -> Self {
+        Self { next: 0, platform }
+    }
+
+    /// Assigns the next index and the platform to `msg`.
+    pub(crate) fn stamp(&mut self, mut msg: Message) -> Message {
+        msg.source_index = Some(self.next);
+        msg.platform = Some(self.platform);
+        self.next += 1;
+        msg
+    }
+}
+
 /// Iterator over messages from a streaming parser with progress tracking.
 ///
 /// Extends the standard [`Iterator`] trait with methods for monitoring
@@ -57,6 +84,30 @@ pub trait MessageIterator: Iterator<Item = StreamingResult<Message>> + Send {
     fn total_bytes(&self) -> Option<u64> {
         None
     }
+
+    /// Returns the number of records dropped so far because they were invalid.
+    ///
+    /// Only counts records skipped under `skip_invalid` and messages flagged
+    /// by a timestamp check; non-message entries such as Telegram service
+    /// messages are not invalid and are not counted.
+    fn skipped(&self) -> usize {
+        0
+    }
+
+    /// Returns the number of system and service messages left out so far.
+    ///
+    /// Counted by the Telegram iterators; others return 0.
+    fn system_messages(&self) -> usize {
+        0
+    }
+
+    /// Returns which message fields this iterator fills in.
+    ///
+    /// A consumer that needs a field, such as content, can check here
+    /// instead of mistaking a projected-out field for an empty one.
+    fn projection(&self) -> Projection {
+        Projection::all()
+    }
 }
 
 /// A parser that streams messages from files without loading everything into memory.
@@ -100,6 +151,29 @@ pub trait StreamingParser: Send + Sync {
     /// Returns [`ChatpackError::Io`] if the file cannot be opened.
     fn stream(&self, file_path: &str) -> Result<Box<dyn MessageIterator>, ChatpackError>;
 
+    /// Returns the last `n` messages of a file in chronological order,
+    /// without parsing the rest of it.
+    ///
+    /// Line-oriented exports (WhatsApp TXT, Discord JSONL) are read
+    /// backwards from the end to where the last `n` messages start, then
+    /// parsed forward from there. Returns every message if the file has
+    /// fewer than `n`. [`Message::source_index`] is `None` on the returned
+    /// messages, since their position in the whole export is not known, and
+    /// so is a WhatsApp [`Message::reply_to`], which refers to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChatpackError::UnsupportedOperation`] for formats that
+    /// cannot be read from the end, such as a single JSON document (the
+    /// default). Callers can fall back to [`stream`](Self::stream) and keep
+    /// the last `n` messages.
+    fn stream_tail(&self, _file_path: &str, _n: usize) -> Result<Vec<Message>, ChatpackError> {
+        Err(ChatpackError::unsupported_operation(
+            "tail reading",
+            self.name(),
+        ))
+    }
+
     /// Returns the recommended buffer size for this parser.
     ///
     /// Default: 64KB
@@ -111,6 +185,13 @@ pub trait StreamingParser: Send + Sync {
     fn supports_progress(&self) -> bool {
         true
     }
+
+    /// Returns which message fields the iterators of this parser fill in.
+    ///
+    /// See [`MessageIterator::projection`]. Default: every field.
+    fn projection(&self) -> Projection {
+        Projection::all()
+    }
 }
 
 /// Configuration options for streaming parsers.
@@ -147,6 +228,27 @@ pub struct StreamingConfig {
     ///
     /// Default: 10,000. Lower values provide more frequent updates.
     pub progress_interval: usize,
+
+    /// Whether to end quietly at a record cut off by the end of the file.
+    ///
+    /// Default: `false`. When set, an export that ends partway through a
+    /// record (after at least one complete record) yields every complete
+    /// record and then ends, counting the cut-off record in
+    /// [`MessageIterator::skipped`] instead of returning an error.
+    pub tolerate_truncation: bool,
+
+    /// Longest line read by line-oriented parsers (WhatsApp), in bytes.
+    ///
+    /// Default: 1MB. Longer lines are never held in memory whole; they are
+    /// skipped or error, as `skip_invalid` says.
+    pub max_line_length: usize,
+
+    /// Which message fields iterators fill in.
+    ///
+    /// Default: [`Projection::all`]. A narrower projection leaves the
+    /// other fields empty, which saves memory when only, say, senders
+    /// and timestamps are read.
+    pub projection: Projection,
 }
 
 impl Default for StreamingConfig {
@@ -156,6 +258,9 @@ impl Default for StreamingConfig {
             max_message_size: 10 * 1024 * 1024, // 10MB
             skip_invalid: true,
             progress_interval: 10_000,
+            tolerate_truncation: false,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            projection: Projection::all(),
         }
     }
 }
@@ -193,6 +298,28 @@ impl StreamingConfig {
         self.progress_interval = interval;
         self
     }
+
+    /// Sets whether a record cut off by the end of the file ends the
+    /// stream instead of failing it.
+    #[must_use]
+    pub fn with_tolerate_truncation(mut self, tolerate: bool) -> Self {
+        self.tolerate_truncation = tolerate;
+        self
+    }
+
+    /// Sets the longest line read, in bytes.
+    #[must_use]
+    pub fn with_max_line_length(mut self, bytes: usize) -> Self {
+        self.max_line_length = bytes;
+        self
+    }
+
+    /// Sets which message fields iterators fill in.
+    #[must_use]
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -210,6 +337,9 @@ mod tests {
         assert_eq!(config.max_message_size, 10 * 1024 * 1024);
         assert!(config.skip_invalid);
         assert_eq!(config.progress_interval, 10_000);
+        assert!(!config.tolerate_truncation);
+        assert_eq!(config.max_line_length, DEFAULT_MAX_LINE_LENGTH);
+        assert!(config.projection.is_all());
     }
 
     #[test]
@@ -238,6 +368,24 @@ mod tests {
         assert_eq!(config.progress_interval, 5000);
     }
 
+    #[test]
+    fn test_streaming_config_with_tolerate_truncation() {
+        let config = StreamingConfig::new().with_tolerate_truncation(true);
+        assert!(config.tolerate_truncation);
+    }
+
+    #[test]
+    fn test_streaming_config_with_max_line_length() {
+        let config = StreamingConfig::new().with_max_line_length(4096);
+        assert_eq!(config.max_line_length, 4096);
+    }
+
+    #[test]
+    fn test_streaming_config_with_projection() {
+        let config = StreamingConfig::new().with_projection(Projection::minimal());
+        assert_eq!(config.projection, Projection::minimal());
+    }
+
     #[test]
     fn test_streaming_config_builder_chain() {
         let config = StreamingConfig::new()
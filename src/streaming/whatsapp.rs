@@ -8,36 +8,187 @@
 //!
 //! This parser streams line-by-line, handling multi-line messages.
 
-use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read, Seek};
 use std::path::Path;
 
 use chrono::{DateTime, Utc};
 use regex::Regex;
 
 use crate::Message;
+use crate::config::{ContentPolicy, TimestampCheck, WhatsAppConfig};
 use crate::error::ChatpackError;
+use crate::parser::Platform;
+use crate::parsing::content::normalize_content_in_place;
+use crate::parsing::encoding::{
+    CappedLine, TextEncoding, detect_encoding, open_text_reader, read_capped_line, strip_bom,
+};
+use crate::parsing::timestamps::keep_message;
 use crate::parsing::whatsapp::{
-    DateFormat, detect_whatsapp_format_owned, is_whatsapp_system_message, parse_whatsapp_timestamp,
+    DETECTION_SAMPLE, DateFormat, KnownSenders, LineKind, LineRules, QuoteResolver, classify_line,
+    detect_whatsapp_format_owned, learn_senders, strip_direction_marks,
 };
 
-use super::{MessageIterator, StreamingConfig, StreamingParser, StreamingResult};
+use super::traits::SourceIndexer;
+use super::{
+    MessageIterator, Projection, StreamingConfig, StreamingError, StreamingParser, StreamingResult,
+};
 
 /// Streaming parser for WhatsApp TXT exports.
 pub struct WhatsAppStreamingParser {
     config: StreamingConfig,
+    normalize_media_placeholders: bool,
+    normalize_deleted_messages: bool,
+    mark_unresolved_quotes: bool,
+    skip_system_messages: bool,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    sender_allowlist: Option<Vec<String>>,
+    strict_sender_detection: bool,
+    content: ContentPolicy,
 }
 
 impl WhatsAppStreamingParser {
     pub fn new() -> Self {
+        Self::with_config(StreamingConfig::default())
+    }
+
+    pub fn with_config(config: StreamingConfig) -> Self {
         Self {
-            config: StreamingConfig::default(),
+            config,
+            normalize_media_placeholders: true,
+            normalize_deleted_messages: true,
+            mark_unresolved_quotes: true,
+            skip_system_messages: true,
+            clamp_timestamps: None,
+            capture_raw: false,
+            sender_allowlist: None,
+            strict_sender_detection: false,
+            content: ContentPolicy::default(),
         }
     }
 
-    pub fn with_config(config: StreamingConfig) -> Self {
-        Self { config }
+    /// Creates a streaming parser from a [`WhatsAppConfig`], honoring its
+    /// buffer size, `skip_invalid`, line length limit, media placeholder
+    /// and deleted message normalization, quote marking, system message
+    /// skipping, timestamp check, raw capture, sender allowlist, strict
+    /// sender detection, and content policy.
+    pub fn from_whatsapp_config(config: &WhatsAppConfig) -> Self {
+        let streaming_config = StreamingConfig::new()
+            .with_buffer_size(config.buffer_size)
+            .with_skip_invalid(config.skip_invalid)
+            .with_max_line_length(config.max_line_length);
+        Self {
+            config: streaming_config,
+            normalize_media_placeholders: config.normalize_media_placeholders,
+            normalize_deleted_messages: config.normalize_deleted_messages,
+            mark_unresolved_quotes: config.mark_unresolved_quotes,
+            skip_system_messages: config.skip_system_messages,
+            clamp_timestamps: config.clamp_timestamps.clone(),
+            capture_raw: config.capture_raw,
+            sender_allowlist: config.sender_allowlist.clone(),
+            strict_sender_detection: config.strict_sender_detection,
+            content: config.content,
+        }
+    }
+
+    /// Skips system messages such as joins and encryption notices
+    /// (default: `true`).
+    ///
+    /// See [`WhatsAppConfig::skip_system_messages`].
+    #[must_use]
+    pub fn with_skip_system_messages(mut self, skip: bool) -> Self {
+        self.skip_system_messages = skip;
+        self
+    }
+
+    /// Replaces localized deleted-message lines with
+    /// [`MESSAGE_DELETED`](crate::message::MESSAGE_DELETED) (default:
+    /// `true`).
+    ///
+    /// See [`WhatsAppConfig::normalize_deleted_messages`].
+    #[must_use]
+    pub fn with_normalize_deleted_messages(mut self, normalize: bool) -> Self {
+        self.normalize_deleted_messages = normalize;
+        self
+    }
+
+    /// Sets the clock sanity check applied to message timestamps.
+    ///
+    /// See [`WhatsAppConfig::clamp_timestamps`].
+    #[must_use]
+    pub fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
+
+    /// Keeps each message's source lines in [`Message::raw`].
+    ///
+    /// See [`WhatsAppConfig::capture_raw`].
+    #[must_use]
+    pub fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
+
+    /// Keeps only messages from the given senders, ignoring ASCII case.
+    ///
+    /// See [`WhatsAppConfig::sender_allowlist`].
+    #[must_use]
+    pub fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.sender_allowlist = senders;
+        self
+    }
+
+    /// Sets the whitespace rules applied to message content.
+    ///
+    /// See [`WhatsAppConfig::content`].
+    #[must_use]
+    pub fn with_content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.content = policy;
+        self
+    }
+
+    /// Reads the whole file once to learn its senders before streaming.
+    ///
+    /// See [`WhatsAppConfig::strict_sender_detection`].
+    #[must_use]
+    pub fn with_strict_sender_detection(mut self, strict: bool) -> Self {
+        self.strict_sender_detection = strict;
+        self
+    }
+
+    /// Learns the senders of `file_path` if strict sender detection is on.
+    fn known_senders(&self, file_path: &str) -> StreamingResult<Option<KnownSenders>> {
+        if !self.strict_sender_detection {
+            return Ok(None);
+        }
+        let mut reader = open_text_reader(File::open(file_path)?, self.config.buffer_size)?;
+        Ok(Some(learn_senders(
+            reader.as_mut(),
+            self.config.max_line_length,
+        )?))
+    }
+
+    /// Creates an iterator with this parser's options.
+    fn iterator<R: BufRead>(
+        &self,
+        reader: R,
+        file_size: u64,
+    ) -> StreamingResult<WhatsAppMessageIterator<R>> {
+        Ok(WhatsAppMessageIterator::new(
+            reader,
+            file_size,
+            self.config,
+            self.normalize_media_placeholders,
+            self.mark_unresolved_quotes,
+            self.content,
+        )?
+        .with_skip_system_messages(self.skip_system_messages)
+        .with_normalize_deleted_messages(self.normalize_deleted_messages)
+        .with_clamp_timestamps(self.clamp_timestamps.clone())
+        .with_capture_raw(self.capture_raw && self.config.projection.need_content)
+        .with_sender_allowlist(self.sender_allowlist.clone()))
     }
 }
 
@@ -57,15 +208,63 @@ impl StreamingParser for WhatsAppStreamingParser {
         let file = File::open(path)?;
         let file_size = file.metadata()?.len();
 
-        let reader = BufReader::with_capacity(self.config.buffer_size, file);
-        let iterator = WhatsAppMessageIterator::new(reader, file_size, self.config)?;
+        let known_senders = self.known_senders(file_path)?;
+        // Skips a UTF-8 BOM and transcodes UTF-16 exports on the fly
+        let reader = open_text_reader(file, self.config.buffer_size)?;
+        let iterator = self.iterator(reader, file_size)?;
+        Ok(Box::new(iterator.with_known_senders(known_senders)))
+    }
+
+    /// Reads the last `n` messages from the end of the file. The date
+    /// format is detected from the start of the file, as in
+    /// [`stream`](StreamingParser::stream). Returns
+    /// [`ChatpackError::UnsupportedOperation`] for UTF-16 exports. Strict
+    /// sender detection still reads the whole file first.
+    fn stream_tail(&self, file_path: &str, n: usize) -> Result<Vec<Message>, ChatpackError> {
+        let mut file = File::open(file_path)?;
+        let file_size = file.metadata()?.len();
+
+        let mut head = Vec::new();
+        (&file).take(512).read_to_end(&mut head)?;
+        if matches!(
+            detect_encoding(&head),
+            TextEncoding::Utf16Le | TextEncoding::Utf16Be
+        ) {
+            return Err(ChatpackError::unsupported_operation(
+                "tail reading",
+                "WhatsApp (Streaming) for UTF-16 exports",
+            ));
+        }
+
+        let known_senders = self.known_senders(file_path)?;
+        file.rewind()?;
+        let head = self.iterator(BufReader::new(file), file_size)?;
+        let (Some(format), Some(regex)) = (head.detected_format, head.format_regex) else {
+            return Ok(Vec::new());
+        };
 
-        Ok(Box::new(iterator))
+        let is_header = |line: &[u8]| {
+            std::str::from_utf8(line).is_ok_and(|line| {
+                regex.is_match(strip_direction_marks(line.trim_end_matches('\r')))
+            })
+        };
+        super::tail::read_tail(file_path, n, true, is_header, |file| {
+            let reader = BufReader::with_capacity(self.config.buffer_size, file);
+            let iterator = self
+                .iterator(reader, file_size)?
+                .with_format(format)
+                .with_known_senders(known_senders.clone());
+            Ok(Box::new(iterator) as Box<dyn MessageIterator>)
+        })
     }
 
     fn recommended_buffer_size(&self) -> usize {
         self.config.buffer_size
     }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 #[derive(Debug, Default)]
@@ -73,6 +272,7 @@ struct PendingMessage {
     sender: String,
     content: String,
     timestamp: Option<DateTime<Utc>>,
+    raw: Option<String>,
 }
 
 impl PendingMessage {
@@ -84,122 +284,253 @@ impl PendingMessage {
         std::mem::take(self)
     }
 
-    fn into_message(self) -> Option<Message> {
+    fn into_message(
+        mut self,
+        policy: ContentPolicy,
+        quotes: &QuoteResolver,
+        mark_unresolved_quotes: bool,
+    ) -> Option<Message> {
         if self.sender.is_empty() || self.content.trim().is_empty() {
             return None;
         }
 
-        if is_whatsapp_system_message(&self.sender, &self.content) {
-            return None;
-        }
-
-        Some(Message::with_metadata(
+        let reply_to = quotes.take_quote(&mut self.content, mark_unresolved_quotes);
+        normalize_content_in_place(&mut self.content, policy);
+        let mut msg = Message::with_metadata(
             self.sender,
-            self.content.trim().to_string(),
+            self.content,
             self.timestamp,
             None,
+            reply_to,
             None,
-            None,
-        ))
+        );
+        msg.raw = self.raw;
+        Some(msg)
     }
 }
 
+/// A line read by [`WhatsAppMessageIterator::read_line`].
+enum ReadLine {
+    /// The line, without direction marks.
+    Text(String),
+    /// A line longer than `max_line_length`, skipped with `skip_invalid`.
+    Skipped,
+}
+
 /// Iterator over WhatsApp messages.
 pub struct WhatsAppMessageIterator<R: BufRead> {
     reader: R,
     file_size: u64,
     bytes_read: u64,
     config: StreamingConfig,
-    line_buffer: String,
+    rules: LineRules,
+    mark_unresolved_quotes: bool,
+    clamp_timestamps: Option<TimestampCheck>,
+    capture_raw: bool,
+    content: ContentPolicy,
+    skipped: usize,
+    indexer: SourceIndexer,
+    quotes: QuoteResolver,
+    line_buffer: Vec<u8>,
     pending: PendingMessage,
-    queued: VecDeque<Message>,
+    sample: std::vec::IntoIter<ReadLine>,
     finished: bool,
     detected_format: Option<DateFormat>,
     format_regex: Option<Regex>,
 }
 
 impl<R: BufRead> WhatsAppMessageIterator<R> {
-    fn new(mut reader: R, file_size: u64, config: StreamingConfig) -> StreamingResult<Self> {
+    fn new(
+        mut reader: R,
+        file_size: u64,
+        config: StreamingConfig,
+        normalize_media_placeholders: bool,
+        mark_unresolved_quotes: bool,
+        content: ContentPolicy,
+    ) -> StreamingResult<Self> {
         // Read first few lines to detect format
         let mut sample_lines = Vec::new();
+        let mut sample = Vec::new();
         let mut sample_bytes = 0u64;
+        let mut skipped = 0;
+        let mut line_buffer = Vec::with_capacity(4096);
 
-        for _ in 0..20 {
-            let mut line = String::new();
-            let bytes = reader.read_line(&mut line)?;
-            if bytes == 0 {
+        for _ in 0..DETECTION_SAMPLE {
+            let Some((line, bytes)) =
+                read_capped_line(&mut reader, &mut line_buffer, config.max_line_length)?
+            else {
                 break;
-            }
+            };
             sample_bytes += bytes as u64;
-            sample_lines.push(line);
+            let CappedLine::Line(mut line) = line else {
+                if !config.skip_invalid {
+                    return Err(StreamingError::BufferOverflow {
+                        max_size: config.max_line_length,
+                        actual_size: bytes,
+                    });
+                }
+                skipped += 1;
+                sample.push(ReadLine::Skipped);
+                continue;
+            };
+            if sample_lines.is_empty() {
+                line = strip_bom(line);
+            }
+            let line = strip_direction_marks(line).to_string();
+            sample_lines.push(line.clone());
+            sample.push(ReadLine::Text(line));
         }
 
         let detected_format = detect_whatsapp_format_owned(&sample_lines);
         let format_regex = detected_format.map(|f| Regex::new(f.pattern()).unwrap());
 
-        let mut iter = Self {
+        Ok(Self {
             reader,
             file_size,
             bytes_read: sample_bytes,
             config,
-            line_buffer: String::with_capacity(4096),
+            rules: LineRules {
+                normalize_media_placeholders,
+                skip_system_messages: true,
+                ..LineRules::default()
+            },
+            mark_unresolved_quotes,
+            clamp_timestamps: None,
+            capture_raw: false,
+            content,
+            skipped,
+            indexer: SourceIndexer::new(Platform::WhatsApp),
+            quotes: QuoteResolver::new(),
+            line_buffer,
             pending: PendingMessage::default(),
-            queued: VecDeque::new(),
+            // Replayed by read_line before the rest of the file
+            sample: sample.into_iter(),
             finished: false,
             detected_format,
             format_regex,
-        };
+        })
+    }
 
-        // Process sample lines, queuing completed messages
-        for line in sample_lines {
-            iter.process_line_queuing(&line);
-        }
+    fn with_skip_system_messages(mut self, skip: bool) -> Self {
+        self.rules.skip_system_messages = skip;
+        self
+    }
 
-        Ok(iter)
+    fn with_normalize_deleted_messages(mut self, normalize: bool) -> Self {
+        self.rules.normalize_deleted_messages = normalize;
+        self
     }
 
-    /// Process line, queuing any completed message before starting new one.
-    fn process_line_queuing(&mut self, line: &str) {
-        if line.trim().is_empty() {
-            return;
-        }
+    fn with_clamp_timestamps(mut self, check: Option<TimestampCheck>) -> Self {
+        self.clamp_timestamps = check;
+        self
+    }
+
+    fn with_capture_raw(mut self, capture: bool) -> Self {
+        self.capture_raw = capture;
+        self
+    }
 
-        if let (Some(format), Some(regex)) = (self.detected_format, &self.format_regex) {
-            if let Some(caps) = regex.captures(line) {
-                // New message - queue the pending one first
+    fn with_sender_allowlist(mut self, senders: Option<Vec<String>>) -> Self {
+        self.rules.sender_allowlist = senders;
+        self
+    }
+
+    fn with_known_senders(mut self, known: Option<KnownSenders>) -> Self {
+        self.rules.known_senders = known;
+        self
+    }
+
+    /// Uses `format` instead of the one detected from the first lines, for
+    /// reading that starts partway through the file.
+    fn with_format(mut self, format: DateFormat) -> Self {
+        self.detected_format = Some(format);
+        self.format_regex = Some(Regex::new(format.pattern()).unwrap());
+        self
+    }
+
+    /// Starts a new pending message or continues the current one, as the
+    /// batch parser does. Returns the previous pending message when this
+    /// line ends it.
+    ///
+    /// A header from a sender outside the allowlist leaves nothing pending,
+    /// so the message and its continuation lines are dropped.
+    fn process_line(&mut self, line: &str) -> Option<PendingMessage> {
+        let (Some(format), Some(regex)) = (self.detected_format, &self.format_regex) else {
+            return None;
+        };
+        match classify_line(line, format, regex, &self.rules) {
+            LineKind::Blank | LineKind::SystemMessage => None,
+            LineKind::Excluded => Some(self.pending.take()),
+            LineKind::Header {
+                sender,
+                content,
+                timestamp,
+            } => {
+                let done = self.pending.take();
+                self.pending = PendingMessage {
+                    sender: sender.to_string(),
+                    content: content.to_string(),
+                    timestamp,
+                    raw: self.capture_raw.then(|| line.to_string()),
+                };
+                Some(done)
+            }
+            LineKind::Continuation => {
                 if !self.pending.is_empty() {
-                    if let Some(msg) = self.pending.take().into_message() {
-                        self.queued.push_back(msg);
+                    self.pending.content.push('\n');
+                    self.pending.content.push_str(line);
+                    if let Some(raw) = &mut self.pending.raw {
+                        raw.push('\n');
+                        raw.push_str(line);
                     }
                 }
-
-                let date_str = caps.get(1).map_or("", |m| m.as_str());
-                let time_str = caps.get(2).map_or("", |m| m.as_str());
-                let sender = caps.get(3).map_or("", |m| m.as_str().trim());
-                let content = caps.get(4).map_or("", |m| m.as_str());
-
-                self.pending.sender = sender.to_string();
-                self.pending.content = content.to_string();
-                self.pending.timestamp = parse_whatsapp_timestamp(date_str, time_str, format);
-                return;
+                None
             }
         }
+    }
 
-        // Continuation line
-        if !self.pending.is_empty() {
-            self.pending.content.push('\n');
-            self.pending.content.push_str(line.trim_end());
+    /// Builds the message for `pending`, numbering it and recording it for
+    /// quote resolution.
+    fn complete(&mut self, pending: PendingMessage) -> Option<Message> {
+        let mut msg =
+            pending.into_message(self.content, &self.quotes, self.mark_unresolved_quotes)?;
+        if !keep_message(&mut msg, self.clamp_timestamps.as_ref(), &mut self.skipped) {
+            return None;
         }
+        let mut msg = self.indexer.stamp(msg);
+        self.quotes
+            .record(msg.source_index.unwrap_or_default(), &msg.content);
+        self.config.projection.apply(&mut msg);
+        Some(msg)
     }
 
-    fn read_line(&mut self) -> std::io::Result<Option<String>> {
-        self.line_buffer.clear();
-        let bytes = self.reader.read_line(&mut self.line_buffer)?;
-        if bytes == 0 {
-            return Ok(None);
+    /// Reads the next line, skipping lines longer than `max_line_length`
+    /// with `skip_invalid`.
+    fn read_line(&mut self) -> StreamingResult<Option<ReadLine>> {
+        if let Some(line) = self.sample.next() {
+            return Ok(Some(line));
         }
+        let max_len = self.config.max_line_length;
+        let Some((line, bytes)) =
+            read_capped_line(&mut self.reader, &mut self.line_buffer, max_len)?
+        else {
+            return Ok(None);
+        };
         self.bytes_read += bytes as u64;
-        Ok(Some(self.line_buffer.clone()))
+        match line {
+            CappedLine::Line(line) => Ok(Some(ReadLine::Text(
+                strip_direction_marks(line).to_string(),
+            ))),
+            CappedLine::TooLong(_) if self.config.skip_invalid => {
+                self.skipped += 1;
+                Ok(Some(ReadLine::Skipped))
+            }
+            CappedLine::TooLong(actual_size) => Err(StreamingError::BufferOverflow {
+                max_size: max_len,
+                actual_size,
+            }),
+        }
     }
 }
 
@@ -218,17 +549,20 @@ impl<R: BufRead + Send> MessageIterator for WhatsAppMessageIterator<R> {
     fn total_bytes(&self) -> Option<u64> {
         Some(self.file_size)
     }
+
+    fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    fn projection(&self) -> Projection {
+        self.config.projection
+    }
 }
 
 impl<R: BufRead + Send> Iterator for WhatsAppMessageIterator<R> {
     type Item = StreamingResult<Message>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // First, drain queued messages from sample lines
-        if let Some(msg) = self.queued.pop_front() {
-            return Some(Ok(msg));
-        }
-
         if self.finished && self.pending.is_empty() {
             return None;
         }
@@ -241,32 +575,33 @@ impl<R: BufRead + Send> Iterator for WhatsAppMessageIterator<R> {
         loop {
             match self.read_line() {
                 Ok(Some(line)) => {
-                    if let Some(regex) = &self.format_regex {
-                        if regex.is_match(&line) {
-                            let to_yield = self.pending.take();
-                            self.process_line_queuing(&line);
-
-                            if let Some(msg) = to_yield.into_message() {
-                                return Some(Ok(msg));
-                            }
-                            continue;
-                        }
+                    let done = match line {
+                        ReadLine::Text(line) => self.process_line(&line),
+                        // The skipped line may have been a header, so the
+                        // lines after it are not appended to this message
+                        ReadLine::Skipped => Some(self.pending.take()),
+                    };
+                    let Some(done) = done else {
+                        continue;
+                    };
+                    if let Some(msg) = self.complete(done) {
+                        return Some(Ok(msg));
                     }
-                    self.process_line_queuing(&line);
                 }
                 Ok(None) => {
                     self.finished = true;
                     let to_yield = self.pending.take();
-                    if let Some(msg) = to_yield.into_message() {
+                    if let Some(msg) = self.complete(to_yield) {
                         return Some(Ok(msg));
                     }
                     return None;
                 }
                 Err(e) => {
                     if self.config.skip_invalid {
+                        self.skipped += 1;
                         continue;
                     }
-                    return Some(Err(e.into()));
+                    return Some(Err(e));
                 }
             }
         }
@@ -276,7 +611,8 @@ impl<R: BufRead + Send> Iterator for WhatsAppMessageIterator<R> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Cursor;
+    use crate::parsing::whatsapp::is_whatsapp_system_message;
+    use std::io::{BufReader, Cursor};
 
     fn create_test_us_format() -> String {
         "[1/15/24, 10:30:00 AM] Alice: Hello everyone!
@@ -337,9 +673,15 @@ This is a continuation line
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
 
@@ -356,9 +698,15 @@ This is a continuation line
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
 
@@ -372,9 +720,15 @@ This is a continuation line
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
         assert!(messages.is_empty());
@@ -390,9 +744,15 @@ This is a continuation line
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let mut iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let mut iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let _: Vec<_> = iterator.by_ref().collect();
 
@@ -406,7 +766,15 @@ This is a continuation line
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator = WhatsAppMessageIterator::new(reader, 0, StreamingConfig::default()).unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            0,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         assert!(iterator.progress().is_none());
     }
@@ -417,9 +785,15 @@ This is a continuation line
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let mut iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let mut iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         // Sample lines already read
         let initial_bytes = iterator.bytes_processed();
@@ -439,8 +813,15 @@ This is a continuation line
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, file_size, StreamingConfig::default()).unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            file_size,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         assert_eq!(iterator.total_bytes(), Some(file_size));
     }
@@ -499,9 +880,15 @@ Line 3
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
 
@@ -519,9 +906,15 @@ Line 3
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
 
@@ -539,9 +932,15 @@ With no pattern";
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
         assert!(messages.is_empty());
@@ -556,11 +955,12 @@ With no pattern";
             sender: "Alice".to_string(),
             content: "Hello".to_string(),
             timestamp: None,
+            raw: None,
         };
         assert!(!with_content.is_empty());
 
         // Test into_message
-        let msg = with_content.into_message();
+        let msg = with_content.into_message(ContentPolicy::default(), &QuoteResolver::new(), true);
         assert!(msg.is_some());
         let msg = msg.unwrap();
         assert_eq!(msg.sender, "Alice");
@@ -573,6 +973,7 @@ With no pattern";
             sender: "Alice".to_string(),
             content: "Hello".to_string(),
             timestamp: None,
+            raw: None,
         };
 
         let taken = pending.take();
@@ -589,9 +990,15 @@ With no pattern";
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
 
@@ -608,9 +1015,15 @@ With no pattern";
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
 
@@ -624,9 +1037,15 @@ With no pattern";
         let cursor = Cursor::new(txt.as_bytes().to_vec());
         let reader = BufReader::new(cursor);
 
-        let mut iterator =
-            WhatsAppMessageIterator::new(reader, txt.len() as u64, StreamingConfig::default())
-                .unwrap();
+        let mut iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
 
         // Consume all messages
         let _: Vec<_> = iterator.by_ref().collect();
@@ -635,4 +1054,53 @@ With no pattern";
         assert!(iterator.next().is_none());
         assert!(iterator.next().is_none());
     }
+
+    // =========================================================================
+    // Encoding tests
+    // =========================================================================
+
+    #[test]
+    fn test_bom_on_first_line() {
+        let txt = "\u{FEFF}[1/15/24, 10:30:00 AM] Alice: Hello\n[1/15/24, 10:31:00 AM] Bob: Hi";
+        let cursor = Cursor::new(txt.as_bytes().to_vec());
+        let reader = BufReader::new(cursor);
+
+        let iterator = WhatsAppMessageIterator::new(
+            reader,
+            txt.len() as u64,
+            StreamingConfig::default(),
+            true,
+            true,
+            ContentPolicy::default(),
+        )
+        .unwrap();
+
+        let messages: Vec<_> = iterator.filter_map(Result::ok).collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender, "Alice");
+    }
+
+    #[test]
+    fn test_stream_utf16le_file() {
+        let txt = "[1/15/24, 10:30:00 AM] Alice: Привет\r\n[1/15/24, 10:31:00 AM] Bob: Hi\r\n";
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in txt.encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chat_utf16.txt");
+        std::fs::write(&path, bytes).unwrap();
+
+        let parser = WhatsAppStreamingParser::new();
+        let messages: Vec<_> = parser
+            .stream(path.to_str().unwrap())
+            .unwrap()
+            .filter_map(Result::ok)
+            .collect();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[0].content, "Привет");
+        assert_eq!(messages[1].sender, "Bob");
+    }
 }
@@ -0,0 +1,145 @@
+//! Golden input/output pairs for conformance checks.
+//!
+//! Each [`TestVector`] is a small export from one platform together with
+//! the exact CSV, JSON, and JSONL chatpack writes for it under
+//! [`output_config`], with no filters and no merging. The files live in
+//! `tests/vectors/` in the repository and are embedded here, so a crate
+//! wrapping chatpack can run the same byte-for-byte check chatpack's own
+//! tests run.
+//!
+//! # Example
+//!
+//! ```
+//! use chatpack::format::OutputFormat;
+//! use chatpack::testvectors;
+//!
+//! # fn main() -> chatpack::Result<()> {
+//! for vector in testvectors::list() {
+//!     for &format in OutputFormat::all() {
+//!         assert_eq!(vector.render(format)?, vector.expected(format));
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::Message;
+use crate::core::models::OutputConfig;
+use crate::error::ChatpackError;
+use crate::format::{OutputFormat, to_format_string};
+use crate::parser::Platform;
+
+/// An export and the output expected for it in each format.
+#[derive(Debug, Clone, Copy)]
+pub struct TestVector {
+    /// Platform the export comes from.
+    pub platform: Platform,
+    /// File name of the export in `tests/vectors/<platform>/`.
+    pub input_name: &'static str,
+    /// The export, byte for byte.
+    pub input: &'static [u8],
+    /// Expected CSV output.
+    pub csv: &'static str,
+    /// Expected JSON output.
+    pub json: &'static str,
+    /// Expected JSONL output.
+    pub jsonl: &'static str,
+    /// Expected HF text output.
+    pub hf: &'static str,
+}
+
+impl TestVector {
+    /// Returns the expected output in `format`.
+    pub fn expected(&self, format: OutputFormat) -> &'static str {
+        match format {
+            OutputFormat::Csv => self.csv,
+            OutputFormat::Json => self.json,
+            OutputFormat::Jsonl => self.jsonl,
+            OutputFormat::HfText => self.hf,
+        }
+    }
+
+    /// Parses the input with the default parser for its platform.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input cannot be parsed.
+    pub fn parse(&self) -> Result<Vec<Message>, ChatpackError> {
+        crate::read_bytes_as(self.platform, self.input)
+    }
+
+    /// Parses the input and writes it in `format` under [`output_config`].
+    ///
+    /// The result should equal [`expected`](Self::expected) byte for byte.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input cannot be parsed or written.
+    pub fn render(&self, format: OutputFormat) -> Result<String, ChatpackError> {
+        to_format_string(&self.parse()?, format, &output_config())
+    }
+}
+
+/// The configuration the expected outputs were written with.
+///
+/// Every `include_*` flag is on. No parser used for the vectors captures
+/// raw source, so records carry no raw value even though the flag is set.
+pub fn output_config() -> OutputConfig {
+    OutputConfig::all()
+        .with_platform()
+        .with_raw()
+        .with_schema_version()
+        .with_hash()
+}
+
+macro_rules! vector {
+    ($platform:expr, $dir:literal, $input:literal) => {
+        TestVector {
+            platform: $platform,
+            input_name: $input,
+            input: include_bytes!(concat!("../tests/vectors/", $dir, "/", $input)),
+            csv: include_str!(concat!("../tests/vectors/", $dir, "/expected.csv")),
+            json: include_str!(concat!("../tests/vectors/", $dir, "/expected.json")),
+            jsonl: include_str!(concat!("../tests/vectors/", $dir, "/expected.jsonl")),
+            hf: include_str!(concat!("../tests/vectors/", $dir, "/expected.hf.jsonl")),
+        }
+    };
+}
+
+static VECTORS: [TestVector; 4] = [
+    vector!(Platform::Telegram, "telegram", "input.json"),
+    vector!(Platform::WhatsApp, "whatsapp", "input.txt"),
+    vector!(Platform::Instagram, "instagram", "input.json"),
+    vector!(Platform::Discord, "discord", "input.json"),
+];
+
+/// Returns the test vectors, one per platform.
+pub fn list() -> &'static [TestVector] {
+    &VECTORS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors_match() {
+        for vector in list() {
+            for &format in OutputFormat::all() {
+                let output = vector.render(format).unwrap();
+                assert_eq!(
+                    output,
+                    vector.expected(format),
+                    "{} {format}",
+                    vector.platform
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_one_vector_per_platform() {
+        let platforms: Vec<Platform> = list().iter().map(|v| v.platform).collect();
+        assert_eq!(platforms, Platform::all());
+    }
+}
@@ -0,0 +1,69 @@
+//! Discord subformat features.
+//!
+//! Run under each combination of `discord-json`, `discord-txt` and
+//! `discord-csv`, e.g. `cargo test --no-default-features --features
+//! discord-json --test discord_features`: formats that are compiled in
+//! parse, and the rest fail with `UnsupportedInputFormat`.
+
+#![cfg(feature = "discord-json")]
+
+use chatpack::parser::Parser;
+use chatpack::parsers::DiscordParser;
+
+const JSON: &str = r#"{"messages":[{"id":"1","timestamp":"2024-01-15T10:30:00+00:00","timestampEdited":null,"content":"Hello","author":{"name":"alice"}}]}"#;
+const JSONL: &str = r#"{"id":"1","timestamp":"2024-01-15T10:30:00+00:00","timestampEdited":null,"content":"Hello","author":{"name":"alice"}}"#;
+const TXT: &str = "[1/15/2024 10:30 AM] alice\nHello\n";
+const CSV: &str = "AuthorID,Author,Date,Content,Attachments,Reactions\n1,alice,2024-01-15T10:30:00+00:00,Hello,,\n";
+
+#[test]
+fn test_json_always_parses() {
+    let parser = DiscordParser::new();
+    for content in [JSON, JSONL] {
+        let messages = parser.parse_str(content).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "Hello");
+    }
+}
+
+#[cfg(feature = "discord-txt")]
+#[test]
+fn test_txt_parses() {
+    let messages = DiscordParser::new().parse_str(TXT).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].sender, "alice");
+}
+
+#[cfg(not(feature = "discord-txt"))]
+#[test]
+fn test_txt_unsupported() {
+    let err = DiscordParser::new().parse_str(TXT).unwrap_err();
+    assert!(err.is_unsupported_input_format());
+    assert!(err.to_string().contains("'discord-txt'"));
+}
+
+#[cfg(feature = "discord-csv")]
+#[test]
+fn test_csv_parses() {
+    let messages = DiscordParser::new().parse_str(CSV).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].sender, "alice");
+}
+
+#[cfg(not(feature = "discord-csv"))]
+#[test]
+fn test_csv_unsupported() {
+    let err = DiscordParser::new().parse_str(CSV).unwrap_err();
+    assert!(err.is_unsupported_input_format());
+    assert!(err.to_string().contains("'discord-csv'"));
+}
+
+#[cfg(not(feature = "discord-csv"))]
+#[test]
+fn test_csv_file_unsupported() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("export.csv");
+    std::fs::write(&path, CSV).unwrap();
+
+    let err = DiscordParser::new().parse(&path).unwrap_err();
+    assert!(err.is_unsupported_input_format());
+}
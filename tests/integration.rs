@@ -1,7 +1,11 @@
 //! Integration tests for parsers with real files
 
 use chatpack::core::{FilterConfig, ProcessingStats};
+use chatpack::fixtures::{
+    DiscordExportBuilder, START, TelegramExportBuilder, WhatsAppExportBuilder,
+};
 use chatpack::parser::{Platform, create_parser};
+use chatpack::parsing::whatsapp::DateFormat;
 use chatpack::prelude::*;
 use std::fs;
 use std::path::Path;
@@ -26,18 +30,15 @@ fn ensure_fixtures() {
             fs::create_dir_all(&dir).unwrap();
         }
 
-        // Telegram: Simple with explicit timestamps and unixtime for robustness
-        let telegram_simple = r#"{
-  "name": "Test Chat",
-  "type": "personal_chat",
-  "id": 123456789,
-  "messages": [
-    {"id": 1, "type": "message", "date": "2024-01-15T10:30:00", "date_unixtime": "1705314600", "from": "Alice", "text": "Hello!"},
-    {"id": 2, "type": "message", "date": "2024-01-15T10:31:00", "date_unixtime": "1705314660", "from": "Bob", "text": "Hi Alice!"},
-    {"id": 3, "type": "message", "date": "2024-01-15T10:31:30", "date_unixtime": "1705314690", "from": "Alice", "text": "How are you?"},
-    {"id": 4, "type": "message", "date": "2024-01-15T10:32:00", "date_unixtime": "1705314720", "from": "Alice", "text": "I'm doing great!"}
-  ]
-}"#;
+        // Telegram: Simple, two messages in the same minute
+        let telegram_simple = TelegramExportBuilder::new("Test Chat")
+            .message("Alice", "Hello!")
+            .message("Bob", "Hi Alice!")
+            .at(START + chrono::Duration::seconds(90))
+            .message("Alice", "How are you?")
+            .at(START + chrono::Duration::minutes(2))
+            .message("Alice", "I'm doing great!")
+            .build();
         fs::write(format!("{dir}/telegram_simple.json"), telegram_simple).unwrap();
 
         // Telegram: Complex
@@ -64,6 +65,117 @@ fn ensure_fixtures() {
 }"#;
         fs::write(format!("{dir}/telegram_complex.json"), telegram_complex).unwrap();
 
+        // Telegram: Recent Telegram Desktop export with text_entities
+        let telegram_entities = r#"{
+  "name": "Entities Chat",
+  "type": "personal_chat",
+  "id": 555,
+  "messages": [
+    {"id": 1, "type": "message", "date": "2025-03-01T09:00:00", "date_unixtime": "1740819600", "from": "Alice", "from_id": "user1",
+     "text": "Plain message", "text_entities": [{"type": "plain", "text": "Plain message"}]},
+    {"id": 2, "type": "message", "date": "2025-03-01T09:01:00", "date_unixtime": "1740819660", "from": "Bob", "from_id": "user2",
+     "text": "", "text_entities": [
+       {"type": "blockquote", "text": "To be or not to be"},
+       {"type": "plain", "text": "\nThe answer is "},
+       {"type": "spoiler", "text": "42"}
+     ]},
+    {"id": 3, "type": "message", "date": "2025-03-01T09:02:00", "date_unixtime": "1740819720", "from": "Alice", "from_id": "user1",
+     "text_entities": [
+       {"type": "mention", "text": "@bob"},
+       {"type": "plain", "text": " see "},
+       {"type": "text_link", "text": "docs", "href": "https://example.com"}
+     ]}
+  ]
+}"#;
+        fs::write(format!("{dir}/telegram_entities.json"), telegram_entities).unwrap();
+
+        // Telegram: mentions of users with and without a username; message 2
+        // is only a mention entity whose text is empty
+        let telegram_mentions = r#"{
+  "name": "Mentions Chat",
+  "messages": [
+    {"id": 1, "type": "message", "date_unixtime": "1740819600", "from": "Alice",
+     "text": ["Thanks ", {"type": "mention_name", "text": "John", "user_id": 12345}, " and ", {"type": "mention", "text": "@bob"}]},
+    {"id": 2, "type": "message", "date_unixtime": "1740819660", "from": "Bob",
+     "text": [{"type": "mention_name", "text": "", "user_id": 67890}],
+     "text_entities": [{"type": "mention_name", "text": "", "user_id": 67890}]}
+  ]
+}"#;
+        fs::write(format!("{dir}/telegram_mentions.json"), telegram_mentions).unwrap();
+
+        // Telegram: Pre-2021 export with only local "date"/"edited" fields
+        let telegram_old = r#"{
+  "name": "Old Chat",
+  "type": "personal_chat",
+  "id": 777,
+  "messages": [
+    {"id": 1, "type": "message", "date": "2019-05-01T10:00:00", "from": "Alice", "from_id": "user1", "text": "Before the cutoff"},
+    {"id": 2, "type": "message", "date": "2019-05-04T12:30:00", "from": "Bob", "from_id": "user2", "text": "After the cutoff", "edited": "2019-05-04T12:35:00"},
+    {"id": 3, "type": "message", "date": "2019-05-05T08:00:00", "from": "Alice", "from_id": "user1", "text": "Still after"}
+  ]
+}"#;
+        fs::write(format!("{dir}/telegram_old.json"), telegram_old).unwrap();
+
+        // Telegram: Export with deliberately corrupted entries
+        let telegram_corrupt = r#"{
+  "name": "Corrupt Chat",
+  "messages": [
+    {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "First"},
+    {"id": "two", "type": "message", "from": "Bob", "text": "Bad id"},
+    {"id": 3, "type": "message", "date_unixtime": "1705314720", "from": "Bob", "text": "Second"},
+    {"id": 4, "from": "Alice", "text": "Missing type"},
+    {"id": 5, "type": "service", "date_unixtime": "1705314780", "action": "pin_message"},
+    {"id": 6, "type": "message", "reply_to_message_id": "x", "from": "Alice", "text": "Bad reply"}
+  ]
+}"#;
+        fs::write(format!("{dir}/telegram_corrupt.json"), telegram_corrupt).unwrap();
+
+        // Telegram: Media messages with and without captions
+        let telegram_media = r#"{
+  "name": "Media Chat",
+  "messages": [
+    {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "No media here"},
+    {"id": 2, "type": "message", "date_unixtime": "1705314660", "from": "Bob", "photo": "photos/photo_1@15-01-2024_10-31-00.jpg", "width": 800, "height": 600, "text": ""},
+    {"id": 3, "type": "message", "date_unixtime": "1705314720", "from": "Alice", "file": "files/report.pdf", "file_name": "Q4 report.pdf", "mime_type": "application/pdf", "text": "See attached"},
+    {"id": 4, "type": "message", "date_unixtime": "1705314780", "from": "Bob", "file": "(File not included. Change data exporting settings to download.)", "media_type": "video_file", "text": ""}
+  ]
+}"#;
+        fs::write(format!("{dir}/telegram_media.json"), telegram_media).unwrap();
+
+        // Telegram: Metadata, service messages, and runs from one sender
+        let telegram_meta = r#"{
+  "name": "Meta Chat",
+  "messages": [
+    {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "Hello", "photo": "photos/photo_1.jpg", "edited_unixtime": "1705314610"},
+    {"id": 2, "type": "message", "date_unixtime": "1705314660", "from": "Alice", "text": "Look at this", "photo": "photos/photo_2.jpg"},
+    {"id": 3, "type": "service", "date_unixtime": "1705314720", "actor": "Alice", "action": "pin_message", "text": ""},
+    {"id": 4, "type": "message", "date_unixtime": "1705314780", "from": "Bob", "text": "Nice!", "reply_to_message_id": 2},
+    {"id": 5, "type": "service", "date_unixtime": "1705314840", "actor": "Carol", "action": "join_group_by_link", "text": ""},
+    {"id": 6, "type": "message", "date_unixtime": "1705314900", "from": "Bob", "text": "Welcome Carol"},
+    {"id": 7, "type": "message", "date_unixtime": "1705314960", "from": "Carol", "text": "Thanks"}
+  ]
+}"#;
+        fs::write(format!("{dir}/telegram_meta.json"), telegram_meta).unwrap();
+
+        // Telegram: An album captioned on its third item, and one broken up
+        // by another sender
+        let telegram_album = r#"{
+  "name": "Album Chat",
+  "messages": [
+    {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "Back from the trip"},
+    {"id": 2, "type": "message", "date_unixtime": "1705314660", "from": "Alice", "grouped_id": 13571, "photo": "photos/photo_1.jpg", "text": ""},
+    {"id": 3, "type": "message", "date_unixtime": "1705314660", "from": "Alice", "grouped_id": 13571, "photo": "photos/photo_2.jpg", "text": ""},
+    {"id": 4, "type": "message", "date_unixtime": "1705314661", "from": "Alice", "grouped_id": 13571, "photo": "photos/photo_3.jpg", "text": "Mountains!"},
+    {"id": 5, "type": "message", "date_unixtime": "1705314661", "from": "Alice", "grouped_id": 13571, "photo": "photos/photo_4.jpg", "text": ""},
+    {"id": 6, "type": "message", "date_unixtime": "1705314662", "from": "Alice", "grouped_id": 13571, "file": "video_files/clip.mp4", "media_type": "video_file", "text": ""},
+    {"id": 7, "type": "message", "date_unixtime": "1705314720", "from": "Bob", "text": "Wow"},
+    {"id": 8, "type": "message", "date_unixtime": "1705314780", "from": "Alice", "grouped_id": 24680, "photo": "photos/photo_5.jpg", "text": "Sunset"},
+    {"id": 9, "type": "message", "date_unixtime": "1705314780", "from": "Bob", "text": "Send more"},
+    {"id": 10, "type": "message", "date_unixtime": "1705314781", "from": "Alice", "grouped_id": 24680, "photo": "photos/photo_6.jpg", "text": "Beach"}
+  ]
+}"#;
+        fs::write(format!("{dir}/telegram_album.json"), telegram_album).unwrap();
+
         // WhatsApp: iOS Bracketed Format (Reliable detection)
         let whatsapp_us = "[1/15/24, 10:30:00 AM] Alice: Hello everyone!
 [1/15/24, 10:31:00 AM] Bob: Hi Alice!
@@ -76,13 +188,114 @@ fn ensure_fixtures() {
         fs::write(format!("{dir}/whatsapp_us.txt"), whatsapp_us).unwrap();
 
         // WhatsApp: EU Format
-        let whatsapp_eu = "[15.01.24, 10:30:00] Alice: Привет всем!
-[15.01.24, 10:31:00] Bob: Привет!
-[15.01.24, 10:32:00] Alice: Как дела?
-[15.01.24, 10:33:00] Alice: Надеюсь все хорошо
-[15.01.24, 10:34:00] Charlie: Сообщения и звонки защищены сквозным шифрованием.
-[15.01.24, 10:35:00] Bob: Все отлично!";
-        fs::write(format!("{dir}/whatsapp_eu.txt"), whatsapp_eu).unwrap();
+        let whatsapp_eu = WhatsAppExportBuilder::new(DateFormat::EuDotBracketed)
+            .message("Alice", "Привет всем!")
+            .message("Bob", "Привет!")
+            .message("Alice", "Как дела?")
+            .message("Alice", "Надеюсь все хорошо")
+            .message("Charlie", "Сообщения и звонки защищены сквозным шифрованием.")
+            .message("Bob", "Все отлично!")
+            .build();
+        fs::write(format!("{dir}/whatsapp_eu.txt"), &whatsapp_eu).unwrap();
+
+        // WhatsApp: Windows exports with a UTF-8 BOM and as UTF-16LE
+        let mut whatsapp_bom = b"\xEF\xBB\xBF".to_vec();
+        whatsapp_bom.extend_from_slice(whatsapp_us.as_bytes());
+        fs::write(format!("{dir}/whatsapp_bom.txt"), whatsapp_bom).unwrap();
+
+        let mut whatsapp_utf16 = vec![0xFF, 0xFE];
+        for unit in whatsapp_eu.replace('\n', "\r\n").encode_utf16() {
+            whatsapp_utf16.extend_from_slice(&unit.to_le_bytes());
+        }
+        fs::write(format!("{dir}/whatsapp_utf16le.txt"), whatsapp_utf16).unwrap();
+
+        // WhatsApp: iOS export with quoted replies; the last quotes a message
+        // that is not in the export
+        let whatsapp_quotes = "\u{200E}[1/15/24, 10:30:00 AM] Alice: Are we still on for lunch tomorrow?
+[1/15/24, 10:31:00 AM] Bob: Let me check my calendar
+[1/15/24, 10:32:00 AM] Bob: > Are we still on for lunch…
+Yes, 12:30 works
+[1/15/24, 10:33:00 AM] Alice: > Yes, 12:30
+> works
+Great, see you there
+[1/15/24, 10:34:00 AM] Charlie: > Did anyone book the table?
+I did";
+        fs::write(format!("{dir}/whatsapp_quotes.txt"), whatsapp_quotes).unwrap();
+
+        // WhatsApp: a contact saved with a colon in its name, and another
+        // sender whose messages start with `Note: `
+        let whatsapp_colon_sender = "[1/15/24, 10:30:00 AM] Alice: Hi Mom
+[1/15/24, 10:31:00 AM] Mom: work: Hello dear
+[1/15/24, 10:32:00 AM] Alice: Note: remember the milk
+[1/15/24, 10:33:00 AM] Mom: work: Note: remember the eggs
+[1/15/24, 10:34:00 AM] Mom: work: See you
+[1/15/24, 10:35:00 AM] Alice: Shopping list
+Note: remember the bread";
+        fs::write(
+            format!("{dir}/whatsapp_colon_sender.txt"),
+            whatsapp_colon_sender,
+        )
+        .unwrap();
+
+        // WhatsApp: Android export from a contact saved with ` - ` in its
+        // name, the separator between time and sender in this format
+        let whatsapp_dash_sender = "15.01.2024, 10:30 - Alice - Bob: Hello
+15.01.2024, 10:31 - Me: Hi! How are you - all good?
+15.01.2024, 10:32 - Alice - Bob: Fine - thanks: the trip is on
+second line - with a dash
+15.01.2024, 10:33 - Alice - Bob: Note: bring the tickets
+15.01.2024, 10:34 - Alice - Bob changed the group description to \"Weekend plans for the whole family - see pinned: Saturday 10am\"
+15.01.2024, 10:35 - Me: See you - bye
+15.01.2024, 10:36 - Alice - Bob: 👍";
+        fs::write(
+            format!("{dir}/whatsapp_dash_sender.txt"),
+            whatsapp_dash_sender,
+        )
+        .unwrap();
+
+        // WhatsApp: localized media placeholders, one Android export per locale
+        // plus an iOS export whose media lines carry left-to-right marks
+        let whatsapp_media = [
+            ("en", "15/01/2024, 10:30 - Alice: <Media omitted>"),
+            ("de", "15.01.24, 10:30 - Alice: <Medien ausgeschlossen>"),
+            ("fr", "15/01/2024, 10:30 - Alice: <Médias omis>"),
+            ("es", "15/01/2024, 10:30 - Alice: <Multimedia omitido>"),
+            ("pt", "15/01/2024, 10:30 - Alice: <Mídia oculta>"),
+            ("ru", "15.01.2024, 10:30 - Alice: <Без медиафайлов>"),
+            ("tr", "15.01.2024, 10:30 - Alice: <Medya dahil edilmedi>"),
+            (
+                "ios",
+                "\u{200E}[1/15/24, 10:30:00 AM] Alice: \u{200E}image omitted",
+            ),
+        ];
+        for (locale, line) in whatsapp_media {
+            let (prefix, _) = line.split_once("Alice: ").unwrap();
+            let content = format!("{line}\n{prefix}null: Bob joined\n");
+            fs::write(format!("{dir}/whatsapp_media_{locale}.txt"), content).unwrap();
+        }
+
+        // WhatsApp: business exports spelling the month out, without and
+        // with brackets
+        let whatsapp_month_name = "15 Jan 2024, 10:30 - Alice: Hello!
+15 jan. 2024, 10:31 - Bob: Hi Alice!
+15 JAN 2024, 10:32 - Alice: Opening hours
+are 9 to 5";
+        fs::write(
+            format!("{dir}/whatsapp_month_name.txt"),
+            whatsapp_month_name,
+        )
+        .unwrap();
+        let whatsapp_month_name_bracketed =
+            WhatsAppExportBuilder::new(DateFormat::MonthNameBracketed)
+                .message("Alice", "Hello!")
+                .message("Bob", "Hi Alice!")
+                .message("Alice", "Opening hours\nare 9 to 5")
+                .build();
+        fs::write(
+            format!("{dir}/whatsapp_month_name_bracketed.txt"),
+            whatsapp_month_name_bracketed,
+        )
+        .unwrap();
 
         // Instagram: Full structure with magic_words to ensure auto-detection
         let instagram = r#"{
@@ -218,6 +431,40 @@ fn ensure_fixtures() {
 }"#;
         fs::write(format!("{dir}/discord.json"), discord_json).unwrap();
 
+        // Discord JSONL: same messages as discord.json, one object per line
+        let discord_jsonl = r#"{"id":"1001","type":"Default","timestamp":"2024-01-15T10:30:00+00:00","timestampEdited":null,"content":"Hello Discord!","author":{"id":"111","name":"alice","nickname":"Alice"},"attachments":[],"stickers":[],"embeds":[]}
+{"id":"1002","type":"Default","timestamp":"2024-01-15T10:31:00+00:00","timestampEdited":"2024-01-15T10:32:00+00:00","content":"Hi Alice!","author":{"id":"222","name":"bob","nickname":null},"reference":{"messageId":"1001"},"attachments":[{"fileName":"image.png"}],"stickers":[],"embeds":[]}
+{"id":"1003","type":"Default","timestamp":"2024-01-15T10:33:00+00:00","timestampEdited":null,"content":"","author":{"id":"111","name":"alice","nickname":"Alice"},"attachments":[],"stickers":[{"name":"Wave"}],"embeds":[]}
+"#;
+        fs::write(format!("{dir}/discord.jsonl"), discord_jsonl).unwrap();
+
+        // Discord JSONL where alice's nickname is only set on her first
+        // message, so her name comes out as "Alice" and then "alice"
+        let discord_nicknames = r#"{"id":"1","type":"Default","timestamp":"2024-01-15T10:30:00+00:00","content":"Morning","author":{"id":"111","name":"alice","nickname":"Alice"}}
+{"id":"2","type":"Default","timestamp":"2024-01-15T10:31:00+00:00","content":"Anyone up?","author":{"id":"111","name":"alice","nickname":null}}
+{"id":"3","type":"Default","timestamp":"2024-01-15T10:32:00+00:00","content":"Me","author":{"id":"222","name":"bob","nickname":null}}
+"#;
+        fs::write(format!("{dir}/discord_nicknames.jsonl"), discord_nicknames).unwrap();
+
+        // Discord JSON with legacy discriminators; bob is a migrated username
+        let discord_tags = r#"{
+  "messages": [
+    {"id": "1", "timestamp": "2024-01-15T10:30:00+00:00", "content": "Hi", "author": {"id": "111", "name": "alice", "discriminator": "1234", "nickname": "Alice"}},
+    {"id": "2", "timestamp": "2024-01-15T10:31:00+00:00", "content": "Hey", "author": {"id": "222", "name": "bob", "discriminator": "0000", "nickname": "Bobby"}},
+    {"id": "3", "timestamp": "2024-01-15T10:32:00+00:00", "content": "Yo", "author": {"name": "carol"}}
+  ]
+}"#;
+        fs::write(format!("{dir}/discord_tags.json"), discord_tags).unwrap();
+
+        // Discord JSONL with deliberately corrupted lines
+        let discord_corrupt = r#"{"id":"1","timestamp":"2024-01-15T10:30:00+00:00","content":"First","author":{"name":"alice"}}
+{"id":"2","timestamp":"2024-01-15T10:31:00+00:00","content":"Truncated","auth
+{"id":3,"timestamp":"2024-01-15T10:32:00+00:00","content":"Numeric id","author":{"name":"bob"}}
+not json at all
+{"id":"5","timestamp":"2024-01-15T10:34:00+00:00","content":"Second","author":{"name":"bob"}}
+"#;
+        fs::write(format!("{dir}/discord_corrupt.jsonl"), discord_corrupt).unwrap();
+
         // Discord TXT
         let discord_txt = r"==============================================================
 Guild: Test Server
@@ -248,12 +495,66 @@ Exported 3 message(s)
 ==============================================================";
         fs::write(format!("{dir}/discord.txt"), discord_txt).unwrap();
 
+        // Discord TXT with reply context lines, above the header and as
+        // the first content line
+        let discord_replies = r"==============================================================
+Guild: Test Server
+Channel: general
+==============================================================
+
+[1/15/2024 10:30 AM] Alice
+Are we still on for lunch tomorrow at noon?
+
+  > Replying to Alice: Are we still on for lunch tomorrow…
+[1/15/2024 10:31 AM] bob
+Yes, see you there
+
+
+[1/15/2024 10:32 AM] Alice
+> Replying to bob: Yes, see you there
+Great!
+
+
+  > Replying to carol: Did anyone book a table?
+[1/15/2024 10:33 AM] bob
+Not yet";
+        fs::write(format!("{dir}/discord_replies.txt"), discord_replies).unwrap();
+
         // Discord CSV
-        let discord_csv = r#"AuthorID,Author,Date,Content,Attachments,Reactions
-"111","Alice","2024-01-15T10:30:00+00:00","Hello Discord!","",""
-"222","bob","2024-01-15T10:31:00+00:00","Hi Alice!","https://cdn.discordapp.com/attachments/123/456/image.png",""
-"111","Alice","2024-01-15T10:33:00+00:00","How are you?","","""#;
+        let discord_csv = DiscordExportBuilder::new("Test Server", "general")
+            .message("Alice", "Hello Discord!")
+            .message("bob", "Hi Alice!")
+            .attachment("image.png")
+            .at(START + chrono::Duration::minutes(3))
+            .message("Alice", "How are you?")
+            .build_csv();
         fs::write(format!("{dir}/discord.csv"), discord_csv).unwrap();
+
+        // Discord TXT: Older export spelling the month out
+        let discord_month_name = "[15-Jan-24 10:30 AM] Alice
+Hello Discord!
+
+[15-Jan-24 10:31 AM] bob
+Hi Alice!
+
+";
+        fs::write(format!("{dir}/discord_month_name.txt"), discord_month_name).unwrap();
+
+        // Discord CSV: Dates as written by other exporter versions and
+        // locales, and a column no format matches
+        for (name, first, second) in [
+            ("rfc3339", "2024-01-15T10:30:00+00:00", "2024-01-15T22:30:00+00:00"),
+            ("short", "15-Jan-24 10:30 AM", "15-Jan-24 10:30 PM"),
+            ("dotted", "15.01.2024 10:30", "15.01.2024 22:30"),
+            ("unparseable", "2024/01/15 10h30", "2024/01/15 22h30"),
+        ] {
+            let csv = format!(
+                "AuthorID,Author,Date,Content,Attachments,Reactions\n\
+                 111,Alice,{first},Good morning,,\n\
+                 222,bob,{second},Good night,,\n"
+            );
+            fs::write(format!("{dir}/discord_dates_{name}.csv"), csv).unwrap();
+        }
     });
 }
 
@@ -314,6 +615,36 @@ mod discord_tests {
         assert_eq!(messages[1].sender, "bob");
     }
 
+    #[test]
+    fn test_sender_naming_combinations() {
+        ensure_fixtures();
+        let path = format!("{}/discord_tags.json", fixtures_dir());
+        let cases = [
+            (true, false, ["Alice", "Bobby", "carol"]),
+            (false, false, ["alice", "bob", "carol"]),
+            (true, true, ["Alice#1234", "Bobby", "carol"]),
+            (false, true, ["alice#1234", "bob", "carol"]),
+        ];
+
+        for (prefer_nickname, append_discriminator, expected) in cases {
+            let config = DiscordConfig::new()
+                .with_prefer_nickname(prefer_nickname)
+                .with_append_discriminator(append_discriminator);
+            let batch = DiscordParser::with_config(config.clone())
+                .parse(Path::new(&path))
+                .unwrap();
+            let senders: Vec<_> = batch.iter().map(|m| m.sender.as_str()).collect();
+            assert_eq!(senders, expected);
+
+            let streamed: Vec<_> = DiscordParser::with_config(config.with_streaming(true))
+                .stream(Path::new(&path))
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(streamed, batch);
+        }
+    }
+
     #[test]
     fn test_parse_txt() {
         ensure_fixtures();
@@ -352,6 +683,62 @@ mod discord_tests {
         assert_eq!(messages[0].content, "Hello Discord!");
     }
 
+    #[test]
+    fn test_parse_txt_month_name() {
+        ensure_fixtures();
+        let path = format!("{}/discord_month_name.txt", fixtures_dir());
+        let messages = create_parser(Platform::Discord).parse_file(&path).unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[0].content, "Hello Discord!");
+        assert_eq!(messages[0].timestamp, Some(START));
+        assert_eq!(
+            messages[1].timestamp,
+            Some(START + chrono::Duration::minutes(1))
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_date_variants() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Discord);
+        let expected = [Some(START), Some(START + chrono::Duration::hours(12))];
+
+        for name in ["rfc3339", "short", "dotted"] {
+            let path = format!("{}/discord_dates_{name}.csv", fixtures_dir());
+            let (messages, unparsed) = parser.parse_counted(Path::new(&path)).unwrap();
+            let timestamps: Vec<_> = messages.iter().map(|m| m.timestamp).collect();
+            assert_eq!(timestamps, expected, "{name}");
+            assert_eq!(unparsed, 0, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_counts_unparseable_dates() {
+        use chatpack::config::DiscordConfig;
+
+        ensure_fixtures();
+        let path = format!("{}/discord_dates_unparseable.csv", fixtures_dir());
+        let (messages, unparsed) = create_parser(Platform::Discord)
+            .parse_counted(Path::new(&path))
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().all(|m| m.timestamp.is_none()));
+        assert_eq!(unparsed, 2);
+
+        // A custom format reads the column
+        let config = DiscordConfig::new().with_csv_date_format("%Y/%m/%d %Hh%M");
+        let (messages, unparsed) = DiscordParser::with_config(config)
+            .parse_counted(Path::new(&path))
+            .unwrap();
+        assert_eq!(
+            messages[1].timestamp,
+            Some(START + chrono::Duration::hours(12))
+        );
+        assert_eq!(unparsed, 0);
+    }
+
     #[test]
     fn test_parse_csv_attachments() {
         ensure_fixtures();
@@ -364,12 +751,92 @@ mod discord_tests {
         assert!(has_attachment);
     }
 
+    #[test]
+    fn test_attachment_refs() {
+        use chatpack::config::DiscordConfig;
+
+        ensure_fixtures();
+        let parser =
+            DiscordParser::with_config(DiscordConfig::new().with_collect_attachment_refs(true));
+
+        for ext in ["json", "jsonl", "txt", "csv"] {
+            let path = format!("{}/discord.{ext}", fixtures_dir());
+            let messages = parser.parse_file(&path).unwrap();
+
+            let refs: Vec<_> = messages.iter().flat_map(|m| &m.attachments).collect();
+            assert_eq!(refs.len(), 1, "{ext}");
+            assert_eq!(refs[0].file_name, "image.png", "{ext}");
+            assert!(messages[0].attachments.is_empty(), "{ext}");
+            // Markers in content are unchanged
+            assert!(
+                messages[1].content.contains("[Attachment: image.png]"),
+                "{ext}"
+            );
+        }
+
+        // Off by default
+        let path = format!("{}/discord.json", fixtures_dir());
+        let messages = create_parser(Platform::Discord).parse_file(&path).unwrap();
+        assert!(messages.iter().all(|m| m.attachments.is_empty()));
+    }
+
     #[test]
     fn test_parser_name() {
         let parser = create_parser(Platform::Discord);
         assert_eq!(parser.name(), "Discord");
     }
 
+    #[test]
+    fn test_parse_jsonl_matches_json() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Discord);
+        let from_json = parser
+            .parse_file(&format!("{}/discord.json", fixtures_dir()))
+            .unwrap();
+
+        let path = format!("{}/discord.jsonl", fixtures_dir());
+        assert_eq!(parser.parse_file(&path).unwrap(), from_json);
+
+        let streaming = create_streaming_parser(Platform::Discord);
+        let streamed: Vec<_> = streaming
+            .stream_file(&path)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(streamed, from_json);
+    }
+
+    #[test]
+    fn test_parse_counted_jsonl_skips() {
+        ensure_fixtures();
+        let path = format!("{}/discord_corrupt.jsonl", fixtures_dir());
+
+        let parser = create_parser(Platform::Discord);
+        let (messages, skipped) = parser.parse_counted(Path::new(&path)).unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["First", "Second"]);
+        assert_eq!(skipped, 3);
+
+        let (_, clean) = parser
+            .parse_counted(Path::new(&format!("{}/discord.jsonl", fixtures_dir())))
+            .unwrap();
+        assert_eq!(clean, 0);
+    }
+
+    #[test]
+    fn test_discord_json_round_trip() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Discord);
+        let messages = parser
+            .parse_file(&format!("{}/discord.json", fixtures_dir()))
+            .unwrap();
+
+        let out = format!("{}/discord_roundtrip.json", fixtures_dir());
+        chatpack::core::output::write_discord_json(&messages, &out, "general").unwrap();
+
+        assert_eq!(parser.parse_file(&out).unwrap(), messages);
+    }
+
     #[test]
     fn test_consecutive_merge() {
         ensure_fixtures();
@@ -408,189 +875,4403 @@ mod telegram_tests {
     }
 
     #[test]
-    fn test_parse_complex_chat() {
-        ensure_fixtures();
-        let parser = create_parser(Platform::Telegram);
-        let messages = parser
-            .parse_file(&format!("{}/telegram_complex.json", fixtures_dir()))
-            .unwrap();
-
-        assert!(messages.len() >= 2);
-        assert!(messages[0].content.contains("https://example.com"));
-
-        let has_reply = messages.iter().any(|m| m.reply_to.is_some());
-        assert!(has_reply);
+    fn test_parse_complex_chat() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Telegram);
+        let messages = parser
+            .parse_file(&format!("{}/telegram_complex.json", fixtures_dir()))
+            .unwrap();
+
+        assert!(messages.len() >= 2);
+        assert!(messages[0].content.contains("https://example.com"));
+
+        let has_reply = messages.iter().any(|m| m.reply_to.is_some());
+        assert!(has_reply);
+
+        let has_edited = messages.iter().any(|m| m.edited.is_some());
+        assert!(has_edited);
+    }
+
+    #[test]
+    fn test_parse_text_entities() {
+        ensure_fixtures();
+        let path = format!("{}/telegram_entities.json", fixtures_dir());
+        let expected = [
+            "Plain message",
+            "To be or not to be\nThe answer is 42",
+            "@bob see docs",
+        ];
+
+        let parser = create_parser(Platform::Telegram);
+        let messages = parser.parse_file(&path).unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, expected);
+
+        let streaming = create_streaming_parser(Platform::Telegram);
+        let streamed: Vec<_> = streaming
+            .stream_file(&path)
+            .unwrap()
+            .map(|r| r.unwrap().content)
+            .collect();
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn test_parse_mentions() {
+        use chatpack::config::{MentionFormat, TelegramConfig};
+
+        ensure_fixtures();
+        let path = format!("{}/telegram_mentions.json", fixtures_dir());
+        let cases = [
+            (MentionFormat::AsExported, "Thanks John and @bob"),
+            (MentionFormat::At, "Thanks @John and @bob"),
+            (MentionFormat::Plain, "Thanks John and bob"),
+        ];
+
+        for (format, first) in cases {
+            let config = TelegramConfig::new().with_mention_format(format);
+            let expected = [first, "@id67890"];
+
+            let messages = TelegramParser::with_config(config.clone())
+                .parse(path.as_ref())
+                .unwrap();
+            let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, expected, "{format:?}");
+
+            let streamed: Vec<_> = TelegramParser::with_config(config.with_streaming(true))
+                .stream(path.as_ref())
+                .unwrap()
+                .map(|r| r.unwrap().content)
+                .collect();
+            assert_eq!(streamed, expected, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_file_size_limit() {
+        use chatpack::config::TelegramConfig;
+
+        ensure_fixtures();
+        let path = Path::new(&fixtures_dir()).join("telegram_simple.json");
+        let parser = TelegramParser::with_config(TelegramConfig::new().with_max_file_size(64));
+
+        let err = parser.parse(&path).unwrap_err();
+        assert!(err.is_file_too_large());
+        assert!(err.to_string().contains("streaming"));
+
+        // stream() switches to the streaming parser instead of failing
+        let streamed: Vec<_> = parser.stream(&path).unwrap().map(Result::unwrap).collect();
+        assert_eq!(streamed, TelegramParser::new().parse(&path).unwrap());
+    }
+
+    #[test]
+    fn test_attachment_refs() {
+        use chatpack::config::TelegramConfig;
+
+        ensure_fixtures();
+        let path = format!("{}/telegram_media.json", fixtures_dir());
+
+        // Without collection, media-only messages are skipped as before
+        let plain = create_parser(Platform::Telegram).parse_file(&path).unwrap();
+        assert_eq!(plain.len(), 2);
+        assert!(plain.iter().all(|m| m.attachments.is_empty()));
+
+        let config = TelegramConfig::new().with_collect_attachment_refs(true);
+        let messages = TelegramParser::with_config(config)
+            .parse_file(&path)
+            .unwrap();
+        assert_eq!(messages.len(), 4);
+
+        assert!(messages[0].attachments.is_empty());
+        let json = serde_json::to_string(&messages[0]).unwrap();
+        assert!(!json.contains("attachments"));
+
+        assert_eq!(
+            messages[1].content,
+            "[Attachment: photo_1@15-01-2024_10-31-00.jpg]"
+        );
+        assert_eq!(
+            messages[1].attachments[0].path.as_deref(),
+            Some("photos/photo_1@15-01-2024_10-31-00.jpg")
+        );
+
+        assert_eq!(messages[2].content, "See attached");
+        assert_eq!(messages[2].attachments[0].file_name, "Q4 report.pdf");
+
+        assert_eq!(messages[3].attachments[0].file_name, "file");
+        assert_eq!(messages[3].attachments[0].path, None);
+    }
+
+    #[cfg(feature = "csv-output")]
+    #[test]
+    fn test_attachment_manifest() {
+        use chatpack::config::TelegramConfig;
+        use chatpack::core::output::write_attachment_manifest;
+
+        ensure_fixtures();
+        let path = format!("{}/telegram_media.json", fixtures_dir());
+        let config = TelegramConfig::new().with_collect_attachment_refs(true);
+        let messages = TelegramParser::with_config(config)
+            .parse_file(&path)
+            .unwrap();
+
+        let out = TempDir::new().unwrap();
+        let manifest_path = out.path().join("attachments.csv");
+        write_attachment_manifest(&messages, manifest_path.to_str().unwrap()).unwrap();
+
+        let manifest = fs::read_to_string(&manifest_path).unwrap();
+        let lines: Vec<&str> = manifest.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[2],
+            "Q4 report.pdf;files/report.pdf;Alice;2024-01-15T10:32:00Z;3"
+        );
+    }
+
+    #[test]
+    fn test_parse_old_format_date_fallback() {
+        ensure_fixtures();
+        let path = format!("{}/telegram_old.json", fixtures_dir());
+        let filter = FilterConfig::new().after_date("2019-05-02").unwrap();
+
+        let parser = create_parser(Platform::Telegram);
+        let messages = parser.parse_file(&path).unwrap();
+        assert!(messages.iter().all(|m| m.timestamp.is_some()));
+        assert!(messages[1].edited.is_some());
+
+        let filtered = apply_filters(messages, &filter);
+        let contents: Vec<_> = filtered.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["After the cutoff", "Still after"]);
+
+        let streaming = create_streaming_parser(Platform::Telegram);
+        let streamed: Vec<_> = streaming
+            .stream_file(&path)
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|m| filter.matches(m))
+            .collect();
+        assert_eq!(streamed.len(), 2);
+        assert!(streamed[0].edited.is_some());
+    }
+
+    #[test]
+    fn test_parse_counted_skips() {
+        ensure_fixtures();
+        let path = format!("{}/telegram_corrupt.json", fixtures_dir());
+
+        let parser = create_parser(Platform::Telegram);
+        let (messages, skipped) = parser.parse_counted(Path::new(&path)).unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        // The service message is filtered, not skipped
+        assert_eq!(contents, ["First", "Second"]);
+        assert_eq!(skipped, 3);
+
+        let (_, clean) = parser
+            .parse_counted(Path::new(&format!(
+                "{}/telegram_simple.json",
+                fixtures_dir()
+            )))
+            .unwrap();
+        assert_eq!(clean, 0);
+    }
+
+    #[test]
+    fn test_merge_consecutive() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Telegram);
+        let messages = parser
+            .parse_file(&format!("{}/telegram_simple.json", fixtures_dir()))
+            .unwrap();
+
+        let original_count = messages.len();
+        let merged = merge_consecutive(messages);
+
+        assert!(merged.len() <= original_count);
+    }
+
+    #[test]
+    fn test_parser_name() {
+        let parser = create_parser(Platform::Telegram);
+        assert_eq!(parser.name(), "Telegram");
+    }
+
+    #[test]
+    fn test_telegram_json_round_trip() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Telegram);
+
+        for fixture in ["telegram_complex.json", "telegram_entities.json"] {
+            let messages = parser
+                .parse_file(&format!("{}/{fixture}", fixtures_dir()))
+                .unwrap();
+
+            let out = format!("{}/roundtrip_{fixture}", fixtures_dir());
+            chatpack::core::output::write_telegram_json(&messages, &out, "Round Trip").unwrap();
+
+            assert_eq!(parser.parse_file(&out).unwrap(), messages);
+        }
+    }
+}
+
+// ============================================================================
+// WhatsApp Parser Tests
+// ============================================================================
+
+mod whatsapp_tests {
+    use super::*;
+    use chatpack::parsing::whatsapp::detect_whatsapp_format;
+
+    #[test]
+    fn test_parse_month_name_formats() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        for name in ["whatsapp_month_name", "whatsapp_month_name_bracketed"] {
+            let path = format!("{}/{name}.txt", fixtures_dir());
+            let messages = parser.parse_file(&path).unwrap();
+
+            let senders: Vec<&str> = messages.iter().map(|m| m.sender.as_str()).collect();
+            assert_eq!(senders, ["Alice", "Bob", "Alice"], "{name}");
+            assert_eq!(messages[2].content, "Opening hours\nare 9 to 5", "{name}");
+            assert_eq!(
+                messages[1].timestamp,
+                Some(START + chrono::Duration::minutes(1)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_numeric_fixtures_detect_unchanged() {
+        ensure_fixtures();
+        for (name, expected) in [
+            ("whatsapp_us", DateFormat::US),
+            ("whatsapp_eu", DateFormat::EuDotBracketed),
+            ("whatsapp_quotes", DateFormat::US),
+            ("whatsapp_colon_sender", DateFormat::US),
+            ("whatsapp_dash_sender", DateFormat::EuDotNoBracket),
+            ("whatsapp_media_en", DateFormat::EuSlash),
+            ("whatsapp_media_de", DateFormat::EuDotNoBracket),
+            ("whatsapp_media_ios", DateFormat::US),
+            ("whatsapp_month_name", DateFormat::MonthName),
+            (
+                "whatsapp_month_name_bracketed",
+                DateFormat::MonthNameBracketed,
+            ),
+        ] {
+            let content = fs::read_to_string(format!("{}/{name}.txt", fixtures_dir())).unwrap();
+            let lines: Vec<&str> = content
+                .lines()
+                .map(|l| l.trim_start_matches('\u{200E}'))
+                .collect();
+            assert_eq!(detect_whatsapp_format(&lines), Some(expected), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_parse_us_format() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser
+            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
+            .unwrap();
+
+        assert!(!messages.is_empty());
+
+        let senders: Vec<&str> = messages.iter().map(|m| m.sender.as_str()).collect();
+        assert!(senders.contains(&"Alice"));
+        assert!(senders.contains(&"Bob"));
+    }
+
+    #[test]
+    fn test_parse_eu_format() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser
+            .parse_file(&format!("{}/whatsapp_eu.txt", fixtures_dir()))
+            .unwrap();
+
+        assert!(!messages.is_empty());
+        let has_cyrillic = messages.iter().any(|m| m.content.contains("Привет"));
+        assert!(has_cyrillic);
+    }
+
+    #[test]
+    fn test_system_messages_filtered() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser
+            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
+            .unwrap();
+
+        let has_system = messages.iter().any(|m| {
+            m.content.contains("end-to-end encrypted") || m.content.contains("сквозным шифрованием")
+        });
+        assert!(!has_system);
+    }
+
+    #[test]
+    fn test_media_preserved() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser
+            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
+            .unwrap();
+
+        let has_media = messages.iter().any(|m| m.content.contains("Media omitted"));
+        assert!(has_media);
+    }
+
+    #[test]
+    fn test_localized_media_placeholders_normalized() {
+        ensure_fixtures();
+        for locale in ["en", "de", "fr", "es", "pt", "ru", "tr", "ios"] {
+            let path = format!("{}/whatsapp_media_{locale}.txt", fixtures_dir());
+            let messages = create_parser(Platform::WhatsApp).parse_file(&path).unwrap();
+            assert_eq!(messages.len(), 1, "{locale}");
+            assert_eq!(messages[0].sender, "Alice", "{locale}");
+            assert_eq!(messages[0].content, "[Media omitted]", "{locale}");
+
+            let streamed: Vec<_> = create_streaming_parser(Platform::WhatsApp)
+                .stream_file(&path)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(streamed, messages, "{locale}");
+        }
+    }
+
+    #[test]
+    fn test_direction_marks_do_not_leak_into_senders() {
+        ensure_fixtures();
+        let path = format!("{}/whatsapp_media_ios.txt", fixtures_dir());
+        let config = WhatsAppConfig::new().with_normalize_media_placeholders(false);
+        let messages = WhatsAppParser::with_config(config)
+            .parse(Path::new(&path))
+            .unwrap();
+        assert_eq!(messages[0].sender, "Alice");
+        assert!(!messages[0].sender.contains(['\u{200E}', '\u{200F}']));
+        assert_eq!(messages[0].content, "\u{200E}image omitted");
+    }
+
+    #[test]
+    fn test_parse_with_utf8_bom() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser
+            .parse_file(&format!("{}/whatsapp_bom.txt", fixtures_dir()))
+            .unwrap();
+
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[0].content, "Hello everyone!");
+    }
+
+    #[test]
+    fn test_parse_utf16le() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser
+            .parse_file(&format!("{}/whatsapp_utf16le.txt", fixtures_dir()))
+            .unwrap();
+
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(messages[0].content, "Привет всем!");
+        assert!(messages.iter().all(|m| !m.content.contains('\r')));
+    }
+
+    #[test]
+    fn test_parse_undecodable_names_encoding() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.txt");
+        fs::write(&path, b"[1/15/24, 10:30:00 AM] Alice: \xFF\xFE\xFD").unwrap();
+
+        let parser = create_parser(Platform::WhatsApp);
+        let err = parser.parse(&path).unwrap_err();
+        assert!(err.is_invalid_format());
+        assert!(err.to_string().contains("detected encoding: UTF-8"));
+    }
+
+    #[test]
+    fn test_quoted_replies_resolve() {
+        ensure_fixtures();
+        let path = format!("{}/whatsapp_quotes.txt", fixtures_dir());
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser.parse_file(&path).unwrap();
+
+        let replies: Vec<_> = messages
+            .iter()
+            .map(|m| (m.content.as_str(), m.reply_to))
+            .collect();
+        assert_eq!(
+            replies,
+            [
+                ("Are we still on for lunch tomorrow?", None),
+                ("Let me check my calendar", None),
+                ("Yes, 12:30 works", Some(0)),
+                ("Great, see you there", Some(2)),
+                ("[Replying to: Did anyone book the table?]\nI did", None),
+            ]
+        );
+
+        let streamed: Vec<Message> = WhatsAppParser::with_config(WhatsAppConfig::streaming())
+            .stream(path.as_ref())
+            .unwrap()
+            .collect::<chatpack::Result<_>>()
+            .unwrap();
+        assert_eq!(streamed, messages);
+
+        let mut visited = Vec::new();
+        parser.parse_into(path.as_ref(), &mut visited).unwrap();
+        assert_eq!(visited, messages);
+    }
+
+    #[test]
+    fn test_quoted_replies_survive_output() {
+        use chatpack::core::output::{from_csv, from_jsonl, to_csv, to_jsonl};
+
+        ensure_fixtures();
+        let path = format!("{}/whatsapp_quotes.txt", fixtures_dir());
+        let messages = create_parser(Platform::WhatsApp).parse_file(&path).unwrap();
+        let config = OutputConfig::new().with_replies().with_index();
+
+        let jsonl = to_jsonl(&messages, &config).unwrap();
+        assert!(
+            jsonl.lines().nth(2).unwrap().starts_with(
+                r#"{"sender":"Bob","content":"Yes, 12:30 works","index":2,"reply_to":0}"#
+            ),
+            "{jsonl}"
+        );
+        let csv = to_csv(&messages, &config).unwrap();
+        let links = |messages: &[Message]| -> Vec<(Option<u64>, Option<u64>)> {
+            messages
+                .iter()
+                .map(|m| (m.source_index, m.reply_to))
+                .collect()
+        };
+        for read in [from_jsonl(&jsonl).unwrap(), from_csv(&csv).unwrap()] {
+            assert_eq!(links(&read), links(&messages));
+        }
+    }
+
+    #[test]
+    fn test_unresolved_quote_dropped_without_marker() {
+        ensure_fixtures();
+        let path = format!("{}/whatsapp_quotes.txt", fixtures_dir());
+        let config = WhatsAppConfig::new().with_mark_unresolved_quotes(false);
+
+        let messages = WhatsAppParser::with_config(config.clone())
+            .parse(path.as_ref())
+            .unwrap();
+        assert_eq!(messages[4].content, "I did");
+        assert_eq!(messages[4].reply_to, None);
+
+        let streamed: Vec<Message> = WhatsAppParser::with_config(config.with_streaming(true))
+            .stream(path.as_ref())
+            .unwrap()
+            .collect::<chatpack::Result<_>>()
+            .unwrap();
+        assert_eq!(streamed, messages);
+    }
+
+    #[test]
+    fn test_parser_name() {
+        let parser = create_parser(Platform::WhatsApp);
+        assert_eq!(parser.name(), "WhatsApp");
+    }
+
+    #[test]
+    fn test_consecutive_merge() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser
+            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
+            .unwrap();
+
+        let original = messages.len();
+        let merged = merge_consecutive(messages);
+
+        assert!(merged.len() <= original);
+    }
+}
+
+// ============================================================================
+// Instagram Parser Tests
+// ============================================================================
+
+mod instagram_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_instagram() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Instagram);
+        let messages = parser
+            .parse_file(&format!("{}/instagram.json", fixtures_dir()))
+            .unwrap();
+
+        assert!(!messages.is_empty());
+
+        let senders: Vec<&str> = messages.iter().map(|m| m.sender.as_str()).collect();
+        assert!(senders.contains(&"user_one"));
+        assert!(senders.contains(&"user_two"));
+    }
+
+    #[test]
+    fn test_shared_links() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Instagram);
+        let messages = parser
+            .parse_file(&format!("{}/instagram.json", fixtures_dir()))
+            .unwrap();
+
+        let has_shared = messages.iter().any(|m| {
+            m.content.contains("Shared") || m.content.contains("https://instagram.com/p/xyz")
+        });
+        assert!(has_shared, "Should contain 'Shared' or the actual link");
+    }
+
+    #[test]
+    fn test_empty_content_filtered() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Instagram);
+        let messages = parser
+            .parse_file(&format!("{}/instagram.json", fixtures_dir()))
+            .unwrap();
+
+        let has_empty = messages.iter().any(|m| m.content.is_empty());
+        assert!(!has_empty);
+    }
+
+    #[test]
+    fn test_parser_name() {
+        let parser = create_parser(Platform::Instagram);
+        assert_eq!(parser.name(), "Instagram");
+    }
+
+    #[test]
+    fn test_timestamps() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Instagram);
+        let messages = parser
+            .parse_file(&format!("{}/instagram.json", fixtures_dir()))
+            .unwrap();
+
+        assert!(messages.iter().all(|m| m.timestamp.is_some()));
+    }
+}
+
+// ============================================================================
+// Custom Platform Tests
+// ============================================================================
+
+mod custom_platform_tests {
+    use super::*;
+    use chatpack::parser::{create_parser_by_name, register_parser};
+
+    /// `YYYY-MM-DD HH:MM | sender | text` lines.
+    struct LogParser;
+
+    impl Parser for LogParser {
+        fn name(&self) -> &'static str {
+            "Log"
+        }
+
+        fn platform(&self) -> Platform {
+            Platform::Custom("chatlog")
+        }
+
+        fn parse(&self, path: &Path) -> chatpack::Result<Vec<Message>> {
+            self.parse_str(&fs::read_to_string(path)?)
+        }
+
+        fn parse_str(&self, content: &str) -> chatpack::Result<Vec<Message>> {
+            let mut messages = Vec::new();
+            for line in content.lines() {
+                let mut parts = line.splitn(3, " | ");
+                let (Some(date), Some(sender), Some(text)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                let timestamp = chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M")
+                    .map_err(|_| chatpack::ChatpackError::invalid_date(date))?
+                    .and_utc();
+                messages.push(Message::new(sender, text).with_timestamp(timestamp));
+            }
+            Ok(messages)
+        }
+    }
+
+    #[test]
+    fn test_custom_parser_end_to_end() {
+        let platform = register_parser("chatlog", &["cl"], || Box::new(LogParser)).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("export.log");
+        fs::write(
+            &input,
+            "2024-01-15 10:00 | Alice | Morning\n\
+             2024-01-15 10:01 | Alice | Anyone here?\n\
+             2024-01-15 10:05 | Bob | Yes\n\
+             2024-01-16 09:00 | Alice | Next day\n",
+        )
+        .unwrap();
+
+        // Same resolution path as a user-supplied source name
+        let parser = create_parser_by_name("CL").unwrap();
+        assert_eq!(parser.platform(), platform);
+        let messages = parser.parse(&input).unwrap();
+        assert_eq!(messages.len(), 4);
+
+        let merged = merge_consecutive(messages);
+        let config = FilterConfig::new().before_date("2024-01-15").unwrap();
+        let filtered = apply_filters(merged, &config);
+        assert_eq!(filtered.len(), 2);
+
+        let output = dir.path().join("out.jsonl");
+        write_jsonl(
+            &filtered,
+            output.to_str().unwrap(),
+            &OutputConfig::new().with_timestamps(),
+        )
+        .unwrap();
+
+        let written = fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("Morning\\nAnyone here?"));
+        assert!(lines[1].contains("\"sender\":\"Bob\""));
+    }
+}
+
+// ============================================================================
+// Diff Tests
+// ============================================================================
+
+mod diff_tests {
+    use super::*;
+    use chatpack::core::diff_messages;
+    use chatpack::core::output::read_jsonl;
+
+    #[test]
+    fn test_diff_against_previous_jsonl_output() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::WhatsApp);
+        let messages = parser
+            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
+            .unwrap();
+        let first_sender = messages[0].sender.clone();
+
+        // Previous run: everything, written as JSONL with timestamps
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("old.jsonl");
+        let config = OutputConfig::new().with_timestamps();
+        write_jsonl(&messages, old_path.to_str().unwrap(), &config).unwrap();
+        let old = read_jsonl(&old_path).unwrap();
+        assert_eq!(old, messages);
+
+        // New run: one sender filtered out, one message re-normalized
+        let mut new: Vec<_> = messages
+            .iter()
+            .filter(|m| m.sender != first_sender)
+            .cloned()
+            .collect();
+        new[0].content = new[0].content.to_uppercase();
+
+        let diff = diff_messages(&old, &new);
+        let removed = messages.iter().filter(|m| m.sender == first_sender).count();
+        assert_eq!(diff.removed.len(), removed);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].new, new[0]);
+        assert_eq!(diff.listing().lines().count(), removed + 1);
+    }
+}
+
+// ============================================================================
+// Seeded Randomness Tests
+// ============================================================================
+
+mod seed_tests {
+    use super::*;
+    use chatpack::core::output::to_jsonl;
+    use chatpack::core::{Seed, anonymize_senders, balance_senders, sample_messages};
+
+    /// Runs the randomized steps end to end and returns the JSONL bytes.
+    fn run(messages: &[Message], seed: Seed) -> String {
+        let mut out = sample_messages(messages.to_vec(), 40, seed);
+        out = balance_senders(out, 10, seed);
+        anonymize_senders(&mut out, seed);
+        to_jsonl(&out, &OutputConfig::new().with_timestamps()).unwrap()
+    }
+
+    #[test]
+    fn test_same_seed_byte_identical_output() {
+        ensure_fixtures();
+        let parser = create_parser(Platform::Telegram);
+        let mut messages = Vec::new();
+        for _ in 0..20 {
+            messages.extend(
+                parser
+                    .parse_file(&format!("{}/telegram_simple.json", fixtures_dir()))
+                    .unwrap(),
+            );
+        }
+
+        assert_eq!(
+            run(&messages, Seed::default()),
+            run(&messages, Seed::default())
+        );
+        assert_ne!(run(&messages, Seed::new(1)), run(&messages, Seed::new(2)));
+    }
+}
+
+// ============================================================================
+// Sink (parse_into) Tests
+// ============================================================================
+
+mod sink_tests {
+    use super::*;
+    use chatpack::parsers::{DiscordParser, WhatsAppParser};
+    use chatpack::sink::{CollectingSink, MessageRef};
+    use std::ops::ControlFlow;
+
+    fn collect(parser: &dyn Parser, path: &Path) -> Vec<Message> {
+        let mut messages: Vec<Message> = Vec::new();
+        parser.parse_into(path, &mut messages).unwrap();
+        messages
+    }
+
+    #[test]
+    fn test_parse_into_matches_parse() {
+        ensure_fixtures();
+        let cases = [
+            (Platform::Discord, "discord.jsonl"),
+            (Platform::Discord, "discord.json"),
+            (Platform::WhatsApp, "whatsapp_us.txt"),
+            (Platform::WhatsApp, "whatsapp_eu.txt"),
+            (Platform::WhatsApp, "whatsapp_bom.txt"),
+            (Platform::WhatsApp, "whatsapp_utf16le.txt"),
+            (Platform::Telegram, "telegram_simple.json"),
+        ];
+        for (platform, file) in cases {
+            let path = format!("{}/{file}", fixtures_dir());
+            let parser = create_parser(platform);
+            assert_eq!(
+                collect(parser.as_ref(), Path::new(&path)),
+                parser.parse_file(&path).unwrap(),
+                "{file}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_whatsapp_parse_into_multiline_past_sample() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        let mut chat = String::new();
+        for i in 0..40 {
+            chat.push_str(&format!("[1/15/24, 10:{i:02}:00 AM] Alice: line {i}\r\n"));
+            if i % 3 == 0 {
+                chat.push_str("continued\r\n\r\n");
+            }
+            if i % 7 == 0 {
+                chat.push_str(&format!(
+                    "[1/15/24, 10:{i:02}:30 AM] Bob: joined using this group's invite link\r\n"
+                ));
+                chat.push_str("after system\r\n");
+            }
+        }
+        fs::write(&path, chat).unwrap();
+
+        let parser = WhatsAppParser::new();
+        let expected = parser.parse(&path).unwrap();
+        assert_eq!(expected.len(), 40);
+        assert_eq!(collect(&parser, &path), expected);
+    }
+
+    #[test]
+    fn test_parse_into_reports_skips() {
+        ensure_fixtures();
+        let path = format!("{}/discord_corrupt.jsonl", fixtures_dir());
+        let parser = DiscordParser::new();
+
+        let mut sink = CollectingSink::new();
+        parser.parse_into(Path::new(&path), &mut sink).unwrap();
+        let (messages, skipped) = parser.parse_counted(Path::new(&path)).unwrap();
+        assert_eq!(sink.messages, messages);
+        assert_eq!(sink.skipped, skipped);
+    }
+
+    #[test]
+    fn test_break_stops_reading() {
+        let dir = TempDir::new().unwrap();
+        // JSONL is recognized by extension or, failing that, by content
+        for name in ["channel.jsonl", "channel.log"] {
+            let path = dir.path().join(name);
+            fs::write(
+                &path,
+                "{\"id\":\"1\",\"timestamp\":\"\",\"content\":\"First\",\"author\":{\"name\":\"alice\"}}\nnot json\n",
+            )
+            .unwrap();
+
+            // The invalid second line is an error, but only if it is read
+            let parser = DiscordParser::with_config(DiscordConfig::new().with_skip_invalid(false));
+            assert!(parser.parse(&path).is_err(), "{name}");
+
+            let mut seen = Vec::new();
+            let mut sink = |msg: MessageRef<'_>| {
+                seen.push(msg.content.to_string());
+                ControlFlow::Break(())
+            };
+            parser.parse_into(&path, &mut sink).unwrap();
+            assert_eq!(seen, ["First"], "{name}");
+        }
+    }
+
+    #[test]
+    fn test_whatsapp_parse_into_checks_file_size() {
+        ensure_fixtures();
+        let path = format!("{}/whatsapp_us.txt", fixtures_dir());
+        let parser = WhatsAppParser::with_config(WhatsAppConfig::new().with_max_file_size(10));
+        let mut messages: Vec<Message> = Vec::new();
+        let err = parser
+            .parse_into(Path::new(&path), &mut messages)
+            .unwrap_err();
+        assert!(matches!(err, ChatpackError::FileTooLarge { .. }), "{err}");
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn test_break_whatsapp() {
+        ensure_fixtures();
+        let path = format!("{}/whatsapp_us.txt", fixtures_dir());
+        let mut calls = 0;
+        let mut sink = |_: MessageRef<'_>| {
+            calls += 1;
+            ControlFlow::Break(())
+        };
+        WhatsAppParser::new()
+            .parse_into(Path::new(&path), &mut sink)
+            .unwrap();
+        assert_eq!(calls, 1);
+    }
+}
+
+// ============================================================================
+// Pipelined Processing Tests
+// ============================================================================
+
+mod pipeline_tests {
+    use super::*;
+    use chatpack::ChatpackError;
+    use chatpack::core::output::{OnEmpty, read_jsonl, write_jsonl};
+    use chatpack::format::OutputFormat;
+    use chatpack::format::{read_from_format, write_to_format};
+    use chatpack::pipeline::{PipelineConfig, run_pipelined, run_sequential, verify};
+
+    #[test]
+    fn test_pipelined_matches_sequential() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let inputs = [
+            (Platform::Telegram, "telegram_simple.json"),
+            (Platform::WhatsApp, "whatsapp_us.txt"),
+            (Platform::WhatsApp, "whatsapp_utf16le.txt"),
+            (Platform::Instagram, "instagram.json"),
+            (Platform::Discord, "discord.json"),
+            (Platform::Discord, "discord.jsonl"),
+            (Platform::Discord, "discord_nicknames.jsonl"),
+        ];
+        let filter = FilterConfig::new().with_sender("Alice");
+
+        for (platform, file) in inputs {
+            for format in [OutputFormat::Csv, OutputFormat::Json, OutputFormat::Jsonl] {
+                for (merge, filter) in [(false, None), (true, None), (true, Some(&filter))] {
+                    let seq_out = dir.path().join(format!("seq.{}", format.extension()));
+                    let par_out = dir.path().join(format!("par.{}", format.extension()));
+                    let input = format!("{}/{file}", fixtures_dir());
+                    let config = |out: &Path| {
+                        PipelineConfig::new(platform, &input, out)
+                            .with_format(format)
+                            .with_output_config(OutputConfig::all())
+                            .with_filter(filter.cloned().unwrap_or_default())
+                            .with_merge(merge)
+                            .with_channel_capacity(1)
+                    };
+
+                    let seq_stats = run_sequential(&config(&seq_out)).unwrap();
+                    let par_stats = run_pipelined(&config(&par_out)).unwrap();
+                    assert_eq!(seq_stats, par_stats, "{file} {format:?}");
+                    assert_eq!(
+                        fs::read(&seq_out).unwrap(),
+                        fs::read(&par_out).unwrap(),
+                        "{file} {format:?} merge={merge}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_memory_budget_matches_unbounded() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let inputs = [
+            (Platform::Telegram, "telegram_simple.json"),
+            (Platform::Telegram, "telegram_complex.json"),
+            (Platform::Telegram, "telegram_meta.json"),
+            (Platform::WhatsApp, "whatsapp_us.txt"),
+            (Platform::Instagram, "instagram.json"),
+            (Platform::Discord, "discord.json"),
+            (Platform::Discord, "discord.jsonl"),
+            (Platform::Discord, "discord.txt"),
+            (Platform::Discord, "discord.csv"),
+            (Platform::Discord, "discord_nicknames.jsonl"),
+        ];
+        let filter = FilterConfig::new().with_sender("Alice");
+
+        for (platform, file) in inputs {
+            for format in [OutputFormat::Csv, OutputFormat::Json, OutputFormat::Jsonl] {
+                for (merge, filter) in [(false, None), (true, None), (true, Some(&filter))] {
+                    let out = dir.path().join(format!("out.{}", format.extension()));
+                    let input = format!("{}/{file}", fixtures_dir());
+                    let config = PipelineConfig::new(platform, &input, &out)
+                        .with_format(format)
+                        .with_output_config(OutputConfig::all())
+                        .with_filter(filter.cloned().unwrap_or_default())
+                        .with_merge(merge);
+
+                    let stats = run_sequential(&config).unwrap();
+                    let expected = fs::read(&out).unwrap();
+                    // One message per window, then a few
+                    for budget in [1, 10_000] {
+                        let bounded = config.clone().with_max_memory(budget);
+                        assert_eq!(
+                            run_sequential(&bounded).unwrap(),
+                            stats,
+                            "{file} {format:?} merge={merge} budget={budget}"
+                        );
+                        assert_eq!(
+                            fs::read(&out).unwrap(),
+                            expected,
+                            "{file} {format:?} merge={merge} budget={budget}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Mismatches `verify` reports for `config` after `stats`.
+    fn mismatches(config: &PipelineConfig, stats: &ProcessingStats) -> Vec<String> {
+        match verify(config, stats) {
+            Err(ChatpackError::VerificationFailed { mismatches, .. }) => mismatches,
+            other => panic!("expected a verification failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_verify_catches_corrupted_output() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/telegram_simple.json", fixtures_dir());
+
+        for format in [OutputFormat::Csv, OutputFormat::Json, OutputFormat::Jsonl] {
+            let out = dir.path().join(format!("out.{}", format.extension()));
+            let config = PipelineConfig::new(Platform::Telegram, &input, &out)
+                .with_format(format)
+                .with_output_config(OutputConfig::all())
+                .with_merge(true);
+            let stats = run_pipelined(&config).unwrap();
+            verify(&config, &stats).unwrap();
+
+            // Drop the last entry and blank the first sender
+            let mut written = read_from_format(&out, format).unwrap();
+            written.pop();
+            written[0].sender.clear();
+            let path = out.to_str().unwrap();
+            write_to_format(&written, path, format, &OutputConfig::all()).unwrap();
+
+            let found = mismatches(&config, &stats);
+            assert_eq!(found.len(), 2, "{format:?}: {found:?}");
+            assert!(found[0].starts_with("expected "), "{found:?}");
+            assert_eq!(found[1], "no sender on entry 1");
+        }
+    }
+
+    #[test]
+    fn test_verify_order_and_sender_filter() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().join("out.jsonl");
+        let config = PipelineConfig::new(
+            Platform::WhatsApp,
+            format!("{}/whatsapp_us.txt", fixtures_dir()),
+            &out,
+        )
+        .with_format(OutputFormat::Jsonl)
+        .with_output_config(OutputConfig::all())
+        .with_filter(FilterConfig::new().with_sender("Alice"));
+        let stats = run_sequential(&config).unwrap();
+        verify(&config, &stats).unwrap();
+
+        let mut written = read_jsonl(&out).unwrap();
+        assert!(written.len() >= 2);
+        written.swap(0, 1);
+        written[1].sender = "Mallory".into();
+        write_jsonl(&written, out.to_str().unwrap(), &OutputConfig::all()).unwrap();
+
+        let found = mismatches(&config, &stats);
+        assert_eq!(
+            found,
+            [
+                "timestamp earlier than the one before on entry 2",
+                "sender other than the filtered \"Alice\" on entry 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_skipped_empty_output() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let config = PipelineConfig::new(
+            Platform::WhatsApp,
+            format!("{}/whatsapp_us.txt", fixtures_dir()),
+            dir.path().join("out.csv"),
+        )
+        .with_filter(FilterConfig::new().with_sender("Nobody"))
+        .with_on_empty(OnEmpty::SkipFile);
+        let stats = run_pipelined(&config).unwrap();
+        verify(&config, &stats).unwrap();
+    }
+
+    #[test]
+    fn test_pipelined_errors_propagate() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+
+        let missing_input = PipelineConfig::new(
+            Platform::Telegram,
+            dir.path().join("missing.json"),
+            dir.path().join("out.csv"),
+        );
+        assert!(run_pipelined(&missing_input).unwrap_err().is_io());
+
+        let unwritable = PipelineConfig::new(
+            Platform::WhatsApp,
+            format!("{}/whatsapp_us.txt", fixtures_dir()),
+            dir.path().join("no/such/dir/out.csv"),
+        )
+        .with_channel_capacity(1);
+        assert!(run_pipelined(&unwritable).unwrap_err().is_io());
+    }
+}
+
+// ============================================================================
+// One-Call Reading Tests
+// ============================================================================
+
+mod reader_tests {
+    use super::*;
+    use chatpack::{read, read_as, read_bytes, read_bytes_as, read_from};
+
+    const FIXTURES: [(Platform, &str); 8] = [
+        (Platform::Telegram, "telegram_simple.json"),
+        (Platform::WhatsApp, "whatsapp_us.txt"),
+        (Platform::WhatsApp, "whatsapp_utf16le.txt"),
+        (Platform::Instagram, "instagram.json"),
+        (Platform::Discord, "discord.json"),
+        (Platform::Discord, "discord.jsonl"),
+        (Platform::Discord, "discord.txt"),
+        (Platform::Discord, "discord.csv"),
+    ];
+
+    #[test]
+    fn test_read_every_fixture() {
+        ensure_fixtures();
+        for (platform, file) in FIXTURES {
+            let path = format!("{}/{file}", fixtures_dir());
+            let expected = create_parser(platform).parse(path.as_ref()).unwrap();
+            assert!(!expected.is_empty(), "{file}");
+
+            assert_eq!(read(&path).unwrap(), expected, "{file}");
+            assert_eq!(read_as(platform, &path).unwrap(), expected, "{file}");
+            let bytes = fs::read(&path).unwrap();
+            assert_eq!(read_bytes(bytes.as_slice()).unwrap(), expected, "{file}");
+            assert_eq!(read_bytes_as(platform, bytes).unwrap(), expected, "{file}");
+            assert_eq!(
+                read_from(fs::File::open(&path).unwrap()).unwrap(),
+                expected,
+                "{file}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_errors_name_platforms() {
+        let err = read_bytes(r#"{"rows": []}"#).unwrap_err();
+        assert!(err.is_invalid_format());
+        let message = err.to_string();
+        assert!(message.contains("the content: it is JSON"), "{message}");
+        assert!(message.contains("telegram, whatsapp, instagram, discord"));
+
+        assert!(read_bytes("").unwrap_err().to_string().contains("empty"));
+        assert!(read("missing/result.json").unwrap_err().is_io());
+    }
+}
+
+// ============================================================================
+// Corpus Building Tests
+// ============================================================================
+
+mod corpus_tests {
+    use super::*;
+    use chatpack::core::output::read_jsonl;
+    use chatpack::pipeline::{CorpusConfig, CorpusSource, PipelineConfig, build_corpus};
+
+    fn sources() -> Vec<CorpusSource> {
+        ensure_fixtures();
+        [
+            ("telegram_simple.json", "team"),
+            ("whatsapp_us.txt", "family"),
+            ("discord.jsonl", "server"),
+        ]
+        .iter()
+        .map(|(file, label)| CorpusSource::new(format!("{}/{file}", fixtures_dir()), *label))
+        .collect()
+    }
+
+    #[test]
+    fn test_corpus_labels_every_record() {
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().join("corpus.jsonl");
+        let sources = sources();
+        let config = CorpusConfig::new()
+            .with_merge(true)
+            .with_channel_capacity(1);
+
+        let report = build_corpus(&sources, &config, &out).unwrap();
+        assert!(report.is_complete());
+
+        // The corpus is each source converted on its own, labeled, in order
+        let mut expected = Vec::new();
+        for (source, converted) in sources.iter().zip(&report.converted) {
+            let single = dir.path().join(format!("{}.jsonl", source.label));
+            let platform = chatpack::parser::detect_platform(&source.path).unwrap();
+            let pipeline = PipelineConfig::new(platform, &source.path, &single)
+                .with_format(OutputFormat::Jsonl)
+                .with_merge(true);
+            let stats = chatpack::pipeline::run_sequential(&pipeline).unwrap();
+            assert_eq!(converted.label, source.label);
+            assert_eq!(converted.stats, stats);
+            expected.extend(
+                read_jsonl(&single)
+                    .unwrap()
+                    .into_iter()
+                    .map(|msg| msg.with_source(source.label.as_str())),
+            );
+        }
+        let records = read_jsonl(&out).unwrap();
+        assert_eq!(records, expected);
+        assert_eq!(records.len(), report.total().merged_count);
+        for source in &sources {
+            assert!(
+                records
+                    .iter()
+                    .any(|msg| msg.source() == Some(source.label.as_str()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_corpus_collects_failures() {
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().join("corpus.jsonl");
+        let mut sources = sources();
+        sources.insert(
+            1,
+            CorpusSource::new(dir.path().join("missing.json"), "gone"),
+        );
+        let notes = dir.path().join("notes.txt");
+        fs::write(&notes, "not a chat export\n").unwrap();
+        sources.insert(2, CorpusSource::new(&notes, "undetectable"));
+
+        let report = build_corpus(&sources, &CorpusConfig::new(), &out).unwrap();
+        let failed: Vec<&str> = report.failed.iter().map(|f| f.label.as_str()).collect();
+        assert_eq!(failed, ["gone", "undetectable"]);
+        assert_eq!(report.converted.len(), 3);
+        let labels: Vec<Option<String>> = read_jsonl(&out)
+            .unwrap()
+            .into_iter()
+            .map(|msg| msg.source)
+            .collect();
+        assert!(labels.iter().all(|label| label.as_deref() != Some("gone")));
+        assert_eq!(labels.len(), report.total().merged_count);
+
+        let fail_fast = CorpusConfig::new().with_fail_fast(true);
+        let err = build_corpus(&sources, &fail_fast, dir.path().join("fast.jsonl")).unwrap_err();
+        assert!(err.is_io());
+    }
+}
+
+// ============================================================================
+// One-Call Conversion Tests
+// ============================================================================
+
+mod convert_tests {
+    use super::*;
+    use chatpack::format::{OutputFormat, write_to_format};
+
+    const FIXTURES: [(Platform, &str); 8] = [
+        (Platform::Telegram, "telegram_simple.json"),
+        (Platform::WhatsApp, "whatsapp_us.txt"),
+        (Platform::WhatsApp, "whatsapp_utf16le.txt"),
+        (Platform::Instagram, "instagram.json"),
+        (Platform::Discord, "discord.json"),
+        (Platform::Discord, "discord.jsonl"),
+        (Platform::Discord, "discord.txt"),
+        (Platform::Discord, "discord.csv"),
+    ];
+
+    #[test]
+    fn test_convert_detects_every_fixture() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+
+        for (platform, file) in FIXTURES {
+            let input = format!("{}/{file}", fixtures_dir());
+            assert_eq!(chatpack::parser::detect_platform(&input).unwrap(), platform);
+
+            for format in [OutputFormat::Csv, OutputFormat::Json, OutputFormat::Jsonl] {
+                let out = dir.path().join(format!("out.{}", format.extension()));
+                let stats = convert(&input, &out, ConvertOptions::default()).unwrap();
+
+                let messages = create_parser(platform).parse(input.as_ref()).unwrap();
+                let original = messages.len();
+                let merged = merge_consecutive(messages);
+                let expected = dir.path().join(format!("expected.{}", format.extension()));
+                write_to_format(
+                    &merged,
+                    expected.to_str().unwrap(),
+                    format,
+                    &OutputConfig::default(),
+                )
+                .unwrap();
+
+                // The loss and ordering reports are covered by
+                // `loss_report_tests` and `ordering_tests`
+                assert_eq!(
+                    stats,
+                    ProcessingStats::new(original, merged.len())
+                        .with_loss(stats.loss.clone())
+                        .with_ordering(stats.ordering)
+                );
+                assert_eq!(
+                    fs::read(&out).unwrap(),
+                    fs::read(&expected).unwrap(),
+                    "{file} {format:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_convert_merges_senders_ignoring_case() {
+        use chatpack::core::{MergeConfig, SenderMatch};
+
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/discord_nicknames.jsonl", fixtures_dir());
+        let out = dir.path().join("out.jsonl");
+
+        let stats = convert(&input, &out, ConvertOptions::default()).unwrap();
+        assert_eq!((stats.original_count, stats.merged_count), (3, 2));
+        let written = fs::read_to_string(&out).unwrap();
+        assert!(
+            written
+                .lines()
+                .next()
+                .unwrap()
+                .contains(r#""sender":"Alice""#)
+        );
+        assert!(written.contains(r"Morning\nAnyone up?"));
+
+        let exact = MergeConfig::new().with_sender_match(SenderMatch::Exact);
+        let options = ConvertOptions::new().with_merge_config(exact);
+        let stats = convert(&input, &out, options).unwrap();
+        assert_eq!(stats.merged_count, 3);
+    }
+
+    #[test]
+    fn test_convert_with_options() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().join("alice.txt");
+        let options = ConvertOptions::new()
+            .with_platform(Platform::WhatsApp)
+            .with_format(OutputFormat::Jsonl)
+            .with_filter(FilterConfig::new().with_sender("Alice"))
+            .with_merge(false);
+
+        let stats = convert(format!("{}/whatsapp_us.txt", fixtures_dir()), &out, options).unwrap();
+
+        let written = fs::read_to_string(&out).unwrap();
+        assert_eq!(stats.filtered_count, Some(stats.merged_count));
+        assert_eq!(written.lines().count(), stats.merged_count);
+        assert!(written.lines().all(|line| line.contains("Alice")));
+    }
+
+    #[test]
+    fn test_convert_records_sample_seed() {
+        use chatpack::core::Seed;
+
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/telegram_simple.json", fixtures_dir());
+        let (first, second) = (dir.path().join("a.jsonl"), dir.path().join("b.jsonl"));
+        let options = ConvertOptions::new()
+            .with_merge(false)
+            .with_sample(2)
+            .with_seed(Seed::new(7));
+
+        let stats = convert(&input, &first, options.clone()).unwrap();
+        convert(&input, &second, options).unwrap();
+        assert_eq!(stats.seed, Some(Seed::new(7)));
+        assert_eq!(stats.merged_count, 2);
+        assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+        let stats = convert(&input, &first, ConvertOptions::new()).unwrap();
+        assert_eq!(stats.seed, None);
+    }
+
+    #[test]
+    fn test_convert_errors() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/telegram_simple.json", fixtures_dir());
+
+        let unknown_ext = convert(
+            &input,
+            dir.path().join("out.xml"),
+            ConvertOptions::default(),
+        );
+        assert!(unknown_ext.is_err());
+
+        let empty = dir.path().join("empty.txt");
+        fs::write(&empty, "").unwrap();
+        let undetectable = convert(
+            &empty,
+            dir.path().join("out.csv"),
+            ConvertOptions::default(),
+        );
+        assert!(undetectable.is_err());
+
+        let missing = convert(
+            dir.path().join("missing.json"),
+            dir.path().join("out.csv"),
+            ConvertOptions::default(),
+        );
+        assert!(missing.unwrap_err().is_io());
+    }
+
+    #[test]
+    fn test_convert_strict_filters() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/whatsapp_us.txt", fixtures_dir());
+        let out = dir.path().join("out.csv");
+        let inverted = FilterConfig::new()
+            .with_date_from("2024-06-01")
+            .unwrap()
+            .with_date_to("2024-01-01")
+            .unwrap();
+
+        let lenient = convert(
+            &input,
+            &out,
+            ConvertOptions::new().with_filter(inverted.clone()),
+        )
+        .unwrap();
+        assert_eq!(lenient.merged_count, 0);
+
+        let strict = ConvertOptions::new()
+            .with_filter(inverted)
+            .with_strict_filters(true);
+        let err = convert(&input, dir.path().join("strict.csv"), strict).unwrap_err();
+        assert!(err.is_invalid_filter());
+        assert!(!dir.path().join("strict.csv").exists());
+    }
+
+    #[test]
+    fn test_convert_on_empty() {
+        use chatpack::core::output::OnEmpty;
+        use chatpack::error::{EXIT_EMPTY, error_exit_code};
+
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/whatsapp_us.txt", fixtures_dir());
+        let nobody = ConvertOptions::new().with_filter(FilterConfig::new().with_sender("Nobody"));
+
+        let out = dir.path().join("error.csv");
+        let err = convert(&input, &out, nobody.clone().with_on_empty(OnEmpty::Error)).unwrap_err();
+        assert!(err.is_empty_output());
+        assert_eq!(error_exit_code(&err), EXIT_EMPTY);
+        assert!(err.to_string().contains("error.csv"));
+        assert!(!out.exists());
+
+        let out = dir.path().join("skip.jsonl");
+        let stats = convert(
+            &input,
+            &out,
+            nobody.clone().with_on_empty(OnEmpty::SkipFile),
+        )
+        .unwrap();
+        assert_eq!(stats.merged_count, 0);
+        assert!(!out.exists());
+
+        // The default still writes an empty output, byte for byte as before
+        for format in [OutputFormat::Csv, OutputFormat::Json, OutputFormat::Jsonl] {
+            let out = dir.path().join(format!("empty.{}", format.extension()));
+            let expected = dir.path().join(format!("expected.{}", format.extension()));
+            convert(&input, &out, nobody.clone()).unwrap();
+            write_to_format(
+                &[],
+                expected.to_str().unwrap(),
+                format,
+                &OutputConfig::default(),
+            )
+            .unwrap();
+            assert_eq!(fs::read(&out).unwrap(), fs::read(&expected).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_convert_many_boundaries() {
+        use chatpack::convert::convert_many;
+        use chatpack::core::models::BoundaryStyle;
+
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let inputs: Vec<String> = ["telegram_simple.json", "whatsapp_us.txt", "discord.jsonl"]
+            .iter()
+            .map(|file| format!("{}/{file}", fixtures_dir()))
+            .collect();
+        let marker = "<|conversation|>";
+        let with_style = |style: BoundaryStyle| {
+            ConvertOptions::new()
+                .with_output_config(OutputConfig::new().with_conversation_boundaries(style))
+        };
+
+        // One marker record per source, before its first message
+        let out = dir.path().join("marker.jsonl");
+        let stats = convert_many(
+            &inputs,
+            &out,
+            with_style(BoundaryStyle::Marker(marker.into())),
+        )
+        .unwrap();
+        let records = chatpack::core::output::read_jsonl(&out).unwrap();
+        assert_eq!(records.iter().filter(|m| m.content == marker).count(), 3);
+        assert_eq!(records[0].content, marker);
+        assert_eq!(records.len(), stats.merged_count + 3);
+
+        // One conversation_start field per source
+        let out = dir.path().join("meta.jsonl");
+        convert_many(&inputs, &out, with_style(BoundaryStyle::MetaField)).unwrap();
+        let written = fs::read_to_string(&out).unwrap();
+        assert_eq!(written.matches(r#""conversation_start":true"#).count(), 3);
+        assert!(
+            written
+                .lines()
+                .next()
+                .unwrap()
+                .contains("conversation_start")
+        );
+
+        let out = dir.path().join("meta.csv");
+        convert_many(&inputs, &out, with_style(BoundaryStyle::MetaField)).unwrap();
+        let written = fs::read_to_string(&out).unwrap();
+        assert!(written.starts_with("Sender;Content;ConversationStart\n"));
+        assert_eq!(written.matches(";true\n").count(), 3);
+
+        // Single-file output never gets boundaries
+        for input in &inputs {
+            let out = dir.path().join("single.jsonl");
+            convert(
+                input,
+                &out,
+                with_style(BoundaryStyle::Marker(marker.into())),
+            )
+            .unwrap();
+            assert!(!fs::read_to_string(&out).unwrap().contains(marker));
+
+            convert(input, &out, with_style(BoundaryStyle::MetaField)).unwrap();
+            assert!(
+                !fs::read_to_string(&out)
+                    .unwrap()
+                    .contains("conversation_start")
+            );
+        }
+    }
+}
+
+// ============================================================================
+// Content Policy Tests
+// ============================================================================
+
+mod content_policy_tests {
+    use super::*;
+    use chatpack::config::{
+        ContentPolicy, DiscordConfig, InstagramConfig, TelegramConfig, WhatsAppConfig,
+    };
+    use chatpack::parsers::{DiscordParser, InstagramParser, TelegramParser, WhatsAppParser};
+
+    /// Asserts that `parse`, native streaming and `parse_into` all yield
+    /// exactly `expected`.
+    fn assert_contents(batch: &dyn Parser, streaming: &dyn Parser, path: &Path, expected: &[&str]) {
+        let parsed = batch.parse(path).unwrap();
+        let streamed: Vec<Message> = streaming
+            .stream(path)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let mut visited: Vec<Message> = Vec::new();
+        batch.parse_into(path, &mut visited).unwrap();
+
+        for (name, messages) in [
+            ("parse", parsed),
+            ("stream", streamed),
+            ("parse_into", visited),
+        ] {
+            let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, expected, "{name}");
+        }
+    }
+
+    fn collapse() -> ContentPolicy {
+        ContentPolicy::new().with_collapse_internal_blank_lines(true)
+    }
+
+    #[test]
+    fn test_telegram() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("result.json");
+        fs::write(
+            &path,
+            r#"{"messages": [
+  {"id": 1, "type": "message", "date": "2024-01-15T10:30:00", "date_unixtime": "1705314600", "from": "Alice", "text": "  hello  \n\n\n  world  "},
+  {"id": 2, "type": "message", "date": "2024-01-15T10:31:00", "date_unixtime": "1705314660", "from": "Bob", "text": "look   \n\n[Attachment: cat.png]\n"}
+]}"#,
+        )
+        .unwrap();
+
+        let cases: [(ContentPolicy, [&str; 2]); 3] = [
+            (
+                ContentPolicy::new(),
+                ["hello  \n\n\n  world", "look\n[Attachment: cat.png]"],
+            ),
+            (
+                collapse(),
+                ["hello  \n\n  world", "look\n[Attachment: cat.png]"],
+            ),
+            (
+                ContentPolicy::raw(),
+                [
+                    "  hello  \n\n\n  world  ",
+                    "look   \n\n[Attachment: cat.png]\n",
+                ],
+            ),
+        ];
+        for (policy, expected) in cases {
+            let config = TelegramConfig::new().with_content_policy(policy);
+            assert_contents(
+                &TelegramParser::with_config(config.clone()),
+                &TelegramParser::with_config(config.with_streaming(true)),
+                &path,
+                &expected,
+            );
+        }
+    }
+
+    #[test]
+    fn test_whatsapp() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        fs::write(
+            &path,
+            "[1/15/24, 10:30:00 AM] Alice:   hello  \r\n  indented  \r\n\
+             [1/15/24, 10:31:00 AM] Bob: look   \r\n[Attachment: cat.png]\r\n",
+        )
+        .unwrap();
+
+        let cases: [(ContentPolicy, [&str; 2]); 2] = [
+            (
+                ContentPolicy::new(),
+                ["hello  \n  indented", "look\n[Attachment: cat.png]"],
+            ),
+            (
+                ContentPolicy::raw(),
+                ["  hello  \n  indented  ", "look   \n[Attachment: cat.png]"],
+            ),
+        ];
+        for (policy, expected) in cases {
+            let config = WhatsAppConfig::new().with_content_policy(policy);
+            assert_contents(
+                &WhatsAppParser::with_config(config.clone()),
+                &WhatsAppParser::with_config(config.with_streaming(true)),
+                &path,
+                &expected,
+            );
+        }
+    }
+
+    #[test]
+    fn test_instagram() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("message_1.json");
+        fs::write(
+            &path,
+            r#"{"participants": [{"name": "Alice"}, {"name": "Bob"}], "messages": [
+  {"sender_name": "Bob", "timestamp_ms": 1705314660000, "content": "look \t\n\n\n[Attachment: cat.png]"},
+  {"sender_name": "Alice", "timestamp_ms": 1705314600000, "content": "\n  hello\n\n\nthere  \n"}
+]}"#,
+        )
+        .unwrap();
+
+        let cases: [(ContentPolicy, [&str; 2]); 3] = [
+            (
+                ContentPolicy::new(),
+                ["hello\n\n\nthere", "look\n[Attachment: cat.png]"],
+            ),
+            (
+                collapse(),
+                ["hello\n\nthere", "look\n[Attachment: cat.png]"],
+            ),
+            (
+                ContentPolicy::raw(),
+                [
+                    "\n  hello\n\n\nthere  \n",
+                    "look \t\n\n\n[Attachment: cat.png]",
+                ],
+            ),
+        ];
+        for (policy, expected) in cases {
+            let config = InstagramConfig::new().with_content_policy(policy);
+            assert_contents(
+                &InstagramParser::with_config(config.clone()),
+                &InstagramParser::with_config(config.with_streaming(true)),
+                &path,
+                &expected,
+            );
+        }
+    }
+
+    #[test]
+    fn test_discord() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("channel.jsonl");
+        fs::write(
+            &path,
+            r#"{"id":"1","timestamp":"2024-01-15T10:30:00+00:00","content":"  see this   ","author":{"name":"alice"},"attachments":[{"fileName":"cat.png"}],"stickers":[{"name":"Wave"}]}
+{"id":"2","timestamp":"2024-01-15T10:31:00+00:00","content":"a\n\n \n\nb ","author":{"name":"bob"}}
+"#,
+        )
+        .unwrap();
+
+        let cases: [(ContentPolicy, [&str; 2]); 3] = [
+            (
+                ContentPolicy::new(),
+                [
+                    "see this\n[Attachment: cat.png]\n[Sticker: Wave]",
+                    "a\n\n \n\nb",
+                ],
+            ),
+            (
+                collapse(),
+                ["see this\n[Attachment: cat.png]\n[Sticker: Wave]", "a\n\nb"],
+            ),
+            (
+                ContentPolicy::raw(),
+                [
+                    "  see this   \n[Attachment: cat.png]\n[Sticker: Wave]",
+                    "a\n\n \n\nb ",
+                ],
+            ),
+        ];
+        for (policy, expected) in cases {
+            let config = DiscordConfig::new().with_content_policy(policy);
+            assert_contents(
+                &DiscordParser::with_config(config.clone()),
+                &DiscordParser::with_config(config.with_streaming(true)),
+                &path,
+                &expected,
+            );
+        }
+    }
+
+    #[test]
+    fn test_policy_serializes_inline() {
+        let config = DiscordConfig::new().with_content_policy(ContentPolicy::raw());
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["trim_content"], false);
+        assert_eq!(json["ensure_single_space_before_markers"], false);
+
+        let parsed: WhatsAppConfig = serde_json::from_str(
+            r#"{"streaming":false,"buffer_size":1,"skip_system_messages":true,"skip_invalid":true,"collapse_internal_blank_lines":true}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.content, collapse());
+    }
+}
+
+// ============================================================================
+// Timestamp Check Tests
+// ============================================================================
+
+mod timestamp_check_tests {
+    use super::*;
+    use chatpack::config::{
+        DiscordConfig, InstagramConfig, TelegramConfig, TimestampAction, TimestampCheck,
+        WhatsAppConfig,
+    };
+    use chatpack::parsers::{DiscordParser, InstagramParser, TelegramParser, WhatsAppParser};
+    use chatpack::sink::CollectingSink;
+
+    /// Asserts that each action applies to the two implausible messages in
+    /// `path` (one from 1970, one from 2124) through `parse_counted` and
+    /// native streaming.
+    fn assert_actions(make: impl Fn(TimestampCheck, bool) -> Box<dyn Parser>, path: &Path) {
+        let plain = make(TimestampCheck::new(TimestampAction::Warn), false)
+            .parse(path)
+            .unwrap();
+        assert_eq!(plain.len(), 3);
+
+        for streaming in [false, true] {
+            let (dropped, flagged) = make(TimestampCheck::new(TimestampAction::Drop), streaming)
+                .parse_counted(path)
+                .unwrap();
+            assert_eq!(flagged, 2, "streaming: {streaming}");
+            assert_eq!(dropped.len(), 1);
+            assert_eq!(dropped[0].content, "sane");
+            assert_eq!(dropped[0].source_index, Some(0));
+
+            let nullified: Vec<Message> =
+                make(TimestampCheck::new(TimestampAction::Nullify), streaming)
+                    .stream(path)
+                    .unwrap()
+                    .map(Result::unwrap)
+                    .collect();
+            assert_eq!(nullified.len(), 3);
+            assert_eq!(
+                nullified.iter().filter(|m| m.timestamp.is_none()).count(),
+                2
+            );
+
+            let warned = make(TimestampCheck::new(TimestampAction::Warn), streaming)
+                .parse(path)
+                .unwrap();
+            assert_eq!(warned, plain);
+        }
+    }
+
+    #[test]
+    fn test_telegram() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("result.json");
+        fs::write(
+            &path,
+            r#"{"messages": [
+  {"id": 1, "type": "message", "date": "2024-01-15T10:30:00", "date_unixtime": "1705314600", "from": "Alice", "text": "sane"},
+  {"id": 2, "type": "message", "date": "1970-01-01T00:00:00", "date_unixtime": "0", "from": "Bob", "text": "epoch"},
+  {"id": 3, "type": "message", "date": "2124-01-15T10:30:00", "date_unixtime": "4861000000", "from": "Bob", "text": "future"}
+]}"#,
+        )
+        .unwrap();
+
+        assert_actions(
+            |check, streaming| {
+                let config = TelegramConfig::new()
+                    .with_clamp_timestamps(check)
+                    .with_streaming(streaming);
+                Box::new(TelegramParser::with_config(config))
+            },
+            &path,
+        );
+    }
+
+    #[test]
+    fn test_whatsapp() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        fs::write(
+            &path,
+            "[1/15/24, 10:30:00 AM] Alice: sane\n\
+             [1/1/1970, 12:00:00 AM] Bob: epoch\n\
+             [1/15/2124, 10:30:00 AM] Bob: future\n",
+        )
+        .unwrap();
+
+        assert_actions(
+            |check, streaming| {
+                let config = WhatsAppConfig::new()
+                    .with_clamp_timestamps(check)
+                    .with_streaming(streaming);
+                Box::new(WhatsAppParser::with_config(config))
+            },
+            &path,
+        );
+
+        let parser = WhatsAppParser::with_config(
+            WhatsAppConfig::new().with_clamp_timestamps(TimestampCheck::new(TimestampAction::Drop)),
+        );
+        let mut sink = CollectingSink::new();
+        parser.parse_into(&path, &mut sink).unwrap();
+        assert_eq!((sink.messages.len(), sink.skipped), (1, 2));
+    }
+
+    #[test]
+    fn test_instagram() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("message_1.json");
+        fs::write(
+            &path,
+            r#"{"participants": [{"name": "Alice"}, {"name": "Bob"}], "messages": [
+  {"sender_name": "Bob", "timestamp_ms": 4861000000000, "content": "future"},
+  {"sender_name": "Bob", "timestamp_ms": 0, "content": "epoch"},
+  {"sender_name": "Alice", "timestamp_ms": 1705315800000, "content": "sane"}
+]}"#,
+        )
+        .unwrap();
+
+        assert_actions(
+            |check, streaming| {
+                let config = InstagramConfig::new()
+                    .with_clamp_timestamps(check)
+                    .with_streaming(streaming);
+                Box::new(InstagramParser::with_config(config))
+            },
+            &path,
+        );
+    }
+
+    #[test]
+    fn test_discord() {
+        let dir = TempDir::new().unwrap();
+        let lines = [
+            ("1", "2024-01-15T10:30:00+00:00", "sane"),
+            ("2", "1970-01-01T00:00:00+00:00", "epoch"),
+            ("3", "2124-01-15T10:30:00+00:00", "future"),
+        ]
+        .map(|(id, timestamp, content)| {
+            format!(
+                r#"{{"id":"{id}","type":"Default","timestamp":"{timestamp}","content":"{content}","author":{{"id":"1","name":"alice"}}}}"#
+            )
+        });
+        let jsonl = dir.path().join("channel.jsonl");
+        fs::write(&jsonl, lines.join("\n")).unwrap();
+        let json = dir.path().join("channel.json");
+        fs::write(
+            &json,
+            format!("{{\"messages\": [\n{}\n]}}", lines.join(",\n")),
+        )
+        .unwrap();
+
+        for path in [&json, &jsonl] {
+            assert_actions(
+                |check, streaming| {
+                    let config = DiscordConfig::new()
+                        .with_clamp_timestamps(check)
+                        .with_streaming(streaming);
+                    Box::new(DiscordParser::with_config(config))
+                },
+                path,
+            );
+        }
+
+        let parser = DiscordParser::with_config(
+            DiscordConfig::new()
+                .with_clamp_timestamps(TimestampCheck::new(TimestampAction::Nullify)),
+        );
+        let mut sink = CollectingSink::new();
+        parser.parse_into(&jsonl, &mut sink).unwrap();
+        assert_eq!((sink.messages.len(), sink.skipped), (3, 2));
+    }
+}
+
+// ============================================================================
+// Raw Capture Tests
+// ============================================================================
+
+mod raw_capture_tests {
+    use super::*;
+    use chatpack::config::{DiscordConfig, InstagramConfig, TelegramConfig, WhatsAppConfig};
+    use chatpack::parsers::{DiscordParser, InstagramParser, TelegramParser, WhatsAppParser};
+
+    /// Asserts that `parse`, native streaming and `parse_into` capture
+    /// exactly `expected`, and that the default parser captures nothing.
+    fn assert_raw(make: impl Fn(bool, bool) -> Box<dyn Parser>, path: &Path, expected: &[&str]) {
+        let parsed = make(true, false).parse(path).unwrap();
+        let streamed: Vec<Message> = make(true, true)
+            .stream(path)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let mut visited: Vec<Message> = Vec::new();
+        make(true, false).parse_into(path, &mut visited).unwrap();
+
+        for (name, messages) in [
+            ("parse", parsed),
+            ("stream", streamed),
+            ("parse_into", visited),
+        ] {
+            let raw: Vec<&str> = messages.iter().filter_map(Message::raw).collect();
+            assert_eq!(raw, expected, "{name}");
+        }
+
+        for streaming in [false, true] {
+            let messages = make(false, streaming).parse(path).unwrap();
+            assert_eq!(messages.len(), expected.len());
+            assert!(messages.iter().all(|m| m.raw.is_none()));
+        }
+    }
+
+    #[test]
+    fn test_telegram() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("result.json");
+        let record = r#"{"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": ["see ", {"type": "link", "text": "{x}"}]}"#;
+        fs::write(&path, format!("{{\"messages\": [\n  {record}\n]}}")).unwrap();
+
+        assert_raw(
+            |capture, streaming| {
+                let config = TelegramConfig::new()
+                    .with_capture_raw(capture)
+                    .with_streaming(streaming);
+                Box::new(TelegramParser::with_config(config))
+            },
+            &path,
+            &[record],
+        );
+    }
+
+    #[test]
+    fn test_whatsapp() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        fs::write(
+            &path,
+            "[1/15/24, 10:30:00 AM] Alice: first  \r\n  second line\r\n\r\n\
+             [1/15/24, 10:31:00 AM] Bob: hi\r\n",
+        )
+        .unwrap();
+
+        assert_raw(
+            |capture, streaming| {
+                let config = WhatsAppConfig::new()
+                    .with_capture_raw(capture)
+                    .with_streaming(streaming);
+                Box::new(WhatsAppParser::with_config(config))
+            },
+            &path,
+            &[
+                "[1/15/24, 10:30:00 AM] Alice: first  \n  second line",
+                "[1/15/24, 10:31:00 AM] Bob: hi",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_instagram() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("message_1.json");
+        let record = r#"{"sender_name": "Alice", "timestamp_ms": 1705315800000, "content": "Hey"}"#;
+        fs::write(
+            &path,
+            format!(
+                "{{\"participants\": [{{\"name\": \"Alice\"}}], \"messages\": [\n  {record}\n]}}"
+            ),
+        )
+        .unwrap();
+
+        assert_raw(
+            |capture, streaming| {
+                let config = InstagramConfig::new()
+                    .with_capture_raw(capture)
+                    .with_streaming(streaming);
+                Box::new(InstagramParser::with_config(config))
+            },
+            &path,
+            &[record],
+        );
+    }
+
+    #[test]
+    fn test_discord() {
+        let dir = TempDir::new().unwrap();
+        let record = r#"{"id": "1", "type": "Default", "timestamp": "2024-01-15T10:30:00+00:00", "content": "Hello", "author": {"id": "1", "name": "alice"}}"#;
+        let json = dir.path().join("channel.json");
+        fs::write(&json, format!("{{\"messages\": [\n  {record}\n]}}")).unwrap();
+        let jsonl = dir.path().join("channel.jsonl");
+        fs::write(&jsonl, format!("{record}\n")).unwrap();
+
+        for path in [&json, &jsonl] {
+            assert_raw(
+                |capture, streaming| {
+                    let config = DiscordConfig::new()
+                        .with_capture_raw(capture)
+                        .with_streaming(streaming);
+                    Box::new(DiscordParser::with_config(config))
+                },
+                path,
+                &[record],
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json-output")]
+    fn test_include_raw_output() {
+        let messages = vec![
+            Message::new("Alice", "Hi").with_raw("{\"a\": 1}"),
+            Message::new("Alice", "Again").with_raw("{\"a\": 2}"),
+        ];
+        let merged = chatpack::core::processor::merge_consecutive(messages);
+        assert_eq!(merged[0].raw(), Some("{\"a\": 1}"));
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("out.jsonl");
+        let path = path.to_str().unwrap();
+
+        write_jsonl(&merged, path, &OutputConfig::new()).unwrap();
+        assert!(!fs::read_to_string(path).unwrap().contains("raw"));
+
+        write_jsonl(&merged, path, &OutputConfig::new().with_raw()).unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(fs::read_to_string(path).unwrap().trim()).unwrap();
+        assert_eq!(line["raw"], "{\"a\": 1}");
+    }
+}
+
+mod call_tests {
+    use super::*;
+    use chatpack::config::{InstagramConfig, TelegramConfig};
+    use chatpack::parsers::{InstagramParser, TelegramParser};
+
+    fn contents(parser: &dyn Parser, path: &Path) -> Vec<(String, String)> {
+        parser
+            .parse(path)
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.sender, m.content))
+            .collect()
+    }
+
+    #[test]
+    fn test_telegram_calls() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("result.json");
+        fs::write(
+            &path,
+            r#"{"messages": [
+  {"id": 1, "type": "service", "date_unixtime": "1705314600", "actor": "Alice", "action": "phone_call", "duration_seconds": 750, "discard_reason": "hangup"},
+  {"id": 2, "type": "service", "date_unixtime": "1705314700", "actor": "Bob", "action": "phone_call", "discard_reason": "missed"},
+  {"id": 3, "type": "message", "date_unixtime": "1705314800", "from": "Alice", "file": "voice_messages/audio_1.ogg", "media_type": "voice_message", "duration_seconds": 4, "text": ""},
+  {"id": 4, "type": "service", "date_unixtime": "1705314900", "actor": "Alice", "action": "pin_message", "text": ""},
+  {"id": 5, "type": "message", "date_unixtime": "1705315000", "from": "Bob", "text": "hi"}
+]}"#,
+        )
+        .unwrap();
+
+        let expected = [
+            ("Alice", "[Call: 12m 30s]"),
+            ("Bob", "[Missed call]"),
+            ("Alice", "[Voice message]"),
+            ("Bob", "hi"),
+        ]
+        .map(|(s, c)| (s.to_string(), c.to_string()));
+        for streaming in [false, true] {
+            let config = TelegramConfig::new()
+                .with_include_calls(true)
+                .with_streaming(streaming);
+            let parser = TelegramParser::with_config(config);
+            assert_eq!(contents(&parser, &path), expected, "streaming: {streaming}");
+        }
+
+        let default = contents(&TelegramParser::new(), &path);
+        assert_eq!(default, [("Bob".to_string(), "hi".to_string())]);
+    }
+
+    #[test]
+    fn test_instagram_calls() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("message_1.json");
+        fs::write(
+            &path,
+            r#"{"messages": [
+  {"sender_name": "Bob", "timestamp_ms": 1705315000000, "content": "hi"},
+  {"sender_name": "Alice", "timestamp_ms": 1705314800000, "audio_files": [{"uri": "messages/inbox/bob/audio/1.mp4"}]},
+  {"sender_name": "Bob", "timestamp_ms": 1705314700000, "content": "Bob started an audio call", "call_duration": 0},
+  {"sender_name": "Alice", "timestamp_ms": 1705314600000, "content": "Alice started a video chat", "call_duration": 3725}
+]}"#,
+        )
+        .unwrap();
+
+        let expected = [
+            ("Alice", "[Call: 1h 2m 5s]"),
+            ("Bob", "[Missed call]"),
+            ("Alice", "[Voice message]"),
+            ("Bob", "hi"),
+        ]
+        .map(|(s, c)| (s.to_string(), c.to_string()));
+        for streaming in [false, true] {
+            let config = InstagramConfig::new()
+                .with_include_calls(true)
+                .with_streaming(streaming);
+            let parser = InstagramParser::with_config(config);
+            assert_eq!(contents(&parser, &path), expected, "streaming: {streaming}");
+        }
+
+        let default = contents(&InstagramParser::new(), &path);
+        assert_eq!(default.len(), 3);
+        assert_eq!(default[0].1, "Alice started a video chat");
+    }
+}
+
+mod crlf_tests {
+    use super::*;
+    use chatpack::config::WhatsAppConfig;
+    use chatpack::parsers::{DiscordParser, WhatsAppParser};
+
+    /// Asserts that every parsing path yields `count` messages with no `\r`
+    /// in any sender or content.
+    fn assert_no_cr(parser: &dyn Parser, path: &Path, count: usize) {
+        let parsed = parser.parse(path).unwrap();
+        let parsed_str = parser
+            .parse_str(&fs::read_to_string(path).unwrap())
+            .unwrap();
+        let streamed: Vec<Message> = parser.stream(path).unwrap().map(Result::unwrap).collect();
+        let mut visited: Vec<Message> = Vec::new();
+        parser.parse_into(path, &mut visited).unwrap();
+
+        for (name, messages) in [
+            ("parse", parsed),
+            ("parse_str", parsed_str),
+            ("stream", streamed),
+            ("parse_into", visited),
+        ] {
+            assert_eq!(messages.len(), count, "{name}");
+            for msg in &messages {
+                assert!(!msg.sender.contains('\r'), "{name}: {:?}", msg.sender);
+                assert!(!msg.content.contains('\r'), "{name}: {:?}", msg.content);
+            }
+        }
+    }
+
+    #[test]
+    fn test_whatsapp_all_formats() {
+        let dir = TempDir::new().unwrap();
+        let headers = [
+            ("[1/15/24, 10:30:00 AM] ", "[1/15/24, 10:31:00 AM] "),
+            ("[15.01.24, 10:30:00] ", "[15.01.24, 10:31:00] "),
+            ("15.01.2024, 10:30 - ", "15.01.2024, 10:31 - "),
+            ("15/01/2024, 10:30 - ", "15/01/2024, 10:31 - "),
+            ("[15/01/2024, 10:30:00] ", "[15/01/2024, 10:31:00] "),
+        ];
+        for (i, (first, second)) in headers.iter().enumerate() {
+            let path = dir.path().join(format!("chat_{i}.txt"));
+            fs::write(
+                &path,
+                format!("{first}Alice: Hello\r\nsecond line\r\n\r\n{second}Bob: Hi\r\n"),
+            )
+            .unwrap();
+
+            for streaming in [false, true] {
+                let parser =
+                    WhatsAppParser::with_config(WhatsAppConfig::new().with_streaming(streaming));
+                assert_no_cr(&parser, &path, 2);
+
+                let messages = parser.parse(&path).unwrap();
+                assert_eq!(messages[0].content, "Hello\nsecond line");
+                let alice = FilterConfig::new().with_sender("Alice");
+                assert_eq!(apply_filters(messages, &alice).len(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_discord_txt() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("channel.txt");
+        fs::write(
+            &path,
+            "[1/15/2024 10:30 AM] Alice\r\nHello\r\nsecond line\r\n\r\n{Attachments}\r\n\
+             https://cdn.discordapp.com/attachments/1/2/cat.png\r\n\r\n\
+             [1/15/2024 10:31 AM] Bob\r\nHi\r\n",
+        )
+        .unwrap();
+
+        let parser = DiscordParser::new();
+        assert_no_cr(&parser, &path, 2);
+        let messages = parser.parse(&path).unwrap();
+        assert_eq!(messages[0].sender, "Alice");
+        assert_eq!(
+            messages[0].content,
+            "Hello\nsecond line\n[Attachment: cat.png]"
+        );
+    }
+
+    #[test]
+    fn test_discord_csv() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("channel.csv");
+        fs::write(
+            &path,
+            "AuthorID,Author,Date,Content,Attachments,Reactions\r\n\
+             1,Alice,2024-01-15T10:30:00+00:00,\"Hello\r\nsecond line\",,\r\n\
+             2,Bob,2024-01-15T10:31:00+00:00,Hi,,\r\n",
+        )
+        .unwrap();
+
+        // Native Discord streaming reads JSON and JSONL only
+        let parser = DiscordParser::new();
+        assert_no_cr(&parser, &path, 2);
+        let messages = parser.parse(&path).unwrap();
+        assert_eq!(messages[0].content, "Hello\nsecond line");
+    }
+}
+
+mod telegram_chunk_tests {
+    use super::*;
+    use chatpack::config::TelegramConfig;
+    use chatpack::parsers::TelegramParser;
+    use chatpack::parsing::telegram::telegram_export_chunks;
+    use chatpack::streaming::{StreamingParser, TelegramStreamingParser};
+
+    fn record(id: u64) -> String {
+        format!(
+            r#"{{"id": {id}, "type": "message", "date_unixtime": "{}", "from": "User{}", "text": "message {id}"}}"#,
+            1_705_314_600 + id * 60,
+            id % 2
+        )
+    }
+
+    fn write_chunk(dir: &Path, name: &str, ids: &[u64]) {
+        let records: Vec<String> = ids.iter().map(|&id| record(id)).collect();
+        fs::write(
+            dir.join(name),
+            format!(
+                "{{\"name\": \"Chat\", \"type\": \"personal_chat\", \"messages\": [\n{}\n]}}",
+                records.join(",\n")
+            ),
+        )
+        .unwrap();
+    }
+
+    /// Three chunks; the second and third repeat the last id of the chunk
+    /// before them. `result(10).json` sorts after `result(2).json`.
+    fn split_export() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        write_chunk(dir.path(), "result.json", &[1, 2, 3]);
+        write_chunk(dir.path(), "result(2).json", &[3, 4, 5]);
+        write_chunk(dir.path(), "result(10).json", &[5, 6]);
+        fs::write(dir.path().join("notes.json"), "{}").unwrap();
+        dir
+    }
+
+    fn ids(messages: &[Message]) -> Vec<u64> {
+        messages.iter().map(|m| m.id.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_discovers_chunks_in_order() {
+        let dir = split_export();
+        let names: Vec<String> = telegram_export_chunks(dir.path())
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["result.json", "result(2).json", "result(10).json"]);
+
+        let first = dir.path().join("result.json");
+        assert_eq!(telegram_export_chunks(&first).unwrap().len(), 3);
+        let later = dir.path().join("result(2).json");
+        assert_eq!(telegram_export_chunks(&later).unwrap(), [later]);
+        assert!(telegram_export_chunks(TempDir::new().unwrap().path()).is_err());
+    }
+
+    #[test]
+    fn test_parse_joins_chunks_once() {
+        let dir = split_export();
+        let first = dir.path().join("result.json");
+
+        for path in [dir.path(), first.as_path()] {
+            for streaming in [false, true] {
+                let parser =
+                    TelegramParser::with_config(TelegramConfig::new().with_streaming(streaming));
+                let parsed = parser.parse(path).unwrap();
+                let streamed: Vec<Message> =
+                    parser.stream(path).unwrap().map(Result::unwrap).collect();
+                let mut visited: Vec<Message> = Vec::new();
+                parser.parse_into(path, &mut visited).unwrap();
+
+                for messages in [&parsed, &streamed, &visited] {
+                    assert_eq!(ids(messages), [1, 2, 3, 4, 5, 6]);
+                    let indices: Vec<u64> =
+                        messages.iter().map(|m| m.source_index.unwrap()).collect();
+                    assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_native_streaming_chains_chunks() {
+        let dir = split_export();
+        let parser = TelegramStreamingParser::new();
+        let mut iter = parser.stream(dir.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(iter.next().unwrap().unwrap().id, Some(1));
+        // Only the first chunk has been opened so far
+        let first_size = fs::metadata(dir.path().join("result.json")).unwrap().len();
+        assert!(iter.bytes_processed() <= first_size);
+
+        let rest: Vec<Message> = iter.by_ref().map(std::result::Result::unwrap).collect();
+        assert_eq!(ids(&rest), [2, 3, 4, 5, 6]);
+        assert_eq!(iter.bytes_processed(), iter.total_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_only_previous_chunk_ids_are_repeats() {
+        let dir = TempDir::new().unwrap();
+        write_chunk(dir.path(), "result.json", &[1, 2, 3]);
+        write_chunk(dir.path(), "result(1).json", &[3, 10, 11]);
+        write_chunk(dir.path(), "result(2).json", &[2, 11, 12]);
+
+        for streaming in [false, true] {
+            let parser =
+                TelegramParser::with_config(TelegramConfig::new().with_streaming(streaming));
+            let parsed = parser.parse(dir.path()).unwrap();
+            // Id 2 was last seen two chunks back, so it is not a repeat
+            assert_eq!(ids(&parsed), [1, 2, 3, 10, 11, 2, 12]);
+        }
+        let streamed: Vec<Message> = TelegramStreamingParser::new()
+            .stream(dir.path().to_str().unwrap())
+            .unwrap()
+            .map(std::result::Result::unwrap)
+            .collect();
+        assert_eq!(ids(&streamed), [1, 2, 3, 10, 11, 2, 12]);
+    }
+
+    #[test]
+    fn test_size_limit_covers_all_chunks() {
+        let dir = split_export();
+        let largest = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .max()
+            .unwrap();
+        let config = TelegramConfig::new().with_max_file_size(largest);
+
+        let err = TelegramParser::with_config(config.clone())
+            .parse(dir.path())
+            .unwrap_err();
+        assert!(err.is_file_too_large(), "{err}");
+        // Each chunk alone is within the limit
+        let first = dir.path().join("result(2).json");
+        assert!(TelegramParser::with_config(config).parse(&first).is_ok());
+    }
+
+    #[test]
+    fn test_single_file_unchanged() {
+        let dir = TempDir::new().unwrap();
+        write_chunk(dir.path(), "export.json", &[1, 2]);
+        write_chunk(dir.path(), "result(1).json", &[7]);
+
+        let messages = TelegramParser::new()
+            .parse(&dir.path().join("export.json"))
+            .unwrap();
+        assert_eq!(ids(&messages), [1, 2]);
+    }
+}
+
+mod sender_allowlist_tests {
+    use super::*;
+    use chatpack::config::{DiscordConfig, InstagramConfig, TelegramConfig, WhatsAppConfig};
+    use chatpack::parsers::{DiscordParser, InstagramParser, TelegramParser, WhatsAppParser};
+
+    /// Asserts that `allowed`, configured with an allowlist of `sender`,
+    /// yields what `all` yields after filtering by `sender`, on every
+    /// parsing path, and returns those messages.
+    fn assert_matches_filter(
+        all: &dyn Parser,
+        allowed: &dyn Parser,
+        path: &Path,
+        sender: &str,
+    ) -> Vec<Message> {
+        let filter = FilterConfig::new().with_sender(sender);
+        let expected = apply_filters(all.parse(path).unwrap(), &filter);
+        assert!(!expected.is_empty());
+
+        let parsed = allowed.parse(path).unwrap();
+        let streamed: Vec<Message> = allowed
+            .stream(path)
+            .unwrap()
+            .map(std::result::Result::unwrap)
+            .collect();
+        let mut visited: Vec<Message> = Vec::new();
+        allowed.parse_into(path, &mut visited).unwrap();
+        let streamed_all = apply_filters(
+            all.stream(path)
+                .unwrap()
+                .map(std::result::Result::unwrap)
+                .collect(),
+            &filter,
+        );
+
+        assert!(!streamed_all.is_empty());
+        assert_eq!(parsed, expected, "parse");
+        assert_eq!(streamed, streamed_all, "stream");
+        assert_eq!(visited, expected, "parse_into");
+        // Indices number the kept messages
+        for messages in [&parsed, &streamed, &visited] {
+            let indices: Vec<Option<u64>> = messages.iter().map(|m| m.source_index).collect();
+            let numbered: Vec<Option<u64>> = (0..messages.len() as u64).map(Some).collect();
+            assert_eq!(indices, numbered);
+        }
+        parsed
+    }
+
+    #[test]
+    fn test_telegram() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("result.json");
+        fs::write(
+            &path,
+            r#"{"messages": [
+  {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "Hello"},
+  {"id": 2, "type": "message", "date_unixtime": "1705314660", "from": "Bob", "text": ["see ", {"type": "link", "text": "example.com"}]},
+  {"id": 3, "type": "service", "date_unixtime": "1705314700", "actor": "Bob", "action": "phone_call", "duration_seconds": 60},
+  {"id": 4, "type": "service", "date_unixtime": "1705314760", "actor": "Alice", "action": "phone_call", "discard_reason": "missed"},
+  {"id": 5, "type": "message", "date_unixtime": "1705314800", "from": "Alice", "text": "Bye", "reply_to_message_id": 2}
+]}"#,
+        )
+        .unwrap();
+
+        for streaming in [false, true] {
+            let config = TelegramConfig::new()
+                .with_include_calls(true)
+                .with_streaming(streaming);
+            let all = TelegramParser::with_config(config.clone());
+            let allowed = TelegramParser::with_config(config.with_sender_allowlist(["alice"]));
+
+            let messages = assert_matches_filter(&all, &allowed, &path, "alice");
+            let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, ["Hello", "[Missed call]", "Bye"]);
+        }
+    }
+
+    #[test]
+    fn test_instagram_compares_fixed_names() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("message_1.json");
+        // "José" exported as mojibake
+        fs::write(
+            &path,
+            r#"{"participants": [{"name": "JosÃ©"}, {"name": "Bob"}], "messages": [
+  {"sender_name": "Bob", "timestamp_ms": 1705314720000, "content": "Later"},
+  {"sender_name": "JosÃ©", "timestamp_ms": 1705314660000, "content": "CafÃ©?"},
+  {"sender_name": "Bob", "timestamp_ms": 1705314630000, "photos": [{"uri": "photos/1.jpg"}]},
+  {"sender_name": "JosÃ©", "timestamp_ms": 1705314600000, "content": "Hi"}
+]}"#,
+        )
+        .unwrap();
+
+        for streaming in [false, true] {
+            let config = InstagramConfig::new().with_streaming(streaming);
+            let all = InstagramParser::with_config(config.clone());
+            let allowed = InstagramParser::with_config(config.with_sender_allowlist(["JOSé"]));
+
+            let messages = assert_matches_filter(&all, &allowed, &path, "José");
+            let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, ["Hi", "Café?"]);
+        }
+    }
+
+    #[test]
+    fn test_discord_formats() {
+        let dir = TempDir::new().unwrap();
+        let record = |id: u32, name: &str, nickname: &str, content: &str| {
+            format!(
+                r#"{{"id":"{id}","timestamp":"2024-01-15T10:3{id}:00+00:00","content":"{content}","author":{{"name":"{name}","nickname":"{nickname}"}},"attachments":[],"stickers":[]}}"#
+            )
+        };
+        let records = [
+            record(1, "alice", "Alice", "Hello"),
+            record(2, "bob", "Bob", "Hi"),
+            record(3, "alice", "Alice", "Bye"),
+        ];
+        let files = [
+            ("channel.jsonl", records.join("\n")),
+            (
+                "channel.json",
+                format!(
+                    "{{\"guild\":{{}},\"messages\":[\n{}\n]}}",
+                    records.join(",\n")
+                ),
+            ),
+            (
+                "channel.txt",
+                "[1/15/2024 10:31 AM] Alice\nHello\n\n[1/15/2024 10:32 AM] Bob\nHi\n\
+                 {Attachments}\nhttps://cdn.discordapp.com/attachments/1/2/cat.png\n\n\
+                 [1/15/2024 10:33 AM] Alice\nBye\n"
+                    .to_string(),
+            ),
+            (
+                "channel.csv",
+                "AuthorID,Author,Date,Content,Attachments,Reactions\n\
+                 1,Alice,2024-01-15T10:31:00+00:00,Hello,,\n\
+                 2,Bob,2024-01-15T10:32:00+00:00,Hi,https://cdn/cat.png,\n\
+                 1,Alice,2024-01-15T10:33:00+00:00,Bye,,\n"
+                    .to_string(),
+            ),
+        ];
+
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            // Native streaming reads JSON and JSONL only
+            let modes: &[bool] = if name.ends_with("json") || name.ends_with("jsonl") {
+                &[false, true]
+            } else {
+                &[false]
+            };
+            for &streaming in modes {
+                let config = DiscordConfig::new().with_streaming(streaming);
+                let all = DiscordParser::with_config(config.clone());
+                let allowed = DiscordParser::with_config(config.with_sender_allowlist(["ALICE"]));
+
+                let messages = assert_matches_filter(&all, &allowed, &path, "alice");
+                assert_eq!(messages.len(), 2, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_whatsapp_drops_continuation_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        fs::write(
+            &path,
+            "[1/15/24, 10:30:00 AM] Alice: Hello\n\
+             [1/15/24, 10:31:00 AM] Bob: Hi\nstill Bob\n\
+             [1/15/24, 10:32:00 AM] Alice: Two\nlines\n\
+             [1/15/24, 10:33:00 AM] Bob: <Media omitted>\n",
+        )
+        .unwrap();
+
+        for streaming in [false, true] {
+            let config = WhatsAppConfig::new().with_streaming(streaming);
+            let all = WhatsAppParser::with_config(config.clone());
+            let allowed = WhatsAppParser::with_config(config.with_sender_allowlist(["alice"]));
+
+            let messages = assert_matches_filter(&all, &allowed, &path, "Alice");
+            let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(contents, ["Hello", "Two\nlines"]);
+        }
+    }
+
+    #[test]
+    fn test_empty_allowlist_keeps_nothing() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        fs::write(&path, "[1/15/24, 10:30:00 AM] Alice: Hello\n").unwrap();
+
+        let config = WhatsAppConfig::new().with_sender_allowlist(Vec::<String>::new());
+        let parser = WhatsAppParser::with_config(config);
+        assert!(parser.parse(&path).unwrap().is_empty());
+    }
+}
+
+mod discord_event_tests {
+    use super::*;
+    use chatpack::config::DiscordConfig;
+    use chatpack::parsers::DiscordParser;
+
+    /// Parses `path` with `include_system_events` set to `include`, through
+    /// every path that reads the format, and returns the contents.
+    fn contents(path: &Path, include: bool, streams: bool) -> Vec<String> {
+        let config = DiscordConfig::new().with_include_system_events(include);
+        let parsed: Vec<String> = DiscordParser::with_config(config.clone())
+            .parse(path)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+
+        let mut visited: Vec<Message> = Vec::new();
+        DiscordParser::with_config(config.clone())
+            .parse_into(path, &mut visited)
+            .unwrap();
+        let visited: Vec<String> = visited.into_iter().map(|m| m.content).collect();
+        assert_eq!(visited, parsed, "parse_into");
+
+        if streams {
+            let streamed: Vec<String> = DiscordParser::with_config(config.with_streaming(true))
+                .stream(path)
+                .unwrap()
+                .map(|m| m.unwrap().content)
+                .collect();
+            assert_eq!(streamed, parsed, "stream");
+        }
+        parsed
+    }
+
+    #[test]
+    fn test_json_types() {
+        let dir = TempDir::new().unwrap();
+        let record = |id: u32, kind: &str, name: &str, content: &str| {
+            format!(
+                r#"{{"id":"{id}","type":"{kind}","timestamp":"2024-01-15T10:{id:02}:00+00:00","content":"{content}","author":{{"name":"{name}","nickname":"{name}"}},"attachments":[],"stickers":[]}}"#
+            )
+        };
+        let records = [
+            record(1, "Default", "Alice", "Hello"),
+            record(2, "GuildMemberJoin", "Bob", "Joined the server."),
+            record(3, "ChannelPinnedMessage", "Alice", "Pinned a message."),
+            record(4, "UserPremiumGuildSubscription", "Bob", ""),
+            record(5, "RecipientAdd", "Alice", "Added Carol to the group."),
+            record(6, "RecipientRemove", "Carol", "Left the group."),
+            record(7, "Call", "Bob", "Started a call that lasted 3 minutes."),
+            record(8, "ChannelNameChange", "Alice", "Changed the channel name: plans"),
+            record(9, "ChannelIconChange", "Alice", "Changed the channel icon."),
+            record(10, "ThreadCreated", "Bob", "Started a thread."),
+            record(11, "Reply", "Bob", "Hi Alice").replace(
+                r#""attachments""#,
+                r#""reference":{"messageId":"1"},"attachments""#,
+            ),
+            // Exports from older DiscordChatExporter versions have no type
+            r#"{"id":"12","timestamp":"2024-01-15T10:12:00+00:00","content":"Bye","author":{"name":"Alice"}}"#.to_string(),
+        ];
+        let files = [
+            ("channel.jsonl", records.join("\n")),
+            (
+                "channel.json",
+                format!(
+                    "{{\"guild\":{{}},\"messages\":[\n{}\n]}}",
+                    records.join(",\n")
+                ),
+            ),
+        ];
+
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+
+            assert_eq!(
+                contents(&path, false, true),
+                ["Hello", "Hi Alice", "Bye"],
+                "{name}"
+            );
+            assert_eq!(
+                contents(&path, true, true),
+                [
+                    "Hello",
+                    "[Bob joined the server]",
+                    "[Alice pinned a message]",
+                    "[Bob boosted the server]",
+                    "[Alice added someone to the group]",
+                    "[Carol left or removed someone from the group]",
+                    "[Bob started a call]",
+                    "[Alice changed the channel name]",
+                    "[Alice changed the channel icon]",
+                    "[Bob started a thread]",
+                    "Hi Alice",
+                    "Bye",
+                ],
+                "{name}"
+            );
+
+            // Replies keep their reference either way
+            let parser = DiscordParser::new();
+            let reply = parser
+                .parse(&path)
+                .unwrap()
+                .into_iter()
+                .find(|m| m.content == "Hi Alice")
+                .unwrap();
+            assert_eq!(reply.reply_to, Some(1));
+        }
+    }
+
+    #[test]
+    fn test_text_formats_match_fallback_content() {
+        let dir = TempDir::new().unwrap();
+        let files = [
+            (
+                "channel.txt",
+                "[1/15/2024 10:31 AM] Alice\nHello\n\n\
+                 [1/15/2024 10:32 AM] Bob\nJoined the server.\n\n\
+                 [1/15/2024 10:33 AM] Alice\nPinned a message.\n\n\
+                 [1/15/2024 10:34 AM] Bob\nPinned a message. Check it out\n",
+            ),
+            (
+                "channel.csv",
+                "AuthorID,Author,Date,Content,Attachments,Reactions\n\
+                 1,Alice,2024-01-15T10:31:00+00:00,Hello,,\n\
+                 2,Bob,2024-01-15T10:32:00+00:00,Joined the server.,,\n\
+                 1,Alice,2024-01-15T10:33:00+00:00,Pinned a message.,,\n\
+                 2,Bob,2024-01-15T10:34:00+00:00,Pinned a message. Check it out,,\n",
+            ),
+        ];
+
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+
+            assert_eq!(
+                contents(&path, false, false),
+                ["Hello", "Pinned a message. Check it out"],
+                "{name}"
+            );
+            assert_eq!(
+                contents(&path, true, false),
+                [
+                    "Hello",
+                    "[Bob joined the server]",
+                    "[Alice pinned a message]",
+                    "Pinned a message. Check it out",
+                ],
+                "{name}"
+            );
+        }
+    }
+}
+
+mod topic_tests {
+    use super::*;
+    use chatpack::config::TelegramConfig;
+    use chatpack::core::output::to_jsonl;
+    use chatpack::core::processor::partition_by_topic;
+    use chatpack::parsers::TelegramParser;
+
+    /// A forum group with two topics, replies inside them, and a message in
+    /// the general topic.
+    const FORUM: &str = r#"{"name": "Dev Forum", "type": "private_supergroup", "messages": [
+  {"id": 1, "type": "service", "date_unixtime": "1705314000", "actor": "Alice", "action": "topic_created", "title": "Releases"},
+  {"id": 2, "type": "service", "date_unixtime": "1705314060", "actor": "Bob", "action": "topic_created", "title": "Support"},
+  {"id": 3, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "v1.0 is out", "reply_to_message_id": 1},
+  {"id": 4, "type": "message", "date_unixtime": "1705314660", "from": "Bob", "text": "My build fails", "reply_to_message_id": 2},
+  {"id": 5, "type": "message", "date_unixtime": "1705314720", "from": "Alice", "text": "Which OS?", "reply_to_message_id": 4},
+  {"id": 6, "type": "message", "date_unixtime": "1705314780", "from": "Carol", "text": "Congrats!", "reply_to_message_id": 3},
+  {"id": 7, "type": "message", "date_unixtime": "1705314840", "from": "Bob", "text": "Hello all"},
+  {"id": 8, "type": "message", "date_unixtime": "1705314900", "from": "Bob", "text": "Linux", "reply_to_message_id": 5, "reply_to_top_message_id": 2}
+]}"#;
+
+    fn write_forum(dir: &TempDir) -> std::path::PathBuf {
+        let path = dir.path().join("result.json");
+        fs::write(&path, FORUM).unwrap();
+        path
+    }
+
+    /// Parses `path` on every parsing path, asserting they agree.
+    fn parse_all_ways(config: &TelegramConfig, path: &Path) -> Vec<Message> {
+        let parsed = TelegramParser::with_config(config.clone())
+            .parse(path)
+            .unwrap();
+        let streamed: Vec<Message> =
+            TelegramParser::with_config(config.clone().with_streaming(true))
+                .stream(path)
+                .unwrap()
+                .map(std::result::Result::unwrap)
+                .collect();
+        let mut visited: Vec<Message> = Vec::new();
+        TelegramParser::with_config(config.clone())
+            .parse_into(path, &mut visited)
+            .unwrap();
+        assert_eq!(streamed, parsed, "stream");
+        assert_eq!(visited, parsed, "parse_into");
+        parsed
+    }
+
+    fn topics(messages: &[Message]) -> Vec<Option<&str>> {
+        messages.iter().map(Message::topic).collect()
+    }
+
+    #[test]
+    fn test_forum_messages_get_topics() {
+        let dir = TempDir::new().unwrap();
+        let messages = parse_all_ways(&TelegramConfig::new(), &write_forum(&dir));
+
+        assert_eq!(
+            topics(&messages),
+            [
+                Some("Releases"),
+                Some("Support"),
+                Some("Support"),
+                Some("Releases"),
+                Some("General"),
+                Some("Support"),
+            ]
+        );
+        // Replies keep pointing at their parents
+        assert_eq!(messages[2].reply_to, Some(4));
+    }
+
+    #[test]
+    fn test_topic_filter() {
+        let dir = TempDir::new().unwrap();
+        let config = TelegramConfig::new().with_topic_filter(["support"]);
+        let messages = parse_all_ways(&config, &write_forum(&dir));
+
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["My build fails", "Which OS?", "Linux"]);
+        let indices: Vec<Option<u64>> = messages.iter().map(|m| m.source_index).collect();
+        assert_eq!(indices, [Some(0), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn test_non_forum_export_has_no_topics() {
+        ensure_fixtures();
+        let path = format!("{}/telegram_simple.json", fixtures_dir());
+        let path = Path::new(&path);
+
+        let messages = parse_all_ways(&TelegramConfig::new(), path);
+        assert!(!messages.is_empty());
+        assert!(messages.iter().all(|m| m.topic.is_none()));
+
+        let filtered = parse_all_ways(&TelegramConfig::new().with_topic_filter(["General"]), path);
+        assert!(filtered.is_empty());
+    }
+
+    #[test]
+    fn test_partition_by_topic() {
+        let dir = TempDir::new().unwrap();
+        let messages = TelegramParser::new().parse(&write_forum(&dir)).unwrap();
+        let partitions = partition_by_topic(messages);
+
+        let sizes: Vec<(Option<&str>, usize)> = partitions
+            .iter()
+            .map(|(topic, messages)| (topic.as_deref(), messages.len()))
+            .collect();
+        assert_eq!(
+            sizes,
+            [
+                (Some("General"), 1),
+                (Some("Releases"), 2),
+                (Some("Support"), 3)
+            ]
+        );
+        let support: Vec<u64> = partitions[&Some("Support".to_string())]
+            .iter()
+            .filter_map(|m| m.id)
+            .collect();
+        assert_eq!(support, [4, 5, 8]);
+    }
+
+    #[test]
+    fn test_topics_are_written_and_kept_apart() {
+        let dir = TempDir::new().unwrap();
+        let messages = TelegramParser::new().parse(&write_forum(&dir)).unwrap();
+
+        let jsonl = to_jsonl(&messages[..1], &OutputConfig::new().with_metadata()).unwrap();
+        assert_eq!(
+            jsonl,
+            "{\"sender\":\"Alice\",\"content\":\"v1.0 is out\",\"topic\":\"Releases\"}\n"
+        );
+        // Bob's consecutive messages are in different topics
+        let merged = merge_consecutive(messages);
+        assert_eq!(merged.len(), 6);
+    }
+}
+
+mod platform_tag_tests {
+    use super::*;
+    use chatpack::config::{TelegramConfig, WhatsAppConfig};
+    use chatpack::parsers::{TelegramParser, WhatsAppParser};
+
+    /// Writes a Telegram export ending with Alice and a WhatsApp export
+    /// starting with her, and returns their paths.
+    fn write_exports(dir: &TempDir) -> (std::path::PathBuf, std::path::PathBuf) {
+        let telegram = dir.path().join("result.json");
+        fs::write(
+            &telegram,
+            r#"{"messages": [
+  {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Bob", "text": "Hi"},
+  {"id": 2, "type": "message", "date_unixtime": "1705314660", "from": "Alice", "text": "On Telegram"}
+]}"#,
+        )
+        .unwrap();
+        let whatsapp = dir.path().join("chat.txt");
+        fs::write(
+            &whatsapp,
+            "[1/15/24, 10:32:00 AM] Alice: On WhatsApp\n\
+             [1/15/24, 10:33:00 AM] Bob: Bye\n",
+        )
+        .unwrap();
+        (telegram, whatsapp)
+    }
+
+    fn assert_tagged(parser: &dyn Parser, path: &Path) {
+        let platform = Some(parser.platform());
+        let parsed = parser.parse(path).unwrap();
+        let streamed: Vec<Message> = parser
+            .stream(path)
+            .unwrap()
+            .map(std::result::Result::unwrap)
+            .collect();
+        let mut visited: Vec<Message> = Vec::new();
+        parser.parse_into(path, &mut visited).unwrap();
+
+        for messages in [&parsed, &streamed, &visited] {
+            assert_eq!(messages.len(), 2);
+            assert!(messages.iter().all(|m| m.platform == platform));
+        }
+    }
+
+    #[test]
+    fn test_every_path_tags_messages() {
+        let dir = TempDir::new().unwrap();
+        let (telegram, whatsapp) = write_exports(&dir);
+
+        for streaming in [false, true] {
+            let config = TelegramConfig::new().with_streaming(streaming);
+            assert_tagged(&TelegramParser::with_config(config), &telegram);
+            let config = WhatsAppConfig::new().with_streaming(streaming);
+            assert_tagged(&WhatsAppParser::with_config(config), &whatsapp);
+        }
+    }
+
+    #[test]
+    fn test_merge_stops_at_platform_boundary() {
+        let dir = TempDir::new().unwrap();
+        let (telegram, whatsapp) = write_exports(&dir);
+
+        let mut messages = TelegramParser::new().parse(&telegram).unwrap();
+        messages.extend(WhatsAppParser::new().parse(&whatsapp).unwrap());
+        let merged = merge_consecutive(messages.clone());
+
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged[1].content, "On Telegram");
+        assert_eq!(merged[2].platform, Some(Platform::WhatsApp));
+
+        // Untagged messages merge as before
+        for msg in &mut messages {
+            msg.platform = None;
+        }
+        assert_eq!(merge_consecutive(messages).len(), 3);
+    }
+
+    #[test]
+    fn test_output_fields() {
+        let dir = TempDir::new().unwrap();
+        let (telegram, whatsapp) = write_exports(&dir);
+        let mut messages = TelegramParser::new().parse(&telegram).unwrap();
+        messages.extend(WhatsAppParser::new().parse(&whatsapp).unwrap());
+
+        let config = OutputConfig::new().with_platform();
+        let csv = to_csv(&messages, &config).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "Sender;Content;Platform");
+        assert_eq!(lines[2], "Alice;On Telegram;telegram");
+        assert_eq!(lines[3], "Alice;On WhatsApp;whatsapp");
+
+        let jsonl = to_jsonl(&messages, &config).unwrap();
+        let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(first["platform"], "telegram");
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&messages, &config).unwrap()).unwrap();
+        assert_eq!(json[3]["platform"], "whatsapp");
+
+        // Left out unless requested, including from Message's own serde
+        let plain = OutputConfig::new();
+        assert!(!to_jsonl(&messages, &plain).unwrap().contains("platform"));
+        assert!(!to_csv(&messages, &plain).unwrap().contains("Platform"));
+        assert!(
+            !serde_json::to_string(&messages)
+                .unwrap()
+                .contains("platform")
+        );
+    }
+}
+
+mod native_digit_tests {
+    use super::*;
+    use chatpack::config::WhatsAppConfig;
+    use chatpack::parsers::{DiscordParser, WhatsAppParser};
+    use chrono::{TimeZone, Utc};
+
+    fn assert_parses(path: &Path, senders: [&str; 2]) {
+        for streaming in [false, true] {
+            let parser =
+                WhatsAppParser::with_config(WhatsAppConfig::new().with_streaming(streaming));
+            let messages = parser.parse(path).unwrap();
+            assert_eq!(messages.len(), 2);
+            assert_eq!(messages[0].sender, senders[0]);
+            assert_eq!(
+                messages[0].timestamp,
+                Some(Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 45).unwrap())
+            );
+            assert_eq!(
+                messages[1].timestamp,
+                Some(Utc.with_ymd_and_hms(2024, 1, 15, 10, 31, 0).unwrap())
+            );
+            // Digits in content are left as written
+            assert_eq!(messages[0].content, "اشتريت ٣ تفاحات");
+            assert_eq!(messages[1].content, "और २ केले");
+        }
+    }
+
+    #[test]
+    fn test_whatsapp_bracketed_arabic_indic() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        fs::write(
+            &path,
+            "[١٥/٠١/٢٤, ١٠:٣٠:٤٥] Alice: اشتريت ٣ تفاحات\n\
+             [١٥/٠١/٢٤, ١٠:٣١:٠٠] Bob: और २ केले\n",
+        )
+        .unwrap();
+        assert_parses(&path, ["Alice", "Bob"]);
+    }
+
+    #[test]
+    fn test_whatsapp_dash_persian_and_devanagari() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        fs::write(
+            &path,
+            "۱۵/۰۱/۲۰۲۴, ۱۰:۳۰:۴۵ - Sara: اشتريت ٣ تفاحات\n\
+             १५/०१/२०२४, १०:३१ - Ravi: और २ केले\n",
+        )
+        .unwrap();
+        assert_parses(&path, ["Sara", "Ravi"]);
+    }
+
+    #[test]
+    fn test_discord_txt_arabic_indic() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.txt");
+        fs::write(&path, "[١/١٥/٢٠٢٤ ١٠:٣٠ AM] Alice\nالساعة ١٠\n").unwrap();
+
+        let messages = DiscordParser::new().parse(&path).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].timestamp,
+            Some(Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap())
+        );
+        assert_eq!(messages[0].content, "الساعة ١٠");
+    }
+}
+
+mod stream_progress_tests {
+    use super::*;
+    use chatpack::parser::create_streaming_parser;
+    use chatpack::progress::Progress;
+    use std::sync::{Arc, Mutex};
+
+    const MESSAGES: usize = 20_000;
+    const INVALID: usize = 7;
+
+    /// Writes a Telegram export with `MESSAGES` messages and `INVALID`
+    /// records missing every field.
+    fn write_export(dir: &TempDir) -> std::path::PathBuf {
+        let path = dir.path().join("result.json");
+        let mut records = Vec::with_capacity(MESSAGES + INVALID);
+        for i in 0..MESSAGES {
+            records.push(format!(
+                r#"{{"id": {i}, "type": "message", "date_unixtime": "{}", "from": "User{}", "text": "Message number {i}"}}"#,
+                1_705_314_600 + i,
+                i % 5
+            ));
+            if i % 3000 == 0 && records.len() <= MESSAGES {
+                records.push(r#"{"invalid": "object"}"#.to_string());
+            }
+        }
+        fs::write(
+            &path,
+            format!("{{\n\"messages\": [\n{}\n]\n}}", records.join(",\n")),
+        )
+        .unwrap();
+        path
+    }
+
+    fn collect_reports(parser: &dyn Parser, path: &Path, every: usize) -> Vec<Progress> {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&reports);
+        let messages = parser
+            .stream_with_progress(path)
+            .unwrap()
+            .with_progress(every, Arc::new(move |p| seen.lock().unwrap().push(p)));
+        assert_eq!(messages.map(std::result::Result::unwrap).count(), MESSAGES);
+        Arc::try_unwrap(reports).unwrap().into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_streaming_reports_progress_and_skips() {
+        let dir = TempDir::new().unwrap();
+        let path = write_export(&dir);
+        let parser = create_streaming_parser(Platform::Telegram);
+
+        let reports = collect_reports(parser.as_ref(), &path, 1000);
+
+        // One report per 1000 messages, then a final one
+        assert_eq!(reports.len(), MESSAGES / 1000 + 1);
+        assert!(
+            reports
+                .windows(2)
+                .all(|w| w[0].bytes_processed <= w[1].bytes_processed)
+        );
+        assert!(reports[0].percentage().unwrap() < 100.0);
+
+        let last = reports.last().unwrap();
+        assert_eq!(last.items_processed, MESSAGES);
+        assert_eq!(last.skipped, INVALID);
+        assert!(last.percentage().unwrap() > 99.0);
+    }
+
+    #[test]
+    fn test_in_memory_parser_reports_once_loaded() {
+        let dir = TempDir::new().unwrap();
+        let path = write_export(&dir);
+        let parser = create_parser(Platform::Telegram);
+
+        let reports = collect_reports(parser.as_ref(), &path, 1000);
+
+        assert_eq!(reports.len(), MESSAGES / 1000 + 1);
+        assert!(reports.iter().all(Progress::is_complete));
+        assert_eq!(reports.last().unwrap().items_processed, MESSAGES);
+    }
+}
+
+// ============================================================================
+// Projection Tests
+// ============================================================================
+
+mod projection_tests {
+    use super::*;
+    use chatpack::streaming::{
+        DiscordStreamingParser, InstagramStreamingParser, Projection, StreamingConfig,
+        StreamingParser, TelegramStreamingParser, WhatsAppStreamingParser,
+    };
+
+    fn parsers(projection: Projection) -> Vec<(Box<dyn StreamingParser>, &'static str)> {
+        let config = StreamingConfig::new().with_projection(projection);
+        vec![
+            (
+                Box::new(TelegramStreamingParser::with_config(config)),
+                "telegram_complex.json",
+            ),
+            (
+                Box::new(TelegramStreamingParser::with_config(config)),
+                "telegram_meta.json",
+            ),
+            (
+                Box::new(WhatsAppStreamingParser::with_config(config)),
+                "whatsapp_us.txt",
+            ),
+            (
+                Box::new(WhatsAppStreamingParser::with_config(config)),
+                "whatsapp_quotes.txt",
+            ),
+            (
+                Box::new(InstagramStreamingParser::with_config(config)),
+                "instagram.json",
+            ),
+            (
+                Box::new(DiscordStreamingParser::with_config(config)),
+                "discord.json",
+            ),
+            (
+                Box::new(DiscordStreamingParser::with_config(config)),
+                "discord.jsonl",
+            ),
+        ]
+    }
+
+    fn stream(parser: &dyn StreamingParser, path: &str) -> (Vec<Message>, Projection) {
+        let iter = parser.stream(path).unwrap();
+        let projection = iter.projection();
+        (iter.map(std::result::Result::unwrap).collect(), projection)
+    }
+
+    fn senders_and_times(messages: &[Message]) -> Vec<(&str, Option<i64>, Option<u64>)> {
+        messages
+            .iter()
+            .map(|m| {
+                let ts = m.timestamp.map(|t| t.timestamp());
+                (m.sender.as_str(), ts, m.source_index)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_minimal_keeps_senders_and_timestamps() {
+        ensure_fixtures();
+        let full = parsers(Projection::all());
+        let minimal = parsers(Projection::minimal());
+
+        for ((full, name), (minimal, _)) in full.iter().zip(&minimal) {
+            let path = format!("{}/{name}", fixtures_dir());
+            let (expected, full_projection) = stream(full.as_ref(), &path);
+            let (projected, projection) = stream(minimal.as_ref(), &path);
+
+            assert!(!expected.is_empty(), "{name}");
+            assert!(full_projection.is_all(), "{name}");
+            assert_eq!(projection, Projection::minimal(), "{name}");
+            assert_eq!(
+                senders_and_times(&projected),
+                senders_and_times(&expected),
+                "{name}"
+            );
+            for msg in &projected {
+                assert!(msg.content.is_empty(), "{name}");
+                assert_eq!((msg.id, msg.reply_to, msg.edited), (None, None, None));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ids_kept_without_content() {
+        ensure_fixtures();
+        let projection = Projection {
+            need_ids: true,
+            ..Projection::minimal()
+        };
+        for ((full, name), (projected, _)) in
+            parsers(Projection::all()).iter().zip(&parsers(projection))
+        {
+            let path = format!("{}/{name}", fixtures_dir());
+            let (expected, _) = stream(full.as_ref(), &path);
+            let (messages, _) = stream(projected.as_ref(), &path);
+
+            // WhatsApp quotes are still resolved from content that is then dropped
+            let ids = |m: &[Message]| -> Vec<_> { m.iter().map(|m| (m.id, m.reply_to)).collect() };
+            assert_eq!(ids(&messages), ids(&expected), "{name}");
+            assert!(messages.iter().all(|m| m.content.is_empty()), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_telegram_chunks_deduplicated_before_projection() {
+        let dir = TempDir::new().unwrap();
+        for (name, ids) in [("result.json", [1, 2, 3]), ("result(2).json", [3, 4, 5])] {
+            let records: Vec<String> = ids
+                .iter()
+                .map(|id| {
+                    format!(
+                        r#"{{"id": {id}, "type": "message", "date_unixtime": "{}", "from": "User{id}", "text": "message {id}"}}"#,
+                        1_705_314_600 + id * 60
+                    )
+                })
+                .collect();
+            let json = format!("{{\"messages\": [{}]}}", records.join(","));
+            fs::write(dir.path().join(name), json).unwrap();
+        }
+        let config = StreamingConfig::new().with_projection(Projection::minimal());
+        let parser = TelegramStreamingParser::with_config(config);
+
+        let (messages, projection) = stream(&parser, dir.path().to_str().unwrap());
+
+        let senders: Vec<&str> = messages.iter().map(|m| m.sender.as_str()).collect();
+        assert_eq!(senders, ["User1", "User2", "User3", "User4", "User5"]);
+        assert!(messages.iter().all(|m| m.id.is_none()));
+        assert_eq!(projection, Projection::minimal());
+    }
+}
+
+// ============================================================================
+// Config Introspection Tests
+// ============================================================================
+
+mod config_introspection_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_schema_describes_current_config_of_every_platform() {
+        for &platform in Platform::all() {
+            let parser = create_parser(platform);
+            let config = parser.config_json();
+            let schema = parser.config_schema();
+
+            assert_eq!(schema.len(), config.as_object().unwrap().len());
+            for field in &schema {
+                assert_eq!(
+                    config[field.name], field.default,
+                    "{platform}: {}",
+                    field.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_config_json_changes_parsing() {
+        ensure_fixtures();
+        let path = format!("{}/telegram_simple.json", fixtures_dir());
+        let mut parser = create_parser(Platform::Telegram);
+
+        parser
+            .set_config_json(json!({ "sender_allowlist": ["bob"] }))
+            .unwrap();
+        let messages = parser.parse(Path::new(&path)).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].sender, "Bob");
+
+        let err = parser
+            .set_config_json(json!({ "mention_format": "shout" }))
+            .unwrap_err();
+        assert!(err.is_invalid_config());
+        assert_eq!(parser.config_json()["mention_format"], json!("as_exported"));
+    }
+}
+
+// ============================================================================
+// Truncated Export Tests
+// ============================================================================
+
+mod truncation_tests {
+    use super::*;
+    use chatpack::config::{DiscordConfig, TelegramConfig};
+    use chatpack::parsers::{DiscordParser, TelegramParser};
+    use chatpack::sink::{MessageRef, MessageSink, SkipReason};
+    use chatpack::streaming::{
+        DiscordStreamingParser, StreamingConfig, StreamingParser, TelegramStreamingParser,
+    };
+    use std::ops::ControlFlow;
+
+    const COMPLETE: usize = 5;
+
+    fn telegram_record(i: usize) -> String {
+        format!(
+            r#"{{"id": {i}, "type": "message", "date_unixtime": "{}", "from": "Alice", "text": "message {i} {{with braces}}"}}"#,
+            1_705_314_600 + i * 60
+        )
+    }
+
+    fn discord_record(i: usize) -> String {
+        format!(
+            r#"{{"id":"{i}","type":"Default","timestamp":"2024-01-15T10:3{i}:00+00:00","timestampEdited":null,"content":"message {i} {{with braces}}","author":{{"id":"111","name":"alice","nickname":"Alice"}},"attachments":[],"stickers":[],"embeds":[]}}"#
+        )
+    }
+
+    /// `record` cut mid-object, just before its `text_key`, and mid-string.
+    fn cuts(record: &str, text_key: &str) -> [(&'static str, String); 2] {
+        let mid_object = &record[..record.find(text_key).unwrap()];
+        let mid_string = &record[..record.find("with braces").unwrap() + 4];
+        [
+            ("mid-object", mid_object.to_string()),
+            ("mid-string", mid_string.to_string()),
+        ]
+    }
+
+    fn write_telegram(dir: &TempDir, cut: &str) -> std::path::PathBuf {
+        let records: Vec<String> = (1..=COMPLETE).map(telegram_record).collect();
+        let path = dir.path().join("result.json");
+        let content = format!(
+            "{{\n\"name\": \"Chat\",\n\"messages\": [\n{},\n{cut}",
+            records.join(",\n")
+        );
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn write_discord_jsonl(dir: &TempDir, cut: &str) -> std::path::PathBuf {
+        let records: Vec<String> = (1..=COMPLETE).map(discord_record).collect();
+        let path = dir.path().join("export.jsonl");
+        fs::write(&path, format!("{}\n{cut}", records.join("\n"))).unwrap();
+        path
+    }
+
+    #[derive(Default)]
+    struct ReasonSink {
+        messages: usize,
+        reasons: Vec<SkipReason>,
+    }
+
+    impl MessageSink for ReasonSink {
+        fn on_message(&mut self, _message: MessageRef<'_>) -> ControlFlow<()> {
+            self.messages += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_skip(&mut self, reason: &SkipReason) {
+            self.reasons.push(reason.clone());
+        }
+    }
+
+    #[test]
+    fn test_telegram_batch_keeps_complete_prefix() {
+        for (name, cut) in cuts(&telegram_record(COMPLETE + 1), "\"text\"") {
+            let dir = TempDir::new().unwrap();
+            let path = write_telegram(&dir, &cut);
+            let config = TelegramConfig::new().with_skip_invalid(false);
+
+            let strict = TelegramParser::with_config(config.clone());
+            assert!(strict.parse(&path).is_err(), "{name}");
+
+            let tolerant = TelegramParser::with_config(config.with_tolerate_truncation(true));
+            let (messages, skipped) = tolerant.parse_counted(&path).unwrap();
+            assert_eq!(messages.len(), COMPLETE, "{name}");
+            assert_eq!(skipped, 1, "{name}");
+            assert_eq!(messages[4].content, "message 5 {with braces}");
+        }
+    }
+
+    #[test]
+    fn test_telegram_streaming_keeps_complete_prefix() {
+        for (name, cut) in cuts(&telegram_record(COMPLETE + 1), "\"text\"") {
+            let dir = TempDir::new().unwrap();
+            let path = write_telegram(&dir, &cut);
+            let path = path.to_str().unwrap();
+            let config = StreamingConfig::new().with_skip_invalid(false);
+
+            let strict = TelegramStreamingParser::with_config(config);
+            let results: Vec<_> = strict.stream(path).unwrap().collect();
+            assert_eq!(results.len(), COMPLETE + 1, "{name}");
+            assert!(results[COMPLETE].is_err(), "{name}");
+
+            let tolerant =
+                TelegramStreamingParser::with_config(config.with_tolerate_truncation(true));
+            let mut iter = tolerant.stream(path).unwrap();
+            let messages: Vec<Message> = iter.by_ref().map(std::result::Result::unwrap).collect();
+            assert_eq!(messages.len(), COMPLETE, "{name}");
+            assert_eq!(iter.skipped(), 1, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_telegram_parser_streams_truncated_export() {
+        let dir = TempDir::new().unwrap();
+        let path = write_telegram(&dir, &cuts(&telegram_record(COMPLETE + 1), "\"text\"")[1].1);
+        let config = TelegramConfig::streaming()
+            .with_skip_invalid(false)
+            .with_tolerate_truncation(true);
+
+        let iter = TelegramParser::with_config(config)
+            .stream_with_progress(&path)
+            .unwrap();
+        let messages: Vec<Message> = iter.map(std::result::Result::unwrap).collect();
+        assert_eq!(messages.len(), COMPLETE);
+    }
+
+    #[test]
+    fn test_discord_jsonl_batch_keeps_complete_prefix() {
+        for (name, cut) in cuts(&discord_record(COMPLETE + 1), "\"content\"") {
+            let dir = TempDir::new().unwrap();
+            let path = write_discord_jsonl(&dir, &cut);
+            let config = DiscordConfig::new().with_skip_invalid(false);
+
+            let strict = DiscordParser::with_config(config.clone());
+            assert!(strict.parse(&path).is_err(), "{name}");
+
+            let tolerant = DiscordParser::with_config(config.with_tolerate_truncation(true));
+            let (messages, skipped) = tolerant.parse_counted(&path).unwrap();
+            assert_eq!(messages.len(), COMPLETE, "{name}");
+            assert_eq!(skipped, 1, "{name}");
+
+            let mut sink = ReasonSink::default();
+            tolerant.parse_into(&path, &mut sink).unwrap();
+            assert_eq!(sink.messages, COMPLETE, "{name}");
+            assert!(
+                matches!(
+                    sink.reasons.as_slice(),
+                    [SkipReason::Truncated { line: Some(6), .. }]
+                ),
+                "{name}: {:?}",
+                sink.reasons
+            );
+        }
+    }
+
+    #[test]
+    fn test_discord_jsonl_cut_line_before_the_end_is_invalid() {
+        let dir = TempDir::new().unwrap();
+        let cut = &cuts(&discord_record(COMPLETE + 1), "\"content\"")[1].1;
+        let path = write_discord_jsonl(&dir, &format!("{cut}\n{}", discord_record(7)));
+        let config = DiscordConfig::new().with_tolerate_truncation(true);
+
+        let (messages, skipped) = DiscordParser::with_config(config.clone())
+            .parse_counted(&path)
+            .unwrap();
+        assert_eq!((messages.len(), skipped), (COMPLETE + 1, 1));
+
+        let strict = DiscordParser::with_config(config.with_skip_invalid(false));
+        assert!(strict.parse(&path).is_err());
+    }
+
+    #[test]
+    fn test_discord_jsonl_streaming_keeps_complete_prefix() {
+        for (name, cut) in cuts(&discord_record(COMPLETE + 1), "\"content\"") {
+            let dir = TempDir::new().unwrap();
+            let path = write_discord_jsonl(&dir, &cut);
+            let path = path.to_str().unwrap();
+            let config = StreamingConfig::new().with_skip_invalid(false);
+
+            let strict = DiscordStreamingParser::with_config(config);
+            let results: Vec<_> = strict.stream(path).unwrap().collect();
+            assert_eq!(results.len(), COMPLETE + 1, "{name}");
+            assert!(results[COMPLETE].is_err(), "{name}");
+
+            let tolerant =
+                DiscordStreamingParser::with_config(config.with_tolerate_truncation(true));
+            let mut iter = tolerant.stream(path).unwrap();
+            let messages: Vec<Message> = iter.by_ref().map(std::result::Result::unwrap).collect();
+            assert_eq!(messages.len(), COMPLETE, "{name}");
+            assert_eq!(iter.skipped(), 1, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_telegram_cut_after_messages_array() {
+        let dir = TempDir::new().unwrap();
+        let path = write_telegram(&dir, "");
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(
+            &path,
+            format!("{}\n]\n", content.trim_end().trim_end_matches(',')),
+        )
+        .unwrap();
+        let config = TelegramConfig::new().with_skip_invalid(false);
+
+        assert!(
+            TelegramParser::with_config(config.clone())
+                .parse(&path)
+                .is_err()
+        );
+        let tolerant = TelegramParser::with_config(config.with_tolerate_truncation(true));
+        let (messages, skipped) = tolerant.parse_counted(&path).unwrap();
+        assert_eq!((messages.len(), skipped), (COMPLETE, 0));
+
+        let streaming = TelegramStreamingParser::with_config(
+            StreamingConfig::new()
+                .with_skip_invalid(false)
+                .with_tolerate_truncation(true),
+        );
+        let streamed: Vec<Message> = streaming
+            .stream(path.to_str().unwrap())
+            .unwrap()
+            .map(std::result::Result::unwrap)
+            .collect();
+        assert_eq!(streamed, messages);
+    }
+
+    #[test]
+    fn test_telegram_nested_messages_key_is_ignored() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("result.json");
+        let decoy = telegram_record(99);
+        fs::write(
+            &path,
+            format!(
+                "{{\n\"name\": \"Chat\",\n\"meta\": {{\"messages\": [{decoy}]}},\n\"messages\": [\n{}\n]\n}}",
+                telegram_record(1)
+            ),
+        )
+        .unwrap();
+
+        let batch = TelegramParser::new().parse(&path).unwrap();
+        let streamed: Vec<Message> = TelegramStreamingParser::new()
+            .stream(path.to_str().unwrap())
+            .unwrap()
+            .map(std::result::Result::unwrap)
+            .collect();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].content, "message 1 {with braces}");
+        assert_eq!(streamed, batch);
+    }
+
+    #[test]
+    fn test_truncation_without_complete_records_still_fails() {
+        let dir = TempDir::new().unwrap();
+        let path = write_telegram(&dir, "");
+        fs::write(&path, "{\"messages\": [{\"id\": 1, \"te").unwrap();
+        let config = TelegramConfig::new().with_tolerate_truncation(true);
+        assert!(TelegramParser::with_config(config).parse(&path).is_err());
+    }
+}
+
+// ============================================================================
+// Tail Reading Tests
+// ============================================================================
+
+mod tail_tests {
+    use super::*;
+    use chatpack::streaming::{
+        DiscordStreamingParser, StreamingParser, TelegramStreamingParser, WhatsAppStreamingParser,
+    };
+
+    const COUNTS: [usize; 7] = [0, 1, 2, 7, 50, 1999, 5000];
+
+    /// The last `n` messages of a full streaming parse, without positions.
+    fn full_tail(parser: &dyn StreamingParser, path: &Path, n: usize) -> Vec<Message> {
+        let mut messages: Vec<Message> = parser
+            .stream(path.to_str().unwrap())
+            .unwrap()
+            .map(std::result::Result::unwrap)
+            .collect();
+        let messages = messages.split_off(messages.len().saturating_sub(n));
+        messages
+            .into_iter()
+            .map(|mut msg| {
+                msg.source_index = None;
+                msg
+            })
+            .collect()
+    }
+
+    fn assert_tails_match(parser: &dyn StreamingParser, path: &Path) {
+        for n in COUNTS {
+            let tail = parser.stream_tail(path.to_str().unwrap(), n).unwrap();
+            assert_eq!(tail, full_tail(parser, path, n), "n = {n}");
+        }
+    }
+
+    /// 2000 headers (over 64 KB) with continuation lines, blank lines, and
+    /// system messages the parser drops.
+    fn write_whatsapp(dir: &TempDir) -> std::path::PathBuf {
+        let mut content = String::new();
+        for i in 0..2000 {
+            let (day, hour, minute) = (1 + i / 720, 1 + (i / 60) % 12, i % 60);
+            let header = format!("[1/{day}/24, {hour}:{minute:02}:00 AM]");
+            match i % 10 {
+                0 => content.push_str(&format!(
+                    "{header} Alice: Messages and calls are end-to-end encrypted.\n"
+                )),
+                3 => content.push_str(&format!("{header} Bob: line {i}\ncontinued {i}\n\n")),
+                _ => content.push_str(&format!("{header} Alice: message {i}\n")),
+            }
+        }
+        let path = dir.path().join("chat.txt");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// 2000 records with blank lines and empty messages the parser drops.
+    fn write_discord_jsonl(dir: &TempDir) -> std::path::PathBuf {
+        let mut content = String::new();
+        for i in 0..2000 {
+            let text = if i % 7 == 0 {
+                String::new()
+            } else {
+                format!("message {i}")
+            };
+            content.push_str(&format!(
+                r#"{{"id":"{i}","timestamp":"2024-01-15T10:30:00+00:00","content":"{text}","author":{{"name":"user{}"}}}}"#,
+                i % 3
+            ));
+            content.push_str(if i % 5 == 0 { "\n\n" } else { "\n" });
+        }
+        let path = dir.path().join("export.jsonl");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_whatsapp_tail_matches_full_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_whatsapp(&dir);
+        assert_tails_match(&WhatsAppStreamingParser::new(), &path);
+
+        let tail = WhatsAppStreamingParser::new()
+            .stream_tail(path.to_str().unwrap(), 7)
+            .unwrap();
+        assert_eq!(tail[0].content, "line 1993\ncontinued 1993");
+        assert_eq!(tail[6].content, "message 1999");
+        assert!(tail.iter().all(|msg| msg.timestamp.is_some()));
+    }
+
+    #[test]
+    fn test_whatsapp_tail_clears_replies() {
+        ensure_fixtures();
+        let path = Path::new(&fixtures_dir()).join("whatsapp_quotes.txt");
+        let parser = WhatsAppStreamingParser::new();
+        let full: Vec<Message> = parser
+            .stream(path.to_str().unwrap())
+            .unwrap()
+            .map(std::result::Result::unwrap)
+            .collect();
+        assert_eq!(full[3].reply_to, Some(2));
+
+        let tail = parser.stream_tail(path.to_str().unwrap(), 3).unwrap();
+        assert_eq!(tail[1].content, "Great, see you there");
+        assert!(tail.iter().all(|msg| msg.reply_to.is_none()));
+    }
+
+    #[test]
+    fn test_whatsapp_tail_with_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_whatsapp(&dir);
+        // Bob wrote one message in ten, so the start moves back repeatedly
+        let parser = WhatsAppStreamingParser::new().with_sender_allowlist(Some(vec!["bob".into()]));
+        assert_tails_match(&parser, &path);
+        assert_eq!(
+            parser
+                .stream_tail(path.to_str().unwrap(), 500)
+                .unwrap()
+                .len(),
+            200
+        );
+    }
+
+    #[test]
+    fn test_discord_jsonl_tail_matches_full_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_discord_jsonl(&dir);
+        assert_tails_match(&DiscordStreamingParser::new(), &path);
+
+        let parser =
+            DiscordStreamingParser::new().with_sender_allowlist(Some(vec!["user0".into()]));
+        assert_tails_match(&parser, &path);
+    }
+
+    #[test]
+    fn test_tail_unsupported() {
+        ensure_fixtures();
+        let dir = fixtures_dir();
+        let cases: [(&dyn StreamingParser, &str); 3] = [
+            (&TelegramStreamingParser::new(), "telegram_simple.json"),
+            (&DiscordStreamingParser::new(), "discord.json"),
+            (&WhatsAppStreamingParser::new(), "whatsapp_utf16le.txt"),
+        ];
+        for (parser, file) in cases {
+            let path = Path::new(&dir).join(file);
+            let err = parser.stream_tail(path.to_str().unwrap(), 10).unwrap_err();
+            assert!(err.is_unsupported_operation(), "{file}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_tail_missing_file() {
+        let err = WhatsAppStreamingParser::new()
+            .stream_tail("/nonexistent/chat.txt", 10)
+            .unwrap_err();
+        assert!(err.is_io());
+    }
+}
+
+// ============================================================================
+// Loss Report Tests
+// ============================================================================
+
+mod loss_report_tests {
+    use super::*;
+    use chatpack::config::TelegramConfig;
+    use chatpack::core::processor::merge_consecutive_with_loss;
+    use chatpack::parsers::TelegramParser;
+    use chatpack::pipeline::{PipelineConfig, run_pipelined, run_sequential};
+    use chatpack::sink::CollectingSink;
+    use std::collections::BTreeMap;
+
+    fn dropped(fields: &[(&str, usize)]) -> BTreeMap<String, usize> {
+        fields
+            .iter()
+            .map(|(field, count)| ((*field).to_string(), *count))
+            .collect()
+    }
+
+    #[test]
+    fn test_convert_reports_loss() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/telegram_meta.json", fixtures_dir());
+        let options =
+            ConvertOptions::new().with_output_config(OutputConfig::new().with_timestamps());
+
+        let stats = convert(&input, dir.path().join("out.csv"), options).unwrap();
+        assert_eq!(
+            stats.loss,
+            LossReport {
+                system_messages_skipped: 2,
+                messages_merged: 2,
+                ids_discarded: 2,
+                fields_dropped: dropped(&[
+                    ("edited", 1),
+                    ("id", 3),
+                    ("platform", 3),
+                    ("reply_to", 1)
+                ]),
+                ..LossReport::default()
+            }
+        );
+
+        let options = ConvertOptions::new()
+            .with_filter(FilterConfig::new().with_sender("Bob"))
+            .with_merge(false)
+            .with_output_config(OutputConfig::all());
+        let stats = convert(&input, dir.path().join("out.jsonl"), options).unwrap();
+        assert_eq!(
+            stats.loss,
+            LossReport {
+                system_messages_skipped: 2,
+                messages_filtered: 3,
+                fields_dropped: dropped(&[("platform", 2)]),
+                ..LossReport::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_pipelined_loss_matches_sequential() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/telegram_meta.json", fixtures_dir());
+        let config = PipelineConfig::new(Platform::Telegram, &input, dir.path().join("out.csv"))
+            .with_merge(true);
+
+        let sequential = run_sequential(&config).unwrap();
+        let pipelined = run_pipelined(&config).unwrap();
+        assert_eq!(sequential.loss, pipelined.loss);
+        assert_eq!(pipelined.loss.system_messages_skipped, 2);
+        assert_eq!(pipelined.loss.messages_merged, 2);
+    }
+
+    #[test]
+    fn test_merging_flattens_attachments() {
+        ensure_fixtures();
+        let input = format!("{}/telegram_meta.json", fixtures_dir());
+        let parser =
+            TelegramParser::with_config(TelegramConfig::new().with_collect_attachment_refs(true));
+        let mut sink = CollectingSink::new();
+        parser.parse_into(input.as_ref(), &mut sink).unwrap();
+        assert_eq!(sink.system_messages, 2);
+
+        let mut loss = LossReport::new();
+        let merged = merge_consecutive_with_loss(sink.messages, &mut loss);
+        for msg in &merged {
+            loss.count_unwritten(msg, &OutputConfig::all());
+        }
+        assert_eq!(loss.attachments_flattened, 1);
+        assert_eq!(
+            loss.fields_dropped,
+            dropped(&[("attachments", 1), ("platform", 3)])
+        );
+        assert_eq!(merged[0].attachments.len(), 2);
+    }
+
+    #[test]
+    fn test_system_messages_reported_by_line_parsers() {
+        ensure_fixtures();
+        let mut sink = CollectingSink::new();
+        create_parser(Platform::WhatsApp)
+            .parse_into(
+                format!("{}/whatsapp_us.txt", fixtures_dir()).as_ref(),
+                &mut sink,
+            )
+            .unwrap();
+        assert_eq!(sink.system_messages, 1);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("channel.jsonl");
+        fs::write(
+            &path,
+            concat!(
+                r#"{"id":"1","type":"Default","timestamp":"2024-01-15T10:01:00+00:00","content":"Hello","author":{"name":"Alice"}}"#,
+                "\n",
+                r#"{"id":"2","type":"GuildMemberJoin","timestamp":"2024-01-15T10:02:00+00:00","content":"Joined the server.","author":{"name":"Bob"}}"#,
+            ),
+        )
+        .unwrap();
+        let mut sink = CollectingSink::new();
+        create_parser(Platform::Discord)
+            .parse_into(&path, &mut sink)
+            .unwrap();
+        assert_eq!((sink.messages.len(), sink.system_messages), (1, 1));
+    }
+
+    #[test]
+    fn test_blank_messages_reported() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("chat.txt");
+        fs::write(
+            &input,
+            "[1/15/24, 10:30 AM] Alice: Hi\n[1/15/24, 10:31 AM] Bob: \n[1/15/24, 10:32 AM] Alice: Bye\n",
+        )
+        .unwrap();
+
+        let options = ConvertOptions::new()
+            .with_platform(Platform::WhatsApp)
+            .with_merge(false);
+        let stats = convert(&input, dir.path().join("out.csv"), options).unwrap();
+        assert_eq!(stats.merged_count, 2);
+        assert_eq!(stats.loss.blank_messages_skipped, 1);
+
+        let config = PipelineConfig::new(Platform::WhatsApp, &input, dir.path().join("out.csv"));
+        assert_eq!(
+            run_pipelined(&config).unwrap().loss.blank_messages_skipped,
+            1
+        );
+        let windowed = config.with_max_memory(1 << 20);
+        assert_eq!(
+            run_sequential(&windowed)
+                .unwrap()
+                .loss
+                .blank_messages_skipped,
+            1
+        );
+    }
+}
+
+// ============================================================================
+// Strict Sender Detection Tests
+// ============================================================================
+
+mod strict_sender_tests {
+    use super::*;
+    use chatpack::config::WhatsAppConfig;
+    use chatpack::parsers::WhatsAppParser;
+    use chatpack::streaming::{StreamingParser, WhatsAppStreamingParser};
+
+    fn fixture() -> String {
+        ensure_fixtures();
+        format!("{}/whatsapp_colon_sender.txt", fixtures_dir())
+    }
+
+    fn strict(streaming: bool) -> WhatsAppParser {
+        WhatsAppParser::with_config(
+            WhatsAppConfig::new()
+                .with_streaming(streaming)
+                .with_strict_sender_detection(true),
+        )
+    }
+
+    fn pairs(messages: &[Message]) -> Vec<(&str, &str)> {
+        messages
+            .iter()
+            .map(|m| (m.sender.as_str(), m.content.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_default_splits_at_first_colon() {
+        let messages = WhatsAppParser::new().parse_file(&fixture()).unwrap();
+        assert_eq!(
+            pairs(&messages)[1..=3],
+            [
+                ("Mom", "work: Hello dear"),
+                ("Alice", "Note: remember the milk"),
+                ("Mom", "work: Note: remember the eggs"),
+            ]
+        );
+    }
 
-        let has_edited = messages.iter().any(|m| m.edited.is_some());
-        assert!(has_edited);
+    #[test]
+    fn test_strict_learns_colon_sender() {
+        let messages = strict(false).parse_file(&fixture()).unwrap();
+        assert_eq!(
+            pairs(&messages),
+            [
+                ("Alice", "Hi Mom"),
+                ("Mom: work", "Hello dear"),
+                ("Alice", "Note: remember the milk"),
+                ("Mom: work", "Note: remember the eggs"),
+                ("Mom: work", "See you"),
+                ("Alice", "Shopping list\nNote: remember the bread"),
+            ]
+        );
     }
 
     #[test]
-    fn test_merge_consecutive() {
-        ensure_fixtures();
-        let parser = create_parser(Platform::Telegram);
-        let messages = parser
-            .parse_file(&format!("{}/telegram_simple.json", fixtures_dir()))
-            .unwrap();
+    fn test_strict_modes_agree() {
+        let path = fixture();
+        let expected = strict(false).parse_file(&path).unwrap();
 
-        let original_count = messages.len();
-        let merged = merge_consecutive(messages);
+        let streamed = strict(true).parse_file(&path).unwrap();
+        assert_eq!(streamed, expected);
 
-        assert!(merged.len() <= original_count);
+        for parser in [strict(false), strict(true)] {
+            let mut visited: Vec<Message> = Vec::new();
+            parser.parse_into(Path::new(&path), &mut visited).unwrap();
+            assert_eq!(visited, expected);
+        }
+
+        let parser = WhatsAppStreamingParser::new().with_strict_sender_detection(true);
+        let streamed: Vec<Message> = parser
+            .stream(&path)
+            .unwrap()
+            .map(std::result::Result::unwrap)
+            .collect();
+        assert_eq!(pairs(&streamed), pairs(&expected));
+        assert_eq!(
+            pairs(&parser.stream_tail(&path, 2).unwrap()),
+            pairs(&expected[4..])
+        );
     }
 
     #[test]
-    fn test_parser_name() {
-        let parser = create_parser(Platform::Telegram);
-        assert_eq!(parser.name(), "Telegram");
+    fn test_dash_in_sender_name() {
+        ensure_fixtures();
+        let path = format!("{}/whatsapp_dash_sender.txt", fixtures_dir());
+        let expected = [
+            ("Alice - Bob", "Hello"),
+            ("Me", "Hi! How are you - all good?"),
+            (
+                "Alice - Bob",
+                "Fine - thanks: the trip is on\nsecond line - with a dash",
+            ),
+            ("Alice - Bob", "Note: bring the tickets"),
+            ("Me", "See you - bye"),
+            ("Alice - Bob", "👍"),
+        ];
+
+        let parsers = [
+            WhatsAppParser::new(),
+            WhatsAppParser::with_config(WhatsAppConfig::new().with_streaming(true)),
+            strict(false),
+            strict(true),
+        ];
+        for parser in parsers {
+            let messages = parser.parse_file(&path).unwrap();
+            assert_eq!(pairs(&messages), expected);
+        }
     }
 }
 
 // ============================================================================
-// WhatsApp Parser Tests
+// Content Transform Tests
 // ============================================================================
 
-mod whatsapp_tests {
+mod transform_tests {
     use super::*;
+    use chatpack::core::output::read_jsonl;
+    use chatpack::core::processor::merge_consecutive;
+    use chatpack::core::transform::{Redact, TransformChain};
+    use chatpack::pipeline::{PipelineConfig, run_pipelined, run_sequential};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Appends " ok", then uppercases, counting the messages seen.
+    fn chain(calls: &Arc<AtomicUsize>) -> TransformChain {
+        let calls = Arc::clone(calls);
+        TransformChain::new()
+            .with(move |msg: &mut Message| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                msg.content.push_str(" ok");
+            })
+            .with(|msg: &mut Message| msg.content = msg.content.to_uppercase())
+            .with(Redact::new(["HTTPS://EXAMPLE.COM"]))
+    }
 
     #[test]
-    fn test_parse_us_format() {
+    fn test_transforms_run_after_filter_before_merge() {
         ensure_fixtures();
-        let parser = create_parser(Platform::WhatsApp);
-        let messages = parser
-            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
-            .unwrap();
-
-        assert!(!messages.is_empty());
-
-        let senders: Vec<&str> = messages.iter().map(|m| m.sender.as_str()).collect();
-        assert!(senders.contains(&"Alice"));
-        assert!(senders.contains(&"Bob"));
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/whatsapp_us.txt", fixtures_dir());
+        let output = dir.path().join("out.jsonl");
+        let calls = Arc::new(AtomicUsize::new(0));
+        let options = ConvertOptions::new()
+            .with_filter(FilterConfig::new().with_sender("Alice"))
+            .with_transforms(chain(&calls));
+
+        convert(&input, &output, options).unwrap();
+
+        let alice: Vec<Message> = WhatsAppParser::new()
+            .parse(Path::new(&input))
+            .unwrap()
+            .into_iter()
+            .filter(|m| m.sender == "Alice")
+            .map(|mut m| {
+                m.content = format!("{} ok", m.content).to_uppercase();
+                m
+            })
+            .collect();
+        assert_eq!(calls.load(Ordering::Relaxed), alice.len());
+
+        let written = read_jsonl(&output).unwrap();
+        assert_eq!(written.len(), 1);
+        assert_eq!(
+            written[0].content,
+            merge_consecutive(alice)[0]
+                .content
+                .replace("HTTPS://EXAMPLE.COM", "[REDACTED]")
+        );
+        assert!(written[0].content.starts_with("HELLO EVERYONE! OK\n"));
     }
 
     #[test]
-    fn test_parse_eu_format() {
+    fn test_pipelined_transforms_match_sequential() {
         ensure_fixtures();
-        let parser = create_parser(Platform::WhatsApp);
-        let messages = parser
-            .parse_file(&format!("{}/whatsapp_eu.txt", fixtures_dir()))
-            .unwrap();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/whatsapp_us.txt", fixtures_dir());
+        let (seq_calls, par_calls) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let config = |out: &str, calls: &Arc<AtomicUsize>| {
+            PipelineConfig::new(Platform::WhatsApp, &input, dir.path().join(out))
+                .with_merge(true)
+                .with_transforms(chain(calls))
+                .with_channel_capacity(1)
+        };
 
-        assert!(!messages.is_empty());
-        let has_cyrillic = messages.iter().any(|m| m.content.contains("Привет"));
-        assert!(has_cyrillic);
+        let seq_stats = run_sequential(&config("seq.csv", &seq_calls)).unwrap();
+        let par_stats = run_pipelined(&config("par.csv", &par_calls)).unwrap();
+        assert_eq!(seq_stats, par_stats);
+        assert_eq!(
+            fs::read(dir.path().join("seq.csv")).unwrap(),
+            fs::read(dir.path().join("par.csv")).unwrap()
+        );
+        assert_eq!(seq_calls.load(Ordering::Relaxed), seq_stats.original_count);
+        assert_eq!(par_calls.load(Ordering::Relaxed), seq_stats.original_count);
     }
+}
 
-    #[test]
-    fn test_system_messages_filtered() {
-        ensure_fixtures();
-        let parser = create_parser(Platform::WhatsApp);
-        let messages = parser
-            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
-            .unwrap();
+// ============================================================================
+// Discord Reply Context Tests
+// ============================================================================
 
-        let has_system = messages.iter().any(|m| {
-            m.content.contains("end-to-end encrypted") || m.content.contains("сквозным шифрованием")
-        });
-        assert!(!has_system);
-    }
+mod discord_reply_tests {
+    use super::*;
+    use chatpack::config::DiscordConfig;
+    use chatpack::parsers::DiscordParser;
 
-    #[test]
-    fn test_media_preserved() {
+    fn fixture() -> String {
         ensure_fixtures();
-        let parser = create_parser(Platform::WhatsApp);
-        let messages = parser
-            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
-            .unwrap();
-
-        let has_media = messages.iter().any(|m| m.content.contains("Media omitted"));
-        assert!(has_media);
+        format!("{}/discord_replies.txt", fixtures_dir())
     }
 
     #[test]
-    fn test_parser_name() {
-        let parser = create_parser(Platform::WhatsApp);
-        assert_eq!(parser.name(), "WhatsApp");
+    fn test_resolved_replies() {
+        let messages = DiscordParser::new().parse_file(&fixture()).unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(
+            messages[0].content,
+            "Are we still on for lunch tomorrow at noon?"
+        );
+        assert_eq!(messages[1].content, "Yes, see you there");
+        assert_eq!(messages[1].reply_to, messages[0].source_index);
+        assert_eq!(messages[2].content, "Great!");
+        assert_eq!(messages[2].reply_to, messages[1].source_index);
     }
 
     #[test]
-    fn test_consecutive_merge() {
-        ensure_fixtures();
-        let parser = create_parser(Platform::WhatsApp);
-        let messages = parser
-            .parse_file(&format!("{}/whatsapp_us.txt", fixtures_dir()))
-            .unwrap();
-
-        let original = messages.len();
-        let merged = merge_consecutive(messages);
+    fn test_unresolved_reply_marker() {
+        let messages = DiscordParser::new().parse_file(&fixture()).unwrap();
+        assert_eq!(
+            messages[3].content,
+            "[Replying to: carol: Did anyone book a table?]\nNot yet"
+        );
+        assert_eq!(messages[3].reply_to, None);
 
-        assert!(merged.len() <= original);
+        let parser =
+            DiscordParser::with_config(DiscordConfig::new().with_mark_unresolved_quotes(false));
+        let messages = parser.parse_file(&fixture()).unwrap();
+        assert_eq!(messages[3].content, "Not yet");
+        assert_eq!(messages[2].reply_to, Some(1));
     }
 }
 
 // ============================================================================
-// Instagram Parser Tests
+// Long Line Tests
 // ============================================================================
 
-mod instagram_tests {
+mod long_line_tests {
     use super::*;
+    use chatpack::config::{DiscordConfig, WhatsAppConfig};
+    use chatpack::error::StreamingErrorKind;
+    use chatpack::parsers::{DiscordParser, WhatsAppParser};
+    use chatpack::sink::CollectingSink;
+    use std::time::{Duration, Instant};
+
+    /// A generated export whose second message is one 20MB line.
+    fn export(dir: &TempDir, name: &str, header: &str, before: &str, after: &str) -> String {
+        let path = dir.path().join(name);
+        let mut content = String::with_capacity(21 * 1024 * 1024);
+        content.push_str(before);
+        content.push_str(header);
+        content.push_str(&"x".repeat(20 * 1024 * 1024));
+        content.push('\n');
+        content.push_str(after);
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
 
-    #[test]
-    fn test_parse_instagram() {
-        ensure_fixtures();
-        let parser = create_parser(Platform::Instagram);
-        let messages = parser
-            .parse_file(&format!("{}/instagram.json", fixtures_dir()))
-            .unwrap();
+    /// The long line is a header followed by a continuation line, which
+    /// goes with it.
+    fn whatsapp_export(dir: &TempDir) -> String {
+        export(
+            dir,
+            "whatsapp_long_line.txt",
+            "[1/15/24, 10:31:00 AM] Bob: ",
+            "[1/15/24, 10:30:00 AM] Alice: Hi\n",
+            "more from Bob\n[1/15/24, 10:32:00 AM] Carol: After\n",
+        )
+    }
 
-        assert!(!messages.is_empty());
+    fn contents(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
 
-        let senders: Vec<&str> = messages.iter().map(|m| m.sender.as_str()).collect();
-        assert!(senders.contains(&"user_one"));
-        assert!(senders.contains(&"user_two"));
+    #[test]
+    fn test_whatsapp_long_line_skipped() {
+        let dir = TempDir::new().unwrap();
+        let path = whatsapp_export(&dir);
+        let started = Instant::now();
+
+        let parser = WhatsAppParser::new();
+        let (messages, skipped) = parser.parse_counted(Path::new(&path)).unwrap();
+        assert_eq!(contents(&messages), ["Hi", "After"]);
+        assert_eq!(skipped, 1);
+
+        let mut sink = CollectingSink::new();
+        parser.parse_into(Path::new(&path), &mut sink).unwrap();
+        assert_eq!(contents(&sink.messages), ["Hi", "After"]);
+        assert_eq!(sink.skipped, 1);
+
+        let mut iter = WhatsAppParser::with_config(
+            WhatsAppConfig::new()
+                .with_streaming(true)
+                .with_strict_sender_detection(true),
+        )
+        .stream_with_progress(Path::new(&path))
+        .unwrap();
+        let streamed: Vec<Message> = iter.by_ref().map(Result::unwrap).collect();
+        assert_eq!(contents(&streamed), ["Hi", "After"]);
+        assert_eq!(iter.skipped(), 1);
+
+        assert!(started.elapsed() < Duration::from_secs(20));
     }
 
     #[test]
-    fn test_shared_links() {
-        ensure_fixtures();
-        let parser = create_parser(Platform::Instagram);
-        let messages = parser
-            .parse_file(&format!("{}/instagram.json", fixtures_dir()))
-            .unwrap();
+    fn test_whatsapp_long_header_after_detection_sample() {
+        let dir = TempDir::new().unwrap();
+        let before = (0..30)
+            .map(|i| format!("[1/15/24, 10:{i:02}:00 AM] Alice: m{i}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n";
+        let path = export(
+            &dir,
+            "whatsapp_late_long_line.txt",
+            "[1/15/24, 10:31:00 AM] Bob: ",
+            &before,
+            "more from Bob\n[1/15/24, 10:32:00 AM] Carol: After\n",
+        );
+        let expected: Vec<String> = (0..30)
+            .map(|i| format!("m{i}"))
+            .chain(["After".to_string()])
+            .collect();
+
+        let parser = WhatsAppParser::new();
+        assert_eq!(contents(&parser.parse(Path::new(&path)).unwrap()), expected);
+        let mut sink = CollectingSink::new();
+        parser.parse_into(Path::new(&path), &mut sink).unwrap();
+        assert_eq!(contents(&sink.messages), expected);
+        let streamed: Vec<Message> = WhatsAppParser::with_streaming()
+            .stream(Path::new(&path))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(contents(&streamed), expected);
+    }
 
-        let has_shared = messages.iter().any(|m| {
-            m.content.contains("Shared") || m.content.contains("https://instagram.com/p/xyz")
-        });
-        assert!(has_shared, "Should contain 'Shared' or the actual link");
+    #[test]
+    fn test_whatsapp_long_line_fails_without_skip_invalid() {
+        let dir = TempDir::new().unwrap();
+        let path = whatsapp_export(&dir);
+        let config = WhatsAppConfig::new().with_skip_invalid(false);
+
+        let err = WhatsAppParser::with_config(config.clone())
+            .parse(Path::new(&path))
+            .unwrap_err();
+        assert!(matches!(err, ChatpackError::BufferOverflow { .. }));
+
+        let mut sink = CollectingSink::new();
+        let err = WhatsAppParser::with_config(config.clone())
+            .parse_into(Path::new(&path), &mut sink)
+            .unwrap_err();
+        assert!(matches!(err, ChatpackError::BufferOverflow { .. }));
+
+        // Detection reads the long line, so the stream fails to open
+        let result =
+            WhatsAppParser::with_config(config.with_streaming(true)).stream(Path::new(&path));
+        assert!(matches!(
+            result,
+            Err(ChatpackError::Streaming(
+                StreamingErrorKind::BufferOverflow { .. }
+            ))
+        ));
     }
 
     #[test]
-    fn test_empty_content_filtered() {
-        ensure_fixtures();
-        let parser = create_parser(Platform::Instagram);
-        let messages = parser
-            .parse_file(&format!("{}/instagram.json", fixtures_dir()))
-            .unwrap();
+    fn test_whatsapp_max_line_length() {
+        let content =
+            "[1/15/24, 10:30:00 AM] Alice: Hi\n[1/15/24, 10:31:00 AM] Bob: a longer line\n";
+        let parser = WhatsAppParser::with_config(WhatsAppConfig::new().with_max_line_length(40));
+        assert_eq!(contents(&parser.parse_str(content).unwrap()), ["Hi"]);
+    }
 
-        let has_empty = messages.iter().any(|m| m.content.is_empty());
-        assert!(!has_empty);
+    #[test]
+    fn test_discord_txt_long_line_skipped() {
+        let dir = TempDir::new().unwrap();
+        let path = export(
+            &dir,
+            "discord_long_line.txt",
+            "",
+            "[1/15/2024 10:30 AM] Alice\nHi\n[1/15/2024 10:31 AM] bob\n",
+            "[1/15/2024 10:32 AM] carol\nAfter",
+        );
+        let started = Instant::now();
+
+        let (messages, skipped) = DiscordParser::new()
+            .parse_counted(Path::new(&path))
+            .unwrap();
+        // bob's only line is gone, so his message is empty and dropped
+        assert_eq!(contents(&messages), ["Hi", "After"]);
+        assert_eq!(skipped, 1);
+        assert!(started.elapsed() < Duration::from_secs(20));
+
+        let err = DiscordParser::with_config(DiscordConfig::new().with_skip_invalid(false))
+            .parse(Path::new(&path))
+            .unwrap_err();
+        assert!(matches!(err, ChatpackError::BufferOverflow { .. }));
     }
+}
+
+// ============================================================================
+// Validation Tests
+// ============================================================================
+
+mod validation_tests {
+    use super::*;
+    use chatpack::core::output::validate_for_format;
+    use chatpack::format::OutputFormat;
 
     #[test]
-    fn test_parser_name() {
-        let parser = create_parser(Platform::Instagram);
-        assert_eq!(parser.name(), "Instagram");
+    fn test_clean_fixtures_have_no_warnings() {
+        ensure_fixtures();
+        let fixtures = [
+            (Platform::Telegram, "telegram_simple.json"),
+            (Platform::WhatsApp, "whatsapp_us.txt"),
+            (Platform::WhatsApp, "whatsapp_eu.txt"),
+        ];
+
+        for (platform, file) in fixtures {
+            let messages = create_parser(platform)
+                .parse_file(&format!("{}/{file}", fixtures_dir()))
+                .unwrap();
+            for &format in OutputFormat::all() {
+                let warnings = validate_for_format(&messages, format, &OutputConfig::all());
+                assert!(warnings.is_empty(), "{file} as {format}: {warnings:?}");
+            }
+        }
     }
 
     #[test]
-    fn test_timestamps() {
+    fn test_multiline_content_flagged_for_csv() {
         ensure_fixtures();
-        let parser = create_parser(Platform::Instagram);
-        let messages = parser
-            .parse_file(&format!("{}/instagram.json", fixtures_dir()))
+        let messages = create_parser(Platform::Discord)
+            .parse_file(&format!("{}/discord.json", fixtures_dir()))
             .unwrap();
+        let config = OutputConfig::all();
 
-        assert!(messages.iter().all(|m| m.timestamp.is_some()));
+        assert!(validate_for_format(&messages, OutputFormat::Jsonl, &config).is_empty());
+
+        let warnings = validate_for_format(&messages, OutputFormat::Csv, &config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].index, 1);
+        assert_eq!(
+            warnings[0].to_string(),
+            "message 1: content contains a line break"
+        );
     }
 }
 
@@ -664,6 +5345,35 @@ mod filter_integration_tests {
                 .all(|m| m.sender.eq_ignore_ascii_case("Alice"))
         );
     }
+
+    #[test]
+    fn test_filter_date_field() {
+        use chatpack::core::DateField;
+
+        ensure_fixtures();
+        let parser = create_parser(Platform::Telegram);
+        let messages = parser
+            .parse_file(&format!("{}/telegram_meta.json", fixtures_dir()))
+            .unwrap();
+
+        // Message 1 was sent at 10:30:00 and edited at 10:30:10; the rest
+        // were sent from 10:31 on and never edited
+        let after = START + chrono::Duration::seconds(5);
+        let ids = |field| -> Vec<Option<u64>> {
+            let config = FilterConfig::new().with_after(after).with_date_field(field);
+            apply_filters(messages.clone(), &config)
+                .iter()
+                .map(|m| m.id)
+                .collect()
+        };
+
+        assert_eq!(ids(DateField::Sent), [Some(2), Some(4), Some(6), Some(7)]);
+        assert_eq!(ids(DateField::Edited), [Some(1)]);
+        assert_eq!(
+            ids(DateField::EitherNewest),
+            [Some(1), Some(2), Some(4), Some(6), Some(7)]
+        );
+    }
 }
 
 // ============================================================================
@@ -715,6 +5425,7 @@ mod stats_tests {
 
 mod output_config_tests {
     use super::*;
+    use chatpack::core::{LineEnding, SortKey};
 
     #[test]
     fn test_output_config_default() {
@@ -749,7 +5460,17 @@ mod output_config_tests {
             include_timestamps: false,
             include_ids: false,
             include_replies: false,
+            include_index: false,
             include_edited: false,
+            include_metadata: false,
+            include_platform: false,
+            include_raw: false,
+            include_schema_version: false,
+            include_hash: false,
+            conversation_boundaries: None,
+            sort_by: SortKey::Chronological,
+            line_ending: LineEnding::Lf,
+            write_bom: false,
         };
         assert!(!empty.has_any());
     }
@@ -972,6 +5693,36 @@ mod error_tests {
         assert!(display.contains("Invalid date"));
         assert!(display.contains("invalid"));
     }
+
+    #[test]
+    fn test_exit_codes() {
+        use chatpack::error::{EXIT_NOT_FOUND, EXIT_PARSE, EXIT_USAGE, error_exit_code};
+
+        ensure_fixtures();
+        let dir = fixtures_dir();
+        let out = TempDir::new().unwrap();
+
+        let missing = convert(
+            format!("{dir}/nonexistent.json"),
+            out.path().join("out.csv"),
+            ConvertOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error_exit_code(&missing), EXIT_NOT_FOUND);
+
+        let bad_after = FilterConfig::new().after_datetime("June 1st").unwrap_err();
+        assert_eq!(error_exit_code(&bad_after), EXIT_USAGE);
+
+        let truncated = out.path().join("truncated.json");
+        fs::write(&truncated, r#"{"name": "Chat", "messages": [{"id": 1"#).unwrap();
+        let corrupt = convert(
+            &truncated,
+            out.path().join("out.csv"),
+            ConvertOptions::new().with_platform(Platform::Telegram),
+        )
+        .unwrap_err();
+        assert_eq!(error_exit_code(&corrupt), EXIT_PARSE);
+    }
 }
 
 // ============================================================================
@@ -1021,3 +5772,246 @@ mod serde_tests {
         assert_eq!(config.include_ids, deserialized.include_ids);
     }
 }
+
+// =========================================================================
+// Telegram album tests
+// =========================================================================
+
+mod telegram_album_tests {
+    use super::*;
+    use chatpack::config::TelegramConfig;
+    use chatpack::parsers::TelegramParser;
+
+    fn parse(config: TelegramConfig, streaming: bool) -> Vec<Message> {
+        ensure_fixtures();
+        let path = format!("{}/telegram_album.json", fixtures_dir());
+        let path = Path::new(&path);
+        let parser = TelegramParser::with_config(config);
+        if streaming {
+            parser.stream(path).unwrap().map(Result::unwrap).collect()
+        } else {
+            parser.parse(path).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_album_becomes_one_message() {
+        for streaming in [false, true] {
+            let messages = parse(TelegramConfig::new(), streaming);
+            let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+            assert_eq!(
+                contents,
+                [
+                    "Back from the trip",
+                    "[Album: 4 photos, 1 video]\nMountains!",
+                    "Wow",
+                    "Sunset",
+                    "Send more",
+                    "Beach",
+                ],
+                "streaming: {streaming}"
+            );
+            let album = &messages[1];
+            assert_eq!(album.id, Some(2));
+            assert_eq!(album.sender, "Alice");
+            assert_eq!(
+                album.timestamp,
+                chrono::DateTime::from_timestamp(1705314660, 0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_album_merging_can_be_disabled() {
+        for streaming in [false, true] {
+            let config = TelegramConfig::new().with_merge_albums(false);
+            let ids: Vec<u64> = parse(config, streaming)
+                .iter()
+                .map(|m| m.id.unwrap())
+                .collect();
+            // Uncaptioned album items carry no text of their own
+            assert_eq!(ids, [1, 4, 7, 8, 9, 10], "streaming: {streaming}");
+        }
+    }
+
+    #[test]
+    fn test_album_attachments_are_collected() {
+        let config = TelegramConfig::new().with_collect_attachment_refs(true);
+        let messages = parse(config, false);
+        assert_eq!(messages[1].attachments.len(), 5);
+        assert_eq!(messages[3].attachments.len(), 1);
+    }
+}
+
+// =============================================================================
+// Deleted message and id gap tests
+// =============================================================================
+
+mod deleted_message_tests {
+    use super::*;
+    use chatpack::core::find_id_gaps;
+
+    #[test]
+    fn test_find_id_gaps_in_telegram_export() {
+        ensure_fixtures();
+        let parser = TelegramParser::new();
+        let path = format!("{}/telegram_simple.json", fixtures_dir());
+        let mut messages = parser.parse(Path::new(&path)).unwrap();
+        assert!(find_id_gaps(&messages).is_empty());
+
+        messages.retain(|m| m.id != Some(2) && m.id != Some(3));
+        assert_eq!(find_id_gaps(&messages), vec![2..4]);
+    }
+
+    #[test]
+    fn test_drop_deleted_whatsapp_messages() {
+        let content = "[15.01.24, 10:30:45] Alice: Это сообщение удалено\n\
+                       [15.01.24, 10:31:00] Bob: Diese Nachricht wurde gelöscht.\n\
+                       [15.01.24, 10:32:00] Alice: \u{200E}You deleted this message\n\
+                       [15.01.24, 10:33:00] Bob: Still here";
+        let messages = WhatsAppParser::new().parse_str(content).unwrap();
+        assert_eq!(messages.iter().filter(|m| m.is_deleted()).count(), 3);
+
+        let filter = FilterConfig::new().with_drop_deleted(true);
+        let kept = chatpack::core::apply_filters(messages, &filter);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].content, "Still here");
+    }
+}
+
+// =============================================================================
+// Ordering tests
+// =============================================================================
+
+mod ordering_tests {
+    use super::*;
+    use chatpack::format::{OutputFormat, read_from_format};
+    use chatpack::pipeline::{PipelineConfig, run_pipelined};
+    use chatpack::report::ordering_warning;
+
+    /// Two channels' CSV exports of the same morning, one after the other.
+    fn concatenated_csv(dir: &TempDir) -> std::path::PathBuf {
+        let general = DiscordExportBuilder::new("Server", "general")
+            .message("Alice", "general 1")
+            .message("Bob", "general 2")
+            .message("Alice", "general 3")
+            .build_csv();
+        let random = DiscordExportBuilder::new("Server", "random")
+            .at(START + chrono::Duration::seconds(30))
+            .message("Alice", "random 1")
+            .message("Alice", "random 2")
+            .message("Bob", "random 3")
+            .build_csv();
+        let body = random.split_once('\n').unwrap().1;
+        let path = dir.path().join("channels.csv");
+        fs::write(&path, format!("{general}{body}")).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_convert_reports_unsorted_input() {
+        let dir = TempDir::new().unwrap();
+        let input = concatenated_csv(&dir);
+        let out = dir.path().join("out.jsonl");
+
+        let stats = convert(&input, &out, ConvertOptions::new()).unwrap();
+        assert_eq!(stats.ordering.inversions, 1);
+        assert_eq!(
+            stats.ordering.max_backward_jump,
+            chrono::Duration::seconds(90)
+        );
+        assert!(ordering_warning(&stats.ordering).is_some());
+        // Alice's "general 3" and "random 1" merge across the seam
+        assert_eq!(stats.merged_count, 4);
+
+        let pipelined = run_pipelined(
+            &PipelineConfig::new(Platform::Discord, &input, dir.path().join("p.jsonl"))
+                .with_format(OutputFormat::Jsonl)
+                .with_merge(true),
+        )
+        .unwrap();
+        assert_eq!(pipelined.ordering, stats.ordering);
+    }
+
+    #[test]
+    fn test_convert_with_sort_repairs_order() {
+        let dir = TempDir::new().unwrap();
+        let input = concatenated_csv(&dir);
+        let out = dir.path().join("out.jsonl");
+
+        let options = ConvertOptions::new().with_sort(true).with_merge(false);
+        let stats = convert(&input, &out, options).unwrap();
+        // The report describes the input, before sorting
+        assert!(stats.ordering.appears_unsorted());
+
+        let messages = read_from_format(&out, OutputFormat::Jsonl).unwrap();
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "general 1",
+                "random 1",
+                "general 2",
+                "random 2",
+                "general 3",
+                "random 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sorted_export_has_no_warning() {
+        ensure_fixtures();
+        let dir = TempDir::new().unwrap();
+        let input = format!("{}/discord.csv", fixtures_dir());
+        let stats = convert(&input, dir.path().join("out.csv"), ConvertOptions::new()).unwrap();
+        assert!(stats.ordering.is_sorted());
+        assert_eq!(ordering_warning(&stats.ordering), None);
+    }
+}
+
+// =============================================================================
+// Near-duplicate tests
+// =============================================================================
+
+mod near_dup_tests {
+    use super::*;
+    use chatpack::core::processor::{NearDupConfig, near_dedupe};
+
+    #[test]
+    fn test_near_dedupe_overlapping_exports() {
+        let telegram = TelegramExportBuilder::new("Friends")
+            .message("Alice", "Are we still on for Friday?")
+            .message("Bob", "Yes, 8pm at the usual place")
+            .message("Alice", "Perfect, I'll book a table")
+            .build();
+        // The WhatsApp continuation starts with the last two messages again,
+        // re-typed
+        let whatsapp = WhatsAppExportBuilder::new(DateFormat::EuDotBracketed)
+            .at(START + chrono::Duration::minutes(1))
+            .message("Bob", "yes 8pm at the usual place")
+            .message("Alice", "Perfect! I'll book a table.")
+            .message("Bob", "ok")
+            .at(START + chrono::Duration::hours(2))
+            .message("Bob", "ok")
+            .build();
+
+        let mut messages = TelegramParser::new().parse_str(&telegram).unwrap();
+        messages.extend(WhatsAppParser::new().parse_str(&whatsapp).unwrap());
+        assert_eq!(messages.len(), 7);
+
+        let removed = near_dedupe(&mut messages, &NearDupConfig::new());
+        assert_eq!(removed, 2);
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "Are we still on for Friday?",
+                "Yes, 8pm at the usual place",
+                "Perfect, I'll book a table",
+                "ok",
+                "ok",
+            ]
+        );
+    }
+}
@@ -1,11 +1,13 @@
 //! Tests for output writers (JSON, JSONL, CSV)
 
 use chatpack::core::output::{write_csv, write_json, write_jsonl};
-use chatpack::core::{Message, OutputConfig};
+use chatpack::core::{LineEnding, Message, OutputConfig, SortKey};
 use chrono::{TimeZone, Utc};
 use std::fs;
 use tempfile::tempdir;
 
+mod support;
+
 fn sample_messages() -> Vec<Message> {
     let ts1 = Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap();
     let ts2 = Utc.with_ymd_and_hms(2024, 1, 15, 10, 31, 0).unwrap();
@@ -80,7 +82,17 @@ mod json_writer_tests {
             include_timestamps: false,
             include_ids: false,
             include_replies: false,
+            include_index: false,
             include_edited: false,
+            include_metadata: false,
+            include_platform: false,
+            include_raw: false,
+            include_schema_version: false,
+            include_hash: false,
+            conversation_boundaries: None,
+            sort_by: SortKey::Chronological,
+            line_ending: LineEnding::Lf,
+            write_bom: false,
         };
 
         write_json(&messages, path_str, &config).unwrap();
@@ -288,6 +300,684 @@ mod csv_writer_tests {
 
         assert!(path.exists());
     }
+
+    #[test]
+    fn test_write_csv_defaults_to_lf_without_bom() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("output.csv");
+
+        write_csv(
+            &sample_messages(),
+            path.to_str().unwrap(),
+            &OutputConfig::new(),
+        )
+        .unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"Sender;Content\n"));
+        assert!(!bytes.contains(&b'\r'));
+    }
+
+    #[test]
+    fn test_write_csv_crlf_and_bom() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("output.csv");
+        let config = OutputConfig::new()
+            .with_timestamps()
+            .with_line_ending(LineEnding::CrLf)
+            .with_bom();
+
+        let mut messages = sample_messages();
+        messages.push(Message::new("Bob", "Line 1\nLine 2"));
+        write_csv(&messages, path.to_str().unwrap(), &config).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[..3], b"\xEF\xBB\xBF");
+        let content = String::from_utf8(bytes[3..].to_vec()).unwrap();
+        assert!(content.ends_with("\"Line 1\nLine 2\"\r\n"), "{content:?}");
+        let records: Vec<&str> = content.split_terminator("\r\n").collect();
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[0], "Timestamp;Sender;Content");
+        assert_eq!(records[1], "2024-01-15 10:30:00;Alice;Hello!");
+        // The line break inside the quoted cell is kept as written
+        assert_eq!(records[4], ";Bob;\"Line 1\nLine 2\"");
+    }
+}
+
+// ============================================================================
+// Sort Order Tests
+// ============================================================================
+
+mod sort_order_tests {
+    use super::*;
+
+    fn sorted_config(key: SortKey) -> OutputConfig {
+        OutputConfig::new().with_timestamps().with_sort_by(key)
+    }
+
+    #[test]
+    fn test_csv_reorders_rows_only() {
+        let dir = tempdir().unwrap();
+        let messages = sample_messages();
+        let before = messages.clone();
+
+        let read_rows = |key| {
+            let path = dir.path().join("out.csv");
+            write_csv(&messages, path.to_str().unwrap(), &sorted_config(key)).unwrap();
+            let content = fs::read_to_string(&path).unwrap();
+            content
+                .lines()
+                .skip(1)
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        let chronological = read_rows(SortKey::Chronological);
+        let grouped = read_rows(SortKey::SenderThenTime);
+
+        assert_ne!(grouped, chronological);
+        let (mut a, mut b) = (chronological.clone(), grouped.clone());
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+        assert!(grouped[0].contains("Hello!"));
+        assert!(grouped[1].contains("How are you?"));
+        assert!(grouped[2].contains("Hi Alice!"));
+        assert_eq!(messages, before);
+    }
+
+    #[test]
+    fn test_jsonl_line_count_matches_input() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("out.jsonl");
+        let messages = sample_messages();
+
+        write_jsonl(
+            &messages,
+            path.to_str().unwrap(),
+            &sorted_config(SortKey::SenderThenTimeDescending),
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), messages.len());
+        let order: Vec<&str> = lines
+            .iter()
+            .map(|l| l["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(order, ["How are you?", "Hello!", "Hi Alice!"]);
+    }
+
+    #[test]
+    fn test_json_sorted() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("out.json");
+
+        write_json(
+            &sample_messages(),
+            path.to_str().unwrap(),
+            &sorted_config(SortKey::SenderThenTime),
+        )
+        .unwrap();
+
+        let parsed: Vec<serde_json::Value> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let senders: Vec<&str> = parsed
+            .iter()
+            .map(|m| m["sender"].as_str().unwrap())
+            .collect();
+        assert_eq!(senders, ["Alice", "Alice", "Bob"]);
+    }
+}
+
+// ============================================================================
+// Existing Output Tests
+// ============================================================================
+
+mod existing_output_tests {
+    use super::*;
+    use chatpack::core::output::{JsonlWriteOptions, OnExists, WriteOptions, write_jsonl_with};
+    use chatpack::format::{OutputFormat, write_to_format_with};
+
+    const ORIGINAL: &[u8] = b"Sender;Content\nAlice;hand-filtered\r\n";
+
+    fn options(on_exists: OnExists) -> WriteOptions {
+        WriteOptions::new().with_on_exists(on_exists)
+    }
+
+    #[test]
+    fn test_error_leaves_file_untouched() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.csv");
+        fs::write(&path, ORIGINAL).unwrap();
+        let config = OutputConfig::new();
+
+        for format in [OutputFormat::Csv, OutputFormat::Json, OutputFormat::Jsonl] {
+            let err = write_to_format_with(
+                &sample_messages(),
+                path.to_str().unwrap(),
+                format,
+                &config,
+                &options(OnExists::Error),
+            )
+            .unwrap_err();
+
+            assert!(err.is_output_exists(), "{format}");
+            assert!(err.to_string().contains("chat.csv already exists"));
+            assert_eq!(fs::read(&path).unwrap(), ORIGINAL, "{format}");
+        }
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_error_writes_new_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.csv");
+        let written = write_to_format_with(
+            &sample_messages(),
+            path.to_str().unwrap(),
+            OutputFormat::Csv,
+            &OutputConfig::new(),
+            &options(OnExists::Error),
+        )
+        .unwrap();
+
+        assert_eq!(written, path);
+        assert!(fs::read_to_string(&path).unwrap().contains("Hi Alice!"));
+    }
+
+    #[test]
+    fn test_overwrite_replaces_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.csv");
+        fs::write(&path, ORIGINAL).unwrap();
+
+        let written = write_to_format_with(
+            &sample_messages(),
+            path.to_str().unwrap(),
+            OutputFormat::Csv,
+            &OutputConfig::new(),
+            &WriteOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(written, path);
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("Hi Alice!"));
+        assert!(!content.contains("hand-filtered"));
+    }
+
+    #[test]
+    fn test_rename_writes_next_to_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        fs::write(&path, ORIGINAL).unwrap();
+        let config = OutputConfig::new();
+
+        let first = write_to_format_with(
+            &sample_messages(),
+            path.to_str().unwrap(),
+            OutputFormat::Jsonl,
+            &config,
+            &options(OnExists::Rename),
+        )
+        .unwrap();
+        let jsonl = JsonlWriteOptions::new().with_on_exists(OnExists::Rename);
+        let second =
+            write_jsonl_with(&sample_messages(), path.to_str().unwrap(), &config, &jsonl).unwrap();
+
+        assert_eq!(first, dir.path().join("chat-1.jsonl"));
+        assert_eq!(second, dir.path().join("chat-2.jsonl"));
+        assert_eq!(fs::read(&path).unwrap(), ORIGINAL);
+        assert_eq!(fs::read_to_string(&first).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_append_ignores_policy() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        let path = path.to_str().unwrap();
+        let config = OutputConfig::new();
+        let append = JsonlWriteOptions::new()
+            .with_append(true)
+            .with_on_exists(OnExists::Error);
+
+        write_jsonl_with(&sample_messages(), path, &config, &append).unwrap();
+        write_jsonl_with(&sample_messages(), path, &config, &append).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap().lines().count(), 6);
+    }
+}
+
+// ============================================================================
+// JSONL Index Tests
+// ============================================================================
+
+mod jsonl_index_tests {
+    use super::*;
+    use chatpack::core::output::{JsonlIndex, JsonlWriteOptions, index_path, write_jsonl_with};
+    use chrono::Duration;
+    use std::io::{BufRead, BufReader, Seek, SeekFrom};
+    use std::time::SystemTime;
+
+    /// One message a minute, numbered in the content.
+    fn minutes(count: usize) -> Vec<Message> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 0, 0, 0).unwrap();
+        (0..count)
+            .map(|i| {
+                Message::new("Alice", format!("message {i} {}", "x".repeat(i % 7)))
+                    .with_timestamp(start + Duration::minutes(i64::try_from(i).unwrap()))
+            })
+            .collect()
+    }
+
+    fn first_line_at(path: &std::path::Path, offset: u64) -> String {
+        let mut file = fs::File::open(path).unwrap();
+        file.seek(SeekFrom::Start(offset)).unwrap();
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line).unwrap();
+        line
+    }
+
+    #[test]
+    fn test_find_offset_seeks_to_indexed_record() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        let options = JsonlWriteOptions::new().with_index_every(10);
+        write_jsonl_with(
+            &minutes(1000),
+            path.to_str().unwrap(),
+            &OutputConfig::new().with_timestamps(),
+            &options,
+        )
+        .unwrap();
+
+        let index = JsonlIndex::open(&path).unwrap();
+        assert!(index_path(&path).exists());
+        assert_eq!(index.records(), 1000);
+        assert_eq!(index.entries().len(), 100);
+        assert!(!index.is_stale().unwrap());
+
+        // Message 505 is after indexed record 500, so reading starts there
+        let after = Utc.with_ymd_and_hms(2024, 1, 15, 8, 25, 0).unwrap();
+        let line = first_line_at(&path, index.find_offset(after));
+        assert!(line.contains("\"message 500 "), "{line}");
+
+        // Message 500 itself may be preceded by others of its second
+        let after = Utc.with_ymd_and_hms(2024, 1, 15, 8, 20, 0).unwrap();
+        let line = first_line_at(&path, index.find_offset(after));
+        assert!(line.contains("\"message 490 "), "{line}");
+
+        let before_all = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(index.find_offset(before_all), 0);
+    }
+
+    #[test]
+    fn test_build_matches_written_index() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        let config = OutputConfig::new().with_timestamps();
+        let options = JsonlWriteOptions::new().with_index_every(7);
+        write_jsonl_with(&minutes(100), path.to_str().unwrap(), &config, &options).unwrap();
+
+        let written = JsonlIndex::open(&path).unwrap();
+        assert_eq!(JsonlIndex::build(&path, 7).unwrap(), written);
+    }
+
+    #[test]
+    fn test_index_without_timestamps_matches_build() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        let options = JsonlWriteOptions::new().with_index_every(10);
+        write_jsonl_with(
+            &minutes(100),
+            path.to_str().unwrap(),
+            &OutputConfig::new(),
+            &options,
+        )
+        .unwrap();
+
+        let written = JsonlIndex::open(&path).unwrap();
+        assert_eq!(JsonlIndex::build(&path, 10).unwrap(), written);
+        assert!(written.entries().iter().all(|e| e.timestamp.is_none()));
+        let after = Utc.with_ymd_and_hms(2024, 1, 15, 1, 0, 0).unwrap();
+        assert_eq!(written.find_offset(after), 0);
+    }
+
+    #[test]
+    fn test_append_reindexes_whole_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        let config = OutputConfig::new().with_timestamps();
+        let options = JsonlWriteOptions::new()
+            .with_append(true)
+            .with_index_every(5);
+        let messages = minutes(40);
+        write_jsonl_with(&messages[..20], path.to_str().unwrap(), &config, &options).unwrap();
+        write_jsonl_with(&messages[20..], path.to_str().unwrap(), &config, &options).unwrap();
+
+        let index = JsonlIndex::open(&path).unwrap();
+        assert_eq!(index.records(), 40);
+        assert!(!index.is_stale().unwrap());
+        let after = Utc.with_ymd_and_hms(2024, 1, 15, 0, 33, 0).unwrap();
+        let line = first_line_at(&path, index.find_offset(after));
+        assert!(line.contains("\"message 30 "), "{line}");
+    }
+
+    #[test]
+    fn test_stale_index_is_rebuilt() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        let config = OutputConfig::new().with_timestamps();
+        let options = JsonlWriteOptions::new().with_index_every(10);
+        write_jsonl_with(&minutes(30), path.to_str().unwrap(), &config, &options).unwrap();
+
+        // Rewritten at the same length, but after the index
+        let index = JsonlIndex::open(&path).unwrap();
+        let hour_ago = SystemTime::now() - std::time::Duration::from_hours(1);
+        fs::File::options()
+            .write(true)
+            .open(index_path(&path))
+            .unwrap()
+            .set_modified(hour_ago)
+            .unwrap();
+        assert!(index.is_stale().unwrap());
+
+        // Grown since indexing
+        write_jsonl_with(
+            &minutes(50),
+            path.to_str().unwrap(),
+            &config,
+            &JsonlWriteOptions::new(),
+        )
+        .unwrap();
+        assert!(index.is_stale().unwrap());
+
+        let rebuilt = JsonlIndex::open_or_build(&path, 10).unwrap();
+        assert_eq!(rebuilt.records(), 50);
+        assert!(!rebuilt.is_stale().unwrap());
+        assert_eq!(JsonlIndex::open(&path).unwrap(), rebuilt);
+    }
+
+    #[test]
+    fn test_open_without_index_fails() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+        write_jsonl(&minutes(3), path.to_str().unwrap(), &OutputConfig::new()).unwrap();
+
+        assert!(JsonlIndex::open(&path).unwrap_err().is_io());
+        assert!(!index_path(&path).exists());
+    }
+}
+
+// ============================================================================
+// Schema Stability Tests
+// ============================================================================
+
+mod schema_tests {
+    use super::*;
+    use chatpack::AttachmentRef;
+    use chatpack::core::SCHEMA_VERSION;
+    use chatpack::core::models::BoundaryStyle;
+    use chatpack::core::output::{RECORD_FIELDS, to_csv, to_json, to_jsonl};
+    use chatpack::parser::Platform;
+    use serde_json::Value;
+
+    const KEYS: &str = include_str!("schema/keys.txt");
+
+    /// Keys recorded in `tests/schema/keys.txt` for `kind` at `version`.
+    fn recorded(version: u32, kind: &str) -> Vec<&'static str> {
+        let header = format!("[{version} {kind}]");
+        KEYS.lines()
+            .skip_while(|line| *line != header)
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .collect()
+    }
+
+    /// Keys of a JSON object, sorted.
+    fn keys(value: &Value) -> Vec<String> {
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    /// Fails unless `actual` holds exactly the recorded keys.
+    fn assert_stable(kind: &str, actual: &[String]) {
+        let mut recorded = recorded(SCHEMA_VERSION, kind);
+        recorded.sort_unstable();
+        assert!(
+            !recorded.is_empty(),
+            "tests/schema/keys.txt has no [{SCHEMA_VERSION} {kind}] section; \
+             record the keys of the new schema version"
+        );
+        let missing: Vec<_> = recorded
+            .iter()
+            .filter(|key| !actual.iter().any(|k| k == *key))
+            .collect();
+        assert!(
+            missing.is_empty(),
+            "{kind} keys {missing:?} were removed or renamed; \
+             bump SCHEMA_VERSION and record the new keys"
+        );
+        assert_eq!(
+            actual, recorded,
+            "{kind} keys added; record them in \
+             [{SCHEMA_VERSION} {kind}] in tests/schema/keys.txt"
+        );
+    }
+
+    fn full_message() -> Message {
+        let ts = Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap();
+        Message::new("Alice", "Hello!")
+            .with_timestamp(ts)
+            .with_id(1)
+            .with_reply_to(0)
+            .with_edited(ts)
+            .with_original_sender("Alice Smith")
+            .with_forwarded_from("Rust News")
+            .with_topic("General")
+            .with_source("team")
+            .with_platform(Platform::Telegram)
+            .with_raw("{}")
+            .with_source_file(0)
+            .with_attachment(AttachmentRef::from_path("photos/a.jpg"))
+    }
+
+    fn full_config() -> OutputConfig {
+        OutputConfig::all()
+            .with_metadata()
+            .with_platform()
+            .with_raw()
+            .with_schema_version()
+            .with_hash()
+            .with_conversation_boundaries(BoundaryStyle::MetaField)
+    }
+
+    #[test]
+    fn test_record_keys_are_stable() {
+        let messages = [full_message()];
+
+        let jsonl = to_jsonl(&messages, &full_config()).unwrap();
+        let line: Value = serde_json::from_str(jsonl.trim()).unwrap();
+        assert_eq!(line["_v"], SCHEMA_VERSION);
+        assert_stable("record", &keys(&line));
+
+        let json: Value =
+            serde_json::from_str(&to_json(&messages, &full_config()).unwrap()).unwrap();
+        assert_stable("record", &keys(&json[0]));
+    }
+
+    /// Record keys in the order they appear in `text`.
+    fn ordered_keys(text: &str) -> Vec<&'static str> {
+        let mut keys: Vec<_> = RECORD_FIELDS
+            .iter()
+            .filter_map(|key| text.find(&format!("\"{key}\":")).map(|at| (at, *key)))
+            .collect();
+        keys.sort_unstable();
+        keys.into_iter().map(|(_, key)| key).collect()
+    }
+
+    /// `ReplyTo` -> `reply_to`
+    fn snake_case(column: &str) -> String {
+        if column == "ID" {
+            return "id".to_string();
+        }
+        column.chars().fold(String::new(), |mut out, c| {
+            if c.is_uppercase() && !out.is_empty() {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+            out
+        })
+    }
+
+    #[test]
+    fn test_writers_agree_on_key_order() {
+        let messages = [full_message(), Message::new("Bob", "Hi")];
+        let config = full_config();
+
+        let jsonl = to_jsonl(&messages, &config).unwrap();
+        let json = to_json(&messages, &config).unwrap();
+        assert!(!jsonl.contains("null") && !json.contains("null"));
+
+        let line = jsonl.lines().next().unwrap();
+        let first = to_json(&messages[..1], &config).unwrap();
+        assert_eq!(ordered_keys(line), ordered_keys(&first));
+        // Same count as the parsed keys, so ordered_keys missed none
+        assert_eq!(
+            ordered_keys(line).len(),
+            keys(&serde_json::from_str(line).unwrap()).len()
+        );
+
+        // The CSV columns are the record keys it shares, in the same order
+        let csv = to_csv(&messages, &config).unwrap();
+        let columns: Vec<String> = csv
+            .lines()
+            .next()
+            .unwrap()
+            .split(';')
+            .map(snake_case)
+            .collect();
+        let shared: Vec<_> = ordered_keys(line)
+            .into_iter()
+            .filter(|key| columns.iter().any(|c| c == key))
+            .collect();
+        assert_eq!(columns, shared);
+
+        // A message without optional values drops keys instead of writing null
+        assert_eq!(
+            jsonl.lines().nth(1).unwrap(),
+            format!(
+                r#"{{"_v":{SCHEMA_VERSION},"sender":"Bob","content":"Hi","hash":"{}"}}"#,
+                messages[1].content_hash_hex()
+            )
+        );
+    }
+
+    #[test]
+    fn test_message_keys_are_stable() {
+        let value = serde_json::to_value(full_message()).unwrap();
+        assert_stable("message", &keys(&value));
+        assert_stable("attachment", &keys(&value["attachments"][0]));
+    }
+
+    #[test]
+    fn test_earlier_versions_recorded() {
+        for version in 1..=SCHEMA_VERSION {
+            for kind in ["record", "message", "attachment"] {
+                assert!(
+                    !recorded(version, kind).is_empty(),
+                    "missing [{version} {kind}]"
+                );
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Content Hash Tests
+// ============================================================================
+
+mod content_hash_tests {
+    use super::*;
+    use chatpack::core::output::{to_csv, to_jsonl};
+    use std::collections::HashSet;
+
+    fn pinned() -> [(Message, u64, &'static str); 3] {
+        let ts = Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap();
+        [
+            (
+                Message::new("Alice", "Hello!").with_timestamp(ts),
+                0xdd79_54b7_6862_ef53,
+                "a5f5c7d5e50f7afddf3494ff4ddef756",
+            ),
+            (
+                Message::new("", ""),
+                0x872a_3972_3b91_ed07,
+                "03442da5f38e6e304f9f10a04d1abdbe",
+            ),
+            (
+                Message::new("李雷", "你好世界 👍🏽"),
+                0xe777_37fa_d40c_d1b2,
+                "066c0371af45ed684e004f6ca24f73e8",
+            ),
+        ]
+    }
+
+    /// Hashes are part of the output format: if this fails, the algorithm
+    /// or the canonical encoding changed.
+    #[test]
+    fn test_hashes_are_pinned() {
+        for (msg, hash, hex) in pinned() {
+            assert_eq!(msg.content_hash(), hash, "{msg:?}");
+            assert_eq!(msg.content_hash_hex(), hex, "{msg:?}");
+        }
+    }
+
+    #[test]
+    fn test_hash_ignores_ids_replies_and_edits() {
+        for (msg, hash, _) in pinned() {
+            let ts = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+            let decorated = msg.with_id(7).with_reply_to(3).with_edited(ts);
+            assert_eq!(decorated.content_hash(), hash);
+        }
+    }
+
+    #[test]
+    fn test_no_collisions_in_fixture_corpus() {
+        let messages = support::canonical_fixture();
+        let distinct: HashSet<_> = messages
+            .iter()
+            .map(|m| (&m.sender, m.timestamp, &m.content))
+            .collect();
+        let hashes: HashSet<_> = messages.iter().map(Message::content_hash).collect();
+        let hex: HashSet<_> = messages.iter().map(Message::content_hash_hex).collect();
+        assert_eq!(hashes.len(), distinct.len());
+        assert_eq!(hex.len(), distinct.len());
+    }
+
+    #[test]
+    fn test_writers_include_hash() {
+        let messages = vec![pinned()[0].0.clone()];
+        let config = OutputConfig::new().with_hash();
+
+        let jsonl = to_jsonl(&messages, &config).unwrap();
+        assert_eq!(
+            jsonl,
+            "{\"sender\":\"Alice\",\"content\":\"Hello!\",\"hash\":\"a5f5c7d5e50f7afddf3494ff4ddef756\"}\n"
+        );
+        let csv = to_csv(&messages, &config).unwrap();
+        assert_eq!(
+            csv,
+            "Sender;Content;Hash\nAlice;Hello!;a5f5c7d5e50f7afddf3494ff4ddef756\n"
+        );
+        assert!(
+            !to_jsonl(&messages, &OutputConfig::all())
+                .unwrap()
+                .contains("hash")
+        );
+    }
 }
 
 // ============================================================================
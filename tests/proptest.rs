@@ -6,8 +6,18 @@
 use proptest::prelude::*;
 use serde_json::{Value, json};
 
-use chatpack::core::output::{to_csv, to_json, to_jsonl};
-use chatpack::core::{FilterConfig, Message, OutputConfig, apply_filters, merge_consecutive};
+use chatpack::core::output::{PreviewOptions, render_preview, to_csv, to_json, to_jsonl};
+use chatpack::core::textutil::{len_graphemes, truncate_graphemes};
+use chatpack::core::{
+    FilterConfig, LineEnding, Message, OutputConfig, SortKey, apply_filters, merge_consecutive,
+    suggest_title,
+};
+use chatpack::fixtures::{
+    DiscordExportBuilder, InstagramExportBuilder, START, TelegramExportBuilder,
+    WhatsAppExportBuilder,
+};
+use chatpack::parser::Parser;
+use chatpack::parsers::{DiscordParser, InstagramParser, TelegramParser, WhatsAppParser};
 use chatpack::parsing::discord::{
     DiscordAttachment, DiscordAuthor, DiscordRawMessage, DiscordReference, DiscordSticker,
     parse_discord_message,
@@ -67,6 +77,15 @@ fn arb_message() -> impl Strategy<Value = Message> {
             id: None,
             reply_to: None,
             edited: None,
+            original_sender: None,
+            forwarded_from: None,
+            topic: None,
+            source: None,
+            source_index: None,
+            source_file: None,
+            platform: None,
+            raw: None,
+            attachments: Vec::new(),
         })
 }
 
@@ -252,12 +271,15 @@ fn arb_discord_raw_message() -> impl Strategy<Value = DiscordRawMessage> {
             |(id, ts, ts_edited, content, name, nickname, ref_id, has_attach, has_sticker)| {
                 DiscordRawMessage {
                     id: id.to_string(),
+                    msg_type: None,
                     timestamp: ts.to_string(),
                     timestamp_edited: ts_edited.map(|s| s.to_string()),
                     content,
                     author: DiscordAuthor {
                         name: name.to_string(),
                         nickname: nickname.map(|s| s.to_string()),
+                        discriminator: None,
+                        id: None,
                     },
                     reference: ref_id.map(|id| DiscordReference {
                         message_id: Some(id.to_string()),
@@ -265,6 +287,7 @@ fn arb_discord_raw_message() -> impl Strategy<Value = DiscordRawMessage> {
                     attachments: if has_attach {
                         Some(vec![DiscordAttachment {
                             file_name: "test.png".to_string(),
+                            url: None,
                         }])
                     } else {
                         None
@@ -310,6 +333,7 @@ fn arb_instagram_raw_message() -> impl Strategy<Value = InstagramRawMessage> {
             photos: None,
             videos: None,
             audio_files: None,
+            call_duration: None,
         })
 }
 
@@ -376,6 +400,15 @@ proptest! {
                 id: None,
                 reply_to: None,
                 edited: None,
+                original_sender: None,
+                forwarded_from: None,
+                topic: None,
+                source: None,
+                source_index: None,
+                source_file: None,
+                platform: None,
+                raw: None,
+                attachments: Vec::new(),
             })
             .collect();
         let merged = merge_consecutive(messages);
@@ -393,6 +426,15 @@ proptest! {
                 id: None,
                 reply_to: None,
                 edited: None,
+                original_sender: None,
+                forwarded_from: None,
+                topic: None,
+                source: None,
+                source_index: None,
+                source_file: None,
+                platform: None,
+                raw: None,
+                attachments: Vec::new(),
             })
             .collect();
         let merged = merge_consecutive(messages);
@@ -426,6 +468,15 @@ proptest! {
                 id: None,
                 reply_to: None,
                 edited: None,
+                original_sender: None,
+                forwarded_from: None,
+                topic: None,
+                source: None,
+                source_index: None,
+                source_file: None,
+                platform: None,
+                raw: None,
+                attachments: Vec::new(),
             })
             .collect();
         let merged = merge_consecutive(messages.clone());
@@ -492,6 +543,33 @@ proptest! {
         prop_assert!(filtered.is_empty());
     }
 
+    /// filter_iter yields exactly what apply_filters returns
+    #[test]
+    fn filter_iter_matches_apply_filters(
+        messages in arb_messages_with_ts(30),
+        sender in prop::option::of(prop::sample::select(vec!["Alice", "bob", "Nobody"])),
+        after in prop::option::of(1700000000i64..1800000000i64),
+        before in prop::option::of(1700000000i64..1800000000i64),
+    ) {
+        let mut config = FilterConfig::new();
+        if let Some(sender) = sender {
+            config = config.with_sender(sender);
+        }
+        if let Some(ts) = after.and_then(|ts| chrono::DateTime::from_timestamp(ts, 0)) {
+            config = config.with_after(ts);
+        }
+        if let Some(ts) = before.and_then(|ts| chrono::DateTime::from_timestamp(ts, 0)) {
+            config = config.with_before(ts);
+        }
+
+        let lazy: Vec<Message> = config
+            .filter_iter(messages.clone().into_iter().map(Ok))
+            .map(Result::unwrap)
+            .collect();
+        let eager = apply_filters(messages, &config);
+        prop_assert_eq!(lazy, eager);
+    }
+
     /// Date filter excludes messages without timestamps
     #[test]
     fn date_filter_excludes_no_timestamp(messages in arb_messages(20)) {
@@ -535,6 +613,15 @@ proptest! {
             id: None,
             reply_to: None,
             edited: None,
+            original_sender: None,
+            forwarded_from: None,
+            topic: None,
+            source: None,
+            source_index: None,
+            source_file: None,
+            platform: None,
+            raw: None,
+            attachments: Vec::new(),
         };
         let _ = merge_consecutive(vec![msg.clone(), msg]);
     }
@@ -559,6 +646,15 @@ proptest! {
             id: None,
             reply_to: None,
             edited: None,
+            original_sender: None,
+            forwarded_from: None,
+            topic: None,
+            source: None,
+            source_index: None,
+            source_file: None,
+            platform: None,
+            raw: None,
+            attachments: Vec::new(),
         };
         let merged = merge_consecutive(vec![msg]);
         prop_assert_eq!(&merged[0].content, &content);
@@ -597,6 +693,15 @@ proptest! {
             id: Some(id),
             reply_to: reply,
             edited: None,
+            original_sender: None,
+            forwarded_from: None,
+            topic: None,
+            source: None,
+            source_index: None,
+            source_file: None,
+            platform: None,
+            raw: None,
+            attachments: Vec::new(),
         };
 
         let json = serde_json::to_string(&msg).expect("serialize");
@@ -678,6 +783,7 @@ proptest! {
             text: Some(text_value),
             reply_to_message_id: None,
             edited_unixtime: None,
+            ..Default::default()
         };
         let _ = parse_telegram_message(&msg);
     }
@@ -695,6 +801,7 @@ proptest! {
             text: Some(json!("Hello")),
             reply_to_message_id: None,
             edited_unixtime: None,
+            ..Default::default()
         };
         let result = parse_telegram_message(&msg);
         prop_assert!(result.is_none());
@@ -793,6 +900,150 @@ proptest! {
         let result = parse_whatsapp_timestamp(&date, &time, DateFormat::US);
         prop_assert!(result.is_some());
     }
+
+    /// Parse, filter and merge give the same output on every run, even when
+    /// many messages share a minute
+    #[test]
+    fn whatsapp_pipeline_is_deterministic(
+        lines in prop::collection::vec(
+            (prop::sample::select(vec!["Alice", "Bob", "Carol"]), 0u32..3, "[a-z ]{1,12}"),
+            1..40,
+        )
+    ) {
+        let content = lines
+            .iter()
+            .fold(WhatsAppExportBuilder::new(DateFormat::US), |builder, (sender, minute, text)| {
+                builder
+                    .at(START + chrono::Duration::minutes(i64::from(*minute)))
+                    .message(sender, text)
+            })
+            .build();
+        let config = FilterConfig::new().with_sender("Alice");
+
+        let run = || {
+            let mut messages = WhatsAppParser::new().parse_str(&content).unwrap();
+            messages.sort_by(Message::cmp_chronological);
+            let indices: Vec<_> = messages.iter().map(Message::source_index).collect();
+            let merged = merge_consecutive(apply_filters(messages.clone(), &config));
+            (messages, indices, merged)
+        };
+
+        let (first, first_indices, first_merged) = run();
+        let (second, second_indices, second_merged) = run();
+
+        prop_assert_eq!(&first, &second);
+        prop_assert_eq!(&first_indices, &second_indices);
+        prop_assert_eq!(first_merged, second_merged);
+
+        // Sorting is stable: the same-minute messages keep their file order
+        for pair in first.windows(2) {
+            if pair[0].timestamp == pair[1].timestamp {
+                prop_assert!(pair[0].source_index() < pair[1].source_index());
+            }
+        }
+    }
+}
+
+// =============================================================================
+// FIXTURE BUILDER PROPERTIES
+// =============================================================================
+
+/// Generate (sender, content) pairs that no parser reads as a system line
+fn arb_conversation() -> impl Strategy<Value = Vec<(String, String)>> {
+    prop::collection::vec(
+        (
+            prop::sample::select(vec!["Alice", "Bob", "Борис", "李雷"]),
+            prop::sample::select(vec![
+                "Hello",
+                "Привет, мир",
+                "你好世界",
+                "emoji 🎉👍🏽",
+                "She said \"hi\", then",
+                "semi;colon",
+            ]),
+            0u32..1000,
+        )
+            .prop_map(|(sender, text, n)| (sender.to_string(), format!("{text} {n}"))),
+        1..20,
+    )
+}
+
+/// Returns the sender and content of each message.
+fn pairs(messages: &[Message]) -> Vec<(String, String)> {
+    messages
+        .iter()
+        .map(|m| (m.sender.clone(), m.content.clone()))
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Telegram exports from the builder parse back to what was added
+    #[test]
+    fn telegram_builder_round_trips(conversation in arb_conversation()) {
+        let export = conversation
+            .iter()
+            .fold(TelegramExportBuilder::new("Chat"), |b, (sender, text)| b.message(sender, text))
+            .build();
+
+        let messages = TelegramParser::new().parse_str(&export).unwrap();
+        prop_assert_eq!(pairs(&messages), conversation);
+    }
+
+    /// WhatsApp exports from the builder parse back in every date format
+    #[test]
+    fn whatsapp_builder_round_trips(
+        conversation in arb_conversation(),
+        format in prop::sample::select(vec![
+            DateFormat::US,
+            DateFormat::EuDotBracketed,
+            DateFormat::EuDotNoBracket,
+            DateFormat::EuSlash,
+            DateFormat::EuSlashBracketed,
+        ]),
+    ) {
+        let export = conversation
+            .iter()
+            .fold(WhatsAppExportBuilder::new(format), |b, (sender, text)| b.message(sender, text))
+            .build();
+
+        let messages = WhatsAppParser::new().parse_str(&export).unwrap();
+        prop_assert_eq!(pairs(&messages), conversation);
+    }
+
+    /// Instagram exports with mojibake parse back once the parser fixes it
+    #[test]
+    fn instagram_builder_round_trips(conversation in arb_conversation()) {
+        let export = conversation
+            .iter()
+            .fold(InstagramExportBuilder::new("Chat"), |b, (sender, text)| b.message(sender, text))
+            .with_mojibake(true)
+            .build();
+
+        let messages = InstagramParser::new().parse_str(&export).unwrap();
+        prop_assert_eq!(pairs(&messages), conversation);
+    }
+
+    /// Discord exports from the builder parse back in every format
+    #[test]
+    fn discord_builder_round_trips(conversation in arb_conversation()) {
+        let builder = conversation
+            .iter()
+            .fold(DiscordExportBuilder::new("Server", "general"), |b, (sender, text)| {
+                b.message(sender, text)
+            });
+
+        for export in [
+            builder.build_json(),
+            builder.build_jsonl(),
+            builder.build_txt(),
+            builder.build_csv(),
+        ] {
+            let messages = DiscordParser::new().parse_str(&export).unwrap();
+            prop_assert_eq!(pairs(&messages), conversation.clone());
+        }
+    }
 }
 
 // =============================================================================
@@ -855,6 +1106,7 @@ proptest! {
             photos: None,
             videos: None,
             audio_files: None,
+            call_duration: None,
         };
         let result = parse_instagram_message(&msg, false);
         prop_assert!(result.is_none());
@@ -874,6 +1126,7 @@ proptest! {
             photos: None,
             videos: None,
             audio_files: None,
+            call_duration: None,
         };
         let result = parse_instagram_message(&msg, false);
         prop_assert!(result.is_some());
@@ -902,12 +1155,15 @@ proptest! {
     ) {
         let msg = DiscordRawMessage {
             id: id.to_string(),
+            msg_type: None,
             timestamp: "2024-01-15T10:30:00+00:00".to_string(),
             timestamp_edited: None,
             content: String::new(),
             author: DiscordAuthor {
                 name: name.to_string(),
                 nickname: None,
+                discriminator: None,
+                id: None,
             },
             reference: None,
             attachments: None,
@@ -925,12 +1181,15 @@ proptest! {
     ) {
         let msg = DiscordRawMessage {
             id: "123".to_string(),
+            msg_type: None,
             timestamp: "2024-01-15T10:30:00+00:00".to_string(),
             timestamp_edited: None,
             content: "Test".to_string(),
             author: DiscordAuthor {
                 name: name.to_string(),
                 nickname: Some(nickname.to_string()),
+                discriminator: None,
+                id: None,
             },
             reference: None,
             attachments: None,
@@ -946,16 +1205,20 @@ proptest! {
     fn discord_attachments_appended(filename in prop::sample::select(vec!["test.png", "file.pdf"])) {
         let msg = DiscordRawMessage {
             id: "123".to_string(),
+            msg_type: None,
             timestamp: "2024-01-15T10:30:00+00:00".to_string(),
             timestamp_edited: None,
             content: "Check this".to_string(),
             author: DiscordAuthor {
                 name: "alice".to_string(),
                 nickname: None,
+                discriminator: None,
+                id: None,
             },
             reference: None,
             attachments: Some(vec![DiscordAttachment {
                 file_name: filename.to_string(),
+                url: None,
             }]),
             stickers: None,
         };
@@ -971,12 +1234,15 @@ proptest! {
     fn discord_stickers_appended(sticker_name in prop::sample::select(vec!["Cool", "Nice"])) {
         let msg = DiscordRawMessage {
             id: "123".to_string(),
+            msg_type: None,
             timestamp: "2024-01-15T10:30:00+00:00".to_string(),
             timestamp_edited: None,
             content: "Look".to_string(),
             author: DiscordAuthor {
                 name: "bob".to_string(),
                 nickname: None,
+                discriminator: None,
+                id: None,
             },
             reference: None,
             attachments: None,
@@ -996,16 +1262,20 @@ proptest! {
     fn discord_attachment_only_kept(filename in prop::sample::select(vec!["a.jpg", "b.mp4"])) {
         let msg = DiscordRawMessage {
             id: "123".to_string(),
+            msg_type: None,
             timestamp: "2024-01-15T10:30:00+00:00".to_string(),
             timestamp_edited: None,
             content: String::new(),
             author: DiscordAuthor {
                 name: "user".to_string(),
                 nickname: None,
+                discriminator: None,
+                id: None,
             },
             reference: None,
             attachments: Some(vec![DiscordAttachment {
                 file_name: filename.to_string(),
+                url: None,
             }]),
             stickers: None,
         };
@@ -1092,6 +1362,112 @@ proptest! {
     }
 }
 
+// =============================================================================
+// TEXT TRUNCATION PROPERTIES
+// =============================================================================
+
+/// Text mixing ZWJ sequences, flags, skin tones, combining marks, astral
+/// characters, and CRLF with arbitrary characters.
+fn arb_cluster_text() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        prop::sample::select(vec![
+            "👨\u{200D}👩\u{200D}👧\u{200D}👦",
+            "🏳\u{FE0F}\u{200D}🌈",
+            "🇰🇿",
+            "🇺🇸",
+            "👍🏽",
+            "e\u{301}",
+            "a\u{308}\u{332}",
+            "\r\n",
+            "𝔸",
+            "ж",
+            "a",
+            " ",
+        ])
+        .prop_map(str::to_string),
+        any::<char>().prop_map(String::from),
+    ];
+    prop::collection::vec(piece, 0..24).prop_map(|pieces| pieces.concat())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(500))]
+
+    /// Truncation cuts on a cluster boundary: the kept prefix and the rest
+    /// account for every cluster, and nothing past the cut is kept.
+    #[test]
+    fn prop_truncate_never_splits_clusters(text in arb_cluster_text(), n in 0usize..30) {
+        let total = len_graphemes(&text);
+        let kept = truncate_graphemes(&text, n, "");
+
+        prop_assert!(text.starts_with(kept.as_ref()));
+        prop_assert_eq!(len_graphemes(&kept), n.min(total));
+        prop_assert_eq!(len_graphemes(&kept) + len_graphemes(&text[kept.len()..]), total);
+    }
+
+    /// Truncated text is valid UTF-8 that survives a JSON round trip.
+    #[test]
+    fn prop_truncate_output_roundtrips(text in arb_cluster_text(), n in 0usize..30) {
+        let truncated = truncate_graphemes(&text, n, "…");
+
+        prop_assert!(String::from_utf8(truncated.as_bytes().to_vec()).is_ok());
+        let json = serde_json::to_string(truncated.as_ref()).unwrap();
+        let back: String = serde_json::from_str(&json).unwrap();
+        prop_assert_eq!(back.as_str(), truncated.as_ref());
+        if n < len_graphemes(&text) {
+            prop_assert!(truncated.ends_with('…'));
+        } else {
+            prop_assert_eq!(truncated.as_ref(), text.as_str());
+        }
+    }
+
+    /// The preview never panics and leaves no raw control characters for
+    /// the terminal to interpret.
+    #[test]
+    fn prop_render_preview_never_panics(
+        senders in prop::collection::vec(arb_cluster_text(), 0..6),
+        contents in prop::collection::vec(arb_cluster_text(), 0..6),
+        width in 0usize..120,
+        max_sender_width in 0usize..30,
+        limit in prop::option::of(0usize..8),
+    ) {
+        let messages: Vec<Message> = senders
+            .iter()
+            .zip(&contents)
+            .map(|(sender, content)| Message::new(sender, content))
+            .collect();
+        let options = PreviewOptions::new()
+            .with_width(width)
+            .with_max_sender_width(max_sender_width)
+            .with_limit(limit.unwrap_or(usize::MAX));
+        let preview = render_preview(&messages, options);
+
+        prop_assert!(!preview.chars().any(|c| c.is_control() && c != '\n'));
+        if !messages.is_empty() {
+            prop_assert!(preview.ends_with('\n'));
+        }
+    }
+
+    /// Titles fit their byte budget and hold nothing but letters, digits,
+    /// and single dashes between words.
+    #[test]
+    fn prop_suggest_title_is_file_name_safe(
+        contents in prop::collection::vec(arb_cluster_text(), 0..6),
+        max_len in 0usize..40,
+    ) {
+        let messages: Vec<Message> = contents.iter().map(|c| Message::new("A", c)).collect();
+        if let Some(title) = suggest_title(&messages, max_len) {
+            prop_assert!(!title.is_empty() && title.len() <= max_len, "{title:?}");
+            prop_assert!(!title.starts_with('-') && !title.ends_with('-') && !title.contains("--"));
+            prop_assert!(
+                title.chars().all(|c| c == '-' || c.is_alphanumeric() || !c.is_ascii()),
+                "{title:?}"
+            );
+            prop_assert!(!title.chars().any(|c| matches!(c, '/' | '\\' | ':' | '.') || c.is_control()));
+        }
+    }
+}
+
 // =============================================================================
 // DATE PARSING PROPERTIES
 // =============================================================================
@@ -1268,20 +1644,25 @@ mod edge_cases {
     fn discord_multiple_attachments() {
         let msg = DiscordRawMessage {
             id: "123".to_string(),
+            msg_type: None,
             timestamp: "2024-01-15T10:30:00+00:00".to_string(),
             timestamp_edited: None,
             content: "Files:".to_string(),
             author: DiscordAuthor {
                 name: "user".to_string(),
                 nickname: None,
+                discriminator: None,
+                id: None,
             },
             reference: None,
             attachments: Some(vec![
                 DiscordAttachment {
                     file_name: "a.png".to_string(),
+                    url: None,
                 },
                 DiscordAttachment {
                     file_name: "b.jpg".to_string(),
+                    url: None,
                 },
             ]),
             stickers: None,
@@ -1300,13 +1681,32 @@ mod edge_cases {
             id: Some(123),
             reply_to: Some(100),
             edited: chrono::DateTime::from_timestamp(1700000100, 0),
+            original_sender: None,
+            forwarded_from: None,
+            topic: None,
+            source: None,
+            source_index: None,
+            source_file: None,
+            platform: None,
+            raw: None,
+            attachments: Vec::new(),
         };
 
         let config = OutputConfig {
             include_timestamps: true,
             include_ids: true,
             include_replies: true,
+            include_index: false,
             include_edited: true,
+            include_metadata: true,
+            include_platform: false,
+            include_raw: false,
+            include_schema_version: false,
+            include_hash: false,
+            conversation_boundaries: None,
+            sort_by: SortKey::Chronological,
+            line_ending: LineEnding::Lf,
+            write_bom: false,
         };
 
         let csv = to_csv(&[msg], &config).unwrap();
@@ -1358,6 +1758,15 @@ mod edge_cases {
                 id: None,
                 reply_to: None,
                 edited: None,
+                original_sender: None,
+                forwarded_from: None,
+                topic: None,
+                source: None,
+                source_index: None,
+                source_file: None,
+                platform: None,
+                raw: None,
+                attachments: Vec::new(),
             },
             Message {
                 sender: "Alice".to_string(),
@@ -1370,6 +1779,15 @@ mod edge_cases {
                 id: None,
                 reply_to: None,
                 edited: None,
+                original_sender: None,
+                forwarded_from: None,
+                topic: None,
+                source: None,
+                source_index: None,
+                source_file: None,
+                platform: None,
+                raw: None,
+                attachments: Vec::new(),
             },
         ];
 
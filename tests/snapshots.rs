@@ -0,0 +1,204 @@
+//! Golden-file tests for every output writer.
+//!
+//! Each writer renders [`support::canonical_fixture`] and the exact output is
+//! compared against the snapshot in `tests/snapshots/`. A failing test means
+//! the output format changed; review the `.snap.new` file (or run
+//! `cargo insta review`) and accept it only if the change is intended.
+//! Formats whose feature is disabled are skipped.
+
+#![cfg(any(feature = "csv-output", feature = "json-output"))]
+
+mod support;
+
+use chatpack::core::models::OutputConfig;
+
+use support::canonical_fixture;
+
+/// Output configurations every message format is rendered with.
+fn configs() -> [(&'static str, OutputConfig); 4] {
+    [
+        ("minimal", OutputConfig::new()),
+        ("timestamps", OutputConfig::new().with_timestamps()),
+        ("ids_replies", OutputConfig::new().with_ids().with_replies()),
+        ("all", OutputConfig::all()),
+    ]
+}
+
+mod message_formats {
+    use super::*;
+    use chatpack::format::{OutputFormat, to_format_string};
+
+    fn snapshot_format(format: OutputFormat) {
+        let messages = canonical_fixture();
+        for (label, config) in configs() {
+            let output = to_format_string(&messages, format, &config).unwrap();
+            let name = match format {
+                // Shares its extension with JSONL
+                OutputFormat::HfText => "hf",
+                _ => format.extension(),
+            };
+            insta::assert_snapshot!(format!("{name}_{label}"), output);
+        }
+    }
+
+    #[cfg(feature = "csv-output")]
+    #[test]
+    fn test_csv() {
+        snapshot_format(OutputFormat::Csv);
+    }
+
+    #[cfg(feature = "json-output")]
+    #[test]
+    fn test_json() {
+        snapshot_format(OutputFormat::Json);
+    }
+
+    #[cfg(feature = "json-output")]
+    #[test]
+    fn test_jsonl() {
+        snapshot_format(OutputFormat::Jsonl);
+    }
+
+    #[cfg(feature = "json-output")]
+    #[test]
+    fn test_hf_text() {
+        snapshot_format(OutputFormat::HfText);
+    }
+
+    #[test]
+    fn test_output_is_deterministic() {
+        let messages = canonical_fixture();
+        for &format in OutputFormat::all() {
+            for (_, config) in configs() {
+                let first = to_format_string(&messages, format, &config);
+                let second = to_format_string(&messages, format, &config);
+                match (first, second) {
+                    (Ok(a), Ok(b)) => assert_eq!(a, b, "{format}"),
+                    // Format's feature is disabled
+                    (Err(_), Err(_)) => {}
+                    _ => panic!("{format} failed only once"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "json-output")]
+#[test]
+fn test_context_jsonl() {
+    use chatpack::core::output::to_context_jsonl;
+    use chatpack::core::with_context;
+
+    let windows = with_context(&canonical_fixture()[..6], 2);
+    for (label, config) in configs() {
+        let output = to_context_jsonl(&windows, &config).unwrap();
+        insta::assert_snapshot!(format!("context_jsonl_{label}"), output);
+    }
+}
+
+#[test]
+fn test_attachment_manifest() {
+    use chatpack::core::output::to_attachment_manifest;
+    use chatpack::format::OutputFormat;
+
+    let messages = canonical_fixture();
+    for &format in OutputFormat::all() {
+        if let Ok(output) = to_attachment_manifest(&messages, format) {
+            insta::assert_snapshot!(format!("manifest_{}", format.extension()), output);
+        }
+    }
+}
+
+#[cfg(feature = "csv-output")]
+#[test]
+fn test_aggregate_csv() {
+    use chatpack::core::output::to_aggregate_csv;
+    use chatpack::core::stats::{Bucket, aggregate_counts};
+
+    let buckets = aggregate_counts(&canonical_fixture(), Bucket::Hour);
+    insta::assert_snapshot!("aggregate_csv", to_aggregate_csv(&buckets, 2).unwrap());
+}
+
+#[cfg(all(feature = "json-output", feature = "telegram"))]
+#[test]
+fn test_telegram_json() {
+    let output = chatpack::core::output::to_telegram_json(&canonical_fixture(), "Snapshots");
+    insta::assert_snapshot!("telegram_json", output.unwrap());
+}
+
+#[cfg(all(feature = "json-output", feature = "discord-json"))]
+#[test]
+fn test_discord_json() {
+    let output = chatpack::core::output::to_discord_json(&canonical_fixture(), "snapshots");
+    insta::assert_snapshot!("discord_json", output.unwrap());
+}
+
+#[cfg(feature = "preview")]
+#[test]
+fn test_preview() {
+    use chatpack::core::output::{PreviewOptions, render_preview};
+
+    let options = PreviewOptions::new().with_width(40).with_limit(20);
+    insta::assert_snapshot!("preview", render_preview(&canonical_fixture(), options));
+}
+
+mod reports {
+    use std::time::Duration;
+
+    use chatpack::core::loss::LossReport;
+    use chatpack::core::processor::ProcessingStats;
+    use chatpack::core::rand::Seed;
+    use chatpack::report::{Performance, ReportStyle, format_performance, format_summary};
+
+    const STYLES: [(&str, ReportStyle); 2] = [
+        ("unicode", ReportStyle::Unicode),
+        ("plain", ReportStyle::Plain),
+    ];
+
+    fn full_stats() -> ProcessingStats {
+        let mut loss = LossReport::new();
+        loss.system_messages_skipped = 3;
+        loss.messages_filtered = 9_000;
+        loss.messages_merged = 640;
+        loss.fields_dropped.insert("timestamp".to_string(), 360);
+        let mut stats = ProcessingStats::new(12_345, 360)
+            .with_filtered(1_000)
+            .with_seed(Seed::new(42));
+        stats.loss = loss;
+        stats
+    }
+
+    #[test]
+    fn test_summary() {
+        for (label, style) in STYLES {
+            insta::assert_snapshot!(
+                format!("summary_{label}"),
+                format_summary(&full_stats(), style)
+            );
+            insta::assert_snapshot!(
+                format!("summary_unfiltered_{label}"),
+                format_summary(&ProcessingStats::new(7, 5), style)
+            );
+        }
+    }
+
+    #[test]
+    fn test_performance() {
+        let full = Performance::new(12_345, Duration::from_millis(2_468))
+            .with_bytes(5_200_000)
+            .with_parse(Duration::from_millis(1_830))
+            .with_process(Duration::from_millis(7))
+            .with_write(Duration::from_millis(631));
+        let total_only = Performance::new(40, Duration::from_millis(80));
+        for (label, style) in STYLES {
+            insta::assert_snapshot!(
+                format!("performance_{label}"),
+                format_performance(&full, style)
+            );
+            insta::assert_snapshot!(
+                format!("performance_total_only_{label}"),
+                format_performance(&total_only, style)
+            );
+        }
+    }
+}
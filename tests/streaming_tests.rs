@@ -1,5 +1,6 @@
 //! Integration tests for streaming parsers.
 
+use chatpack::fixtures::TelegramExportBuilder;
 use chatpack::parser::Platform;
 use chatpack::streaming::{
     StreamingConfig, StreamingParser, TelegramStreamingParser, create_streaming_parser,
@@ -7,33 +8,20 @@ use chatpack::streaming::{
 use std::io::Write;
 use tempfile::NamedTempFile;
 
-/// Helper to create a test Telegram JSON file
-fn create_telegram_test_file(count: usize) -> NamedTempFile {
+/// Writes `export` to a temporary file.
+fn write_export(export: &str) -> NamedTempFile {
     let mut file = NamedTempFile::new().unwrap();
+    file.write_all(export.as_bytes()).unwrap();
+    file
+}
 
-    let mut messages = Vec::with_capacity(count);
-    for i in 0..count {
+/// Helper to create a test Telegram JSON file
+fn create_telegram_test_file(count: usize) -> NamedTempFile {
+    let builder = (0..count).fold(TelegramExportBuilder::new("Test Chat"), |builder, i| {
         let sender = if i % 2 == 0 { "Alice" } else { "Bob" };
-        let timestamp = 1705314600 + (i as i64 * 60);
-        messages.push(format!(
-            r#"    {{"id": {}, "type": "message", "date_unixtime": "{}", "from": "{}", "text": "Message number {}"}}"#,
-            i, timestamp, sender, i
-        ));
-    }
-
-    let json = format!(
-        r#"{{
-  "name": "Test Chat",
-  "type": "personal_chat",
-  "messages": [
-{}
-  ]
-}}"#,
-        messages.join(",\n")
-    );
-
-    file.write_all(json.as_bytes()).unwrap();
-    file
+        builder.message(sender, &format!("Message number {i}"))
+    });
+    write_export(&builder.build())
 }
 
 #[test]
@@ -97,16 +85,13 @@ fn test_streaming_large_file() {
 
 #[test]
 fn test_streaming_skips_service_messages() {
-    let mut file = NamedTempFile::new().unwrap();
-    let json = r#"{
-  "name": "Test",
-  "messages": [
-    {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "Hello"},
-    {"id": 2, "type": "service", "action": "pin_message"},
-    {"id": 3, "type": "message", "date_unixtime": "1705314660", "from": "Bob", "text": "Hi"}
-  ]
-}"#;
-    file.write_all(json.as_bytes()).unwrap();
+    let file = write_export(
+        &TelegramExportBuilder::new("Test")
+            .message("Alice", "Hello")
+            .service_pin()
+            .message("Bob", "Hi")
+            .build(),
+    );
 
     let parser = TelegramStreamingParser::new();
     let messages: Vec<_> = parser
@@ -120,16 +105,13 @@ fn test_streaming_skips_service_messages() {
 
 #[test]
 fn test_streaming_handles_empty_content() {
-    let mut file = NamedTempFile::new().unwrap();
-    let json = r#"{
-  "name": "Test",
-  "messages": [
-    {"id": 1, "type": "message", "date_unixtime": "1705314600", "from": "Alice", "text": "Hello"},
-    {"id": 2, "type": "message", "date_unixtime": "1705314620", "from": "Bob", "text": ""},
-    {"id": 3, "type": "message", "date_unixtime": "1705314660", "from": "Alice", "text": "Bye"}
-  ]
-}"#;
-    file.write_all(json.as_bytes()).unwrap();
+    let file = write_export(
+        &TelegramExportBuilder::new("Test")
+            .message("Alice", "Hello")
+            .message("Bob", "")
+            .message("Alice", "Bye")
+            .build(),
+    );
 
     let parser = TelegramStreamingParser::new();
     let messages: Vec<_> = parser
@@ -179,3 +161,112 @@ fn test_streaming_bytes_tracking() {
     assert!(processed > 0);
     assert!(processed <= total);
 }
+
+#[test]
+fn test_streaming_source_indices_match_batch() {
+    use chatpack::parser::create_parser;
+
+    let file = create_telegram_test_file(20);
+
+    let batch = create_parser(Platform::Telegram)
+        .parse(file.path())
+        .unwrap();
+    let streamed: Vec<_> = TelegramStreamingParser::new()
+        .stream(file.path().to_str().unwrap())
+        .unwrap()
+        .filter_map(Result::ok)
+        .collect();
+
+    let batch_indices: Vec<_> = batch.iter().map(|m| m.source_index()).collect();
+    let stream_indices: Vec<_> = streamed.iter().map(|m| m.source_index()).collect();
+    assert_eq!(batch_indices, stream_indices);
+    assert_eq!(stream_indices, (0..20).map(Some).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_whatsapp_streaming_source_indices_match_batch() {
+    use chatpack::parser::create_parser;
+
+    // Same-minute timestamps: only source_index can order these
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..10 {
+        writeln!(file, "[1/15/24, 10:30:00 AM] Alice: message {i}").unwrap();
+    }
+
+    let batch = create_parser(Platform::WhatsApp)
+        .parse(file.path())
+        .unwrap();
+    let streamed: Vec<_> = create_streaming_parser(Platform::WhatsApp)
+        .stream(file.path().to_str().unwrap())
+        .unwrap()
+        .filter_map(Result::ok)
+        .collect();
+
+    assert_eq!(batch.len(), 10);
+    for (b, s) in batch.iter().zip(&streamed) {
+        assert_eq!(b.content, s.content);
+        assert_eq!(b.source_index(), s.source_index());
+    }
+}
+
+#[test]
+fn test_whatsapp_streaming_matches_batch_multiline() {
+    use chatpack::config::WhatsAppConfig;
+    use chatpack::parser::Parser;
+    use chatpack::parsers::WhatsAppParser;
+    use chatpack::streaming::WhatsAppStreamingParser;
+
+    // Multi-line messages, blank lines inside a message, a system message
+    // between continuation lines, an empty message, and a file ending
+    // mid-message without a final newline
+    let mut file = NamedTempFile::new().unwrap();
+    write!(
+        file,
+        "[1/15/24, 10:30:00 AM] Alice: First line\n\
+         second line\n\
+         \n\
+         after a blank line\n\
+         [1/15/24, 10:31:00 AM] Bob: Hi\n\
+         [1/15/24, 10:31:30 AM] Bob: Messages and calls are end-to-end encrypted\n\
+         still Bob's message\n\
+         [1/15/24, 10:32:00 AM] Carol:\n\
+         [1/15/24, 10:33:00 AM] Alice: Ending\n\
+         \n\
+         mid-message"
+    )
+    .unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let configs = [
+        WhatsAppConfig::new(),
+        WhatsAppConfig::new().with_skip_system_messages(false),
+        WhatsAppConfig::new().with_sender_allowlist(["alice"]),
+        WhatsAppConfig::new().with_capture_raw(true),
+    ];
+    for config in configs {
+        let batch = WhatsAppParser::with_config(config.clone())
+            .parse(file.path())
+            .unwrap();
+        let streamed: Vec<_> = WhatsAppStreamingParser::from_whatsapp_config(&config)
+            .stream(path)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(batch, streamed, "{config:?}");
+        for (b, s) in batch.iter().zip(&streamed) {
+            assert_eq!(b.raw, s.raw);
+            assert_eq!(b.source_index, s.source_index);
+        }
+    }
+
+    let messages = WhatsAppParser::new().parse(file.path()).unwrap();
+    let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(
+        contents,
+        [
+            "First line\nsecond line\nafter a blank line",
+            "Hi\nstill Bob's message",
+            "Ending\nmid-message",
+        ]
+    );
+}
@@ -0,0 +1,57 @@
+//! Shared fixtures for integration tests.
+
+use chatpack::{AttachmentRef, Message};
+use chrono::{TimeZone, Utc};
+
+/// Contents exercising delimiters, quoting, escapes, and non-ASCII text.
+const CONTENTS: [&str; 16] = [
+    "Hello!",
+    "semi;colon, comma",
+    "She said \"hi\"",
+    "line one\nline two",
+    "tab\tseparated",
+    "back\\slash",
+    "Привет, мир",
+    "你好世界",
+    "مرحبا بالعالم",
+    "emoji 🎉👍🏽 family 👨‍👩‍👧",
+    "zero\u{200B}width",
+    "",
+    "  padded  ",
+    "{\"looks\": \"like json\"}",
+    "trailing newline\n",
+    "carriage\r\nreturn",
+];
+
+const SENDERS: [&str; 4] = ["Alice", "Бора", "李雷", "O'Brien; \"Ziggy\""];
+
+/// Builds a deterministic set of messages covering every combination of the
+/// optional fields (timestamp, id, reply, edit, attachment) at least once.
+pub fn canonical_fixture() -> Vec<Message> {
+    let base = Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap();
+
+    (0..32u64)
+        .map(|i| {
+            let position = usize::try_from(i).unwrap();
+            let mut msg = Message::new(SENDERS[position % 4], CONTENTS[position % 16]);
+            let sent = base + chrono::Duration::minutes(i64::try_from(i).unwrap());
+            if i & 1 != 0 {
+                msg = msg.with_timestamp(sent);
+            }
+            if i & 2 != 0 {
+                msg = msg.with_id(1000 + i);
+            }
+            if i & 4 != 0 && i > 0 {
+                msg = msg.with_reply_to(1000 + i - 1);
+            }
+            if i & 8 != 0 {
+                msg = msg.with_edited(sent + chrono::Duration::seconds(30));
+            }
+            if i & 16 != 0 {
+                msg =
+                    msg.with_attachment(AttachmentRef::from_path(format!("photos/photo_{i}.jpg")));
+            }
+            msg
+        })
+        .collect()
+}
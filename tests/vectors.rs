@@ -0,0 +1,95 @@
+//! Golden pipeline tests over `tests/vectors/`.
+//!
+//! Each platform directory holds a small export and the exact CSV, JSON,
+//! and JSONL written for it with every `include_*` flag on and no filters
+//! or merging. The outputs are regenerated here and compared byte for
+//! byte; the same files are embedded by `chatpack::testvectors` for
+//! downstream conformance checks.
+//!
+//! Run with `UPDATE_VECTORS=1` to rewrite the expected files after an
+//! intended format change, and review the diff before committing it.
+
+#![cfg(all(
+    feature = "telegram",
+    feature = "whatsapp",
+    feature = "instagram",
+    feature = "discord-json",
+    feature = "csv-output",
+    feature = "json-output"
+))]
+
+use std::fs;
+use std::path::PathBuf;
+
+use chatpack::core::models::OutputConfig;
+use chatpack::format::{OutputFormat, to_format_string};
+use chatpack::parser::Platform;
+
+/// Must stay equal to `chatpack::testvectors::output_config`.
+fn vector_config() -> OutputConfig {
+    OutputConfig::all()
+        .with_platform()
+        .with_raw()
+        .with_schema_version()
+        .with_hash()
+}
+
+fn vector_dir(platform: Platform) -> PathBuf {
+    let name = platform.to_string().to_lowercase();
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/vectors")
+        .join(name)
+}
+
+fn input_path(platform: Platform) -> PathBuf {
+    let dir = vector_dir(platform);
+    let name = match platform {
+        Platform::WhatsApp => "input.txt",
+        _ => "input.json",
+    };
+    dir.join(name)
+}
+
+/// File name of the expected output in `format`.
+fn expected_name(format: OutputFormat) -> String {
+    match format {
+        // Shares its extension with JSONL
+        OutputFormat::HfText => "expected.hf.jsonl".to_string(),
+        _ => format!("expected.{}", format.extension()),
+    }
+}
+
+#[test]
+fn test_vectors_match_expected_output() {
+    let update = std::env::var_os("UPDATE_VECTORS").is_some();
+    for &platform in Platform::all() {
+        let input = fs::read(input_path(platform)).unwrap();
+        let messages = chatpack::read_bytes_as(platform, input).unwrap();
+        assert!(!messages.is_empty(), "{platform}");
+
+        for &format in OutputFormat::all() {
+            let output = to_format_string(&messages, format, &vector_config()).unwrap();
+            let path = vector_dir(platform).join(expected_name(format));
+            if update {
+                fs::write(&path, &output).unwrap();
+                continue;
+            }
+            let expected = fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+            assert!(
+                output.as_bytes() == expected,
+                "{} differs from the regenerated output:\n{output}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_vector_inputs_parse_the_same_from_disk() {
+    for &platform in Platform::all() {
+        let path = input_path(platform);
+        let from_disk = chatpack::read_as(platform, &path).unwrap();
+        let from_bytes = chatpack::read_bytes_as(platform, fs::read(&path).unwrap()).unwrap();
+        assert_eq!(from_disk, from_bytes, "{platform}");
+    }
+}